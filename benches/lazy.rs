@@ -0,0 +1,95 @@
+//! The lookups of a large file with the cached tables of `Elf` and with
+//! the tables parsed again for each lookup, as they were before the cache
+//!
+//! Run with `cargo bench --bench lazy`, the file has 2000 sections and
+//! 50000 symbols. Each line is the mean time of one lookup.
+use readbin::headers::elf::builder::{ElfBuilder, Section, Symbol};
+use readbin::headers::elf::machine::Machine;
+use readbin::headers::elf::section::{self, SHF, SHT};
+use readbin::headers::elf::symbols::{STB, STT};
+use readbin::headers::elf::types::Type;
+use readbin::Elf;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const SECTIONS: usize = 2000;
+const SYMBOLS: usize = 50000;
+
+fn large_file() -> Vec<u8> {
+    let mut builder = ElfBuilder::new(Type::Rel, Machine::X86_64);
+    let sections: Vec<usize> = (0..SECTIONS)
+        .map(|index| {
+            builder.add_section(
+                Section::new(&format!(".text.f{}", index), SHT::PROGBITS)
+                    .flags(SHF::ALLOC | SHF::EXECINSTR)
+                    .content([0xc3; 16]),
+            )
+        })
+        .collect();
+    for index in 0..SYMBOLS {
+        builder.add_symbol(
+            Symbol::new(&format!("f{}", index))
+                .bind(STB::GLOBAL)
+                .kind(STT::FUNC)
+                .section(sections[index % SECTIONS])
+                .size(1),
+        );
+    }
+    builder.build().expect("valid file")
+}
+
+/// Run `f` for `runs` inputs and print the mean time of a run
+fn bench(name: &str, runs: usize, mut f: impl FnMut(usize)) -> Duration {
+    let start = Instant::now();
+    for run in 0..runs {
+        f(black_box(run));
+    }
+    let mean = start.elapsed() / runs as u32;
+    println!("{:<40} {:>12?}", name, mean);
+    mean
+}
+
+fn main() {
+    let data = large_file();
+    println!("{} bytes", data.len());
+    bench("parse, file header only", 100_000, |_| {
+        black_box(Elf::parse(&data).expect("ELF64 file"));
+    });
+
+    let elf = Elf::parse(&data).expect("ELF64 file");
+    let name = |run: usize| format!(".text.f{}", run * 7 % SECTIONS);
+    let uncached = bench("section_by_name, tables parsed again", 500, |run| {
+        let header = elf.header();
+        let names = section::names(&data, header).expect("section names");
+        let name = name(run);
+        black_box(
+            section::from_bytes(&data, header)
+                .expect("section headers")
+                .find(|shdr| shdr.name(&names).is_ok_and(|found| found == name.as_str())),
+        );
+    });
+    let cached = bench("section_by_name, cached tables", 500, |run| {
+        black_box(elf.section_by_name(name(run)));
+    });
+    println!(
+        "{:<40} {:>11.1}x",
+        "",
+        uncached.as_secs_f64() / cached.as_secs_f64()
+    );
+
+    bench("section_containing, cached tables", 500, |run| {
+        black_box(elf.section_containing(run as u64 * 16).expect("sections"));
+    });
+    let uncached = bench("symbols, tables parsed again", 500, |_| {
+        let elf = Elf::parse(&data).expect("ELF64 file");
+        black_box(elf.symbols().expect("symbol table"));
+    });
+    let cached = bench("symbols, cached tables", 500, |_| {
+        black_box(elf.symbols().expect("symbol table"));
+    });
+    println!(
+        "{:<40} {:>11.1}x",
+        "",
+        uncached.as_secs_f64() / cached.as_secs_f64()
+    );
+}
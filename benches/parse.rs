@@ -0,0 +1,156 @@
+//! Parsing the headers, sections and symbols of a large file, and
+//! printing its tables like `readbin -S -s -r`
+//!
+//! Run with `cargo bench --bench parse`, on a generated file of 200000
+//! symbols with long mangled names, or with a path after `--` to time a
+//! real binary, e.g.
+//! `cargo bench --bench parse -- /usr/lib/x86_64-linux-gnu/libLLVM-14.so.1`.
+//! Each line is the mean time of one run.
+//!
+//! Before and after the tables stopped allocating per entry, the nul bytes
+//! were searched 8 at a time and the section names were resolved once per
+//! table, "before" being the parent of the commit adding this benchmark.
+//! Each cell is the best of 5 runs of `cargo bench` (the release profile,
+//! rustc 1.95), alternating the two trees on an idle single core Xeon VM,
+//! with the `libLLVM-14.so.1` of the libllvm14 package of Debian 12:
+//!
+//! | | generated file | libLLVM-14.so.1, 44983 dynamic symbols |
+//! |---|---|---|
+//! | symbols and names | 6.1 ms, 2.6 ms | 3.9 ms, 1.6 ms |
+//! | all_symbols, with section names | 12.4 ms, 2.9 ms | 4.7 ms, 1.7 ms |
+//! | print sections | 1.43 ms, 0.82 ms | 59 µs, 34 µs |
+//! | print symbols | 246 ms, 100 ms | 78 ms, 29 ms |
+//! | print relocations, 355159 in libLLVM | | 119 ms, 58 ms |
+use readbin::headers::elf::builder::{ElfBuilder, Section, Symbol};
+use readbin::headers::elf::machine::Machine;
+use readbin::headers::elf::object::SymbolSection;
+use readbin::headers::elf::section::{SHF, SHT};
+use readbin::headers::elf::symbols::{STB, STT};
+use readbin::headers::elf::types::Type;
+use readbin::headers::elf::{relocations, section, symbols};
+use readbin::Elf;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const SECTIONS: usize = 1000;
+const SYMBOLS: usize = 200_000;
+
+fn large_file() -> Vec<u8> {
+    let mut builder = ElfBuilder::new(Type::Rel, Machine::X86_64);
+    let sections: Vec<usize> = (0..SECTIONS)
+        .map(|index| {
+            builder.add_section(
+                Section::new(
+                    &format!(".text._ZN4llvm6detail5Pass{}E", index),
+                    SHT::PROGBITS,
+                )
+                .flags(SHF::ALLOC | SHF::EXECINSTR)
+                .content([0xc3; 16]),
+            )
+        })
+        .collect();
+    for index in 0..SYMBOLS {
+        builder.add_symbol(
+            Symbol::new(&format!(
+                "_ZN4llvm6detail{}Analysis{}runERNS_6ModuleE",
+                index % 97,
+                index
+            ))
+            .bind(STB::GLOBAL)
+            .kind(STT::FUNC)
+            .section(sections[index % SECTIONS])
+            .size(1),
+        );
+    }
+    builder.build().expect("valid file")
+}
+
+/// Run `f` `runs` times and print the mean time of a run
+fn bench(name: &str, runs: usize, mut f: impl FnMut(usize)) -> Duration {
+    let start = Instant::now();
+    for run in 0..runs {
+        f(black_box(run));
+    }
+    let mean = start.elapsed() / runs as u32;
+    println!("{:<40} {:>12?}", name, mean);
+    mean
+}
+
+fn main() {
+    // cargo passes --bench to the benchmarks without harness
+    let data = match std::env::args().skip(1).find(|arg| !arg.starts_with("--")) {
+        Some(path) => std::fs::read(&path).expect("readable file"),
+        None => large_file(),
+    };
+    let elf = Elf::parse(&data).expect("ELF64 file");
+    let header = elf.header();
+    let symtab = elf.symbols().expect("symbol table");
+    let dynsym = elf.dynamic_symbols().expect("dynamic symbol table");
+    let count =
+        |table: &Option<SymbolSection<'_>>| table.as_ref().map_or(0, |table| table.symbols.len());
+    println!(
+        "{} bytes, {} sections, {} symbols, {} dynamic symbols",
+        data.len(),
+        header.shnum(&data),
+        count(&symtab),
+        count(&dynsym)
+    );
+
+    bench("parse, file header only", 100_000, |_| {
+        black_box(Elf::parse(&data).expect("ELF64 file"));
+    });
+    bench("section headers and names", 1000, |_| {
+        let names = section::names(&data, header).expect("section names");
+        for shdr in section::from_bytes(&data, header).expect("section headers") {
+            black_box(shdr.name(&names).ok());
+        }
+    });
+    bench("symbols and names", 20, |_| {
+        for table in [&symtab, &dynsym].into_iter().flatten() {
+            for symbol in table.symbols.clone() {
+                black_box(symbol.name(&table.strtab).ok());
+            }
+        }
+    });
+    bench("all_symbols, with section names", 20, |_| {
+        for symbol in elf.all_symbols().expect("symbol tables") {
+            black_box((symbol.name, symbol.section));
+        }
+    });
+    bench("relocations", 20, |_| {
+        black_box(elf.relocations().expect("relocations"));
+    });
+    let print = |name, table: &dyn std::fmt::Display| {
+        bench(name, 5, |_| {
+            black_box(table.to_string());
+        });
+    };
+    print(
+        "print sections",
+        &section::SectionHeaderTable {
+            data: &data,
+            header,
+            color: false,
+            wide: false,
+        },
+    );
+    print(
+        "print symbols",
+        &symbols::SymbolTable {
+            data: &data,
+            header,
+            demangle: false,
+            color: false,
+            wide: false,
+        },
+    );
+    print(
+        "print relocations",
+        &relocations::RelocationTable {
+            data: &data,
+            header,
+            demangle: false,
+            wide: false,
+        },
+    );
+}
@@ -0,0 +1,190 @@
+/*
+ * C bindings of readbin, the ELF64 parsers of https://github.com/Lsh0x/readbin
+ *
+ * Link with -lreadbin_ffi, built by `cargo build -p readbin-ffi`.
+ *
+ * Every function returning a readbin_status writes its out pointers only
+ * when it returns READBIN_OK, readbin_last_error() describes a failure
+ * until the next failure on the same thread. A readbin_file owns a copy
+ * of the file and the data pointers it returns stay valid until
+ * readbin_close(). An iterator owns the records it returns, their names
+ * stay valid until its _free function, which is called before the file
+ * is closed. Files and iterators can move between threads but can't be
+ * used from two at once.
+ */
+#ifndef READBIN_H
+#define READBIN_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum readbin_status {
+    /* success, the out pointers are written */
+    READBIN_OK = 0,
+    /* an iterator has no more records */
+    READBIN_END = 1,
+    /* a required pointer argument is null */
+    READBIN_NULL_ARGUMENT = -1,
+    /* the file can't be read */
+    READBIN_IO = -2,
+    /* the file or one of its tables can't be parsed */
+    READBIN_PARSE = -3,
+    /* the operation doesn't apply to the format of the file */
+    READBIN_UNSUPPORTED = -4,
+    /* an index is past the end of its table */
+    READBIN_OUT_OF_RANGE = -5,
+    /* a bug of readbin, the message says where */
+    READBIN_PANIC = -6,
+} readbin_status;
+
+typedef enum readbin_file_format {
+    READBIN_FORMAT_UNKNOWN = 0,
+    READBIN_FORMAT_ELF = 1,
+    READBIN_FORMAT_PE = 2,
+    READBIN_FORMAT_MACHO = 3,
+    READBIN_FORMAT_MACHO_FAT = 4,
+    READBIN_FORMAT_ARCHIVE = 5,
+    READBIN_FORMAT_WASM = 6,
+} readbin_file_format;
+
+/* The fields of an ELF64 file header, in the byte order of the host */
+typedef struct readbin_elf_header {
+    /* EI_CLASS, 2 for ELF64 */
+    uint8_t ei_class;
+    /* EI_DATA, 1 for little and 2 for big endian */
+    uint8_t ei_data;
+    /* EI_OSABI */
+    uint8_t ei_osabi;
+    /* ET_* type of the file */
+    uint16_t type;
+    /* EM_* machine */
+    uint16_t machine;
+    uint32_t version;
+    uint64_t entry;
+    uint64_t phoff;
+    uint64_t shoff;
+    uint32_t flags;
+    /* number of program headers, from the first section header for PN_XNUM */
+    uint64_t phnum;
+    /* number of section headers, from the first section header when 0 */
+    uint64_t shnum;
+    /* index of the section name string table */
+    uint32_t shstrndx;
+} readbin_elf_header;
+
+typedef struct readbin_section {
+    size_t index;
+    /* nul-terminated name, empty if it can't be read */
+    const char *name;
+    /* SHT_* type */
+    uint32_t type;
+    /* SHF_* flags */
+    uint64_t flags;
+    uint64_t addr;
+    uint64_t offset;
+    uint64_t size;
+    uint32_t link;
+    uint32_t info;
+    uint64_t addralign;
+    uint64_t entsize;
+} readbin_section;
+
+typedef struct readbin_segment {
+    size_t index;
+    /* PT_* type */
+    uint32_t type;
+    /* PF_* flags */
+    uint32_t flags;
+    uint64_t offset;
+    uint64_t vaddr;
+    uint64_t paddr;
+    uint64_t filesz;
+    uint64_t memsz;
+    uint64_t align;
+} readbin_segment;
+
+/* A symbol of .symtab or .dynsym */
+typedef struct readbin_symbol {
+    /* index in its table */
+    size_t index;
+    /* nul-terminated name, empty if it can't be read */
+    const char *name;
+    uint64_t value;
+    uint64_t size;
+    /* STT_* type */
+    uint8_t type;
+    /* STB_* binding */
+    uint8_t bind;
+    /* STV_* visibility */
+    uint8_t visibility;
+    /* index of the section, or SHN_UNDEF, SHN_ABS, ... */
+    uint16_t shndx;
+    /* true for the symbols of .dynsym */
+    bool dynamic;
+} readbin_symbol;
+
+typedef struct readbin_file readbin_file;
+typedef struct readbin_sections readbin_sections;
+typedef struct readbin_segments readbin_segments;
+typedef struct readbin_symbols readbin_symbols;
+
+/* The version of the library, a static string */
+const char *readbin_version(void);
+
+/* The message of the last failed call of this thread, NULL if none has
+ * failed. It stays valid until the next failed call of the thread. */
+const char *readbin_last_error(void);
+
+/* Open the file at path */
+readbin_status readbin_open(const char *path, readbin_file **out);
+
+/* Open a file from len bytes at data, copied. data may be NULL if len is 0 */
+readbin_status readbin_open_bytes(const uint8_t *data, size_t len, readbin_file **out);
+
+/* Free a file, NULL is ignored */
+void readbin_close(readbin_file *file);
+
+/* The format of a file, READBIN_FORMAT_UNKNOWN for NULL */
+readbin_file_format readbin_format(const readbin_file *file);
+
+/* Read the ELF64 file header */
+readbin_status readbin_header(const readbin_file *file, readbin_elf_header *out);
+
+/* The bytes of the section at index in the file, empty for SHT_NOBITS.
+ * They stay valid until the file is closed, compressed sections are
+ * returned as stored. */
+readbin_status readbin_section_data(const readbin_file *file, size_t index,
+                                    const uint8_t **data, size_t *len);
+
+/* Iterate over the section headers of an ELF64 file */
+readbin_status readbin_sections_iter(const readbin_file *file, readbin_sections **out);
+/* Write the next section, READBIN_END after the last one */
+readbin_status readbin_sections_next(readbin_sections *iterator, readbin_section *out);
+/* Free a section iterator and its names, NULL is ignored */
+void readbin_sections_free(readbin_sections *iterator);
+
+/* Iterate over the program headers of an ELF64 file */
+readbin_status readbin_segments_iter(const readbin_file *file, readbin_segments **out);
+/* Write the next segment, READBIN_END after the last one */
+readbin_status readbin_segments_next(readbin_segments *iterator, readbin_segment *out);
+/* Free a segment iterator, NULL is ignored */
+void readbin_segments_free(readbin_segments *iterator);
+
+/* Iterate over the symbols of .symtab then of .dynsym, without the null
+ * symbol starting each table */
+readbin_status readbin_symbols_iter(const readbin_file *file, readbin_symbols **out);
+/* Write the next symbol, READBIN_END after the last one */
+readbin_status readbin_symbols_next(readbin_symbols *iterator, readbin_symbol *out);
+/* Free a symbol iterator and its names, NULL is ignored */
+void readbin_symbols_free(readbin_symbols *iterator);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* READBIN_H */
@@ -0,0 +1,664 @@
+//! C bindings of the readbin parsers
+//!
+//! The functions of `include/readbin.h` open a file, read its ELF64 header
+//! and iterate over its sections, segments and symbols. The rules of the
+//! interface:
+//!
+//! - Every function returning a [`readbin_status`] writes its results
+//!   through its out pointers, only when it returns `READBIN_OK`. On a
+//!   failure [`readbin_last_error`] describes it, until the next failure
+//!   on the same thread.
+//! - A `readbin_file` owns a copy of the bytes of the file and is freed
+//!   by [`readbin_close`]. The data pointers it returns stay valid until
+//!   then.
+//! - An iterator owns the records it returns, their names stay valid
+//!   until it is freed by its `_free` function, which must happen before
+//!   the file is closed. Iterators and files can be used from any thread,
+//!   but not from two at once.
+//! - No panic crosses the interface: it is reported as `READBIN_PANIC`.
+//!
+//! The header is written by hand, `tests/ffi.rs` checks that it declares
+//! every exported function and that a C program using it builds and runs.
+#![allow(non_camel_case_types)]
+
+use readbin::headers::elf::query::Symbol;
+use readbin::headers::elf::{program, section, ElfHeader};
+use readbin::{Binary, Elf, Error, Format};
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// The result of a call
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum readbin_status {
+    /// success, the out pointers are written
+    READBIN_OK = 0,
+    /// an iterator has no more records
+    READBIN_END = 1,
+    /// a required pointer argument is null
+    READBIN_NULL_ARGUMENT = -1,
+    /// the file can't be read
+    READBIN_IO = -2,
+    /// the file or one of its tables can't be parsed
+    READBIN_PARSE = -3,
+    /// the operation doesn't apply to the format of the file
+    READBIN_UNSUPPORTED = -4,
+    /// an index is past the end of its table
+    READBIN_OUT_OF_RANGE = -5,
+    /// a bug of readbin, the message says where
+    READBIN_PANIC = -6,
+}
+
+use readbin_status::*;
+
+/// The format of a file, from its magic bytes
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum readbin_file_format {
+    READBIN_FORMAT_UNKNOWN = 0,
+    READBIN_FORMAT_ELF = 1,
+    READBIN_FORMAT_PE = 2,
+    READBIN_FORMAT_MACHO = 3,
+    READBIN_FORMAT_MACHO_FAT = 4,
+    READBIN_FORMAT_ARCHIVE = 5,
+    READBIN_FORMAT_WASM = 6,
+}
+
+/// The fields of an ELF64 file header, in the byte order of the host
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct readbin_elf_header {
+    /// EI_CLASS, 2 for ELF64
+    pub ei_class: u8,
+    /// EI_DATA, 1 for little and 2 for big endian
+    pub ei_data: u8,
+    /// EI_OSABI
+    pub ei_osabi: u8,
+    /// ET_* type of the file
+    pub r#type: u16,
+    /// EM_* machine
+    pub machine: u16,
+    pub version: u32,
+    pub entry: u64,
+    pub phoff: u64,
+    pub shoff: u64,
+    pub flags: u32,
+    /// number of program headers, from the first section header for PN_XNUM
+    pub phnum: u64,
+    /// number of section headers, from the first section header when 0
+    pub shnum: u64,
+    /// index of the section name string table
+    pub shstrndx: u32,
+}
+
+/// A section header
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct readbin_section {
+    pub index: usize,
+    /// nul-terminated name, empty if it can't be read
+    pub name: *const c_char,
+    /// SHT_* type
+    pub r#type: u32,
+    /// SHF_* flags
+    pub flags: u64,
+    pub addr: u64,
+    pub offset: u64,
+    pub size: u64,
+    pub link: u32,
+    pub info: u32,
+    pub addralign: u64,
+    pub entsize: u64,
+}
+
+/// A program header
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct readbin_segment {
+    pub index: usize,
+    /// PT_* type
+    pub r#type: u32,
+    /// PF_* flags
+    pub flags: u32,
+    pub offset: u64,
+    pub vaddr: u64,
+    pub paddr: u64,
+    pub filesz: u64,
+    pub memsz: u64,
+    pub align: u64,
+}
+
+/// A symbol of `.symtab` or `.dynsym`
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct readbin_symbol {
+    /// index in its table
+    pub index: usize,
+    /// nul-terminated name, empty if it can't be read
+    pub name: *const c_char,
+    pub value: u64,
+    pub size: u64,
+    /// STT_* type
+    pub r#type: u8,
+    /// STB_* binding
+    pub bind: u8,
+    /// STV_* visibility
+    pub visibility: u8,
+    /// index of the section, or SHN_UNDEF, SHN_ABS, ...
+    pub shndx: u16,
+    /// true for the symbols of `.dynsym`
+    pub dynamic: bool,
+}
+
+/// An opened file
+pub struct readbin_file {
+    data: Vec<u8>,
+}
+
+/// Records collected when an iterator is created, with the names they
+/// point to
+pub struct Records<T> {
+    records: Vec<T>,
+    // the pointers of the records into these strings stay valid when
+    // the vector moves
+    _names: Vec<CString>,
+    next: usize,
+}
+
+pub type readbin_sections = Records<readbin_section>;
+pub type readbin_segments = Records<readbin_segment>;
+pub type readbin_symbols = Records<readbin_symbol>;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// A failed call: its status and the message of [`readbin_last_error`]
+type Failure = (readbin_status, String);
+
+fn parse_failure(err: Error) -> Failure {
+    let status = match err {
+        Error::UnsupportedFormat(_) | Error::UnsupportedClass(_) => READBIN_UNSUPPORTED,
+        Error::Io(_) => READBIN_IO,
+        _ => READBIN_PARSE,
+    };
+    (status, err.to_string())
+}
+
+fn null(name: &str) -> Failure {
+    (READBIN_NULL_ARGUMENT, format!("{} is null", name))
+}
+
+/// Run the body of a function, store the message of its failure or panic
+fn guard(body: impl FnOnce() -> Result<readbin_status, Failure>) -> readbin_status {
+    let result = panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        Err((READBIN_PANIC, format!("panic: {}", message)))
+    });
+    match result {
+        Ok(status) => status,
+        Err((status, message)) => {
+            let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+            LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+            status
+        }
+    }
+}
+
+/// Put a value behind an out pointer
+///
+/// # Safety
+///
+/// `out` must be null or valid for writes.
+unsafe fn write<T>(out: *mut T, name: &str, value: T) -> Result<readbin_status, Failure> {
+    if out.is_null() {
+        return Err(null(name));
+    }
+    out.write(value);
+    Ok(READBIN_OK)
+}
+
+/// Borrow the file behind a pointer
+///
+/// # Safety
+///
+/// `file` must be null or returned by an open function and not closed.
+unsafe fn file<'a>(file: *const readbin_file) -> Result<&'a readbin_file, Failure> {
+    file.as_ref().ok_or_else(|| null("file"))
+}
+
+impl readbin_file {
+    fn elf(&self) -> Result<Elf<'_>, Failure> {
+        match readbin::parse(&self.data) {
+            Ok(Binary::Elf(ElfHeader::Elf64(_))) => Elf::parse(&self.data).map_err(parse_failure),
+            Ok(Binary::Elf(ElfHeader::Elf32(_))) => Err((
+                READBIN_UNSUPPORTED,
+                "ELF32 files only have their header parsed".to_string(),
+            )),
+            Ok(binary) => Err((
+                READBIN_UNSUPPORTED,
+                format!("{} files have no ELF64 header", binary.format()),
+            )),
+            Err(err) => Err(parse_failure(err)),
+        }
+    }
+}
+
+/// A C string of a name, the names with a nul byte are cut there
+fn c_name(name: &[u8]) -> CString {
+    let end = name.iter().position(|b| *b == 0).unwrap_or(name.len());
+    CString::new(&name[..end]).unwrap_or_default()
+}
+
+/// The version of the library, a static string
+#[no_mangle]
+pub extern "C" fn readbin_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+/// The message of the last failed call of this thread, NULL if none has
+/// failed
+///
+/// The string stays valid until the next failed call of the thread.
+#[no_mangle]
+pub extern "C" fn readbin_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| match &*last.borrow() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+/// Open the file at `path`
+///
+/// # Safety
+///
+/// `path` must be a nul-terminated string and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn readbin_open(
+    path: *const c_char,
+    out: *mut *mut readbin_file,
+) -> readbin_status {
+    guard(|| {
+        if path.is_null() {
+            return Err(null("path"));
+        }
+        let path = CStr::from_ptr(path)
+            .to_str()
+            .map_err(|_| (READBIN_IO, "path is not valid UTF-8".to_string()))?;
+        let data = std::fs::read(path)
+            .map_err(|err| (READBIN_IO, format!("can't read {}: {}", path, err)))?;
+        let file = Box::into_raw(Box::new(readbin_file { data }));
+        write(out, "out", file).inspect_err(|_| drop(Box::from_raw(file)))
+    })
+}
+
+/// Open a file from `len` bytes at `data`, copied
+///
+/// # Safety
+///
+/// `data` must be valid for `len` bytes of reads, it may be null if
+/// `len` is 0, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn readbin_open_bytes(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut readbin_file,
+) -> readbin_status {
+    guard(|| {
+        let data = match (data.is_null(), len) {
+            (true, 0) => Vec::new(),
+            (true, _) => return Err(null("data")),
+            (false, len) => std::slice::from_raw_parts(data, len).to_vec(),
+        };
+        let file = Box::into_raw(Box::new(readbin_file { data }));
+        write(out, "out", file).inspect_err(|_| drop(Box::from_raw(file)))
+    })
+}
+
+/// Free a file, NULL is ignored
+///
+/// # Safety
+///
+/// `file` must be null or returned by an open function, and its
+/// iterators freed.
+#[no_mangle]
+pub unsafe extern "C" fn readbin_close(file: *mut readbin_file) {
+    if !file.is_null() {
+        drop(Box::from_raw(file));
+    }
+}
+
+/// The format of a file, READBIN_FORMAT_UNKNOWN for NULL
+///
+/// # Safety
+///
+/// `file` must be null or an open file.
+#[no_mangle]
+pub unsafe extern "C" fn readbin_format(file: *const readbin_file) -> readbin_file_format {
+    use readbin_file_format::*;
+    let file = match file.as_ref() {
+        Some(file) => file,
+        None => return READBIN_FORMAT_UNKNOWN,
+    };
+    match readbin::identify(&file.data) {
+        Format::Elf => READBIN_FORMAT_ELF,
+        Format::Pe => READBIN_FORMAT_PE,
+        Format::MachO => READBIN_FORMAT_MACHO,
+        Format::MachOFat => READBIN_FORMAT_MACHO_FAT,
+        Format::Archive => READBIN_FORMAT_ARCHIVE,
+        Format::Wasm => READBIN_FORMAT_WASM,
+        _ => READBIN_FORMAT_UNKNOWN,
+    }
+}
+
+/// Read the ELF64 file header
+///
+/// # Safety
+///
+/// `file` must be an open file and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn readbin_header(
+    file: *const readbin_file,
+    out: *mut readbin_elf_header,
+) -> readbin_status {
+    guard(|| {
+        let file = self::file(file)?;
+        let elf = file.elf()?;
+        let header = elf.header();
+        let data = elf.data();
+        let value = readbin_elf_header {
+            ei_class: header.e_ident[4],
+            ei_data: header.e_ident[5],
+            ei_osabi: header.e_ident[7],
+            r#type: header.e_type,
+            machine: header.e_machine,
+            version: header.e_version,
+            entry: header.e_entry,
+            phoff: header.e_phoff,
+            shoff: header.e_shoff,
+            flags: header.e_flags,
+            phnum: header.phnum(data),
+            shnum: header.shnum(data),
+            shstrndx: header.shstrndx(data),
+        };
+        write(out, "out", value)
+    })
+}
+
+/// The bytes of the section at `index` in the file, empty for SHT_NOBITS
+///
+/// The bytes stay valid until the file is closed, compressed sections
+/// are returned as stored.
+///
+/// # Safety
+///
+/// `file` must be an open file, `data` and `len` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn readbin_section_data(
+    file: *const readbin_file,
+    index: usize,
+    data: *mut *const u8,
+    len: *mut usize,
+) -> readbin_status {
+    guard(|| {
+        let file = self::file(file)?;
+        let elf = file.elf()?;
+        if data.is_null() || len.is_null() {
+            return Err(null(if data.is_null() { "data" } else { "len" }));
+        }
+        let shdr = elf
+            .sections()
+            .map_err(parse_failure)?
+            .nth(index)
+            .ok_or_else(|| (READBIN_OUT_OF_RANGE, format!("no section {}", index)))?;
+        let bytes = match shdr.sh_type {
+            section::SHT::NOBITS => &[][..],
+            _ => shdr.data(elf.data()).ok_or_else(|| {
+                parse_failure(Error::OutOfBounds {
+                    offset: shdr.sh_offset,
+                    size: shdr.sh_size,
+                })
+            })?,
+        };
+        data.write(bytes.as_ptr());
+        len.write(bytes.len());
+        Ok(READBIN_OK)
+    })
+}
+
+/// Create an iterator over the records built by `collect`
+///
+/// # Safety
+///
+/// `out` must be valid for writes.
+unsafe fn iterator<T>(
+    out: *mut *mut Records<T>,
+    collect: impl FnOnce(&mut Vec<CString>) -> Result<Vec<T>, Failure>,
+) -> readbin_status {
+    guard(|| {
+        if out.is_null() {
+            return Err(null("out"));
+        }
+        let mut names = Vec::new();
+        let records = collect(&mut names)?;
+        let iterator = Records {
+            records,
+            _names: names,
+            next: 0,
+        };
+        write(out, "out", Box::into_raw(Box::new(iterator)))
+    })
+}
+
+/// Write the next record of an iterator
+///
+/// # Safety
+///
+/// `iterator` must be null or a live iterator, `out` valid for writes.
+unsafe fn next<T: Copy>(iterator: *mut Records<T>, out: *mut T) -> readbin_status {
+    guard(|| {
+        let iterator = iterator.as_mut().ok_or_else(|| null("iterator"))?;
+        if out.is_null() {
+            return Err(null("out"));
+        }
+        match iterator.records.get(iterator.next) {
+            Some(record) => {
+                iterator.next += 1;
+                write(out, "out", *record)
+            }
+            None => Ok(READBIN_END),
+        }
+    })
+}
+
+/// Free an iterator, NULL is ignored
+///
+/// # Safety
+///
+/// `iterator` must be null or a live iterator.
+unsafe fn free<T>(iterator: *mut Records<T>) {
+    if !iterator.is_null() {
+        drop(Box::from_raw(iterator));
+    }
+}
+
+/// Iterate over the section headers of an ELF64 file
+///
+/// # Safety
+///
+/// `file` must be an open file and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn readbin_sections_iter(
+    file: *const readbin_file,
+    out: *mut *mut readbin_sections,
+) -> readbin_status {
+    iterator(out, |names| {
+        let file = self::file(file)?;
+        let elf = file.elf()?;
+        let entries = section::entries(elf.data(), elf.header()).map_err(parse_failure)?;
+        Ok(entries
+            .into_iter()
+            .map(|entry| {
+                let name = c_name(entry.name.as_bytes());
+                let section = readbin_section {
+                    index: entry.index,
+                    name: name.as_ptr(),
+                    r#type: entry.shdr.sh_type,
+                    flags: entry.shdr.sh_flags,
+                    addr: entry.shdr.sh_addr,
+                    offset: entry.shdr.sh_offset,
+                    size: entry.shdr.sh_size,
+                    link: entry.shdr.sh_link,
+                    info: entry.shdr.sh_info,
+                    addralign: entry.shdr.sh_addralign,
+                    entsize: entry.shdr.sh_entsize,
+                };
+                names.push(name);
+                section
+            })
+            .collect())
+    })
+}
+
+/// Write the next section, READBIN_END after the last one
+///
+/// # Safety
+///
+/// `iterator` must be a live iterator and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn readbin_sections_next(
+    iterator: *mut readbin_sections,
+    out: *mut readbin_section,
+) -> readbin_status {
+    next(iterator, out)
+}
+
+/// Free a section iterator and its names, NULL is ignored
+///
+/// # Safety
+///
+/// `iterator` must be null or a live iterator.
+#[no_mangle]
+pub unsafe extern "C" fn readbin_sections_free(iterator: *mut readbin_sections) {
+    free(iterator)
+}
+
+/// Iterate over the program headers of an ELF64 file
+///
+/// # Safety
+///
+/// `file` must be an open file and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn readbin_segments_iter(
+    file: *const readbin_file,
+    out: *mut *mut readbin_segments,
+) -> readbin_status {
+    iterator(out, |_| {
+        let file = self::file(file)?;
+        let elf = file.elf()?;
+        let phdrs = program::from_bytes(elf.data(), elf.header()).map_err(parse_failure)?;
+        Ok(phdrs
+            .enumerate()
+            .map(|(index, phdr)| readbin_segment {
+                index,
+                r#type: phdr.p_type,
+                flags: phdr.p_flags,
+                offset: phdr.p_offset,
+                vaddr: phdr.p_vaddr,
+                paddr: phdr.p_paddr,
+                filesz: phdr.p_filesz,
+                memsz: phdr.p_memsz,
+                align: phdr.p_align,
+            })
+            .collect())
+    })
+}
+
+/// Write the next segment, READBIN_END after the last one
+///
+/// # Safety
+///
+/// `iterator` must be a live iterator and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn readbin_segments_next(
+    iterator: *mut readbin_segments,
+    out: *mut readbin_segment,
+) -> readbin_status {
+    next(iterator, out)
+}
+
+/// Free a segment iterator, NULL is ignored
+///
+/// # Safety
+///
+/// `iterator` must be null or a live iterator.
+#[no_mangle]
+pub unsafe extern "C" fn readbin_segments_free(iterator: *mut readbin_segments) {
+    free(iterator)
+}
+
+/// Iterate over the symbols of `.symtab` then of `.dynsym`, without the
+/// null symbol starting each table
+///
+/// # Safety
+///
+/// `file` must be an open file and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn readbin_symbols_iter(
+    file: *const readbin_file,
+    out: *mut *mut readbin_symbols,
+) -> readbin_status {
+    iterator(out, |names| {
+        let file = self::file(file)?;
+        let elf = file.elf()?;
+        let symbols = elf.all_symbols().map_err(parse_failure)?;
+        Ok(symbols
+            .filter(|symbol| symbol.index != 0)
+            .map(|symbol: Symbol| {
+                let name = c_name(symbol.name.as_bytes());
+                let record = readbin_symbol {
+                    index: symbol.index,
+                    name: name.as_ptr(),
+                    value: symbol.value(),
+                    size: symbol.size(),
+                    r#type: symbol.kind(),
+                    bind: symbol.bind(),
+                    visibility: symbol.visibility(),
+                    shndx: symbol.sym.st_shndx,
+                    dynamic: symbol.dynamic,
+                };
+                names.push(name);
+                record
+            })
+            .collect())
+    })
+}
+
+/// Write the next symbol, READBIN_END after the last one
+///
+/// # Safety
+///
+/// `iterator` must be a live iterator and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn readbin_symbols_next(
+    iterator: *mut readbin_symbols,
+    out: *mut readbin_symbol,
+) -> readbin_status {
+    next(iterator, out)
+}
+
+/// Free a symbol iterator and its names, NULL is ignored
+///
+/// # Safety
+///
+/// `iterator` must be null or a live iterator.
+#[no_mangle]
+pub unsafe extern "C" fn readbin_symbols_free(iterator: *mut readbin_symbols) {
+    free(iterator)
+}
@@ -0,0 +1,247 @@
+//! The C bindings called from Rust and from a C program built with the
+//! header
+use readbin_ffi::readbin_status::*;
+use readbin_ffi::*;
+use std::ffi::{CStr, CString};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::ptr;
+
+fn root() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// The message of the last failure
+fn last_error() -> String {
+    let message = readbin_last_error();
+    assert!(!message.is_null());
+    unsafe { CStr::from_ptr(message) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn open(path: &Path) -> *mut readbin_file {
+    let path = CString::new(path.to_str().expect("UTF-8 path")).expect("no nul");
+    let mut file = ptr::null_mut();
+    assert_eq!(
+        unsafe { readbin_open(path.as_ptr(), &mut file) },
+        READBIN_OK
+    );
+    file
+}
+
+fn section_names(file: *const readbin_file) -> Vec<String> {
+    let mut names = Vec::new();
+    unsafe {
+        let mut sections = ptr::null_mut();
+        assert_eq!(readbin_sections_iter(file, &mut sections), READBIN_OK);
+        let mut section = std::mem::zeroed();
+        while readbin_sections_next(sections, &mut section) == READBIN_OK {
+            assert_eq!(section.index, names.len());
+            names.push(CStr::from_ptr(section.name).to_string_lossy().into_owned());
+        }
+        readbin_sections_free(sections);
+    }
+    names
+}
+
+#[test]
+fn test_binary() {
+    let file = open(&std::env::current_exe().expect("test binary"));
+    unsafe {
+        assert_eq!(
+            readbin_format(file),
+            readbin_file_format::READBIN_FORMAT_ELF
+        );
+        let mut header = readbin_elf_header::default();
+        assert_eq!(readbin_header(file, &mut header), READBIN_OK);
+        assert_eq!(header.ei_class, 2);
+
+        let names = section_names(file);
+        assert_eq!(names.len() as u64, header.shnum);
+        let text = names
+            .iter()
+            .position(|name| name == ".text")
+            .expect(".text");
+        let (mut data, mut len) = (ptr::null(), 0);
+        assert_eq!(
+            readbin_section_data(file, text, &mut data, &mut len),
+            READBIN_OK
+        );
+        assert!(!data.is_null() && len > 0);
+        assert_eq!(
+            readbin_section_data(file, names.len(), &mut data, &mut len),
+            READBIN_OUT_OF_RANGE
+        );
+
+        let mut segments = ptr::null_mut();
+        assert_eq!(readbin_segments_iter(file, &mut segments), READBIN_OK);
+        let mut segment = readbin_segment::default();
+        let mut count = 0;
+        while readbin_segments_next(segments, &mut segment) == READBIN_OK {
+            count += 1;
+        }
+        assert_eq!(count, header.phnum);
+        assert_eq!(readbin_segments_next(segments, &mut segment), READBIN_END);
+        readbin_segments_free(segments);
+
+        let mut symbols = ptr::null_mut();
+        assert_eq!(readbin_symbols_iter(file, &mut symbols), READBIN_OK);
+        let mut symbol = std::mem::zeroed();
+        let mut found = false;
+        while readbin_symbols_next(symbols, &mut symbol) == READBIN_OK {
+            found |= CStr::from_ptr(symbol.name).to_bytes() == b"readbin_open" && !symbol.dynamic;
+        }
+        readbin_symbols_free(symbols);
+        assert!(found, "readbin_open is a symbol of the test binary");
+        readbin_close(file);
+    }
+}
+
+#[test]
+fn failures() {
+    unsafe {
+        let mut file = ptr::null_mut();
+        let missing = CString::new("/nonexistent/readbin").expect("no nul");
+        assert_eq!(readbin_open(missing.as_ptr(), &mut file), READBIN_IO);
+        assert!(last_error().contains("/nonexistent/readbin"));
+        assert_eq!(readbin_open(ptr::null(), &mut file), READBIN_NULL_ARGUMENT);
+        assert_eq!(last_error(), "path is null");
+        assert!(file.is_null());
+
+        let bytes = b"not a binary";
+        assert_eq!(
+            readbin_open_bytes(bytes.as_ptr(), bytes.len(), &mut file),
+            READBIN_OK
+        );
+        assert_eq!(
+            readbin_format(file),
+            readbin_file_format::READBIN_FORMAT_UNKNOWN
+        );
+        let mut header = readbin_elf_header::default();
+        assert_eq!(readbin_header(file, &mut header), READBIN_UNSUPPORTED);
+        assert_eq!(
+            readbin_header(ptr::null(), &mut header),
+            READBIN_NULL_ARGUMENT
+        );
+        readbin_close(file);
+        readbin_close(ptr::null_mut());
+        readbin_sections_free(ptr::null_mut());
+    }
+}
+
+#[test]
+fn fixture_bytes() {
+    let bytes = std::fs::read(root().join("../tests/fixtures/riscv64.o")).expect("fixture");
+    let mut file = ptr::null_mut();
+    unsafe {
+        assert_eq!(
+            readbin_open_bytes(bytes.as_ptr(), bytes.len(), &mut file),
+            READBIN_OK
+        );
+        // RISC-V
+        let mut header = readbin_elf_header::default();
+        assert_eq!(readbin_header(file, &mut header), READBIN_OK);
+        assert_eq!((header.ei_data, header.machine), (1, 243));
+        assert!(section_names(file).iter().any(|name| name == ".symtab"));
+        readbin_close(file);
+    }
+}
+
+#[test]
+fn header_declares_every_function() {
+    let header = std::fs::read_to_string(root().join("include/readbin.h")).expect("header");
+    let source = std::fs::read_to_string(root().join("src/lib.rs")).expect("source");
+    let exported: Vec<&str> = source
+        .split("#[no_mangle]")
+        .skip(1)
+        .filter_map(|item| item.split("fn ").nth(1)?.split('(').next())
+        .collect();
+    assert!(exported.len() > 10);
+    for name in exported {
+        assert!(
+            header.contains(&format!(" {}(", name)) || header.contains(&format!("*{}(", name)),
+            "{} is not declared in include/readbin.h",
+            name
+        );
+    }
+}
+
+/// A C program built with the header, printing the machine and the size
+/// of `.text`
+const PROGRAM: &str = r#"
+#include <readbin.h>
+#include <stdio.h>
+#include <string.h>
+
+int main(int argc, char **argv) {
+    readbin_file *file;
+    readbin_elf_header header;
+    readbin_sections *sections;
+    readbin_section section;
+    if (readbin_open(argv[1], &file) != READBIN_OK) {
+        fprintf(stderr, "%s\n", readbin_last_error());
+        return 1;
+    }
+    if (readbin_header(file, &header) != READBIN_OK
+        || readbin_sections_iter(file, &sections) != READBIN_OK) {
+        fprintf(stderr, "%s\n", readbin_last_error());
+        return 1;
+    }
+    printf("machine %u shnum %llu\n", header.machine, (unsigned long long)header.shnum);
+    while (readbin_sections_next(sections, &section) == READBIN_OK) {
+        if (strcmp(section.name, ".text") == 0) {
+            printf(".text %llu\n", (unsigned long long)section.size);
+        }
+    }
+    readbin_sections_free(sections);
+    readbin_close(file);
+    return 0;
+}
+"#;
+
+#[test]
+fn c_program() {
+    // target/<profile>/deps/ffi-<hash>, the library is in target/<profile>
+    let exe = std::env::current_exe().expect("test binary");
+    let lib = exe
+        .parent()
+        .and_then(Path::parent)
+        .expect("target directory");
+    if !lib.join("libreadbin_ffi.so").exists() {
+        eprintln!("skipped: no libreadbin_ffi.so in {}", lib.display());
+        return;
+    }
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let source = dir.join("readbin_ffi.c");
+    let program = dir.join("readbin_ffi");
+    std::fs::write(&source, PROGRAM).expect("source written");
+    let built = Command::new("cc")
+        .arg(&source)
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(root().join("include"))
+        .arg("-L")
+        .arg(lib)
+        .arg(format!("-Wl,-rpath,{}", lib.display()))
+        .arg("-lreadbin_ffi")
+        .arg("-o")
+        .arg(&program)
+        .status();
+    match built {
+        Ok(status) => assert!(status.success(), "the C program doesn't build"),
+        Err(err) => {
+            eprintln!("skipped: no C compiler: {}", err);
+            return;
+        }
+    }
+    let output = Command::new(&program)
+        .arg(root().join("../tests/fixtures/riscv64.o"))
+        .output()
+        .expect("program run");
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("machine 243 shnum "), "{}", stdout);
+    assert!(stdout.contains(".text "), "{}", stdout);
+}
@@ -0,0 +1,293 @@
+//! Parse arbitrary bytes and format every table, none of it may panic
+//!
+//! Run with `cargo +nightly fuzz run parse` from the root of the repository,
+//! seeding `fuzz/corpus/parse` with a few real binaries helps a lot.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use readbin::headers::elf::find::Pattern;
+use readbin::headers::elf::query::SymbolFilter;
+use readbin::headers::elf::section::SHT;
+use readbin::headers::elf::strings::Encoding;
+use readbin::headers::elf::{self, Elf};
+use readbin::headers::{ihex, pe};
+use readbin::utils::json::ToJson;
+use readbin::utils::template::{Record, Template};
+use readbin::utils::yaml::Yaml;
+use readbin::Binary;
+use std::fmt::Write;
+
+/// Format the value and its JSON into the sink
+fn render(out: &mut String, value: &(impl std::fmt::Display + ToJson)) {
+    let _ = write!(out, "{}{}", value, value.to_json());
+}
+
+/// Print every field of the records with a template
+fn fields<R: Record>(out: &mut String, records: impl IntoIterator<Item = R>) {
+    let text: Vec<String> = R::FIELDS
+        .iter()
+        .map(|name| format!("{{{}:>#12x}}", name))
+        .collect();
+    if let Ok(template) = Template::parse(&text.join(" ")) {
+        for record in records {
+            let _ = template.render(&record).map(|line| out.push_str(&line));
+        }
+    }
+}
+
+fn elf32(out: &mut String, data: &[u8], header: &elf::x86::x86) {
+    for color in [false, true] {
+        render(
+            out,
+            &elf::x86::SectionHeaderTable {
+                data,
+                header,
+                color,
+                wide: color,
+            },
+        );
+        render(
+            out,
+            &elf::x86::SymbolTable {
+                data,
+                header,
+                demangle: color,
+                color,
+                wide: color,
+            },
+        );
+    }
+    render(out, &elf::exidx::ExceptionTables { data, header });
+}
+
+fn elf64(out: &mut String, data: &[u8], header: &elf::x64::x64) {
+    for color in [false, true] {
+        render(
+            out,
+            &elf::section::SectionHeaderTable {
+                data,
+                header,
+                color,
+                wide: color,
+            },
+        );
+    }
+    render(out, &elf::program::ProgramHeaderTable { data, header });
+    let _ = write!(out, "{}", elf::raw::RawProgramHeaders { data, header });
+    let _ = write!(out, "{}", elf::raw::RawSectionHeaders { data, header });
+    render(out, &elf::dynamic::DynamicTable { data, header });
+    for demangle in [false, true] {
+        render(
+            out,
+            &elf::symbols::SymbolTable {
+                data,
+                header,
+                demangle,
+                color: demangle,
+                wide: demangle,
+            },
+        );
+        render(
+            out,
+            &elf::relocations::RelocationTable {
+                data,
+                header,
+                demangle,
+                wide: !demangle,
+            },
+        );
+    }
+    render(out, &elf::symver::VersionTable { data, header });
+    render(out, &elf::hash::HashTable { data, header });
+    render(out, &elf::notes::NoteTable { data, header });
+    render(out, &elf::ehframe::UnwindTable { data, header });
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut out = String::new();
+    if ihex::is_ihex(data) {
+        if let Ok(image) = ihex::from_bytes(data) {
+            render(
+                &mut out,
+                &elf::strings::from_image(&image, 4, Encoding::Ascii),
+            );
+            let _ = write!(out, "{}", elf::disasm::from_image(&image));
+        }
+    }
+    // the input as memory maps, and as the memory they map from 0
+    if let Ok(maps) = core::str::from_utf8(data) {
+        let read = |address: u64, len: usize| {
+            let start = usize::try_from(address).ok()?;
+            data.get(start..start.checked_add(len)?).map(<[u8]>::to_vec)
+        };
+        if let Ok(process) = elf::process::from_maps(0, maps, "", read) {
+            render(&mut out, &process);
+        }
+    }
+    match readbin::parse(data) {
+        Ok(Binary::Elf(header)) => {
+            render(&mut out, &header);
+            match &header {
+                elf::ElfHeader::Elf64(header) => elf64(&mut out, data, header),
+                elf::ElfHeader::Elf32(header) => elf32(&mut out, data, header),
+            }
+        }
+        Ok(Binary::Pe(headers)) => render(
+            &mut out,
+            &pe::PeHeaderTable {
+                data,
+                headers: &headers,
+            },
+        ),
+        Ok(Binary::MachO(macho)) => render(&mut out, &macho),
+        Ok(Binary::Archive(archive)) => render(&mut out, &archive),
+        Ok(Binary::Wasm(module)) => render(&mut out, &module),
+        Err(_) => {}
+    }
+
+    if let Ok((elf, warnings)) = Elf::parse_lenient(data) {
+        for warning in &warnings {
+            let _ = write!(out, "{}", warning);
+        }
+        if let Ok(gaps) = elf.gaps() {
+            render(&mut out, &gaps);
+        }
+        if let Ok(sizes) = elf.sizes() {
+            render(&mut out, &sizes);
+        }
+        if let Ok(map) = elf.address_map() {
+            render(&mut out, &map);
+            let diagram = elf::visualize::Diagram::new("fuzz", map);
+            out.push_str(&diagram.svg());
+            out.push_str(&diagram.html());
+        }
+        render(&mut out, &elf.provenance());
+        if let Ok(Some(auxv)) = elf.auxv() {
+            render(&mut out, &auxv);
+        }
+        if let Ok(Some(module)) = elf.kernel_module() {
+            render(&mut out, &module);
+        }
+        if let Ok(checksec) = elf.checksec() {
+            render(&mut out, &checksec);
+        }
+        if let Ok(triage) = elf.triage() {
+            render(&mut out, &triage);
+        }
+        if let Ok(digests) = elf.digests(Default::default()) {
+            render(&mut out, &digests);
+        }
+        let mut editor = elf.editor();
+        editor
+            .add_needed("libfuzz.so")
+            .add_section(".fuzz", *b"fuzz", elf::section::SHF::ALLOC)
+            .rename_symbol("main", "fuzz_main");
+        if let Ok(edited) = editor.write() {
+            let _ = Elf::parse(&edited).map(|elf| elf.header().validate(&edited));
+        }
+        if let Ok(lint) = elf.lint() {
+            render(&mut out, &lint);
+        }
+        if let Ok(plt) = elf.plt() {
+            render(&mut out, &plt);
+        }
+        if let Ok(references) = elf.references("main") {
+            render(&mut out, &references);
+        }
+        if let Ok(Some(mini)) = elf.mini_debug_info() {
+            if let Ok(header) = elf::x64::from_bytes(&mini) {
+                elf64(&mut out, &mini, &header);
+            }
+        }
+        if let Ok(init_fini) = elf.init_fini() {
+            render(&mut out, &init_fini);
+        }
+        if let Ok(paths) = elf.search_paths(Some("/origin")) {
+            render(&mut out, &paths);
+        }
+        if let Ok(exports) = elf.exports() {
+            render(&mut out, &exports);
+            if let Ok(mut imports) = elf.imports() {
+                imports.resolve("self", &exports);
+                render(&mut out, &imports);
+            }
+        }
+        // the start of the input as a pattern, most of them are invalid
+        let text = String::from_utf8_lossy(&data[..data.len().min(16)]);
+        for pattern in [Pattern::hex(&text), Pattern::hex("7f 45 ?? 4? ??")] {
+            if let Ok(matches) = pattern.and_then(|pattern| elf.find(&pattern)) {
+                render(&mut out, &matches);
+            }
+        }
+        for encoding in [Encoding::Ascii, Encoding::Utf16Le] {
+            if let Ok(strings) = elf.strings(4, encoding) {
+                render(&mut out, &strings);
+            }
+        }
+        if let Ok(tls) = elf.tls() {
+            render(&mut out, &tls);
+        }
+        if let Ok(diff) = elf.diff(&elf) {
+            render(&mut out, &diff);
+        }
+        if let Ok(disassembly) = elf.disassemble(None) {
+            let _ = write!(out, "{}", disassembly);
+        }
+        if let Some(recovered) = elf.recover() {
+            if let Ok(header) = elf::x64::from_bytes(&recovered.data) {
+                elf64(&mut out, &recovered.data, &header);
+            }
+        }
+        // build gives the same bytes back except for headers past the end of
+        // the file, built as large files, and a first section that isn't
+        // SHT_NULL, which gets a null one in front of it
+        let header = elf.header();
+        let size = data.len() as u64;
+        let null_first = elf
+            .sections()
+            .map(|mut shdrs| shdrs.next().is_none_or(|shdr| shdr.sh_type == SHT::NULL));
+        if let Ok(description) = elf.describe() {
+            let text = description.to_string();
+            assert_eq!(Yaml::parse(&text).as_ref(), Ok(&description));
+            if header.e_phoff <= size && header.e_shoff <= size && null_first == Ok(true) {
+                let built = elf::description::build(&description);
+                assert_eq!(built.as_deref(), Ok(data));
+            }
+        }
+        if let Ok(symbols) = elf.all_symbols() {
+            let _ = symbols.defined().functions().globals().count();
+        }
+        if let Ok(symbols) = elf.all_symbols() {
+            fields(&mut out, symbols);
+        }
+        if let Ok(sections) = elf::section::entries(data, &header) {
+            fields(&mut out, sections);
+        }
+        // the image spans the whole address space of the segments
+        let span = elf.program_headers().map_or(u64::MAX, |phdrs| {
+            phdrs.fold(0, |span, phdr| {
+                span.saturating_add(phdr.p_vaddr)
+                    .saturating_add(phdr.p_memsz)
+            })
+        });
+        if span < 1 << 24 {
+            if let Ok(mut image) = elf.load_image() {
+                let _ = elf.apply_relocations(&mut image, 0x7f00_0000_0000, |_| Some(0));
+            }
+        }
+        let _ = elf.lookup("main");
+        let _ = elf.line_programs();
+        if let Ok(Some(debug_line)) = elf.debug_line() {
+            let _ = debug_line.programs();
+        }
+        if let Ok(sections) = elf.sections() {
+            for shdr in sections {
+                let _ = elf.section_contents(&shdr);
+            }
+        }
+        let _ = elf.core();
+        let _ = elf.strip();
+        let _ = elf.section_containing(elf.header().e_entry);
+        let _ = elf.file_offset_for_vaddr(elf.header().e_entry);
+    }
+});
@@ -1,19 +1,2799 @@
-use readbin::headers::elf;
+use readbin::headers::elf::debuglink::{self, DebugFile};
+use readbin::headers::elf::reader::ElfReader;
+use readbin::headers::{archive, elf, ihex, macho, pe, wasm};
+use readbin::prelude::*;
+use readbin::summary::{self, Summary};
+use readbin::utils::bytestr::ByteStr;
+use readbin::utils::digest::Algorithm;
+use readbin::utils::filter::Filter;
+use readbin::utils::hexdump::HexDump;
+use readbin::utils::table::{Align, Table};
+use readbin::utils::template::{Record, Template, Value};
+use readbin::utils::yaml::Yaml;
+use readbin::utils::ToBytes;
 use std::env;
 use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process;
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    match args.len() {
-        2 => match fs::read(&args[1]) {
-            Ok(data) => match elf::x64::from_bytes(&data) {
-                Some(header) => println!("{}", header),
-                None => println!("Failed to parse elf"),
+/// Like `print!`, through the lock of the standard output
+macro_rules! out {
+    ($($arg:tt)*) => {
+        write_stdout(format_args!($($arg)*))
+    };
+}
+
+/// Like `println!`, through the lock of the standard output
+macro_rules! outln {
+    () => {
+        write_stdout(format_args!("\n"))
+    };
+    ($($arg:tt)*) => {
+        write_stdout(format_args!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+/// Write to the standard output, exiting quietly once it is closed, like
+/// at the end of a `head`, where `print!` panics
+fn write_stdout(args: std::fmt::Arguments<'_>) {
+    match io::stdout().lock().write_fmt(args) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => process::exit(0),
+        Err(err) => {
+            eprintln!("readbin: Error writing the output: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+/// Ignore the error of an output closed before the end, like by `head`
+fn closed(err: io::Error) -> io::Result<()> {
+    match err.kind() {
+        io::ErrorKind::BrokenPipe => Ok(()),
+        _ => Err(err),
+    }
+}
+
+const USAGE: &str = "usage: readbin <option(s)> <binary file(s)>
+       readbin dump-section <section> <binary file> [-o <output file>]
+       readbin hexdump [--section <name|index>] [--segment <index>] [<input>] <binary file>
+       readbin deps [--json] <binary file(s)>
+       readbin exports [--json] <binary file>
+       readbin imports [--json] [--path <dir(s)>] <binary file>
+       readbin rpath [--json] [--check-fs] <binary file(s)>
+       readbin core [--json] <core file>
+       readbin auxv [--json] <core file|/proc/<pid>/auxv>
+       readbin pid [--json] <pid>
+       readbin patch [--entry <addr>] [--type <type>] [--flags <flags>] <binary file> -o <output file>
+       readbin strip <binary file> -o <output file>
+       readbin edit rename-symbol <old name> <new name> <binary file> -o <output file>
+       readbin edit add-section [--flags <flags>] <name> <content file> <binary file> -o <output file>
+       readbin edit set-soname <soname> <binary file> -o <output file>
+       readbin edit add-needed <library> <binary file> -o <output file>
+       readbin addr2line <address(es)> <binary file>
+       readbin resolve <address(es)|entry> <binary file>
+       readbin diff [--json] <old binary file> <new binary file>
+       readbin tls [--json] <binary file>
+       readbin plt [--json] <binary file>
+       readbin xref --symbol <name> [--json] <binary file(s)>
+       readbin init-fini [--json] <binary file>
+       readbin strings [-n <min length>] [-e s|l] [--json] [<input>] <binary file>
+       readbin find (--hex <pattern>|--string <text>) [--json] [<input>] <binary file>
+       readbin checksec [--json] <binary file(s)>
+       readbin lint [--json] <binary file(s)>
+       readbin triage [--json] <binary file(s)>
+       readbin hash [--algo crc32|md5|sha1|sha256] [--json] <binary file(s)>
+       readbin gaps [--json] <binary file>
+       readbin disasm [--section <name>] [<input>] <binary file>
+       readbin size [--json] <binary file>
+       readbin map [--json] <binary file>
+       readbin visualize [--html] <binary file> [-o <output file>]
+       readbin provenance [--json] <binary file>
+       readbin summary [--json] [-j <jobs>] <binary file(s)|directory(ies)>
+       readbin export <binary file> [-o <output file>]
+       readbin build <description file> -o <output file>
+ Display information about the contents of ELF, PE, Mach-O and WebAssembly
+ format files and list the members of static archives
+ Options are:
+  -a --all               Equivalent to: -h -l -S -s -r -d -V -n
+  -h --file-header       Display the ELF file header
+  -l --program-headers   Display the program headers
+     --segments          An alias for --program-headers
+  -S --section-headers   Display the sections' header
+     --sections          An alias for --section-headers
+  -s --syms              Display the symbol table
+     --symbols           An alias for --syms
+  -d --dynamic           Display the dynamic section (if present)
+  -r --relocs            Display the relocations (if present)
+  -V --version-info      Display the version sections (if present)
+  -n --notes             Display the core notes (if present)
+  -u --unwind            Display the unwind info of .eh_frame or .ARM.exidx
+                         (if present)
+  -I --histogram         Display histogram of bucket list lengths
+  -C --demangle          Decode the C++ and Rust symbol names
+  -W --wide              Print the section and symbol names whole instead of
+                         cutting them with [...] to keep the columns narrow
+     --raw               Print the fields of the file, program and section
+                         headers with their raw values, without decoding them
+     --numeric           An alias for --raw
+     --json              Print the selected tables as JSON, one document per file
+     --format <template> Print a line per symbol, section or segment of the
+                         -s, -S and -l listings, e.g. {name} {vaddr:x} {size}
+     --filter <expr>     Only list the symbols, sections or segments of -s, -S
+                         and -l matching the expression, with the fields of
+                         --format, e.g. type==FUNC && size>4096
+     --force             Rebuild a damaged or missing section header table from
+                         the program headers, or the other way around
+     --recover           An alias for --force
+     --color=WHEN        Color the file header, section and symbol tables,
+                         WHEN is auto (the default), always or never
+  -H --help              Display this information
+ Commands are:
+  dump-section           Write the raw contents of an ELF64 section to a file,
+                         or to the standard output without -o
+  hexdump                Dump the contents of ELF64 sections or segments as
+                         bytes, like readelf -x, every block of a flash image
+                         without --segment
+  deps                   Display the interpreter and the libraries needed by
+                         ELF64 files
+  exports                Display the defined dynamic symbols of an ELF64 file
+                         with their version, @@ marks the default one
+  imports                Display the undefined dynamic symbols of an ELF64 file
+                         with the needed library of their version, --path
+                         looks for the others in the exports of the needed
+                         libraries found in its colon separated directories
+  rpath                  Display the DT_RPATH and DT_RUNPATH directories of
+                         ELF64 files with $ORIGIN expanded, which one applies,
+                         and the insecure ones: empty or relative entries, and
+                         with --check-fs the missing or world writable ones
+  core                   Display the threads, registers and mappings of an
+                         ELF64 core dump
+  auxv                   Display the auxiliary vector the kernel passed to the
+                         loader, from the NT_AUXV note of an ELF64 core dump or
+                         from a /proc/<pid>/auxv file of this machine
+  pid                    Display the ELF files mapped by a running process from
+                         its /proc maps and memory: their address range, load
+                         bias, relocated entry point and dynamic section, then
+                         the link map of the loader, needs the proc feature
+                         and Linux
+  patch                  Write a copy of an ELF64 file with new header fields,
+                         the type is one of NONE, REL, EXEC, DYN, CORE or a number
+  strip                  Write a copy of an ELF64 file without its static symbol
+                         table and debug sections
+  edit                   Write a copy of an ELF64 file with a symbol renamed, a
+                         section added, a new DT_SONAME or one more DT_NEEDED,
+                         the flags of a section are letters of WAX or a number
+  resolve                Display the segment, section and file offset of
+                         virtual addresses, entry is the entry point
+  diff                   Display the header fields, sections, segments,
+                         symbols and dynamic entries that differ between two
+                         ELF64 files
+  tls                    Display the TLS template, sections, symbols and
+                         relocations of an ELF64 file
+  plt                    Display the PLT stubs and GOT slots of an ELF64 file
+                         with the functions they resolve to
+  xref                   Display the relocations and PLT stubs referencing a
+                         symbol in ELF64 files, with their section and offset
+  init-fini              Display the constructors and destructors of an ELF64
+                         file, from DT_INIT, DT_FINI and the init/fini arrays
+  strings                Display the printable strings of an ELF64 file with
+                         their section and address, at least 4 characters
+                         long by default, -e l looks for UTF-16LE strings
+  find                   Display the file offset, section and address of every
+                         match of hex bytes, where ?? matches any byte and ?
+                         any nibble like in 48 8b ?? ??, or of a string
+  checksec               Display the RELRO, stack canary, NX, PIE, FORTIFY and
+                         symbols status of ELF64 files
+  lint                   Display the RWX segments, executable stack, writable
+                         code, text relocations and the sections whose flags
+                         don't match their segment in ELF64 files, by
+                         severity, fails if an error is found
+  triage                 Display the entropy of the sections and segments of
+                         ELF64 files and the signs of a packer: UPX magic,
+                         few sections with a segment unpacked in memory, an
+                         entry point outside .text and high entropy data
+  hash                   Display the digest of ELF64 files, of their allocated
+                         sections and of their executable sections together,
+                         sha256 by default
+  gaps                   Display the padding and overlay bytes of an ELF64 file,
+                         not covered by its headers, segments or sections
+  disasm                 Disassemble the executable sections of an x86_64
+                         ELF64 file, like objdump -d, needs the disasm feature
+  size                   Display the text, data and bss sizes of an ELF64 file
+                         like size(1), then its sections and functions from
+                         the largest
+  map                    Display the loaded segments of an ELF64 file in address
+                         order with their sections, symbols and unused
+                         addresses, like the map file of a linker
+  visualize              Draw the loaded segments of an ELF64 file and their
+                         sections as an SVG picture, or an HTML report with
+                         --html or a .html output file
+  provenance             Display the compilers, linker, build id, control flow
+                         protections and Go build information of an ELF64 file
+  summary                Display one line per file with its format, class,
+                         machine, type, PIE, stripped and build id columns,
+                         the directories are walked recursively, the files
+                         of an unknown format like scripts are skipped and
+                         the files that fail to parse are listed at the end,
+                         with the parallel feature -j threads parse them, one
+                         per CPU by default
+  export                 Write every field of an ELF64 file as a YAML
+                         description, to the standard output without -o
+  build                  Write the ELF64 file of a YAML description made by
+                         export or by hand
+ The <input> of hexdump, strings, find and disasm is:
+     --input elf|ihex|binary
+                         Read the file as ELF (the default), Intel HEX (found
+                         without --input too) or raw binary flash image
+     --base <address>    Address of the first byte of a raw binary, 0 by default";
+
+/// When to write ANSI colors
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum When {
+    /// only when printing to a terminal and NO_COLOR isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Tables selected on the command line
+#[derive(Debug, Default)]
+struct Options {
+    file_header: bool,
+    program_headers: bool,
+    section_headers: bool,
+    symbols: bool,
+    dynamic: bool,
+    relocations: bool,
+    versions: bool,
+    notes: bool,
+    unwind: bool,
+    histogram: bool,
+    demangle: bool,
+    wide: bool,
+    raw: bool,
+    json: bool,
+    force: bool,
+    format: Option<Template>,
+    filter: Option<Filter>,
+    color: When,
+    help: bool,
+    /// the tables were selected with `-a`
+    all: bool,
+}
+
+impl Options {
+    fn all(&mut self) {
+        self.all = true;
+        self.file_header = true;
+        self.program_headers = true;
+        self.section_headers = true;
+        self.symbols = true;
+        self.dynamic = true;
+        self.relocations = true;
+        self.versions = true;
+        self.notes = true;
+    }
+
+    fn any(&self) -> bool {
+        self.file_header
+            || self.program_headers
+            || self.section_headers
+            || self.symbols
+            || self.dynamic
+            || self.relocations
+            || self.versions
+            || self.notes
+            || self.unwind
+            || self.histogram
+    }
+
+    /// The flags of the selected tables that are only read from ELF64 files
+    fn elf64_only(&self) -> Vec<&'static str> {
+        [
+            (self.program_headers, "-l"),
+            (self.dynamic, "-d"),
+            (self.relocations, "-r"),
+            (self.versions, "-V"),
+            (self.notes, "-n"),
+            (self.histogram, "-I"),
+        ]
+        .into_iter()
+        .filter(|(selected, _)| *selected)
+        .map(|(_, flag)| flag)
+        .collect()
+    }
+
+    /// Fail for the tables that can't be read from an ELF32 file
+    ///
+    /// With `-a` they are skipped, the returned note names them.
+    fn check_elf32(&self) -> Result<Option<String>, String> {
+        match self.elf64_only()[..] {
+            [] => Ok(None),
+            ref flags if self.all => Ok(Some(format!(
+                "options {} are not supported for ELF32 files, their tables are skipped",
+                flags.join(" ")
+            ))),
+            ref flags => Err(format!(
+                "options {} are not supported for ELF32 files",
+                flags.join(" ")
+            )),
+        }
+    }
+
+    fn set_short(&mut self, flag: char) -> Result<(), String> {
+        match flag {
+            'a' => self.all(),
+            'h' => self.file_header = true,
+            'l' => self.program_headers = true,
+            'S' => self.section_headers = true,
+            's' => self.symbols = true,
+            'd' => self.dynamic = true,
+            'r' => self.relocations = true,
+            'V' => self.versions = true,
+            'n' => self.notes = true,
+            'u' => self.unwind = true,
+            'I' => self.histogram = true,
+            'C' => self.demangle = true,
+            'W' => self.wide = true,
+            'H' => self.help = true,
+            _ => return Err(format!("invalid option -- '{}'", flag)),
+        }
+        Ok(())
+    }
+
+    fn set_long(&mut self, flag: &str) -> Result<(), String> {
+        match flag {
+            "all" => self.all(),
+            "file-header" => self.file_header = true,
+            "program-headers" | "segments" => self.program_headers = true,
+            "section-headers" | "sections" => self.section_headers = true,
+            "syms" | "symbols" => self.symbols = true,
+            "dynamic" => self.dynamic = true,
+            "relocs" => self.relocations = true,
+            "version-info" => self.versions = true,
+            "notes" => self.notes = true,
+            "unwind" => self.unwind = true,
+            "histogram" => self.histogram = true,
+            "demangle" => self.demangle = true,
+            "wide" => self.wide = true,
+            "raw" | "numeric" => self.raw = true,
+            "json" => self.json = true,
+            "force" | "recover" => self.force = true,
+            "color" | "color=always" => self.color = When::Always,
+            "color=auto" => self.color = When::Auto,
+            "color=never" => self.color = When::Never,
+            "help" => self.help = true,
+            _ => match flag.strip_prefix("color=") {
+                Some(when) => return Err(format!("invalid argument '{}' for '--color'", when)),
+                None => return Err(format!("unrecognized option '--{}'", flag)),
             },
-            Err(err) => println!("Error reading binary: {}", err),
-        },
+        }
+        Ok(())
+    }
+
+    /// Whether the tables are written with colors
+    fn color(&self) -> bool {
+        match self.color {
+            When::Always => true,
+            When::Never => false,
+            When::Auto => {
+                io::stdout().is_terminal()
+                    && env::var_os("NO_COLOR").is_none()
+                    && env::var_os("TERM").is_some_and(|term| term != "dumb")
+            }
+        }
+    }
+}
+
+/// Split the arguments into options and file names
+///
+/// Short options can be grouped (`-lS`) and `--` ends the options.
+fn parse_args(args: &[String]) -> Result<(Options, Vec<&str>), String> {
+    let mut options = Options::default();
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            files.extend(args.by_ref().map(String::as_str));
+        } else if arg == "--format" || arg.starts_with("--format=") {
+            let text = match arg.strip_prefix("--format=") {
+                Some(text) => text,
+                None => args
+                    .next()
+                    .ok_or("option '--format' requires an argument")?,
+            };
+            let template = Template::parse(text)
+                .map_err(|err| format!("invalid argument for '--format': {}", err))?;
+            options.format = Some(template);
+        } else if arg == "--filter" || arg.starts_with("--filter=") {
+            let text = match arg.strip_prefix("--filter=") {
+                Some(text) => text,
+                None => args
+                    .next()
+                    .ok_or("option '--filter' requires an argument")?,
+            };
+            let filter = Filter::parse(text)
+                .map_err(|err| format!("invalid argument for '--filter': {}", err))?;
+            options.filter = Some(filter);
+        } else if let Some(flag) = arg.strip_prefix("--") {
+            options.set_long(flag)?;
+        } else if let Some(flags) = arg.strip_prefix('-').filter(|flags| !flags.is_empty()) {
+            for flag in flags.chars() {
+                options.set_short(flag)?;
+            }
+        } else {
+            files.push(arg.as_str());
+        }
+    }
+    if let Some(template) = &options.format {
+        if !(options.symbols || options.section_headers || options.program_headers) {
+            return Err("'--format' needs -s, -S or -l".to_string());
+        }
+        if options.json {
+            return Err("'--format' and '--json' can't be used together".to_string());
+        }
+        let checks = [
+            (options.symbols, template.check::<elf::query::Symbol>()),
+            (
+                options.section_headers,
+                template.check::<elf::section::SectionEntry>(),
+            ),
+            (
+                options.program_headers,
+                template.check::<elf::program::SegmentEntry>(),
+            ),
+        ];
+        for (_, check) in checks.into_iter().filter(|(selected, _)| *selected) {
+            check.map_err(|err| format!("invalid argument for '--format': {}", err))?;
+        }
+    }
+    if let Some(filter) = &options.filter {
+        if !(options.symbols || options.section_headers || options.program_headers) {
+            return Err("'--filter' needs -s, -S or -l".to_string());
+        }
+        if options.json {
+            return Err("'--filter' and '--json' can't be used together".to_string());
+        }
+        let checks = [
+            (options.symbols, filter.check::<elf::query::Symbol>()),
+            (
+                options.section_headers,
+                filter.check::<elf::section::SectionEntry>(),
+            ),
+            (
+                options.program_headers,
+                filter.check::<elf::program::SegmentEntry>(),
+            ),
+        ];
+        for (_, check) in checks.into_iter().filter(|(selected, _)| *selected) {
+            check.map_err(|err| format!("invalid argument for '--filter': {}", err))?;
+        }
+    }
+    // without any table selected, behave like -h
+    if !options.any() {
+        options.file_header = true;
+    }
+    Ok((options, files))
+}
+
+/// Print the headers of a PE file like `dumpbin /headers`
+fn display_pe(options: &Options, file: &str, data: &[u8], headers: &pe::PeHeaders) {
+    let table = pe::PeHeaderTable { data, headers };
+    if options.json {
+        let members = vec![("file", Json::from(file)), ("pe", table.to_json())];
+        outln!("{}", Json::object(members));
+    } else {
+        outln!("{}", table);
+    }
+}
+
+/// Print the header and load commands of a Mach-O file like `otool -hl`
+fn display_macho(options: &Options, file: &str, macho: &macho::MachOFile) {
+    if options.json {
+        let members = vec![("file", Json::from(file)), ("macho", macho.to_json())];
+        outln!("{}", Json::object(members));
+    } else {
+        out!("{}", macho);
+    }
+}
+
+/// List the members of a static archive like `ar tv`, with their object type
+fn display_archive(options: &Options, file: &str, archive: &archive::Archive) {
+    if options.json {
+        let members = vec![("file", Json::from(file)), ("archive", archive.to_json())];
+        outln!("{}", Json::object(members));
+    } else {
+        out!("{}", archive);
+    }
+}
+
+/// Print the sections, imports and exports of a WebAssembly module like `wasm-objdump -x`
+fn display_wasm(options: &Options, file: &str, module: &wasm::Wasm) {
+    if options.json {
+        let members = vec![("file", Json::from(file)), ("wasm", module.to_json())];
+        outln!("{}", Json::object(members));
+    } else {
+        out!("{}", module);
+    }
+}
+
+/// The separate debug file of a binary without a static symbol table
+///
+/// Looked up with the build ID and `.gnu_debuglink` under /usr/lib/debug.
+fn separate_symbols(file: &str, data: &[u8], header: &elf::x64::x64) -> Option<DebugFile> {
+    let elf = Elf::parse(data).ok()?;
+    if !matches!(elf.symbols(), Ok(None)) {
+        return None;
+    }
+    let debug_dir = Path::new(debuglink::DEBUG_DIRECTORY);
+    debuglink::find(Path::new(file), data, header, debug_dir)
+}
+
+/// Lines of the listings filtered without `--format`, like the columns of
+/// the tables
+const SECTION_LINE: &str =
+    "[{index:>2}] {name:<17} {type:<15} {addr:016x} {offset:08x} {size:016x} {flags:>3}";
+const SEGMENT_LINE: &str =
+    "{type:<14} {offset:#018x} {vaddr:#018x} {filesz:#018x} {memsz:#018x} {flags:<3}";
+const SYMBOL_LINE: &str =
+    "{index:>6}: {value:016x} {size:>5} {type:<7} {bind:<6} {visibility:<8} {ndx:>4} {name}";
+
+/// The template of a listing printed a line per record, the `--format` one
+/// or `default` when only `--filter` is given
+fn listing_template(options: &Options, default: &str) -> Option<Template> {
+    match (&options.format, &options.filter) {
+        (Some(template), _) => Some(template.clone()),
+        (None, Some(_)) => Template::parse(default).ok(),
+        (None, None) => None,
+    }
+}
+
+/// A symbol of the `--format` and `--filter` listings, its name is the
+/// demangled one with `-C`
+struct Listed<'data> {
+    symbol: elf::query::Symbol<'data>,
+    demangle: bool,
+}
+
+impl Record for Listed<'_> {
+    const FIELDS: &'static [&'static str] = elf::query::Symbol::FIELDS;
+
+    fn field(&self, name: &str) -> Option<Value<'_>> {
+        match name {
+            "name" if self.demangle => self.symbol.field("demangled"),
+            _ => self.symbol.field(name),
+        }
+    }
+}
+
+/// Print a line per record kept by the `--filter` expression, with the
+/// `--format` template
+///
+/// Stops quietly when the output is closed, like the end of a `head`.
+fn print_records<R: Record>(
+    template: &Template,
+    filter: Option<&Filter>,
+    records: impl IntoIterator<Item = R>,
+) {
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    for record in records {
+        if filter.is_some_and(|filter| !filter.matches(&record)) {
+            continue;
+        }
+        // the fields were checked against the listing with the options
+        let line = template.render(&record).unwrap_or_default();
+        if writeln!(out, "{}", line).is_err() {
+            return;
+        }
+    }
+    let _ = out.flush();
+}
+
+/// The ELF file embedded in `.gnu_debugdata`, None if there is none
+fn mini_debug_info(data: &[u8]) -> Option<Result<Vec<u8>, readbin::Error>> {
+    Elf::parse(data)
+        .and_then(|elf| elf.mini_debug_info())
+        .transpose()
+}
+
+/// Print the selected tables of a file as a single JSON object
+fn display_elf_json(
+    options: &Options,
+    file: &str,
+    data: &[u8],
+    header: elf::ElfHeader,
+) -> Result<(), String> {
+    let mut members = vec![("file", Json::from(file))];
+    if options.file_header {
+        members.push(("header", header.to_json()));
+    }
+    if let Err(err) = header.validate(data) {
+        members.push(("warning", Json::from(err.to_string())));
+    }
+    if let elf::ElfHeader::Elf64(header) = header {
+        let (data, header) = (data, &*header);
+        if options.file_header {
+            if let Ok(Some(module)) = elf::module::from_bytes(data, header) {
+                members.push(("module", module.to_json()));
+            }
+        }
+        if options.section_headers {
+            let table = elf::section::SectionHeaderTable {
+                data,
+                header,
+                color: false,
+                wide: true,
+            };
+            members.push(("sections", table.to_json()));
+        }
+        if options.program_headers {
+            let table = elf::program::ProgramHeaderTable { data, header };
+            members.push(("segments", table.to_json()));
+        }
+        if options.dynamic {
+            let table = elf::dynamic::DynamicTable { data, header };
+            members.push(("dynamic", table.to_json()));
+        }
+        if options.relocations {
+            let table = elf::relocations::RelocationTable {
+                data,
+                header,
+                demangle: options.demangle,
+                wide: true,
+            };
+            members.push(("relocations", table.to_json()));
+        }
+        if options.symbols {
+            let table = elf::symbols::SymbolTable {
+                data,
+                header,
+                demangle: options.demangle,
+                color: false,
+                wide: true,
+            };
+            members.push(("symbols", table.to_json()));
+            let debug = separate_symbols(file, data, header);
+            let debug_header = debug
+                .as_ref()
+                .and_then(|debug| elf::x64::from_bytes(&debug.data).ok());
+            if let (Some(debug), Some(debug_header)) = (&debug, debug_header) {
+                let table = elf::symbols::SymbolTable {
+                    data: &debug.data,
+                    header: &debug_header,
+                    demangle: options.demangle,
+                    color: false,
+                    wide: true,
+                };
+                let path = debug.path.display().to_string();
+                members.push(("debug_file", Json::from(path)));
+                members.push(("debug_symbols", table.to_json()));
+            }
+            if let Some(Ok(mini)) = mini_debug_info(data) {
+                if let Ok(mini_header) = elf::x64::from_bytes(&mini) {
+                    let table = elf::symbols::SymbolTable {
+                        data: &mini,
+                        header: &mini_header,
+                        demangle: options.demangle,
+                        color: false,
+                        wide: true,
+                    };
+                    members.push(("mini_debug_info_symbols", table.to_json()));
+                }
+            }
+        }
+        if options.versions {
+            let table = elf::symver::VersionTable { data, header };
+            members.push(("versions", table.to_json()));
+        }
+        if options.histogram {
+            let table = elf::hash::HashTable { data, header };
+            members.push(("histogram", table.to_json()));
+        }
+        if options.notes {
+            let table = elf::notes::NoteTable { data, header };
+            members.push(("notes", table.to_json()));
+        }
+        if options.unwind {
+            let table = elf::ehframe::UnwindTable { data, header };
+            members.push(("unwind", table.to_json()));
+        }
+    } else if let elf::ElfHeader::Elf32(header) = header {
+        let checked = options.check_elf32();
+        let (data, header) = (data, &*header);
+        if options.section_headers {
+            let table = elf::x86::SectionHeaderTable {
+                data,
+                header,
+                color: false,
+                wide: true,
+            };
+            members.push(("sections", table.to_json()));
+        }
+        if options.symbols {
+            let table = elf::x86::SymbolTable {
+                data,
+                header,
+                demangle: options.demangle,
+                color: false,
+                wide: true,
+            };
+            members.push(("symbols", table.to_json()));
+        }
+        if options.unwind {
+            let table = elf::exidx::ExceptionTables { data, header };
+            members.push(("unwind", table.to_json()));
+        }
+        if let Ok(Some(note)) = &checked {
+            members.push(("note", Json::from(note.as_str())));
+        }
+        outln!("{}", Json::object(members));
+        return checked.map(drop);
+    }
+    outln!("{}", Json::object(members));
+    Ok(())
+}
+
+/// Print the selected tables of a file, in the same order as readelf
+///
+/// Only the file header, the section headers, the symbols and the unwind
+/// tables are read from ELF32 files. The other tables fail once those are
+/// printed, or are skipped with a note for `-a`.
+fn display_elf(
+    options: &Options,
+    file: &str,
+    data: &[u8],
+    header: elf::ElfHeader,
+) -> Result<(), String> {
+    let color = options.color();
+    if options.file_header && options.raw {
+        outln!("{}", elf::raw::RawFileHeader { header: &header });
+    } else if options.file_header {
+        let table = elf::FileHeader {
+            header: &header,
+            color,
+        };
+        outln!("{}", table);
+    }
+    if let Err(err) = header.validate(data) {
+        outln!("Warning: {}", err);
+    }
+    let header = match header {
+        elf::ElfHeader::Elf64(header) => header,
+        elf::ElfHeader::Elf32(header) => {
+            let header = &*header;
+            if options.section_headers {
+                let table = elf::x86::SectionHeaderTable {
+                    data,
+                    header,
+                    color,
+                    wide: options.wide,
+                };
+                outln!("{}", table);
+            }
+            if options.symbols {
+                let table = elf::x86::SymbolTable {
+                    data,
+                    header,
+                    demangle: options.demangle,
+                    color,
+                    wide: options.wide,
+                };
+                outln!("{}", table);
+            }
+            if options.unwind {
+                let table = elf::exidx::ExceptionTables { data, header };
+                out!("{}", table);
+            }
+            if let Some(note) = options.check_elf32()? {
+                outln!("Note: {}", note);
+            }
+            return Ok(());
+        }
+    };
+    if options.file_header {
+        if let Ok(Some(module)) = elf::module::from_bytes(data, &header) {
+            outln!("{}", module);
+        }
+    }
+    let filter = options.filter.as_ref();
+    let format = |selected, default| listing_template(options, default).filter(|_| selected);
+    if let Some(template) = format(options.section_headers, SECTION_LINE) {
+        match elf::section::entries(data, &header) {
+            Ok(sections) => print_records(&template, filter, sections),
+            Err(err) => outln!("Warning: invalid section header table: {}", err),
+        }
+    } else if options.section_headers && options.raw {
+        let table = elf::raw::RawSectionHeaders {
+            data,
+            header: &header,
+        };
+        outln!("{}", table);
+    } else if options.section_headers {
+        let table = elf::section::SectionHeaderTable {
+            data,
+            header: &header,
+            color,
+            wide: options.wide,
+        };
+        outln!("{}", table);
+    }
+    if let Some(template) = format(options.program_headers, SEGMENT_LINE) {
+        match elf::program::entries(data, &header) {
+            Ok(entries) => print_records(&template, filter, entries),
+            Err(err) => outln!("Warning: invalid program header table: {}", err),
+        }
+    } else if options.program_headers && options.raw {
+        let table = elf::raw::RawProgramHeaders {
+            data,
+            header: &header,
+        };
+        outln!("{}", table);
+    } else if options.program_headers {
+        let table = elf::program::ProgramHeaderTable {
+            data,
+            header: &header,
+        };
+        outln!("{}", table);
+    }
+    if options.dynamic {
+        let table = elf::dynamic::DynamicTable {
+            data,
+            header: &header,
+        };
+        outln!("{}", table);
+    }
+    if options.relocations {
+        let table = elf::relocations::RelocationTable {
+            data,
+            header: &header,
+            demangle: options.demangle,
+            wide: options.wide,
+        };
+        outln!("{}", table);
+    }
+    if let Some(template) = format(options.symbols, SYMBOL_LINE) {
+        // the MiniDebugInfo symbols follow the ones of the file
+        let mini = mini_debug_info(data).and_then(Result::ok);
+        let mini = mini.as_deref().and_then(|mini| Elf::parse(mini).ok());
+        let mini_symbols = mini.as_ref().and_then(|mini| mini.all_symbols().ok());
+        match Elf::parse(data).and_then(|elf| elf.all_symbols()) {
+            Ok(symbols) => print_records(
+                &template,
+                filter,
+                symbols
+                    .chain(mini_symbols.into_iter().flatten())
+                    .filter(|symbol| symbol.index != 0)
+                    .map(|symbol| Listed {
+                        symbol,
+                        demangle: options.demangle,
+                    }),
+            ),
+            Err(err) => outln!("Warning: invalid symbol table: {}", err),
+        }
+    } else if options.symbols {
+        let table = elf::symbols::SymbolTable {
+            data,
+            header: &header,
+            demangle: options.demangle,
+            color,
+            wide: options.wide,
+        };
+        outln!("{}", table);
+        let debug = separate_symbols(file, data, &header);
+        let debug_header = debug
+            .as_ref()
+            .and_then(|debug| elf::x64::from_bytes(&debug.data).ok());
+        if let (Some(debug), Some(debug_header)) = (&debug, debug_header) {
+            outln!(
+                "Symbols of the separate debug file '{}':",
+                debug.path.display()
+            );
+            let table = elf::symbols::SymbolTable {
+                data: &debug.data,
+                header: &debug_header,
+                demangle: options.demangle,
+                color,
+                wide: options.wide,
+            };
+            outln!("{}", table);
+        }
+        let mini = mini_debug_info(data).map(|mini| {
+            mini.and_then(|mini| {
+                let header = elf::x64::from_bytes(&mini)?.into_owned();
+                Ok((header, mini))
+            })
+        });
+        match mini {
+            Some(Ok((mini_header, mini))) => {
+                outln!(
+                    "Symbols of the MiniDebugInfo in '{}':",
+                    elf::debugdata::SECTION
+                );
+                let table = elf::symbols::SymbolTable {
+                    data: &mini,
+                    header: &mini_header,
+                    demangle: options.demangle,
+                    color,
+                    wide: options.wide,
+                };
+                outln!("{}", table);
+            }
+            Some(Err(_)) if !cfg!(feature = "xz") => outln!(
+                "Warning: readbin was built without the xz feature to decode {}",
+                elf::debugdata::SECTION
+            ),
+            Some(Err(err)) => outln!("Warning: invalid {}: {}", elf::debugdata::SECTION, err),
+            None => {}
+        }
+    }
+    if options.versions {
+        let table = elf::symver::VersionTable {
+            data,
+            header: &header,
+        };
+        out!("{}", table);
+    }
+    if options.histogram {
+        let table = elf::hash::HashTable {
+            data,
+            header: &header,
+        };
+        out!("{}", table);
+    }
+    if options.notes {
+        let table = elf::notes::NoteTable {
+            data,
+            header: &header,
+        };
+        outln!("{}", table);
+    }
+    if options.unwind {
+        let table = elf::ehframe::UnwindTable {
+            data,
+            header: &header,
+        };
+        out!("{}", table);
+    }
+    Ok(())
+}
+
+/// Read the whole file in memory
+#[cfg(not(all(unix, feature = "mmap")))]
+fn read(file: &str) -> io::Result<Vec<u8>> {
+    fs::read(file)
+}
+
+/// Map the file in memory instead of copying it
+#[cfg(all(unix, feature = "mmap"))]
+fn read(file: &str) -> io::Result<readbin::utils::mmap::Mmap> {
+    readbin::utils::mmap::Mmap::map(&fs::File::open(file)?)
+}
+
+/// Detect the format of a file and print it
+fn display(options: &Options, file: &str, data: &[u8]) -> Result<(), String> {
+    let recovered = match Elf::parse(data) {
+        Ok(elf) if options.force => elf.recover(),
+        _ => None,
+    };
+    if let Some(recovered) = &recovered {
+        eprintln!("Warning: {}: {}", file, recovered);
+    }
+    let data = recovered
+        .as_ref()
+        .map_or(data, |recovered| &recovered.data[..]);
+    let binary = readbin::parse(data).map_err(|err| parse_error(data, err))?;
+    match binary {
+        Binary::Elf(header) if options.json => display_elf_json(options, file, data, header)?,
+        Binary::Elf(header) => display_elf(options, file, data, header)?,
+        Binary::Pe(headers) => display_pe(options, file, data, &headers),
+        Binary::MachO(macho) => display_macho(options, file, &macho),
+        Binary::Archive(archive) => display_archive(options, file, &archive),
+        Binary::Wasm(module) => display_wasm(options, file, &module),
+    }
+    Ok(())
+}
+
+/// The message of a file that can't be parsed, with its format
+fn parse_error(data: &[u8], err: readbin::Error) -> String {
+    match err {
+        readbin::Error::UnsupportedFormat(_) => err.to_string(),
+        _ => format!("Failed to parse {}: {}", readbin::identify(data), err),
+    }
+}
+
+/// Write the contents of a section, like `objcopy --dump-section`
+///
+/// Expects the section name and the file, optionally followed by
+/// `-o <output file>`.
+fn dump_section(args: &[String]) -> Result<(), String> {
+    let mut output = None;
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => {
+                let file = args.next().ok_or("option requires an argument -- 'o'")?;
+                output = Some(file.as_str());
+            }
+            _ => positional.push(arg.as_str()),
+        }
+    }
+    let (name, file) = match positional[..] {
+        [name, file] => (name, file),
+        _ => return Err("dump-section expects a section name and a file".to_string()),
+    };
+    // Only the section headers and the section itself are read
+    let source = fs::File::open(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let mut elf = ElfReader::new(io::BufReader::new(source))
+        .map_err(|err| format!("Failed to parse elf: {}", err))?;
+    let shdr = elf
+        .section_by_name(name)
+        .map_err(|err| format!("Failed to parse elf: {}", err))?
+        .ok_or(format!("section '{}' does not exist in {}", name, file))?;
+    if shdr.section_type() == elf::section::SectionType::Nobits {
+        return Err(format!("section '{}' has no contents", name));
+    }
+    let bytes = elf
+        .section_data(&shdr)
+        .map_err(|err| format!("section '{}' has no contents: {}", name, err))?;
+    let big_endian = elf::data::Data::is_big_endian(&elf.header().e_ident);
+    let bytes = elf::compress::decompress(name, &shdr, &bytes, big_endian)
+        .map_err(|err| format!("section '{}' can't be decompressed: {}", name, err))?;
+    match output {
+        Some(output) => fs::write(output, &bytes),
+        None => io::stdout().lock().write_all(&bytes).or_else(closed),
+    }
+    .map_err(|err| format!("Error writing section: {}", err))
+}
+
+/// How hexdump, strings and disasm read their file
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Input {
+    /// an ELF file, or an Intel HEX file recognized by its first record
+    #[default]
+    Elf,
+    IntelHex,
+    Binary,
+}
+
+/// The input options shared by hexdump, strings and disasm
+#[derive(Debug, Default)]
+struct InputOptions {
+    input: Input,
+    base: Option<u64>,
+}
+
+impl InputOptions {
+    /// Take `--input` or `--base` and its value, false for another argument
+    fn parse<'a>(
+        &mut self,
+        arg: &str,
+        args: &mut impl Iterator<Item = &'a String>,
+    ) -> Result<bool, String> {
+        match arg {
+            "--input" => {
+                let value = args
+                    .next()
+                    .ok_or("option requires an argument -- 'input'")?;
+                self.input = match value.as_str() {
+                    "elf" => Input::Elf,
+                    "ihex" => Input::IntelHex,
+                    "binary" => Input::Binary,
+                    _ => {
+                        return Err(format!(
+                            "invalid input '{}', expected elf, ihex or binary",
+                            value
+                        ))
+                    }
+                };
+            }
+            "--base" => {
+                let value = args.next().ok_or("option requires an argument -- 'base'")?;
+                self.base = Some(parse_number(value)?);
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// The memory image of an Intel HEX file or of a raw binary, None for
+    /// an ELF file
+    fn image(&self, data: &[u8]) -> Result<Option<LoadImage>, String> {
+        let input = match self.input {
+            Input::Elf if !data.starts_with(b"\x7fELF") && ihex::is_ihex(data) => Input::IntelHex,
+            input => input,
+        };
+        if self.base.is_some() && input != Input::Binary {
+            return Err("--base only applies to a raw binary".to_string());
+        }
+        match input {
+            Input::Elf => Ok(None),
+            Input::IntelHex => ihex::from_bytes(data)
+                .map(Some)
+                .map_err(|err| format!("Failed to parse Intel HEX: {}", err)),
+            Input::Binary => {
+                let flags = elf::program::PF::R | elf::program::PF::W | elf::program::PF::X;
+                elf::image::from_blocks(&[(self.base.unwrap_or(0), data)], flags)
+                    .map(Some)
+                    .map_err(|err| format!("Failed to load binary: {}", err))
+            }
+        }
+    }
+}
+
+/// Section or segment selected by `hexdump`
+enum Dump<'a> {
+    Section(&'a str),
+    Segment(&'a str),
+}
+
+/// Print the contents of sections and segments, like `readelf -x`
+///
+/// Sections are looked up by name first, then by index.
+fn hexdump(args: &[String]) -> Result<(), String> {
+    let mut dumps = Vec::new();
+    let mut files = Vec::new();
+    let mut input = InputOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            _ if input.parse(arg, &mut args)? => {}
+            "-x" | "--section" => {
+                let name = args
+                    .next()
+                    .ok_or("option requires an argument -- 'section'")?;
+                dumps.push(Dump::Section(name));
+            }
+            "--segment" => {
+                let index = args
+                    .next()
+                    .ok_or("option requires an argument -- 'segment'")?;
+                dumps.push(Dump::Segment(index));
+            }
+            _ => files.push(arg.as_str()),
+        }
+    }
+    let file = match files[..] {
+        [file] => file,
+        _ => return Err("hexdump expects a single file".to_string()),
+    };
+    let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    if let Some(image) = input.image(&data)? {
+        return hexdump_image(&image, &dumps);
+    }
+    if dumps.is_empty() {
+        return Err("hexdump expects at least one --section or --segment".to_string());
+    }
+    let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+    for dump in dumps {
+        match dump {
+            Dump::Section(name) => {
+                let shdr = elf.section_by_name(name).or_else(|| {
+                    let index = name.parse::<usize>().ok()?;
+                    elf.sections().ok()?.nth(index)
+                });
+                let shdr = match shdr {
+                    Some(shdr) => shdr,
+                    None => {
+                        eprintln!(
+                            "readbin: Warning: Section '{}' was not dumped because it does not exist",
+                            name
+                        );
+                        continue;
+                    }
+                };
+                let name = elf.section_name(&shdr).unwrap_or(ByteStr::from(name));
+                match elf.section_contents(&shdr) {
+                    Ok(bytes) if !bytes.is_empty() => {
+                        outln!();
+                        outln!("Hex dump of section '{}':", name);
+                        outln!("{}", HexDump::new(&bytes, shdr.sh_addr));
+                    }
+                    Err(
+                        err @ (Error::InvalidCompressedData(_) | Error::UnsupportedCompression(_)),
+                    ) => {
+                        eprintln!(
+                            "readbin: Warning: Section '{}' can't be decompressed: {}",
+                            name, err
+                        )
+                    }
+                    _ => outln!("Section '{}' has no data to dump.", name),
+                }
+            }
+            Dump::Segment(index) => {
+                let phdr = index
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| elf.program_headers().ok()?.nth(index));
+                let phdr = match phdr {
+                    Some(phdr) => phdr,
+                    None => {
+                        eprintln!(
+                            "readbin: Warning: Segment '{}' was not dumped because it does not exist",
+                            index
+                        );
+                        continue;
+                    }
+                };
+                match phdr.data(elf.data()) {
+                    Some(bytes) if !bytes.is_empty() => {
+                        outln!();
+                        outln!("Hex dump of segment {} ({}):", index, phdr.type_name());
+                        outln!("{}", HexDump::new(bytes, phdr.p_vaddr));
+                    }
+                    _ => outln!("Segment {} has no data to dump.", index),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Print the blocks of a flash image selected with `--segment`, all of
+/// them without
+fn hexdump_image(image: &LoadImage, dumps: &[Dump]) -> Result<(), String> {
+    let mut selected = Vec::new();
+    for dump in dumps {
+        let (kind, index) = match dump {
+            Dump::Section(name) => ("Section", *name),
+            Dump::Segment(index) => ("Segment", *index),
+        };
+        match index.parse::<usize>().ok().filter(|_| kind == "Segment") {
+            Some(index) if index < image.mappings.len() => selected.push(index),
+            _ => eprintln!(
+                "readbin: Warning: {} '{}' was not dumped because it does not exist",
+                kind, index
+            ),
+        }
+    }
+    if dumps.is_empty() {
+        selected.extend(0..image.mappings.len());
+    }
+    for index in selected {
+        let mapping = &image.mappings[index];
+        let bytes = image
+            .get(mapping.vaddr, mapping.filesz as usize)
+            .unwrap_or_default();
+        outln!();
+        outln!("Hex dump of .sec{}:", index + 1);
+        outln!("{}", HexDump::new(bytes, mapping.vaddr));
+    }
+    Ok(())
+}
+
+/// Print the interpreter and the needed libraries of each file
+fn deps(args: &[String]) -> Result<(), String> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let files: Vec<&str> = args
+        .iter()
+        .filter(|arg| *arg != "--json")
+        .map(String::as_str)
+        .collect();
+    if files.is_empty() {
+        return Err("deps expects at least one file".to_string());
+    }
+    for file in &files {
+        let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+        let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+        let needed = elf
+            .needed_libraries()
+            .map_err(|err| format!("Failed to parse the dynamic section: {}", err))?;
+        let dynamic = elf.dynamic().ok().flatten();
+        let rpath = dynamic.as_ref().and_then(|dynamic| dynamic.rpath());
+        let runpath = dynamic.as_ref().and_then(|dynamic| dynamic.runpath());
+        if json {
+            let members = vec![
+                ("file", Json::from(*file)),
+                ("interpreter", Json::from(elf.interpreter())),
+                ("needed", Json::array(needed)),
+                ("rpath", Json::from(rpath)),
+                ("runpath", Json::from(runpath)),
+            ];
+            outln!("{}", Json::object(members));
+            continue;
+        }
+        if files.len() > 1 {
+            outln!("{}:", file);
+        }
+        match elf.interpreter() {
+            Some(interpreter) => outln!("  Interpreter: {}", interpreter),
+            None => outln!("  Interpreter: none"),
+        }
+        if let Some(rpath) = rpath {
+            outln!("  Library rpath: [{}]", rpath);
+        }
+        if let Some(runpath) = runpath {
+            outln!("  Library runpath: [{}]", runpath);
+        }
+        if needed.is_empty() {
+            outln!("  statically linked");
+        }
+        for library in needed {
+            outln!("  NEEDED {}", library);
+        }
+    }
+    Ok(())
+}
+
+/// Print the defined dynamic symbols of a file
+fn exports(args: &[String]) -> Result<(), String> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let file = match args
+        .iter()
+        .filter(|arg| *arg != "--json")
+        .collect::<Vec<_>>()[..]
+    {
+        [file] => file,
+        _ => return Err("exports expects a single file".to_string()),
+    };
+    let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+    let exports = elf
+        .exports()
+        .map_err(|err| format!("Failed to parse the dynamic symbols: {}", err))?;
+    if json {
+        let members = vec![
+            ("file", Json::from(file.as_str())),
+            ("exports", exports.to_json()),
+        ];
+        outln!("{}", Json::object(members));
+    } else {
+        out!("{}", exports);
+    }
+    Ok(())
+}
+
+/// Print the undefined dynamic symbols of a file and the libraries
+/// providing them
+fn imports(args: &[String]) -> Result<(), String> {
+    let mut json = false;
+    let mut path = Vec::new();
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--path" => {
+                let value = args.next().ok_or("option '--path' requires an argument")?;
+                path.extend(value.split(':').filter(|dir| !dir.is_empty()));
+            }
+            _ => files.push(arg.as_str()),
+        }
+    }
+    let file = match files[..] {
+        [file] => file,
+        _ => return Err("imports expects a single file".to_string()),
+    };
+    let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+    let mut imports = elf
+        .imports()
+        .map_err(|err| format!("Failed to parse the dynamic symbols: {}", err))?;
+    for library in imports.needed.clone() {
+        // the first directory holding the library, like the dynamic linker
+        let found = path
+            .iter()
+            .map(|dir| Path::new(dir).join(library))
+            .find(|candidate| candidate.is_file());
+        let candidate = match found {
+            Some(candidate) => candidate,
+            None => continue,
+        };
+        let data = match read(&candidate.to_string_lossy()) {
+            Ok(data) => data,
+            Err(err) => {
+                eprintln!("readbin: Warning: {}: {}", candidate.display(), err);
+                continue;
+            }
+        };
+        match Elf::parse(&data).and_then(|library| library.exports()) {
+            Ok(exports) => imports.resolve(library, &exports),
+            Err(err) => eprintln!("readbin: Warning: {}: {}", candidate.display(), err),
+        }
+    }
+    if json {
+        let members = vec![("file", Json::from(file)), ("imports", imports.to_json())];
+        outln!("{}", Json::object(members));
+    } else {
+        out!("{}", imports);
+    }
+    Ok(())
+}
+
+/// Print the library search paths of files and the insecure directories
+fn rpath(args: &[String]) -> Result<(), String> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let check_fs = args.iter().any(|arg| arg == "--check-fs");
+    let files: Vec<&str> = args
+        .iter()
+        .filter(|arg| *arg != "--json" && *arg != "--check-fs")
+        .map(String::as_str)
+        .collect();
+    if files.is_empty() {
+        return Err("rpath expects at least one file".to_string());
+    }
+    for file in &files {
+        let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+        let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+        // $ORIGIN is the directory of the file once the symbolic links are followed
+        let origin = fs::canonicalize(file)
+            .ok()
+            .and_then(|path| Some(path.parent()?.to_string_lossy().into_owned()));
+        let mut paths = elf
+            .search_paths(origin.as_deref())
+            .map_err(|err| format!("Failed to parse the dynamic section: {}", err))?;
+        if check_fs {
+            paths.check_fs();
+        }
+        if json {
+            let members = vec![("file", Json::from(*file)), ("rpath", paths.to_json())];
+            outln!("{}", Json::object(members));
+            continue;
+        }
+        if files.len() > 1 {
+            outln!("{}:", file);
+        }
+        out!("{}", paths);
+    }
+    Ok(())
+}
+
+/// Print the process state stored in a core dump
+fn core(args: &[String]) -> Result<(), String> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let file = match args
+        .iter()
+        .filter(|arg| *arg != "--json")
+        .collect::<Vec<_>>()[..]
+    {
+        [file] => file,
+        _ => return Err("core expects a single file".to_string()),
+    };
+    let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+    let core = elf
+        .core()
+        .map_err(|err| format!("Failed to parse core file: {}", err))?;
+    if json {
+        let members = vec![
+            ("file", Json::from(file.as_str())),
+            ("core", core.to_json()),
+        ];
+        outln!("{}", Json::object(members));
+    } else {
+        out!("{}", core);
+    }
+    Ok(())
+}
+
+/// Print the auxiliary vector of a core dump or of a running process
+///
+/// A file that isn't ELF is read as the raw vector of `/proc/<pid>/auxv`,
+/// in the byte order and for the machine of the host.
+fn auxv(args: &[String]) -> Result<(), String> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let file = match args
+        .iter()
+        .filter(|arg| *arg != "--json")
+        .collect::<Vec<_>>()[..]
+    {
+        [file] => file,
+        _ => return Err("auxv expects a single file".to_string()),
+    };
+    // the files of /proc have no size and can't be mapped
+    let data = fs::read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let auxv = match readbin::identify(&data) {
+        readbin::Format::Elf => {
+            let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+            elf.auxv()
+                .map_err(|err| format!("Failed to parse core file: {}", err))?
+                .ok_or_else(|| format!("{} has no NT_AUXV note", file))?
+        }
         _ => {
-            println!("usage: {:?} <binary file>", args[0]);
+            let machine = match env::consts::ARCH {
+                "x86_64" => elf::machine::Machine::X86_64,
+                "aarch64" => elf::machine::Machine::Aarch64,
+                _ => elf::machine::Machine::None,
+            };
+            elf::auxv::Auxv::parse(&data, cfg!(target_endian = "big"), machine)
         }
+    };
+    if json {
+        let members = vec![
+            ("file", Json::from(file.as_str())),
+            ("auxv", auxv.to_json()),
+        ];
+        outln!("{}", Json::object(members));
+    } else {
+        out!("{}", auxv);
+    }
+    Ok(())
+}
+
+/// Print the ELF files mapped by a running process
+#[cfg(all(target_os = "linux", feature = "proc"))]
+fn pid(args: &[String]) -> Result<(), String> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let pid = match args
+        .iter()
+        .filter(|arg| *arg != "--json")
+        .collect::<Vec<_>>()[..]
+    {
+        [pid] => pid
+            .parse::<u32>()
+            .map_err(|_| format!("invalid process id '{}'", pid))?,
+        _ => return Err("pid expects a single process id".to_string()),
+    };
+    let process = elf::process::from_pid(pid)
+        .map_err(|err| format!("Failed to read process {}: {}", pid, err))?;
+    if process.modules.is_empty() {
+        eprintln!(
+            "Warning: no ELF file could be read in the memory of process {}",
+            pid
+        );
+    }
+    if json {
+        outln!("{}", process.to_json());
+    } else {
+        out!("{}", process);
+    }
+    Ok(())
+}
+
+/// The pid command reads /proc, only built on Linux with the proc feature
+#[cfg(not(all(target_os = "linux", feature = "proc")))]
+fn pid(_args: &[String]) -> Result<(), String> {
+    match cfg!(target_os = "linux") {
+        true => Err("readbin was built without the proc feature".to_string()),
+        false => Err("the pid command needs the /proc of Linux".to_string()),
+    }
+}
+
+/// Parse a decimal or `0x` prefixed hexadecimal number
+fn parse_number(value: &str) -> Result<u64, String> {
+    let parsed = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    parsed.map_err(|_| format!("invalid number '{}'", value))
+}
+
+/// Parse an object file type name as printed by readelf, or its value
+fn parse_type(value: &str) -> Result<u16, String> {
+    use elf::consts::Type;
+    const TYPES: [Type; 5] = [Type::None, Type::Rel, Type::Exec, Type::Dyn, Type::Core];
+    if let Some(e_type) = TYPES
+        .into_iter()
+        .find(|e_type| e_type.as_str().eq_ignore_ascii_case(value))
+    {
+        return Ok(e_type.into());
+    }
+    parse_number(value)
+        .ok()
+        .and_then(|number| u16::try_from(number).ok())
+        .ok_or_else(|| {
+            let names: Vec<_> = TYPES.iter().map(Type::as_str).collect();
+            format!(
+                "invalid object file type '{}', neither one of {} nor a number up to 0xffff",
+                value,
+                names.join(", ")
+            )
+        })
+}
+
+/// Write a new binary with the permissions of the file it was made from,
+/// executable by whoever can read it when `runnable`
+fn write_like(input: &str, output: &str, data: &[u8], runnable: bool) -> Result<(), String> {
+    let error = |err: io::Error| format!("Error writing binary: {}", err);
+    fs::write(output, data).map_err(error)?;
+    #[allow(unused_mut)]
+    let mut permissions = fs::metadata(input).map_err(error)?.permissions();
+    #[cfg(unix)]
+    if runnable {
+        use std::os::unix::fs::PermissionsExt;
+        permissions.set_mode(permissions.mode() | (permissions.mode() & 0o444) >> 2);
+    }
+    #[cfg(not(unix))]
+    let _ = runnable;
+    fs::set_permissions(output, permissions).map_err(error)
+}
+
+/// Write a copy of a file with some of its ELF header fields changed
+///
+/// The patched file is parsed and validated before being written.
+fn patch(args: &[String]) -> Result<(), String> {
+    let mut entry = None;
+    let mut e_type = None;
+    let mut flags = None;
+    let mut output = None;
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .map(String::as_str)
+                .ok_or(format!("option '{}' requires an argument", arg))
+        };
+        match arg.as_str() {
+            "--entry" => entry = Some(parse_number(value()?)?),
+            "--type" => e_type = Some(parse_type(value()?)?),
+            "--flags" => {
+                let value = value()?;
+                let value = u32::try_from(parse_number(value)?)
+                    .map_err(|_| format!("invalid flags '{}'", value))?;
+                flags = Some(value);
+            }
+            "-o" | "--output" => output = Some(value()?),
+            _ => files.push(arg.as_str()),
+        }
+    }
+    let file = match files[..] {
+        [file] => file,
+        _ => return Err("patch expects a single input file".to_string()),
+    };
+    let output = output.ok_or("patch expects an output file, use -o")?;
+    let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+    let mut header = *elf.header();
+    if let Some(entry) = entry {
+        header.e_entry = entry;
+    }
+    if let Some(e_type) = e_type {
+        header.e_type = e_type;
+    }
+    if let Some(flags) = flags {
+        header.e_flags = flags;
+    }
+    let big_endian = elf::data::Data::is_big_endian(&header.e_ident);
+    let mut patched = data.to_vec();
+    patched[..elf::x64::x64::SIZE].copy_from_slice(&header.to_bytes(big_endian));
+    Elf::parse(&patched)
+        .and_then(|elf| elf.header().validate(&patched))
+        .map_err(|err| format!("Patched file is invalid: {}", err))?;
+    write_like(file, output, &patched, false)
+}
+
+/// Write a copy of a file without its static symbols and debug sections
+fn strip(args: &[String]) -> Result<(), String> {
+    let mut output = None;
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => {
+                let file = args.next().ok_or("option requires an argument -- 'o'")?;
+                output = Some(file.as_str());
+            }
+            _ => files.push(arg.as_str()),
+        }
+    }
+    let file = match files[..] {
+        [file] => file,
+        _ => return Err("strip expects a single input file".to_string()),
+    };
+    let output = output.ok_or("strip expects an output file, use -o")?;
+    let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+    let stripped = elf
+        .strip()
+        .map_err(|err| format!("Failed to strip {}: {}", file, err))?;
+    Elf::parse(&stripped)
+        .and_then(|elf| elf.header().validate(&stripped))
+        .map_err(|err| format!("Stripped file is invalid: {}", err))?;
+    write_like(file, output, &stripped, false)
+}
+
+/// Parse section flags, letters of W (write), A (alloc) and X (execute)
+/// or a number
+fn parse_section_flags(value: &str) -> Result<u64, String> {
+    if let Ok(flags) = parse_number(value) {
+        return Ok(flags);
+    }
+    value.chars().try_fold(0, |flags, letter| match letter {
+        'W' | 'w' => Ok(flags | elf::section::SHF::WRITE),
+        'A' | 'a' => Ok(flags | elf::section::SHF::ALLOC),
+        'X' | 'x' => Ok(flags | elf::section::SHF::EXECINSTR),
+        _ => Err(format!("invalid section flags '{}'", value)),
+    })
+}
+
+/// Write a copy of a file with a symbol, a section or its dependencies
+/// edited
+///
+/// The edited file is parsed and validated before being written.
+fn edit(args: &[String]) -> Result<(), String> {
+    let commands = "rename-symbol, add-section, set-soname or add-needed";
+    let (command, args) = args
+        .split_first()
+        .ok_or(format!("edit expects {}", commands))?;
+    let mut flags = 0;
+    let mut output = None;
+    let mut values = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .map(String::as_str)
+                .ok_or(format!("option '{}' requires an argument", arg))
+        };
+        match arg.as_str() {
+            "--flags" => flags = parse_section_flags(value()?)?,
+            "-o" | "--output" => output = Some(value()?),
+            _ => values.push(arg.as_str()),
+        }
+    }
+    let count = match command.as_str() {
+        "rename-symbol" | "add-section" => 3,
+        "set-soname" | "add-needed" => 2,
+        _ => return Err(format!("unknown edit '{}', expected {}", command, commands)),
+    };
+    if values.len() != count {
+        return Err(format!("{} expects {} arguments", command, count));
+    }
+    let file = values[count - 1];
+    let output = output.ok_or("edit expects an output file, use -o")?;
+    let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+    let mut editor = elf.editor();
+    match command.as_str() {
+        "rename-symbol" => editor.rename_symbol(values[0], values[1]),
+        "add-section" => {
+            let content =
+                fs::read(values[1]).map_err(|err| format!("Error reading content: {}", err))?;
+            editor.add_section(values[0], content, flags)
+        }
+        "set-soname" => editor.set_soname(values[0]),
+        _ => editor.add_needed(values[0]),
+    };
+    let edited = editor
+        .write()
+        .map_err(|err| format!("Failed to edit {}: {}", file, err))?;
+    Elf::parse(&edited)
+        .and_then(|elf| elf.header().validate(&edited))
+        .map_err(|err| format!("Edited file is invalid: {}", err))?;
+    write_like(file, output, &edited, false)
+}
+
+/// Print the source file and line of each address, like `addr2line -e`
+///
+/// Addresses are hexadecimal, with or without the `0x` prefix.
+fn addr2line(args: &[String]) -> Result<(), String> {
+    let (file, addresses) = match args.split_last() {
+        Some((file, addresses)) if !addresses.is_empty() => (file, addresses),
+        _ => return Err("addr2line expects addresses followed by a file".to_string()),
+    };
+    let addresses = addresses
+        .iter()
+        .map(|address| {
+            let hex = address.strip_prefix("0x").unwrap_or(address);
+            u64::from_str_radix(hex, 16).map_err(|_| format!("invalid address '{}'", address))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+    // stripped binaries keep their line tables in a separate debug file
+    let debug = match elf.section_by_name(".debug_line") {
+        Some(_) => None,
+        None => debuglink::find(
+            Path::new(file),
+            &data,
+            elf.header(),
+            Path::new(debuglink::DEBUG_DIRECTORY),
+        ),
+    };
+    let debug_elf = match &debug {
+        Some(debug) => Some(Elf::parse(&debug.data).map_err(|err| {
+            format!(
+                "Failed to parse the debug file {}: {}",
+                debug.path.display(),
+                err
+            )
+        })?),
+        None => None,
+    };
+    let debug_line = debug_elf
+        .as_ref()
+        .unwrap_or(&elf)
+        .debug_line()
+        .map_err(|err| format!("Failed to read .debug_line: {}", err))?
+        .unwrap_or_default();
+    let programs = debug_line
+        .programs()
+        .map_err(|err| format!("Failed to parse .debug_line: {}", err))?;
+    for address in addresses {
+        match elf::debug::find(&programs, address) {
+            Some(location) => outln!("{}", location),
+            None => outln!("??:?"),
+        }
+    }
+    Ok(())
+}
+
+/// Print the segment, section and file offset of each virtual address
+///
+/// Addresses are numbers like the patch options, `entry` stands for
+/// the entry point of the file.
+fn resolve(args: &[String]) -> Result<(), String> {
+    let (file, addresses) = match args.split_last() {
+        Some((file, addresses)) if !addresses.is_empty() => (file, addresses),
+        _ => return Err("resolve expects addresses followed by a file".to_string()),
+    };
+    let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+    let names = elf.section_names().unwrap_or_default();
+    for address in addresses {
+        let vaddr = match address.as_str() {
+            "entry" => elf.header().e_entry,
+            address => parse_number(address)?,
+        };
+        let segment = elf
+            .segment_containing(vaddr)
+            .map_err(|err| format!("Failed to parse the program headers: {}", err))?;
+        let section = elf
+            .section_containing(vaddr)
+            .map_err(|err| format!("Failed to parse the section headers: {}", err))?;
+        let segment = match segment {
+            Some((index, phdr)) => format!("segment [{:02}] {}", index, phdr.type_name()),
+            None => "no segment".to_string(),
+        };
+        let section = match section {
+            Some((index, shdr)) => format!(
+                "section [{:2}] {}+{:#x}",
+                index,
+                shdr.name(&names).unwrap_or(elf::strtab::CORRUPT),
+                vaddr - shdr.sh_addr
+            ),
+            None => "no section".to_string(),
+        };
+        let offset = match elf.file_offset_for_vaddr(vaddr) {
+            Some(offset) => format!("offset {:#x}", offset),
+            None => "not in the file".to_string(),
+        };
+        outln!("{:#x}: {}, {}, {}", vaddr, segment, section, offset);
+    }
+    Ok(())
+}
+
+/// Print what changed from a file to another
+fn diff(args: &[String]) -> Result<(), String> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let (old, new) = match args
+        .iter()
+        .filter(|arg| *arg != "--json")
+        .collect::<Vec<_>>()[..]
+    {
+        [old, new] => (old, new),
+        _ => return Err("diff expects two files".to_string()),
+    };
+    let old_data = read(old).map_err(|err| format!("Error reading binary: {}", err))?;
+    let new_data = read(new).map_err(|err| format!("Error reading binary: {}", err))?;
+    let parse = |data| Elf::parse(data).map_err(|err| format!("Failed to parse elf: {}", err));
+    let diff = parse(&old_data)?
+        .diff(&parse(&new_data)?)
+        .map_err(|err| format!("Failed to compare {} and {}: {}", old, new, err))?;
+    if json {
+        let members = vec![
+            ("old", Json::from(old.as_str())),
+            ("new", Json::from(new.as_str())),
+            ("diff", diff.to_json()),
+        ];
+        outln!("{}", Json::object(members));
+    } else {
+        out!("{}", diff);
+    }
+    Ok(())
+}
+
+/// Print the thread local storage of a file
+fn tls(args: &[String]) -> Result<(), String> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let file = match args
+        .iter()
+        .filter(|arg| *arg != "--json")
+        .collect::<Vec<_>>()[..]
+    {
+        [file] => file,
+        _ => return Err("tls expects a single file".to_string()),
+    };
+    let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+    let tls = elf
+        .tls()
+        .map_err(|err| format!("Failed to parse the TLS of {}: {}", file, err))?;
+    if json {
+        let members = vec![("file", Json::from(file.as_str())), ("tls", tls.to_json())];
+        outln!("{}", Json::object(members));
+    } else {
+        out!("{}", tls);
+    }
+    Ok(())
+}
+
+/// Print the PLT stubs and GOT slots of a file with their targets
+fn plt(args: &[String]) -> Result<(), String> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let file = match args
+        .iter()
+        .filter(|arg| *arg != "--json")
+        .collect::<Vec<_>>()[..]
+    {
+        [file] => file,
+        _ => return Err("plt expects a single file".to_string()),
+    };
+    let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+    let plt = elf
+        .plt()
+        .map_err(|err| format!("Failed to parse the PLT of {}: {}", file, err))?;
+    if json {
+        let members = vec![("file", Json::from(file.as_str())), ("plt", plt.to_json())];
+        outln!("{}", Json::object(members));
+    } else {
+        out!("{}", plt);
+    }
+    Ok(())
+}
+
+/// Print the places referencing a symbol in each file
+fn xref(args: &[String]) -> Result<(), String> {
+    let mut json = false;
+    let mut symbol = None;
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--symbol" => {
+                let value = args
+                    .next()
+                    .ok_or("option requires an argument -- 'symbol'")?;
+                symbol = Some(value.as_str());
+            }
+            _ => files.push(arg.as_str()),
+        }
+    }
+    let symbol = symbol.ok_or("xref expects --symbol <name>")?;
+    if files.is_empty() {
+        return Err("xref expects at least one file".to_string());
+    }
+    for file in &files {
+        let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+        let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+        let references = elf
+            .references(symbol)
+            .map_err(|err| format!("Failed to inspect {}: {}", file, err))?;
+        if json {
+            let members = vec![("file", Json::from(*file)), ("xref", references.to_json())];
+            outln!("{}", Json::object(members));
+            continue;
+        }
+        if files.len() > 1 {
+            outln!("{}:", file);
+        }
+        out!("{}", references);
+    }
+    Ok(())
+}
+
+/// Print the functions run before main and at exit
+fn init_fini(args: &[String]) -> Result<(), String> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let file = match args
+        .iter()
+        .filter(|arg| *arg != "--json")
+        .collect::<Vec<_>>()[..]
+    {
+        [file] => file,
+        _ => return Err("init-fini expects a single file".to_string()),
+    };
+    let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+    let init_fini = elf.init_fini().map_err(|err| {
+        format!(
+            "Failed to parse the init and fini functions of {}: {}",
+            file, err
+        )
+    })?;
+    if json {
+        let members = vec![
+            ("file", Json::from(file.as_str())),
+            ("init_fini", init_fini.to_json()),
+        ];
+        outln!("{}", Json::object(members));
+    } else {
+        out!("{}", init_fini);
+    }
+    Ok(())
+}
+
+/// Print the printable strings of a file, like `strings -a`
+fn strings(args: &[String]) -> Result<(), String> {
+    let mut json = false;
+    let mut min_len = 4;
+    let mut encoding = elf::strings::Encoding::Ascii;
+    let mut files = Vec::new();
+    let mut input = InputOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            _ if input.parse(arg, &mut args)? => {}
+            "--json" => json = true,
+            "-n" | "--bytes" => {
+                let value = args.next().ok_or("option requires an argument -- 'n'")?;
+                min_len = match value.parse::<usize>() {
+                    Ok(min_len) if min_len > 0 => min_len,
+                    _ => return Err(format!("invalid minimum string length '{}'", value)),
+                };
+            }
+            "-e" | "--encoding" => {
+                let value = args.next().ok_or("option requires an argument -- 'e'")?;
+                encoding = match value.as_str() {
+                    "s" => elf::strings::Encoding::Ascii,
+                    "l" => elf::strings::Encoding::Utf16Le,
+                    _ => return Err(format!("invalid encoding '{}', expected s or l", value)),
+                };
+            }
+            _ => files.push(arg.as_str()),
+        }
+    }
+    let file = match files[..] {
+        [file] => file,
+        _ => return Err("strings expects a single file".to_string()),
+    };
+    let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let strings = match input.image(&data)? {
+        Some(image) => elf::strings::from_image(&image, min_len, encoding),
+        None => Elf::parse(&data)
+            .map_err(|err| format!("Failed to parse elf: {}", err))?
+            .strings(min_len, encoding)
+            .map_err(|err| format!("Failed to inspect {}: {}", file, err))?,
+    };
+    if json {
+        let members = vec![("file", Json::from(file)), ("strings", strings.to_json())];
+        outln!("{}", Json::object(members));
+    } else {
+        out!("{}", strings);
+    }
+    Ok(())
+}
+
+/// Print the matches of a byte pattern or of a string
+fn find(args: &[String]) -> Result<(), String> {
+    let mut json = false;
+    let mut pattern = None;
+    let mut files = Vec::new();
+    let mut input = InputOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            _ if input.parse(arg, &mut args)? => {}
+            "--json" => json = true,
+            "--hex" => {
+                let value = args.next().ok_or("option requires an argument -- 'hex'")?;
+                let hex = elf::find::Pattern::hex(value)
+                    .map_err(|err| format!("'{}': {}", value, err))?;
+                pattern = Some(hex);
+            }
+            "--string" => {
+                let value = args
+                    .next()
+                    .ok_or("option requires an argument -- 'string'")?;
+                pattern = Some(elf::find::Pattern::text(value));
+            }
+            _ => files.push(arg.as_str()),
+        }
+    }
+    let pattern = pattern.ok_or("find expects --hex <pattern> or --string <text>")?;
+    if pattern.is_empty() {
+        return Err("find expects a non empty string".to_string());
+    }
+    let file = match files[..] {
+        [file] => file,
+        _ => return Err("find expects a single file".to_string()),
+    };
+    let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let image = input.image(&data)?;
+    let matches = match &image {
+        Some(image) => elf::find::from_image(image, &pattern),
+        None => Elf::parse(&data)
+            .map_err(|err| format!("Failed to parse elf: {}", err))?
+            .find(&pattern)
+            .map_err(|err| format!("Failed to inspect {}: {}", file, err))?,
+    };
+    if json {
+        let members = vec![("file", Json::from(file)), ("find", matches.to_json())];
+        outln!("{}", Json::object(members));
+    } else {
+        out!("{}", matches);
+    }
+    Ok(())
+}
+
+/// Print the hardening features of each file
+fn checksec(args: &[String]) -> Result<(), String> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let files: Vec<&str> = args
+        .iter()
+        .filter(|arg| *arg != "--json")
+        .map(String::as_str)
+        .collect();
+    if files.is_empty() {
+        return Err("checksec expects at least one file".to_string());
+    }
+    for file in &files {
+        let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+        let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+        let checksec = elf
+            .checksec()
+            .map_err(|err| format!("Failed to inspect {}: {}", file, err))?;
+        if json {
+            let members = vec![
+                ("file", Json::from(*file)),
+                ("checksec", checksec.to_json()),
+            ];
+            outln!("{}", Json::object(members));
+            continue;
+        }
+        if files.len() > 1 {
+            outln!("{}:", file);
+        }
+        out!("{}", checksec);
+    }
+    Ok(())
+}
+
+/// Print the entropy and the packer findings of each file
+fn triage(args: &[String]) -> Result<(), String> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let files: Vec<&str> = args
+        .iter()
+        .filter(|arg| *arg != "--json")
+        .map(String::as_str)
+        .collect();
+    if files.is_empty() {
+        return Err("triage expects at least one file".to_string());
+    }
+    for file in &files {
+        let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+        let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+        let triage = elf
+            .triage()
+            .map_err(|err| format!("Failed to inspect {}: {}", file, err))?;
+        if json {
+            let members = vec![("file", Json::from(*file)), ("triage", triage.to_json())];
+            outln!("{}", Json::object(members));
+            continue;
+        }
+        if files.len() > 1 {
+            outln!("{}:", file);
+        }
+        out!("{}", triage);
+    }
+    Ok(())
+}
+
+/// Print the digests of each file and of its sections
+fn hash(args: &[String]) -> Result<(), String> {
+    let mut json = false;
+    let mut algorithm = Algorithm::default();
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--algo" => {
+                let value = args.next().ok_or("option requires an argument -- 'algo'")?;
+                algorithm = value.parse()?;
+            }
+            _ => files.push(arg.as_str()),
+        }
+    }
+    if files.is_empty() {
+        return Err("hash expects at least one file".to_string());
+    }
+    for file in &files {
+        let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+        let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+        let digests = elf
+            .digests(algorithm)
+            .map_err(|err| format!("Failed to inspect {}: {}", file, err))?;
+        if json {
+            let members = vec![("file", Json::from(*file)), ("hash", digests.to_json())];
+            outln!("{}", Json::object(members));
+            continue;
+        }
+        if files.len() > 1 {
+            outln!("{}:", file);
+        }
+        out!("{}", digests);
+    }
+    Ok(())
+}
+
+/// Print the permission problems of files, fails if one has an error
+fn lint(args: &[String]) -> Result<(), String> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let files: Vec<&str> = args
+        .iter()
+        .filter(|arg| *arg != "--json")
+        .map(String::as_str)
+        .collect();
+    if files.is_empty() {
+        return Err("lint expects at least one file".to_string());
+    }
+    let mut failed = 0;
+    for file in &files {
+        let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+        let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+        let lint = elf
+            .lint()
+            .map_err(|err| format!("Failed to inspect {}: {}", file, err))?;
+        if lint.max_severity() == Some(elf::lint::Severity::Error) {
+            failed += 1;
+        }
+        if json {
+            let members = vec![("file", Json::from(*file)), ("lint", lint.to_json())];
+            outln!("{}", Json::object(members));
+            continue;
+        }
+        if files.len() > 1 {
+            outln!("{}:", file);
+        }
+        out!("{}", lint);
+    }
+    match failed {
+        0 => Ok(()),
+        count => Err(format!("{} files have permission errors", count)),
+    }
+}
+
+/// Print the ranges of a file that no header, segment or section covers
+fn gaps(args: &[String]) -> Result<(), String> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let file = match args
+        .iter()
+        .filter(|arg| *arg != "--json")
+        .collect::<Vec<_>>()[..]
+    {
+        [file] => file,
+        _ => return Err("gaps expects a single file".to_string()),
+    };
+    let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+    let gaps = elf
+        .gaps()
+        .map_err(|err| format!("Failed to inspect {}: {}", file, err))?;
+    if json {
+        let members = vec![
+            ("file", Json::from(file.as_str())),
+            ("gaps", gaps.to_json()),
+        ];
+        outln!("{}", Json::object(members));
+    } else {
+        out!("{}", gaps);
+    }
+    Ok(())
+}
+
+/// Print the size of the sections and of the functions
+fn size(args: &[String]) -> Result<(), String> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let file = match args
+        .iter()
+        .filter(|arg| *arg != "--json")
+        .collect::<Vec<_>>()[..]
+    {
+        [file] => file,
+        _ => return Err("size expects a single file".to_string()),
+    };
+    let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+    let sizes = elf
+        .sizes()
+        .map_err(|err| format!("Failed to measure {}: {}", file, err))?;
+    if json {
+        let members = vec![
+            ("file", Json::from(file.as_str())),
+            ("size", sizes.to_json()),
+        ];
+        outln!("{}", Json::object(members));
+    } else {
+        out!("{}", sizes);
+    }
+    Ok(())
+}
+
+/// Print the address map of a file, like a linker map file
+fn map(args: &[String]) -> Result<(), String> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let file = match args
+        .iter()
+        .filter(|arg| *arg != "--json")
+        .collect::<Vec<_>>()[..]
+    {
+        [file] => file,
+        _ => return Err("map expects a single file".to_string()),
+    };
+    let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+    let map = elf
+        .address_map()
+        .map_err(|err| format!("Failed to map {}: {}", file, err))?;
+    if json {
+        let members = vec![("file", Json::from(file.as_str())), ("map", map.to_json())];
+        outln!("{}", Json::object(members));
+    } else {
+        out!("{}", map);
+    }
+    Ok(())
+}
+
+/// Draw the address space of a file as SVG or HTML
+fn visualize(args: &[String]) -> Result<(), String> {
+    let html = args.iter().any(|arg| arg == "--html");
+    let args: Vec<String> = args
+        .iter()
+        .filter(|arg| *arg != "--html")
+        .cloned()
+        .collect();
+    let (output, files) = output_and_files(&args)?;
+    let file = match files[..] {
+        [file] => file,
+        _ => return Err("visualize expects a single file".to_string()),
+    };
+    let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+    let map = elf
+        .address_map()
+        .map_err(|err| format!("Failed to map {}: {}", file, err))?;
+    let title = Path::new(file)
+        .file_name()
+        .map_or(file.into(), |name| name.to_string_lossy());
+    let diagram = elf::visualize::Diagram::new(&title, map);
+    let html = html || output.is_some_and(|output| output.ends_with(".html"));
+    let picture = match html {
+        true => diagram.html(),
+        false => diagram.svg(),
+    };
+    match output {
+        Some(output) => fs::write(output, picture),
+        None => io::stdout()
+            .lock()
+            .write_all(picture.as_bytes())
+            .or_else(closed),
+    }
+    .map_err(|err| format!("Error writing picture: {}", err))
+}
+
+/// Print what built a file: compilers, linker and build properties
+fn provenance(args: &[String]) -> Result<(), String> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let file = match args
+        .iter()
+        .filter(|arg| *arg != "--json")
+        .collect::<Vec<_>>()[..]
+    {
+        [file] => file,
+        _ => return Err("provenance expects a single file".to_string()),
+    };
+    let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+    let provenance = elf.provenance();
+    if json {
+        let members = vec![
+            ("file", Json::from(file.as_str())),
+            ("provenance", provenance.to_json()),
+        ];
+        outln!("{}", Json::object(members));
+    } else {
+        out!("{}", provenance);
+    }
+    Ok(())
+}
+
+/// The cells of the summary row of `file`
+fn summary_row(file: &str, summary: &Summary) -> [String; 8] {
+    let yes_no = |value: Option<bool>| match value {
+        Some(true) => "yes",
+        Some(false) => "no",
+        None => "-",
+    };
+    [
+        file.to_string(),
+        summary.format.to_string(),
+        summary.class.unwrap_or("-").to_string(),
+        summary.machine.unwrap_or("-").to_string(),
+        summary.kind.unwrap_or("-").to_string(),
+        yes_no(summary.pie).to_string(),
+        yes_no(summary.stripped).to_string(),
+        summary.build_id.as_deref().unwrap_or("-").to_string(),
+    ]
+}
+
+/// The summary of `file` as a JSON object
+fn summary_json(file: &str, summary: &Summary) -> Json {
+    Json::object([
+        ("file", Json::from(file)),
+        ("format", Json::from(summary.format)),
+        ("class", Json::from(summary.class)),
+        ("machine", Json::from(summary.machine)),
+        ("type", Json::from(summary.kind)),
+        ("pie", Json::from(summary.pie)),
+        ("stripped", Json::from(summary.stripped)),
+        ("build_id", Json::from(summary.build_id.clone())),
+    ])
+}
+
+/// Apply `f` to every file, the results in the order of the files
+///
+/// The files are shared between `jobs` threads, one per CPU by default.
+#[cfg(feature = "parallel")]
+fn map_files<T, F>(files: &[PathBuf], jobs: Option<usize>, f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&Path) -> T + Sync,
+{
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::thread;
+
+    let jobs = jobs
+        .or_else(|| thread::available_parallelism().ok().map(usize::from))
+        .unwrap_or(1)
+        .min(files.len());
+    if jobs <= 1 {
+        return files.iter().map(|file| f(file)).collect();
+    }
+    // each thread takes the next file, the results are put back in order
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..files.len()).map(|_| None).collect::<Vec<_>>());
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let file = match files.get(index) {
+                    Some(file) => file,
+                    None => break,
+                };
+                let result = f(file);
+                results.lock().expect("no thread panicked")[index] = Some(result);
+            });
+        }
+    });
+    results
+        .into_inner()
+        .expect("no thread panicked")
+        .into_iter()
+        .map(|result| result.expect("every file is mapped"))
+        .collect()
+}
+
+/// Apply `f` to every file, the results in the order of the files
+///
+/// Without the `parallel` feature the files are read one after the other.
+#[cfg(not(feature = "parallel"))]
+fn map_files<T, F>(files: &[PathBuf], _jobs: Option<usize>, f: F) -> Vec<T>
+where
+    F: Fn(&Path) -> T,
+{
+    files.iter().map(|file| f(file)).collect()
+}
+
+/// Print a line per file, then the files skipped and the files that
+/// couldn't be read or parsed
+///
+/// The files without a known magic, like scripts, are skipped. Fails once
+/// every file is summarized if any of the others failed.
+fn summary(args: &[String]) -> Result<(), String> {
+    let mut json = false;
+    let mut jobs = None;
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "-j" | "--jobs" => {
+                let value = args.next().ok_or("option requires an argument -- 'j'")?;
+                jobs = match value.parse::<usize>() {
+                    Ok(jobs) if jobs > 0 => Some(jobs),
+                    _ => return Err(format!("invalid number of jobs '{}'", value)),
+                };
+            }
+            _ => paths.push(arg.as_str()),
+        }
+    }
+    if paths.is_empty() {
+        return Err("summary expects at least one file or directory".to_string());
+    }
+    let mut files = Vec::new();
+    let mut failures = Vec::new();
+    for path in &paths {
+        let path = Path::new(path);
+        if !fs::metadata(path).is_ok_and(|metadata| metadata.is_dir()) {
+            files.push(path.to_path_buf());
+        } else if let Err(err) = summary::walk(path, &mut files) {
+            failures.push((
+                path.display().to_string(),
+                format!("Error reading directory: {}", err),
+            ));
+        }
+    }
+    let mut table = Table::new(false)
+        .column("File", Align::Left, None)
+        .column("Format", Align::Left, None)
+        .column("Class", Align::Left, None)
+        .column("Machine", Align::Left, None)
+        .column("Type", Align::Left, None)
+        .column("PIE", Align::Left, None)
+        .column("Stripped", Align::Left, None)
+        .column("Build ID", Align::Left, None);
+    // None for the files skipped
+    let summaries = map_files(&files, jobs, |file| {
+        let data = read(&file.display().to_string())
+            .map_err(|err| format!("Error reading binary: {}", err))?;
+        match Summary::from_bytes(&data) {
+            Err(readbin::Error::UnsupportedFormat(Format::Unknown)) => Ok(None),
+            summary => summary.map(Some).map_err(|err| parse_error(&data, err)),
+        }
+    });
+    let mut skipped = Vec::new();
+    for (file, summary) in files.iter().zip(summaries) {
+        let file = file.display().to_string();
+        match summary {
+            Ok(Some(summary)) if json => outln!("{}", summary_json(&file, &summary)),
+            Ok(Some(summary)) => table.row(summary_row(&file, &summary)),
+            Ok(None) => skipped.push(file),
+            Err(err) => failures.push((file, err)),
+        }
+    }
+    if json {
+        for file in &skipped {
+            let skip = Json::object([
+                ("file", Json::from(file.as_str())),
+                ("format", Json::from(Format::Unknown.name())),
+                ("skipped", Json::from(true)),
+            ]);
+            outln!("{}", skip);
+        }
+        for (file, err) in &failures {
+            let error = Json::object([
+                ("file", Json::from(file.as_str())),
+                ("error", Json::from(err.as_str())),
+            ]);
+            outln!("{}", error);
+        }
+    } else {
+        out!("{}", table);
+        if !skipped.is_empty() {
+            outln!();
+            outln!("Skipped {} files of an unknown format:", skipped.len());
+            for file in &skipped {
+                outln!("  {}", file);
+            }
+        }
+        if !failures.is_empty() {
+            outln!();
+            outln!("Failed to summarize {} paths:", failures.len());
+            for (file, err) in &failures {
+                outln!("  {}: {}", file, err);
+            }
+        }
+    }
+    match failures.len() {
+        0 => Ok(()),
+        count => Err(format!("{} paths could not be summarized", count)),
+    }
+}
+
+/// Split the arguments of a command in its `-o` output file and the others
+fn output_and_files(args: &[String]) -> Result<(Option<&str>, Vec<&str>), String> {
+    let mut output = None;
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => {
+                let file = args.next().ok_or("option requires an argument -- 'o'")?;
+                output = Some(file.as_str());
+            }
+            _ => files.push(arg.as_str()),
+        }
+    }
+    Ok((output, files))
+}
+
+/// Write the YAML description of a file, like `obj2yaml`
+fn export(args: &[String]) -> Result<(), String> {
+    let (output, files) = output_and_files(args)?;
+    let file = match files[..] {
+        [file] => file,
+        _ => return Err("export expects a single input file".to_string()),
+    };
+    let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+    let description = elf
+        .describe()
+        .map_err(|err| format!("Failed to describe {}: {}", file, err))?
+        .to_string();
+    match output {
+        Some(output) => fs::write(output, description),
+        None => io::stdout()
+            .lock()
+            .write_all(description.as_bytes())
+            .or_else(closed),
+    }
+    .map_err(|err| format!("Error writing description: {}", err))
+}
+
+/// Write the file of a YAML description, like `yaml2obj`
+///
+/// The file is parsed and validated before being written.
+fn build(args: &[String]) -> Result<(), String> {
+    let (output, files) = output_and_files(args)?;
+    let file = match files[..] {
+        [file] => file,
+        _ => return Err("build expects a single description file".to_string()),
+    };
+    let output = output.ok_or("build expects an output file, use -o")?;
+    let text =
+        fs::read_to_string(file).map_err(|err| format!("Error reading description: {}", err))?;
+    let description = Yaml::parse(&text).map_err(|err| format!("{}: {}", file, err))?;
+    let data = elf::description::build(&description).map_err(|err| format!("{}: {}", file, err))?;
+    let e_type = Elf::parse(&data)
+        .and_then(|elf| elf.header().validate(&data).map(|_| elf.header().e_type))
+        .map_err(|err| format!("Built file is invalid: {}", err))?;
+    // executables and shared objects can be run, like the output of a linker
+    let runnable = matches!(
+        elf::consts::Type::from(e_type),
+        elf::consts::Type::Exec | elf::consts::Type::Dyn
+    );
+    write_like(file, output, &data, runnable)
+}
+
+/// Print the instructions of the code sections, like `objdump -d`
+#[cfg(feature = "disasm")]
+fn disasm(args: &[String]) -> Result<(), String> {
+    let mut section = None;
+    let mut files = Vec::new();
+    let mut input = InputOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            _ if input.parse(arg, &mut args)? => {}
+            "-j" | "--section" => {
+                let value = args
+                    .next()
+                    .ok_or("option requires an argument -- 'section'")?;
+                section = Some(value.as_str());
+            }
+            _ => files.push(arg.as_str()),
+        }
+    }
+    let file = match files[..] {
+        [file] => file,
+        _ => return Err("disasm expects a single file".to_string()),
+    };
+    let data = read(file).map_err(|err| format!("Error reading binary: {}", err))?;
+    let image = input.image(&data)?;
+    let (disassembly, format) = match &image {
+        Some(image) => (elf::disasm::from_image(image), "binary"),
+        None => {
+            let elf = Elf::parse(&data).map_err(|err| format!("Failed to parse elf: {}", err))?;
+            let disassembly = elf
+                .disassemble(section)
+                .map_err(|err| format!("Failed to disassemble {}: {}", file, err))?;
+            (disassembly, "elf64-x86-64")
+        }
+    };
+    if let Some(name) = section.filter(|_| disassembly.sections.is_empty() || image.is_some()) {
+        return Err(format!("section '{}' does not exist in {}", name, file));
+    }
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    write!(
+        out,
+        "\n{}:     file format {}\n\n{}",
+        file, format, disassembly
+    )
+    .and_then(|_| out.flush())
+    .or_else(closed)
+    .map_err(|err| format!("Error writing the disassembly: {}", err))
+}
+
+/// The disasm command isn't built without the disasm feature
+#[cfg(not(feature = "disasm"))]
+fn disasm(_args: &[String]) -> Result<(), String> {
+    Err("readbin was built without the disasm feature".to_string())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = match args.first().map(String::as_str) {
+        Some("dump-section") => Some(dump_section(&args[1..])),
+        Some("hexdump") => Some(hexdump(&args[1..])),
+        Some("deps") => Some(deps(&args[1..])),
+        Some("exports") => Some(exports(&args[1..])),
+        Some("imports") => Some(imports(&args[1..])),
+        Some("rpath") => Some(rpath(&args[1..])),
+        Some("core") => Some(core(&args[1..])),
+        Some("auxv") => Some(auxv(&args[1..])),
+        Some("pid") => Some(pid(&args[1..])),
+        Some("patch") => Some(patch(&args[1..])),
+        Some("strip") => Some(strip(&args[1..])),
+        Some("edit") => Some(edit(&args[1..])),
+        Some("addr2line") => Some(addr2line(&args[1..])),
+        Some("resolve") => Some(resolve(&args[1..])),
+        Some("diff") => Some(diff(&args[1..])),
+        Some("tls") => Some(tls(&args[1..])),
+        Some("plt") => Some(plt(&args[1..])),
+        Some("xref") => Some(xref(&args[1..])),
+        Some("init-fini") => Some(init_fini(&args[1..])),
+        Some("strings") => Some(strings(&args[1..])),
+        Some("find") => Some(find(&args[1..])),
+        Some("checksec") => Some(checksec(&args[1..])),
+        Some("lint") => Some(lint(&args[1..])),
+        Some("triage") => Some(triage(&args[1..])),
+        Some("hash") => Some(hash(&args[1..])),
+        Some("gaps") => Some(gaps(&args[1..])),
+        Some("disasm") => Some(disasm(&args[1..])),
+        Some("size") => Some(size(&args[1..])),
+        Some("map") => Some(map(&args[1..])),
+        Some("visualize") => Some(visualize(&args[1..])),
+        Some("provenance") => Some(provenance(&args[1..])),
+        Some("summary") => Some(summary(&args[1..])),
+        Some("export") => Some(export(&args[1..])),
+        Some("build") => Some(build(&args[1..])),
+        _ => None,
+    };
+    if let Some(result) = command {
+        if let Err(err) = result {
+            eprintln!("readbin: {}", err);
+            process::exit(1);
+        }
+        return;
+    }
+    let (options, files) = match parse_args(&args) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            eprintln!("readbin: {}", err);
+            eprintln!("{}", USAGE);
+            process::exit(1);
+        }
+    };
+    if options.help {
+        outln!("{}", USAGE);
+        return;
+    }
+    if files.is_empty() {
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    }
+    let mut failed = false;
+    for file in &files {
+        if files.len() > 1 && !options.json {
+            outln!();
+            outln!("File: {}", file);
+        }
+        let result = match read(file) {
+            Ok(data) => display(&options, file, &data),
+            Err(err) => Err(format!("Error reading binary: {}", err)),
+        };
+        if let Err(err) = result {
+            if options.json {
+                let error = Json::object([("file", Json::from(*file)), ("error", Json::from(err))]);
+                outln!("{}", error);
+            } else {
+                outln!("{}", err);
+            }
+            failed = true;
+        }
+    }
+    if failed {
+        process::exit(1);
     }
 }
@@ -0,0 +1,177 @@
+use crate::alloc_prelude::*;
+use crate::format::Format;
+use core::fmt;
+
+/// Errors returned while parsing a binary
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The data is smaller than the header to parse
+    TruncatedHeader { expected: usize, found: usize },
+    /// The identification bytes don't match the expected magic
+    BadMagic([u8; 4]),
+    /// `EI_CLASS` is neither ELF32 nor ELF64
+    UnsupportedClass(u8),
+    /// A table offset doesn't respect the alignment of its entries
+    MisalignedOffset { offset: u64, align: usize },
+    /// A size field of the header doesn't match the structure it describes
+    InvalidHeaderField {
+        field: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    /// A table entry is smaller than the structure it should hold
+    InvalidEntrySize { expected: usize, found: usize },
+    /// A table or a section lies outside of the file
+    OutOfBounds { offset: u64, size: u64 },
+    /// A string offset lies outside of its string table
+    InvalidStringOffset(u64),
+    /// A string of a string table has no ending nul byte
+    UnterminatedString(u64),
+    /// A string of a string table isn't valid UTF-8
+    InvalidUtf8(u64),
+    /// A section index doesn't point to an existing section header
+    InvalidSectionIndex(u32),
+    /// A section can't be removed because another section links to it
+    SectionInUse { section: u32, by: u32 },
+    /// The format of the binary isn't recognized or can't be parsed yet
+    UnsupportedFormat(Format),
+    /// A DWARF section ends in the middle of an entry
+    TruncatedDwarf { section: &'static str, offset: u64 },
+    /// A WebAssembly section ends in the middle of an entry or holds an invalid value
+    InvalidWasm(u64),
+    /// The memory image of the loadable segments is too large to be allocated
+    ImageTooLarge(u64),
+    /// The `e_machine` of the file isn't handled by this operation
+    UnsupportedMachine(u16),
+    /// A YAML document can't be parsed, at this line
+    InvalidYaml(usize),
+    /// A record of an Intel HEX file can't be parsed, at this line
+    InvalidIntelHex(usize),
+    /// A field of a file description is missing or holds an invalid value
+    InvalidDescription(String),
+    /// A compressed section can't be decoded, at this offset of its data
+    InvalidCompressedData(u64),
+    /// A section is compressed with an algorithm that isn't known or
+    /// whose feature isn't enabled
+    UnsupportedCompression(u32),
+    /// A hex search pattern can't be parsed, at this character
+    InvalidPattern(usize),
+    /// An output template can't be parsed, at this character
+    InvalidTemplate(usize),
+    /// A template or filter names a field the listing doesn't have
+    UnknownField(String),
+    /// A filter expression can't be parsed, at this character
+    InvalidFilter(usize),
+    /// A line of `/proc/<pid>/maps` can't be parsed, at this line
+    InvalidMaps(usize),
+    /// An entry of `.ARM.exidx` or the `.ARM.extab` entry it points to is
+    /// truncated or invalid, at this offset of `.ARM.exidx`
+    InvalidUnwindEntry(u64),
+    /// An edit of [`ElfEditor`](crate::headers::elf::editor::ElfEditor)
+    /// can't be applied to the file
+    InvalidEdit(String),
+    /// Reading the source of a streamed file failed
+    #[cfg(feature = "std")]
+    Io(std::io::ErrorKind),
+}
+
+/// Result type used by the parsers of this crate
+///
+/// The error type defaults to [`Error`], so a glob import of the prelude
+/// doesn't hide `Result<T, E>` of the std prelude.
+pub type Result<T, E = Error> = core::result::Result<T, E>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::TruncatedHeader { expected, found } => write!(
+                f,
+                "truncated header: expected {} bytes, found {}",
+                expected, found
+            ),
+            Error::BadMagic(magic) => write!(f, "bad magic: {:02x?}", magic),
+            Error::UnsupportedClass(class) => write!(f, "unsupported class: {}", class),
+            Error::MisalignedOffset { offset, align } => {
+                write!(f, "offset {:#x} is not aligned on {} bytes", offset, align)
+            }
+            Error::InvalidHeaderField {
+                field,
+                expected,
+                found,
+            } => write!(
+                f,
+                "invalid {}: expected {}, found {}",
+                field, expected, found
+            ),
+            Error::InvalidEntrySize { expected, found } => write!(
+                f,
+                "invalid entry size: expected at least {} bytes, found {}",
+                expected, found
+            ),
+            Error::OutOfBounds { offset, size } => write!(
+                f,
+                "{:#x} bytes at offset {:#x} lie outside of the file",
+                size, offset
+            ),
+            Error::InvalidStringOffset(offset) => {
+                write!(f, "string offset {:#x} lies outside of its table", offset)
+            }
+            Error::UnterminatedString(offset) => {
+                write!(f, "string at offset {:#x} is not terminated", offset)
+            }
+            Error::InvalidUtf8(offset) => {
+                write!(f, "string at offset {:#x} is not valid UTF-8", offset)
+            }
+            Error::InvalidSectionIndex(index) => write!(f, "invalid section index: {}", index),
+            Error::SectionInUse { section, by } => {
+                write!(f, "section {} is still used by section {}", section, by)
+            }
+            Error::UnsupportedFormat(format) => write!(f, "unsupported format: {}", format),
+            Error::TruncatedDwarf { section, offset } => {
+                write!(f, "{} is truncated at offset {:#x}", section, offset)
+            }
+            Error::InvalidWasm(offset) => {
+                write!(f, "malformed WebAssembly module at offset {:#x}", offset)
+            }
+            Error::ImageTooLarge(size) => {
+                write!(f, "memory image of {:#x} bytes is too large", size)
+            }
+            Error::UnsupportedMachine(machine) => write!(f, "unsupported machine: {:#x}", machine),
+            Error::InvalidYaml(line) => write!(f, "invalid YAML at line {}", line),
+            Error::InvalidIntelHex(line) => write!(f, "invalid Intel HEX record at line {}", line),
+            Error::InvalidDescription(reason) => write!(f, "invalid description: {}", reason),
+            Error::InvalidCompressedData(offset) => {
+                write!(f, "invalid compressed data at offset {:#x}", offset)
+            }
+            Error::UnsupportedCompression(kind) => write!(f, "unsupported compression: {}", kind),
+            Error::InvalidPattern(index) => {
+                write!(f, "invalid hex pattern at character {}", index)
+            }
+            Error::InvalidTemplate(index) => {
+                write!(f, "invalid template at character {}", index)
+            }
+            Error::UnknownField(name) => write!(f, "unknown field '{}'", name),
+            Error::InvalidFilter(index) => {
+                write!(f, "invalid filter at character {}", index)
+            }
+            Error::InvalidMaps(line) => write!(f, "invalid memory map at line {}", line),
+            Error::InvalidUnwindEntry(offset) => {
+                write!(f, "invalid ARM unwind entry at offset {:#x}", offset)
+            }
+            Error::InvalidEdit(reason) => write!(f, "invalid edit: {}", reason),
+            #[cfg(feature = "std")]
+            Error::Io(kind) => write!(f, "read error: {}", kind),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err.kind())
+    }
+}
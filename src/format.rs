@@ -0,0 +1,124 @@
+use crate::headers::archive::{self, Archive};
+use crate::headers::elf::identification::Indent;
+use crate::headers::elf::{self, ElfHeader};
+use crate::headers::macho::{self, fat, MachOFile};
+use crate::headers::pe::dos::DosHeader;
+use crate::headers::pe::{self, PeHeaders};
+use crate::headers::wasm::{self, Wasm};
+use crate::{Error, Result};
+use core::fmt;
+
+/// Binary formats recognized by their magic bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Format {
+    /// Executable and Linkable Format, `\x7fELF`
+    Elf,
+    /// Portable Executable, starting with the `MZ` DOS stub
+    Pe,
+    /// Thin Mach-O file, `0xfeedface` or `0xfeedfacf` in either byte order
+    MachO,
+    /// Universal Mach-O binary, `0xcafebabe`
+    MachOFat,
+    /// Unix static archive, `!<arch>\n`
+    Archive,
+    /// WebAssembly module, `\0asm`
+    Wasm,
+    /// Anything else
+    Unknown,
+}
+
+impl Format {
+    /// Magic of Unix static archives
+    pub const ARCHIVE_MAGIC: [u8; 8] = *b"!<arch>\n";
+    /// Magic of GNU thin archives
+    pub const THIN_ARCHIVE_MAGIC: [u8; 8] = *b"!<thin>\n";
+    /// Magic of WebAssembly modules
+    pub const WASM_MAGIC: [u8; 4] = *b"\0asm";
+
+    /// Short name of the format
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Elf => "ELF",
+            Format::Pe => "PE",
+            Format::MachO => "Mach-O",
+            Format::MachOFat => "Mach-O universal",
+            Format::Archive => "archive",
+            Format::Wasm => "WebAssembly",
+            Format::Unknown => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Guess the format of a binary from its first bytes
+///
+/// Only the magic numbers are checked, the file may still fail to parse.
+pub fn identify(data: &[u8]) -> Format {
+    if data.starts_with(&Indent::MAGIC) {
+        Format::Elf
+    } else if data.starts_with(&DosHeader::MAGIC) {
+        Format::Pe
+    } else if macho::header::from_bytes(data).is_ok() {
+        Format::MachO
+    } else if fat::is_fat(data) {
+        Format::MachOFat
+    } else if data.starts_with(&Format::ARCHIVE_MAGIC)
+        || data.starts_with(&Format::THIN_ARCHIVE_MAGIC)
+    {
+        Format::Archive
+    } else if data.starts_with(&Format::WASM_MAGIC) {
+        Format::Wasm
+    } else {
+        Format::Unknown
+    }
+}
+
+/// A parsed binary tagged with its format
+#[derive(Debug, Clone)]
+pub enum Binary<'a> {
+    /// ELF file header of either class
+    Elf(ElfHeader<'a>),
+    /// PE headers
+    Pe(PeHeaders<'a>),
+    /// thin or universal Mach-O file
+    MachO(MachOFile<'a>),
+    /// static archive and its members
+    Archive(Archive<'a>),
+    /// WebAssembly module
+    Wasm(Wasm<'a>),
+}
+
+impl Binary<'_> {
+    /// Format of the binary
+    pub fn format(&self) -> Format {
+        match self {
+            Binary::Elf(_) => Format::Elf,
+            Binary::Pe(_) => Format::Pe,
+            Binary::MachO(MachOFile::Thin(_)) => Format::MachO,
+            Binary::MachO(MachOFile::Fat(_)) => Format::MachOFat,
+            Binary::Archive(_) => Format::Archive,
+            Binary::Wasm(_) => Format::Wasm,
+        }
+    }
+}
+
+/// Identify the format of a binary and parse its headers
+///
+/// Fails if the format isn't recognized, if there is no parser for it
+/// yet or if the headers are invalid.
+pub fn parse(data: &[u8]) -> Result<Binary<'_>> {
+    match identify(data) {
+        Format::Elf => elf::from_bytes(data).map(Binary::Elf),
+        Format::Pe => pe::from_bytes(data).map(Binary::Pe),
+        Format::MachO | Format::MachOFat => macho::from_bytes(data).map(Binary::MachO),
+        Format::Archive => archive::from_bytes(data).map(Binary::Archive),
+        Format::Wasm => wasm::from_bytes(data).map(Binary::Wasm),
+        format => Err(Error::UnsupportedFormat(format)),
+    }
+}
@@ -0,0 +1,419 @@
+//! Unix static archives, `.a` files
+//!
+//! An archive starts with `!<arch>\n` and is followed by members, each
+//! one made of a 60 bytes text header and its data padded to 2 bytes.
+//! Special members hold the symbol index (`/` or `/SYM64/`) and the
+//! names longer than 15 bytes (`//`). GNU thin archives (`!<thin>\n`)
+//! only store the paths of their members, BSD archives store long names
+//! right after the header (`#1/<length>`).
+//! Sources:
+//! * <https://www.freebsd.org/cgi/man.cgi?query=ar&sektion=5>
+//! * <https://sourceware.org/binutils/docs/binutils/ar.html>
+use crate::alloc_prelude::*;
+use crate::format::Format;
+use crate::utils::json::{Json, ToJson};
+use crate::utils::{read_u32, read_u64};
+use crate::{Error, Result};
+use alloc::borrow::Cow;
+use core::fmt;
+
+/// Raw header of an archive member, all fields are ASCII padded with spaces
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct MemberHeader {
+    /// member name, `/` terminated for GNU archives
+    pub ar_name: [u8; 16],
+    /// modification time, decimal seconds since the epoch
+    pub ar_date: [u8; 12],
+    /// owner id, decimal
+    pub ar_uid: [u8; 6],
+    /// group id, decimal
+    pub ar_gid: [u8; 6],
+    /// file mode, octal
+    pub ar_mode: [u8; 8],
+    /// size of the member data, decimal
+    pub ar_size: [u8; 10],
+    /// always "`\n"
+    pub ar_fmag: [u8; 2],
+}
+
+impl MemberHeader {
+    pub const SIZE: usize = 60;
+    /// Value of `ar_fmag`
+    pub const FMAG: [u8; 2] = *b"`\n";
+
+    /// Decode the header stored at the start of `data`
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        let bytes = data.get(..Self::SIZE)?;
+        let mut header = MemberHeader::default();
+        let mut offset = 0;
+        for field in [
+            &mut header.ar_name[..],
+            &mut header.ar_date[..],
+            &mut header.ar_uid[..],
+            &mut header.ar_gid[..],
+            &mut header.ar_mode[..],
+            &mut header.ar_size[..],
+            &mut header.ar_fmag[..],
+        ] {
+            field.copy_from_slice(&bytes[offset..offset + field.len()]);
+            offset += field.len();
+        }
+        Some(header)
+    }
+
+    /// Name field without its padding
+    pub fn raw_name(&self) -> &[u8] {
+        trim(&self.ar_name)
+    }
+
+    /// Modification time in seconds since the epoch
+    pub fn date(&self) -> u64 {
+        number(&self.ar_date, 10)
+    }
+
+    /// Owner id
+    pub fn uid(&self) -> u64 {
+        number(&self.ar_uid, 10)
+    }
+
+    /// Group id
+    pub fn gid(&self) -> u64 {
+        number(&self.ar_gid, 10)
+    }
+
+    /// File mode
+    pub fn mode(&self) -> u64 {
+        number(&self.ar_mode, 8)
+    }
+
+    /// Size of the member data, None if the field isn't a number
+    pub fn size(&self) -> Option<u64> {
+        let field = core::str::from_utf8(trim(&self.ar_size)).ok()?;
+        field.parse().ok()
+    }
+}
+
+fn trim(field: &[u8]) -> &[u8] {
+    let end = field
+        .iter()
+        .rposition(|b| *b != b' ')
+        .map_or(0, |end| end + 1);
+    &field[..end]
+}
+
+/// Numeric field, 0 when empty or invalid as `ar` does
+fn number(field: &[u8], radix: u32) -> u64 {
+    core::str::from_utf8(trim(field))
+        .ok()
+        .and_then(|field| u64::from_str_radix(field, radix).ok())
+        .unwrap_or(0)
+}
+
+/// A regular member of an archive
+#[derive(Debug, Clone)]
+pub struct Member<'a> {
+    /// offset of the member header in the archive
+    pub offset: u64,
+    /// the raw member header
+    pub header: MemberHeader,
+    /// resolved member name
+    pub name: Cow<'a, str>,
+    /// size of the member, without the BSD long name
+    pub size: u64,
+    /// member bytes, empty for the members of a thin archive
+    pub data: &'a [u8],
+}
+
+/// A symbol of the archive index and the offset of the member defining it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexEntry<'a> {
+    /// symbol name
+    pub name: &'a str,
+    /// offset of the header of the member defining the symbol
+    pub offset: u64,
+}
+
+/// A static archive, borrowed from its bytes
+#[derive(Debug, Clone)]
+pub struct Archive<'a> {
+    /// whether this is a GNU thin archive
+    pub thin: bool,
+    /// symbol index, empty if the archive has none
+    pub symbols: Vec<IndexEntry<'a>>,
+    /// regular members in archive order
+    pub members: Vec<Member<'a>>,
+}
+
+/// Parse the symbol index stored by GNU `ar` in the `/` or `/SYM64/` member
+///
+/// The index is a big endian count, the member offsets, then the nul
+/// terminated symbol names.
+fn symbol_index(data: &[u8], word: usize) -> Option<Vec<IndexEntry<'_>>> {
+    let read = |offset: usize| match word {
+        8 => read_u64(data, offset, true),
+        _ => read_u32(data, offset, true).map(u64::from),
+    };
+    let count = usize::try_from(read(0)?).ok()?;
+    let names_start = count.checked_add(1)?.checked_mul(word)?;
+    let mut names = data.get(names_start..)?.split(|b| *b == 0);
+    (0..count)
+        .map(|index| {
+            Some(IndexEntry {
+                offset: read((index + 1) * word)?,
+                name: core::str::from_utf8(names.next()?).ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Parse the symbol index stored by BSD `ar` in the `__.SYMDEF` member
+///
+/// The index is a little endian size, `ranlib` entries of a string offset
+/// and a member offset, then the string table.
+fn bsd_symbol_index(data: &[u8]) -> Option<Vec<IndexEntry<'_>>> {
+    let size = read_u32(data, 0, false)? as usize;
+    let strings_start = size.checked_add(8)?;
+    let strings = data.get(strings_start..)?;
+    (0..size / 8)
+        .map(|index| {
+            let name = read_u32(data, 4 + index * 8, false)? as usize;
+            let offset = read_u32(data, 8 + index * 8, false)?;
+            let name = strings.get(name..)?.split(|b| *b == 0).next()?;
+            Some(IndexEntry {
+                name: core::str::from_utf8(name).ok()?,
+                offset: offset as u64,
+            })
+        })
+        .collect()
+}
+
+/// Name stored at `offset` of the GNU extended name table, `/` terminated
+fn extended_name(names: &[u8], offset: usize) -> Option<&str> {
+    let name = names.get(offset..)?;
+    let end = name.iter().position(|b| *b == b'\n').unwrap_or(name.len());
+    let name = &name[..end];
+    core::str::from_utf8(name.strip_suffix(b"/").unwrap_or(name)).ok()
+}
+
+impl<'a> Archive<'a> {
+    /// Parse the members of an archive
+    ///
+    /// Fails if the data doesn't start with the archive magic or if a
+    /// member header is corrupted or lies outside of the file.
+    pub fn parse(data: &'a [u8]) -> Result<Self> {
+        let thin = if data.starts_with(&Format::ARCHIVE_MAGIC) {
+            false
+        } else if data.starts_with(&Format::THIN_ARCHIVE_MAGIC) {
+            true
+        } else {
+            let mut magic = [0; 4];
+            let len = data.len().min(magic.len());
+            magic[..len].copy_from_slice(&data[..len]);
+            return Err(Error::BadMagic(magic));
+        };
+        let mut archive = Archive {
+            thin,
+            symbols: Vec::new(),
+            members: Vec::new(),
+        };
+        let mut names: &[u8] = &[];
+        let mut offset = Format::ARCHIVE_MAGIC.len();
+        while offset < data.len() {
+            let truncated = Error::TruncatedHeader {
+                expected: MemberHeader::SIZE,
+                found: data.len() - offset,
+            };
+            let header = MemberHeader::from_bytes(&data[offset..]).ok_or(truncated)?;
+            let size = header
+                .size()
+                .filter(|_| header.ar_fmag == MemberHeader::FMAG);
+            let size = size.ok_or(Error::InvalidHeaderField {
+                field: "ar_size",
+                expected: 0,
+                found: offset,
+            })?;
+            let start = offset + MemberHeader::SIZE;
+            // borrowed from the data rather than the header so names can outlive it
+            let raw_name = trim(&data[offset..offset + header.ar_name.len()]);
+            // only the special members are stored in thin archives
+            let special = raw_name == b"/" || raw_name == b"//" || raw_name == b"/SYM64/";
+            let stored = if thin && !special { 0 } else { size };
+            let end = usize::try_from(stored)
+                .ok()
+                .and_then(|stored| start.checked_add(stored))
+                .filter(|end| *end <= data.len())
+                .ok_or(Error::OutOfBounds {
+                    offset: start as u64,
+                    size: stored,
+                })?;
+            let content = &data[start..end];
+            match raw_name {
+                b"/" => archive.symbols = symbol_index(content, 4).unwrap_or_default(),
+                b"/SYM64/" => archive.symbols = symbol_index(content, 8).unwrap_or_default(),
+                b"//" => names = content,
+                _ => {
+                    let (name, content, size) = if let Some(index) = raw_name.strip_prefix(b"/") {
+                        let index = core::str::from_utf8(index)
+                            .ok()
+                            .and_then(|i| i.parse().ok());
+                        let name = index.and_then(|index| extended_name(names, index));
+                        (Cow::Borrowed(name.unwrap_or("<corrupt>")), content, size)
+                    } else if let Some(length) = raw_name.strip_prefix(b"#1/") {
+                        // BSD long names are stored at the start of the data
+                        let length = core::str::from_utf8(length)
+                            .ok()
+                            .and_then(|length| length.parse::<usize>().ok())
+                            .unwrap_or(0)
+                            .min(content.len());
+                        let name = &content[..length];
+                        let end = name.iter().position(|b| *b == 0).unwrap_or(length);
+                        let size = size.saturating_sub(length as u64);
+                        (
+                            String::from_utf8_lossy(&name[..end]),
+                            &content[length..],
+                            size,
+                        )
+                    } else {
+                        let name = raw_name.strip_suffix(b"/").unwrap_or(raw_name);
+                        (String::from_utf8_lossy(name), content, size)
+                    };
+                    if name == "__.SYMDEF" || name == "__.SYMDEF SORTED" {
+                        archive.symbols = bsd_symbol_index(content).unwrap_or_default();
+                    } else {
+                        archive.members.push(Member {
+                            offset: offset as u64,
+                            header,
+                            name,
+                            size,
+                            data: content,
+                        });
+                    }
+                }
+            }
+            offset = end + (end % 2);
+        }
+        Ok(archive)
+    }
+
+    /// The member whose header starts at `offset`, as found in the symbol index
+    pub fn member_at(&self, offset: u64) -> Option<&Member<'a>> {
+        self.members.iter().find(|member| member.offset == offset)
+    }
+
+    /// The first member with the given name
+    pub fn member(&self, name: &str) -> Option<&Member<'a>> {
+        self.members.iter().find(|member| member.name == name)
+    }
+}
+
+/// Parse a static archive
+pub fn from_bytes(data: &[u8]) -> Result<Archive<'_>> {
+    Archive::parse(data)
+}
+
+/// Short description of the object stored in a member, ex: `ELF64 REL X86-64`
+fn describe(data: &[u8]) -> String {
+    use super::elf::machine::Machine;
+    use super::elf::types::Type;
+    use super::elf::{self, ElfHeader};
+
+    match elf::from_bytes(data) {
+        Ok(header) => {
+            let (class, e_type, e_machine) = match &header {
+                ElfHeader::Elf32(header) => ("ELF32", header.e_type, header.e_machine),
+                ElfHeader::Elf64(header) => ("ELF64", header.e_type, header.e_machine),
+            };
+            let kind = Type::from(e_type).as_str();
+            format!(
+                "{} {} {}",
+                class,
+                kind,
+                Machine::from_u16(e_machine).as_str()
+            )
+        }
+        Err(_) => crate::format::identify(data).to_string(),
+    }
+}
+
+impl fmt::Display for Archive<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.thin { "Thin archive" } else { "Archive" };
+        writeln!(
+            f,
+            "{} with {} members and {} symbols:",
+            kind,
+            self.members.len(),
+            self.symbols.len()
+        )?;
+        let width = self
+            .members
+            .iter()
+            .map(|member| member.name.len())
+            .max()
+            .unwrap_or(0)
+            .max(6);
+        writeln!(
+            f,
+            "  {:<width$} {:>10}  Type",
+            "Member",
+            "Size",
+            width = width
+        )?;
+        for member in &self.members {
+            let kind = if self.thin {
+                "external file".to_string()
+            } else {
+                describe(member.data)
+            };
+            writeln!(
+                f,
+                "  {:<width$} {:>10}  {}",
+                member.name,
+                member.size,
+                kind,
+                width = width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for Archive<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("thin", Json::from(self.thin)),
+            (
+                "members",
+                Json::array(self.members.iter().map(|member| {
+                    Json::object([
+                        ("name", Json::from(member.name.as_ref())),
+                        ("offset", Json::from(member.offset)),
+                        ("size", Json::from(member.size)),
+                        ("date", Json::from(member.header.date())),
+                        ("uid", Json::from(member.header.uid())),
+                        ("gid", Json::from(member.header.gid())),
+                        ("mode", Json::from(member.header.mode())),
+                        (
+                            "type",
+                            Json::from((!self.thin).then(|| describe(member.data))),
+                        ),
+                    ])
+                })),
+            ),
+            (
+                "symbols",
+                Json::array(self.symbols.iter().map(|symbol| {
+                    let member = self.member_at(symbol.offset);
+                    Json::object([
+                        ("name", Json::from(symbol.name)),
+                        (
+                            "member",
+                            Json::from(member.map(|member| member.name.as_ref())),
+                        ),
+                    ])
+                })),
+            ),
+        ])
+    }
+}
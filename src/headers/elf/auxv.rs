@@ -0,0 +1,275 @@
+//! The auxiliary vector, what the kernel passes to the loader
+//!
+//! The kernel writes `(a_type, a_val)` pairs after the environment on
+//! the initial stack of a process: the program headers and entry point of
+//! the executable, the base address of the interpreter, the page size,
+//! the CPU features, the ids of the user. A core file saves them in its
+//! NT_AUXV note, a running process exposes them in `/proc/<pid>/auxv`.
+//! The entries holding an address of the stack (AT_EXECFN, AT_PLATFORM,
+//! AT_RANDOM) are followed in the memory of a core file.
+//! Sources:
+//! * <https://github.com/torvalds/linux/blob/master/include/uapi/linux/auxvec.h>
+//! * <https://github.com/torvalds/linux/blob/master/arch/arm64/include/uapi/asm/hwcap.h>
+use crate::alloc_prelude::*;
+use crate::utils::json::{Json, ToJson};
+use crate::Result;
+use core::fmt;
+
+use super::coredump::{self, AT};
+use super::data::Data;
+use super::machine::Machine;
+use super::notes::{self, NT_CORE};
+use super::program;
+use super::x64::x64;
+
+/// Number of bytes AT_RANDOM points to
+const RANDOM_SIZE: usize = 16;
+
+/// Bits of AT_HWCAP on x86_64, the EDX register of CPUID leaf 1
+const X86_64_HWCAP: [&str; 32] = [
+    "fpu", "vme", "de", "pse", "tsc", "msr", "pae", "mce", "cx8", "apic", "", "sep", "mtrr", "pge",
+    "mca", "cmov", "pat", "pse36", "pn", "clflush", "", "dts", "acpi", "mmx", "fxsr", "sse",
+    "sse2", "ss", "ht", "tm", "ia64", "pbe",
+];
+
+/// Bits of AT_HWCAP2 on x86_64
+const X86_64_HWCAP2: [&str; 2] = ["ring3mwait", "fsgsbase"];
+
+/// Bits of AT_HWCAP on aarch64
+const AARCH64_HWCAP: [&str; 32] = [
+    "fp", "asimd", "evtstrm", "aes", "pmull", "sha1", "sha2", "crc32", "atomics", "fphp",
+    "asimdhp", "cpuid", "asimdrdm", "jscvt", "fcma", "lrcpc", "dcpop", "sha3", "sm3", "sm4",
+    "asimddp", "sha512", "sve", "asimdfhm", "dit", "uscat", "ilrcpc", "flagm", "ssbs", "sb",
+    "paca", "pacg",
+];
+
+/// What an entry of the auxiliary vector holds
+fn description(a_type: u64) -> Option<&'static str> {
+    let description = match a_type {
+        AT::IGNORE => "ignored",
+        AT::EXECFD => "file descriptor of the program",
+        AT::PHDR => "program headers of the program",
+        AT::PHENT => "size of a program header",
+        AT::PHNUM => "number of program headers",
+        AT::PAGESZ => "page size",
+        AT::BASE => "base address of the interpreter",
+        AT::FLAGS => "flags",
+        AT::ENTRY => "entry point of the program",
+        AT::NOTELF => "the program isn't ELF",
+        AT::UID => "real user id",
+        AT::EUID => "effective user id",
+        AT::GID => "real group id",
+        AT::EGID => "effective group id",
+        AT::PLATFORM => "platform",
+        AT::HWCAP => "CPU features",
+        AT::CLKTCK => "clock ticks per second",
+        AT::SECURE => "secure mode, setuid or setgid program",
+        AT::BASE_PLATFORM => "real platform",
+        AT::RANDOM => "random bytes",
+        AT::HWCAP2 => "more CPU features",
+        AT::RSEQ_FEATURE_SIZE => "size of the rseq area",
+        AT::RSEQ_ALIGN => "alignment of the rseq area",
+        AT::EXECFN => "file name of the program",
+        AT::SYSINFO => "vsyscall entry point",
+        AT::SYSINFO_EHDR => "address of the vDSO",
+        AT::MINSIGSTKSZ => "minimal signal stack size",
+        _ => return None,
+    };
+    Some(description)
+}
+
+/// An entry of the auxiliary vector
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuxEntry<'a> {
+    /// type of the entry, one of [`AT`]
+    pub a_type: u64,
+    /// value of the entry
+    pub a_val: u64,
+    /// the bytes the entry points to, the string without its NUL for
+    /// AT_EXECFN, AT_PLATFORM and AT_BASE_PLATFORM and the 16 bytes of
+    /// AT_RANDOM, when the memory is known
+    pub target: Option<&'a [u8]>,
+}
+
+impl AuxEntry<'_> {
+    /// Name of the type, ex: `AT_PAGESZ`, or `AT_<number>`
+    pub fn name(&self) -> String {
+        match AT::name(self.a_type) {
+            Some(name) => name.to_string(),
+            None => format!("AT_{}", self.a_type),
+        }
+    }
+
+    /// The value as a number rather than an address or a bitmask
+    fn is_number(&self) -> bool {
+        matches!(
+            self.a_type,
+            AT::EXECFD
+                | AT::PHENT
+                | AT::PHNUM
+                | AT::PAGESZ
+                | AT::UID
+                | AT::EUID
+                | AT::GID
+                | AT::EGID
+                | AT::CLKTCK
+                | AT::SECURE
+                | AT::RSEQ_FEATURE_SIZE
+                | AT::RSEQ_ALIGN
+                | AT::MINSIGSTKSZ
+        )
+    }
+
+    /// The string an entry points to
+    pub fn string(&self) -> Option<&str> {
+        match self.a_type {
+            AT::EXECFN | AT::PLATFORM | AT::BASE_PLATFORM => {
+                core::str::from_utf8(self.target?).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// The names of the bits of AT_HWCAP and AT_HWCAP2
+    pub fn features(&self, machine: Machine) -> Vec<&'static str> {
+        let names: &[&str] = match (machine, self.a_type) {
+            (Machine::X86_64, AT::HWCAP) => &X86_64_HWCAP,
+            (Machine::X86_64, AT::HWCAP2) => &X86_64_HWCAP2,
+            (Machine::Aarch64, AT::HWCAP) => &AARCH64_HWCAP,
+            _ => &[],
+        };
+        names
+            .iter()
+            .enumerate()
+            .filter(|(bit, name)| !name.is_empty() && self.a_val & (1 << bit) != 0)
+            .map(|(_, name)| *name)
+            .collect()
+    }
+}
+
+/// The auxiliary vector of a process
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Auxv<'a> {
+    /// machine of the process, to name the CPU features
+    pub machine: Machine,
+    /// the entries up to AT_NULL
+    pub entries: Vec<AuxEntry<'a>>,
+}
+
+impl<'a> Auxv<'a> {
+    /// Decode a 64 bits auxiliary vector, the content of NT_AUXV or of
+    /// `/proc/<pid>/auxv`, without the bytes its entries point to
+    pub fn parse(desc: &[u8], big_endian: bool, machine: Machine) -> Self {
+        let entries = coredump::auxv(desc, big_endian)
+            .into_iter()
+            .map(|(a_type, a_val)| AuxEntry {
+                a_type,
+                a_val,
+                target: None,
+            })
+            .collect();
+        Auxv { machine, entries }
+    }
+
+    /// The value of the first entry of a type
+    pub fn get(&self, a_type: u64) -> Option<u64> {
+        self.entries
+            .iter()
+            .find(|entry| entry.a_type == a_type)
+            .map(|entry| entry.a_val)
+    }
+
+    /// Read the strings and the random bytes the entries point to
+    ///
+    /// `read` returns the memory of the process from an address to the
+    /// end of its mapping.
+    pub fn resolve(&mut self, read: impl Fn(u64) -> Option<&'a [u8]>) {
+        for entry in &mut self.entries {
+            entry.target = match entry.a_type {
+                AT::EXECFN | AT::PLATFORM | AT::BASE_PLATFORM => read(entry.a_val).map(|bytes| {
+                    let end = bytes.iter().position(|byte| *byte == 0);
+                    &bytes[..end.unwrap_or(bytes.len())]
+                }),
+                AT::RANDOM => read(entry.a_val).and_then(|bytes| bytes.get(..RANDOM_SIZE)),
+                _ => continue,
+            };
+        }
+    }
+}
+
+/// Decode the NT_AUXV note of a core file
+///
+/// The strings and random bytes are read in the PT_LOAD segments holding
+/// the stack. Returns None when there is no NT_AUXV note.
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64) -> Result<Option<Auxv<'a>>> {
+    let big_endian = Data::is_big_endian(&header.e_ident);
+    let note = notes::areas(data, header)
+        .into_iter()
+        .flat_map(|(_, _, notes)| notes)
+        .find(|note| note.name == b"CORE" && note.n_type == NT_CORE::AUXV);
+    let Some(note) = note else {
+        return Ok(None);
+    };
+    let mut auxv = Auxv::parse(note.desc, big_endian, Machine::from_u16(header.e_machine));
+    let phdrs: Vec<_> = program::from_bytes(data, header)?.collect();
+    auxv.resolve(|vaddr| {
+        let phdr = phdrs.iter().find(|phdr| {
+            phdr.p_type == program::PT::LOAD
+                && vaddr >= phdr.p_vaddr
+                && vaddr - phdr.p_vaddr < phdr.p_filesz
+        })?;
+        let start = phdr.p_offset.checked_add(vaddr - phdr.p_vaddr)?;
+        let end = phdr.p_offset.saturating_add(phdr.p_filesz);
+        data.get(usize::try_from(start).ok()?..usize::try_from(end).ok()?)
+    });
+    Ok(Some(auxv))
+}
+
+impl fmt::Display for Auxv<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Auxiliary vector of {} entries:", self.entries.len())?;
+        for entry in &self.entries {
+            let value = match entry.is_number() {
+                true => entry.a_val.to_string(),
+                false => format!("0x{:x}", entry.a_val),
+            };
+            write!(f, "  {:<20} {:<18}", entry.name(), value)?;
+            if let Some(description) = description(entry.a_type) {
+                write!(f, " {}", description)?;
+            }
+            if let Some(string) = entry.string() {
+                write!(f, ": \"{}\"", string.escape_debug())?;
+            } else if let Some(bytes) = entry.target {
+                write!(f, ": ")?;
+                for byte in bytes {
+                    write!(f, "{:02x}", byte)?;
+                }
+            }
+            let features = entry.features(self.machine);
+            if !features.is_empty() {
+                write!(f, ": {}", features.join(" "))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for Auxv<'_> {
+    fn to_json(&self) -> Json {
+        Json::array(self.entries.iter().map(|entry| {
+            let target = entry.target.map(|bytes| match entry.string() {
+                Some(string) => string.to_string(),
+                None => bytes.iter().map(|byte| format!("{:02x}", byte)).collect(),
+            });
+            Json::object([
+                ("type", Json::from(entry.a_type)),
+                ("name", Json::from(AT::name(entry.a_type))),
+                ("value", Json::from(entry.a_val)),
+                ("description", Json::from(description(entry.a_type))),
+                ("target", Json::from(target)),
+                ("features", Json::from(entry.features(self.machine))),
+            ])
+        }))
+    }
+}
@@ -0,0 +1,573 @@
+//! Build an ELF64 file from scratch
+//!
+//! [`ElfBuilder`] collects sections, segments and symbols, lays them out
+//! and writes the file with [`description::build`], the writer behind
+//! `readbin build`. The symbols go to a `.symtab` section with its
+//! `.strtab`, and the section names table is generated:
+//!
+//! ```
+//! use readbin::headers::elf::builder::{ElfBuilder, Section, Segment, Symbol};
+//! use readbin::headers::elf::machine::Machine;
+//! use readbin::headers::elf::program::{PF, PT};
+//! use readbin::headers::elf::section::{SHF, SHT};
+//! use readbin::headers::elf::symbols::{STB, STT};
+//! use readbin::headers::elf::types::Type;
+//!
+//! let mut builder = ElfBuilder::new(Type::Exec, Machine::X86_64);
+//! let text = builder.add_section(
+//!     Section::new(".text", SHT::PROGBITS)
+//!         .flags(SHF::ALLOC | SHF::EXECINSTR)
+//!         .align(16)
+//!         .content([0xb8, 0x3c, 0, 0, 0, 0x0f, 0x05]),
+//! );
+//! builder.add_segment(Segment::new(PT::LOAD, PF::R | PF::X).sections(text, text));
+//! builder.add_symbol(Symbol::new("_start").bind(STB::GLOBAL).kind(STT::FUNC).section(text));
+//! let builder = builder.entry_symbol("_start");
+//! let data = builder.build().unwrap();
+//! let elf = readbin::Elf::parse(&data).unwrap();
+//! assert_eq!(elf.header().e_entry, 0x400080);
+//! ```
+use crate::alloc_prelude::*;
+use crate::utils::yaml::Yaml;
+use crate::utils::ToBytes;
+use crate::{Error, Result};
+
+use super::description::{self, align_up, encode, hex};
+use super::machine::Machine;
+use super::program::{Elf64_Phdr, PT};
+use super::section::{SHF, SHN, SHT};
+use super::symbols::{Elf64_Sym, STB, STT, STV};
+use super::types::Type;
+use super::x64::x64;
+
+/// Address of the first page of the executables, like `ld` on x86-64
+const EXEC_BASE: u64 = 0x400000;
+
+/// Smallest alignment of the loaded segments, a page
+const PAGE: u64 = 0x1000;
+
+/// A section to add, its offset is computed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    name: String,
+    sh_type: u32,
+    flags: u64,
+    address: Option<u64>,
+    align: u64,
+    content: Vec<u8>,
+    size: Option<u64>,
+    link: u32,
+    info: u32,
+    entsize: u64,
+}
+
+impl Section {
+    /// An empty section of type `sh_type`, one of the `SHT` values
+    pub fn new(name: &str, sh_type: u32) -> Self {
+        Section {
+            name: name.to_string(),
+            sh_type,
+            flags: 0,
+            address: None,
+            align: 1,
+            content: Vec::new(),
+            size: None,
+            link: 0,
+            info: 0,
+            entsize: 0,
+        }
+    }
+
+    /// The `SHF` flags
+    pub fn flags(mut self, flags: u64) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// The virtual address, computed for the allocated sections of
+    /// executables and shared objects if not given
+    pub fn address(mut self, address: u64) -> Self {
+        self.address = Some(address);
+        self
+    }
+
+    /// Alignment of the offset and of the computed address
+    pub fn align(mut self, align: u64) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// The bytes of the section
+    pub fn content(mut self, content: impl Into<Vec<u8>>) -> Self {
+        self.content = content.into();
+        self
+    }
+
+    /// The size, only needed for SHT_NOBITS sections which have no content
+    pub fn size(mut self, size: u64) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// `sh_link`, usually the index of a section returned by
+    /// [`ElfBuilder::add_section`]
+    pub fn link(mut self, link: u32) -> Self {
+        self.link = link;
+        self
+    }
+
+    /// `sh_info`
+    pub fn info(mut self, info: u32) -> Self {
+        self.info = info;
+        self
+    }
+
+    /// Size of the entries of a table
+    pub fn entsize(mut self, entsize: u64) -> Self {
+        self.entsize = entsize;
+        self
+    }
+}
+
+/// A program header to add
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    p_type: u32,
+    flags: u32,
+    sections: Option<(usize, usize)>,
+    align: u64,
+}
+
+impl Segment {
+    /// A segment of type `p_type` with the `PF` flags
+    ///
+    /// PT_LOAD segments are aligned on a page by default.
+    pub fn new(p_type: u32, flags: u32) -> Self {
+        Segment {
+            p_type,
+            flags,
+            sections: None,
+            align: if p_type == PT::LOAD { PAGE } else { 0 },
+        }
+    }
+
+    /// Map the sections from `first` to `last`, indexes returned by
+    /// [`ElfBuilder::add_section`]
+    pub fn sections(mut self, first: usize, last: usize) -> Self {
+        self.sections = Some((first, last));
+        self
+    }
+
+    /// `p_align`
+    pub fn align(mut self, align: u64) -> Self {
+        self.align = align;
+        self
+    }
+}
+
+/// A symbol to add to `.symtab`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    name: String,
+    bind: u8,
+    kind: u8,
+    visibility: u8,
+    section: Option<usize>,
+    shndx: u16,
+    value: u64,
+    size: u64,
+}
+
+impl Symbol {
+    /// An undefined global symbol without type
+    pub fn new(name: &str) -> Self {
+        Symbol {
+            name: name.to_string(),
+            bind: STB::GLOBAL,
+            kind: STT::NOTYPE,
+            visibility: STV::DEFAULT,
+            section: None,
+            shndx: SHN::UNDEF,
+            value: 0,
+            size: 0,
+        }
+    }
+
+    /// The `STB` binding
+    pub fn bind(mut self, bind: u8) -> Self {
+        self.bind = bind;
+        self
+    }
+
+    /// The `STT` type
+    pub fn kind(mut self, kind: u8) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// The `STV` visibility
+    pub fn visibility(mut self, visibility: u8) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    /// Define the symbol in a section returned by [`ElfBuilder::add_section`]
+    pub fn section(mut self, section: usize) -> Self {
+        self.section = Some(section);
+        self
+    }
+
+    /// Define the symbol with an absolute value, SHN_ABS
+    pub fn absolute(mut self) -> Self {
+        self.section = None;
+        self.shndx = SHN::ABS;
+        self
+    }
+
+    /// The value, an offset in its section: the address of the section is
+    /// added in executables and shared objects
+    pub fn value(mut self, value: u64) -> Self {
+        self.value = value;
+        self
+    }
+
+    /// Size of the object or function
+    pub fn size(mut self, size: u64) -> Self {
+        self.size = size;
+        self
+    }
+}
+
+/// Builder of an ELF64 file
+///
+/// The sections are laid out after the program headers in the order they
+/// are added, each at an offset aligned on its alignment. The allocated
+/// sections of ET_EXEC and ET_DYN files without an address get one
+/// congruent to their offset modulo the page, from 0x400000 for
+/// executables and 0 for shared objects, so the segments mapping them can
+/// be loaded. Put the SHT_NOBITS sections of a segment last.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElfBuilder {
+    e_type: Type,
+    machine: Machine,
+    big_endian: bool,
+    entry: u64,
+    entry_symbol: Option<String>,
+    flags: u32,
+    sections: Vec<Section>,
+    segments: Vec<Segment>,
+    symbols: Vec<Symbol>,
+}
+
+/// A section with its place in the file and in memory
+struct Placed {
+    section: Section,
+    offset: u64,
+    address: u64,
+}
+
+impl ElfBuilder {
+    /// An empty file of type `e_type` for `machine`, little endian
+    pub fn new(e_type: Type, machine: Machine) -> Self {
+        ElfBuilder {
+            e_type,
+            machine,
+            big_endian: false,
+            entry: 0,
+            entry_symbol: None,
+            flags: 0,
+            sections: Vec::new(),
+            segments: Vec::new(),
+            symbols: Vec::new(),
+        }
+    }
+
+    /// Write a big endian file
+    pub fn big_endian(mut self) -> Self {
+        self.big_endian = true;
+        self
+    }
+
+    /// `e_entry`
+    pub fn entry(mut self, entry: u64) -> Self {
+        self.entry = entry;
+        self.entry_symbol = None;
+        self
+    }
+
+    /// Take `e_entry` from the value of a symbol, once laid out
+    pub fn entry_symbol(mut self, name: &str) -> Self {
+        self.entry_symbol = Some(name.to_string());
+        self
+    }
+
+    /// `e_flags`, machine specific
+    pub fn flags(mut self, flags: u32) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Add a section, returns its index in the section header table
+    pub fn add_section(&mut self, section: Section) -> usize {
+        self.sections.push(section);
+        self.sections.len()
+    }
+
+    /// Add a program header, returns its index
+    pub fn add_segment(&mut self, segment: Segment) -> usize {
+        self.segments.push(segment);
+        self.segments.len() - 1
+    }
+
+    /// Add a symbol, the local symbols are written before the others
+    pub fn add_symbol(&mut self, symbol: Symbol) {
+        self.symbols.push(symbol);
+    }
+
+    /// Place the sections in the file and in memory
+    fn layout(&self, sections: Vec<Section>) -> Vec<Placed> {
+        let page = self
+            .segments
+            .iter()
+            .filter(|segment| segment.p_type == PT::LOAD)
+            .map(|segment| segment.align)
+            .fold(PAGE, u64::max);
+        let base = match self.e_type {
+            Type::Exec => Some(EXEC_BASE),
+            Type::Dyn => Some(0),
+            _ => None,
+        };
+        let mut end = x64::SIZE as u64 + (self.segments.len() * Elf64_Phdr::SIZE) as u64;
+        // end of the memory used by the sections with a computed address
+        let mut memory_end = base.unwrap_or_default();
+        let mut placed = Vec::new();
+        for (index, section) in sections.into_iter().enumerate() {
+            // a segment starts in a new page, its permissions are its own
+            let first = self.segments.iter().any(|segment| {
+                segment.p_type == PT::LOAD
+                    && segment
+                        .sections
+                        .is_some_and(|(first, _)| first == index + 1)
+            });
+            let offset = align_up(end, section.align);
+            let len = section.content.len() as u64;
+            if len != 0 {
+                end = offset + len;
+            }
+            let address = match (section.address, base) {
+                (Some(address), _) => address,
+                (None, Some(base)) if section.flags & SHF::ALLOC != 0 => {
+                    let address = match section.sh_type {
+                        _ if first => align_up(memory_end, page) + offset % page,
+                        SHT::NOBITS => align_up(memory_end.max(base + offset), section.align),
+                        // the next page with the same offset in it
+                        _ => {
+                            let address = base + offset;
+                            address + align_up(memory_end.saturating_sub(address), page)
+                        }
+                    };
+                    memory_end = address + section.size.unwrap_or(len);
+                    address
+                }
+                (None, _) => 0,
+            };
+            placed.push(Placed {
+                section,
+                offset,
+                address,
+            });
+        }
+        placed
+    }
+
+    /// The `.symtab` and `.strtab` sections of the symbols, None without
+    ///
+    /// `index` is the index of `.symtab` in the section header table.
+    fn symbol_sections(&self, placed: &[Placed], index: usize) -> Result<Option<[Section; 2]>> {
+        if self.symbols.is_empty() {
+            return Ok(None);
+        }
+        let mut strtab = vec![0];
+        let mut table = Elf64_Sym::default().to_bytes(self.big_endian);
+        let locals = self
+            .symbols
+            .iter()
+            .filter(|symbol| symbol.bind == STB::LOCAL);
+        let others = self
+            .symbols
+            .iter()
+            .filter(|symbol| symbol.bind != STB::LOCAL);
+        for symbol in locals.clone().chain(others) {
+            let (shndx, value) = match symbol.section {
+                Some(section) => {
+                    let placed = placed.get(section.wrapping_sub(1)).ok_or_else(|| {
+                        Error::InvalidDescription(format!(
+                            "symbol {}: no section {}",
+                            symbol.name, section
+                        ))
+                    })?;
+                    let shndx = u16::try_from(section)
+                        .ok()
+                        .filter(|shndx| *shndx < SHN::LORESERVE)
+                        .unwrap_or(SHN::XINDEX);
+                    (shndx, placed.address.wrapping_add(symbol.value))
+                }
+                None => (symbol.shndx, symbol.value),
+            };
+            let st_name = match symbol.name.as_str() {
+                "" => 0,
+                name => {
+                    let offset = strtab.len() as u32;
+                    strtab.extend_from_slice(name.as_bytes());
+                    strtab.push(0);
+                    offset
+                }
+            };
+            let entry = Elf64_Sym {
+                st_name,
+                st_info: symbol.bind << 4 | symbol.kind & 0xf,
+                st_other: symbol.visibility,
+                st_shndx: shndx,
+                st_value: value,
+                st_size: symbol.size,
+            };
+            table.extend_from_slice(&entry.to_bytes(self.big_endian));
+        }
+        let symtab = Section::new(".symtab", SHT::SYMTAB)
+            .align(8)
+            .entsize(Elf64_Sym::SIZE as u64)
+            .link(index as u32 + 1)
+            .info(locals.count() as u32 + 1)
+            .content(table);
+        let strtab = Section::new(".strtab", SHT::STRTAB).content(strtab);
+        Ok(Some([symtab, strtab]))
+    }
+
+    /// The value of a symbol once laid out
+    fn symbol_value(&self, placed: &[Placed], name: &str) -> Option<u64> {
+        let symbol = self.symbols.iter().find(|symbol| symbol.name == name)?;
+        match symbol.section {
+            Some(section) => Some(placed.get(section.checked_sub(1)?)?.address + symbol.value),
+            None => Some(symbol.value),
+        }
+    }
+
+    /// The description of the file, like `readbin export` writes them
+    ///
+    /// Every offset, address and size is given, [`description::build`]
+    /// only adds the null section and the section names table. Fails if
+    /// a segment or a symbol refers to a section that wasn't added, or if
+    /// the entry symbol doesn't exist.
+    pub fn description(&self) -> Result<Yaml> {
+        let mut sections = self.sections.clone();
+        // the tables aren't allocated, the other sections keep their place
+        let placed = self.layout(sections.clone());
+        if let Some(tables) = self.symbol_sections(&placed, sections.len() + 1)? {
+            sections.extend(tables);
+        }
+        let placed = self.layout(sections);
+        let entry = match &self.entry_symbol {
+            Some(name) => self.symbol_value(&placed, name).ok_or_else(|| {
+                Error::InvalidDescription(format!("entry symbol {} is not defined", name))
+            })?,
+            None => self.entry,
+        };
+        let data = if self.big_endian {
+            "ELFDATA2MSB"
+        } else {
+            "ELFDATA2LSB"
+        };
+        let file_header = Yaml::map([
+            ("Class", Yaml::from("ELFCLASS64")),
+            ("Data", Yaml::from(data)),
+            ("Type", hex(u16::from(self.e_type) as u64)),
+            ("Machine", hex(self.machine.to_u16() as u64)),
+            ("Entry", hex(entry)),
+            ("Flags", hex(self.flags as u64)),
+        ]);
+
+        let mut segments = Vec::new();
+        for (index, segment) in self.segments.iter().enumerate() {
+            let (offset, vaddr, filesz, memsz) = match segment.sections {
+                Some((first, last)) => {
+                    let mapped = placed
+                        .get(first.wrapping_sub(1)..last)
+                        .filter(|mapped| !mapped.is_empty() && last <= self.sections.len())
+                        .ok_or_else(|| {
+                            Error::InvalidDescription(format!(
+                                "segment {}: no sections {} to {}",
+                                index, first, last
+                            ))
+                        })?;
+                    let (first, last) = (&mapped[0], &mapped[mapped.len() - 1]);
+                    let size = |placed: &Placed| {
+                        let section = &placed.section;
+                        section.size.unwrap_or(section.content.len() as u64)
+                    };
+                    // the SHT_NOBITS sections take no room in the file
+                    let file_end = mapped
+                        .iter()
+                        .filter(|placed| placed.section.sh_type != SHT::NOBITS)
+                        .map(|placed| placed.offset + size(placed))
+                        .max()
+                        .unwrap_or(first.offset);
+                    (
+                        first.offset,
+                        first.address,
+                        file_end.saturating_sub(first.offset),
+                        (last.address + size(last)).saturating_sub(first.address),
+                    )
+                }
+                None => (0, 0, 0, 0),
+            };
+            segments.push(Yaml::map([
+                ("Type", hex(segment.p_type as u64)),
+                ("Flags", hex(segment.flags as u64)),
+                ("Offset", hex(offset)),
+                ("VAddr", hex(vaddr)),
+                ("FileSize", hex(filesz)),
+                ("MemSize", hex(memsz)),
+                ("Align", hex(segment.align)),
+            ]));
+        }
+
+        let mut sections = Vec::new();
+        for placed in &placed {
+            let section = &placed.section;
+            let mut fields = vec![
+                ("Name", Yaml::from(section.name.as_str())),
+                ("Type", hex(section.sh_type as u64)),
+                ("Flags", hex(section.flags)),
+                ("Address", hex(placed.address)),
+                ("Offset", hex(placed.offset)),
+            ];
+            match section.size {
+                Some(size) => fields.push(("Size", hex(size))),
+                None if section.sh_type == SHT::NOBITS => fields.push(("Size", hex(0))),
+                None => {}
+            }
+            fields.extend([
+                ("Link", hex(section.link as u64)),
+                ("Info", hex(section.info as u64)),
+                ("AddressAlign", hex(section.align)),
+                ("EntSize", hex(section.entsize)),
+            ]);
+            if section.sh_type != SHT::NOBITS && !section.content.is_empty() {
+                fields.push(("Content", encode(&section.content)));
+            }
+            sections.push(Yaml::map(fields));
+        }
+        Ok(Yaml::map([
+            ("FileHeader", file_header),
+            ("ProgramHeaders", Yaml::List(segments)),
+            ("Sections", Yaml::List(sections)),
+        ]))
+    }
+
+    /// Write the file
+    ///
+    /// Fails like [`ElfBuilder::description`].
+    pub fn build(&self) -> Result<Vec<u8>> {
+        description::build(&self.description()?)
+    }
+}
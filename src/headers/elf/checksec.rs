@@ -0,0 +1,204 @@
+use crate::alloc_prelude::*;
+use crate::utils::json::{Json, ToJson};
+use crate::Result;
+use core::fmt;
+
+use super::dynamic::{self, DF, DF_1, DT};
+use super::program::{self, PF, PT};
+use super::section::{self, SHN, SHT};
+use super::symbols;
+use super::types::Type;
+use super::x64::x64;
+
+/// Protection of the relocated data against overwrites
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relro {
+    /// no PT_GNU_RELRO segment
+    None,
+    /// PT_GNU_RELRO without immediate binding, the PLT GOT stays writable
+    Partial,
+    /// PT_GNU_RELRO with immediate binding
+    Full,
+}
+
+/// Position independence of the code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pie {
+    /// ET_EXEC, loaded at a fixed address
+    No,
+    /// ET_DYN executable
+    Pie,
+    /// ET_DYN shared object
+    Dso,
+    /// ET_REL object file
+    Rel,
+}
+
+/// Hardening features of an ELF64 file, like `checksec --file`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checksec {
+    /// RELRO level
+    pub relro: Relro,
+    /// the stack protector is used, `__stack_chk_fail` is referenced
+    pub canary: bool,
+    /// the stack isn't executable, PT_GNU_STACK without PF_X
+    pub nx: bool,
+    /// kind of relocation of the code
+    pub pie: Pie,
+    /// the `_chk` variants of the libc functions imported by FORTIFY_SOURCE
+    pub fortified: Vec<String>,
+    /// there's no `.symtab` section
+    pub stripped: bool,
+}
+
+/// Inspect the hardening features of the file
+///
+/// Fails if the program or section header tables can't be parsed, a
+/// missing or invalid dynamic section counts as no dynamic flags.
+pub fn from_bytes(data: &[u8], header: &x64) -> Result<Checksec> {
+    let phdrs: Vec<_> = program::from_bytes(data, header)?.collect();
+    let shdrs: Vec<_> = section::from_bytes(data, header)?.collect();
+
+    let dynamic = dynamic::from_bytes(data, header).ok().flatten();
+    let get = |tag| dynamic.as_ref().and_then(|dynamic| dynamic.get(tag));
+    let bind_now = get(DT::BIND_NOW).is_some()
+        || get(DT::FLAGS).is_some_and(|flags| flags & DF::BIND_NOW != 0)
+        || get(DT::FLAGS_1).is_some_and(|flags| flags & DF_1::NOW != 0);
+    let relro = match phdrs.iter().any(|phdr| phdr.p_type == PT::GNU_RELRO) {
+        false => Relro::None,
+        true if bind_now => Relro::Full,
+        true => Relro::Partial,
+    };
+
+    let nx = phdrs
+        .iter()
+        .find(|phdr| phdr.p_type == PT::GNU_STACK)
+        .is_some_and(|phdr| phdr.p_flags & PF::X == 0);
+
+    let pie = match Type::from(header.e_type) {
+        Type::Exec => Pie::No,
+        Type::Rel => Pie::Rel,
+        // older linkers don't set DF_1_PIE, an interpreter also tells
+        _ if get(DT::FLAGS_1).is_some_and(|flags| flags & DF_1::PIE != 0)
+            || phdrs.iter().any(|phdr| phdr.p_type == PT::INTERP) =>
+        {
+            Pie::Pie
+        }
+        _ => Pie::Dso,
+    };
+
+    let mut canary = false;
+    let mut fortified = Vec::new();
+    for shdr in shdrs
+        .iter()
+        .filter(|shdr| shdr.sh_type == SHT::SYMTAB || shdr.sh_type == SHT::DYNSYM)
+    {
+        let strtab = symbols::strtab(data, header, shdr).unwrap_or_default();
+        let symbols = match symbols::from_bytes(data, header, shdr) {
+            Ok(symbols) => symbols,
+            Err(_) => continue,
+        };
+        for symbol in symbols {
+            // the names looked for are ASCII
+            let name = match symbol.name(&strtab).map(|name| name.to_str()) {
+                Ok(Some(name)) => name.split('@').next().unwrap_or(name),
+                _ => continue,
+            };
+            if name == "__stack_chk_fail" || name == "__stack_chk_guard" {
+                canary = true;
+            } else if symbol.st_shndx == SHN::UNDEF
+                && name.starts_with("__")
+                && name.ends_with("_chk")
+                && !fortified.iter().any(|known| known == name)
+            {
+                fortified.push(name.to_string());
+            }
+        }
+    }
+    fortified.sort();
+
+    Ok(Checksec {
+        relro,
+        canary,
+        nx,
+        pie,
+        fortified,
+        stripped: !shdrs.iter().any(|shdr| shdr.sh_type == SHT::SYMTAB),
+    })
+}
+
+impl fmt::Display for Relro {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Relro::None => "No RELRO",
+            Relro::Partial => "Partial RELRO",
+            Relro::Full => "Full RELRO",
+        })
+    }
+}
+
+impl fmt::Display for Pie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Pie::No => "No PIE",
+            Pie::Pie => "PIE enabled",
+            Pie::Dso => "DSO",
+            Pie::Rel => "REL",
+        })
+    }
+}
+
+impl fmt::Display for Checksec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "RELRO:    {}", self.relro)?;
+        writeln!(
+            f,
+            "Stack:    {}",
+            if self.canary {
+                "Canary found"
+            } else {
+                "No canary found"
+            }
+        )?;
+        writeln!(
+            f,
+            "NX:       {}",
+            if self.nx { "NX enabled" } else { "NX disabled" }
+        )?;
+        writeln!(f, "PIE:      {}", self.pie)?;
+        match self.fortified.len() {
+            0 => writeln!(f, "FORTIFY:  No")?,
+            count => writeln!(
+                f,
+                "FORTIFY:  Yes ({} fortified: {})",
+                count,
+                self.fortified.join(", ")
+            )?,
+        }
+        writeln!(
+            f,
+            "Symbols:  {}",
+            if self.stripped {
+                "Stripped"
+            } else {
+                "Not stripped"
+            }
+        )
+    }
+}
+
+impl ToJson for Checksec {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("relro", Json::from(self.relro.to_string())),
+            ("canary", Json::from(self.canary)),
+            ("nx", Json::from(self.nx)),
+            ("pie", Json::from(self.pie.to_string())),
+            (
+                "fortified",
+                Json::array(self.fortified.iter().map(String::as_str)),
+            ),
+            ("stripped", Json::from(self.stripped)),
+        ])
+    }
+}
@@ -1,13 +1,51 @@
+use core::fmt;
+
 /// Define the architecture for the binary
 ///
-/// class defined possible value for a binary
-/// Its store in the fifth byte of the identifaction 16 bits called `e_ident`
-pub struct Class {}
-impl Class {
+/// Class define the possible values of the fifth byte of the
+/// identification bytes `e_ident`. Decoding never fails, the values not
+/// known by this crate are kept in `Unknown`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Class {
     /// Invalid class
-    pub const NONE: u8 = 0;
+    None,
     /// 32 bits object
-    pub const ELF32: u8 = 1;
-    /// 32 bits object
-    pub const ELF64: u8 = 2;
+    Elf32,
+    /// 64 bits object
+    Elf64,
+    /// Value not known by this crate
+    Unknown(u8),
+}
+
+impl From<u8> for Class {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Class::None,
+            1 => Class::Elf32,
+            2 => Class::Elf64,
+            value => Class::Unknown(value),
+        }
+    }
+}
+
+impl From<Class> for u8 {
+    fn from(class: Class) -> Self {
+        match class {
+            Class::None => 0,
+            Class::Elf32 => 1,
+            Class::Elf64 => 2,
+            Class::Unknown(value) => value,
+        }
+    }
+}
+
+impl fmt::Display for Class {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Class::None => f.write_str("Invalid class"),
+            Class::Elf32 => f.write_str("ELF32"),
+            Class::Elf64 => f.write_str("ELF64"),
+            Class::Unknown(value) => write!(f, "<unknown: {:x}>", value),
+        }
+    }
 }
@@ -0,0 +1,199 @@
+//! Compressed sections, mostly the debug sections
+//!
+//! A section with the SHF_COMPRESSED flag starts with an `Elf64_Chdr`
+//! giving the algorithm, the size and the alignment of the decoded
+//! content, followed by the compressed bytes. The older GNU convention
+//! renames the sections `.zdebug_*` instead: their content starts with
+//! `ZLIB` and the decoded size as a 64 bits big endian number, followed by
+//! a zlib stream. The zlib streams are decoded with the `zlib` feature,
+//! the zstd ones with the `zstd` feature.
+//! Sources:
+//! * <https://www.sco.com/developers/gabi/latest/ch4.sheader.html#compression_header>
+//! * <https://github.com/bminor/binutils-gdb/blob/master/bfd/compress.c>
+use crate::alloc_prelude::*;
+use crate::utils::{read_u32, read_u64};
+use crate::{Error, Result};
+use alloc::borrow::Cow;
+use core::fmt;
+use core::mem::size_of;
+
+use super::section::{Elf64_Shdr, SectionFlags};
+
+/// Define the compression algorithms
+///
+/// ELFCOMPRESS define the possible values for the `ch_type` field
+pub struct ELFCOMPRESS {}
+impl ELFCOMPRESS {
+    /// zlib, RFC 1950
+    pub const ZLIB: u32 = 1;
+    /// Zstandard, RFC 8878
+    pub const ZSTD: u32 = 2;
+    /// Start of the OS specific range
+    pub const LOOS: u32 = 0x6000_0000;
+    /// End of the OS specific range
+    pub const HIOS: u32 = 0x6fff_ffff;
+    /// Start of the processor specific range
+    pub const LOPROC: u32 = 0x7000_0000;
+    /// End of the processor specific range
+    pub const HIPROC: u32 = 0x7fff_ffff;
+}
+
+/// Prefix of the content of the `.zdebug_*` sections
+pub const ZDEBUG_MAGIC: &[u8; 4] = b"ZLIB";
+
+/// Header of a section with the SHF_COMPRESSED flag
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct Elf64_Chdr {
+    /// compression algorithm, one of [`ELFCOMPRESS`]
+    pub ch_type: u32,
+    pub ch_reserved: u32,
+    /// size of the decoded content
+    pub ch_size: u64,
+    /// alignment of the decoded content
+    pub ch_addralign: u64,
+}
+
+impl Elf64_Chdr {
+    pub const SIZE: usize = size_of::<Self>();
+
+    /// Read the header at the start of the content of a section
+    pub fn parse(bytes: &[u8], big_endian: bool) -> Option<Self> {
+        Some(Elf64_Chdr {
+            ch_type: read_u32(bytes, 0, big_endian)?,
+            ch_reserved: read_u32(bytes, 4, big_endian)?,
+            ch_size: read_u64(bytes, 8, big_endian)?,
+            ch_addralign: read_u64(bytes, 16, big_endian)?,
+        })
+    }
+
+    /// Name of the algorithm as printed by readelf
+    pub fn type_name(&self) -> &'static str {
+        match self.ch_type {
+            ELFCOMPRESS::ZLIB => "ZLIB",
+            ELFCOMPRESS::ZSTD => "ZSTD",
+            _ => "<unknown>",
+        }
+    }
+}
+
+/// How the content of a section is compressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// SHF_COMPRESSED, the content starts with this header
+    Chdr(Elf64_Chdr),
+    /// a `.zdebug_*` section decoding to this size
+    Gnu(u64),
+}
+
+impl Compression {
+    /// Size of the decoded content
+    pub fn size(&self) -> u64 {
+        match self {
+            Compression::Chdr(chdr) => chdr.ch_size,
+            Compression::Gnu(size) => *size,
+        }
+    }
+
+    /// The algorithm, one of [`ELFCOMPRESS`]
+    pub fn algorithm(&self) -> u32 {
+        match self {
+            Compression::Chdr(chdr) => chdr.ch_type,
+            Compression::Gnu(_) => ELFCOMPRESS::ZLIB,
+        }
+    }
+
+    /// Number of bytes in front of the compressed stream
+    fn header_size(&self) -> usize {
+        match self {
+            Compression::Chdr(_) => Elf64_Chdr::SIZE,
+            Compression::Gnu(_) => ZDEBUG_MAGIC.len() + size_of::<u64>(),
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compression::Chdr(chdr) => write!(
+                f,
+                "{}, {:#x} bytes, alignment {}",
+                chdr.type_name(),
+                chdr.ch_size,
+                chdr.ch_addralign
+            ),
+            Compression::Gnu(size) => write!(f, "ZLIB (GNU), {:#x} bytes", size),
+        }
+    }
+}
+
+/// How a section is compressed, None if it isn't
+///
+/// `bytes` is the content of the section in the file.
+pub fn compression(
+    name: &str,
+    shdr: &Elf64_Shdr,
+    bytes: &[u8],
+    big_endian: bool,
+) -> Option<Compression> {
+    if shdr.flags().contains(SectionFlags::COMPRESSED) {
+        return Elf64_Chdr::parse(bytes, big_endian).map(Compression::Chdr);
+    }
+    if name.starts_with(".zdebug") && bytes.starts_with(ZDEBUG_MAGIC) {
+        return read_u64(bytes, ZDEBUG_MAGIC.len(), true).map(Compression::Gnu);
+    }
+    None
+}
+
+/// The name of the section once decompressed, `.debug_info` for
+/// `.zdebug_info`
+pub fn uncompressed_name(name: &str) -> Cow<'_, str> {
+    match name.strip_prefix(".zdebug") {
+        Some(rest) => Cow::Owned(format!(".debug{}", rest)),
+        None => Cow::Borrowed(name),
+    }
+}
+
+/// The decoded content of a section, borrowed when it isn't compressed
+///
+/// Fails if the compression header is truncated, if the algorithm isn't
+/// known or its feature isn't enabled, or if the stream is invalid or
+/// doesn't decode to the size given by the header.
+pub fn decompress<'a>(
+    name: &str,
+    shdr: &Elf64_Shdr,
+    bytes: &'a [u8],
+    big_endian: bool,
+) -> Result<Cow<'a, [u8]>> {
+    let compression = match compression(name, shdr, bytes, big_endian) {
+        Some(compression) => compression,
+        None if shdr.flags().contains(SectionFlags::COMPRESSED) => {
+            return Err(Error::InvalidCompressedData(0))
+        }
+        None => return Ok(Cow::Borrowed(bytes)),
+    };
+    let header_size = compression.header_size();
+    let size = usize::try_from(compression.size())
+        .map_err(|_| Error::InvalidCompressedData(header_size as u64))?;
+    decode(compression.algorithm(), &bytes[header_size..], size)
+        .map(Cow::Owned)
+        .map_err(|err| match err {
+            Error::InvalidCompressedData(offset) => {
+                Error::InvalidCompressedData(offset + header_size as u64)
+            }
+            err => err,
+        })
+}
+
+/// Decode a stream with the backend of its algorithm
+#[allow(unused_variables)]
+fn decode(algorithm: u32, data: &[u8], size: usize) -> Result<Vec<u8>> {
+    match algorithm {
+        #[cfg(feature = "zlib")]
+        ELFCOMPRESS::ZLIB => crate::utils::inflate::zlib(data, size),
+        #[cfg(feature = "zstd")]
+        ELFCOMPRESS::ZSTD => crate::utils::zstd::decompress(data, size),
+        algorithm => Err(Error::UnsupportedCompression(algorithm)),
+    }
+}
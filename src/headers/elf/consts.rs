@@ -0,0 +1,10 @@
+//! Typed values of the ELF file header fields
+//!
+//! Each type decodes its field with `From`, keeps the values it doesn't
+//! know in an `Unknown` variant and prints like readelf.
+pub use super::class::Class;
+pub use super::data::Data;
+pub use super::machine::Machine;
+pub use super::osabit::OsAbi;
+pub use super::types::Type;
+pub use super::version::Version;
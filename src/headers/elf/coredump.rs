@@ -0,0 +1,523 @@
+use crate::alloc_prelude::*;
+use crate::utils::json::{Json, ToJson};
+use crate::utils::{read_u16, read_u32, read_u64};
+use crate::{Error, Result};
+use core::fmt;
+
+use super::auxv::{self, Auxv};
+use super::data::Data;
+use super::machine::Machine;
+use super::notes::{self, NT_CORE};
+use super::program::{self, Elf64_Phdr, PT};
+use super::types::Type;
+use super::x64::x64;
+
+/// Define the types of the auxiliary vector entries
+///
+/// AT define possible values for the `a_type` field of the NT_AUXV note
+pub struct AT {}
+impl AT {
+    /// End of the vector
+    pub const NULL: u64 = 0;
+    /// Entry should be ignored
+    pub const IGNORE: u64 = 1;
+    /// File descriptor of the program
+    pub const EXECFD: u64 = 2;
+    /// Program headers of the program
+    pub const PHDR: u64 = 3;
+    /// Size of a program header entry
+    pub const PHENT: u64 = 4;
+    /// Number of program headers
+    pub const PHNUM: u64 = 5;
+    /// System page size
+    pub const PAGESZ: u64 = 6;
+    /// Base address of the interpreter
+    pub const BASE: u64 = 7;
+    /// Flags
+    pub const FLAGS: u64 = 8;
+    /// Entry point of the program
+    pub const ENTRY: u64 = 9;
+    /// Program is not ELF
+    pub const NOTELF: u64 = 10;
+    /// Real user id
+    pub const UID: u64 = 11;
+    /// Effective user id
+    pub const EUID: u64 = 12;
+    /// Real group id
+    pub const GID: u64 = 13;
+    /// Effective group id
+    pub const EGID: u64 = 14;
+    /// Address of the platform string
+    pub const PLATFORM: u64 = 15;
+    /// Machine dependent hints about processor capabilities
+    pub const HWCAP: u64 = 16;
+    /// Frequency of `times()`
+    pub const CLKTCK: u64 = 17;
+    /// Secure mode boolean
+    pub const SECURE: u64 = 23;
+    /// Address of the real platform string
+    pub const BASE_PLATFORM: u64 = 24;
+    /// Address of 16 random bytes
+    pub const RANDOM: u64 = 25;
+    /// Extension of AT_HWCAP
+    pub const HWCAP2: u64 = 26;
+    /// Size of the rseq area supported by the kernel
+    pub const RSEQ_FEATURE_SIZE: u64 = 27;
+    /// Alignment of the rseq area
+    pub const RSEQ_ALIGN: u64 = 28;
+    /// Address of the file name of the program
+    pub const EXECFN: u64 = 31;
+    /// Address of the vsyscall entry point
+    pub const SYSINFO: u64 = 32;
+    /// Address of the vDSO
+    pub const SYSINFO_EHDR: u64 = 33;
+    /// Minimal stack size for signal delivery
+    pub const MINSIGSTKSZ: u64 = 51;
+
+    /// Name of an auxiliary vector entry type, ex: `AT_PAGESZ`
+    pub fn name(a_type: u64) -> Option<&'static str> {
+        let name = match a_type {
+            AT::NULL => "AT_NULL",
+            AT::IGNORE => "AT_IGNORE",
+            AT::EXECFD => "AT_EXECFD",
+            AT::PHDR => "AT_PHDR",
+            AT::PHENT => "AT_PHENT",
+            AT::PHNUM => "AT_PHNUM",
+            AT::PAGESZ => "AT_PAGESZ",
+            AT::BASE => "AT_BASE",
+            AT::FLAGS => "AT_FLAGS",
+            AT::ENTRY => "AT_ENTRY",
+            AT::NOTELF => "AT_NOTELF",
+            AT::UID => "AT_UID",
+            AT::EUID => "AT_EUID",
+            AT::GID => "AT_GID",
+            AT::EGID => "AT_EGID",
+            AT::PLATFORM => "AT_PLATFORM",
+            AT::HWCAP => "AT_HWCAP",
+            AT::CLKTCK => "AT_CLKTCK",
+            AT::SECURE => "AT_SECURE",
+            AT::BASE_PLATFORM => "AT_BASE_PLATFORM",
+            AT::RANDOM => "AT_RANDOM",
+            AT::HWCAP2 => "AT_HWCAP2",
+            AT::RSEQ_FEATURE_SIZE => "AT_RSEQ_FEATURE_SIZE",
+            AT::RSEQ_ALIGN => "AT_RSEQ_ALIGN",
+            AT::EXECFN => "AT_EXECFN",
+            AT::SYSINFO => "AT_SYSINFO",
+            AT::SYSINFO_EHDR => "AT_SYSINFO_EHDR",
+            AT::MINSIGSTKSZ => "AT_MINSIGSTKSZ",
+            _ => return None,
+        };
+        Some(name)
+    }
+}
+
+/// Offset of `pr_reg` in the 64 bits `elf_prstatus` structure
+const PR_REG: usize = 112;
+
+/// General purpose registers of x86_64, in `user_regs_struct` order
+const X86_64_REGISTERS: [&str; 27] = [
+    "r15", "r14", "r13", "r12", "rbp", "rbx", "r11", "r10", "r9", "r8", "rax", "rcx", "rdx", "rsi",
+    "rdi", "orig_rax", "rip", "cs", "eflags", "rsp", "ss", "fs_base", "gs_base", "ds", "es", "fs",
+    "gs",
+];
+
+/// General purpose registers of aarch64, in `user_pt_regs` order
+const AARCH64_REGISTERS: [&str; 34] = [
+    "x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7", "x8", "x9", "x10", "x11", "x12", "x13", "x14",
+    "x15", "x16", "x17", "x18", "x19", "x20", "x21", "x22", "x23", "x24", "x25", "x26", "x27",
+    "x28", "x29", "x30", "sp", "pc", "pstate",
+];
+
+/// State of a thread when the core was dumped, from NT_PRSTATUS
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrStatus {
+    /// signal that caused the dump
+    pub signal: u16,
+    /// thread id
+    pub pid: u32,
+    /// parent process id
+    pub ppid: u32,
+    /// process group id
+    pub pgrp: u32,
+    /// session id
+    pub sid: u32,
+    /// general purpose registers, named by [`register_names`]
+    pub registers: Vec<u64>,
+}
+
+impl PrStatus {
+    /// Decode a 64 bits `elf_prstatus` structure
+    ///
+    /// The register set fills the structure up to `pr_fpvalid` and its
+    /// padding, its size depends on the machine.
+    pub fn parse(desc: &[u8], big_endian: bool) -> Option<Self> {
+        let count = desc.len().checked_sub(PR_REG + 8)? / 8;
+        let registers = (0..count)
+            .map(|index| read_u64(desc, PR_REG + index * 8, big_endian))
+            .collect::<Option<_>>()?;
+        Some(PrStatus {
+            signal: read_u16(desc, 12, big_endian)?,
+            pid: read_u32(desc, 32, big_endian)?,
+            ppid: read_u32(desc, 36, big_endian)?,
+            pgrp: read_u32(desc, 40, big_endian)?,
+            sid: read_u32(desc, 44, big_endian)?,
+            registers,
+        })
+    }
+}
+
+/// Information about the dumped process, from NT_PRPSINFO
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrPsInfo<'a> {
+    /// numeric process state
+    pub state: u8,
+    /// process state as a letter, ex: `R`
+    pub sname: u8,
+    /// whether the process is a zombie
+    pub zombie: bool,
+    /// nice value
+    pub nice: i8,
+    /// process flags
+    pub flag: u64,
+    /// user id
+    pub uid: u32,
+    /// group id
+    pub gid: u32,
+    /// process id
+    pub pid: u32,
+    /// parent process id
+    pub ppid: u32,
+    /// process group id
+    pub pgrp: u32,
+    /// session id
+    pub sid: u32,
+    /// file name of the executable, truncated to 16 bytes
+    pub fname: &'a str,
+    /// start of the command line, truncated to 80 bytes
+    pub psargs: &'a str,
+}
+
+/// String stored in a fixed size nul padded array
+fn fixed_str(bytes: &[u8]) -> &str {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    core::str::from_utf8(&bytes[..end]).unwrap_or("<corrupt>")
+}
+
+impl<'a> PrPsInfo<'a> {
+    /// Decode a 64 bits `elf_prpsinfo` structure
+    pub fn parse(desc: &'a [u8], big_endian: bool) -> Option<Self> {
+        Some(PrPsInfo {
+            state: *desc.first()?,
+            sname: *desc.get(1)?,
+            zombie: *desc.get(2)? != 0,
+            nice: *desc.get(3)? as i8,
+            flag: read_u64(desc, 8, big_endian)?,
+            uid: read_u32(desc, 16, big_endian)?,
+            gid: read_u32(desc, 20, big_endian)?,
+            pid: read_u32(desc, 24, big_endian)?,
+            ppid: read_u32(desc, 28, big_endian)?,
+            pgrp: read_u32(desc, 32, big_endian)?,
+            sid: read_u32(desc, 36, big_endian)?,
+            fname: fixed_str(desc.get(40..56)?),
+            psargs: fixed_str(desc.get(56..136)?),
+        })
+    }
+}
+
+/// File mapped in the address space of the process, from NT_FILE
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MappedFile<'a> {
+    /// start address of the mapping
+    pub start: u64,
+    /// end address of the mapping
+    pub end: u64,
+    /// offset of the mapping in the file, in pages
+    pub page_offset: u64,
+    /// path of the file
+    pub name: &'a str,
+}
+
+/// Decode a 64 bits NT_FILE note into its page size and mappings
+pub fn mapped_files(desc: &[u8], big_endian: bool) -> Option<(u64, Vec<MappedFile<'_>>)> {
+    let count = usize::try_from(read_u64(desc, 0, big_endian)?).ok()?;
+    let page_size = read_u64(desc, 8, big_endian)?;
+    let names_start = count.checked_mul(24)?.checked_add(16)?;
+    let mut names = desc.get(names_start..)?.split(|b| *b == 0);
+    let mut files = Vec::with_capacity(count.min(desc.len() / 24));
+    for index in 0..count {
+        let entry = 16 + index * 24;
+        files.push(MappedFile {
+            start: read_u64(desc, entry, big_endian)?,
+            end: read_u64(desc, entry + 8, big_endian)?,
+            page_offset: read_u64(desc, entry + 16, big_endian)?,
+            name: core::str::from_utf8(names.next()?).unwrap_or("<corrupt>"),
+        });
+    }
+    Some((page_size, files))
+}
+
+/// Decode a 64 bits NT_AUXV note, up to the AT_NULL entry
+pub fn auxv(desc: &[u8], big_endian: bool) -> Vec<(u64, u64)> {
+    (0..desc.len() / 16)
+        .map_while(|index| {
+            let a_type = read_u64(desc, index * 16, big_endian)?;
+            let a_val = read_u64(desc, index * 16 + 8, big_endian)?;
+            (a_type != AT::NULL).then_some((a_type, a_val))
+        })
+        .collect()
+}
+
+/// Names of the registers stored in NT_PRSTATUS for the given machine
+///
+/// Returns an empty slice for the machines not known by this crate.
+pub fn register_names(machine: Machine) -> &'static [&'static str] {
+    match machine {
+        Machine::X86_64 => &X86_64_REGISTERS,
+        Machine::Aarch64 => &AARCH64_REGISTERS,
+        _ => &[],
+    }
+}
+
+/// The process state stored in a core file
+#[derive(Debug, Clone)]
+pub struct Core<'a> {
+    /// machine of the dumped process
+    pub machine: Machine,
+    /// process information
+    pub process: Option<PrPsInfo<'a>>,
+    /// one entry per thread, the first one caused the dump
+    pub threads: Vec<PrStatus>,
+    /// auxiliary vector of the process
+    pub auxv: Auxv<'a>,
+    /// page size used by the mapped files offsets
+    pub page_size: u64,
+    /// files mapped in memory
+    pub files: Vec<MappedFile<'a>>,
+    /// PT_LOAD segments holding the memory of the process
+    pub segments: Vec<Elf64_Phdr>,
+}
+
+/// Returns the process state stored in a core file
+///
+/// Fails if the file isn't an ET_CORE file or if its program header table
+/// is invalid. Notes that can't be decoded are skipped.
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64) -> Result<Core<'a>> {
+    if Type::from(header.e_type) != Type::Core {
+        return Err(Error::InvalidHeaderField {
+            field: "e_type",
+            expected: u16::from(Type::Core) as usize,
+            found: header.e_type as usize,
+        });
+    }
+    let big_endian = Data::is_big_endian(&header.e_ident);
+    let machine = Machine::from_u16(header.e_machine);
+    let mut core = Core {
+        machine,
+        process: None,
+        threads: Vec::new(),
+        auxv: auxv::from_bytes(data, header)?.unwrap_or(Auxv {
+            machine,
+            entries: Vec::new(),
+        }),
+        page_size: 0,
+        files: Vec::new(),
+        segments: program::from_bytes(data, header)?
+            .filter(|phdr| phdr.p_type == PT::LOAD)
+            .map(|phdr| *phdr)
+            .collect(),
+    };
+    let notes = notes::areas(data, header)
+        .into_iter()
+        .flat_map(|(_, _, notes)| notes)
+        .filter(|note| note.name == b"CORE");
+    for note in notes {
+        match note.n_type {
+            NT_CORE::PRSTATUS => core.threads.extend(PrStatus::parse(note.desc, big_endian)),
+            NT_CORE::PRPSINFO => core.process = PrPsInfo::parse(note.desc, big_endian),
+            NT_CORE::FILE => {
+                if let Some((page_size, files)) = mapped_files(note.desc, big_endian) {
+                    core.page_size = page_size;
+                    core.files = files;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(core)
+}
+
+/// Name of a signal number, ex: `SIGSEGV`
+fn signal_name(signal: u16) -> Option<&'static str> {
+    let name = match signal {
+        1 => "SIGHUP",
+        2 => "SIGINT",
+        3 => "SIGQUIT",
+        4 => "SIGILL",
+        5 => "SIGTRAP",
+        6 => "SIGABRT",
+        7 => "SIGBUS",
+        8 => "SIGFPE",
+        9 => "SIGKILL",
+        10 => "SIGUSR1",
+        11 => "SIGSEGV",
+        12 => "SIGUSR2",
+        13 => "SIGPIPE",
+        14 => "SIGALRM",
+        15 => "SIGTERM",
+        24 => "SIGXCPU",
+        25 => "SIGXFSZ",
+        31 => "SIGSYS",
+        _ => return None,
+    };
+    Some(name)
+}
+
+impl fmt::Display for Core<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(process) = &self.process {
+            writeln!(
+                f,
+                "Process: {} (pid {}, ppid {}, uid {}, gid {})",
+                process.fname, process.pid, process.ppid, process.uid, process.gid
+            )?;
+            writeln!(f, "Command line: {}", process.psargs)?;
+            writeln!(f, "State: {}", process.sname as char)?;
+        }
+        if let Some(thread) = self.threads.first() {
+            match signal_name(thread.signal) {
+                Some(name) => writeln!(f, "Signal: {} ({})", thread.signal, name)?,
+                None => writeln!(f, "Signal: {}", thread.signal)?,
+            }
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Threads: {}", self.threads.len())?;
+        let names = register_names(self.machine);
+        for thread in &self.threads {
+            writeln!(f, "  Thread {}, signal {}", thread.pid, thread.signal)?;
+            for registers in thread.registers.chunks(4).enumerate() {
+                let (line, values) = registers;
+                write!(f, "   ")?;
+                for (column, value) in values.iter().enumerate() {
+                    let index = line * 4 + column;
+                    match names.get(index) {
+                        Some(name) => write!(f, " {:>8} 0x{:016x}", name, value)?,
+                        None => write!(f, " {:>8} 0x{:016x}", format!("r{}", index), value)?,
+                    }
+                }
+                writeln!(f)?;
+            }
+        }
+
+        if !self.auxv.entries.is_empty() {
+            writeln!(f)?;
+            write!(f, "{}", self.auxv)?;
+        }
+
+        if !self.files.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Mapped files (page size {}):", self.page_size)?;
+            writeln!(
+                f,
+                "  Start              End                Page Offset        File"
+            )?;
+            for file in &self.files {
+                writeln!(
+                    f,
+                    "  0x{:016x} 0x{:016x} 0x{:016x} {}",
+                    file.start, file.end, file.page_offset, file.name
+                )?;
+            }
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Memory segments:")?;
+        writeln!(
+            f,
+            "  VirtAddr           MemSiz             FileSiz            Flg"
+        )?;
+        for segment in &self.segments {
+            writeln!(
+                f,
+                "  0x{:016x} 0x{:016x} 0x{:016x} {}",
+                segment.p_vaddr,
+                segment.p_memsz,
+                segment.p_filesz,
+                segment.flags_string()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for Core<'_> {
+    fn to_json(&self) -> Json {
+        let names = register_names(self.machine);
+        Json::object([
+            (
+                "process",
+                match &self.process {
+                    Some(process) => Json::object([
+                        ("fname", Json::from(process.fname)),
+                        ("psargs", Json::from(process.psargs)),
+                        ("state", Json::from((process.sname as char).to_string())),
+                        ("pid", Json::from(process.pid)),
+                        ("ppid", Json::from(process.ppid)),
+                        ("uid", Json::from(process.uid)),
+                        ("gid", Json::from(process.gid)),
+                    ]),
+                    None => Json::Null,
+                },
+            ),
+            (
+                "threads",
+                Json::array(self.threads.iter().map(|thread| {
+                    Json::object([
+                        ("pid", Json::from(thread.pid)),
+                        ("signal", Json::from(thread.signal)),
+                        (
+                            "registers",
+                            Json::Object(
+                                thread
+                                    .registers
+                                    .iter()
+                                    .enumerate()
+                                    .map(|(index, value)| {
+                                        let name = names
+                                            .get(index)
+                                            .map(|name| name.to_string())
+                                            .unwrap_or_else(|| format!("r{}", index));
+                                        (name, Json::from(*value))
+                                    })
+                                    .collect(),
+                            ),
+                        ),
+                    ])
+                })),
+            ),
+            ("auxv", self.auxv.to_json()),
+            (
+                "files",
+                Json::array(self.files.iter().map(|file| {
+                    Json::object([
+                        ("start", Json::from(file.start)),
+                        ("end", Json::from(file.end)),
+                        ("offset", Json::from(file.page_offset * self.page_size)),
+                        ("name", Json::from(file.name)),
+                    ])
+                })),
+            ),
+            (
+                "segments",
+                Json::array(self.segments.iter().map(|segment| {
+                    Json::object([
+                        ("vaddr", Json::from(segment.p_vaddr)),
+                        ("memsz", Json::from(segment.p_memsz)),
+                        ("filesz", Json::from(segment.p_filesz)),
+                        ("flags", Json::from(segment.flags_string())),
+                    ])
+                })),
+            ),
+        ])
+    }
+}
@@ -1,13 +1,60 @@
+use core::fmt;
+
+use super::identification::Indent;
+
 /// Define the data encoding for the processor-specific data
 ///
-/// Data define possible values for the encoding
-/// Its store in the sixth byte of the identifaction 16 bits called `e_ident`
-pub struct DATA {}
-impl DATA {
+/// Data define the possible values of the sixth byte of the
+/// identification bytes `e_ident`. Decoding never fails, the values not
+/// known by this crate are kept in `Unknown`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Data {
     /// Unknow data encoding
-    pub const NONE: u8 = 0;
+    None,
     /// Little endian data encoding
-    pub const LE: u8 = 1;
+    Le,
     /// Big endian data encoding
-    pub const BE: u8 = 2;
+    Be,
+    /// Value not known by this crate
+    Unknown(u8),
+}
+
+impl Data {
+    /// Whether the multi-byte fields of the file are stored in big endian
+    pub fn is_big_endian(e_ident: &[u8; 16]) -> bool {
+        Data::from(e_ident[Indent::DATA]) == Data::Be
+    }
+}
+
+impl From<u8> for Data {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Data::None,
+            1 => Data::Le,
+            2 => Data::Be,
+            value => Data::Unknown(value),
+        }
+    }
+}
+
+impl From<Data> for u8 {
+    fn from(data: Data) -> Self {
+        match data {
+            Data::None => 0,
+            Data::Le => 1,
+            Data::Be => 2,
+            Data::Unknown(value) => value,
+        }
+    }
+}
+
+impl fmt::Display for Data {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Data::None => f.write_str("Unknown data encoding"),
+            Data::Le => f.write_str("2's complement, little endian"),
+            Data::Be => f.write_str("2's complement, big endian"),
+            Data::Unknown(value) => write!(f, "<unknown: {:x}>", value),
+        }
+    }
 }
@@ -0,0 +1,607 @@
+//! DWARF line number information, stored in `.debug_line`
+//!
+//! Each compilation unit has a line number program: a header listing the
+//! directories and source files, followed by opcodes driving a state
+//! machine that emits one row per instruction address. Versions 2 to 5
+//! are decoded, the VLIW `op_index` register is not tracked.
+//! Sources:
+//! * <https://dwarfstd.org/doc/DWARF5.pdf> section 6.2
+//! * <https://dwarfstd.org/doc/DWARF4.pdf> section 6.2
+use crate::alloc_prelude::*;
+use crate::utils::{read_u16, read_u32, read_u64};
+use crate::{Error, Result};
+use alloc::borrow::Cow;
+use core::fmt;
+
+/// Standard opcodes of the line number program
+#[allow(non_camel_case_types)]
+pub struct DW_LNS {}
+
+impl DW_LNS {
+    pub const COPY: u8 = 0x01;
+    pub const ADVANCE_PC: u8 = 0x02;
+    pub const ADVANCE_LINE: u8 = 0x03;
+    pub const SET_FILE: u8 = 0x04;
+    pub const SET_COLUMN: u8 = 0x05;
+    pub const NEGATE_STMT: u8 = 0x06;
+    pub const SET_BASIC_BLOCK: u8 = 0x07;
+    pub const CONST_ADD_PC: u8 = 0x08;
+    pub const FIXED_ADVANCE_PC: u8 = 0x09;
+    pub const SET_PROLOGUE_END: u8 = 0x0a;
+    pub const SET_EPILOGUE_BEGIN: u8 = 0x0b;
+    pub const SET_ISA: u8 = 0x0c;
+}
+
+/// Extended opcodes of the line number program, introduced by a 0 byte
+#[allow(non_camel_case_types)]
+pub struct DW_LNE {}
+
+impl DW_LNE {
+    pub const END_SEQUENCE: u8 = 0x01;
+    pub const SET_ADDRESS: u8 = 0x02;
+    pub const DEFINE_FILE: u8 = 0x03;
+    pub const SET_DISCRIMINATOR: u8 = 0x04;
+}
+
+/// Content types of the DWARF 5 directory and file entries
+#[allow(non_camel_case_types)]
+pub struct DW_LNCT {}
+
+impl DW_LNCT {
+    pub const PATH: u64 = 0x1;
+    pub const DIRECTORY_INDEX: u64 = 0x2;
+    pub const TIMESTAMP: u64 = 0x3;
+    pub const SIZE: u64 = 0x4;
+    pub const MD5: u64 = 0x5;
+}
+
+/// Attribute forms used by the DWARF 5 directory and file entries
+#[allow(non_camel_case_types)]
+pub struct DW_FORM {}
+
+impl DW_FORM {
+    pub const BLOCK: u64 = 0x09;
+    pub const DATA1: u64 = 0x0b;
+    pub const DATA2: u64 = 0x05;
+    pub const DATA4: u64 = 0x06;
+    pub const DATA8: u64 = 0x07;
+    pub const DATA16: u64 = 0x1e;
+    pub const STRING: u64 = 0x08;
+    pub const STRP: u64 = 0x0e;
+    pub const UDATA: u64 = 0x0f;
+    pub const LINE_STRP: u64 = 0x1f;
+    pub const STRX: u64 = 0x1a;
+    pub const STRX1: u64 = 0x25;
+    pub const STRX2: u64 = 0x26;
+    pub const STRX3: u64 = 0x27;
+    pub const STRX4: u64 = 0x28;
+}
+
+/// Cursor over a DWARF section, every read returns None past its end
+pub(crate) struct Reader<'a> {
+    pub(crate) data: &'a [u8],
+    pub(crate) offset: usize,
+    pub(crate) big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8], big_endian: bool) -> Self {
+        Reader {
+            data,
+            offset: 0,
+            big_endian,
+        }
+    }
+
+    pub(crate) fn u8(&mut self) -> Option<u8> {
+        let value = *self.data.get(self.offset)?;
+        self.offset += 1;
+        Some(value)
+    }
+
+    pub(crate) fn u16(&mut self) -> Option<u16> {
+        let value = read_u16(self.data, self.offset, self.big_endian)?;
+        self.offset += 2;
+        Some(value)
+    }
+
+    pub(crate) fn u32(&mut self) -> Option<u32> {
+        let value = read_u32(self.data, self.offset, self.big_endian)?;
+        self.offset += 4;
+        Some(value)
+    }
+
+    pub(crate) fn u64(&mut self) -> Option<u64> {
+        let value = read_u64(self.data, self.offset, self.big_endian)?;
+        self.offset += 8;
+        Some(value)
+    }
+
+    /// A section offset, 8 bytes in the 64-bit DWARF format
+    pub(crate) fn offset(&mut self, dwarf64: bool) -> Option<u64> {
+        if dwarf64 {
+            self.u64()
+        } else {
+            self.u32().map(u64::from)
+        }
+    }
+
+    /// An unsigned integer of `size` bytes, as used by DW_LNE_set_address
+    pub(crate) fn address(&mut self, size: usize) -> Option<u64> {
+        match size {
+            1 => self.u8().map(u64::from),
+            2 => self.u16().map(u64::from),
+            4 => self.u32().map(u64::from),
+            8 => self.u64(),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn uleb128(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift < 64 {
+                value |= u64::from(byte & 0x7f) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+    }
+
+    pub(crate) fn sleb128(&mut self) -> Option<i64> {
+        let mut value = 0i64;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift < 64 {
+                value |= i64::from(byte & 0x7f) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    value |= -1 << shift;
+                }
+                return Some(value);
+            }
+        }
+    }
+
+    pub(crate) fn skip(&mut self, size: u64) -> Option<()> {
+        let end = self.offset.checked_add(usize::try_from(size).ok()?)?;
+        (end <= self.data.len()).then(|| self.offset = end)
+    }
+
+    /// A nul terminated string, the empty string is returned for invalid UTF-8
+    pub(crate) fn cstr(&mut self) -> Option<&'a str> {
+        let bytes = self.data.get(self.offset..)?;
+        let len = bytes.iter().position(|b| *b == 0)?;
+        self.offset += len + 1;
+        Some(core::str::from_utf8(&bytes[..len]).unwrap_or_default())
+    }
+}
+
+/// Nul terminated string at `offset` of a string section
+fn string_at(section: &[u8], offset: u64) -> Option<&str> {
+    let bytes = section.get(usize::try_from(offset).ok()?..)?;
+    let len = bytes.iter().position(|b| *b == 0)?;
+    core::str::from_utf8(&bytes[..len]).ok()
+}
+
+/// A source file of a line number program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileEntry<'a> {
+    /// file name, relative to its directory unless absolute
+    pub name: &'a str,
+    /// index of the directory in the header
+    pub directory: u64,
+}
+
+/// Header of the line number program of a compilation unit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineProgramHeader<'a> {
+    /// offset of the unit in `.debug_line`
+    pub offset: u64,
+    /// DWARF version of the program
+    pub version: u16,
+    /// size of the addresses, 0 if not stated by the header
+    pub address_size: u8,
+    pub minimum_instruction_length: u8,
+    pub maximum_operations_per_instruction: u8,
+    pub default_is_stmt: bool,
+    pub line_base: i8,
+    pub line_range: u8,
+    pub opcode_base: u8,
+    /// number of operands of each standard opcode
+    pub standard_opcode_lengths: Vec<u8>,
+    /// include directories, the first one is the compilation directory since DWARF 5
+    pub directories: Vec<&'a str>,
+    /// source files, indexed from 0 since DWARF 5 and from 1 before
+    pub files: Vec<FileEntry<'a>>,
+}
+
+impl LineProgramHeader<'_> {
+    /// Path of a file as referenced by the `file` register
+    ///
+    /// Before DWARF 5 the directory 0 is the compilation directory, which
+    /// is only known by `.debug_info`, so these paths stay relative.
+    pub fn file_path(&self, index: u64) -> Option<String> {
+        let index = if self.version >= 5 {
+            index
+        } else {
+            index.checked_sub(1)?
+        };
+        let file = self.files.get(usize::try_from(index).ok()?)?;
+        if file.name.starts_with('/') {
+            return Some(file.name.to_string());
+        }
+        let directory = match file.directory {
+            0 if self.version < 5 => None,
+            index if self.version < 5 => self.directories.get(index as usize - 1),
+            index => self.directories.get(index as usize),
+        };
+        Some(match directory {
+            Some(directory) if !directory.is_empty() => format!("{}/{}", directory, file.name),
+            _ => file.name.to_string(),
+        })
+    }
+}
+
+/// A row of the line number matrix
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LineRow {
+    pub address: u64,
+    pub file: u64,
+    pub line: u64,
+    pub column: u64,
+    pub is_stmt: bool,
+    /// the row marks the first address after the end of a sequence
+    pub end_sequence: bool,
+}
+
+/// The decoded line number program of a compilation unit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineProgram<'a> {
+    pub header: LineProgramHeader<'a>,
+    /// rows in program order, each sequence ends with an `end_sequence` row
+    pub rows: Vec<LineRow>,
+}
+
+/// Source location of an address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    /// path of the source file
+    pub file: String,
+    /// line number, starting at 1, 0 when unknown
+    pub line: u64,
+    /// column number, starting at 1, 0 when unknown
+    pub column: u64,
+}
+
+impl fmt::Display for Location {
+    /// Formatted like `addr2line`, `file:line`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+impl LineProgram<'_> {
+    /// The row covering `address`, found from the sequences of the program
+    pub fn row(&self, address: u64) -> Option<&LineRow> {
+        self.rows.windows(2).find_map(|rows| {
+            let (row, next) = (&rows[0], &rows[1]);
+            (!row.end_sequence && row.address <= address && address < next.address).then_some(row)
+        })
+    }
+
+    /// Source location of `address`, if covered by this program
+    pub fn location(&self, address: u64) -> Option<Location> {
+        let row = self.row(address)?;
+        Some(Location {
+            file: self
+                .header
+                .file_path(row.file)
+                .unwrap_or_else(|| "??".to_string()),
+            line: row.line,
+            column: row.column,
+        })
+    }
+}
+
+/// Strings sections referenced by the DWARF 5 entry formats
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StringSections<'a> {
+    /// `.debug_str`
+    pub debug_str: &'a [u8],
+    /// `.debug_line_str`
+    pub debug_line_str: &'a [u8],
+}
+
+/// The sections holding the line number programs, decompressed when the
+/// file stores them compressed
+#[derive(Debug, Default, Clone)]
+pub struct DebugLine<'a> {
+    /// `.debug_line`
+    pub debug_line: Cow<'a, [u8]>,
+    /// `.debug_str`, empty without it
+    pub debug_str: Cow<'a, [u8]>,
+    /// `.debug_line_str`, empty without it
+    pub debug_line_str: Cow<'a, [u8]>,
+    pub big_endian: bool,
+}
+
+impl DebugLine<'_> {
+    /// Decode the line number programs, see [`from_bytes`]
+    pub fn programs(&self) -> Result<Vec<LineProgram<'_>>> {
+        let strings = StringSections {
+            debug_str: &self.debug_str,
+            debug_line_str: &self.debug_line_str,
+        };
+        from_bytes(&self.debug_line, strings, self.big_endian)
+    }
+}
+
+/// Read one attribute of a DWARF 5 entry format, strings are returned as `Ok`
+fn entry_attribute<'a>(
+    reader: &mut Reader<'a>,
+    form: u64,
+    dwarf64: bool,
+    strings: &StringSections<'a>,
+) -> Option<core::result::Result<&'a str, u64>> {
+    Some(match form {
+        DW_FORM::STRING => Ok(reader.cstr()?),
+        DW_FORM::LINE_STRP => {
+            Ok(string_at(strings.debug_line_str, reader.offset(dwarf64)?).unwrap_or_default())
+        }
+        DW_FORM::STRP => {
+            Ok(string_at(strings.debug_str, reader.offset(dwarf64)?).unwrap_or_default())
+        }
+        // the string offsets table of the unit is only known by .debug_info
+        DW_FORM::STRX | DW_FORM::UDATA => Err(reader.uleb128()?),
+        DW_FORM::STRX1 | DW_FORM::DATA1 => Err(u64::from(reader.u8()?)),
+        DW_FORM::STRX2 | DW_FORM::DATA2 => Err(u64::from(reader.u16()?)),
+        DW_FORM::STRX3 => {
+            reader.skip(3)?;
+            Err(0)
+        }
+        DW_FORM::STRX4 | DW_FORM::DATA4 => Err(u64::from(reader.u32()?)),
+        DW_FORM::DATA8 => Err(reader.u64()?),
+        DW_FORM::DATA16 => {
+            reader.skip(16)?;
+            Err(0)
+        }
+        DW_FORM::BLOCK => {
+            let size = reader.uleb128()?;
+            reader.skip(size)?;
+            Err(0)
+        }
+        _ => return None,
+    })
+}
+
+/// Read the DWARF 5 list of directories or files of a header
+fn entries<'a>(
+    reader: &mut Reader<'a>,
+    dwarf64: bool,
+    strings: &StringSections<'a>,
+) -> Option<Vec<FileEntry<'a>>> {
+    let format_count = reader.u8()?;
+    let format = (0..format_count)
+        .map(|_| Some((reader.uleb128()?, reader.uleb128()?)))
+        .collect::<Option<Vec<_>>>()?;
+    let count = reader.uleb128()?;
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        let mut entry = FileEntry {
+            name: "",
+            directory: 0,
+        };
+        for (content, form) in &format {
+            match (*content, entry_attribute(reader, *form, dwarf64, strings)?) {
+                (DW_LNCT::PATH, Ok(name)) => entry.name = name,
+                (DW_LNCT::DIRECTORY_INDEX, Err(index)) => entry.directory = index,
+                _ => (),
+            }
+        }
+        entries.push(entry);
+    }
+    Some(entries)
+}
+
+/// Parse the header of a unit, the reader is left on the first opcode
+fn header<'a>(
+    reader: &mut Reader<'a>,
+    offset: u64,
+    dwarf64: bool,
+    strings: &StringSections<'a>,
+) -> Option<LineProgramHeader<'a>> {
+    let version = reader.u16()?;
+    let mut address_size = 0;
+    if version >= 5 {
+        address_size = reader.u8()?;
+        let _segment_selector_size = reader.u8()?;
+    }
+    let header_length = reader.offset(dwarf64)?;
+    let program = reader
+        .offset
+        .checked_add(usize::try_from(header_length).ok()?)?;
+    let minimum_instruction_length = reader.u8()?;
+    let maximum_operations_per_instruction = if version >= 4 { reader.u8()? } else { 1 };
+    let default_is_stmt = reader.u8()? != 0;
+    let line_base = reader.u8()? as i8;
+    let line_range = reader.u8()?;
+    let opcode_base = reader.u8()?;
+    let mut header = LineProgramHeader {
+        offset,
+        version,
+        address_size,
+        minimum_instruction_length,
+        maximum_operations_per_instruction,
+        default_is_stmt,
+        line_base,
+        line_range,
+        opcode_base,
+        standard_opcode_lengths: Vec::new(),
+        directories: Vec::new(),
+        files: Vec::new(),
+    };
+    for _ in 1..opcode_base {
+        header.standard_opcode_lengths.push(reader.u8()?);
+    }
+    if version >= 5 {
+        let directories = entries(reader, dwarf64, strings)?;
+        header.directories = directories.iter().map(|entry| entry.name).collect();
+        header.files = entries(reader, dwarf64, strings)?;
+    } else {
+        loop {
+            match reader.cstr()? {
+                "" => break,
+                directory => header.directories.push(directory),
+            }
+        }
+        loop {
+            match reader.cstr()? {
+                "" => break,
+                name => {
+                    let directory = reader.uleb128()?;
+                    let _mtime = reader.uleb128()?;
+                    let _length = reader.uleb128()?;
+                    header.files.push(FileEntry { name, directory });
+                }
+            }
+        }
+    }
+    reader.offset = program;
+    Some(header)
+}
+
+/// Run the line number program of a unit up to `end`
+fn run(
+    reader: &mut Reader<'_>,
+    end: usize,
+    header: &LineProgramHeader<'_>,
+) -> Option<Vec<LineRow>> {
+    let initial = LineRow {
+        file: 1,
+        line: 1,
+        is_stmt: header.default_is_stmt,
+        ..LineRow::default()
+    };
+    let line_range = header.line_range.max(1);
+    let advance = |state: &mut LineRow, operations: u64| {
+        let delta = operations.wrapping_mul(u64::from(header.minimum_instruction_length));
+        state.address = state.address.wrapping_add(delta);
+    };
+    let mut rows = Vec::new();
+    let mut state = initial;
+    while reader.offset < end {
+        let opcode = reader.u8()?;
+        if opcode >= header.opcode_base {
+            // special opcode, advance both the address and the line
+            let adjusted = opcode - header.opcode_base;
+            advance(&mut state, u64::from(adjusted / line_range));
+            let delta = i64::from(header.line_base) + i64::from(adjusted % line_range);
+            state.line = state.line.wrapping_add_signed(delta);
+            rows.push(state);
+            continue;
+        }
+        match opcode {
+            0 => {
+                let size = reader.uleb128()?;
+                let next = reader.offset.checked_add(usize::try_from(size).ok()?)?;
+                match reader.u8()? {
+                    DW_LNE::END_SEQUENCE => {
+                        state.end_sequence = true;
+                        rows.push(state);
+                        state = initial;
+                    }
+                    DW_LNE::SET_ADDRESS => {
+                        state.address = reader.address(next.saturating_sub(reader.offset))?;
+                    }
+                    _ => (),
+                }
+                reader.offset = next;
+            }
+            DW_LNS::COPY => rows.push(state),
+            DW_LNS::ADVANCE_PC => {
+                let operations = reader.uleb128()?;
+                advance(&mut state, operations);
+            }
+            DW_LNS::ADVANCE_LINE => {
+                let delta = reader.sleb128()?;
+                state.line = state.line.wrapping_add_signed(delta);
+            }
+            DW_LNS::SET_FILE => state.file = reader.uleb128()?,
+            DW_LNS::SET_COLUMN => state.column = reader.uleb128()?,
+            DW_LNS::NEGATE_STMT => state.is_stmt = !state.is_stmt,
+            DW_LNS::CONST_ADD_PC => {
+                let adjusted = 255 - header.opcode_base;
+                advance(&mut state, u64::from(adjusted / line_range));
+            }
+            DW_LNS::FIXED_ADVANCE_PC => {
+                let delta = reader.u16()?;
+                state.address = state.address.wrapping_add(u64::from(delta));
+            }
+            DW_LNS::SET_BASIC_BLOCK | DW_LNS::SET_PROLOGUE_END | DW_LNS::SET_EPILOGUE_BEGIN => (),
+            _ => {
+                // DW_LNS_set_isa and the unknown opcodes, skip their operands
+                let count = header.standard_opcode_lengths.get(opcode as usize - 1)?;
+                for _ in 0..*count {
+                    reader.uleb128()?;
+                }
+            }
+        }
+    }
+    Some(rows)
+}
+
+/// Decode every line number program of a `.debug_line` section
+///
+/// Fails if a unit is truncated or has an unknown version.
+pub fn from_bytes<'a>(
+    debug_line: &'a [u8],
+    strings: StringSections<'a>,
+    big_endian: bool,
+) -> Result<Vec<LineProgram<'a>>> {
+    let mut reader = Reader::new(debug_line, big_endian);
+    let mut programs = Vec::new();
+    while reader.offset < debug_line.len() {
+        let offset = reader.offset as u64;
+        let truncated = Error::TruncatedDwarf {
+            section: ".debug_line",
+            offset,
+        };
+        let (length, dwarf64) = match reader.u32().ok_or(truncated.clone())? {
+            0xffff_ffff => (reader.u64().ok_or(truncated.clone())?, true),
+            length => (u64::from(length), false),
+        };
+        let end = usize::try_from(length)
+            .ok()
+            .and_then(|length| reader.offset.checked_add(length))
+            .filter(|end| *end <= debug_line.len())
+            .ok_or(truncated.clone())?;
+        let version = read_u16(debug_line, reader.offset, big_endian).unwrap_or_default();
+        if !(2..=5).contains(&version) {
+            return Err(Error::InvalidHeaderField {
+                field: "version",
+                expected: 5,
+                found: version as usize,
+            });
+        }
+        let header = header(&mut reader, offset, dwarf64, &strings).ok_or(truncated.clone())?;
+        let rows = run(&mut reader, end, &header).ok_or(truncated)?;
+        programs.push(LineProgram { header, rows });
+        reader.offset = end;
+    }
+    Ok(programs)
+}
+
+/// Source location of `address` in the first program covering it
+pub fn find(programs: &[LineProgram<'_>], address: u64) -> Option<Location> {
+    programs
+        .iter()
+        .find_map(|program| program.location(address))
+}
@@ -0,0 +1,48 @@
+//! MiniDebugInfo, the symbols kept in `.gnu_debugdata`
+//!
+//! Fedora and RHEL strip their binaries but keep the names of the local
+//! functions for the backtraces: `.gnu_debugdata` holds an xz compressed
+//! ELF file whose `.symtab` lists them, without the symbols already in
+//! `.dynsym`. The section is decoded with the `xz` feature.
+//! Sources:
+//! * <https://sourceware.org/gdb/current/onlinedocs/gdb.html/MiniDebugInfo.html>
+use crate::alloc_prelude::*;
+use crate::{Error, Result};
+
+use super::section::{self, SHT};
+use super::x64::x64;
+
+/// Name of the section holding the MiniDebugInfo
+pub const SECTION: &str = ".gnu_debugdata";
+
+/// Decode the ELF file embedded in `.gnu_debugdata`, None if the file has
+/// no such section
+///
+/// Fails if the section lies outside of the file or can't be decoded, and
+/// with [`Error::UnsupportedCompression`] without the `xz` feature.
+pub fn from_bytes(data: &[u8], header: &x64) -> Result<Option<Vec<u8>>> {
+    let names = section::names(data, header)?;
+    let shdr = match section::from_bytes(data, header)?.find(|shdr| {
+        shdr.sh_type != SHT::NOBITS && shdr.name(&names).is_ok_and(|name| name == SECTION)
+    }) {
+        Some(shdr) => shdr,
+        None => return Ok(None),
+    };
+    let bytes = shdr.data(data).ok_or(Error::OutOfBounds {
+        offset: shdr.sh_offset,
+        size: shdr.sh_size,
+    })?;
+    decode(bytes).map(Some)
+}
+
+#[cfg(feature = "xz")]
+fn decode(bytes: &[u8]) -> Result<Vec<u8>> {
+    // a symbol table compresses well, but not without bounds
+    crate::utils::xz::decompress(bytes, bytes.len().saturating_mul(64))
+}
+
+/// Without the decoder, LZMA2 is the filter that isn't supported
+#[cfg(not(feature = "xz"))]
+fn decode(_bytes: &[u8]) -> Result<Vec<u8>> {
+    Err(Error::UnsupportedCompression(0x21))
+}
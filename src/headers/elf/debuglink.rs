@@ -0,0 +1,186 @@
+//! Separate debug files of stripped binaries
+//!
+//! Distributions ship the debug information of their binaries in files of
+//! their own, found with two GNU conventions: the NT_GNU_BUILD_ID note
+//! names `/usr/lib/debug/.build-id/xx/yyyy.debug`, and the `.gnu_debuglink`
+//! section holds the name of the file and the CRC-32 of its content. The
+//! lookup in the file system is only available with the `std` feature.
+use crate::utils::read_u32;
+use crate::{Error, Result};
+use core::fmt;
+
+use super::data::Data;
+use super::section;
+use super::x64::x64;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+
+/// Directory of the debug files of the system
+pub const DEBUG_DIRECTORY: &str = "/usr/lib/debug";
+
+/// Content of the `.gnu_debuglink` section
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebugLink<'a> {
+    /// name of the debug file, without directory
+    pub file: &'a str,
+    /// CRC-32 of the whole debug file
+    pub crc: u32,
+}
+
+impl fmt::Display for DebugLink<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (crc {:#010x})", self.file, self.crc)
+    }
+}
+
+/// Parse the `.gnu_debuglink` section, None if the file has none
+///
+/// The section holds the nul-terminated file name, padded to 4 bytes, and
+/// the CRC-32 in the byte order of the file.
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64) -> Result<Option<DebugLink<'a>>> {
+    let names = section::names(data, header)?;
+    let shdr = match section::from_bytes(data, header)?.find(|shdr| {
+        shdr.sh_type != section::SHT::NOBITS
+            && shdr.name(&names).is_ok_and(|name| name == ".gnu_debuglink")
+    }) {
+        Some(shdr) => shdr,
+        None => return Ok(None),
+    };
+    let bytes = shdr.data(data).ok_or(Error::OutOfBounds {
+        offset: shdr.sh_offset,
+        size: shdr.sh_size,
+    })?;
+    let end = bytes
+        .iter()
+        .position(|byte| *byte == 0)
+        .ok_or(Error::UnterminatedString(0))?;
+    let file = core::str::from_utf8(&bytes[..end]).map_err(|_| Error::InvalidUtf8(0))?;
+    let at = (end + 1).next_multiple_of(4);
+    let crc =
+        read_u32(bytes, at, Data::is_big_endian(&header.e_ident)).ok_or(Error::OutOfBounds {
+            offset: shdr.sh_offset.wrapping_add(at as u64),
+            size: 4,
+        })?;
+    Ok(Some(DebugLink { file, crc }))
+}
+
+/// CRC-32 lookup table of the reflected 0xedb88320 polynomial
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut crc = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = match crc & 1 {
+                1 => 0xedb8_8320 ^ (crc >> 1),
+                _ => crc >> 1,
+            };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+};
+
+/// The CRC-32 of `.gnu_debuglink`, the same as zlib and gzip
+///
+/// ```
+/// use readbin::headers::elf::debuglink::crc32;
+///
+/// assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+/// ```
+pub fn crc32(data: &[u8]) -> u32 {
+    !crc32_update(!0, data)
+}
+
+/// Continue a CRC-32 with more bytes, without the final inversion
+pub(crate) fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    data.iter().fold(crc, |crc, byte| {
+        CRC_TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Path of the debug file named after a build ID in `debug_dir`
+///
+/// The first byte of the ID names a directory of `.build-id` and the
+/// others the file, as in `.build-id/ab/cdef0123.debug`. None for IDs
+/// shorter than 2 bytes.
+#[cfg(feature = "std")]
+pub fn build_id_path(debug_dir: &Path, build_id: &[u8]) -> Option<PathBuf> {
+    let (first, rest) = match build_id {
+        [first, rest @ ..] if !rest.is_empty() => (first, rest),
+        _ => return None,
+    };
+    let name: String = rest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    Some(
+        debug_dir
+            .join(".build-id")
+            .join(format!("{:02x}", first))
+            .join(format!("{}.debug", name)),
+    )
+}
+
+/// A separate debug file found for a binary
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct DebugFile {
+    /// where the file was found
+    pub path: PathBuf,
+    /// its whole content
+    pub data: Vec<u8>,
+}
+
+/// Find the debug file of the binary read from `path`
+///
+/// Like GDB, the build ID is tried first, then the `.gnu_debuglink` name
+/// in the directory of the binary, in its `.debug` subdirectory and under
+/// `debug_dir` followed by the directory of the binary. A file found with
+/// the build ID must have the same ID, one found with the debug link must
+/// match its CRC. None if no candidate matches.
+#[cfg(feature = "std")]
+pub fn find(path: &Path, data: &[u8], header: &x64, debug_dir: &Path) -> Option<DebugFile> {
+    use super::notes;
+    use std::fs;
+
+    let binary = fs::canonicalize(path).ok();
+    let found = |path: PathBuf, check: &dyn Fn(&[u8]) -> bool| {
+        // a debug link may name the binary itself
+        if binary.is_some() && fs::canonicalize(&path).ok() == binary {
+            return None;
+        }
+        let data = fs::read(&path).ok()?;
+        check(&data).then_some(DebugFile { path, data })
+    };
+
+    if let Some(build_id) = notes::build_id(data, header) {
+        let same_id = |candidate: &[u8]| {
+            super::Elf::parse(candidate)
+                .ok()
+                .and_then(|elf| elf.build_id())
+                == Some(build_id)
+        };
+        if let Some(file) =
+            build_id_path(debug_dir, build_id).and_then(|path| found(path, &same_id))
+        {
+            return Some(file);
+        }
+    }
+
+    let link = from_bytes(data, header).ok().flatten()?;
+    let same_crc = |candidate: &[u8]| crc32(candidate) == link.crc;
+    let dir = binary
+        .as_deref()
+        .and_then(Path::parent)
+        .map(Path::to_path_buf)
+        .or_else(|| path.parent().map(Path::to_path_buf))?;
+    let relative = dir.strip_prefix("/").unwrap_or(&dir);
+    [
+        dir.join(link.file),
+        dir.join(".debug").join(link.file),
+        debug_dir.join(relative).join(link.file),
+    ]
+    .into_iter()
+    .find_map(|path| found(path, &same_crc))
+}
@@ -0,0 +1,949 @@
+//! A YAML description of an ELF64 file and the file built back from it
+//!
+//! [`describe`] writes the file header, the program headers and the
+//! section headers field by field, the content of the sections in
+//! hexadecimal and the other bytes of the file as `Fill` entries, so
+//! [`build`] gives back the same bytes. Hand written descriptions can
+//! leave most fields out, like the ones of `yaml2obj`: the offsets, the
+//! sizes, the counts and the section names table are then computed and
+//! the other fields are 0.
+//!
+//! Constants are written with their `ET_`, `EM_`, `PT_`, `PF_`, `SHT_`
+//! and `SHF_` names, flags as a list of names, and numbers are read in
+//! decimal or with a `0x` prefix:
+//!
+//! ```yaml
+//! FileHeader:
+//!   Class: ELFCLASS64
+//!   Data: ELFDATA2LSB
+//!   Type: ET_EXEC
+//!   Machine: EM_X86_64
+//!   Entry: 0x401000
+//! ProgramHeaders:
+//!   - Type: PT_LOAD
+//!     Flags: [PF_R, PF_X]
+//!     FirstSection: .text
+//!     LastSection: .text
+//!     Align: 0x1000
+//! Sections:
+//!   - Name: .text
+//!     Type: SHT_PROGBITS
+//!     Flags: [SHF_ALLOC, SHF_EXECINSTR]
+//!     Address: 0x401000
+//!     AddressAlign: 0x10
+//!     Content: b83c0000000f05
+//! ```
+use crate::alloc_prelude::*;
+use crate::utils::yaml::Yaml;
+use crate::utils::ToBytes;
+use crate::{Error, Result};
+
+use super::identification::Indent;
+use super::program::{self, Elf64_Phdr, PF, PT};
+use super::section::{self, Elf64_Shdr, SHF, SHN, SHT};
+use super::x64::x64;
+
+/// Names of the constants of the description
+type Names = [(&'static str, u64)];
+
+const CLASS_NAMES: [(&str, u64); 2] = [("ELFCLASS32", 1), ("ELFCLASS64", 2)];
+
+const DATA_NAMES: [(&str, u64); 2] = [("ELFDATA2LSB", 1), ("ELFDATA2MSB", 2)];
+
+const ET_NAMES: [(&str, u64); 5] = [
+    ("ET_NONE", 0),
+    ("ET_REL", 1),
+    ("ET_EXEC", 2),
+    ("ET_DYN", 3),
+    ("ET_CORE", 4),
+];
+
+const EM_NAMES: [(&str, u64); 13] = [
+    ("EM_NONE", 0),
+    ("EM_386", 3),
+    ("EM_MIPS", 8),
+    ("EM_PPC", 20),
+    ("EM_PPC64", 21),
+    ("EM_S390", 22),
+    ("EM_ARM", 40),
+    ("EM_SPARCV9", 43),
+    ("EM_X86_64", 62),
+    ("EM_AARCH64", 183),
+    ("EM_RISCV", 243),
+    ("EM_BPF", 247),
+    ("EM_LOONGARCH", 258),
+];
+
+const PT_NAMES: [(&str, u64); 13] = [
+    ("PT_NULL", PT::NULL as u64),
+    ("PT_LOAD", PT::LOAD as u64),
+    ("PT_DYNAMIC", PT::DYNAMIC as u64),
+    ("PT_INTERP", PT::INTERP as u64),
+    ("PT_NOTE", PT::NOTE as u64),
+    ("PT_SHLIB", PT::SHLIB as u64),
+    ("PT_PHDR", PT::PHDR as u64),
+    ("PT_TLS", PT::TLS as u64),
+    ("PT_GNU_EH_FRAME", PT::GNU_EH_FRAME as u64),
+    ("PT_GNU_STACK", PT::GNU_STACK as u64),
+    ("PT_GNU_RELRO", PT::GNU_RELRO as u64),
+    ("PT_GNU_PROPERTY", PT::GNU_PROPERTY as u64),
+    ("PT_GNU_SFRAME", PT::GNU_SFRAME as u64),
+];
+
+const PF_NAMES: [(&str, u64); 3] = [
+    ("PF_X", PF::X as u64),
+    ("PF_W", PF::W as u64),
+    ("PF_R", PF::R as u64),
+];
+
+const SHT_NAMES: [(&str, u64); 25] = [
+    ("SHT_NULL", SHT::NULL as u64),
+    ("SHT_PROGBITS", SHT::PROGBITS as u64),
+    ("SHT_SYMTAB", SHT::SYMTAB as u64),
+    ("SHT_STRTAB", SHT::STRTAB as u64),
+    ("SHT_RELA", SHT::RELA as u64),
+    ("SHT_HASH", SHT::HASH as u64),
+    ("SHT_DYNAMIC", SHT::DYNAMIC as u64),
+    ("SHT_NOTE", SHT::NOTE as u64),
+    ("SHT_NOBITS", SHT::NOBITS as u64),
+    ("SHT_REL", SHT::REL as u64),
+    ("SHT_SHLIB", SHT::SHLIB as u64),
+    ("SHT_DYNSYM", SHT::DYNSYM as u64),
+    ("SHT_INIT_ARRAY", SHT::INIT_ARRAY as u64),
+    ("SHT_FINI_ARRAY", SHT::FINI_ARRAY as u64),
+    ("SHT_PREINIT_ARRAY", SHT::PREINIT_ARRAY as u64),
+    ("SHT_GROUP", SHT::GROUP as u64),
+    ("SHT_SYMTAB_SHNDX", SHT::SYMTAB_SHNDX as u64),
+    ("SHT_RELR", SHT::RELR as u64),
+    ("SHT_GNU_ATTRIBUTES", SHT::GNU_ATTRIBUTES as u64),
+    ("SHT_GNU_HASH", SHT::GNU_HASH as u64),
+    ("SHT_GNU_LIBLIST", SHT::GNU_LIBLIST as u64),
+    ("SHT_CHECKSUM", SHT::CHECKSUM as u64),
+    ("SHT_GNU_verdef", SHT::GNU_VERDEF as u64),
+    ("SHT_GNU_verneed", SHT::GNU_VERNEED as u64),
+    ("SHT_GNU_versym", SHT::GNU_VERSYM as u64),
+];
+
+const SHF_NAMES: [(&str, u64); 12] = [
+    ("SHF_WRITE", SHF::WRITE),
+    ("SHF_ALLOC", SHF::ALLOC),
+    ("SHF_EXECINSTR", SHF::EXECINSTR),
+    ("SHF_MERGE", SHF::MERGE),
+    ("SHF_STRINGS", SHF::STRINGS),
+    ("SHF_INFO_LINK", SHF::INFO_LINK),
+    ("SHF_LINK_ORDER", SHF::LINK_ORDER),
+    ("SHF_OS_NONCONFORMING", SHF::OS_NONCONFORMING),
+    ("SHF_GROUP", SHF::GROUP),
+    ("SHF_TLS", SHF::TLS),
+    ("SHF_COMPRESSED", SHF::COMPRESSED),
+    ("SHF_EXCLUDE", SHF::EXCLUDE),
+];
+
+/// Name of the section names table added to the descriptions without one
+const SHSTRTAB: &str = ".shstrtab";
+
+pub(super) fn align_up(value: u64, align: u64) -> u64 {
+    match align {
+        0 | 1 => value,
+        align => value.div_ceil(align).saturating_mul(align),
+    }
+}
+
+/// Pad `out` with zeros to `len` bytes, failing instead of aborting when
+/// `len` is above `limit` or can't be allocated
+///
+/// The offsets and alignments read from a damaged file can ask for
+/// terabytes, `limit` is derived from the size of the input.
+pub(super) fn grow(out: &mut Vec<u8>, len: u64, limit: u64) -> Result<()> {
+    let current = out.len() as u64;
+    if len <= current {
+        return Ok(());
+    }
+    let error = Error::OutOfBounds {
+        offset: current,
+        size: len - current,
+    };
+    if len > limit {
+        return Err(error);
+    }
+    let len = usize::try_from(len).map_err(|_| error.clone())?;
+    out.try_reserve_exact(len - out.len()).map_err(|_| error)?;
+    out.resize(len, 0);
+    Ok(())
+}
+
+pub(super) fn hex(value: u64) -> Yaml {
+    Yaml::from(format!("{:#x}", value))
+}
+
+/// The name of a constant, the number itself if it has none
+fn named(value: u64, names: &Names) -> Yaml {
+    match names.iter().find(|(_, known)| *known == value) {
+        Some((name, _)) => Yaml::from(*name),
+        None => hex(value),
+    }
+}
+
+/// The names of the bits of a flags field, a number if one has no name
+fn flags(value: u64, names: &Names) -> Yaml {
+    let known = names.iter().fold(0, |mask, (_, bit)| mask | bit);
+    if value & !known != 0 {
+        return hex(value);
+    }
+    Yaml::list(
+        names
+            .iter()
+            .filter(|(_, bit)| value & bit != 0)
+            .map(|(name, _)| Yaml::from(*name)),
+    )
+}
+
+pub(super) fn encode(bytes: &[u8]) -> Yaml {
+    let mut text = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        text.push_str(&format!("{:02x}", byte));
+    }
+    Yaml::from(text)
+}
+
+/// The nul terminated name at `offset` of a string table
+fn name_at(table: &[u8], offset: u32) -> Option<&str> {
+    let bytes = table.get(offset as usize..)?;
+    let end = bytes.iter().position(|byte| *byte == 0)?;
+    core::str::from_utf8(&bytes[..end]).ok()
+}
+
+/// Offset of the first nul terminated occurrence of `name` in a string table
+fn find_name(table: &[u8], name: &str) -> Option<u32> {
+    let name = name.as_bytes();
+    table
+        .windows(name.len() + 1)
+        .position(|window| &window[..name.len()] == name && window[name.len()] == 0)
+        .map(|offset| offset as u32)
+}
+
+/// Describe every field and every byte of the file
+///
+/// Fails if the program or section header table can't be parsed.
+pub fn describe(data: &[u8], header: &x64) -> Result<Yaml> {
+    let phdrs: Vec<Elf64_Phdr> = program::from_bytes(data, header)?
+        .map(|phdr| *phdr)
+        .collect();
+    let shdrs: Vec<Elf64_Shdr> = section::from_bytes(data, header)?
+        .map(|shdr| *shdr)
+        .collect();
+    let shstrndx = header.shstrndx(data);
+    let table = shdrs
+        .get(shstrndx as usize)
+        .filter(|shdr| shdr.sh_type != SHT::NOBITS)
+        .and_then(|shdr| shdr.data(data))
+        .unwrap_or_default();
+
+    // the bytes described by the headers and the sections, the others are filled
+    let mut covered = vec![(0, x64::SIZE as u64)];
+    for index in 0..phdrs.len() as u64 {
+        let offset = header.e_phoff + index * header.e_phentsize as u64;
+        covered.push((offset, Elf64_Phdr::SIZE as u64));
+    }
+    for index in 0..shdrs.len() as u64 {
+        let offset = header.e_shoff + index * header.e_shentsize as u64;
+        covered.push((offset, Elf64_Shdr::SIZE as u64));
+    }
+
+    let mut sections = Vec::new();
+    let mut names = Vec::new();
+    let mut end = (x64::SIZE as u64).max(
+        header
+            .e_phoff
+            .saturating_add(phdrs.len() as u64 * header.e_phentsize as u64),
+    );
+    for (index, shdr) in shdrs.iter().enumerate() {
+        let name = name_at(table, shdr.sh_name).filter(|name| {
+            find_name(table, name) == Some(shdr.sh_name) || shdr.sh_name == 0 && name.is_empty()
+        });
+        names.push(name.unwrap_or_default());
+        let content = match shdr.sh_type {
+            SHT::NOBITS => None,
+            _ => shdr.data(data),
+        };
+        let mut fields = Vec::new();
+        match name {
+            Some("") => {}
+            Some(name) => fields.push(("Name", Yaml::from(name))),
+            None => match name_at(table, shdr.sh_name) {
+                Some(name) if !name.is_empty() => fields.push(("Name", Yaml::from(name))),
+                _ => {}
+            },
+        }
+        fields.push(("Type", named(shdr.sh_type as u64, &SHT_NAMES)));
+        if shdr.sh_flags != 0 {
+            fields.push(("Flags", flags(shdr.sh_flags, &SHF_NAMES)));
+        }
+        if shdr.sh_addr != 0 {
+            fields.push(("Address", hex(shdr.sh_addr)));
+        }
+        if index != 0 || shdr.sh_offset != 0 {
+            fields.push(("Offset", hex(shdr.sh_offset)));
+        }
+        if content.map_or(0, <[u8]>::len) as u64 != shdr.sh_size {
+            fields.push(("Size", hex(shdr.sh_size)));
+        }
+        if shdr.sh_link != 0 {
+            fields.push(("Link", Yaml::from(shdr.sh_link.to_string())));
+        }
+        if shdr.sh_info != 0 {
+            fields.push(("Info", Yaml::from(shdr.sh_info.to_string())));
+        }
+        if shdr.sh_addralign != 0 {
+            fields.push(("AddressAlign", hex(shdr.sh_addralign)));
+        }
+        if shdr.sh_entsize != 0 {
+            fields.push(("EntSize", hex(shdr.sh_entsize)));
+        }
+        let found = match names[index] {
+            "" => table.iter().position(|byte| *byte == 0).unwrap_or(0) as u32,
+            name => find_name(table, name).unwrap_or(0),
+        };
+        if found != shdr.sh_name {
+            fields.push(("NameOffset", hex(shdr.sh_name as u64)));
+        }
+        if let Some(content) = content.filter(|content| !content.is_empty()) {
+            fields.push(("Content", encode(content)));
+            covered.push((shdr.sh_offset, shdr.sh_size));
+            end = end.max(shdr.sh_offset + shdr.sh_size);
+        }
+        sections.push(Yaml::map(fields));
+    }
+
+    let file_size = data.len() as u64;
+    covered.sort_unstable();
+    let mut fills = Vec::new();
+    let mut at = 0;
+    let fill = |start: u64, stop: u64, fills: &mut Vec<Yaml>| {
+        let bytes = &data[start as usize..stop as usize];
+        // the zeros at the end of the file are kept to keep its size
+        if stop == file_size || bytes.iter().any(|byte| *byte != 0) {
+            fills.push(Yaml::map([
+                ("Offset", hex(start)),
+                ("Content", encode(bytes)),
+            ]));
+        }
+    };
+    for (offset, size) in covered {
+        let offset = offset.min(file_size);
+        if offset > at {
+            fill(at, offset, &mut fills);
+        }
+        at = at.max(offset.saturating_add(size).min(file_size));
+    }
+    if at < file_size {
+        fill(at, file_size, &mut fills);
+    }
+    for entry in &fills {
+        let offset = entry.get("Offset").and_then(Yaml::as_str).and_then(number);
+        let size = entry
+            .get("Content")
+            .and_then(Yaml::as_str)
+            .map_or(0, str::len)
+            / 2;
+        end = end.max(offset.unwrap_or(0) + size as u64);
+    }
+
+    // the fields whose value isn't the one build computes
+    let ident = &header.e_ident;
+    let mut fields = vec![
+        ("Class", named(ident[Indent::CLASS] as u64, &CLASS_NAMES)),
+        ("Data", named(ident[Indent::DATA] as u64, &DATA_NAMES)),
+    ];
+    if ident[Indent::VERSION] != 1 {
+        fields.push(("IdentVersion", hex(ident[Indent::VERSION] as u64)));
+    }
+    if ident[Indent::OSABIT] != 0 {
+        fields.push(("OSABI", hex(ident[Indent::OSABIT] as u64)));
+    }
+    if ident[Indent::ABIVERSION] != 0 {
+        fields.push(("ABIVersion", hex(ident[Indent::ABIVERSION] as u64)));
+    }
+    if ident[Indent::ABIVERSION + 1..]
+        .iter()
+        .any(|byte| *byte != 0)
+    {
+        fields.push(("Padding", encode(&ident[Indent::ABIVERSION + 1..])));
+    }
+    fields.push(("Type", named(header.e_type as u64, &ET_NAMES)));
+    fields.push(("Machine", named(header.e_machine as u64, &EM_NAMES)));
+    if header.e_version != 1 {
+        fields.push(("Version", hex(header.e_version as u64)));
+    }
+    fields.push(("Entry", hex(header.e_entry)));
+    if header.e_flags != 0 {
+        fields.push(("Flags", hex(header.e_flags as u64)));
+    }
+    let defaults = Defaults {
+        phnum: phdrs.len(),
+        shnum: shdrs.len(),
+    };
+    if header.e_phoff != defaults.phoff() {
+        fields.push(("PhOff", hex(header.e_phoff)));
+    }
+    if !shdrs.is_empty() && header.e_shoff != align_up(end, 8)
+        || shdrs.is_empty() && header.e_shoff != 0
+    {
+        fields.push(("ShOff", hex(header.e_shoff)));
+    }
+    if header.e_ehsize as usize != x64::SIZE {
+        fields.push(("EhSize", hex(header.e_ehsize as u64)));
+    }
+    if header.e_phentsize != defaults.phentsize() {
+        fields.push(("PhEntSize", hex(header.e_phentsize as u64)));
+    }
+    if header.e_shentsize != defaults.shentsize() {
+        fields.push(("ShEntSize", hex(header.e_shentsize as u64)));
+    }
+    if header.e_phnum != defaults.e_phnum() {
+        fields.push(("PhNum", hex(header.e_phnum as u64)));
+    }
+    if header.e_shnum != defaults.e_shnum() {
+        fields.push(("ShNum", hex(header.e_shnum as u64)));
+    }
+    let named_table = names.iter().position(|name| *name == SHSTRTAB).unwrap_or(0) as u32;
+    if shstrndx != named_table || header.e_shstrndx != defaults.e_shstrndx(shstrndx) {
+        fields.push(("ShStrNdx", Yaml::from(header.e_shstrndx.to_string())));
+    }
+
+    let segments = phdrs.iter().map(|phdr| {
+        Yaml::map([
+            ("Type", named(phdr.p_type as u64, &PT_NAMES)),
+            ("Flags", flags(phdr.p_flags as u64, &PF_NAMES)),
+            ("Offset", hex(phdr.p_offset)),
+            ("VAddr", hex(phdr.p_vaddr)),
+            ("PAddr", hex(phdr.p_paddr)),
+            ("FileSize", hex(phdr.p_filesz)),
+            ("MemSize", hex(phdr.p_memsz)),
+            ("Align", hex(phdr.p_align)),
+        ])
+    });
+    let mut document = vec![
+        ("FileHeader", Yaml::map(fields)),
+        ("ProgramHeaders", Yaml::list(segments)),
+        ("Sections", Yaml::List(sections)),
+    ];
+    if !fills.is_empty() {
+        document.push(("Fill", Yaml::List(fills)));
+    }
+    Ok(Yaml::map(document))
+}
+
+/// The header fields computed by [`build`] from the number of entries
+struct Defaults {
+    phnum: usize,
+    shnum: usize,
+}
+
+impl Defaults {
+    fn phoff(&self) -> u64 {
+        if self.phnum > 0 {
+            x64::SIZE as u64
+        } else {
+            0
+        }
+    }
+
+    fn phentsize(&self) -> u16 {
+        if self.phnum > 0 {
+            Elf64_Phdr::SIZE as u16
+        } else {
+            0
+        }
+    }
+
+    fn shentsize(&self) -> u16 {
+        if self.shnum > 0 {
+            Elf64_Shdr::SIZE as u16
+        } else {
+            0
+        }
+    }
+
+    fn e_phnum(&self) -> u16 {
+        u16::try_from(self.phnum)
+            .ok()
+            .filter(|phnum| *phnum < x64::PN_XNUM)
+            .unwrap_or(x64::PN_XNUM)
+    }
+
+    fn e_shnum(&self) -> u16 {
+        u16::try_from(self.shnum)
+            .ok()
+            .filter(|shnum| *shnum < SHN::LORESERVE)
+            .unwrap_or(0)
+    }
+
+    fn e_shstrndx(&self, index: u32) -> u16 {
+        u16::try_from(index)
+            .ok()
+            .filter(|index| *index < SHN::LORESERVE)
+            .unwrap_or(SHN::XINDEX)
+    }
+}
+
+/// A number in decimal or with a `0x` prefix
+fn number(text: &str) -> Option<u64> {
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+/// A number, a constant name, or the names and numbers of a list or
+/// separated by `|` ORed together
+fn value(yaml: &Yaml, names: &Names) -> Option<u64> {
+    let one = |text: &str| {
+        let text = text.trim();
+        names
+            .iter()
+            .find(|(name, _)| *name == text)
+            .map(|(_, value)| *value)
+            .or_else(|| number(text))
+    };
+    match yaml {
+        Yaml::Scalar(text) => text
+            .split('|')
+            .try_fold(0, |flags, part| Some(flags | one(part)?)),
+        Yaml::List(values) => values
+            .iter()
+            .try_fold(0, |flags, item| Some(flags | value(item, names)?)),
+        Yaml::Map(_) => None,
+    }
+}
+
+fn decode(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = text
+        .bytes()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+    if !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let pair = core::str::from_utf8(pair).ok()?;
+            u8::from_str_radix(pair, 16).ok()
+        })
+        .collect()
+}
+
+/// A mapping of the description, with its path for the error messages
+struct Entry<'a> {
+    path: String,
+    yaml: Option<&'a Yaml>,
+}
+
+impl<'a> Entry<'a> {
+    fn new(path: String, yaml: Option<&'a Yaml>) -> Result<Self> {
+        match yaml {
+            None | Some(Yaml::Map(_)) => Ok(Entry { path, yaml }),
+            Some(Yaml::Scalar(text)) if text.is_empty() => Ok(Entry { path, yaml: None }),
+            Some(_) => Err(Error::InvalidDescription(format!(
+                "{} is not a mapping",
+                path
+            ))),
+        }
+    }
+
+    fn error(&self, key: &str, reason: &str) -> Error {
+        match self.path.is_empty() {
+            true => Error::InvalidDescription(format!("{}: {}", key, reason)),
+            false => Error::InvalidDescription(format!("{}.{}: {}", self.path, key, reason)),
+        }
+    }
+
+    /// The value of a field, None if it's missing or empty
+    fn get(&self, key: &str) -> Option<&'a Yaml> {
+        self.yaml?
+            .get(key)
+            .filter(|value| value.as_str() != Some(""))
+    }
+
+    fn child(&self, key: &str) -> Result<Entry<'a>> {
+        let path = match self.path.is_empty() {
+            true => key.to_string(),
+            false => format!("{}.{}", self.path, key),
+        };
+        Entry::new(path, self.get(key))
+    }
+
+    /// The mappings of a list field
+    fn list(&self, key: &str) -> Result<Vec<Entry<'a>>> {
+        let values = match self.get(key) {
+            None => return Ok(Vec::new()),
+            Some(Yaml::List(values)) => values,
+            Some(_) => return Err(self.error(key, "expected a list")),
+        };
+        values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| Entry::new(format!("{}[{}]", key, index), Some(value)))
+            .collect()
+    }
+
+    fn text(&self, key: &str) -> Result<Option<&'a str>> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(value) => value
+                .as_str()
+                .map(Some)
+                .ok_or_else(|| self.error(key, "expected a string")),
+        }
+    }
+
+    fn number<T: TryFrom<u64>>(&self, key: &str, names: &Names) -> Result<Option<T>> {
+        let yaml = match self.get(key) {
+            None => return Ok(None),
+            Some(yaml) => yaml,
+        };
+        value(yaml, names)
+            .and_then(|value| T::try_from(value).ok())
+            .map(Some)
+            .ok_or_else(|| self.error(key, "invalid or too large value"))
+    }
+
+    fn bytes(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match self.text(key)? {
+            None => Ok(None),
+            Some(text) => decode(text)
+                .map(Some)
+                .ok_or_else(|| self.error(key, "expected hexadecimal bytes")),
+        }
+    }
+}
+
+/// A section being built
+struct Section {
+    name: Option<String>,
+    shdr: Elf64_Shdr,
+    content: Option<Vec<u8>>,
+    offset: Option<u64>,
+    size: Option<u64>,
+    name_offset: Option<u32>,
+}
+
+/// Build the file described by a document written by [`describe`] or by hand
+///
+/// A null section is added in front of the sections if the first one
+/// isn't SHT_NULL, a `.shstrtab` section after them if none has this
+/// name and ShStrNdx isn't given, and the section names table is filled
+/// with the names when it has no Content, Offset or Size. A ShStrNdx of
+/// 65535, SHN_XINDEX, is kept in the file header and the index is the Link
+/// of the first section, as in the file. The sections without Offset are
+/// laid out after the headers in order, aligned on their AddressAlign,
+/// and the section header table is put last. The program headers with a
+/// FirstSection and a LastSection get the offset, addresses and sizes of
+/// the sections between them as default values.
+///
+/// Fails if a field has an invalid value, if a name isn't in the names
+/// table, or if the class isn't ELFCLASS64.
+pub fn build(description: &Yaml) -> Result<Vec<u8>> {
+    let root = Entry::new(String::new(), Some(description))?;
+    let file_header = root.child("FileHeader")?;
+    let class = file_header
+        .number::<u8>("Class", &CLASS_NAMES)?
+        .unwrap_or(2);
+    if class != 2 {
+        return Err(Error::UnsupportedClass(class));
+    }
+    let data = file_header.number::<u8>("Data", &DATA_NAMES)?.unwrap_or(1);
+    let big_endian = data == 2;
+
+    let mut header = x64::default();
+    header.e_ident[..4].copy_from_slice(&Indent::MAGIC);
+    header.e_ident[Indent::CLASS] = class;
+    header.e_ident[Indent::DATA] = data;
+    header.e_ident[Indent::VERSION] = file_header.number("IdentVersion", &[])?.unwrap_or(1);
+    header.e_ident[Indent::OSABIT] = file_header.number("OSABI", &[])?.unwrap_or(0);
+    header.e_ident[Indent::ABIVERSION] = file_header.number("ABIVersion", &[])?.unwrap_or(0);
+    if let Some(padding) = file_header.bytes("Padding")? {
+        let ident = &mut header.e_ident[Indent::ABIVERSION + 1..];
+        if padding.len() > ident.len() {
+            return Err(file_header.error("Padding", "more than 7 bytes"));
+        }
+        ident[..padding.len()].copy_from_slice(&padding);
+    }
+    header.e_type = file_header.number("Type", &ET_NAMES)?.unwrap_or(0);
+    header.e_machine = file_header.number("Machine", &EM_NAMES)?.unwrap_or(0);
+    header.e_version = file_header.number("Version", &[])?.unwrap_or(1);
+    header.e_entry = file_header.number("Entry", &[])?.unwrap_or(0);
+    header.e_flags = file_header.number("Flags", &[])?.unwrap_or(0);
+    header.e_ehsize = file_header
+        .number("EhSize", &[])?
+        .unwrap_or(x64::SIZE as u16);
+
+    let mut sections = Vec::new();
+    for entry in root.list("Sections")? {
+        let mut shdr = Elf64_Shdr {
+            sh_type: entry.number("Type", &SHT_NAMES)?.unwrap_or(SHT::NULL),
+            sh_flags: entry.number("Flags", &SHF_NAMES)?.unwrap_or(0),
+            sh_addr: entry.number("Address", &[])?.unwrap_or(0),
+            sh_link: entry.number("Link", &[])?.unwrap_or(0),
+            sh_info: entry.number("Info", &[])?.unwrap_or(0),
+            sh_addralign: entry.number("AddressAlign", &[])?.unwrap_or(0),
+            sh_entsize: entry.number("EntSize", &[])?.unwrap_or(0),
+            ..Elf64_Shdr::default()
+        };
+        let content = entry.bytes("Content")?;
+        if shdr.sh_type == SHT::NOBITS && content.is_some() {
+            return Err(entry.error("Content", "a SHT_NOBITS section has no content"));
+        }
+        shdr.sh_name = entry.number("NameOffset", &[])?.unwrap_or(0);
+        sections.push(Section {
+            name: entry.text("Name")?.map(str::to_string),
+            shdr,
+            content,
+            offset: entry.number("Offset", &[])?,
+            size: entry.number("Size", &[])?,
+            name_offset: entry.number("NameOffset", &[])?,
+        });
+    }
+    let null = |name: Option<String>| Section {
+        name,
+        shdr: Elf64_Shdr::default(),
+        content: None,
+        offset: None,
+        size: None,
+        name_offset: None,
+    };
+    if sections
+        .first()
+        .is_some_and(|section| section.shdr.sh_type != SHT::NULL)
+    {
+        sections.insert(0, null(None));
+    }
+
+    // the section names table
+    let named = sections
+        .iter()
+        .position(|section| section.name.as_deref() == Some(SHSTRTAB));
+    let has_names = sections
+        .iter()
+        .any(|section| section.name.as_deref().is_some_and(|name| !name.is_empty()));
+    // ShStrNdx is the field of the file header, SHN_XINDEX escapes the index
+    // to the Link of the null section
+    let given = file_header.number::<u32>("ShStrNdx", &[])?;
+    let escaped = given == Some(SHN::XINDEX as u32);
+    let shstrndx = match (given, named) {
+        (Some(_), _) if escaped => sections
+            .first()
+            .map_or(SHN::XINDEX as u32, |initial| initial.shdr.sh_link),
+        (Some(index), _) => index,
+        (None, Some(index)) => index as u32,
+        (None, None) if has_names => {
+            let mut table = null(Some(SHSTRTAB.to_string()));
+            table.shdr.sh_type = SHT::STRTAB;
+            table.shdr.sh_addralign = 1;
+            sections.push(table);
+            (sections.len() - 1) as u32
+        }
+        (None, None) => 0,
+    };
+    let index = shstrndx as usize;
+    // a table with an Offset or a Size is described as it is, even empty
+    // or past the end of the file
+    let generated = index != 0
+        && sections.get(index).is_some_and(|section| {
+            section.content.is_none()
+                && section.offset.is_none()
+                && section.size.is_none()
+                && section.shdr.sh_type != SHT::NOBITS
+        });
+    if generated {
+        let mut table = vec![0];
+        for section in &sections {
+            match section.name.as_deref() {
+                Some(name) if !name.is_empty() && find_name(&table, name).is_none() => {
+                    table.extend_from_slice(name.as_bytes());
+                    table.push(0);
+                }
+                _ => {}
+            }
+        }
+        sections[index].content = Some(table);
+    }
+    let table = sections
+        .get(index)
+        .filter(|_| index != 0)
+        .and_then(|section| section.content.clone())
+        .unwrap_or_default();
+    for (number, section) in sections.iter_mut().enumerate() {
+        if section.name_offset.is_some() {
+            continue;
+        }
+        section.shdr.sh_name = match section.name.as_deref().unwrap_or_default() {
+            "" => table.iter().position(|byte| *byte == 0).unwrap_or(0) as u32,
+            name => find_name(&table, name).ok_or_else(|| {
+                Error::InvalidDescription(format!(
+                    "Sections[{}]: '{}' is not in the section names table",
+                    number, name
+                ))
+            })?,
+        };
+    }
+
+    // the layout of the file
+    let segments = root.list("ProgramHeaders")?;
+    let defaults = Defaults {
+        phnum: segments.len(),
+        shnum: sections.len(),
+    };
+    header.e_phentsize = file_header
+        .number("PhEntSize", &[])?
+        .unwrap_or(defaults.phentsize());
+    header.e_shentsize = file_header
+        .number("ShEntSize", &[])?
+        .unwrap_or(defaults.shentsize());
+    if !segments.is_empty() && (header.e_phentsize as usize) < Elf64_Phdr::SIZE {
+        return Err(file_header.error("PhEntSize", "smaller than a program header"));
+    }
+    if !sections.is_empty() && (header.e_shentsize as usize) < Elf64_Shdr::SIZE {
+        return Err(file_header.error("ShEntSize", "smaller than a section header"));
+    }
+    header.e_phoff = file_header
+        .number("PhOff", &[])?
+        .unwrap_or(defaults.phoff());
+    let mut end = (x64::SIZE as u64).max(
+        header
+            .e_phoff
+            .saturating_add(segments.len() as u64 * header.e_phentsize as u64),
+    );
+    for (number, section) in sections.iter_mut().enumerate() {
+        let len = section.content.as_ref().map_or(0, Vec::len) as u64;
+        section.shdr.sh_offset = match section.offset {
+            Some(offset) => offset,
+            None if number == 0 => 0,
+            None => align_up(end, section.shdr.sh_addralign),
+        };
+        section.shdr.sh_size = section.size.unwrap_or(len);
+        if len != 0 {
+            end = end.max(section.shdr.sh_offset.saturating_add(len));
+        }
+    }
+
+    let mut phdrs = Vec::new();
+    for entry in &segments {
+        let bound = |key: &str| -> Result<Option<&Elf64_Shdr>> {
+            match entry.text(key)? {
+                None => Ok(None),
+                Some(name) => sections
+                    .iter()
+                    .find(|section| section.name.as_deref() == Some(name))
+                    .map(|section| Some(&section.shdr))
+                    .ok_or_else(|| entry.error(key, "no section of this name")),
+            }
+        };
+        let (first, last) = (bound("FirstSection")?, bound("LastSection")?);
+        let (offset, vaddr, filesz, memsz) = match (first, last) {
+            (Some(first), Some(last)) => {
+                let file_end = match last.sh_type {
+                    SHT::NOBITS => last.sh_offset,
+                    _ => last.sh_offset.saturating_add(last.sh_size),
+                };
+                let memory_end = last.sh_addr.saturating_add(last.sh_size);
+                (
+                    first.sh_offset,
+                    first.sh_addr,
+                    file_end.saturating_sub(first.sh_offset),
+                    memory_end.saturating_sub(first.sh_addr),
+                )
+            }
+            (None, None) => (0, 0, 0, 0),
+            _ => return Err(entry.error("LastSection", "FirstSection and LastSection go together")),
+        };
+        let vaddr = entry.number("VAddr", &[])?.unwrap_or(vaddr);
+        phdrs.push(Elf64_Phdr {
+            p_type: entry.number("Type", &PT_NAMES)?.unwrap_or(PT::NULL),
+            p_flags: entry.number("Flags", &PF_NAMES)?.unwrap_or(0),
+            p_offset: entry.number("Offset", &[])?.unwrap_or(offset),
+            p_vaddr: vaddr,
+            p_paddr: entry.number("PAddr", &[])?.unwrap_or(vaddr),
+            p_filesz: entry.number("FileSize", &[])?.unwrap_or(filesz),
+            p_memsz: entry.number("MemSize", &[])?.unwrap_or(memsz),
+            p_align: entry.number("Align", &[])?.unwrap_or(0),
+        });
+    }
+
+    let mut fills = Vec::new();
+    for entry in root.list("Fill")? {
+        let offset: u64 = entry
+            .number("Offset", &[])?
+            .ok_or_else(|| entry.error("Offset", "missing"))?;
+        let bytes = entry.bytes("Content")?.unwrap_or_default();
+        end = end.max(offset.saturating_add(bytes.len() as u64));
+        fills.push((offset, bytes));
+    }
+
+    header.e_shoff = match file_header.number("ShOff", &[])? {
+        Some(offset) => offset,
+        None if sections.is_empty() => 0,
+        None => align_up(end, 8),
+    };
+    if !sections.is_empty() {
+        end = end.max(
+            header
+                .e_shoff
+                .saturating_add(sections.len() as u64 * header.e_shentsize as u64),
+        );
+    }
+    header.e_phnum = file_header
+        .number("PhNum", &[])?
+        .unwrap_or(defaults.e_phnum());
+    header.e_shnum = file_header
+        .number("ShNum", &[])?
+        .unwrap_or(defaults.e_shnum());
+    header.e_shstrndx = if escaped {
+        SHN::XINDEX
+    } else {
+        defaults.e_shstrndx(shstrndx)
+    };
+    // the counts that don't fit in the file header go to the null section
+    if let Some(initial) = sections.first_mut() {
+        if defaults.e_phnum() == x64::PN_XNUM && initial.shdr.sh_info == 0 {
+            initial.shdr.sh_info = phdrs.len() as u32;
+        }
+        if defaults.e_shnum() == 0 && initial.size.is_none() {
+            initial.shdr.sh_size = defaults.shnum as u64;
+        }
+        if header.e_shstrndx == SHN::XINDEX && initial.shdr.sh_link == 0 {
+            initial.shdr.sh_link = shstrndx;
+        }
+    }
+
+    let size = usize::try_from(end)
+        .ok()
+        .filter(|size| *size <= isize::MAX as usize);
+    let mut out = Vec::new();
+    size.and_then(|size| out.try_reserve_exact(size).ok())
+        .ok_or_else(|| Error::InvalidDescription(format!("the file would be {:#x} bytes", end)))?;
+    out.resize(end as usize, 0);
+    let mut write = |offset: u64, bytes: &[u8]| {
+        let offset = offset as usize;
+        out[offset..offset + bytes.len()].copy_from_slice(bytes);
+    };
+    for (offset, bytes) in &fills {
+        write(*offset, bytes);
+    }
+    for section in &sections {
+        if let Some(content) = &section.content {
+            write(section.shdr.sh_offset, content);
+        }
+    }
+    for (index, phdr) in phdrs.iter().enumerate() {
+        let offset = header.e_phoff + index as u64 * header.e_phentsize as u64;
+        write(offset, &phdr.to_bytes(big_endian));
+    }
+    for (index, section) in sections.iter().enumerate() {
+        let offset = header.e_shoff + index as u64 * header.e_shentsize as u64;
+        write(offset, &section.shdr.to_bytes(big_endian));
+    }
+    write(0, &header.to_bytes(big_endian));
+    Ok(out)
+}
@@ -0,0 +1,284 @@
+use crate::alloc_prelude::*;
+use crate::utils::json::{Json, ToJson};
+use crate::Result;
+use alloc::collections::BTreeMap;
+use core::fmt;
+
+use super::machine::Machine;
+use super::object::Elf;
+use super::types::Type;
+
+/// One difference between the two files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// only in the second file
+    Added(String),
+    /// only in the first file
+    Removed(String),
+    /// in both files with different properties, old and new
+    Changed(String, String, String),
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added(name) => write!(f, "+ {}", name),
+            Change::Removed(name) => write!(f, "- {}", name),
+            Change::Changed(name, old, new) => write!(f, "~ {}: {} -> {}", name, old, new),
+        }
+    }
+}
+
+impl ToJson for Change {
+    fn to_json(&self) -> Json {
+        match self {
+            Change::Added(name) => Json::object([
+                ("change", Json::from("added")),
+                ("name", Json::from(name.as_str())),
+            ]),
+            Change::Removed(name) => Json::object([
+                ("change", Json::from("removed")),
+                ("name", Json::from(name.as_str())),
+            ]),
+            Change::Changed(name, old, new) => Json::object([
+                ("change", Json::from("changed")),
+                ("name", Json::from(name.as_str())),
+                ("old", Json::from(old.as_str())),
+                ("new", Json::from(new.as_str())),
+            ]),
+        }
+    }
+}
+
+/// The differences between two ELF64 files, grouped by table
+///
+/// Sections and symbols are matched by name, segments by type and rank
+/// among the segments of that type. Symbol values and file offsets are
+/// left out, any change of layout moves them.
+#[derive(Debug, Clone, Default)]
+pub struct Diff {
+    /// changed fields of the file header
+    pub header: Vec<Change>,
+    /// sections added, removed or changed
+    pub sections: Vec<Change>,
+    /// segments added, removed or changed
+    pub segments: Vec<Change>,
+    /// symbols of `.symtab` and `.dynsym` added, removed or changed
+    pub symbols: Vec<Change>,
+    /// dynamic entries added, removed or changed
+    pub dynamic: Vec<Change>,
+}
+
+impl Diff {
+    /// True if no difference was found
+    pub fn is_empty(&self) -> bool {
+        self.groups().iter().all(|(_, changes)| changes.is_empty())
+    }
+
+    fn groups(&self) -> [(&'static str, &[Change]); 5] {
+        [
+            ("header", &self.header),
+            ("sections", &self.sections),
+            ("segments", &self.segments),
+            ("symbols", &self.symbols),
+            ("dynamic", &self.dynamic),
+        ]
+    }
+}
+
+/// Compare the properties of the entries found in both maps
+fn compare(
+    changes: &mut Vec<Change>,
+    old: &BTreeMap<String, String>,
+    new: &BTreeMap<String, String>,
+) {
+    for (name, properties) in old {
+        match new.get(name) {
+            None => changes.push(Change::Removed(name.clone())),
+            Some(other) if other != properties => changes.push(Change::Changed(
+                name.clone(),
+                properties.clone(),
+                other.clone(),
+            )),
+            Some(_) => {}
+        }
+    }
+    for name in new.keys().filter(|name| !old.contains_key(*name)) {
+        changes.push(Change::Added(name.clone()));
+    }
+}
+
+fn header(elf: &Elf) -> BTreeMap<String, String> {
+    let header = elf.header();
+    [
+        ("Type", Type::from(header.e_type).to_string()),
+        (
+            "Machine",
+            Machine::from_u16(header.e_machine).as_str().to_string(),
+        ),
+        ("Entry point address", format!("{:#x}", header.e_entry)),
+        ("Flags", header.flags_string()),
+        (
+            "Number of program headers",
+            header.phnum(elf.data()).to_string(),
+        ),
+        (
+            "Number of section headers",
+            header.shnum(elf.data()).to_string(),
+        ),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value))
+    .collect()
+}
+
+fn sections(elf: &Elf) -> Result<BTreeMap<String, String>> {
+    let names = elf.section_names().unwrap_or_default();
+    let mut sections = BTreeMap::new();
+    for (index, shdr) in elf.sections()?.enumerate().skip(1) {
+        let name = match shdr.name(&names) {
+            Ok(name) => name.to_string(),
+            Err(_) => format!("[{}]", index),
+        };
+        let properties = format!(
+            "{} addr {:#x} size {:#x} flags {} align {}",
+            shdr.type_name(),
+            shdr.sh_addr,
+            shdr.sh_size,
+            shdr.flags_string(),
+            shdr.sh_addralign
+        );
+        sections.entry(name).or_insert(properties);
+    }
+    Ok(sections)
+}
+
+fn segments(elf: &Elf) -> Result<BTreeMap<String, String>> {
+    let mut ranks = BTreeMap::new();
+    let mut segments = BTreeMap::new();
+    for phdr in elf.program_headers()? {
+        let rank = ranks.entry(phdr.p_type).or_insert(0);
+        let name = format!("{} #{}", phdr.type_name(), rank);
+        *rank += 1;
+        let properties = format!(
+            "vaddr {:#x} filesz {:#x} memsz {:#x} flags {} align {:#x}",
+            phdr.p_vaddr,
+            phdr.p_filesz,
+            phdr.p_memsz,
+            phdr.flags_string().trim(),
+            phdr.p_align
+        );
+        segments.insert(name, properties);
+    }
+    Ok(segments)
+}
+
+fn symbols(elf: &Elf) -> Result<BTreeMap<String, String>> {
+    let mut symbols = BTreeMap::new();
+    for table in [elf.symbols()?, elf.dynamic_symbols()?]
+        .into_iter()
+        .flatten()
+    {
+        for symbol in table.symbols.clone() {
+            let name = match table.name(&symbol) {
+                Ok(name) if !name.is_empty() => name.to_string(),
+                _ => continue,
+            };
+            let properties = format!(
+                "{} {} size {}",
+                symbol.type_name(),
+                symbol.bind_name(),
+                symbol.st_size
+            );
+            symbols.entry(name).or_insert(properties);
+        }
+    }
+    Ok(symbols)
+}
+
+/// The entries of each tag, a tag found once in both files is a change
+fn dynamic(elf: &Elf) -> Result<BTreeMap<String, Vec<String>>> {
+    let mut entries: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    if let Some(dynamic) = elf.dynamic()? {
+        for entry in &dynamic.entries {
+            entries
+                .entry(entry.tag_name().into_owned())
+                .or_default()
+                .push(dynamic.describe(entry));
+        }
+    }
+    Ok(entries)
+}
+
+fn compare_dynamic(
+    changes: &mut Vec<Change>,
+    old: &BTreeMap<String, Vec<String>>,
+    new: &BTreeMap<String, Vec<String>>,
+) {
+    let empty = Vec::new();
+    let mut tags: Vec<&String> = old.keys().chain(new.keys()).collect();
+    tags.sort();
+    tags.dedup();
+    for tag in tags {
+        let old = old.get(tag).unwrap_or(&empty);
+        let new = new.get(tag).unwrap_or(&empty);
+        if let ([old], [new]) = (&old[..], &new[..]) {
+            if old != new {
+                changes.push(Change::Changed(tag.clone(), old.clone(), new.clone()));
+            }
+            continue;
+        }
+        for value in old.iter().filter(|value| !new.contains(value)) {
+            changes.push(Change::Removed(format!("{} {}", tag, value)));
+        }
+        for value in new.iter().filter(|value| !old.contains(value)) {
+            changes.push(Change::Added(format!("{} {}", tag, value)));
+        }
+    }
+}
+
+/// Compare two files, `old` is the reference the changes are relative to
+pub fn diff(old: &Elf, new: &Elf) -> Result<Diff> {
+    let mut diff = Diff::default();
+    compare(&mut diff.header, &header(old), &header(new));
+    compare(&mut diff.sections, &sections(old)?, &sections(new)?);
+    compare(&mut diff.segments, &segments(old)?, &segments(new)?);
+    compare(&mut diff.symbols, &symbols(old)?, &symbols(new)?);
+    compare_dynamic(&mut diff.dynamic, &dynamic(old)?, &dynamic(new)?);
+    Ok(diff)
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No differences found.");
+        }
+        let titles = [
+            "File header",
+            "Section headers",
+            "Program headers",
+            "Symbols",
+            "Dynamic section",
+        ];
+        for (title, (_, changes)) in titles.iter().zip(self.groups()) {
+            if changes.is_empty() {
+                continue;
+            }
+            writeln!(f, "{}:", title)?;
+            for change in changes {
+                writeln!(f, "  {}", change)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for Diff {
+    fn to_json(&self) -> Json {
+        Json::object(
+            self.groups()
+                .into_iter()
+                .map(|(name, changes)| (name, Json::array(changes.iter().map(Change::to_json)))),
+        )
+    }
+}
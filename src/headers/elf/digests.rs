@@ -0,0 +1,164 @@
+//! Digests of an ELF64 file and of its allocated sections
+//!
+//! Two builds of the same sources rarely have the same file digest, the
+//! build ID, paths or timestamps differ, while the digests of their
+//! sections tell which parts changed. The text digest covers the bytes of
+//! the executable sections one after the other, it only changes with the
+//! code.
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::digest::{hex, Algorithm};
+use crate::utils::json::{Json, ToJson};
+use crate::utils::table::{Align, Table as TextTable};
+use crate::Result;
+use core::fmt;
+
+use super::section::{self, SHF, SHT};
+use super::strtab::CORRUPT;
+use super::x64::x64;
+
+/// Digest of an allocated section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionDigest<'a> {
+    /// index of the section
+    pub index: usize,
+    /// name of the section
+    pub name: ByteStr<'a>,
+    /// address of the section
+    pub address: u64,
+    /// size of the section in the file
+    pub size: u64,
+    /// digest of its bytes
+    pub digest: Vec<u8>,
+}
+
+/// The digests of a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digests<'a> {
+    /// the algorithm of every digest
+    pub algorithm: Algorithm,
+    /// size of the file
+    pub size: u64,
+    /// digest of the whole file
+    pub file: Vec<u8>,
+    /// digest of the executable sections, in the order of the table
+    pub text: Vec<u8>,
+    /// names of the sections in the text digest
+    pub text_sections: Vec<ByteStr<'a>>,
+    /// the allocated sections with bytes in the file
+    pub sections: Vec<SectionDigest<'a>>,
+}
+
+/// Compute the digests of the file and of its allocated sections
+///
+/// Fails if the section header table can't be parsed. SHT_NOBITS
+/// sections and the sections outside of the file are skipped.
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64, algorithm: Algorithm) -> Result<Digests<'a>> {
+    let names = section::names(data, header).unwrap_or_default();
+    let mut sections = Vec::new();
+    let mut text = Vec::new();
+    let mut text_sections = Vec::new();
+    for (index, shdr) in section::from_bytes(data, header)?.enumerate() {
+        if shdr.sh_flags & SHF::ALLOC == 0 || shdr.sh_type == SHT::NOBITS {
+            continue;
+        }
+        let bytes = match shdr.data(data) {
+            Some(bytes) => bytes,
+            None => continue,
+        };
+        let name = shdr.name(&names).unwrap_or(CORRUPT);
+        if shdr.sh_flags & SHF::EXECINSTR != 0 {
+            text.push(bytes);
+            text_sections.push(name);
+        }
+        sections.push(SectionDigest {
+            index,
+            name,
+            address: shdr.sh_addr,
+            size: shdr.sh_size,
+            digest: algorithm.digest(bytes),
+        });
+    }
+    Ok(Digests {
+        algorithm,
+        size: data.len() as u64,
+        file: algorithm.digest(data),
+        text: algorithm.digest_all(&text),
+        text_sections,
+        sections,
+    })
+}
+
+impl fmt::Display for Digests<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Algorithm: {}", self.algorithm)?;
+        writeln!(f, "File:      {}  {} bytes", hex(&self.file), self.size)?;
+        if self.text_sections.is_empty() {
+            writeln!(f, "Text:      {}  no code", hex(&self.text))?;
+        } else {
+            writeln!(
+                f,
+                "Text:      {}  {}",
+                hex(&self.text),
+                self.text_sections
+                    .iter()
+                    .map(ByteStr::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )?;
+        }
+        if self.sections.is_empty() {
+            return Ok(());
+        }
+        writeln!(f)?;
+        writeln!(f, "Sections:")?;
+        let mut table = TextTable::new(false)
+            .indent(2)
+            .column("[Nr]", Align::Left, None)
+            .column("Name", Align::Left, None)
+            .column("Address", Align::Left, None)
+            .column("Size", Align::Left, None)
+            .column("Digest", Align::Left, None);
+        for section in &self.sections {
+            table.row([
+                format!("[{:>2}]", section.index),
+                section.name.to_string(),
+                format!("{:016x}", section.address),
+                format!("{:#08x}", section.size),
+                hex(&section.digest),
+            ]);
+        }
+        write!(f, "{}", table)
+    }
+}
+
+impl ToJson for SectionDigest<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("index", Json::from(self.index)),
+            ("name", Json::from(self.name)),
+            ("address", Json::from(self.address)),
+            ("size", Json::from(self.size)),
+            ("digest", Json::from(hex(&self.digest))),
+        ])
+    }
+}
+
+impl ToJson for Digests<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("algorithm", Json::from(self.algorithm.name())),
+            ("size", Json::from(self.size)),
+            ("file", Json::from(hex(&self.file))),
+            ("text", Json::from(hex(&self.text))),
+            (
+                "text_sections",
+                Json::array(self.text_sections.iter().map(|name| Json::from(*name))),
+            ),
+            (
+                "sections",
+                Json::array(self.sections.iter().map(ToJson::to_json)),
+            ),
+        ])
+    }
+}
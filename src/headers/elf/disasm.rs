@@ -0,0 +1,326 @@
+//! Disassembly of the code sections, like `objdump -d`
+//!
+//! The instructions are decoded by [`x86_64::decode`], the symbols of the
+//! file label the functions and name the targets of the branches and of
+//! the RIP relative operands. Only built with the `disasm` feature.
+use crate::alloc_prelude::*;
+use crate::utils::disasm::{x86_64, Instruction, Reference};
+use crate::{Error, Result};
+use alloc::borrow::Cow;
+use core::fmt;
+
+use super::image::LoadImage;
+use super::machine::Machine;
+use super::plt::{self, Target};
+use super::section::{self, SHF, SHN, SHT};
+use super::symbols::{self, STB, STT};
+use super::x64::x64;
+
+/// Number of bytes printed on each line of the listing
+const BYTES_PER_LINE: usize = 7;
+
+/// A section to disassemble
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeSection<'a> {
+    /// name of the section, `.secN` for the mappings of a memory image
+    pub name: Cow<'a, str>,
+    /// virtual address of the first byte
+    pub address: u64,
+    /// the content of the section
+    pub bytes: &'a [u8],
+}
+
+impl<'a> CodeSection<'a> {
+    /// The instructions of the section, from its first byte
+    pub fn instructions(&self) -> Instructions<'a> {
+        Instructions {
+            bytes: self.bytes,
+            address: self.address,
+            offset: 0,
+        }
+    }
+}
+
+/// Iterator over the instructions of a [`CodeSection`]
+#[derive(Debug, Clone)]
+pub struct Instructions<'a> {
+    bytes: &'a [u8],
+    address: u64,
+    offset: usize,
+}
+
+impl Iterator for Instructions<'_> {
+    type Item = Instruction;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let address = self.address.wrapping_add(self.offset as u64);
+        let instruction = x86_64::decode(self.bytes.get(self.offset..)?, address)?;
+        self.offset += instruction.len;
+        Some(instruction)
+    }
+}
+
+/// An allocated section, to name the addresses without symbol
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Area<'a> {
+    name: Cow<'a, str>,
+    address: u64,
+    size: u64,
+}
+
+/// The code sections of a file with the names of its addresses
+#[derive(Debug, Clone, Default)]
+pub struct Disassembly<'a> {
+    /// the sections in section header order
+    pub sections: Vec<CodeSection<'a>>,
+    /// one name per address in address order, the functions and objects
+    /// of the symbol tables, the `name@plt` stubs and `name@got` slots
+    pub symbols: Vec<(u64, Cow<'a, str>)>,
+    areas: Vec<Area<'a>>,
+}
+
+impl Disassembly<'_> {
+    /// The name of `address`: the closest symbol before it in its section
+    /// and the offset from that symbol, else the section itself
+    pub fn name(&self, address: u64) -> Option<(&str, u64)> {
+        let area = self
+            .areas
+            .iter()
+            .find(|area| address >= area.address && address - area.address < area.size);
+        let start = area.map_or(0, |area| area.address);
+        let index = self.symbols.partition_point(|(value, _)| *value <= address);
+        match index.checked_sub(1).map(|index| &self.symbols[index]) {
+            Some((value, name)) if *value >= start => Some((name, address - value)),
+            _ => area.map(|area| (&*area.name, address - area.address)),
+        }
+    }
+
+    /// Write `<name+offset>` for `address`, nothing if it has no name
+    fn fmt_name(&self, f: &mut fmt::Formatter<'_>, address: u64) -> fmt::Result {
+        match self.name(address) {
+            Some((name, 0)) => write!(f, " <{}>", name),
+            Some((name, offset)) => write!(f, " <{}+{:#x}>", name, offset),
+            None => Ok(()),
+        }
+    }
+
+    /// Write one instruction and the lines of its remaining bytes
+    fn fmt_instruction(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        section: &CodeSection<'_>,
+        instruction: &Instruction,
+    ) -> fmt::Result {
+        // the addresses wrap around at the end of the address space
+        let start = instruction.address.wrapping_sub(section.address) as usize;
+        let bytes = match start
+            .checked_add(instruction.len)
+            .and_then(|end| section.bytes.get(start..end))
+        {
+            Some(bytes) => bytes,
+            None => return Ok(()),
+        };
+        let mut chunks = bytes.chunks(BYTES_PER_LINE);
+        let hex = |chunk: &[u8]| {
+            chunk
+                .iter()
+                .map(|byte| format!("{:02x} ", byte))
+                .collect::<String>()
+        };
+        let first = hex(chunks.next().unwrap_or_default());
+        write!(
+            f,
+            "{:8x}:\t{:<width$}\t{}",
+            instruction.address,
+            first,
+            instruction,
+            width = BYTES_PER_LINE * 3
+        )?;
+        match instruction.reference {
+            Some(Reference::Branch(target)) => self.fmt_name(f, target)?,
+            Some(Reference::Memory(address)) => {
+                write!(f, "        # {:x}", address)?;
+                self.fmt_name(f, address)?;
+            }
+            None => {}
+        }
+        writeln!(f)?;
+        let mut address = instruction.address;
+        for chunk in chunks {
+            address = address.wrapping_add(BYTES_PER_LINE as u64);
+            writeln!(f, "{:8x}:\t{}", address, hex(chunk).trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Disassembly<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.sections.is_empty() {
+            return writeln!(f, "There are no code sections in this file.");
+        }
+        for section in &self.sections {
+            writeln!(f)?;
+            writeln!(f, "Disassembly of section {}:", section.name)?;
+            let mut labels = self.symbols[self
+                .symbols
+                .partition_point(|(value, _)| *value < section.address)..]
+                .iter()
+                .peekable();
+            if labels.peek().map(|(value, _)| *value) != Some(section.address) {
+                writeln!(f)?;
+                writeln!(f, "{:016x} <{}>:", section.address, section.name)?;
+            }
+            for instruction in section.instructions() {
+                // the labels inside of an instruction aren't printed
+                while let Some((value, name)) =
+                    labels.next_if(|(value, _)| *value <= instruction.address)
+                {
+                    if *value == instruction.address {
+                        writeln!(f)?;
+                        writeln!(f, "{:016x} <{}>:", value, name)?;
+                    }
+                }
+                self.fmt_instruction(f, section, &instruction)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Disassemble the mappings of a memory image as x86_64 code
+///
+/// A raw flash dump or an Intel HEX file has no machine nor symbols, the
+/// mappings are named `.sec1`, `.sec2`... like objdump names the sections
+/// of these files.
+pub fn from_image(image: &LoadImage) -> Disassembly<'_> {
+    let sections = image
+        .mappings
+        .iter()
+        .enumerate()
+        .filter_map(|(index, mapping)| {
+            Some(CodeSection {
+                name: Cow::from(format!(".sec{}", index + 1)),
+                address: mapping.vaddr,
+                bytes: image.get(mapping.vaddr, mapping.filesz as usize)?,
+            })
+        })
+        .collect();
+    Disassembly {
+        sections,
+        ..Disassembly::default()
+    }
+}
+
+/// Rank of a symbol naming an address, the lowest one is kept
+fn rank(kind: u8, bind: u8) -> u8 {
+    let kind = match kind {
+        STT::FUNC | STT::GNU_IFUNC => 0,
+        STT::OBJECT => 1,
+        _ => 2,
+    };
+    let bind = match bind {
+        STB::GLOBAL => 0,
+        STB::WEAK => 1,
+        _ => 2,
+    };
+    kind * 3 + bind
+}
+
+/// Collect the code sections of the file and the names of its addresses
+///
+/// Without `section` every executable section is listed, else the section
+/// of that name whatever its flags, and none if it has no content in the
+/// file. The names come from `.symtab`, or `.dynsym` in stripped files,
+/// and from the PLT stubs and GOT slots. Fails if the machine isn't
+/// x86_64 or if the section header table can't be parsed.
+pub fn from_bytes<'a>(
+    data: &'a [u8],
+    header: &x64,
+    section: Option<&str>,
+) -> Result<Disassembly<'a>> {
+    if Machine::from_u16(header.e_machine) != Machine::X86_64 {
+        return Err(Error::UnsupportedMachine(header.e_machine));
+    }
+    let names = section::names(data, header).unwrap_or_default();
+    let shdrs: Vec<_> = section::from_bytes(data, header)?.collect();
+
+    let mut sections = Vec::new();
+    let mut areas = Vec::new();
+    for shdr in &shdrs {
+        let name = shdr.name(&names).unwrap_or_default();
+        if shdr.sh_flags & SHF::ALLOC != 0 && shdr.sh_type != SHT::NULL {
+            areas.push(Area {
+                name: name.to_str_lossy(),
+                address: shdr.sh_addr,
+                size: shdr.sh_size,
+            });
+        }
+        let selected = match section {
+            Some(wanted) => name == wanted,
+            None => shdr.sh_flags & SHF::EXECINSTR != 0,
+        };
+        if !selected || shdr.sh_type == SHT::NOBITS {
+            continue;
+        }
+        if let Some(bytes) = shdr.data(data) {
+            sections.push(CodeSection {
+                name: name.to_str_lossy(),
+                address: shdr.sh_addr,
+                bytes,
+            });
+        }
+    }
+
+    let table = [SHT::SYMTAB, SHT::DYNSYM]
+        .iter()
+        .find_map(|sh_type| shdrs.iter().find(|shdr| shdr.sh_type == *sh_type));
+    let mut ranked = Vec::new();
+    if let Some(table) = table {
+        let strtab = symbols::strtab(data, header, table)?;
+        for sym in symbols::from_bytes(data, header, table)? {
+            let defined = sym.st_shndx != SHN::UNDEF && sym.st_shndx < SHN::LORESERVE;
+            let named = matches!(
+                sym.kind(),
+                STT::NOTYPE | STT::FUNC | STT::OBJECT | STT::GNU_IFUNC
+            );
+            match sym.name(&strtab) {
+                Ok(name) if defined && named && !name.is_empty() => ranked.push((
+                    sym.st_value,
+                    rank(sym.kind(), sym.bind()),
+                    name.to_str_lossy(),
+                )),
+                _ => {}
+            }
+        }
+    }
+    if let Ok(plt) = plt::from_bytes(data, header) {
+        for entry in plt.entries {
+            if let Target::Symbol(name) = entry.target {
+                ranked.push((entry.address, u8::MAX, format!("{}@plt", name).into()));
+            }
+        }
+        // the first slot of .got.plt names _DYNAMIC without relocation
+        for entry in plt
+            .got
+            .into_iter()
+            .filter(|entry| entry.relocation.is_some())
+        {
+            if let Target::Symbol(name) = entry.target {
+                ranked.push((entry.address, u8::MAX, format!("{}@got", name).into()));
+            }
+        }
+    }
+    ranked.sort_by_key(|(address, rank, _)| (*address, *rank));
+    ranked.dedup_by_key(|(address, _, _)| *address);
+    let symbols = ranked
+        .into_iter()
+        .map(|(address, _, name)| (address, name))
+        .collect();
+
+    Ok(Disassembly {
+        sections,
+        symbols,
+        areas,
+    })
+}
@@ -0,0 +1,631 @@
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::json::{Json, ToJson};
+use crate::utils::{impl_swap_bytes, impl_to_bytes};
+use crate::Result;
+use alloc::borrow::Cow;
+use core::fmt;
+use core::mem::size_of;
+
+use super::data::Data;
+use super::program::{self, PT};
+use super::section::{self, SHT};
+use super::strtab::{StrTab, CORRUPT};
+use super::table::Table;
+use super::x64::x64;
+
+/// Define the tag of a dynamic entry
+///
+/// DT define possible values for the `d_tag` field
+pub struct DT {}
+impl DT {
+    /// Marks the end of the dynamic section
+    pub const NULL: i64 = 0;
+    /// Name of a needed library
+    pub const NEEDED: i64 = 1;
+    /// Size of the PLT relocations
+    pub const PLTRELSZ: i64 = 2;
+    /// Address of the PLT or GOT
+    pub const PLTGOT: i64 = 3;
+    /// Address of the symbol hash table
+    pub const HASH: i64 = 4;
+    /// Address of the string table
+    pub const STRTAB: i64 = 5;
+    /// Address of the symbol table
+    pub const SYMTAB: i64 = 6;
+    /// Address of the Rela relocations
+    pub const RELA: i64 = 7;
+    /// Size of the Rela relocations
+    pub const RELASZ: i64 = 8;
+    /// Size of a Rela relocation entry
+    pub const RELAENT: i64 = 9;
+    /// Size of the string table
+    pub const STRSZ: i64 = 10;
+    /// Size of a symbol table entry
+    pub const SYMENT: i64 = 11;
+    /// Address of the init function
+    pub const INIT: i64 = 12;
+    /// Address of the termination function
+    pub const FINI: i64 = 13;
+    /// Name of the shared object
+    pub const SONAME: i64 = 14;
+    /// Library search path (deprecated)
+    pub const RPATH: i64 = 15;
+    /// Start symbol search within the object
+    pub const SYMBOLIC: i64 = 16;
+    /// Address of the Rel relocations
+    pub const REL: i64 = 17;
+    /// Size of the Rel relocations
+    pub const RELSZ: i64 = 18;
+    /// Size of a Rel relocation entry
+    pub const RELENT: i64 = 19;
+    /// Type of the PLT relocations
+    pub const PLTREL: i64 = 20;
+    /// Used for debugging
+    pub const DEBUG: i64 = 21;
+    /// Relocations might modify read only segments
+    pub const TEXTREL: i64 = 22;
+    /// Address of the PLT relocations
+    pub const JMPREL: i64 = 23;
+    /// Process relocations at load time
+    pub const BIND_NOW: i64 = 24;
+    /// Address of the array of constructors
+    pub const INIT_ARRAY: i64 = 25;
+    /// Address of the array of destructors
+    pub const FINI_ARRAY: i64 = 26;
+    /// Size of the array of constructors
+    pub const INIT_ARRAYSZ: i64 = 27;
+    /// Size of the array of destructors
+    pub const FINI_ARRAYSZ: i64 = 28;
+    /// Library search path
+    pub const RUNPATH: i64 = 29;
+    /// Flags for the object being loaded
+    pub const FLAGS: i64 = 30;
+    /// Address of the array of pre-constructors
+    pub const PREINIT_ARRAY: i64 = 32;
+    /// Size of the array of pre-constructors
+    pub const PREINIT_ARRAYSZ: i64 = 33;
+    /// Address of the SYMTAB_SHNDX section
+    pub const SYMTAB_SHNDX: i64 = 34;
+    /// Size of the Relr relocations
+    pub const RELRSZ: i64 = 35;
+    /// Address of the Relr relocations
+    pub const RELR: i64 = 36;
+    /// Size of a Relr relocation entry
+    pub const RELRENT: i64 = 37;
+    /// Prelinking timestamp
+    pub const GNU_PRELINKED: i64 = 0x6fff_fdf5;
+    /// Size of the conflict section
+    pub const GNU_CONFLICTSZ: i64 = 0x6fff_fdf6;
+    /// Size of the library list
+    pub const GNU_LIBLISTSZ: i64 = 0x6fff_fdf7;
+    /// Checksum of the object
+    pub const CHECKSUM: i64 = 0x6fff_fdf8;
+    /// Size of the PLT padding
+    pub const PLTPADSZ: i64 = 0x6fff_fdf9;
+    /// Size of a move table entry
+    pub const MOVEENT: i64 = 0x6fff_fdfa;
+    /// Size of the move table
+    pub const MOVESZ: i64 = 0x6fff_fdfb;
+    /// Size of the syminfo table
+    pub const SYMINSZ: i64 = 0x6fff_fdfe;
+    /// Size of a syminfo table entry
+    pub const SYMINENT: i64 = 0x6fff_fdff;
+    /// Address of the GNU style hash table
+    pub const GNU_HASH: i64 = 0x6fff_fef5;
+    /// Address of the TLS descriptor PLT entry
+    pub const TLSDESC_PLT: i64 = 0x6fff_fef6;
+    /// Address of the TLS descriptor GOT entry
+    pub const TLSDESC_GOT: i64 = 0x6fff_fef7;
+    /// Address of the conflict section
+    pub const GNU_CONFLICT: i64 = 0x6fff_fef8;
+    /// Address of the library list
+    pub const GNU_LIBLIST: i64 = 0x6fff_fef9;
+    /// Configuration information
+    pub const CONFIG: i64 = 0x6fff_fefa;
+    /// Dependency auditing
+    pub const DEPAUDIT: i64 = 0x6fff_fefb;
+    /// Object auditing
+    pub const AUDIT: i64 = 0x6fff_fefc;
+    /// Address of the PLT padding
+    pub const PLTPAD: i64 = 0x6fff_fefd;
+    /// Address of the move table
+    pub const MOVETAB: i64 = 0x6fff_fefe;
+    /// Address of the syminfo table
+    pub const SYMINFO: i64 = 0x6fff_feff;
+    /// Address of the version symbol table
+    pub const VERSYM: i64 = 0x6fff_fff0;
+    /// Number of relative Rela relocations
+    pub const RELACOUNT: i64 = 0x6fff_fff9;
+    /// Number of relative Rel relocations
+    pub const RELCOUNT: i64 = 0x6fff_fffa;
+    /// State flags
+    pub const FLAGS_1: i64 = 0x6fff_fffb;
+    /// Address of the version definition table
+    pub const VERDEF: i64 = 0x6fff_fffc;
+    /// Number of version definitions
+    pub const VERDEFNUM: i64 = 0x6fff_fffd;
+    /// Address of the version needs table
+    pub const VERNEED: i64 = 0x6fff_fffe;
+    /// Number of version needs
+    pub const VERNEEDNUM: i64 = 0x6fff_ffff;
+    /// Shared object to load before self
+    pub const AUXILIARY: i64 = 0x7fff_fffd;
+    /// Shared object to get values from
+    pub const FILTER: i64 = 0x7fff_ffff;
+}
+
+/// Define the flags of the DT_FLAGS entry
+pub struct DF {}
+impl DF {
+    /// Object may use $ORIGIN
+    pub const ORIGIN: u64 = 0x1;
+    /// Symbol resolutions starts here
+    pub const SYMBOLIC: u64 = 0x2;
+    /// Object contains text relocations
+    pub const TEXTREL: u64 = 0x4;
+    /// No lazy binding for this object
+    pub const BIND_NOW: u64 = 0x8;
+    /// Module uses the static TLS model
+    pub const STATIC_TLS: u64 = 0x10;
+}
+
+/// Define the flags of the DT_FLAGS_1 entry
+#[allow(non_camel_case_types)]
+pub struct DF_1 {}
+impl DF_1 {
+    /// Set RTLD_NOW for this object
+    pub const NOW: u64 = 0x1;
+    /// Set RTLD_GLOBAL for this object
+    pub const GLOBAL: u64 = 0x2;
+    /// Set RTLD_GROUP for this object
+    pub const GROUP: u64 = 0x4;
+    /// Set RTLD_NODELETE for this object
+    pub const NODELETE: u64 = 0x8;
+    /// Trigger filtee loading at runtime
+    pub const LOADFLTR: u64 = 0x10;
+    /// Set RTLD_INITFIRST for this object
+    pub const INITFIRST: u64 = 0x20;
+    /// Set RTLD_NOOPEN for this object
+    pub const NOOPEN: u64 = 0x40;
+    /// $ORIGIN must be handled
+    pub const ORIGIN: u64 = 0x80;
+    /// Direct binding enabled
+    pub const DIRECT: u64 = 0x100;
+    pub const TRANS: u64 = 0x200;
+    /// Object is used to interpose
+    pub const INTERPOSE: u64 = 0x400;
+    /// Ignore default lib search path
+    pub const NODEFLIB: u64 = 0x800;
+    /// Object can't be dldump'ed
+    pub const NODUMP: u64 = 0x1000;
+    /// Configuration alternative created
+    pub const CONFALT: u64 = 0x2000;
+    /// Filtee terminates filters search
+    pub const ENDFILTEE: u64 = 0x4000;
+    /// Disp reloc applied at build time
+    pub const DISPRELDNE: u64 = 0x8000;
+    /// Disp reloc applied at run time
+    pub const DISPRELPND: u64 = 0x10000;
+    /// Object has no direct binding
+    pub const NODIRECT: u64 = 0x20000;
+    pub const IGNMULDEF: u64 = 0x40000;
+    pub const NOKSYMS: u64 = 0x80000;
+    pub const NOHDR: u64 = 0x100000;
+    /// Object is modified after built
+    pub const EDITED: u64 = 0x200000;
+    pub const NORELOC: u64 = 0x400000;
+    /// Object has individual interposers
+    pub const SYMINTPOSE: u64 = 0x800000;
+    /// Global auditing required
+    pub const GLOBAUDIT: u64 = 0x1000000;
+    /// Singleton symbols are used
+    pub const SINGLETON: u64 = 0x2000000;
+    pub const STUB: u64 = 0x4000000;
+    /// Object is a position independent executable
+    pub const PIE: u64 = 0x8000000;
+}
+
+/// Dynamic section entry of an ELF64 file
+///
+/// If an object file participates in dynamic linking, its program header
+/// table will have an element of type PT_DYNAMIC. This segment contains
+/// an array of entries describing what the dynamic linker needs.
+/// Sources:
+/// * <https://www.man7.org/linux/man-pages/man5/elf.5.html>
+/// * <https://uclibc.org/docs/elf-64-gen.pdf>
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct Elf64_Dyn {
+    /// entry type
+    pub d_tag: i64,
+    /// integer value or address, depending of the tag
+    pub d_val: u64,
+}
+
+impl_swap_bytes!(Elf64_Dyn, d_tag, d_val);
+
+impl_to_bytes!(Elf64_Dyn, d_tag, d_val);
+
+impl Elf64_Dyn {
+    pub const SIZE: usize = size_of::<Self>();
+
+    /// Name of the entry tag as printed by readelf
+    pub fn tag_name(&self) -> Cow<'static, str> {
+        let name = match self.d_tag {
+            DT::NULL => "NULL",
+            DT::NEEDED => "NEEDED",
+            DT::PLTRELSZ => "PLTRELSZ",
+            DT::PLTGOT => "PLTGOT",
+            DT::HASH => "HASH",
+            DT::STRTAB => "STRTAB",
+            DT::SYMTAB => "SYMTAB",
+            DT::RELA => "RELA",
+            DT::RELASZ => "RELASZ",
+            DT::RELAENT => "RELAENT",
+            DT::STRSZ => "STRSZ",
+            DT::SYMENT => "SYMENT",
+            DT::INIT => "INIT",
+            DT::FINI => "FINI",
+            DT::SONAME => "SONAME",
+            DT::RPATH => "RPATH",
+            DT::SYMBOLIC => "SYMBOLIC",
+            DT::REL => "REL",
+            DT::RELSZ => "RELSZ",
+            DT::RELENT => "RELENT",
+            DT::PLTREL => "PLTREL",
+            DT::DEBUG => "DEBUG",
+            DT::TEXTREL => "TEXTREL",
+            DT::JMPREL => "JMPREL",
+            DT::BIND_NOW => "BIND_NOW",
+            DT::INIT_ARRAY => "INIT_ARRAY",
+            DT::FINI_ARRAY => "FINI_ARRAY",
+            DT::INIT_ARRAYSZ => "INIT_ARRAYSZ",
+            DT::FINI_ARRAYSZ => "FINI_ARRAYSZ",
+            DT::RUNPATH => "RUNPATH",
+            DT::FLAGS => "FLAGS",
+            DT::PREINIT_ARRAY => "PREINIT_ARRAY",
+            DT::PREINIT_ARRAYSZ => "PREINIT_ARRAYSZ",
+            DT::SYMTAB_SHNDX => "SYMTAB_SHNDX",
+            DT::RELRSZ => "RELRSZ",
+            DT::RELR => "RELR",
+            DT::RELRENT => "RELRENT",
+            DT::GNU_PRELINKED => "GNU_PRELINKED",
+            DT::GNU_CONFLICTSZ => "GNU_CONFLICTSZ",
+            DT::GNU_LIBLISTSZ => "GNU_LIBLISTSZ",
+            DT::CHECKSUM => "CHECKSUM",
+            DT::PLTPADSZ => "PLTPADSZ",
+            DT::MOVEENT => "MOVEENT",
+            DT::MOVESZ => "MOVESZ",
+            DT::SYMINSZ => "SYMINSZ",
+            DT::SYMINENT => "SYMINENT",
+            DT::GNU_HASH => "GNU_HASH",
+            DT::TLSDESC_PLT => "TLSDESC_PLT",
+            DT::TLSDESC_GOT => "TLSDESC_GOT",
+            DT::GNU_CONFLICT => "GNU_CONFLICT",
+            DT::GNU_LIBLIST => "GNU_LIBLIST",
+            DT::CONFIG => "CONFIG",
+            DT::DEPAUDIT => "DEPAUDIT",
+            DT::AUDIT => "AUDIT",
+            DT::PLTPAD => "PLTPAD",
+            DT::MOVETAB => "MOVETAB",
+            DT::SYMINFO => "SYMINFO",
+            DT::VERSYM => "VERSYM",
+            DT::RELACOUNT => "RELACOUNT",
+            DT::RELCOUNT => "RELCOUNT",
+            DT::FLAGS_1 => "FLAGS_1",
+            DT::VERDEF => "VERDEF",
+            DT::VERDEFNUM => "VERDEFNUM",
+            DT::VERNEED => "VERNEED",
+            DT::VERNEEDNUM => "VERNEEDNUM",
+            DT::AUXILIARY => "AUXILIARY",
+            DT::FILTER => "FILTER",
+            _ => return Cow::Owned(format!("{:#x}", self.d_tag)),
+        };
+        Cow::Borrowed(name)
+    }
+
+    /// Whether the value of the entry is an offset in the dynamic string table
+    pub fn is_string(&self) -> bool {
+        matches!(
+            self.d_tag,
+            DT::NEEDED
+                | DT::SONAME
+                | DT::RPATH
+                | DT::RUNPATH
+                | DT::AUXILIARY
+                | DT::FILTER
+                | DT::CONFIG
+                | DT::DEPAUDIT
+                | DT::AUDIT
+        )
+    }
+}
+
+/// Names of the DT_FLAGS bits as printed by readelf
+fn flags_names(value: u64) -> String {
+    const NAMES: [(u64, &str); 5] = [
+        (DF::ORIGIN, "ORIGIN"),
+        (DF::SYMBOLIC, "SYMBOLIC"),
+        (DF::TEXTREL, "TEXTREL"),
+        (DF::BIND_NOW, "BIND_NOW"),
+        (DF::STATIC_TLS, "STATIC_TLS"),
+    ];
+    bits_names(value, &NAMES)
+}
+
+/// Names of the DT_FLAGS_1 bits as printed by readelf
+fn flags_1_names(value: u64) -> String {
+    const NAMES: [(u64, &str); 28] = [
+        (DF_1::NOW, "NOW"),
+        (DF_1::GLOBAL, "GLOBAL"),
+        (DF_1::GROUP, "GROUP"),
+        (DF_1::NODELETE, "NODELETE"),
+        (DF_1::LOADFLTR, "LOADFLTR"),
+        (DF_1::INITFIRST, "INITFIRST"),
+        (DF_1::NOOPEN, "NOOPEN"),
+        (DF_1::ORIGIN, "ORIGIN"),
+        (DF_1::DIRECT, "DIRECT"),
+        (DF_1::TRANS, "TRANS"),
+        (DF_1::INTERPOSE, "INTERPOSE"),
+        (DF_1::NODEFLIB, "NODEFLIB"),
+        (DF_1::NODUMP, "NODUMP"),
+        (DF_1::CONFALT, "CONFALT"),
+        (DF_1::ENDFILTEE, "ENDFILTEE"),
+        (DF_1::DISPRELDNE, "DISPRELDNE"),
+        (DF_1::DISPRELPND, "DISPRELPND"),
+        (DF_1::NODIRECT, "NODIRECT"),
+        (DF_1::IGNMULDEF, "IGNMULDEF"),
+        (DF_1::NOKSYMS, "NOKSYMS"),
+        (DF_1::NOHDR, "NOHDR"),
+        (DF_1::EDITED, "EDITED"),
+        (DF_1::NORELOC, "NORELOC"),
+        (DF_1::SYMINTPOSE, "SYMINTPOSE"),
+        (DF_1::GLOBAUDIT, "GLOBAUDIT"),
+        (DF_1::SINGLETON, "SINGLETON"),
+        (DF_1::STUB, "STUB"),
+        (DF_1::PIE, "PIE"),
+    ];
+    bits_names(value, &NAMES)
+}
+
+fn bits_names(value: u64, names: &[(u64, &str)]) -> String {
+    let mut words: Vec<String> = names
+        .iter()
+        .filter(|(bit, _)| value & bit != 0)
+        .map(|(_, name)| name.to_string())
+        .collect();
+    let known = names.iter().fold(0, |acc, (bit, _)| acc | bit);
+    if value & !known != 0 {
+        words.push(format!("{:#x}", value & !known));
+    }
+    words.join(" ")
+}
+
+/// Dynamic section of an ELF64 file
+///
+/// Holds the entries up to and including the terminating DT_NULL, and
+/// the dynamic string table used to resolve their names.
+#[derive(Debug, Clone)]
+pub struct Dynamic<'a> {
+    /// file offset of the dynamic section
+    pub offset: u64,
+    /// dynamic entries
+    pub entries: Vec<Elf64_Dyn>,
+    /// dynamic string table
+    pub strtab: StrTab<'a>,
+}
+
+impl<'a> Dynamic<'a> {
+    /// Value of the first entry with the given tag
+    pub fn get(&self, tag: i64) -> Option<u64> {
+        self.entries
+            .iter()
+            .find(|entry| entry.d_tag == tag)
+            .map(|entry| entry.d_val)
+    }
+
+    /// Resolve a string of the dynamic string table
+    pub fn string(&self, offset: u64) -> Result<&'a str> {
+        self.strtab.get(offset as usize)
+    }
+
+    /// Resolve a name of the dynamic string table, whatever its encoding
+    pub fn name(&self, offset: u64) -> Result<ByteStr<'a>> {
+        self.strtab.name(offset as usize)
+    }
+
+    /// Names of the libraries the object depends on (DT_NEEDED)
+    pub fn needed(&self) -> Vec<&'a str> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.d_tag == DT::NEEDED)
+            .filter_map(|entry| self.string(entry.d_val).ok())
+            .collect()
+    }
+
+    /// Name of the shared object (DT_SONAME)
+    pub fn soname(&self) -> Option<&'a str> {
+        self.string(self.get(DT::SONAME)?).ok()
+    }
+
+    /// Library search path (DT_RPATH)
+    pub fn rpath(&self) -> Option<&'a str> {
+        self.string(self.get(DT::RPATH)?).ok()
+    }
+
+    /// Library search path (DT_RUNPATH)
+    pub fn runpath(&self) -> Option<&'a str> {
+        self.string(self.get(DT::RUNPATH)?).ok()
+    }
+
+    /// Describe the value of an entry as printed by readelf
+    pub fn describe(&self, entry: &Elf64_Dyn) -> String {
+        let string = || self.strtab.name(entry.d_val as usize).unwrap_or(CORRUPT);
+        match entry.d_tag {
+            DT::NEEDED => format!("Shared library: [{}]", string()),
+            DT::SONAME => format!("Library soname: [{}]", string()),
+            DT::RPATH => format!("Library rpath: [{}]", string()),
+            DT::RUNPATH => format!("Library runpath: [{}]", string()),
+            DT::AUXILIARY => format!("Auxiliary library: [{}]", string()),
+            DT::FILTER => format!("Filter library: [{}]", string()),
+            DT::CONFIG | DT::DEPAUDIT | DT::AUDIT => string().to_string(),
+            DT::PLTRELSZ
+            | DT::RELASZ
+            | DT::RELAENT
+            | DT::STRSZ
+            | DT::SYMENT
+            | DT::RELSZ
+            | DT::RELENT
+            | DT::INIT_ARRAYSZ
+            | DT::FINI_ARRAYSZ
+            | DT::PREINIT_ARRAYSZ
+            | DT::RELRSZ
+            | DT::RELRENT
+            | DT::GNU_CONFLICTSZ
+            | DT::GNU_LIBLISTSZ
+            | DT::PLTPADSZ
+            | DT::MOVEENT
+            | DT::MOVESZ
+            | DT::SYMINSZ
+            | DT::SYMINENT => format!("{} (bytes)", entry.d_val),
+            DT::VERDEFNUM | DT::VERNEEDNUM | DT::RELACOUNT | DT::RELCOUNT => {
+                entry.d_val.to_string()
+            }
+            DT::PLTREL => match entry.d_val as i64 {
+                DT::RELA => "RELA".to_string(),
+                DT::REL => "REL".to_string(),
+                _ => format!("{:#x}", entry.d_val),
+            },
+            DT::FLAGS => flags_names(entry.d_val),
+            DT::FLAGS_1 => format!("Flags: {}", flags_1_names(entry.d_val)),
+            _ => format!("{:#x}", entry.d_val),
+        }
+    }
+}
+
+/// Parse the dynamic section of the file
+///
+/// The `.dynamic` section is used when present, otherwise the PT_DYNAMIC
+/// segment. Returns None if the file has neither.
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64) -> Result<Option<Dynamic<'a>>> {
+    let big_endian = Data::is_big_endian(&header.e_ident);
+    let shdr = section::from_bytes(data, header)
+        .ok()
+        .and_then(|mut shdrs| shdrs.find(|shdr| shdr.sh_type == SHT::DYNAMIC));
+    let (offset, size, strtab) = match shdr {
+        Some(shdr) => (
+            shdr.sh_offset,
+            shdr.sh_size,
+            section::get_data(data, header, shdr.sh_link).ok(),
+        ),
+        None => match program::from_bytes(data, header)?.find(|phdr| phdr.p_type == PT::DYNAMIC) {
+            Some(phdr) => (phdr.p_offset, phdr.p_filesz, None),
+            None => return Ok(None),
+        },
+    };
+    let entries: Vec<Elf64_Dyn> = Table::<Elf64_Dyn>::parse(
+        data,
+        offset,
+        Elf64_Dyn::SIZE as u64,
+        size / Elf64_Dyn::SIZE as u64,
+        big_endian,
+    )?
+    .map(|entry| *entry)
+    .scan(false, |done, entry| {
+        // keep the first DT_NULL then stop
+        if *done {
+            return None;
+        }
+        *done = entry.d_tag == DT::NULL;
+        Some(entry)
+    })
+    .collect();
+    let mut dynamic = Dynamic {
+        offset,
+        entries,
+        strtab: StrTab::default(),
+    };
+    dynamic.strtab = StrTab::new(match strtab {
+        Some(strtab) => strtab,
+        None => dynamic_strtab(data, header, &dynamic).unwrap_or_default(),
+    });
+    Ok(Some(dynamic))
+}
+
+/// Locate the dynamic string table through DT_STRTAB and DT_STRSZ
+fn dynamic_strtab<'a>(data: &'a [u8], header: &x64, dynamic: &Dynamic) -> Option<&'a [u8]> {
+    let offset = program::vaddr_to_offset(data, header, dynamic.get(DT::STRTAB)?)?;
+    let start = usize::try_from(offset).ok()?;
+    let size = usize::try_from(dynamic.get(DT::STRSZ)?).ok()?;
+    data.get(start..start.checked_add(size)?)
+}
+
+/// Dynamic section ready to be printed like `readelf -d`
+pub struct DynamicTable<'a> {
+    /// the whole file
+    pub data: &'a [u8],
+    /// the file header
+    pub header: &'a x64,
+}
+
+impl fmt::Display for DynamicTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dynamic = match from_bytes(self.data, self.header) {
+            Ok(Some(dynamic)) => dynamic,
+            Ok(None) => return writeln!(f, "There is no dynamic section in this file."),
+            Err(err) => return writeln!(f, "Warning: invalid dynamic section: {}", err),
+        };
+        writeln!(
+            f,
+            "Dynamic section at offset {:#x} contains {} entries:",
+            dynamic.offset,
+            dynamic.entries.len()
+        )?;
+        writeln!(f, "  Tag        Type                         Name/Value")?;
+        for entry in &dynamic.entries {
+            writeln!(
+                f,
+                " {:#018x} {:<21}{}",
+                entry.d_tag,
+                format!("({})", entry.tag_name()),
+                dynamic.describe(entry)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for Elf64_Dyn {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("d_tag", Json::from(self.d_tag)),
+            ("tag", Json::from(self.tag_name())),
+            ("d_val", Json::from(self.d_val)),
+        ])
+    }
+}
+
+impl ToJson for DynamicTable<'_> {
+    fn to_json(&self) -> Json {
+        let dynamic = match from_bytes(self.data, self.header) {
+            Ok(Some(dynamic)) => dynamic,
+            Ok(None) => return Json::Null,
+            Err(err) => return Json::error(err),
+        };
+        Json::object([
+            ("offset", Json::from(dynamic.offset)),
+            (
+                "entries",
+                Json::array(dynamic.entries.iter().map(|entry| {
+                    let mut json = entry.to_json();
+                    if let Json::Object(members) = &mut json {
+                        members.push(("value".to_string(), Json::from(dynamic.describe(entry))));
+                    }
+                    json
+                })),
+            ),
+        ])
+    }
+}
@@ -0,0 +1,614 @@
+//! Edit the symbols, sections and dependencies of an ELF64 file
+//!
+//! [`ElfEditor`] records the edits, [`ElfEditor::write`] applies them
+//! like `patchelf` would, to a copy of the file. The existing content
+//! doesn't move: a string table is grown by appending the new names to a
+//! copy of it, and the tables that no longer fit where they are are
+//! written at the end of the file. The loaded ones, `.dynstr`, a grown
+//! `.dynamic` or the added allocated sections, go to a new PT_LOAD
+//! segment together with the program header table, which needs one more
+//! entry. The segment is placed after the memory of the others at an
+//! offset keeping `p_vaddr - p_offset` of the first segment, so the file
+//! may grow by the size of `.bss` too, as long as the output stays under
+//! twice the size of the input plus the new content.
+//!
+//! ```
+//! use readbin::Elf;
+//!
+//! let data = std::fs::read("/bin/ls").unwrap();
+//! let elf = Elf::parse(&data).unwrap();
+//! let mut editor = elf.editor();
+//! editor.add_needed("libm.so.6").set_soname("ls.so");
+//! let edited = editor.write().unwrap();
+//! let elf = Elf::parse(&edited).unwrap();
+//! let dynamic = elf.dynamic().unwrap().unwrap();
+//! assert_eq!(dynamic.needed().last(), Some(&"libm.so.6"));
+//! assert_eq!(dynamic.soname(), Some("ls.so"));
+//! ```
+use crate::alloc_prelude::*;
+use crate::utils::{read_u32, ToBytes};
+use crate::{Error, Result};
+use alloc::collections::BTreeMap;
+
+use super::data::Data;
+use super::description::{align_up, grow};
+use super::dynamic::{self, Elf64_Dyn, DT};
+use super::hash::sysv_hash;
+use super::program::{self, Elf64_Phdr, PF, PT};
+use super::section::{self, Elf64_Shdr, SHF, SHN, SHT};
+use super::symbols::{self, Elf64_Sym};
+use super::x64::x64;
+
+/// Smallest alignment of the new segment, a page
+const PAGE: u64 = 0x1000;
+
+/// Alignment of the added sections in the file and in memory
+const SECTION_ALIGN: u64 = 8;
+
+/// A section to add
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct NewSection {
+    name: String,
+    content: Vec<u8>,
+    flags: u64,
+}
+
+/// Edits to apply to an ELF64 file
+///
+/// Every method records an edit, nothing is checked before
+/// [`ElfEditor::write`].
+#[derive(Debug, Clone)]
+pub struct ElfEditor<'a> {
+    data: &'a [u8],
+    header: x64,
+    renames: Vec<(String, String)>,
+    sections: Vec<NewSection>,
+    soname: Option<String>,
+    needed: Vec<String>,
+}
+
+/// The string tables edited so far, by section index
+struct Strings<'a, 'b> {
+    data: &'a [u8],
+    shdrs: &'b [Elf64_Shdr],
+    tables: BTreeMap<u32, Vec<u8>>,
+}
+
+impl Strings<'_, '_> {
+    /// The current bytes of a string table
+    fn table(&mut self, index: u32) -> Result<&mut Vec<u8>> {
+        if !self.tables.contains_key(&index) {
+            let shdr = self
+                .shdrs
+                .get(index as usize)
+                .filter(|shdr| shdr.sh_type == SHT::STRTAB)
+                .ok_or(Error::InvalidSectionIndex(index))?;
+            let bytes = shdr.data(self.data).ok_or(Error::OutOfBounds {
+                offset: shdr.sh_offset,
+                size: shdr.sh_size,
+            })?;
+            self.tables.insert(index, bytes.to_vec());
+        }
+        Ok(self.tables.get_mut(&index).expect("inserted above"))
+    }
+
+    /// Offset of `name` in a string table, appended if it isn't there
+    ///
+    /// A name can be the end of a longer string, as linkers merge them.
+    fn add(&mut self, index: u32, name: &str) -> Result<u32> {
+        let table = self.table(index)?;
+        let mut wanted = name.as_bytes().to_vec();
+        wanted.push(0);
+        let offset = match table
+            .windows(wanted.len())
+            .position(|bytes| bytes == wanted)
+        {
+            Some(offset) => offset,
+            None => {
+                table.extend_from_slice(&wanted);
+                table.len() - wanted.len()
+            }
+        };
+        Ok(offset as u32)
+    }
+
+    /// The string at `offset` of a string table
+    fn get(&mut self, index: u32, offset: u32) -> Result<&[u8]> {
+        let table = self.table(index)?;
+        let bytes = table
+            .get(offset as usize..)
+            .ok_or(Error::InvalidStringOffset(offset as u64))?;
+        let end = bytes
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or(Error::UnterminatedString(offset as u64))?;
+        Ok(&bytes[..end])
+    }
+}
+
+/// The bytes of `out` from `offset`, `len` of them
+fn slot(out: &mut [u8], offset: u64, len: usize) -> Result<&mut [u8]> {
+    usize::try_from(offset)
+        .ok()
+        .and_then(|offset| out.get_mut(offset..offset.checked_add(len)?))
+        .ok_or(Error::OutOfBounds {
+            offset,
+            size: len as u64,
+        })
+}
+
+/// Write `value` in the byte order of the file at `offset` of `out`
+fn put_u32(out: &mut [u8], offset: u64, value: u32, big_endian: bool) -> Result<()> {
+    let bytes = if big_endian {
+        value.to_be_bytes()
+    } else {
+        value.to_le_bytes()
+    };
+    slot(out, offset, 4)?.copy_from_slice(&bytes);
+    Ok(())
+}
+
+/// A SysV hash table of the names, `names[0]` being the null symbol
+fn sysv_table(names: &[Vec<u8>], nbucket: usize, big_endian: bool) -> Vec<u8> {
+    let nbucket = nbucket.max(1);
+    let mut buckets = vec![0u32; nbucket];
+    let mut chains = vec![0u32; names.len()];
+    for (index, name) in names.iter().enumerate().skip(1) {
+        let bucket = sysv_hash(name) as usize % nbucket;
+        chains[index] = buckets[bucket];
+        buckets[bucket] = index as u32;
+    }
+    let words = [nbucket as u32, names.len() as u32]
+        .into_iter()
+        .chain(buckets)
+        .chain(chains);
+    words
+        .flat_map(|word| {
+            if big_endian {
+                word.to_be_bytes()
+            } else {
+                word.to_le_bytes()
+            }
+        })
+        .collect()
+}
+
+impl<'a> ElfEditor<'a> {
+    /// No edit yet of the file described by `header`
+    pub fn new(data: &'a [u8], header: &x64) -> Self {
+        ElfEditor {
+            data,
+            header: *header,
+            renames: Vec::new(),
+            sections: Vec::new(),
+            soname: None,
+            needed: Vec::new(),
+        }
+    }
+
+    /// Rename the symbols named `old` in `.symtab` and `.dynsym`
+    ///
+    /// The dynamic symbols keep their version. Renaming a symbol of the
+    /// GNU hash table replaces the table by a SysV one, the symbols can't
+    /// be reordered for their new buckets.
+    pub fn rename_symbol(&mut self, old: &str, new: &str) -> &mut Self {
+        self.renames.push((old.to_string(), new.to_string()));
+        self
+    }
+
+    /// Add a SHT_PROGBITS section with the `SHF` flags
+    ///
+    /// An allocated section of an executable or a shared object gets an
+    /// address in the new segment, which takes the write and execute
+    /// permissions of its sections.
+    pub fn add_section(
+        &mut self,
+        name: &str,
+        content: impl Into<Vec<u8>>,
+        flags: u64,
+    ) -> &mut Self {
+        self.sections.push(NewSection {
+            name: name.to_string(),
+            content: content.into(),
+            flags,
+        });
+        self
+    }
+
+    /// Set DT_SONAME, added after the DT_NEEDED entries if missing
+    pub fn set_soname(&mut self, soname: &str) -> &mut Self {
+        self.soname = Some(soname.to_string());
+        self
+    }
+
+    /// Add a DT_NEEDED entry after the others, unless the library is
+    /// already needed
+    pub fn add_needed(&mut self, library: &str) -> &mut Self {
+        self.needed.push(library.to_string());
+        self
+    }
+
+    /// Write the edited file
+    ///
+    /// Fails if the file has no section headers, if a renamed symbol
+    /// doesn't exist, if the dynamic edits are applied to a file without
+    /// a dynamic section, or if the new segment can't be placed.
+    pub fn write(&self) -> Result<Vec<u8>> {
+        let data = self.data;
+        let mut header = self.header;
+        if header.shnum(data) == 0 {
+            return Err(Error::InvalidHeaderField {
+                field: "e_shnum",
+                expected: 1,
+                found: 0,
+            });
+        }
+        let big_endian = Data::is_big_endian(&header.e_ident);
+        let shstrndx = header.shstrndx(data);
+        let mut shdrs: Vec<Elf64_Shdr> = section::from_bytes(data, &header)?
+            .map(|shdr| *shdr)
+            .collect();
+        let mut phdrs: Vec<Elf64_Phdr> = program::from_bytes(data, &header)?
+            .map(|phdr| *phdr)
+            .collect();
+        let original = shdrs.clone();
+        let mut strings = Strings {
+            data,
+            shdrs: &original,
+            tables: BTreeMap::new(),
+        };
+        let mut out = data.to_vec();
+
+        // the names are patched in place, only their string table grows
+        let mut renamed_dynamic = Vec::new();
+        for (old, new) in &self.renames {
+            let mut found = false;
+            for (index, shdr) in original.iter().enumerate() {
+                let dynsym = shdr.sh_type == SHT::DYNSYM;
+                if !(dynsym || shdr.sh_type == SHT::SYMTAB)
+                    || (shdr.sh_entsize as usize) < Elf64_Sym::SIZE
+                {
+                    continue;
+                }
+                // read from the output so that renames can follow each other
+                let symbols: Vec<Elf64_Sym> = symbols::from_bytes(&out, &header, shdr)?
+                    .map(|symbol| *symbol)
+                    .collect();
+                for (number, symbol) in symbols.iter().enumerate() {
+                    if strings.get(shdr.sh_link, symbol.st_name)? != old.as_bytes() {
+                        continue;
+                    }
+                    let st_name = strings.add(shdr.sh_link, new)?;
+                    let offset = (number as u64)
+                        .saturating_mul(shdr.sh_entsize)
+                        .saturating_add(shdr.sh_offset);
+                    put_u32(&mut out, offset, st_name, big_endian)?;
+                    if dynsym {
+                        renamed_dynamic.push((index, number));
+                    }
+                    found = true;
+                }
+            }
+            if !found {
+                return Err(Error::InvalidEdit(format!("no symbol named {}", old)));
+            }
+        }
+
+        // sections whose new content is written elsewhere
+        let mut moved: Vec<(usize, Vec<u8>)> = Vec::new();
+        let dynamic_index = original
+            .iter()
+            .position(|shdr| shdr.sh_type == SHT::DYNAMIC);
+        let mut entries = Vec::new();
+        let mut dynamic_moves = false;
+        if let Some(index) = dynamic_index {
+            entries = dynamic::from_bytes(data, &header)?
+                .map(|dynamic| dynamic.entries)
+                .unwrap_or_default();
+            if entries.last().is_none_or(|entry| entry.d_tag != DT::NULL) {
+                entries.push(Elf64_Dyn::default());
+            }
+            let dynstr = original[index].sh_link;
+            let after_needed = |entries: &[Elf64_Dyn]| {
+                entries
+                    .iter()
+                    .rposition(|entry| entry.d_tag == DT::NEEDED)
+                    .map_or(0, |last| last + 1)
+            };
+            for library in &self.needed {
+                let mut needed = false;
+                for entry in entries.iter().filter(|entry| entry.d_tag == DT::NEEDED) {
+                    needed |= strings.get(dynstr, entry.d_val as u32)? == library.as_bytes();
+                }
+                if needed {
+                    continue;
+                }
+                let d_val = strings.add(dynstr, library)? as u64;
+                let at = after_needed(&entries);
+                entries.insert(
+                    at,
+                    Elf64_Dyn {
+                        d_tag: DT::NEEDED,
+                        d_val,
+                    },
+                );
+            }
+            if let Some(soname) = &self.soname {
+                let d_val = strings.add(dynstr, soname)? as u64;
+                match entries.iter_mut().find(|entry| entry.d_tag == DT::SONAME) {
+                    Some(entry) => entry.d_val = d_val,
+                    None => {
+                        let at = after_needed(&entries);
+                        entries.insert(
+                            at,
+                            Elf64_Dyn {
+                                d_tag: DT::SONAME,
+                                d_val,
+                            },
+                        );
+                    }
+                }
+            }
+        } else if !self.needed.is_empty() || self.soname.is_some() {
+            return Err(Error::InvalidEdit(
+                "the file has no dynamic section".to_string(),
+            ));
+        }
+
+        // the hash tables follow the new names of the dynamic symbols
+        let mut new_hash = None;
+        if let Some((dynsym, _)) = renamed_dynamic.first() {
+            let shdr = original[*dynsym];
+            // the table was read for the renames, its sh_entsize isn't 0
+            let count = shdr.sh_size.checked_div(shdr.sh_entsize).unwrap_or(0);
+            let mut names = Vec::with_capacity(count.min(data.len() as u64) as usize);
+            for symbol in symbols::from_bytes(&out, &header, &shdr)? {
+                names.push(strings.get(shdr.sh_link, symbol.st_name)?.to_vec());
+            }
+            let linked = |sh_type| {
+                original
+                    .iter()
+                    .enumerate()
+                    .find(|(_, hash)| hash.sh_type == sh_type && hash.sh_link as usize == *dynsym)
+                    .map(|(index, hash)| (index, *hash))
+            };
+            if let Some((_, hash)) = linked(SHT::HASH) {
+                let nbucket = read_u32(data, hash.sh_offset as usize, big_endian).ok_or(
+                    Error::OutOfBounds {
+                        offset: hash.sh_offset,
+                        size: hash.sh_size,
+                    },
+                )?;
+                let table = sysv_table(&names, nbucket as usize, big_endian);
+                if table.len() as u64 != hash.sh_size {
+                    return Err(Error::InvalidEdit(
+                        "the SysV hash table doesn't chain every dynamic symbol".to_string(),
+                    ));
+                }
+                slot(&mut out, hash.sh_offset, table.len())?.copy_from_slice(&table);
+            }
+            if let Some((index, gnu)) = linked(SHT::GNU_HASH) {
+                let word = |at: u64| {
+                    let offset = usize::try_from(gnu.sh_offset.checked_add(at)?).ok()?;
+                    read_u32(data, offset, big_endian)
+                };
+                let (nbuckets, symoffset) = word(0).zip(word(4)).ok_or(Error::OutOfBounds {
+                    offset: gnu.sh_offset,
+                    size: gnu.sh_size,
+                })?;
+                let hashed = renamed_dynamic
+                    .iter()
+                    .any(|(_, number)| *number >= symoffset as usize);
+                if hashed && linked(SHT::HASH).is_some() {
+                    // the table no longer matches the symbols
+                    entries.retain(|entry| entry.d_tag != DT::GNU_HASH);
+                    shdrs[index].sh_type = SHT::NULL;
+                } else if hashed {
+                    // a SysV table with as many buckets takes its place
+                    let shdr = &mut shdrs[index];
+                    shdr.sh_name = strings.add(shstrndx, ".hash")?;
+                    shdr.sh_type = SHT::HASH;
+                    shdr.sh_entsize = 4;
+                    moved.push((index, sysv_table(&names, nbuckets as usize, big_endian)));
+                    new_hash = Some(index);
+                    for entry in entries
+                        .iter_mut()
+                        .filter(|entry| entry.d_tag == DT::GNU_HASH)
+                    {
+                        entry.d_tag = DT::HASH;
+                    }
+                }
+            }
+        }
+
+        for section in &self.sections {
+            shdrs.push(Elf64_Shdr {
+                sh_name: strings.add(shstrndx, &section.name)?,
+                sh_type: SHT::PROGBITS,
+                sh_flags: section.flags,
+                sh_addralign: SECTION_ALIGN,
+                ..Elf64_Shdr::default()
+            });
+            moved.push((shdrs.len() - 1, section.content.clone()));
+        }
+        for (index, table) in &strings.tables {
+            if table.len() as u64 != original[*index as usize].sh_size {
+                moved.push((*index as usize, table.clone()));
+            }
+        }
+        if let Some(index) = dynamic_index {
+            let slots = original[index].sh_size / Elf64_Dyn::SIZE as u64;
+            if entries.len() as u64 > slots {
+                dynamic_moves = true;
+                shdrs[index].sh_size = (entries.len() * Elf64_Dyn::SIZE) as u64;
+                moved.push((index, vec![0; entries.len() * Elf64_Dyn::SIZE]));
+            }
+        }
+
+        // damaged offsets, sizes or alignments fail here instead of
+        // asking for terabytes
+        let added = moved
+            .iter()
+            .map(|(_, content)| content.len())
+            .sum::<usize>()
+            + (phdrs.len() + 1) * Elf64_Phdr::SIZE
+            + shdrs.len() * (Elf64_Shdr::SIZE + SECTION_ALIGN as usize);
+        let limit = (data.len() as u64)
+            .saturating_mul(2)
+            .saturating_add(added as u64);
+
+        // the loaded content goes to a new segment, after the program headers
+        let loads: Vec<&Elf64_Phdr> = phdrs
+            .iter()
+            .filter(|phdr| phdr.p_type == PT::LOAD)
+            .collect();
+        let (loaded, rest): (Vec<_>, Vec<_>) = moved
+            .into_iter()
+            .partition(|(index, _)| shdrs[*index].sh_flags & SHF::ALLOC != 0 && !loads.is_empty());
+        let segment_added = !loaded.is_empty();
+        if segment_added {
+            let page = loads.iter().map(|phdr| phdr.p_align).fold(PAGE, u64::max);
+            let first = loads
+                .iter()
+                .min_by_key(|phdr| phdr.p_offset)
+                .expect("a PT_LOAD");
+            let delta = first.p_vaddr.checked_sub(first.p_offset).ok_or_else(|| {
+                Error::InvalidEdit("the first segment is mapped below its offset".to_string())
+            })?;
+            let memory_end = loads
+                .iter()
+                .map(|phdr| phdr.p_vaddr.saturating_add(phdr.p_memsz))
+                .max()
+                .unwrap_or_default();
+            let vaddr = align_up(
+                memory_end.max((out.len() as u64).saturating_add(delta)),
+                page,
+            );
+            let offset = vaddr - delta;
+            let phdrs_size = (phdrs.len() + 1) * Elf64_Phdr::SIZE;
+            grow(&mut out, offset.saturating_add(phdrs_size as u64), limit)
+                .map_err(|_| Error::ImageTooLarge(offset))?;
+            let mut flags = PF::R;
+            for (index, content) in loaded {
+                let shdr = &mut shdrs[index];
+                let at = align_up(out.len() as u64, shdr.sh_addralign);
+                grow(&mut out, at, limit)?;
+                out.extend_from_slice(&content);
+                shdr.sh_offset = at;
+                shdr.sh_addr = vaddr + (at - offset);
+                shdr.sh_size = content.len() as u64;
+                if shdr.sh_flags & SHF::WRITE != 0 {
+                    flags |= PF::W;
+                }
+                if shdr.sh_flags & SHF::EXECINSTR != 0 {
+                    flags |= PF::X;
+                }
+            }
+            let size = out.len() as u64 - offset;
+            let last = phdrs
+                .iter()
+                .rposition(|phdr| phdr.p_type == PT::LOAD)
+                .expect("a PT_LOAD");
+            phdrs.insert(
+                last + 1,
+                Elf64_Phdr {
+                    p_type: PT::LOAD,
+                    p_flags: flags,
+                    p_offset: offset,
+                    p_vaddr: vaddr,
+                    p_paddr: vaddr,
+                    p_filesz: size,
+                    p_memsz: size,
+                    p_align: page,
+                },
+            );
+            for phdr in phdrs.iter_mut().filter(|phdr| phdr.p_type == PT::PHDR) {
+                phdr.p_offset = offset;
+                phdr.p_vaddr = vaddr;
+                phdr.p_paddr = vaddr;
+                phdr.p_filesz = phdrs_size as u64;
+                phdr.p_memsz = phdrs_size as u64;
+            }
+            header.e_phoff = offset;
+        }
+        for (index, content) in rest {
+            let shdr = &mut shdrs[index];
+            let at = align_up(out.len() as u64, shdr.sh_addralign);
+            grow(&mut out, at, limit)?;
+            out.extend_from_slice(&content);
+            shdr.sh_offset = at;
+            shdr.sh_size = content.len() as u64;
+        }
+
+        // the addresses of the moved tables are known now
+        if let Some(index) = dynamic_index {
+            let link = shdrs[index].sh_link;
+            let dynstr = shdrs
+                .get(link as usize)
+                .ok_or(Error::InvalidSectionIndex(link))?;
+            for entry in entries.iter_mut() {
+                match entry.d_tag {
+                    DT::STRTAB => entry.d_val = dynstr.sh_addr,
+                    DT::STRSZ => entry.d_val = dynstr.sh_size,
+                    DT::HASH => {
+                        if let Some(hash) = new_hash {
+                            entry.d_val = shdrs[hash].sh_addr;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            let shdr = shdrs[index];
+            let bytes: Vec<u8> = entries
+                .iter()
+                .flat_map(|entry| entry.to_bytes(big_endian))
+                .collect();
+            // the entries fit in sh_size, or the section was moved
+            let size = usize::try_from(shdr.sh_size).unwrap_or(usize::MAX);
+            let section = slot(&mut out, shdr.sh_offset, size)?;
+            section.fill(0);
+            section[..bytes.len()].copy_from_slice(&bytes);
+            if dynamic_moves {
+                for phdr in phdrs.iter_mut().filter(|phdr| phdr.p_type == PT::DYNAMIC) {
+                    phdr.p_offset = shdr.sh_offset;
+                    phdr.p_vaddr = shdr.sh_addr;
+                    phdr.p_paddr = shdr.sh_addr;
+                    phdr.p_filesz = shdr.sh_size;
+                    phdr.p_memsz = shdr.sh_size;
+                }
+            }
+        }
+
+        // the counts too big for the file header go to the first section
+        if segment_added {
+            header.e_phnum = match phdrs.len() {
+                phnum if phnum >= x64::PN_XNUM as usize => {
+                    shdrs[0].sh_info = phnum as u32;
+                    x64::PN_XNUM
+                }
+                phnum => phnum as u16,
+            };
+            header.e_phentsize = Elf64_Phdr::SIZE as u16;
+            let table = slot(&mut out, header.e_phoff, phdrs.len() * Elf64_Phdr::SIZE)?;
+            for (phdr, bytes) in phdrs.iter().zip(table.chunks_exact_mut(Elf64_Phdr::SIZE)) {
+                bytes.copy_from_slice(&phdr.to_bytes(big_endian));
+            }
+        }
+        header.e_shnum = match shdrs.len() {
+            shnum if shnum >= SHN::LORESERVE as usize => {
+                shdrs[0].sh_size = shnum as u64;
+                0
+            }
+            shnum => shnum as u16,
+        };
+        let e_shoff = align_up(out.len() as u64, 8);
+        grow(&mut out, e_shoff, limit)?;
+        for shdr in &shdrs {
+            out.extend_from_slice(&shdr.to_bytes(big_endian));
+        }
+        header.e_shoff = e_shoff;
+        header.e_shentsize = Elf64_Shdr::SIZE as u16;
+        out[..x64::SIZE].copy_from_slice(&header.to_bytes(big_endian));
+        Ok(out)
+    }
+}
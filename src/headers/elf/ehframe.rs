@@ -0,0 +1,516 @@
+//! Call frame information used to unwind the stack, `.eh_frame` and `.eh_frame_hdr`
+//!
+//! `.eh_frame` is a list of Common Information Entries (CIE) shared by
+//! Frame Description Entries (FDE), each FDE gives the unwind rules of an
+//! address range. Pointers are encoded as described by the augmentation
+//! string of their CIE. `.eh_frame_hdr` holds a table sorted by address
+//! to find the FDE of a program counter with a binary search.
+//! Sources:
+//! * <https://refspecs.linuxfoundation.org/LSB_5.0.0/LSB-Core-generic/LSB-Core-generic/ehframechpt.html>
+//! * <https://dwarfstd.org/doc/DWARF5.pdf> section 6.4
+use super::data::Data;
+use super::debug::Reader;
+use super::section::{self, Elf64_Shdr};
+use super::x64::x64;
+use crate::alloc_prelude::*;
+use crate::utils::json::{Json, ToJson};
+use crate::{Error, Result};
+use core::fmt;
+
+/// Encodings of the pointers in `.eh_frame` and `.eh_frame_hdr`
+///
+/// The low nibble gives the format of the value, the high nibble what it
+/// is relative to.
+#[allow(non_camel_case_types)]
+pub struct DW_EH_PE {}
+
+impl DW_EH_PE {
+    pub const ABSPTR: u8 = 0x00;
+    pub const ULEB128: u8 = 0x01;
+    pub const UDATA2: u8 = 0x02;
+    pub const UDATA4: u8 = 0x03;
+    pub const UDATA8: u8 = 0x04;
+    pub const SLEB128: u8 = 0x09;
+    pub const SDATA2: u8 = 0x0a;
+    pub const SDATA4: u8 = 0x0b;
+    pub const SDATA8: u8 = 0x0c;
+
+    pub const PCREL: u8 = 0x10;
+    pub const TEXTREL: u8 = 0x20;
+    pub const DATAREL: u8 = 0x30;
+    pub const FUNCREL: u8 = 0x40;
+    pub const ALIGNED: u8 = 0x50;
+
+    /// The value is the address of the pointer
+    pub const INDIRECT: u8 = 0x80;
+    /// No value is present
+    pub const OMIT: u8 = 0xff;
+}
+
+/// Read a pointer with the given encoding
+///
+/// `address` is the virtual address of the start of the reader data, used
+/// by pc relative pointers, `data_base` is used by data relative ones.
+/// Indirect pointers are returned without being dereferenced.
+fn pointer(reader: &mut Reader<'_>, encoding: u8, address: u64, data_base: u64) -> Option<u64> {
+    if encoding == DW_EH_PE::OMIT {
+        return Some(0);
+    }
+    let position = address.wrapping_add(reader.offset as u64);
+    let value = match encoding & 0x0f {
+        DW_EH_PE::ABSPTR | DW_EH_PE::UDATA8 | DW_EH_PE::SDATA8 => reader.u64()?,
+        DW_EH_PE::ULEB128 => reader.uleb128()?,
+        DW_EH_PE::UDATA2 => u64::from(reader.u16()?),
+        DW_EH_PE::UDATA4 => u64::from(reader.u32()?),
+        DW_EH_PE::SLEB128 => reader.sleb128()? as u64,
+        DW_EH_PE::SDATA2 => reader.u16()? as i16 as u64,
+        DW_EH_PE::SDATA4 => reader.u32()? as i32 as u64,
+        _ => return None,
+    };
+    let base = match encoding & 0x70 {
+        DW_EH_PE::PCREL => position,
+        DW_EH_PE::DATAREL => data_base,
+        _ => 0,
+    };
+    Some(base.wrapping_add(value))
+}
+
+/// A Common Information Entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cie<'a> {
+    /// offset of the entry in the section
+    pub offset: u64,
+    /// size of the entry after its length field
+    pub length: u64,
+    pub version: u8,
+    /// letters describing the augmentation data, ex: `zR` or `zPLR`
+    pub augmentation: &'a str,
+    pub code_alignment_factor: u64,
+    pub data_alignment_factor: i64,
+    pub return_address_register: u64,
+    /// encoding of the FDE addresses, `R` augmentation
+    pub fde_encoding: u8,
+    /// encoding of the FDE LSDA pointers, `L` augmentation
+    pub lsda_encoding: u8,
+    /// address of the personality routine, `P` augmentation
+    pub personality: Option<u64>,
+    /// the frames are signal handlers, `S` augmentation
+    pub signal_frame: bool,
+    /// initial call frame instructions
+    pub instructions: &'a [u8],
+}
+
+/// A Frame Description Entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fde<'a> {
+    /// offset of the entry in the section
+    pub offset: u64,
+    /// size of the entry after its length field
+    pub length: u64,
+    /// value of the CIE pointer, the distance back to the CIE
+    pub cie_pointer: u32,
+    /// offset of the CIE of the entry in the section
+    pub cie: u64,
+    /// first address covered by the entry
+    pub pc_begin: u64,
+    /// number of bytes covered by the entry
+    pub pc_range: u64,
+    /// address of the language specific data area, used by C++ exceptions
+    pub lsda: Option<u64>,
+    /// call frame instructions
+    pub instructions: &'a [u8],
+}
+
+impl Fde<'_> {
+    /// First address after the range covered by the entry
+    pub fn pc_end(&self) -> u64 {
+        self.pc_begin.wrapping_add(self.pc_range)
+    }
+}
+
+/// The content of the `.eh_frame` section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EhFrame<'a> {
+    /// virtual address of the section
+    pub address: u64,
+    pub cies: Vec<Cie<'a>>,
+    /// entries in section order
+    pub fdes: Vec<Fde<'a>>,
+}
+
+impl<'a> EhFrame<'a> {
+    /// The CIE stored at `offset` of the section
+    pub fn cie(&self, offset: u64) -> Option<&Cie<'a>> {
+        self.cies.iter().find(|cie| cie.offset == offset)
+    }
+
+    /// The FDE covering the address `pc`
+    pub fn fde_for(&self, pc: u64) -> Option<&Fde<'a>> {
+        self.fdes
+            .iter()
+            .find(|fde| fde.pc_begin <= pc && pc < fde.pc_end())
+    }
+}
+
+/// Parse the augmentation and the fields of a CIE, after its id
+fn cie<'a>(reader: &mut Reader<'a>, address: u64, end: usize) -> Option<Cie<'a>> {
+    let version = reader.u8()?;
+    let augmentation = reader.cstr()?;
+    // the old GCC "eh" augmentation stores a pointer to an exception table
+    if augmentation.contains("eh") {
+        reader.u64()?;
+    }
+    let code_alignment_factor = reader.uleb128()?;
+    let data_alignment_factor = reader.sleb128()?;
+    let return_address_register = if version == 1 {
+        u64::from(reader.u8()?)
+    } else {
+        reader.uleb128()?
+    };
+    let mut cie = Cie {
+        offset: 0,
+        length: 0,
+        version,
+        augmentation,
+        code_alignment_factor,
+        data_alignment_factor,
+        return_address_register,
+        fde_encoding: DW_EH_PE::ABSPTR,
+        lsda_encoding: DW_EH_PE::OMIT,
+        personality: None,
+        signal_frame: false,
+        instructions: &[],
+    };
+    if let Some(letters) = augmentation.strip_prefix('z') {
+        let size = reader.uleb128()?;
+        let data_end = reader.offset.checked_add(usize::try_from(size).ok()?)?;
+        for letter in letters.chars() {
+            match letter {
+                'L' => cie.lsda_encoding = reader.u8()?,
+                'R' => cie.fde_encoding = reader.u8()?,
+                'P' => {
+                    let encoding = reader.u8()?;
+                    cie.personality = Some(pointer(reader, encoding, address, 0)?);
+                }
+                'S' => cie.signal_frame = true,
+                // the size of the data lets the unknown letters be skipped
+                _ => break,
+            }
+        }
+        reader.offset = data_end;
+    }
+    cie.instructions = reader.data.get(reader.offset..end)?;
+    Some(cie)
+}
+
+/// Parse the entries of an `.eh_frame` section loaded at `address`
+///
+/// Parsing stops at the zero terminator or at the end of the section,
+/// fails if an entry is truncated or refers to an unknown CIE.
+pub fn parse(data: &[u8], address: u64, big_endian: bool) -> Result<EhFrame<'_>> {
+    let mut frame = EhFrame {
+        address,
+        cies: Vec::new(),
+        fdes: Vec::new(),
+    };
+    let mut reader = Reader::new(data, big_endian);
+    while reader.offset < data.len() {
+        let offset = reader.offset as u64;
+        let truncated = Error::TruncatedDwarf {
+            section: ".eh_frame",
+            offset,
+        };
+        let length = match reader.u32().ok_or(truncated.clone())? {
+            0 => break,
+            0xffff_ffff => reader.u64().ok_or(truncated.clone())?,
+            length => u64::from(length),
+        };
+        let end = usize::try_from(length)
+            .ok()
+            .and_then(|length| reader.offset.checked_add(length))
+            .filter(|end| *end <= data.len())
+            .ok_or(truncated.clone())?;
+        let id_offset = reader.offset as u64;
+        let id = reader.u32().ok_or(truncated.clone())?;
+        if id == 0 {
+            let mut cie = cie(&mut reader, address, end).ok_or(truncated)?;
+            cie.offset = offset;
+            cie.length = length;
+            frame.cies.push(cie);
+        } else {
+            let cie_offset = id_offset.wrapping_sub(u64::from(id));
+            let cie = frame.cie(cie_offset).ok_or(Error::TruncatedDwarf {
+                section: ".eh_frame",
+                offset: id_offset,
+            })?;
+            let (encoding, lsda_encoding) = (cie.fde_encoding, cie.lsda_encoding);
+            let has_data = cie.augmentation.starts_with('z');
+            let fde = (|| {
+                let pc_begin = pointer(&mut reader, encoding, address, 0)?;
+                // the range has the format of the address but is never relative
+                let pc_range = pointer(&mut reader, encoding & 0x0f, address, 0)?;
+                let mut lsda = None;
+                if has_data {
+                    let size = reader.uleb128()?;
+                    let data_end = reader.offset.checked_add(usize::try_from(size).ok()?)?;
+                    if lsda_encoding != DW_EH_PE::OMIT && size > 0 {
+                        lsda = Some(pointer(&mut reader, lsda_encoding, address, 0)?);
+                    }
+                    reader.offset = data_end;
+                }
+                Some(Fde {
+                    offset,
+                    length,
+                    cie_pointer: id,
+                    cie: cie_offset,
+                    pc_begin,
+                    pc_range,
+                    lsda,
+                    instructions: data.get(reader.offset..end)?,
+                })
+            })();
+            frame.fdes.push(fde.ok_or(truncated)?);
+        }
+        reader.offset = end;
+    }
+    Ok(frame)
+}
+
+/// The content of the `.eh_frame_hdr` section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EhFrameHdr {
+    /// virtual address of the section
+    pub address: u64,
+    pub version: u8,
+    /// address of the `.eh_frame` section
+    pub eh_frame: u64,
+    /// initial location and address of the FDEs, sorted by location
+    pub table: Vec<(u64, u64)>,
+}
+
+/// Parse an `.eh_frame_hdr` section loaded at `address`
+pub fn parse_hdr(data: &[u8], address: u64, big_endian: bool) -> Result<EhFrameHdr> {
+    let truncated = Error::TruncatedDwarf {
+        section: ".eh_frame_hdr",
+        offset: 0,
+    };
+    let mut reader = Reader::new(data, big_endian);
+    let hdr = (|| {
+        let version = reader.u8()?;
+        let eh_frame_encoding = reader.u8()?;
+        let count_encoding = reader.u8()?;
+        let table_encoding = reader.u8()?;
+        let eh_frame = pointer(&mut reader, eh_frame_encoding, address, address)?;
+        let count = match count_encoding {
+            DW_EH_PE::OMIT => 0,
+            encoding => pointer(&mut reader, encoding, address, address)?,
+        };
+        let mut table = Vec::new();
+        if table_encoding != DW_EH_PE::OMIT {
+            for _ in 0..count {
+                let location = pointer(&mut reader, table_encoding, address, address)?;
+                let fde = pointer(&mut reader, table_encoding, address, address)?;
+                table.push((location, fde));
+            }
+        }
+        Some(EhFrameHdr {
+            address,
+            version,
+            eh_frame,
+            table,
+        })
+    })();
+    hdr.ok_or(truncated)
+}
+
+/// Find the first section with the given name
+fn section_named<'a>(
+    data: &'a [u8],
+    header: &x64,
+    name: &str,
+) -> Result<Option<alloc::borrow::Cow<'a, Elf64_Shdr>>> {
+    let names = section::names(data, header)?;
+    Ok(section::from_bytes(data, header)?
+        .find(|shdr| shdr.name(&names).is_ok_and(|found| found == name)))
+}
+
+fn section_bytes<'a>(data: &'a [u8], shdr: &Elf64_Shdr) -> Result<&'a [u8]> {
+    shdr.data(data).ok_or(Error::OutOfBounds {
+        offset: shdr.sh_offset,
+        size: shdr.sh_size,
+    })
+}
+
+/// Parse the `.eh_frame` section of a file, None if there is none
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64) -> Result<Option<EhFrame<'a>>> {
+    let shdr = match section_named(data, header, ".eh_frame")? {
+        Some(shdr) => shdr,
+        None => return Ok(None),
+    };
+    let bytes = section_bytes(data, &shdr)?;
+    parse(bytes, shdr.sh_addr, Data::is_big_endian(&header.e_ident)).map(Some)
+}
+
+/// Parse the `.eh_frame_hdr` section of a file, None if there is none
+pub fn hdr_from_bytes(data: &[u8], header: &x64) -> Result<Option<EhFrameHdr>> {
+    let shdr = match section_named(data, header, ".eh_frame_hdr")? {
+        Some(shdr) => shdr,
+        None => return Ok(None),
+    };
+    let bytes = section_bytes(data, &shdr)?;
+    parse_hdr(bytes, shdr.sh_addr, Data::is_big_endian(&header.e_ident)).map(Some)
+}
+
+/// Unwind sections ready to be printed
+///
+/// The entries are listed like `readelf --debug-dump=frames-interp`
+/// without the decoded instructions.
+pub struct UnwindTable<'a> {
+    /// the whole file
+    pub data: &'a [u8],
+    /// the file header
+    pub header: &'a x64,
+}
+
+impl fmt::Display for UnwindTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let frame = match from_bytes(self.data, self.header) {
+            Ok(Some(frame)) => frame,
+            Ok(None) => return writeln!(f, "\nThere is no .eh_frame section in this file."),
+            Err(err) => return writeln!(f, "\nFailed to parse .eh_frame: {}", err),
+        };
+        writeln!(f, "Contents of the .eh_frame section:\n\n")?;
+        let mut fdes = frame.fdes.iter().peekable();
+        for cie in &frame.cies {
+            writeln!(
+                f,
+                "{:08x} {:016x} {:08x} CIE \"{}\" cf={} df={} ra={}",
+                cie.offset,
+                cie.length,
+                0,
+                cie.augmentation,
+                cie.code_alignment_factor,
+                cie.data_alignment_factor,
+                cie.return_address_register
+            )?;
+            if let Some(personality) = cie.personality {
+                writeln!(f, "  Personality: 0x{:x}", personality)?;
+            }
+            writeln!(f)?;
+            // print the FDEs stored between this CIE and the next one
+            let next = frame
+                .cies
+                .iter()
+                .map(|other| other.offset)
+                .find(|offset| *offset > cie.offset)
+                .unwrap_or(u64::MAX);
+            while let Some(fde) = fdes.next_if(|fde| fde.offset < next) {
+                writeln!(
+                    f,
+                    "{:08x} {:016x} {:08x} FDE cie={:08x} pc={:016x}..{:016x}",
+                    fde.offset,
+                    fde.length,
+                    fde.cie_pointer,
+                    fde.cie,
+                    fde.pc_begin,
+                    fde.pc_end()
+                )?;
+                if let Some(lsda) = fde.lsda {
+                    writeln!(f, "  LSDA: 0x{:x}", lsda)?;
+                }
+                writeln!(f)?;
+            }
+        }
+        match hdr_from_bytes(self.data, self.header) {
+            Ok(Some(hdr)) => {
+                writeln!(
+                    f,
+                    "The .eh_frame_hdr section at 0x{:x} indexes {} FDEs of .eh_frame at 0x{:x}",
+                    hdr.address,
+                    hdr.table.len(),
+                    hdr.eh_frame
+                )?;
+                // each FDE of the table should be at the start of an entry covering its location
+                let missing = hdr
+                    .table
+                    .iter()
+                    .filter(|(location, fde)| {
+                        let offset = fde.wrapping_sub(frame.address);
+                        !frame
+                            .fdes
+                            .iter()
+                            .any(|entry| entry.offset == offset && entry.pc_begin == *location)
+                    })
+                    .count();
+                if missing > 0 {
+                    writeln!(f, "Warning: {} entries don't match an FDE", missing)?;
+                }
+            }
+            Ok(None) => (),
+            Err(err) => writeln!(f, "Failed to parse .eh_frame_hdr: {}", err)?,
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for Cie<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("offset", Json::from(self.offset)),
+            ("version", Json::from(self.version)),
+            ("augmentation", Json::from(self.augmentation)),
+            (
+                "code_alignment_factor",
+                Json::from(self.code_alignment_factor),
+            ),
+            (
+                "data_alignment_factor",
+                Json::from(self.data_alignment_factor),
+            ),
+            (
+                "return_address_register",
+                Json::from(self.return_address_register),
+            ),
+            ("personality", Json::from(self.personality)),
+            ("signal_frame", Json::from(self.signal_frame)),
+        ])
+    }
+}
+
+impl ToJson for Fde<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("offset", Json::from(self.offset)),
+            ("cie", Json::from(self.cie)),
+            ("pc_begin", Json::from(self.pc_begin)),
+            ("pc_end", Json::from(self.pc_end())),
+            ("lsda", Json::from(self.lsda)),
+        ])
+    }
+}
+
+impl ToJson for UnwindTable<'_> {
+    fn to_json(&self) -> Json {
+        let frame = match from_bytes(self.data, self.header) {
+            Ok(Some(frame)) => frame,
+            Ok(None) => return Json::Null,
+            Err(err) => return Json::error(err),
+        };
+        let hdr = match hdr_from_bytes(self.data, self.header) {
+            Ok(hdr) => Json::from(hdr.map(|hdr| {
+                Json::object([
+                    ("address", Json::from(hdr.address)),
+                    ("eh_frame", Json::from(hdr.eh_frame)),
+                    ("entries", Json::from(hdr.table.len())),
+                ])
+            })),
+            Err(err) => Json::error(err),
+        };
+        Json::object([
+            ("address", Json::from(frame.address)),
+            ("cies", Json::array(frame.cies.iter().map(ToJson::to_json))),
+            ("fdes", Json::array(frame.fdes.iter().map(ToJson::to_json))),
+            ("eh_frame_hdr", hdr),
+        ])
+    }
+}
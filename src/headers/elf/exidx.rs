@@ -0,0 +1,672 @@
+//! ARM exception handling tables, `.ARM.exidx` and `.ARM.extab`
+//!
+//! 32 bit ARM files don't unwind with `.eh_frame` but with the tables of
+//! the Exception Handling ABI (EHABI). `.ARM.exidx` holds a pair of words
+//! per function, sorted by address: the start of the function then either
+//! EXIDX_CANTUNWIND, the unwind instructions themselves or the location
+//! of a longer entry in `.ARM.extab`. An entry of `.ARM.extab` starts with
+//! its personality routine, one of the compact models of the ABI or a
+//! routine of the language runtime, followed by the instructions that
+//! restore the registers of the caller.
+//!
+//! The locations are 31 bit offsets relative to the word holding them.
+//! In relocatable files they are resolved with the R_ARM_PREL31
+//! relocations of the section, like readelf does.
+//! Sources:
+//! * <https://github.com/ARM-software/abi-aa/blob/main/ehabi32/ehabi32.rst>
+//! * <https://github.com/bminor/binutils-gdb/blob/master/binutils/readelf.c>
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::json::{Json, ToJson};
+use crate::utils::read_u32;
+use crate::{Error, Result};
+use alloc::borrow::Cow;
+use core::fmt;
+
+use super::data::Data;
+use super::debug::Reader;
+use super::machine::Machine;
+use super::section::{SHF, SHT};
+use super::strtab::{StrTab, CORRUPT};
+use super::symbols::STT;
+use super::table::Table;
+use super::types::Type;
+use super::x86::{self as elf32, x86, Elf32_Rel, Elf32_Shdr, Elf32_Sym};
+
+/// Type of the index sections, SHT_ARM_EXIDX
+pub const SHT_ARM_EXIDX: u32 = 0x7000_0001;
+/// Second word of an index entry for a function that can't be unwound
+pub const EXIDX_CANTUNWIND: u32 = 1;
+/// Relocation of the 31 bit offsets, R_ARM_PREL31
+pub const R_ARM_PREL31: u32 = 42;
+
+/// The personality routines of GNU runtimes, whose entries hold unwind
+/// instructions like the compact model 1
+const GNU_PERSONALITIES: [&str; 4] = [
+    "__gcc_personality_v0",
+    "__gxx_personality_v0",
+    "__gcj_personality_v0",
+    "__gnu_objc_personality_v0",
+];
+
+/// An unwind instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// vsp = vsp + value
+    AddVsp(u64),
+    /// vsp = vsp - value
+    SubVsp(u64),
+    /// the function can't be unwound
+    RefuseUnwind,
+    /// pop the core registers of the mask, bit n for rn
+    Pop(u16),
+    /// vsp = the register
+    SetVsp(u8),
+    /// end of the instructions
+    Finish,
+    /// pop `count` VFP double registers from `first`, stored by FSTMFDX
+    /// when `fstmfdx` and by VPUSH otherwise
+    PopVfp { first: u8, count: u8, fstmfdx: bool },
+    /// pop `count` iWMMXt wR registers from `first`
+    PopWmmx { first: u8, count: u8 },
+    /// pop the iWMMXt wCGR registers of the mask
+    PopWcgr(u8),
+    /// reserved encoding
+    Spare,
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn range(f: &mut fmt::Formatter<'_>, name: &str, first: u8, count: u8) -> fmt::Result {
+            match count {
+                1 => write!(f, "pop {{{}{}}}", name, first),
+                _ => write!(
+                    f,
+                    "pop {{{}{}-{}{}}}",
+                    name,
+                    first,
+                    name,
+                    u32::from(first) + u32::from(count) - 1
+                ),
+            }
+        }
+        fn mask(f: &mut fmt::Formatter<'_>, name: &str, mask: u16) -> fmt::Result {
+            let registers: Vec<String> = (0..16)
+                .filter(|bit| mask & (1 << bit) != 0)
+                .map(|bit| format!("{}{}", name, bit))
+                .collect();
+            write!(f, "pop {{{}}}", registers.join(", "))
+        }
+        match *self {
+            Op::AddVsp(value) => write!(f, "vsp = vsp + {}", value),
+            Op::SubVsp(value) => write!(f, "vsp = vsp - {}", value),
+            Op::RefuseUnwind => write!(f, "refuse to unwind"),
+            Op::Pop(registers) => mask(f, "r", registers),
+            Op::SetVsp(register) => write!(f, "vsp = r{}", register),
+            Op::Finish => write!(f, "finish"),
+            Op::PopVfp { first, count, .. } => range(f, "D", first, count),
+            Op::PopWmmx { first, count } => range(f, "wR", first, count),
+            Op::PopWcgr(registers) => mask(f, "wCGR", registers.into()),
+            Op::Spare => write!(f, "[Spare]"),
+        }
+    }
+}
+
+/// An unwind instruction with its encoding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    /// the bytes of the instruction
+    pub bytes: Vec<u8>,
+    /// what it does
+    pub op: Op,
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes: Vec<String> = self.bytes.iter().map(|b| format!("0x{:02x}", b)).collect();
+        write!(f, "{:<9} {}", bytes.join(" "), self.op)
+    }
+}
+
+/// Decode a sequence of unwind instructions
+///
+/// An instruction missing its operands at the end is decoded as spare.
+pub fn decode(bytes: &[u8]) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    let mut offset = 0;
+    while let Some(&byte) = bytes.get(offset) {
+        let next = bytes.get(offset + 1).copied();
+        // the registers of the operand byte, ssss and cccc
+        let (first, count) = next.map_or((0, 0), |next| (next >> 4, (next & 0x0f) + 1));
+        let (size, op) = match (byte, next) {
+            (0x00..=0x3f, _) => (1, Op::AddVsp((u64::from(byte) << 2) + 4)),
+            (0x40..=0x7f, _) => (1, Op::SubVsp((u64::from(byte & 0x3f) << 2) + 4)),
+            (0x80, Some(0)) => (2, Op::RefuseUnwind),
+            (0x80..=0x8f, Some(next)) => {
+                let mask = (u16::from(byte & 0x0f) << 8 | u16::from(next)) << 4;
+                (2, Op::Pop(mask))
+            }
+            (0x9d | 0x9f, _) => (1, Op::Spare),
+            (0x90..=0x9f, _) => (1, Op::SetVsp(byte & 0x0f)),
+            (0xa0..=0xaf, _) => {
+                let mask = ((1u16 << ((byte & 0x07) + 1)) - 1) << 4;
+                let link = if byte & 0x08 != 0 { 1 << 14 } else { 0 };
+                (1, Op::Pop(mask | link))
+            }
+            (0xb0, _) => (1, Op::Finish),
+            (0xb1, Some(next)) if next != 0 && next & 0xf0 == 0 => (2, Op::Pop(next.into())),
+            (0xb2, Some(_)) => {
+                let mut reader = Reader::new(&bytes[offset + 1..], false);
+                match reader.uleb128() {
+                    Some(value) => (
+                        1 + reader.offset,
+                        Op::AddVsp(0x204u64.saturating_add(value.saturating_mul(4))),
+                    ),
+                    None => (bytes.len() - offset, Op::Spare),
+                }
+            }
+            (0xb3, Some(_)) => (
+                2,
+                Op::PopVfp {
+                    first,
+                    count,
+                    fstmfdx: true,
+                },
+            ),
+            (0xb8..=0xbf, _) => (
+                1,
+                Op::PopVfp {
+                    first: 8,
+                    count: (byte & 0x07) + 1,
+                    fstmfdx: true,
+                },
+            ),
+            (0xc0..=0xc5, _) => (
+                1,
+                Op::PopWmmx {
+                    first: 10,
+                    count: (byte & 0x07) + 1,
+                },
+            ),
+            (0xc6, Some(_)) => (2, Op::PopWmmx { first, count }),
+            (0xc7, Some(next)) if next != 0 && next & 0xf0 == 0 => (2, Op::PopWcgr(next)),
+            (0xc8, Some(_)) => (
+                2,
+                Op::PopVfp {
+                    first: first + 16,
+                    count,
+                    fstmfdx: false,
+                },
+            ),
+            (0xc9, Some(_)) => (
+                2,
+                Op::PopVfp {
+                    first,
+                    count,
+                    fstmfdx: false,
+                },
+            ),
+            (0xd0..=0xd7, _) => (
+                1,
+                Op::PopVfp {
+                    first: 8,
+                    count: (byte & 0x07) + 1,
+                    fstmfdx: false,
+                },
+            ),
+            (0x80..=0x8f | 0xb1..=0xb3 | 0xc6..=0xc9, None) => (1, Op::Spare),
+            (0xb1 | 0xc7, Some(_)) => (2, Op::Spare),
+            _ => (1, Op::Spare),
+        };
+        instructions.push(Instruction {
+            bytes: bytes[offset..offset + size].to_vec(),
+            op,
+        });
+        offset += size;
+    }
+    instructions
+}
+
+/// The personality routine of an entry of `.ARM.extab`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Personality<'a> {
+    /// one of the routines of the ABI, `__aeabi_unwind_cpp_pr<index>`
+    Compact(u8),
+    /// a routine of the language runtime
+    Routine {
+        /// address of the routine
+        address: u64,
+        /// name of the routine, if a symbol is defined there
+        name: Option<&'a str>,
+    },
+}
+
+/// How a function is unwound
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Unwind<'a> {
+    /// the function can't be unwound, EXIDX_CANTUNWIND
+    CantUnwind,
+    /// the instructions are stored in the index entry, with the compact
+    /// model 0
+    Inline {
+        /// the second word of the index entry
+        word: u32,
+        /// the unwind instructions
+        instructions: Vec<Instruction>,
+    },
+    /// the instructions are in an entry of `.ARM.extab`
+    Table {
+        /// address of the entry
+        address: u64,
+        /// the personality routine of the entry
+        personality: Personality<'a>,
+        /// the unwind instructions, empty if the personality routine
+        /// isn't known
+        instructions: Vec<Instruction>,
+    },
+}
+
+/// An entry of `.ARM.exidx`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry<'a> {
+    /// start address of the function
+    pub function: u64,
+    /// name of the function, if a symbol is defined there
+    pub name: Option<&'a str>,
+    /// how it is unwound
+    pub unwind: Unwind<'a>,
+}
+
+/// A `.ARM.exidx` section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExceptionIndex<'a> {
+    /// name of the section
+    pub name: ByteStr<'a>,
+    /// offset of the section in the file
+    pub offset: u64,
+    /// address of the section
+    pub address: u64,
+    /// the entries, one per function
+    pub entries: Vec<Entry<'a>>,
+}
+
+/// Sign extend a 31 bit offset
+fn prel31(word: u32) -> i64 {
+    i64::from(((word << 1) as i32) >> 1)
+}
+
+/// The location a 31 bit offset points to
+struct Target<'a> {
+    /// index of the section, if it is known
+    section: Option<usize>,
+    address: u64,
+    /// name of the symbol the relocation points to
+    symbol: Option<&'a str>,
+}
+
+/// The sections, symbols and relocations of an ELF32 file
+struct Context<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+    relocatable: bool,
+    shdrs: Vec<Cow<'a, Elf32_Shdr>>,
+    symbols: Vec<Cow<'a, Elf32_Sym>>,
+    strtab: StrTab<'a>,
+    /// the R_ARM_PREL31 relocations and the section they apply to
+    relocations: Vec<(usize, Cow<'a, Elf32_Rel>)>,
+}
+
+impl<'a> Context<'a> {
+    fn new(data: &'a [u8], header: &x86) -> Result<Self> {
+        let big_endian = Data::is_big_endian(&header.e_ident);
+        let shdrs: Vec<_> = elf32::section_headers(data, header)?.collect();
+        let table = |shdr: &Elf32_Shdr, size| {
+            let entsize = match shdr.sh_entsize {
+                0 => size,
+                entsize => u64::from(entsize),
+            };
+            let num = u64::from(shdr.sh_size) / entsize.max(1);
+            (u64::from(shdr.sh_offset), entsize, num)
+        };
+        let mut context = Context {
+            data,
+            big_endian,
+            relocatable: Type::from(header.e_type) == Type::Rel,
+            symbols: Vec::new(),
+            strtab: StrTab::default(),
+            relocations: Vec::new(),
+            shdrs: Vec::new(),
+        };
+        if let Some(symtab) = shdrs.iter().find(|shdr| shdr.sh_type == SHT::SYMTAB) {
+            let (offset, entsize, num) = table(symtab, 16);
+            context.symbols = Table::parse(data, offset, entsize, num, big_endian)?.collect();
+            context.strtab = shdrs
+                .get(symtab.sh_link as usize)
+                .and_then(|strtab| strtab.data(data))
+                .map(StrTab::new)
+                .unwrap_or_default();
+        }
+        if context.relocatable {
+            for shdr in shdrs.iter().filter(|shdr| shdr.sh_type == SHT::REL) {
+                let (offset, entsize, num) = table(shdr, 8);
+                let relocations =
+                    Table::<Elf32_Rel>::parse(data, offset, entsize, num, big_endian)?;
+                context.relocations.extend(
+                    relocations
+                        .filter(|rel| rel.r_type() == R_ARM_PREL31)
+                        .map(|rel| (shdr.sh_info as usize, rel)),
+                );
+            }
+        }
+        context.shdrs = shdrs;
+        Ok(context)
+    }
+
+    /// The word at `offset` of a section
+    fn word(&self, section: usize, offset: u64) -> Option<u32> {
+        let bytes = self.shdrs.get(section)?.data(self.data)?;
+        read_u32(bytes, usize::try_from(offset).ok()?, self.big_endian)
+    }
+
+    /// Resolve the 31 bit offset stored in the word at `offset` of a section
+    fn target(&self, section: usize, offset: u64, word: u32) -> Option<Target<'a>> {
+        let relocation = self
+            .relocations
+            .iter()
+            .find(|(applies_to, rel)| *applies_to == section && u64::from(rel.r_offset) == offset);
+        if let Some((_, rel)) = relocation {
+            // S + A, the place is only known once linked
+            let symbol = self.symbols.get(rel.sym() as usize)?;
+            let is_section = symbol.st_info & 0x0f == STT::SECTION;
+            return Some(Target {
+                section: Some(symbol.st_shndx as usize),
+                address: (u64::from(symbol.st_value) as i64 + prel31(word)) as u64 & 0xffff_ffff,
+                symbol: match is_section {
+                    true => None,
+                    false => self.strtab.get(symbol.st_name as usize).ok(),
+                },
+            });
+        }
+        let place = u64::from(self.shdrs.get(section)?.sh_addr) + offset;
+        let address = (place as i64 + prel31(word)) as u64 & 0xffff_ffff;
+        let section = match self.relocatable {
+            true => Some(section),
+            false => self.shdrs.iter().position(|shdr| {
+                shdr.sh_type != SHT::NULL
+                    && u64::from(shdr.sh_flags) & SHF::ALLOC != 0
+                    && address.wrapping_sub(u64::from(shdr.sh_addr)) < u64::from(shdr.sh_size)
+            }),
+        };
+        Some(Target {
+            section,
+            address,
+            symbol: None,
+        })
+    }
+
+    /// Name of the function or routine starting at `address`
+    fn name(&self, target: &Target<'a>) -> Option<&'a str> {
+        if target.symbol.is_some() {
+            return target.symbol;
+        }
+        self.symbols
+            .iter()
+            .filter(|symbol| matches!(symbol.st_info & 0x0f, STT::NOTYPE | STT::FUNC))
+            .filter(|symbol| symbol.st_name != 0)
+            .filter(|symbol| !self.relocatable || target.section == Some(symbol.st_shndx as usize))
+            .filter(|symbol| u64::from(symbol.st_value & !1) == target.address)
+            // functions before the other symbols
+            .min_by_key(|symbol| symbol.st_info & 0x0f != STT::FUNC)
+            .and_then(|symbol| self.strtab.get(symbol.st_name as usize).ok())
+    }
+
+    /// The bytes of the instructions starting with the `skip` byte of the
+    /// word at `offset`, followed by `count` words
+    fn instructions(
+        &self,
+        section: usize,
+        offset: u64,
+        skip: usize,
+        count: u32,
+    ) -> Option<Vec<u8>> {
+        let mut bytes = self.word(section, offset)?.to_be_bytes()[skip..].to_vec();
+        for index in 1..=u64::from(count) {
+            bytes.extend(self.word(section, offset + 4 * index)?.to_be_bytes());
+        }
+        Some(bytes)
+    }
+
+    /// Decode the entry of `.ARM.extab` at a location
+    fn table(&self, target: &Target<'a>) -> Option<Unwind<'a>> {
+        let section = target.section?;
+        let offset = target
+            .address
+            .checked_sub(u64::from(self.shdrs.get(section)?.sh_addr))?;
+        let word = self.word(section, offset)?;
+        let (personality, bytes) = if word & 0x8000_0000 != 0 {
+            let index = ((word >> 24) & 0x0f) as u8;
+            let bytes = match index {
+                0 => self.instructions(section, offset, 1, 0)?,
+                1 | 2 => self.instructions(section, offset, 2, (word >> 16) & 0xff)?,
+                _ => Vec::new(),
+            };
+            (Personality::Compact(index), bytes)
+        } else {
+            let routine = self.target(section, offset, word)?;
+            let name = self.name(&routine);
+            let bytes = match name {
+                Some(name) if GNU_PERSONALITIES.contains(&name) => {
+                    let count = self.word(section, offset + 4)? >> 24;
+                    self.instructions(section, offset + 4, 1, count)?
+                }
+                _ => Vec::new(),
+            };
+            let personality = Personality::Routine {
+                address: routine.address,
+                name,
+            };
+            (personality, bytes)
+        };
+        Some(Unwind::Table {
+            address: target.address,
+            personality,
+            instructions: decode(&bytes),
+        })
+    }
+
+    /// Decode the entries of the index section at `section`
+    fn index(&self, section: usize, name: ByteStr<'a>) -> Result<ExceptionIndex<'a>> {
+        let shdr = &self.shdrs[section];
+        let size = u64::from(shdr.sh_size);
+        let mut entries = Vec::new();
+        for offset in (0..size).step_by(8) {
+            let invalid = Error::InvalidUnwindEntry(offset);
+            let entry = (|| {
+                let (first, second) =
+                    (self.word(section, offset)?, self.word(section, offset + 4)?);
+                if first & 0x8000_0000 != 0 {
+                    return None;
+                }
+                let function = self.target(section, offset, first)?;
+                let unwind = if second == EXIDX_CANTUNWIND {
+                    Unwind::CantUnwind
+                } else if second & 0x8000_0000 != 0 {
+                    // only the compact model 0 fits in the index entry
+                    if second & 0x0f00_0000 != 0 {
+                        return None;
+                    }
+                    Unwind::Inline {
+                        word: second,
+                        instructions: decode(&second.to_be_bytes()[1..]),
+                    }
+                } else {
+                    self.table(&self.target(section, offset + 4, second)?)?
+                };
+                Some(Entry {
+                    function: function.address,
+                    name: self.name(&function),
+                    unwind,
+                })
+            })();
+            entries.push(entry.ok_or(invalid)?);
+        }
+        Ok(ExceptionIndex {
+            name,
+            offset: u64::from(shdr.sh_offset),
+            address: u64::from(shdr.sh_addr),
+            entries,
+        })
+    }
+}
+
+/// Parse the `.ARM.exidx` sections of a 32 bit ARM file
+///
+/// Returns no section for the other machines. Fails if a section header
+/// can't be read or if an entry or the `.ARM.extab` entry it points to
+/// is truncated or invalid.
+pub fn from_bytes<'a>(data: &'a [u8], header: &x86) -> Result<Vec<ExceptionIndex<'a>>> {
+    if Machine::from_u16(header.e_machine) != Machine::Arm {
+        return Ok(Vec::new());
+    }
+    let context = Context::new(data, header)?;
+    let names = elf32::section_names(data, header).unwrap_or_default();
+    context
+        .shdrs
+        .iter()
+        .enumerate()
+        .filter(|(_, shdr)| shdr.sh_type == SHT_ARM_EXIDX)
+        .map(|(index, shdr)| context.index(index, shdr.name(&names).unwrap_or(CORRUPT)))
+        .collect()
+}
+
+/// The ARM unwind tables ready to be printed like `readelf -u`
+pub struct ExceptionTables<'a> {
+    /// the whole file
+    pub data: &'a [u8],
+    /// the file header
+    pub header: &'a x86,
+}
+
+impl fmt::Display for Entry<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}", self.function)?;
+        if let Some(name) = self.name {
+            write!(f, " <{}>", name)?;
+        }
+        let instructions = match &self.unwind {
+            Unwind::CantUnwind => return writeln!(f, ": 0x1 [cantunwind]"),
+            Unwind::Inline { word, instructions } => {
+                writeln!(f, ": {:#x}", word)?;
+                writeln!(f, "  Compact model index: 0")?;
+                instructions
+            }
+            Unwind::Table {
+                address,
+                personality,
+                instructions,
+            } => {
+                writeln!(f, ": @{:#x}", address)?;
+                match personality {
+                    Personality::Compact(index) => writeln!(f, "  Compact model index: {}", index)?,
+                    Personality::Routine { address, name } => {
+                        write!(f, "  Personality routine: {:#x}", address)?;
+                        match name {
+                            Some(name) => writeln!(f, " <{}>", name)?,
+                            None => writeln!(f)?,
+                        }
+                    }
+                }
+                instructions
+            }
+        };
+        for instruction in instructions {
+            writeln!(f, "  {}", instruction)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ExceptionTables<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sections = match from_bytes(self.data, self.header) {
+            Ok(sections) if sections.is_empty() => {
+                return writeln!(f, "\nThere are no unwind sections in this file.")
+            }
+            Ok(sections) => sections,
+            Err(err) => return writeln!(f, "\nFailed to parse .ARM.exidx: {}", err),
+        };
+        for section in sections {
+            writeln!(
+                f,
+                "\nUnwind section '{}' at offset {:#x} contains {} entries:",
+                section.name,
+                section.offset,
+                section.entries.len()
+            )?;
+            for entry in &section.entries {
+                writeln!(f)?;
+                write!(f, "{}", entry)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for Entry<'_> {
+    fn to_json(&self) -> Json {
+        let (kind, table, personality, instructions) = match &self.unwind {
+            Unwind::CantUnwind => ("cantunwind", None, Json::Null, &[][..]),
+            Unwind::Inline { instructions, .. } => {
+                ("inline", None, Json::from(0u8), &instructions[..])
+            }
+            Unwind::Table {
+                address,
+                personality,
+                instructions,
+            } => {
+                let personality = match personality {
+                    Personality::Compact(index) => Json::from(*index),
+                    Personality::Routine { address, name } => Json::object([
+                        ("address", Json::from(*address)),
+                        ("name", Json::from(*name)),
+                    ]),
+                };
+                ("table", Some(*address), personality, &instructions[..])
+            }
+        };
+        Json::object([
+            ("function", Json::from(self.function)),
+            ("name", Json::from(self.name)),
+            ("kind", Json::from(kind)),
+            ("table", Json::from(table)),
+            ("personality", personality),
+            (
+                "instructions",
+                Json::array(
+                    instructions
+                        .iter()
+                        .map(|instruction| instruction.op.to_string()),
+                ),
+            ),
+        ])
+    }
+}
+
+impl ToJson for ExceptionTables<'_> {
+    fn to_json(&self) -> Json {
+        match from_bytes(self.data, self.header) {
+            Ok(sections) => Json::array(sections.iter().map(|section| {
+                Json::object([
+                    ("name", Json::from(section.name)),
+                    ("offset", Json::from(section.offset)),
+                    ("address", Json::from(section.address)),
+                    (
+                        "entries",
+                        Json::array(section.entries.iter().map(ToJson::to_json)),
+                    ),
+                ])
+            })),
+            Err(err) => Json::error(err),
+        }
+    }
+}
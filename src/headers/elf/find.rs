@@ -0,0 +1,296 @@
+//! Search the bytes of a file for a pattern
+//!
+//! A pattern is a string, or hex bytes where `??` matches any byte and a
+//! `?` digit any nibble, like the signatures of reverse engineering
+//! tools: `48 8b ?? ??`, `e8????????` or `4? 89 e5`. Each match is
+//! reported with its file offset, section and virtual address.
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::json::{Json, ToJson};
+use crate::{Error, Result};
+use core::fmt;
+
+use super::image::LoadImage;
+use super::program::{self, PT};
+use super::section::{self, SHF, SHT};
+use super::x64::x64;
+
+/// Bytes to look for, each with the mask of the bits that must match
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    /// the bytes, 0 for the wildcard bits
+    pub bytes: Vec<u8>,
+    /// the bits of each byte compared, 0 for a wildcard
+    pub mask: Vec<u8>,
+}
+
+impl Pattern {
+    /// Parse hex bytes with `?` wildcard digits
+    ///
+    /// Whitespace between the bytes is optional, a lone `?` is a whole
+    /// byte. Fails with the index of the first invalid character, of the
+    /// end of a byte missing its second digit, or of the end of an empty
+    /// pattern.
+    pub fn hex(text: &str) -> Result<Pattern> {
+        let mut pattern = Pattern {
+            bytes: Vec::new(),
+            mask: Vec::new(),
+        };
+        let digit = |index: usize, c: char| match c {
+            '?' => Ok((0, 0)),
+            c => c
+                .to_digit(16)
+                .map(|value| (value as u8, 0xf))
+                .ok_or(Error::InvalidPattern(index)),
+        };
+        // the characters of the current word, a word ends at a whitespace
+        let mut word = Vec::new();
+        for (index, c) in text.char_indices().chain([(text.len(), ' ')]) {
+            if !c.is_whitespace() {
+                word.push((index, c));
+                continue;
+            }
+            if let [(_, '?')] = word[..] {
+                word.push((index, '?'));
+            }
+            for pair in word.chunks(2) {
+                let [(high_index, high), (low_index, low)] = pair else {
+                    return Err(Error::InvalidPattern(index));
+                };
+                let (high, high_mask) = digit(*high_index, *high)?;
+                let (low, low_mask) = digit(*low_index, *low)?;
+                pattern.bytes.push(high << 4 | low);
+                pattern.mask.push(high_mask << 4 | low_mask);
+            }
+            word.clear();
+        }
+        if pattern.bytes.is_empty() {
+            return Err(Error::InvalidPattern(text.len()));
+        }
+        Ok(pattern)
+    }
+
+    /// The bytes of a string, matched exactly
+    pub fn text(text: &str) -> Pattern {
+        Pattern {
+            bytes: text.as_bytes().to_vec(),
+            mask: vec![0xff; text.len()],
+        }
+    }
+
+    /// Number of bytes matched
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Whether the pattern matches nothing
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Whether `bytes` starts with the pattern
+    pub fn matches(&self, bytes: &[u8]) -> bool {
+        bytes.len() >= self.len()
+            && self
+                .bytes
+                .iter()
+                .zip(&self.mask)
+                .zip(bytes)
+                .all(|((byte, mask), value)| value & mask == *byte)
+    }
+
+    /// Offsets of the matches in `bytes`, overlapping ones included
+    pub fn find_all(&self, bytes: &[u8]) -> Vec<usize> {
+        if self.is_empty() || bytes.len() < self.len() {
+            return Vec::new();
+        }
+        (0..=bytes.len() - self.len())
+            .filter(|at| self.matches(&bytes[*at..]))
+            .collect()
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, (byte, mask)) in self.bytes.iter().zip(&self.mask).enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+            for shift in [4, 0] {
+                match (mask >> shift) & 0xf {
+                    0 => f.write_str("?")?,
+                    _ => write!(f, "{:x}", (byte >> shift) & 0xf)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A place where the pattern was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match<'a> {
+    /// file offset of the first byte
+    pub offset: u64,
+    /// virtual address of the first byte, None when it isn't loaded
+    pub address: Option<u64>,
+    /// name of the section holding the first byte, None outside of the sections
+    pub section: Option<ByteStr<'a>>,
+    /// the bytes matched
+    pub bytes: &'a [u8],
+}
+
+/// The matches of a pattern in a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Matches<'a> {
+    /// the pattern looked for
+    pub pattern: Pattern,
+    /// the matches in increasing offset order
+    pub matches: Vec<Match<'a>>,
+}
+
+/// Find every match of `pattern` in the file
+///
+/// The whole file is scanned, headers and padding included, so a match
+/// may start in a section and end in the next one. Its address comes
+/// from the allocated section holding its first byte, or from the PT_LOAD
+/// segment when the file has no section header table.
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64, pattern: &Pattern) -> Result<Matches<'a>> {
+    let file_size = data.len() as u64;
+    let names = section::names(data, header).unwrap_or_default();
+    // (start, end, name, address of start) of the sections holding bytes
+    let sections: Vec<_> = section::from_bytes(data, header)?
+        .filter(|shdr| shdr.sh_type != SHT::NOBITS && shdr.sh_size != 0)
+        .filter(|shdr| shdr.sh_offset < file_size)
+        .map(|shdr| {
+            let end = shdr.sh_offset.saturating_add(shdr.sh_size).min(file_size);
+            let address = (shdr.sh_flags & SHF::ALLOC != 0).then_some(shdr.sh_addr);
+            (shdr.sh_offset, end, shdr.name(&names).ok(), address)
+        })
+        .collect();
+    let segments: Vec<_> = program::from_bytes(data, header)?
+        .filter(|phdr| phdr.p_type == PT::LOAD)
+        .map(|phdr| (phdr.p_offset, phdr.p_filesz, phdr.p_vaddr))
+        .collect();
+    let matches = pattern
+        .find_all(data)
+        .into_iter()
+        .map(|at| {
+            let offset = at as u64;
+            let section = sections
+                .iter()
+                .find(|(first, last, _, _)| offset >= *first && offset < *last);
+            let address = match section {
+                Some((first, _, _, address)) => {
+                    address.map(|address| address.wrapping_add(offset - first))
+                }
+                None if sections.is_empty() => segments
+                    .iter()
+                    .find(|(first, size, _)| offset >= *first && offset - first < *size)
+                    .map(|(first, _, vaddr)| vaddr.wrapping_add(offset - first)),
+                None => None,
+            };
+            Match {
+                offset,
+                address,
+                section: section.and_then(|(_, _, name, _)| *name),
+                bytes: &data[at..at + pattern.len()],
+            }
+        })
+        .collect();
+    Ok(Matches {
+        pattern: pattern.clone(),
+        matches,
+    })
+}
+
+/// Find every match of `pattern` in a memory image, a raw flash dump or
+/// an Intel HEX file
+///
+/// Each mapping is scanned on its own, the offsets are counted from the
+/// base of the image and the matches have no section.
+pub fn from_image<'a>(image: &'a LoadImage, pattern: &Pattern) -> Matches<'a> {
+    let mut matches = Vec::new();
+    for mapping in &image.mappings {
+        let start = mapping.vaddr.wrapping_sub(image.base);
+        let bytes = image
+            .get(mapping.vaddr, mapping.filesz as usize)
+            .unwrap_or_default();
+        for at in pattern.find_all(bytes) {
+            matches.push(Match {
+                offset: start + at as u64,
+                address: Some(mapping.vaddr.wrapping_add(at as u64)),
+                section: None,
+                bytes: &bytes[at..at + pattern.len()],
+            });
+        }
+    }
+    matches.sort_by_key(|found| found.offset);
+    Matches {
+        pattern: pattern.clone(),
+        matches,
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    hex.join(" ")
+}
+
+impl fmt::Display for Matches<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.matches.is_empty() {
+            return writeln!(f, "Pattern '{}' was not found in this file.", self.pattern);
+        }
+        let times = match self.matches.len() {
+            1 => "time",
+            _ => "times",
+        };
+        writeln!(
+            f,
+            "Pattern '{}' found {} {}:",
+            self.pattern,
+            self.matches.len(),
+            times
+        )?;
+        writeln!(f, "  Offset   Address          Section              Bytes")?;
+        for found in &self.matches {
+            let address = match found.address {
+                Some(address) => format!("{:016x}", address),
+                None => String::new(),
+            };
+            writeln!(
+                f,
+                "  {:08x} {:<16} {:<20} {}",
+                found.offset,
+                address,
+                found.section.unwrap_or_default(),
+                hex(found.bytes)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for Match<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("offset", Json::from(self.offset)),
+            ("address", Json::from(self.address)),
+            ("section", Json::from(self.section)),
+            ("bytes", Json::from(hex(self.bytes))),
+        ])
+    }
+}
+
+impl ToJson for Matches<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("pattern", Json::from(self.pattern.to_string())),
+            (
+                "matches",
+                Json::array(self.matches.iter().map(Match::to_json)),
+            ),
+        ])
+    }
+}
@@ -0,0 +1,327 @@
+use crate::alloc_prelude::*;
+
+use super::machine::Machine;
+
+/// ARM `e_flags`
+///
+/// The top byte holds the version of the EABI the file conforms to, the
+/// meaning of the other bits depends on it.
+#[allow(non_camel_case_types)]
+pub struct EF_ARM {}
+impl EF_ARM {
+    pub const EABIMASK: u32 = 0xff00_0000;
+    pub const EABI_UNKNOWN: u32 = 0x0000_0000;
+    pub const EABI_VER1: u32 = 0x0100_0000;
+    pub const EABI_VER2: u32 = 0x0200_0000;
+    pub const EABI_VER3: u32 = 0x0300_0000;
+    pub const EABI_VER4: u32 = 0x0400_0000;
+    pub const EABI_VER5: u32 = 0x0500_0000;
+    /// relocatable executable, for any EABI version
+    pub const RELEXEC: u32 = 0x01;
+    /// the symbol table is sorted, EABI version 1 and 2
+    pub const SYMSORTED: u32 = 0x04;
+    /// the dynamic symbols use segment indexes, EABI version 2
+    pub const DYNSYMSUSESEGIDX: u32 = 0x08;
+    /// the mapping symbols precede the others, EABI version 2
+    pub const MAPSYMSFIRST: u32 = 0x10;
+    /// BE8 code, big endian data with little endian instructions
+    pub const BE8: u32 = 0x0080_0000;
+    /// LE8 code, EABI version 4 and 5
+    pub const LE8: u32 = 0x0040_0000;
+    /// floats are passed in integer registers, EABI version 5
+    pub const ABI_FLOAT_SOFT: u32 = 0x200;
+    /// floats are passed in VFP registers, EABI version 5
+    pub const ABI_FLOAT_HARD: u32 = 0x400;
+    /// the code was compiled for ARM and Thumb interworking, GNU EABI
+    pub const INTERWORK: u32 = 0x04;
+    /// APCS-26 calling convention, GNU EABI
+    pub const APCS_26: u32 = 0x08;
+    /// floats are passed in float registers, GNU EABI
+    pub const APCS_FLOAT: u32 = 0x10;
+    /// position independent code, for any EABI version
+    pub const PIC: u32 = 0x20;
+    /// 8 bit structure alignment, GNU EABI
+    pub const ALIGN8: u32 = 0x40;
+    pub const NEW_ABI: u32 = 0x80;
+    pub const OLD_ABI: u32 = 0x100;
+    /// software floating point, GNU EABI
+    pub const SOFT_FLOAT: u32 = 0x200;
+    /// VFP floating point, GNU EABI
+    pub const VFP_FLOAT: u32 = 0x400;
+    /// Maverick floating point, GNU EABI
+    pub const MAVERICK_FLOAT: u32 = 0x800;
+}
+
+/// RISC-V `e_flags`
+#[allow(non_camel_case_types)]
+pub struct EF_RISCV {}
+impl EF_RISCV {
+    /// uses compressed instructions
+    pub const RVC: u32 = 0x0001;
+    /// registers used to pass floats
+    pub const FLOAT_ABI: u32 = 0x0006;
+    pub const FLOAT_ABI_SOFT: u32 = 0x0000;
+    pub const FLOAT_ABI_SINGLE: u32 = 0x0002;
+    pub const FLOAT_ABI_DOUBLE: u32 = 0x0004;
+    pub const FLOAT_ABI_QUAD: u32 = 0x0006;
+    /// RV32E, 16 integer registers
+    pub const RVE: u32 = 0x0008;
+    /// total store ordering memory model
+    pub const TSO: u32 = 0x0010;
+}
+
+/// MIPS `e_flags`
+#[allow(non_camel_case_types)]
+pub struct EF_MIPS {}
+impl EF_MIPS {
+    /// the assembler didn't reorder the instructions
+    pub const NOREORDER: u32 = 0x0000_0001;
+    /// position independent code
+    pub const PIC: u32 = 0x0000_0002;
+    /// calls PIC code
+    pub const CPIC: u32 = 0x0000_0004;
+    pub const UCODE: u32 = 0x0000_0010;
+    /// N32 ABI
+    pub const ABI2: u32 = 0x0000_0020;
+    /// the ODK options are first in `.MIPS.options`
+    pub const OPTIONS_FIRST: u32 = 0x0000_0080;
+    /// 64 bits code running in 32 bits mode
+    pub const _32BITMODE: u32 = 0x0000_0100;
+    /// 64 bits floating point registers
+    pub const FP64: u32 = 0x0000_0200;
+    /// IEEE 754-2008 NaN encoding
+    pub const NAN2008: u32 = 0x0000_0400;
+    /// the 32 bits ABI
+    pub const ABI: u32 = 0x0000_f000;
+    pub const ABI_O32: u32 = 0x0000_1000;
+    pub const ABI_O64: u32 = 0x0000_2000;
+    pub const ABI_EABI32: u32 = 0x0000_3000;
+    pub const ABI_EABI64: u32 = 0x0000_4000;
+    /// the processor the code was tuned for
+    pub const MACH: u32 = 0x00ff_0000;
+    /// the application specific extensions used
+    pub const ARCH_ASE: u32 = 0x0f00_0000;
+    pub const ARCH_ASE_MDMX: u32 = 0x0800_0000;
+    pub const ARCH_ASE_M16: u32 = 0x0400_0000;
+    pub const ARCH_ASE_MICROMIPS: u32 = 0x0200_0000;
+    /// the instruction set
+    pub const ARCH: u32 = 0xf000_0000;
+    pub const ARCH_1: u32 = 0x0000_0000;
+    pub const ARCH_2: u32 = 0x1000_0000;
+    pub const ARCH_3: u32 = 0x2000_0000;
+    pub const ARCH_4: u32 = 0x3000_0000;
+    pub const ARCH_5: u32 = 0x4000_0000;
+    pub const ARCH_32: u32 = 0x5000_0000;
+    pub const ARCH_64: u32 = 0x6000_0000;
+    pub const ARCH_32R2: u32 = 0x7000_0000;
+    pub const ARCH_64R2: u32 = 0x8000_0000;
+    pub const ARCH_32R6: u32 = 0x9000_0000;
+    pub const ARCH_64R6: u32 = 0xa000_0000;
+}
+
+/// Push the names of the bits of `flags` found in `names`, and `<unknown>`
+/// once if some bits of `known` have no name
+fn bits(out: &mut Vec<&'static str>, flags: u32, known: u32, names: &[(u32, &'static str)]) {
+    let mut left = flags & known;
+    for (bit, name) in names {
+        if flags & bit != 0 {
+            out.push(name);
+            left &= !bit;
+        }
+    }
+    if left != 0 {
+        out.push("<unknown>");
+    }
+}
+
+fn arm(flags: u32) -> Vec<&'static str> {
+    let mut out = Vec::new();
+    // like readelf, the bits common to every version come first
+    if flags & EF_ARM::RELEXEC != 0 {
+        out.push("relocatable executable");
+    }
+    if flags & EF_ARM::PIC != 0 {
+        out.push("position independent");
+    }
+    let rest = !(EF_ARM::EABIMASK | EF_ARM::RELEXEC | EF_ARM::PIC);
+    match flags & EF_ARM::EABIMASK {
+        EF_ARM::EABI_UNKNOWN => {
+            out.push("GNU EABI");
+            let names = [
+                (EF_ARM::INTERWORK, "interworking enabled"),
+                (EF_ARM::APCS_26, "uses APCS/26"),
+                (EF_ARM::APCS_FLOAT, "uses APCS/float"),
+                (EF_ARM::ALIGN8, "8 bit structure alignment"),
+                (EF_ARM::NEW_ABI, "uses new ABI"),
+                (EF_ARM::OLD_ABI, "uses old ABI"),
+                (EF_ARM::SOFT_FLOAT, "software FP"),
+                (EF_ARM::VFP_FLOAT, "VFP"),
+                (EF_ARM::MAVERICK_FLOAT, "Maverick FP"),
+            ];
+            bits(&mut out, flags, rest, &names);
+        }
+        EF_ARM::EABI_VER1 => {
+            out.push("Version1 EABI");
+            bits(
+                &mut out,
+                flags,
+                rest,
+                &[(EF_ARM::SYMSORTED, "sorted symbol tables")],
+            );
+        }
+        EF_ARM::EABI_VER2 => {
+            out.push("Version2 EABI");
+            let names = [
+                (EF_ARM::SYMSORTED, "sorted symbol tables"),
+                (
+                    EF_ARM::DYNSYMSUSESEGIDX,
+                    "dynamic symbols use segment index",
+                ),
+                (EF_ARM::MAPSYMSFIRST, "mapping symbols precede others"),
+            ];
+            bits(&mut out, flags, rest, &names);
+        }
+        EF_ARM::EABI_VER3 => out.push("Version3 EABI"),
+        EF_ARM::EABI_VER4 => {
+            out.push("Version4 EABI");
+            let names = [(EF_ARM::BE8, "BE8"), (EF_ARM::LE8, "LE8")];
+            bits(&mut out, flags, rest, &names);
+        }
+        EF_ARM::EABI_VER5 => {
+            out.push("Version5 EABI");
+            let names = [
+                (EF_ARM::BE8, "BE8"),
+                (EF_ARM::LE8, "LE8"),
+                (EF_ARM::ABI_FLOAT_SOFT, "soft-float ABI"),
+                (EF_ARM::ABI_FLOAT_HARD, "hard-float ABI"),
+            ];
+            bits(&mut out, flags, rest, &names);
+        }
+        _ => out.push("<unrecognized EABI>"),
+    }
+    out
+}
+
+fn riscv(flags: u32) -> Vec<&'static str> {
+    let mut out = Vec::new();
+    if flags & EF_RISCV::RVC != 0 {
+        out.push("RVC");
+    }
+    if flags & EF_RISCV::RVE != 0 {
+        out.push("RVE");
+    }
+    if flags & EF_RISCV::TSO != 0 {
+        out.push("TSO");
+    }
+    out.push(match flags & EF_RISCV::FLOAT_ABI {
+        EF_RISCV::FLOAT_ABI_SOFT => "soft-float ABI",
+        EF_RISCV::FLOAT_ABI_SINGLE => "single-float ABI",
+        EF_RISCV::FLOAT_ABI_DOUBLE => "double-float ABI",
+        _ => "quad-float ABI",
+    });
+    out
+}
+
+fn mips(flags: u32) -> Vec<&'static str> {
+    let mut out = Vec::new();
+    let names = [
+        (EF_MIPS::NOREORDER, "noreorder"),
+        (EF_MIPS::PIC, "pic"),
+        (EF_MIPS::CPIC, "cpic"),
+        (EF_MIPS::UCODE, "ugen_reserved"),
+        (EF_MIPS::ABI2, "abi2"),
+        (EF_MIPS::OPTIONS_FIRST, "odk first"),
+        (EF_MIPS::_32BITMODE, "32bitmode"),
+        (EF_MIPS::NAN2008, "nan2008"),
+        (EF_MIPS::FP64, "fp64"),
+    ];
+    for (bit, name) in names {
+        if flags & bit != 0 {
+            out.push(name);
+        }
+    }
+    match flags & EF_MIPS::MACH {
+        0 => {}
+        0x0081_0000 => out.push("3900"),
+        0x0082_0000 => out.push("4010"),
+        0x0083_0000 => out.push("4100"),
+        0x0085_0000 => out.push("4650"),
+        0x0087_0000 => out.push("4120"),
+        0x0088_0000 => out.push("4111"),
+        0x008a_0000 => out.push("sb1"),
+        0x008b_0000 => out.push("octeon"),
+        0x008c_0000 => out.push("xlr"),
+        0x008d_0000 => out.push("octeon2"),
+        0x008e_0000 => out.push("octeon3"),
+        0x0091_0000 => out.push("5400"),
+        0x0092_0000 => out.push("5900"),
+        0x0098_0000 => out.push("5500"),
+        0x0099_0000 => out.push("9000"),
+        0x00a0_0000 => out.push("loongson-2e"),
+        0x00a1_0000 => out.push("loongson-2f"),
+        0x00a2_0000 => out.push("gs464"),
+        0x00a3_0000 => out.push("gs464e"),
+        0x00a4_0000 => out.push("gs264e"),
+        _ => out.push("unknown CPU"),
+    }
+    match flags & EF_MIPS::ABI {
+        0 => {}
+        EF_MIPS::ABI_O32 => out.push("o32"),
+        EF_MIPS::ABI_O64 => out.push("o64"),
+        EF_MIPS::ABI_EABI32 => out.push("eabi32"),
+        EF_MIPS::ABI_EABI64 => out.push("eabi64"),
+        _ => out.push("unknown ABI"),
+    }
+    let ases = [
+        (EF_MIPS::ARCH_ASE_MDMX, "mdmx"),
+        (EF_MIPS::ARCH_ASE_M16, "mips16"),
+        (EF_MIPS::ARCH_ASE_MICROMIPS, "micromips"),
+    ];
+    for (bit, name) in ases {
+        if flags & bit != 0 {
+            out.push(name);
+        }
+    }
+    out.push(match flags & EF_MIPS::ARCH {
+        EF_MIPS::ARCH_1 => "mips1",
+        EF_MIPS::ARCH_2 => "mips2",
+        EF_MIPS::ARCH_3 => "mips3",
+        EF_MIPS::ARCH_4 => "mips4",
+        EF_MIPS::ARCH_5 => "mips5",
+        EF_MIPS::ARCH_32 => "mips32",
+        EF_MIPS::ARCH_64 => "mips64",
+        EF_MIPS::ARCH_32R2 => "mips32r2",
+        EF_MIPS::ARCH_64R2 => "mips64r2",
+        EF_MIPS::ARCH_32R6 => "mips32r6",
+        EF_MIPS::ARCH_64R6 => "mips64r6",
+        _ => "unknown ISA",
+    });
+    out
+}
+
+/// The names of the bits of `e_flags` for the given machine
+///
+/// Empty for the machines that define no flag, like x86-64, and when no
+/// bit is set.
+pub fn names(e_machine: u16, e_flags: u32) -> Vec<&'static str> {
+    if e_flags == 0 {
+        return Vec::new();
+    }
+    match Machine::from_u16(e_machine) {
+        Machine::Arm => arm(e_flags),
+        Machine::Riscv => riscv(e_flags),
+        Machine::Mips | Machine::MipsRs3Le => mips(e_flags),
+        _ => Vec::new(),
+    }
+}
+
+/// The flags like readelf prints them: the value followed by the names
+/// of its bits, as in `0x5000400, Version5 EABI, hard-float ABI`
+pub fn describe(e_machine: u16, e_flags: u32) -> String {
+    let mut description = format!("{:#x}", e_flags);
+    for name in names(e_machine, e_flags) {
+        description.push_str(", ");
+        description.push_str(name);
+    }
+    description
+}
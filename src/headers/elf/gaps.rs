@@ -0,0 +1,157 @@
+use crate::alloc_prelude::*;
+use crate::utils::json::{Json, ToJson};
+use crate::Result;
+use core::fmt;
+
+use super::program;
+use super::section::{self, SHT};
+use super::x64::x64;
+
+/// Where an uncovered range of bytes lies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GapKind {
+    /// between two covered ranges, usually alignment padding
+    Padding,
+    /// after the last covered byte, data appended to the file
+    Overlay,
+}
+
+/// A range of bytes not covered by the headers, a segment or a section
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gap {
+    /// file offset of the first byte
+    pub offset: u64,
+    /// number of bytes
+    pub size: u64,
+    /// padding or overlay
+    pub kind: GapKind,
+    /// every byte of the range is zero
+    pub zero: bool,
+}
+
+/// The uncovered ranges of an ELF64 file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Gaps {
+    /// size of the file
+    pub file_size: u64,
+    /// the ranges in increasing offset order
+    pub gaps: Vec<Gap>,
+}
+
+impl Gaps {
+    /// The data appended after the end of the file contents, if any
+    pub fn overlay(&self) -> Option<&Gap> {
+        self.gaps.iter().find(|gap| gap.kind == GapKind::Overlay)
+    }
+}
+
+/// Find the bytes of the file that nothing describes
+///
+/// The file header, the program and section header tables, the file
+/// image of the segments and the sections other than SHT_NOBITS count as
+/// covered, ranges reaching past the end of the file are clipped.
+pub fn from_bytes(data: &[u8], header: &x64) -> Result<Gaps> {
+    let file_size = data.len() as u64;
+    let mut ranges = vec![
+        (0, x64::SIZE as u64),
+        (
+            header.e_phoff,
+            (header.e_phentsize as u64).saturating_mul(header.phnum(data)),
+        ),
+        (
+            header.e_shoff,
+            (header.e_shentsize as u64).saturating_mul(header.shnum(data)),
+        ),
+    ];
+    ranges.extend(program::from_bytes(data, header)?.map(|phdr| (phdr.p_offset, phdr.p_filesz)));
+    ranges.extend(
+        section::from_bytes(data, header)?
+            .filter(|shdr| shdr.sh_type != SHT::NOBITS)
+            .map(|shdr| (shdr.sh_offset, shdr.sh_size)),
+    );
+    let mut ranges: Vec<(u64, u64)> = ranges
+        .into_iter()
+        .filter(|(offset, size)| *size != 0 && *offset < file_size)
+        .map(|(offset, size)| (offset, offset.saturating_add(size).min(file_size)))
+        .collect();
+    ranges.sort_unstable();
+
+    let mut gaps = Vec::new();
+    let mut gap = |start: u64, end: u64, kind| {
+        let bytes = &data[start as usize..end as usize];
+        gaps.push(Gap {
+            offset: start,
+            size: end - start,
+            kind,
+            zero: bytes.iter().all(|byte| *byte == 0),
+        });
+    };
+    let mut covered = 0;
+    for (start, end) in ranges {
+        if start > covered {
+            gap(covered, start, GapKind::Padding);
+        }
+        covered = covered.max(end);
+    }
+    if covered < file_size {
+        gap(covered, file_size, GapKind::Overlay);
+    }
+    Ok(Gaps { file_size, gaps })
+}
+
+impl fmt::Display for GapKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            GapKind::Padding => "padding",
+            GapKind::Overlay => "overlay",
+        })
+    }
+}
+
+impl fmt::Display for Gaps {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.gaps.is_empty() {
+            return writeln!(f, "Every byte of the file is covered.");
+        }
+        let total: u64 = self.gaps.iter().map(|gap| gap.size).sum();
+        writeln!(
+            f,
+            "There are {} uncovered ranges, {:#x} of {:#x} bytes:",
+            self.gaps.len(),
+            total,
+            self.file_size
+        )?;
+        writeln!(f, "  Offset             Size               Kind")?;
+        for gap in &self.gaps {
+            writeln!(
+                f,
+                "  0x{:016x} 0x{:016x} {}{}",
+                gap.offset,
+                gap.size,
+                gap.kind,
+                if gap.zero { " (zeroes)" } else { "" }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for Gap {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("offset", Json::from(self.offset)),
+            ("size", Json::from(self.size)),
+            ("kind", Json::from(self.kind.to_string())),
+            ("zero", Json::from(self.zero)),
+        ])
+    }
+}
+
+impl ToJson for Gaps {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("file_size", Json::from(self.file_size)),
+            ("gaps", Json::array(self.gaps.iter().map(Gap::to_json))),
+        ])
+    }
+}
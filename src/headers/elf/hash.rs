@@ -0,0 +1,423 @@
+//! Symbol hash tables of the dynamic linker, `.hash` and `.gnu.hash`
+//!
+//! Both tables map the hash of a name to a chain of `.dynsym` indexes.
+//! The SysV table chains every symbol, the GNU one sorts the symbols by
+//! bucket so each chain is a contiguous run of the table and adds a bloom
+//! filter to reject most missing names without touching the chains.
+//! Sources:
+//! * <https://refspecs.linuxfoundation.org/elf/gabi4+/ch5.dynamic.html#hash>
+//! * <https://sourceware.org/legacy-ml/binutils/2006-10/msg00377.html>
+use super::data::Data;
+use super::dynamic::{Dynamic, DT};
+use super::program;
+use super::section::{self, Elf64_Shdr, SHT};
+use super::symbols::{self, Elf64_Sym};
+use super::x64::x64;
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::json::{Json, ToJson};
+use crate::utils::{read_u32, read_u64};
+use crate::{Error, Result};
+use alloc::borrow::Cow;
+use core::fmt;
+
+/// Hash function of the SysV `.hash` table
+pub fn sysv_hash(name: &[u8]) -> u32 {
+    let mut hash = 0u32;
+    for byte in name {
+        hash = (hash << 4).wrapping_add(u32::from(*byte));
+        let high = hash & 0xf000_0000;
+        hash ^= high >> 24;
+        hash &= !high;
+    }
+    hash
+}
+
+/// Hash function of the `.gnu.hash` table, DJB's `h * 33 + c`
+pub fn gnu_hash(name: &[u8]) -> u32 {
+    name.iter().fold(5381u32, |hash, byte| {
+        hash.wrapping_mul(33).wrapping_add(u32::from(*byte))
+    })
+}
+
+/// Read `count` words stored from `offset`
+fn words(data: &[u8], offset: usize, count: usize, big_endian: bool) -> Option<Vec<u32>> {
+    (0..count)
+        .map(|index| read_u32(data, offset.checked_add(index.checked_mul(4)?)?, big_endian))
+        .collect()
+}
+
+/// The SysV hash table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SysvHash {
+    pub buckets: Vec<u32>,
+    /// next symbol of the chain of each symbol, 0 ends the chain
+    pub chains: Vec<u32>,
+}
+
+impl SysvHash {
+    /// Parse the content of a `.hash` section
+    pub fn parse(data: &[u8], big_endian: bool) -> Option<Self> {
+        let nbucket = read_u32(data, 0, big_endian)? as usize;
+        let nchain = read_u32(data, 4, big_endian)? as usize;
+        Some(SysvHash {
+            buckets: words(data, 8, nbucket, big_endian)?,
+            chains: words(data, 8 + nbucket.checked_mul(4)?, nchain, big_endian)?,
+        })
+    }
+
+    /// Index of the symbol named `name`, `name_of` resolves a symbol index
+    pub fn find<'a>(&self, name: &str, name_of: impl Fn(u32) -> Option<&'a str>) -> Option<u32> {
+        if self.buckets.is_empty() {
+            return None;
+        }
+        let hash = sysv_hash(name.as_bytes());
+        let mut index = self.buckets[hash as usize % self.buckets.len()];
+        // a corrupted chain could loop, it can't be longer than the table
+        for _ in 0..self.chains.len() {
+            if index == 0 {
+                break;
+            }
+            if name_of(index) == Some(name) {
+                return Some(index);
+            }
+            index = *self.chains.get(index as usize)?;
+        }
+        None
+    }
+
+    /// Number of symbols in the chain of each bucket
+    pub fn chain_lengths(&self) -> Vec<u64> {
+        self.buckets
+            .iter()
+            .map(|bucket| {
+                let mut length = 0;
+                let mut index = *bucket;
+                while index != 0 && length < self.chains.len() as u64 {
+                    length += 1;
+                    index = self.chains.get(index as usize).copied().unwrap_or(0);
+                }
+                length
+            })
+            .collect()
+    }
+}
+
+/// The GNU hash table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GnuHash {
+    /// index of the first symbol of `.dynsym` in the table
+    pub symoffset: u32,
+    pub bloom_shift: u32,
+    /// bloom filter words, 64 bits for ELF64
+    pub bloom: Vec<u64>,
+    /// first symbol of each bucket, 0 for an empty bucket
+    pub buckets: Vec<u32>,
+    /// hash of each symbol from `symoffset`, the low bit ends a chain
+    pub chains: Vec<u32>,
+}
+
+impl GnuHash {
+    /// Parse the content of a `.gnu.hash` section for a table of `nsyms` symbols
+    pub fn parse(data: &[u8], nsyms: usize, big_endian: bool) -> Option<Self> {
+        let nbuckets = read_u32(data, 0, big_endian)? as usize;
+        let symoffset = read_u32(data, 4, big_endian)?;
+        let bloom_size = read_u32(data, 8, big_endian)? as usize;
+        let bloom_shift = read_u32(data, 12, big_endian)?;
+        let bloom = (0..bloom_size)
+            .map(|index| read_u64(data, 16 + index * 8, big_endian))
+            .collect::<Option<Vec<_>>>()?;
+        let buckets_start = 16 + bloom_size.checked_mul(8)?;
+        let buckets = words(data, buckets_start, nbuckets, big_endian)?;
+        let chains_start = buckets_start + nbuckets * 4;
+        let count = nsyms.saturating_sub(symoffset as usize);
+        // the chains of a stripped table may be shorter than the symbols
+        let available = data.len().saturating_sub(chains_start) / 4;
+        let chains = words(data, chains_start, count.min(available), big_endian)?;
+        Some(GnuHash {
+            symoffset,
+            bloom_shift,
+            bloom,
+            buckets,
+            chains,
+        })
+    }
+
+    /// Whether the bloom filter lets `hash` through
+    pub fn may_contain(&self, hash: u32) -> bool {
+        if self.bloom.is_empty() {
+            return true;
+        }
+        let word = self.bloom[(hash as usize / 64) % self.bloom.len()];
+        let mask = (1u64 << (hash % 64)) | (1u64 << ((hash >> (self.bloom_shift % 32)) % 64));
+        word & mask == mask
+    }
+
+    /// Index of the symbol named `name`, `name_of` resolves a symbol index
+    pub fn find<'a>(&self, name: &str, name_of: impl Fn(u32) -> Option<&'a str>) -> Option<u32> {
+        let hash = gnu_hash(name.as_bytes());
+        if self.buckets.is_empty() || !self.may_contain(hash) {
+            return None;
+        }
+        let mut index = self.buckets[hash as usize % self.buckets.len()];
+        if index < self.symoffset {
+            return None;
+        }
+        loop {
+            let chain = *self.chains.get((index - self.symoffset) as usize)?;
+            if chain | 1 == hash | 1 && name_of(index) == Some(name) {
+                return Some(index);
+            }
+            if chain & 1 != 0 {
+                return None;
+            }
+            index += 1;
+        }
+    }
+
+    /// Number of symbols in the chain of each bucket
+    pub fn chain_lengths(&self) -> Vec<u64> {
+        self.buckets
+            .iter()
+            .map(|bucket| {
+                if *bucket < self.symoffset {
+                    return 0;
+                }
+                let start = (*bucket - self.symoffset) as usize;
+                let chain = self.chains.get(start..).unwrap_or_default();
+                let end = chain.iter().position(|hash| hash & 1 != 0);
+                end.map_or(chain.len(), |end| end + 1) as u64
+            })
+            .collect()
+    }
+}
+
+/// A hash section together with the symbol table it indexes
+#[derive(Debug, Clone)]
+pub enum HashSection<'a> {
+    Sysv(Cow<'a, Elf64_Shdr>, SysvHash),
+    Gnu(Cow<'a, Elf64_Shdr>, GnuHash),
+}
+
+impl<'a> HashSection<'a> {
+    /// Section header of the table
+    pub fn section(&self) -> &Elf64_Shdr {
+        match self {
+            HashSection::Sysv(shdr, _) | HashSection::Gnu(shdr, _) => shdr,
+        }
+    }
+
+    /// Number of symbols in the chain of each bucket
+    pub fn chain_lengths(&self) -> Vec<u64> {
+        match self {
+            HashSection::Sysv(_, table) => table.chain_lengths(),
+            HashSection::Gnu(_, table) => table.chain_lengths(),
+        }
+    }
+
+    /// Look up a symbol by name in the linked symbol table
+    ///
+    /// Returns the index of the symbol and the symbol itself.
+    pub fn lookup(
+        &self,
+        data: &'a [u8],
+        header: &x64,
+        name: &str,
+    ) -> Result<Option<(usize, Cow<'a, Elf64_Sym>)>> {
+        let symtab = section::get(data, header, self.section().sh_link)?;
+        let symbols = symbols::from_bytes(data, header, &symtab)?;
+        let strtab = symbols::strtab(data, header, &symtab)?;
+        let name_of = |index: u32| symbols.get(index as usize)?.name(&strtab).ok()?.to_str();
+        let index = match self {
+            HashSection::Sysv(_, table) => table.find(name, name_of),
+            HashSection::Gnu(_, table) => table.find(name, name_of),
+        };
+        Ok(index.and_then(|index| Some((index as usize, symbols.get(index as usize)?))))
+    }
+}
+
+/// Number of dynamic symbols, from the hash table of the dynamic section
+///
+/// Files without section headers don't give the size of the dynamic
+/// symbol table, the loader never needs it. DT_HASH holds it as the number
+/// of chains. With DT_GNU_HASH only, it is one past the last symbol of
+/// the last chain. None without hash table or if it lies outside of the file.
+pub fn symbol_count(data: &[u8], header: &x64, dynamic: &Dynamic) -> Option<u64> {
+    let big_endian = Data::is_big_endian(&header.e_ident);
+    let table = |tag| {
+        let offset = program::vaddr_to_offset(data, header, dynamic.get(tag)?)?;
+        data.get(usize::try_from(offset).ok()?..)
+    };
+    if let Some(bytes) = table(DT::HASH) {
+        return read_u32(bytes, 4, big_endian).map(u64::from);
+    }
+    let bytes = table(DT::GNU_HASH)?;
+    let nbuckets = read_u32(bytes, 0, big_endian)? as usize;
+    let symoffset = read_u32(bytes, 4, big_endian)?;
+    let bloom_size = read_u32(bytes, 8, big_endian)? as usize;
+    let buckets_start = 16usize.checked_add(bloom_size.checked_mul(8)?)?;
+    let last = words(bytes, buckets_start, nbuckets, big_endian)?
+        .into_iter()
+        .max()
+        .unwrap_or(0);
+    if last < symoffset {
+        return Some(u64::from(symoffset));
+    }
+    let chains_start = buckets_start.checked_add(nbuckets.checked_mul(4)?)?;
+    let mut index = last;
+    loop {
+        let at = chains_start.checked_add(((index - symoffset) as usize).checked_mul(4)?)?;
+        if read_u32(bytes, at, big_endian)? & 1 != 0 {
+            return Some(u64::from(index) + 1);
+        }
+        index = index.checked_add(1)?;
+    }
+}
+
+/// Parse the `.hash` and `.gnu.hash` sections, in the section header table order
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64) -> Result<Vec<HashSection<'a>>> {
+    let big_endian = Data::is_big_endian(&header.e_ident);
+    section::from_bytes(data, header)?
+        .filter(|shdr| shdr.sh_type == SHT::HASH || shdr.sh_type == SHT::GNU_HASH)
+        .map(|shdr| {
+            let bytes = shdr.data(data).ok_or(Error::OutOfBounds {
+                offset: shdr.sh_offset,
+                size: shdr.sh_size,
+            })?;
+            let invalid = Error::InvalidHeaderField {
+                field: "sh_size",
+                expected: 16,
+                found: bytes.len(),
+            };
+            if shdr.sh_type == SHT::HASH {
+                let table = SysvHash::parse(bytes, big_endian).ok_or(invalid)?;
+                return Ok(HashSection::Sysv(shdr, table));
+            }
+            let symtab = section::get(data, header, shdr.sh_link)?;
+            let nsyms = symtab
+                .sh_size
+                .checked_div(symtab.sh_entsize)
+                .unwrap_or_default();
+            let table = GnuHash::parse(bytes, nsyms as usize, big_endian).ok_or(invalid)?;
+            Ok(HashSection::Gnu(shdr, table))
+        })
+        .collect()
+}
+
+/// Hash sections ready to be printed like `readelf -I`
+pub struct HashTable<'a> {
+    /// the whole file
+    pub data: &'a [u8],
+    /// the file header
+    pub header: &'a x64,
+}
+
+/// Number of buckets holding each chain length, from 0 to the longest
+fn histogram(lengths: &[u64]) -> Vec<u64> {
+    let longest = lengths.iter().copied().max().unwrap_or(0);
+    let mut counts = vec![0; longest as usize + 1];
+    for length in lengths {
+        counts[*length as usize] += 1;
+    }
+    counts
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
+
+impl fmt::Display for HashTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tables = match from_bytes(self.data, self.header) {
+            Ok(tables) => tables,
+            Err(err) => return writeln!(f, "\nFailed to parse the hash sections: {}", err),
+        };
+        // readelf prints the SysV table first
+        let tables = tables
+            .iter()
+            .filter(|table| matches!(table, HashSection::Sysv(..)))
+            .chain(
+                tables
+                    .iter()
+                    .filter(|table| matches!(table, HashSection::Gnu(..))),
+            );
+        let names = section::names(self.data, self.header).unwrap_or_default();
+        for table in tables {
+            let lengths = table.chain_lengths();
+            if lengths.is_empty() {
+                continue;
+            }
+            let counts = histogram(&lengths);
+            let nbuckets = lengths.len() as u64;
+            let nsyms: u64 = lengths.iter().sum();
+            let buckets = if nbuckets == 1 { "bucket" } else { "buckets" };
+            match table {
+                HashSection::Sysv(..) => writeln!(
+                    f,
+                    "\nHistogram for bucket list length (total of {} {}):",
+                    nbuckets, buckets
+                )?,
+                HashSection::Gnu(shdr, _) => writeln!(
+                    f,
+                    "\nHistogram for `{}' bucket list length (total of {} {}):",
+                    shdr.name(&names).unwrap_or(ByteStr::new(b".gnu.hash")),
+                    nbuckets,
+                    buckets
+                )?,
+            }
+            writeln!(f, " Length  Number     % of total  Coverage")?;
+            writeln!(
+                f,
+                "      0  {:<10} ({:5.1}%)",
+                counts[0],
+                percent(counts[0], nbuckets)
+            )?;
+            let mut covered = 0;
+            for (length, count) in counts.iter().enumerate().skip(1) {
+                covered += count * length as u64;
+                writeln!(
+                    f,
+                    "{:7}  {:<10} ({:5.1}%)    {:5.1}%",
+                    length,
+                    count,
+                    percent(*count, nbuckets),
+                    percent(covered, nsyms)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for HashTable<'_> {
+    fn to_json(&self) -> Json {
+        let tables = match from_bytes(self.data, self.header) {
+            Ok(tables) => tables,
+            Err(err) => return Json::error(err),
+        };
+        Json::array(tables.iter().map(|table| {
+            let lengths = table.chain_lengths();
+            let kind = match table {
+                HashSection::Sysv(..) => "sysv",
+                HashSection::Gnu(..) => "gnu",
+            };
+            let bloom = match table {
+                HashSection::Sysv(..) => None,
+                HashSection::Gnu(_, table) => Some(table.bloom.len()),
+            };
+            Json::object([
+                ("type", Json::from(kind)),
+                ("address", Json::from(table.section().sh_addr)),
+                ("buckets", Json::from(lengths.len())),
+                ("symbols", Json::from(lengths.iter().sum::<u64>())),
+                ("bloom_words", Json::from(bloom)),
+                ("longest_chain", Json::from(lengths.iter().max().copied())),
+                ("histogram", Json::from(histogram(&lengths))),
+            ])
+        }))
+    }
+}
@@ -1,15 +1,101 @@
+use crate::alloc_prelude::*;
+use crate::utils::json::Json;
+use crate::utils::table::{Align, Color, Table};
+use crate::{Error, Result};
+use core::fmt;
+
+use super::class::Class;
+use super::data::Data;
+use super::osabit::OsAbi;
+use super::version::Version;
+
 /// Identification offset for the elf binary `e_ident` field
 pub struct Indent {}
 #[warn(unused_must_use)]
 impl Indent {
-    //const MAG0: usize = 0;
-    //const MAG1: usize = 1;
-    //const MAG2: usize = 2;
-    //const MAG3: usize = 3;
+    pub const MAG0: usize = 0;
+    pub const MAG1: usize = 1;
+    pub const MAG2: usize = 2;
+    pub const MAG3: usize = 3;
     pub const CLASS: usize = 4;
     pub const DATA: usize = 5;
     pub const VERSION: usize = 6;
     pub const OSABIT: usize = 7;
     pub const ABIVERSION: usize = 8;
     // const PAD: usize = 9;
+
+    /// Magic number stored in the first four bytes of `e_ident`
+    pub const MAGIC: [u8; 4] = *b"\x7fELF";
+
+    /// Check that the data starts with the ELF magic number
+    pub fn check_magic(data: &[u8]) -> Result<()> {
+        let mut magic = [0; 4];
+        let len = data.len().min(magic.len());
+        magic[..len].copy_from_slice(&data[..len]);
+        if magic != Indent::MAGIC {
+            return Err(Error::BadMagic(magic));
+        }
+        Ok(())
+    }
+}
+
+/// The readelf like description of the `e_ident` field, as (name, value) rows
+///
+/// Shared by the 32 and 64 bits headers since `e_ident` has the same
+/// layout for both classes.
+pub(crate) fn ident_rows(e_ident: &[u8; 16]) -> Vec<(&'static str, String)> {
+    let magic: Vec<String> = e_ident.iter().map(|hex| format!("{:02X}", hex)).collect();
+    let abi_version = match e_ident[Indent::ABIVERSION] {
+        0 => "0",
+        _ => "Warning: Not compatible with the specification",
+    };
+    vec![
+        ("Magic:", magic.join(" ")),
+        ("Class:", Class::from(e_ident[Indent::CLASS]).to_string()),
+        ("Data:", Data::from(e_ident[Indent::DATA]).to_string()),
+        (
+            "Version:",
+            Version::from(e_ident[Indent::VERSION]).to_string(),
+        ),
+        ("OS/ABI:", OsAbi::from(e_ident[Indent::OSABIT]).to_string()),
+        ("ABI Version:", abi_version.to_string()),
+    ]
+}
+
+/// Write the rows of a file header under an `ELF Header:` title
+pub(crate) fn fmt_header(
+    f: &mut fmt::Formatter<'_>,
+    rows: &[(&'static str, String)],
+    color: bool,
+) -> fmt::Result {
+    writeln!(f, "ELF Header:")?;
+    let mut table = Table::new(color)
+        .indent(2)
+        .column("", Align::Left, Some(Color::Cyan))
+        .column("", Align::Left, None);
+    for (name, value) in rows {
+        table.row([*name, value.as_str()]);
+    }
+    write!(f, "{}", table)
+}
+
+/// JSON description of the `e_ident` field, shared by both classes
+pub(crate) fn ident_json(e_ident: &[u8; 16]) -> Vec<(&'static str, Json)> {
+    let class = match Class::from(e_ident[Indent::CLASS]) {
+        class @ (Class::Elf32 | Class::Elf64) => Json::from(class.to_string()),
+        _ => Json::Null,
+    };
+    let data = match Data::from(e_ident[Indent::DATA]) {
+        Data::Le => Json::from("little endian"),
+        Data::Be => Json::from("big endian"),
+        _ => Json::Null,
+    };
+    vec![
+        ("ident", Json::array(e_ident.iter().copied())),
+        ("class", class),
+        ("data", data),
+        ("version", Json::from(e_ident[Indent::VERSION])),
+        ("osabi", Json::from(e_ident[Indent::OSABIT])),
+        ("abi_version", Json::from(e_ident[Indent::ABIVERSION])),
+    ]
 }
@@ -0,0 +1,318 @@
+//! The memory image of a program, as built by a loader
+//!
+//! The PT_LOAD segments are copied at their virtual address in a single
+//! buffer: the first `p_filesz` bytes come from the file and the rest of
+//! `p_memsz`, the `.bss`, is zero filled, so emulators can run the code
+//! without mapping the file themselves.
+use crate::alloc_prelude::*;
+use crate::utils::read_u64;
+use crate::{Error, Result};
+
+use super::data::Data;
+use super::machine::Machine;
+use super::program::{self, PT};
+use super::relocations::{self, Relocation, R_AARCH64, R_X86_64};
+use super::section::{self, SHF, SHN, SHT};
+use super::symbols::{self, STB};
+use super::x64::x64;
+
+/// A loaded segment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mapping {
+    /// virtual address of the first byte
+    pub vaddr: u64,
+    /// size in memory, `p_memsz`
+    pub memsz: u64,
+    /// number of bytes read from the file, the others are zeros
+    pub filesz: u64,
+    /// the PF permissions of the segment
+    pub flags: u32,
+}
+
+impl Mapping {
+    /// Returns true if `vaddr` is inside the segment
+    pub fn contains(&self, vaddr: u64) -> bool {
+        vaddr >= self.vaddr && vaddr - self.vaddr < self.memsz
+    }
+}
+
+/// The PT_LOAD segments laid out in memory
+///
+/// `bytes[0]` is the byte at `base`, the address space between the
+/// segments is zero filled. Segments aren't rounded to pages, the bytes
+/// of the file around them that a loader maps with their pages are left
+/// out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadImage {
+    /// virtual address of the first byte, the lowest segment address
+    pub base: u64,
+    /// the content of the address space from `base`
+    pub bytes: Vec<u8>,
+    /// the segments in program header order
+    pub mappings: Vec<Mapping>,
+}
+
+impl LoadImage {
+    /// Virtual address following the last byte of the image
+    pub fn end(&self) -> u64 {
+        self.base.wrapping_add(self.bytes.len() as u64)
+    }
+
+    /// The segment holding `vaddr`, the last one when they overlap
+    pub fn mapping(&self, vaddr: u64) -> Option<&Mapping> {
+        self.mappings
+            .iter()
+            .rev()
+            .find(|mapping| mapping.contains(vaddr))
+    }
+
+    /// The `size` bytes at `vaddr`
+    ///
+    /// Returns None if the range isn't inside the image, the bytes
+    /// between two segments are zeros.
+    pub fn get(&self, vaddr: u64, size: usize) -> Option<&[u8]> {
+        let start = usize::try_from(vaddr.checked_sub(self.base)?).ok()?;
+        self.bytes.get(start..start.checked_add(size)?)
+    }
+}
+
+/// Build the memory image of the PT_LOAD segments
+///
+/// The segments are copied in program header order, a later segment
+/// overwrites the bytes of an earlier one it overlaps. Files without
+/// PT_LOAD segments give an empty image. A `p_filesz` larger than
+/// `p_memsz` is cut to `p_memsz`. Fails if the bytes of a segment lie
+/// outside of the file or if the image can't be allocated.
+pub fn from_bytes(data: &[u8], header: &x64) -> Result<LoadImage> {
+    let segments: Vec<_> = program::from_bytes(data, header)?
+        .filter(|phdr| phdr.p_type == PT::LOAD && phdr.p_memsz != 0)
+        .collect();
+    let base = match segments.iter().map(|phdr| phdr.p_vaddr).min() {
+        Some(base) => base,
+        None => return Ok(LoadImage::default()),
+    };
+    let mut end = base;
+    for phdr in &segments {
+        let last = phdr
+            .p_vaddr
+            .checked_add(phdr.p_memsz)
+            .ok_or(Error::ImageTooLarge(u64::MAX))?;
+        end = end.max(last);
+    }
+    let size = end - base;
+    let mut bytes = Vec::new();
+    usize::try_from(size)
+        .ok()
+        .and_then(|size| bytes.try_reserve_exact(size).ok())
+        .ok_or(Error::ImageTooLarge(size))?;
+    bytes.resize(size as usize, 0);
+
+    let mut mappings = Vec::with_capacity(segments.len());
+    for phdr in &segments {
+        let filesz = phdr.p_filesz.min(phdr.p_memsz);
+        let content = usize::try_from(phdr.p_offset)
+            .ok()
+            .zip(usize::try_from(filesz).ok())
+            .and_then(|(offset, size)| data.get(offset..offset.checked_add(size)?))
+            .ok_or(Error::OutOfBounds {
+                offset: phdr.p_offset,
+                size: filesz,
+            })?;
+        let start = (phdr.p_vaddr - base) as usize;
+        bytes[start..start + content.len()].copy_from_slice(content);
+        // an overlapping segment may have written the .bss of this one
+        let bss_end = start + phdr.p_memsz as usize;
+        bytes[start + content.len()..bss_end].fill(0);
+        mappings.push(Mapping {
+            vaddr: phdr.p_vaddr,
+            memsz: phdr.p_memsz,
+            filesz,
+            flags: phdr.p_flags,
+        });
+    }
+    Ok(LoadImage {
+        base,
+        bytes,
+        mappings,
+    })
+}
+
+/// Lay out blocks of bytes at their address, the memory of a flash dump
+///
+/// Each block becomes a mapping with the given PF permissions, a later
+/// block overwrites the bytes of an earlier one it overlaps. Fails if the
+/// image can't be allocated.
+pub fn from_blocks(blocks: &[(u64, &[u8])], flags: u32) -> Result<LoadImage> {
+    let base = match blocks.iter().map(|(vaddr, _)| *vaddr).min() {
+        Some(base) => base,
+        None => return Ok(LoadImage::default()),
+    };
+    let mut end = base;
+    for (vaddr, bytes) in blocks {
+        let last = vaddr
+            .checked_add(bytes.len() as u64)
+            .ok_or(Error::ImageTooLarge(u64::MAX))?;
+        end = end.max(last);
+    }
+    let size = end - base;
+    let mut image = Vec::new();
+    usize::try_from(size)
+        .ok()
+        .and_then(|size| image.try_reserve_exact(size).ok())
+        .ok_or(Error::ImageTooLarge(size))?;
+    image.resize(size as usize, 0);
+    let mut mappings = Vec::with_capacity(blocks.len());
+    for (vaddr, bytes) in blocks {
+        let start = (vaddr - base) as usize;
+        image[start..start + bytes.len()].copy_from_slice(bytes);
+        mappings.push(Mapping {
+            vaddr: *vaddr,
+            memsz: bytes.len() as u64,
+            filesz: bytes.len() as u64,
+            flags,
+        });
+    }
+    Ok(LoadImage {
+        base,
+        bytes: image,
+        mappings,
+    })
+}
+
+/// How the value written by a relocation is computed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Formula {
+    /// B + A, the load address plus the addend
+    Relative,
+    /// S, the address of the symbol
+    Symbol,
+    /// S + A
+    SymbolAddend,
+}
+
+/// The relocation types the loader applies, None for the others
+fn formula(machine: u16, r_type: u32) -> Option<Formula> {
+    match (Machine::from_u16(machine), r_type) {
+        (Machine::X86_64, R_X86_64::RELATIVE) => Some(Formula::Relative),
+        (Machine::X86_64, R_X86_64::GLOB_DAT | R_X86_64::JUMP_SLOT) => Some(Formula::Symbol),
+        (Machine::X86_64, R_X86_64::_64) => Some(Formula::SymbolAddend),
+        (Machine::Aarch64, R_AARCH64::RELATIVE) => Some(Formula::Relative),
+        (Machine::Aarch64, R_AARCH64::GLOB_DAT | R_AARCH64::JUMP_SLOT | R_AARCH64::ABS64) => {
+            Some(Formula::SymbolAddend)
+        }
+        _ => None,
+    }
+}
+
+/// Move the image to `base` and apply the dynamic relocations to it
+///
+/// `image` must be the image built by [`from_bytes`] for the same file,
+/// at the addresses of the file: the image and its mappings are moved by
+/// `base`, which is 0 for executables that aren't position independent.
+/// The relocations of the allocated SHT_REL and SHT_RELA sections are
+/// applied with the RELATIVE, GLOB_DAT, JUMP_SLOT and 64 bits absolute
+/// types of x86_64 and AArch64, and the addresses of the SHT_RELR ones as
+/// RELATIVE relocations. The symbols defined by the file are moved
+/// by `base` too, the undefined ones are looked up with `resolve`, and
+/// undefined weak symbols it doesn't know are 0 like with the dynamic
+/// linker.
+///
+/// Returns the relocations that weren't applied: other types, IRELATIVE
+/// ones needing the resolver function to be run, symbols `resolve`
+/// doesn't know and slots outside of the image. Those of SHT_RELR have
+/// the RELATIVE type of the machine, 0 for the other machines. Fails if a relocation
+/// section can't be parsed.
+pub fn apply_relocations<F>(
+    image: &mut LoadImage,
+    data: &[u8],
+    header: &x64,
+    base: u64,
+    mut resolve: F,
+) -> Result<Vec<Relocation>>
+where
+    F: FnMut(&str) -> Option<u64>,
+{
+    let big_endian = Data::is_big_endian(&header.e_ident);
+    let link_base = image.base;
+    image.base = image.base.wrapping_add(base);
+    for mapping in &mut image.mappings {
+        mapping.vaddr = mapping.vaddr.wrapping_add(base);
+    }
+
+    let mut skipped = Vec::new();
+    for shdr in section::from_bytes(data, header)?.filter(|shdr| {
+        matches!(shdr.sh_type, SHT::REL | SHT::RELA | SHT::RELR) && shdr.sh_flags & SHF::ALLOC != 0
+    }) {
+        // a SHT_RELR entry is a RELATIVE relocation with its addend in the slot
+        let relocations = match shdr.sh_type {
+            SHT::RELR => relocations::relr_from_bytes(data, header, &shdr)?
+                .into_iter()
+                .map(|r_offset| Relocation {
+                    r_offset,
+                    r_type: relocations::relative_type(header.e_machine).unwrap_or_default(),
+                    ..Relocation::default()
+                })
+                .collect(),
+            _ => relocations::from_bytes(data, header, &shdr)?,
+        };
+        let symtab = section::get(data, header, shdr.sh_link).ok();
+        let symbols = symtab
+            .as_ref()
+            .and_then(|symtab| symbols::from_bytes(data, header, symtab).ok());
+        let strtab = symtab
+            .as_ref()
+            .and_then(|symtab| symbols::strtab(data, header, symtab).ok());
+        for relocation in relocations {
+            let slot = usize::try_from(relocation.r_offset.wrapping_sub(link_base))
+                .ok()
+                .filter(|slot| {
+                    slot.checked_add(8)
+                        .is_some_and(|end| end <= image.bytes.len())
+                });
+            let (slot, formula) = match (slot, formula(header.e_machine, relocation.r_type)) {
+                (Some(slot), Some(formula)) => (slot, formula),
+                _ => {
+                    skipped.push(relocation);
+                    continue;
+                }
+            };
+            // SHT_REL relocations find their addend in the slot
+            let addend = match relocation.r_addend {
+                Some(addend) => addend as u64,
+                None => read_u64(&image.bytes, slot, big_endian).unwrap_or_default(),
+            };
+            let symbol = symbols
+                .as_ref()
+                .and_then(|symbols| symbols.get(relocation.r_sym as usize));
+            let address = match (&symbol, formula) {
+                (_, Formula::Relative) => Some(0),
+                (_, _) if relocation.r_sym == 0 => Some(0),
+                (None, _) => None,
+                (Some(symbol), _) if symbol.st_shndx == SHN::ABS => Some(symbol.st_value),
+                (Some(symbol), _) if symbol.st_shndx != SHN::UNDEF => {
+                    Some(symbol.st_value.wrapping_add(base))
+                }
+                (Some(symbol), _) => strtab
+                    .as_ref()
+                    .and_then(|strtab| symbol.name(strtab).ok()?.to_str())
+                    .and_then(&mut resolve)
+                    .or((symbol.bind() == STB::WEAK).then_some(0)),
+            };
+            let value = match (address, formula) {
+                (Some(_), Formula::Relative) => base.wrapping_add(addend),
+                (Some(address), Formula::Symbol) => address,
+                (Some(address), Formula::SymbolAddend) => address.wrapping_add(addend),
+                (None, _) => {
+                    skipped.push(relocation);
+                    continue;
+                }
+            };
+            let bytes = match big_endian {
+                true => value.to_be_bytes(),
+                false => value.to_le_bytes(),
+            };
+            image.bytes[slot..slot + 8].copy_from_slice(&bytes);
+        }
+    }
+    Ok(skipped)
+}
@@ -0,0 +1,358 @@
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::json::{Json, ToJson};
+use crate::utils::read_u64;
+use crate::Result;
+use alloc::borrow::Cow;
+use core::fmt;
+
+use super::data::Data;
+use super::dynamic::{self, DT};
+use super::program;
+use super::relocations::{self, Relocation};
+use super::section::{self, Elf64_Shdr, SHN, SHT};
+use super::symbols::{self, Elf64_Sym, STT};
+use super::types::Type;
+use super::x64::x64;
+
+/// The arrays of function pointers run around `main`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrayKind {
+    /// `.preinit_array`, run before the constructors of the libraries, executables only
+    PreInit,
+    /// `.init_array`, the constructors, run in order after DT_INIT
+    Init,
+    /// `.fini_array`, the destructors, run in reverse order before DT_FINI
+    Fini,
+}
+
+impl ArrayKind {
+    /// Name of the section holding the array
+    pub fn section_name(&self) -> &'static str {
+        match self {
+            ArrayKind::PreInit => ".preinit_array",
+            ArrayKind::Init => ".init_array",
+            ArrayKind::Fini => ".fini_array",
+        }
+    }
+
+    fn section_type(&self) -> u32 {
+        match self {
+            ArrayKind::PreInit => SHT::PREINIT_ARRAY,
+            ArrayKind::Init => SHT::INIT_ARRAY,
+            ArrayKind::Fini => SHT::FINI_ARRAY,
+        }
+    }
+
+    /// The dynamic tags of the address and the size of the array
+    fn tags(&self) -> (i64, i64) {
+        match self {
+            ArrayKind::PreInit => (DT::PREINIT_ARRAY, DT::PREINIT_ARRAYSZ),
+            ArrayKind::Init => (DT::INIT_ARRAY, DT::INIT_ARRAYSZ),
+            ArrayKind::Fini => (DT::FINI_ARRAY, DT::FINI_ARRAYSZ),
+        }
+    }
+}
+
+/// A function run at load or exit time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Function<'a> {
+    /// address of the function, once relocated
+    pub address: u64,
+    /// name of the function symbol covering the address and the offset in it
+    pub symbol: Option<(ByteStr<'a>, u64)>,
+}
+
+/// An array of function pointers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionArray<'a> {
+    /// which array this is
+    pub kind: ArrayKind,
+    /// name of the section, None if found with the dynamic tags
+    pub section: Option<ByteStr<'a>>,
+    /// address of the array
+    pub address: u64,
+    /// the functions in array order
+    pub functions: Vec<Function<'a>>,
+}
+
+/// The constructors and destructors of an ELF64 file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InitFini<'a> {
+    /// DT_INIT, or the start of `.init` without dynamic section
+    pub init: Option<Function<'a>>,
+    /// DT_FINI, or the start of `.fini` without dynamic section
+    pub fini: Option<Function<'a>>,
+    /// the preinit, init and fini arrays found, in that order, relocatable
+    /// files may have several of each kind
+    pub arrays: Vec<FunctionArray<'a>>,
+}
+
+/// The function symbols of `.symtab`, or of `.dynsym` when stripped
+fn functions<'a>(
+    data: &'a [u8],
+    header: &x64,
+    shdrs: &[Cow<'a, Elf64_Shdr>],
+) -> Vec<(Cow<'a, Elf64_Sym>, ByteStr<'a>)> {
+    let symtab = shdrs
+        .iter()
+        .find(|shdr| shdr.sh_type == SHT::SYMTAB)
+        .or_else(|| shdrs.iter().find(|shdr| shdr.sh_type == SHT::DYNSYM));
+    let symtab = match symtab {
+        Some(symtab) => symtab,
+        None => return Vec::new(),
+    };
+    let strtab = symbols::strtab(data, header, symtab).unwrap_or_default();
+    match symbols::from_bytes(data, header, symtab) {
+        Ok(symbols) => symbols
+            .filter(|symbol| matches!(symbol.kind(), STT::FUNC | STT::GNU_IFUNC))
+            .filter(|symbol| symbol.st_shndx != SHN::UNDEF)
+            .filter_map(|symbol| Some((symbol.clone(), symbol.name(&strtab).ok()?)))
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Name the function at `address`, in section `shndx` for relocatable files
+fn lookup<'a>(
+    functions: &[(Cow<'a, Elf64_Sym>, ByteStr<'a>)],
+    shndx: Option<u16>,
+    address: u64,
+) -> Option<(ByteStr<'a>, u64)> {
+    let candidates = functions
+        .iter()
+        .filter(|(symbol, _)| shndx.is_none_or(|shndx| symbol.st_shndx == shndx));
+    let exact = candidates
+        .clone()
+        .find(|(symbol, _)| symbol.st_value == address)
+        .map(|(_, name)| (*name, 0));
+    exact.or_else(|| {
+        candidates
+            .filter(|(symbol, _)| {
+                address > symbol.st_value && address - symbol.st_value < symbol.st_size
+            })
+            .map(|(symbol, name)| (*name, address - symbol.st_value))
+            .next()
+    })
+}
+
+/// A relocation with the section it applies to and its symbol
+type SlotRelocation<'a> = (u32, Relocation, Option<Cow<'a, Elf64_Sym>>);
+
+fn slot_relocations<'a>(
+    data: &'a [u8],
+    header: &x64,
+    shdrs: &[Cow<'a, Elf64_Shdr>],
+) -> Vec<SlotRelocation<'a>> {
+    let mut slots = Vec::new();
+    for shdr in shdrs
+        .iter()
+        .filter(|shdr| shdr.sh_type == SHT::REL || shdr.sh_type == SHT::RELA)
+    {
+        let relocations = match relocations::from_bytes(data, header, shdr) {
+            Ok(relocations) => relocations,
+            Err(_) => continue,
+        };
+        let symbols = section::get(data, header, shdr.sh_link)
+            .ok()
+            .and_then(|symtab| symbols::from_bytes(data, header, &symtab).ok());
+        for relocation in relocations {
+            let symbol = match relocation.r_sym {
+                0 => None,
+                index => symbols
+                    .as_ref()
+                    .and_then(|symbols| symbols.get(index as usize)),
+            };
+            slots.push((shdr.sh_info, relocation, symbol));
+        }
+    }
+    slots
+}
+
+/// List the functions run at load and exit time
+///
+/// The array slots are read from the file and replaced by the target of
+/// their relocation when they have one, like the RELATIVE relocations of
+/// position independent files. Fails if the section header table can't
+/// be parsed.
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64) -> Result<InitFini<'a>> {
+    let shdrs: Vec<Cow<Elf64_Shdr>> = section::from_bytes(data, header)?.collect();
+    let names = section::names(data, header).unwrap_or_default();
+    let relocatable = Type::from(header.e_type) == Type::Rel;
+    let functions = functions(data, header, &shdrs);
+    let relocations = slot_relocations(data, header, &shdrs);
+    let big_endian = Data::is_big_endian(&header.e_ident);
+    let dynamic = dynamic::from_bytes(data, header).ok().flatten();
+    let get = |tag| dynamic.as_ref().and_then(|dynamic| dynamic.get(tag));
+    let function = |address| Function {
+        address,
+        symbol: lookup(&functions, None, address),
+    };
+    let named = |name: &str| {
+        shdrs
+            .iter()
+            .find(|shdr| shdr.name(&names).is_ok_and(|found| found == name))
+            .map(|shdr| shdr.sh_addr)
+    };
+
+    let (init, fini) = match dynamic {
+        Some(_) => (get(DT::INIT), get(DT::FINI)),
+        None if !relocatable => (named(".init"), named(".fini")),
+        None => (None, None),
+    };
+
+    let mut arrays = Vec::new();
+    for kind in [ArrayKind::PreInit, ArrayKind::Init, ArrayKind::Fini] {
+        // (section index, address, file offset, size) of each array
+        let mut found: Vec<_> = shdrs
+            .iter()
+            .enumerate()
+            .filter(|(_, shdr)| shdr.sh_type == kind.section_type())
+            .map(|(index, shdr)| (Some(index), shdr.sh_addr, shdr.sh_offset, shdr.sh_size))
+            .collect();
+        if found.is_empty() {
+            let (tag, size_tag) = kind.tags();
+            if let (Some(address), Some(size)) = (get(tag), get(size_tag)) {
+                if let Some(offset) = program::vaddr_to_offset(data, header, address) {
+                    found.push((None, address, offset, size));
+                }
+            }
+        }
+        for (index, address, offset, size) in found {
+            let bytes = usize::try_from(offset)
+                .ok()
+                .and_then(|offset| data.get(offset..))
+                .unwrap_or_default();
+            let mut functions_of_array = Vec::new();
+            for slot in 0..size / 8 {
+                let value = match read_u64(bytes, slot as usize * 8, big_endian) {
+                    Some(value) => value,
+                    None => break,
+                };
+                // relocatable files are relocated by section offset
+                let offset = slot * 8;
+                let relocation =
+                    relocations
+                        .iter()
+                        .find(|(target, relocation, _)| match relocatable {
+                            true => {
+                                Some(*target as usize) == index && relocation.r_offset == offset
+                            }
+                            false => relocation.r_offset == address.wrapping_add(offset),
+                        });
+                let entry = match relocation {
+                    Some((_, relocation, symbol)) => {
+                        let addend = relocation.r_addend.map_or(value, |addend| addend as u64);
+                        let base = symbol.as_ref().map_or(0, |symbol| symbol.st_value);
+                        let address = base.wrapping_add(addend);
+                        let shndx = symbol
+                            .as_ref()
+                            .filter(|_| relocatable)
+                            .map(|symbol| symbol.st_shndx);
+                        Function {
+                            address,
+                            symbol: lookup(&functions, shndx, address),
+                        }
+                    }
+                    None => function(value),
+                };
+                functions_of_array.push(entry);
+            }
+            arrays.push(FunctionArray {
+                kind,
+                section: index.and_then(|index| shdrs[index].name(&names).ok()),
+                address,
+                functions: functions_of_array,
+            });
+        }
+    }
+
+    Ok(InitFini {
+        init: init.map(function),
+        fini: fini.map(function),
+        arrays,
+    })
+}
+
+impl fmt::Display for Function<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.address)?;
+        match self.symbol {
+            Some((name, 0)) => write!(f, " {}", name),
+            Some((name, offset)) => write!(f, " {}+{:#x}", name, offset),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for InitFini<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.init.is_none() && self.fini.is_none() && self.arrays.is_empty() {
+            return writeln!(
+                f,
+                "There are no initialization or termination functions in this file."
+            );
+        }
+        if let Some(init) = &self.init {
+            writeln!(f, "INIT:  {}", init)?;
+        }
+        if let Some(fini) = &self.fini {
+            writeln!(f, "FINI:  {}", fini)?;
+        }
+        for array in &self.arrays {
+            writeln!(f)?;
+            writeln!(
+                f,
+                "{} at {:#x} contains {} entries:",
+                array
+                    .section
+                    .unwrap_or(ByteStr::from(array.kind.section_name())),
+                array.address,
+                array.functions.len()
+            )?;
+            for (index, function) in array.functions.iter().enumerate() {
+                writeln!(f, "  [{:>2}] {}", index, function)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for Function<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("address", Json::from(self.address)),
+            ("symbol", Json::from(self.symbol.map(|(name, _)| name))),
+            ("offset", Json::from(self.symbol.map(|(_, offset)| offset))),
+        ])
+    }
+}
+
+impl ToJson for InitFini<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            (
+                "init",
+                Json::from(self.init.as_ref().map(Function::to_json)),
+            ),
+            (
+                "fini",
+                Json::from(self.fini.as_ref().map(Function::to_json)),
+            ),
+            (
+                "arrays",
+                Json::array(self.arrays.iter().map(|array| {
+                    Json::object([
+                        ("kind", Json::from(array.kind.section_name())),
+                        ("section", Json::from(array.section)),
+                        ("address", Json::from(array.address)),
+                        (
+                            "functions",
+                            Json::array(array.functions.iter().map(Function::to_json)),
+                        ),
+                    ])
+                })),
+            ),
+        ])
+    }
+}
@@ -0,0 +1,319 @@
+//! The dynamic symbols a file exports and imports
+//!
+//! The exports are the defined global and weak symbols of `.dynsym`, what
+//! other files can link against, with their GNU version. The imports are
+//! its undefined symbols, resolved at load time in the DT_NEEDED
+//! libraries. A versioned import names the library of its version in
+//! `.gnu.version_r`, the others may come from any needed library until
+//! [`Imports::resolve`] finds them in the exports of one.
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::json::{Json, ToJson};
+use crate::Result;
+use alloc::borrow::Cow;
+use core::fmt;
+
+use super::dynamic;
+use super::section::{self, SHN, SHT};
+use super::symbols::{self, Elf64_Sym, STB};
+use super::symver::{self, SymbolVersion};
+use super::x64::x64;
+
+/// A defined dynamic symbol
+#[derive(Debug, Clone)]
+pub struct Export<'a> {
+    /// name of the symbol
+    pub name: ByteStr<'a>,
+    /// the entry of `.dynsym`, in the byte order of the host
+    pub symbol: Elf64_Sym,
+    /// the version defined for the symbol, None if unversioned
+    pub version: Option<SymbolVersion<'a>>,
+}
+
+/// Where the definition of an imported symbol is expected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider<'a> {
+    /// the library its version is needed from
+    Version(&'a str),
+    /// the needed library exporting it, found by [`Imports::resolve`]
+    Found(&'a str),
+    /// unversioned and not found yet
+    Unknown,
+}
+
+impl<'a> Provider<'a> {
+    /// The library, None if unknown
+    pub fn library(&self) -> Option<&'a str> {
+        match self {
+            Provider::Version(library) | Provider::Found(library) => Some(library),
+            Provider::Unknown => None,
+        }
+    }
+}
+
+/// An undefined dynamic symbol
+#[derive(Debug, Clone)]
+pub struct Import<'a> {
+    /// name of the symbol
+    pub name: ByteStr<'a>,
+    /// the entry of `.dynsym`, a weak import may stay unresolved
+    pub symbol: Elf64_Sym,
+    /// name of the version needed, None if unversioned
+    pub version: Option<&'a str>,
+    /// the library likely providing it
+    pub provider: Provider<'a>,
+}
+
+/// The exported symbols of a file
+#[derive(Debug, Clone, Default)]
+pub struct Exports<'a> {
+    /// DT_SONAME, the name other files record in their DT_NEEDED
+    pub soname: Option<&'a str>,
+    /// the symbols in `.dynsym` order
+    pub symbols: Vec<Export<'a>>,
+}
+
+/// The imported symbols of a file
+#[derive(Debug, Clone, Default)]
+pub struct Imports<'a> {
+    /// the DT_NEEDED libraries, in load order
+    pub needed: Vec<&'a str>,
+    /// the symbols in `.dynsym` order
+    pub symbols: Vec<Import<'a>>,
+}
+
+impl<'a> Imports<'a> {
+    /// Mark the imports defined by the exports of the needed `library`
+    ///
+    /// Call it for each library in DT_NEEDED order: like the dynamic
+    /// linker the first definition wins. A versioned import only matches an
+    /// export of the same version, an unversioned one any export.
+    pub fn resolve(&mut self, library: &'a str, exports: &Exports) {
+        for import in &mut self.symbols {
+            if matches!(import.provider, Provider::Found(_)) {
+                continue;
+            }
+            let found = exports.symbols.iter().any(|export| {
+                export.name == import.name
+                    && import.version.is_none_or(|version| {
+                        export.version.map(|version| version.name()) == Some(version)
+                    })
+            });
+            if found {
+                import.provider = Provider::Found(library);
+            }
+        }
+    }
+}
+
+/// The dynamic symbols, with their index in `.dynsym`
+fn dynamic_symbols<'a>(
+    data: &'a [u8],
+    header: &x64,
+) -> Result<Vec<(usize, Elf64_Sym, ByteStr<'a>)>> {
+    let (shdr, strtab) =
+        match section::from_bytes(data, header)?.find(|shdr| shdr.sh_type == SHT::DYNSYM) {
+            Some(shdr) => {
+                let strtab = symbols::strtab(data, header, &shdr)?;
+                (shdr, strtab)
+            }
+            // without section headers, the table the loader uses
+            None => match symbols::from_dynamic(data, header) {
+                Some((shdr, strtab)) => (Cow::Owned(shdr), strtab),
+                None => return Ok(Vec::new()),
+            },
+        };
+    Ok(symbols::from_bytes(data, header, &shdr)?
+        .enumerate()
+        .skip(1)
+        .filter_map(|(index, symbol)| {
+            let name = symbol.name(&strtab).ok().filter(|name| !name.is_empty())?;
+            Some((index, symbol.into_owned(), name))
+        })
+        .collect())
+}
+
+/// List the defined global, weak and unique dynamic symbols
+///
+/// Empty for files without a dynamic symbol table. Fails if the section header table
+/// or the dynamic symbol table can't be parsed.
+pub fn exports<'a>(data: &'a [u8], header: &x64) -> Result<Exports<'a>> {
+    let versions = symver::from_bytes(data, header).ok().flatten();
+    let soname = dynamic::from_bytes(data, header)
+        .ok()
+        .flatten()
+        .and_then(|dynamic| dynamic.soname());
+    let symbols = dynamic_symbols(data, header)?
+        .into_iter()
+        .filter(|(_, symbol, _)| symbol.st_shndx != SHN::UNDEF)
+        .filter(|(_, symbol, _)| matches!(symbol.bind(), STB::GLOBAL | STB::WEAK | STB::GNU_UNIQUE))
+        .map(|(index, symbol, name)| Export {
+            name,
+            version: versions
+                .as_ref()
+                .and_then(|versions| versions.get(index, &symbol)),
+            symbol,
+        })
+        .collect();
+    Ok(Exports { soname, symbols })
+}
+
+/// List the undefined dynamic symbols and the library of their version
+///
+/// Empty for files without a dynamic symbol table. Fails if the section header table
+/// or the dynamic symbol table can't be parsed.
+pub fn imports<'a>(data: &'a [u8], header: &x64) -> Result<Imports<'a>> {
+    let versions = symver::from_bytes(data, header).ok().flatten();
+    let needed = dynamic::from_bytes(data, header)
+        .ok()
+        .flatten()
+        .map(|dynamic| dynamic.needed())
+        .unwrap_or_default();
+    let symbols = dynamic_symbols(data, header)?
+        .into_iter()
+        .filter(|(_, symbol, _)| symbol.st_shndx == SHN::UNDEF)
+        .map(|(index, symbol, name)| {
+            let version = versions
+                .as_ref()
+                .and_then(|versions| versions.get(index, &symbol));
+            let library = versions
+                .as_ref()
+                .zip(version)
+                .and_then(|(versions, _)| versions.library(versions.versym(index)?));
+            Import {
+                name,
+                symbol,
+                version: version.map(|version| version.name()),
+                provider: library.map_or(Provider::Unknown, Provider::Version),
+            }
+        })
+        .collect();
+    Ok(Imports { needed, symbols })
+}
+
+impl fmt::Display for Exports<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(soname) = self.soname {
+            writeln!(f, "Library soname: [{}]", soname)?;
+        }
+        if self.symbols.is_empty() {
+            return writeln!(f, "There are no exported dynamic symbols in this file.");
+        }
+        writeln!(f, "{} exported symbols:", self.symbols.len())?;
+        writeln!(
+            f,
+            "  {:<16} {:>7} {:<7} {:<6} Name",
+            "Value", "Size", "Type", "Bind"
+        )?;
+        for export in &self.symbols {
+            // like readelf, omit the version of the symbols defining a version
+            let version = export
+                .version
+                .filter(|version| version.name() != export.name)
+                .map(|version| version.to_string());
+            writeln!(
+                f,
+                "  {:016x} {:>7} {:<7} {:<6} {}{}",
+                export.symbol.st_value,
+                export.symbol.st_size,
+                export.symbol.type_name(),
+                export.symbol.bind_name(),
+                export.name,
+                version.unwrap_or_default()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Imports<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for library in &self.needed {
+            writeln!(f, "NEEDED {}", library)?;
+        }
+        if self.symbols.is_empty() {
+            return writeln!(f, "There are no imported dynamic symbols in this file.");
+        }
+        writeln!(f, "{} imported symbols:", self.symbols.len())?;
+        writeln!(f, "  {:<7} {:<6} {:<20} Name", "Type", "Bind", "Library")?;
+        for import in &self.symbols {
+            let library = match import.provider {
+                Provider::Version(library) | Provider::Found(library) => library,
+                Provider::Unknown => "?",
+            };
+            let version = import.version.map(|version| format!("@{}", version));
+            writeln!(
+                f,
+                "  {:<7} {:<6} {:<20} {}{}",
+                import.symbol.type_name(),
+                import.symbol.bind_name(),
+                library,
+                import.name,
+                version.unwrap_or_default()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for Export<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("name", Json::from(self.name)),
+            ("value", Json::from(self.symbol.st_value)),
+            ("size", Json::from(self.symbol.st_size)),
+            ("type", Json::from(self.symbol.type_name().as_ref())),
+            ("bind", Json::from(self.symbol.bind_name().as_ref())),
+            (
+                "version",
+                Json::from(self.version.map(|version| version.name())),
+            ),
+            (
+                "default",
+                Json::from(matches!(self.version, Some(SymbolVersion::Default(_)))),
+            ),
+        ])
+    }
+}
+
+impl ToJson for Exports<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("soname", Json::from(self.soname)),
+            (
+                "symbols",
+                Json::array(self.symbols.iter().map(Export::to_json)),
+            ),
+        ])
+    }
+}
+
+impl ToJson for Import<'_> {
+    fn to_json(&self) -> Json {
+        let source = match self.provider {
+            Provider::Version(_) => Some("version"),
+            Provider::Found(_) => Some("search path"),
+            Provider::Unknown => None,
+        };
+        Json::object([
+            ("name", Json::from(self.name)),
+            ("type", Json::from(self.symbol.type_name().as_ref())),
+            ("bind", Json::from(self.symbol.bind_name().as_ref())),
+            ("version", Json::from(self.version)),
+            ("library", Json::from(self.provider.library())),
+            ("source", Json::from(source)),
+        ])
+    }
+}
+
+impl ToJson for Imports<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("needed", Json::array(self.needed.iter().copied())),
+            (
+                "symbols",
+                Json::array(self.symbols.iter().map(Import::to_json)),
+            ),
+        ])
+    }
+}
@@ -0,0 +1,368 @@
+//! Check the memory permissions of a file, W^X and the segment mappings
+//!
+//! A page should be writable or executable, never both: RWX segments,
+//! executable stacks, writable code and text relocations let an attacker
+//! write code and run it. The flags of each allocated section are also
+//! compared with the permissions of the PT_LOAD segment mapping it, a
+//! writable section in a read only segment faults at its first write.
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::json::{Json, ToJson};
+use crate::Result;
+use core::fmt;
+
+use super::dynamic::{self, DF, DT};
+use super::program::{self, PF, PT};
+use super::section::{self, SectionFlags, SHT};
+use super::strtab::CORRUPT;
+use super::types::Type;
+use super::x64::x64;
+
+/// How serious a finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// unusual but harmless, like old linker layouts
+    Note,
+    /// weakens the protections or may break at load time
+    Warning,
+    /// breaks W^X or faults at run time
+    Error,
+}
+
+impl Severity {
+    /// Name of the severity, upper case
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Note => "NOTE",
+            Severity::Warning => "WARNING",
+            Severity::Error => "ERROR",
+        }
+    }
+}
+
+/// What a finding is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    /// a PT_LOAD segment is readable, writable and executable
+    RwxSegment,
+    /// PT_GNU_STACK asks for an executable stack
+    ExecutableStack,
+    /// no PT_GNU_STACK, the stack is executable on older kernels and machines
+    MissingStack,
+    /// a code section is mapped writable
+    WritableCode,
+    /// DT_TEXTREL, the loader makes the code writable to relocate it
+    TextRelocations,
+    /// the flags of a section don't match the segment mapping it
+    SectionFlags,
+    /// an allocated section isn't mapped by any PT_LOAD segment
+    UnmappedSection,
+}
+
+impl Check {
+    /// Short name of the check, ex: `rwx-segment`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Check::RwxSegment => "rwx-segment",
+            Check::ExecutableStack => "exec-stack",
+            Check::MissingStack => "missing-stack",
+            Check::WritableCode => "writable-code",
+            Check::TextRelocations => "textrel",
+            Check::SectionFlags => "section-flags",
+            Check::UnmappedSection => "unmapped-section",
+        }
+    }
+}
+
+/// A problem found in the file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding<'a> {
+    /// how serious it is
+    pub severity: Severity,
+    /// which check found it
+    pub check: Check,
+    /// index of the program header concerned
+    pub segment: Option<usize>,
+    /// name of the section concerned
+    pub section: Option<ByteStr<'a>>,
+    /// what is wrong
+    pub message: String,
+}
+
+/// The findings of the permission checks of an ELF64 file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Lint<'a> {
+    /// the findings, the most serious first, then in file order
+    pub findings: Vec<Finding<'a>>,
+}
+
+impl Lint<'_> {
+    /// Number of findings of a severity
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .count()
+    }
+
+    /// The most serious severity found, None for a clean file
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.findings.iter().map(|finding| finding.severity).max()
+    }
+}
+
+/// Segment permissions like readelf prints them, ex: `RW`
+fn permissions(flags: u32) -> String {
+    let flag = |bit, c| if flags & bit != 0 { Some(c) } else { None };
+    [flag(PF::R, 'R'), flag(PF::W, 'W'), flag(PF::X, 'X')]
+        .iter()
+        .flatten()
+        .collect()
+}
+
+/// Run the permission checks
+///
+/// Relocatable files have no segments, only their sections are checked.
+/// Fails if the program or section header tables can't be parsed.
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64) -> Result<Lint<'a>> {
+    let phdrs: Vec<_> = program::from_bytes(data, header)?.collect();
+    let shdrs: Vec<_> = section::from_bytes(data, header)?.collect();
+    let names = section::names(data, header).unwrap_or_default();
+    let mut findings = Vec::new();
+    let mut finding = |severity, check, segment, section, message| {
+        findings.push(Finding {
+            severity,
+            check,
+            segment,
+            section,
+            message,
+        })
+    };
+
+    for (index, phdr) in phdrs.iter().enumerate() {
+        if phdr.p_type == PT::LOAD && phdr.p_flags & (PF::W | PF::X) == PF::W | PF::X {
+            finding(
+                Severity::Error,
+                Check::RwxSegment,
+                Some(index),
+                None,
+                format!(
+                    "LOAD segment {} at {:#x} is writable and executable",
+                    index, phdr.p_vaddr
+                ),
+            );
+        }
+    }
+    let loaded = phdrs.iter().any(|phdr| phdr.p_type == PT::LOAD);
+    let runnable = matches!(Type::from(header.e_type), Type::Exec | Type::Dyn);
+    match phdrs.iter().position(|phdr| phdr.p_type == PT::GNU_STACK) {
+        Some(index) if phdrs[index].p_flags & PF::X != 0 => finding(
+            Severity::Error,
+            Check::ExecutableStack,
+            Some(index),
+            None,
+            "GNU_STACK asks for an executable stack".to_string(),
+        ),
+        None if loaded && runnable => finding(
+            Severity::Warning,
+            Check::MissingStack,
+            None,
+            None,
+            "no GNU_STACK segment, the stack may be executable".to_string(),
+        ),
+        _ => {}
+    }
+    let dynamic = dynamic::from_bytes(data, header).ok().flatten();
+    let textrel = dynamic.as_ref().is_some_and(|dynamic| {
+        dynamic.get(DT::TEXTREL).is_some()
+            || dynamic
+                .get(DT::FLAGS)
+                .is_some_and(|flags| flags & DF::TEXTREL != 0)
+    });
+    if textrel {
+        finding(
+            Severity::Warning,
+            Check::TextRelocations,
+            None,
+            None,
+            "text relocations, the code is made writable while relocated".to_string(),
+        );
+    }
+
+    // the read only sections of a writable segment are fine under RELRO
+    let relro: Vec<_> = phdrs
+        .iter()
+        .filter(|phdr| phdr.p_type == PT::GNU_RELRO)
+        .collect();
+    let mut executable_data = Vec::new();
+    for shdr in shdrs.iter().skip(1) {
+        let flags = shdr.flags();
+        if !flags.contains(SectionFlags::ALLOC) || shdr.sh_size == 0 {
+            continue;
+        }
+        let name = shdr.name(&names).ok();
+        let label = name.unwrap_or(CORRUPT);
+        let write = flags.contains(SectionFlags::WRITE);
+        let exec = flags.contains(SectionFlags::EXECINSTR);
+        if write && exec {
+            finding(
+                Severity::Error,
+                Check::WritableCode,
+                None,
+                name,
+                format!("section {} is writable and executable", label),
+            );
+        }
+        if !loaded {
+            continue;
+        }
+        // .tbss only takes room in the TLS template
+        let tbss = flags.contains(SectionFlags::TLS) && shdr.sh_type == SHT::NOBITS;
+        let segment = phdrs
+            .iter()
+            .position(|phdr| phdr.p_type == PT::LOAD && phdr.contains_section(shdr));
+        let index = match segment {
+            Some(index) => index,
+            None if tbss => continue,
+            None => {
+                finding(
+                    Severity::Warning,
+                    Check::UnmappedSection,
+                    None,
+                    name,
+                    format!("allocated section {} is not in a LOAD segment", label),
+                );
+                continue;
+            }
+        };
+        let segment_flags = phdrs[index].p_flags;
+        let mapped = format!(
+            "section {} ({}) is mapped by LOAD segment {} ({})",
+            label,
+            flags,
+            index,
+            permissions(segment_flags)
+        );
+        if exec && !write && segment_flags & PF::W != 0 {
+            finding(
+                Severity::Error,
+                Check::WritableCode,
+                Some(index),
+                name,
+                format!("{}, the code is writable", mapped),
+            );
+        } else if write && segment_flags & PF::W == 0 {
+            finding(
+                Severity::Error,
+                Check::SectionFlags,
+                Some(index),
+                name,
+                format!("{}, writes fault", mapped),
+            );
+        } else if exec && segment_flags & PF::X == 0 {
+            finding(
+                Severity::Error,
+                Check::SectionFlags,
+                Some(index),
+                name,
+                format!("{}, the code can't run", mapped),
+            );
+        } else if !write
+            && segment_flags & PF::W != 0
+            && !relro.iter().any(|phdr| phdr.contains_section(shdr))
+        {
+            finding(
+                Severity::Warning,
+                Check::SectionFlags,
+                Some(index),
+                name,
+                format!("{}, the read only data is writable", mapped),
+            );
+        } else if !exec && segment_flags & PF::X != 0 {
+            executable_data.push((index, label));
+        }
+    }
+    // linked without -z separate-code, one note per segment is enough
+    let mut segments: Vec<usize> = executable_data.iter().map(|(index, _)| *index).collect();
+    segments.sort_unstable();
+    segments.dedup();
+    for index in segments {
+        let sections: Vec<String> = executable_data
+            .iter()
+            .filter(|(segment, _)| *segment == index)
+            .map(|(_, name)| name.to_string())
+            .collect();
+        finding(
+            Severity::Note,
+            Check::SectionFlags,
+            Some(index),
+            None,
+            format!(
+                "LOAD segment {} ({}) also maps data sections: {}",
+                index,
+                permissions(phdrs[index].p_flags),
+                sections.join(", ")
+            ),
+        );
+    }
+    // stable, the file order is kept for each severity
+    findings.sort_by_key(|finding| core::cmp::Reverse(finding.severity));
+    Ok(Lint { findings })
+}
+
+impl fmt::Display for Finding<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<7}  {:<16}  {}",
+            self.severity.as_str(),
+            self.check.as_str(),
+            self.message
+        )
+    }
+}
+
+impl fmt::Display for Lint<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.findings.is_empty() {
+            return writeln!(f, "No permission problems found in this file.");
+        }
+        for finding in &self.findings {
+            writeln!(f, "{}", finding)?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{} errors, {} warnings, {} notes",
+            self.count(Severity::Error),
+            self.count(Severity::Warning),
+            self.count(Severity::Note)
+        )
+    }
+}
+
+impl ToJson for Finding<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("severity", Json::from(self.severity.as_str())),
+            ("check", Json::from(self.check.as_str())),
+            ("segment", Json::from(self.segment)),
+            ("section", Json::from(self.section)),
+            ("message", Json::from(self.message.as_str())),
+        ])
+    }
+}
+
+impl ToJson for Lint<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("errors", Json::from(self.count(Severity::Error))),
+            ("warnings", Json::from(self.count(Severity::Warning))),
+            ("notes", Json::from(self.count(Severity::Note))),
+            (
+                "findings",
+                Json::array(self.findings.iter().map(Finding::to_json)),
+            ),
+        ])
+    }
+}
@@ -0,0 +1,295 @@
+/// Define the architecture of the object file
+///
+/// Machine define the possible values for the `e_machine` field
+/// Sources:
+/// * <https://www.man7.org/linux/man-pages/man5/elf.5.html>
+/// * <https://github.com/bminor/binutils-gdb/blob/master/include/elf/common.h>
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Machine {
+    /// No machine
+    None,
+    /// AT&T WE 32100
+    M32,
+    /// SUN SPARC
+    Sparc,
+    /// Intel 80386
+    I386,
+    /// Motorola m68k family
+    M68k,
+    /// Motorola m88k family
+    M88k,
+    /// Intel MCU
+    Iamcu,
+    /// Intel 80860
+    I860,
+    /// MIPS R3000 big-endian
+    Mips,
+    /// IBM System/370
+    S370,
+    /// MIPS R3000 little-endian
+    MipsRs3Le,
+    /// HPPA
+    Parisc,
+    /// Fujitsu VPP500
+    Vpp500,
+    /// Sun's v8plus
+    Sparc32Plus,
+    /// Intel 80960
+    I960,
+    /// PowerPC
+    Ppc,
+    /// PowerPC 64-bit
+    Ppc64,
+    /// IBM S390
+    S390,
+    /// IBM SPU/SPC
+    Spu,
+    /// NEC V800 series
+    V800,
+    /// Fujitsu FR20
+    Fr20,
+    /// TRW RH-32
+    Rh32,
+    /// Motorola RCE
+    Rce,
+    /// ARM
+    Arm,
+    /// Digital Alpha
+    FakeAlpha,
+    /// Hitachi SH
+    Sh,
+    /// SPARC v9 64-bit
+    SparcV9,
+    /// Siemens Tricore
+    Tricore,
+    /// Argonaut RISC Core
+    Arc,
+    /// Hitachi H8/300
+    H8_300,
+    /// Hitachi H8/300H
+    H8_300h,
+    /// Hitachi H8S
+    H8s,
+    /// Hitachi H8/500
+    H8_500,
+    /// Intel Merced
+    Ia64,
+    /// Stanford MIPS-X
+    MipsX,
+    /// Motorola Coldfire
+    Coldfire,
+    /// Motorola M68HC12
+    M68hc12,
+    /// AMD x86-64 architecture
+    X86_64,
+    /// Atmel AVR 8-bit microcontroller
+    Avr,
+    /// Tensilica Xtensa Architecture
+    Xtensa,
+    /// Texas Instruments msp430
+    Msp430,
+    /// QUALCOMM Hexagon
+    Hexagon,
+    /// ARM AARCH64
+    Aarch64,
+    /// Tilera TILEPro
+    Tilepro,
+    /// NVIDIA CUDA architecture
+    Cuda,
+    /// Tilera TILE-Gx
+    Tilegx,
+    /// AMD GPU
+    Amdgpu,
+    /// RISC-V
+    Riscv,
+    /// Linux BPF -- in-kernel virtual machine
+    Bpf,
+    /// C-SKY
+    Csky,
+    /// LoongArch
+    Loongarch,
+    /// Digital Alpha
+    Alpha,
+    /// Value not known by this crate
+    Unknown(u16),
+}
+
+impl Machine {
+    /// Decode the `e_machine` field
+    pub fn from_u16(value: u16) -> Self {
+        match value {
+            0 => Machine::None,
+            1 => Machine::M32,
+            2 => Machine::Sparc,
+            3 => Machine::I386,
+            4 => Machine::M68k,
+            5 => Machine::M88k,
+            6 => Machine::Iamcu,
+            7 => Machine::I860,
+            8 => Machine::Mips,
+            9 => Machine::S370,
+            10 => Machine::MipsRs3Le,
+            15 => Machine::Parisc,
+            17 => Machine::Vpp500,
+            18 => Machine::Sparc32Plus,
+            19 => Machine::I960,
+            20 => Machine::Ppc,
+            21 => Machine::Ppc64,
+            22 => Machine::S390,
+            23 => Machine::Spu,
+            36 => Machine::V800,
+            37 => Machine::Fr20,
+            38 => Machine::Rh32,
+            39 => Machine::Rce,
+            40 => Machine::Arm,
+            41 => Machine::FakeAlpha,
+            42 => Machine::Sh,
+            43 => Machine::SparcV9,
+            44 => Machine::Tricore,
+            45 => Machine::Arc,
+            46 => Machine::H8_300,
+            47 => Machine::H8_300h,
+            48 => Machine::H8s,
+            49 => Machine::H8_500,
+            50 => Machine::Ia64,
+            51 => Machine::MipsX,
+            52 => Machine::Coldfire,
+            53 => Machine::M68hc12,
+            62 => Machine::X86_64,
+            83 => Machine::Avr,
+            94 => Machine::Xtensa,
+            105 => Machine::Msp430,
+            164 => Machine::Hexagon,
+            183 => Machine::Aarch64,
+            188 => Machine::Tilepro,
+            190 => Machine::Cuda,
+            191 => Machine::Tilegx,
+            224 => Machine::Amdgpu,
+            243 => Machine::Riscv,
+            247 => Machine::Bpf,
+            252 => Machine::Csky,
+            0x102 => Machine::Loongarch,
+            0x9026 => Machine::Alpha,
+            value => Machine::Unknown(value),
+        }
+    }
+
+    /// The `e_machine` value of the architecture
+    pub fn to_u16(self) -> u16 {
+        match self {
+            Machine::None => 0,
+            Machine::M32 => 1,
+            Machine::Sparc => 2,
+            Machine::I386 => 3,
+            Machine::M68k => 4,
+            Machine::M88k => 5,
+            Machine::Iamcu => 6,
+            Machine::I860 => 7,
+            Machine::Mips => 8,
+            Machine::S370 => 9,
+            Machine::MipsRs3Le => 10,
+            Machine::Parisc => 15,
+            Machine::Vpp500 => 17,
+            Machine::Sparc32Plus => 18,
+            Machine::I960 => 19,
+            Machine::Ppc => 20,
+            Machine::Ppc64 => 21,
+            Machine::S390 => 22,
+            Machine::Spu => 23,
+            Machine::V800 => 36,
+            Machine::Fr20 => 37,
+            Machine::Rh32 => 38,
+            Machine::Rce => 39,
+            Machine::Arm => 40,
+            Machine::FakeAlpha => 41,
+            Machine::Sh => 42,
+            Machine::SparcV9 => 43,
+            Machine::Tricore => 44,
+            Machine::Arc => 45,
+            Machine::H8_300 => 46,
+            Machine::H8_300h => 47,
+            Machine::H8s => 48,
+            Machine::H8_500 => 49,
+            Machine::Ia64 => 50,
+            Machine::MipsX => 51,
+            Machine::Coldfire => 52,
+            Machine::M68hc12 => 53,
+            Machine::X86_64 => 62,
+            Machine::Avr => 83,
+            Machine::Xtensa => 94,
+            Machine::Msp430 => 105,
+            Machine::Hexagon => 164,
+            Machine::Aarch64 => 183,
+            Machine::Tilepro => 188,
+            Machine::Cuda => 190,
+            Machine::Tilegx => 191,
+            Machine::Amdgpu => 224,
+            Machine::Riscv => 243,
+            Machine::Bpf => 247,
+            Machine::Csky => 252,
+            Machine::Loongarch => 0x102,
+            Machine::Alpha => 0x9026,
+            Machine::Unknown(value) => value,
+        }
+    }
+
+    /// Name of the architecture as printed by readelf
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Machine::None => "None",
+            Machine::M32 => "WE32100",
+            Machine::Sparc => "Sparc",
+            Machine::I386 => "Intel 80386",
+            Machine::M68k => "MC68000",
+            Machine::M88k => "MC88000",
+            Machine::Iamcu => "Intel MCU",
+            Machine::I860 => "Intel 80860",
+            Machine::Mips => "MIPS R3000",
+            Machine::S370 => "IBM System/370",
+            Machine::MipsRs3Le => "MIPS R4000 big-endian",
+            Machine::Parisc => "HPPA",
+            Machine::Vpp500 => "Fujitsu VPP500",
+            Machine::Sparc32Plus => "Sparc v8+",
+            Machine::I960 => "Intel 80960",
+            Machine::Ppc => "PowerPC",
+            Machine::Ppc64 => "PowerPC64",
+            Machine::S390 => "IBM S/390",
+            Machine::Spu => "SPU",
+            Machine::V800 => "Renesas V850",
+            Machine::Fr20 => "Fujitsu FR20",
+            Machine::Rh32 => "TRW RH32",
+            Machine::Rce => "Motorola RCE",
+            Machine::Arm => "ARM",
+            Machine::FakeAlpha => "Digital Alpha (old)",
+            Machine::Sh => "Renesas / SuperH SH",
+            Machine::SparcV9 => "Sparc v9",
+            Machine::Tricore => "Siemens Tricore",
+            Machine::Arc => "ARC",
+            Machine::H8_300 => "Renesas H8/300",
+            Machine::H8_300h => "Renesas H8/300H",
+            Machine::H8s => "Renesas H8S",
+            Machine::H8_500 => "Renesas H8/500",
+            Machine::Ia64 => "Intel IA-64",
+            Machine::MipsX => "Stanford MIPS-X",
+            Machine::Coldfire => "Motorola Coldfire",
+            Machine::M68hc12 => "Motorola MC68HC12",
+            Machine::X86_64 => "Advanced Micro Devices X86-64",
+            Machine::Avr => "Atmel AVR 8-bit microcontroller",
+            Machine::Xtensa => "Tensilica Xtensa Processor",
+            Machine::Msp430 => "Texas Instruments msp430 microcontroller",
+            Machine::Hexagon => "QUALCOMM Hexagon",
+            Machine::Aarch64 => "AArch64",
+            Machine::Tilepro => "Tilera TILEPro",
+            Machine::Cuda => "NVIDIA CUDA architecture",
+            Machine::Tilegx => "Tilera TILE-Gx",
+            Machine::Amdgpu => "AMD GPU",
+            Machine::Riscv => "RISC-V",
+            Machine::Bpf => "Linux BPF",
+            Machine::Csky => "C-SKY",
+            Machine::Loongarch => "LoongArch",
+            Machine::Alpha => "Alpha",
+            Machine::Unknown(_) => "<unknown>",
+        }
+    }
+}
@@ -0,0 +1,331 @@
+//! The virtual address space of a file, like the map file of a linker
+//!
+//! The PT_LOAD segments are listed in address order with the allocated
+//! sections they hold and the symbols of each section, the addresses of a
+//! segment that no section covers are marked as fill and the addresses
+//! between two segments as gaps.
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::json::{Json, ToJson};
+use crate::Result;
+use core::fmt;
+
+use super::program::{self, PT};
+use super::section::{self, SHF, SHN, SHT};
+use super::symbols::{self, STT};
+use super::x64::x64;
+
+/// A range of addresses that nothing covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hole {
+    /// first address of the range
+    pub address: u64,
+    /// number of bytes
+    pub size: u64,
+}
+
+/// A symbol of the map
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MapSymbol<'a> {
+    /// name of the symbol
+    pub name: ByteStr<'a>,
+    /// `st_value` of the symbol
+    pub address: u64,
+    /// `st_size` of the symbol
+    pub size: u64,
+}
+
+/// An allocated section with the symbols defined in it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapSection<'a> {
+    /// name of the section
+    pub name: ByteStr<'a>,
+    /// first address of the section
+    pub address: u64,
+    /// size of the section in memory
+    pub size: u64,
+    /// the symbols in address order
+    pub symbols: Vec<MapSymbol<'a>>,
+}
+
+/// A PT_LOAD segment with the sections it holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapSegment<'a> {
+    /// index of the segment in the program header table
+    pub index: usize,
+    /// first address of the segment
+    pub address: u64,
+    /// size of the segment in memory
+    pub size: u64,
+    /// permissions of the segment, like readelf
+    pub flags: String,
+    /// the sections in address order
+    pub sections: Vec<MapSection<'a>>,
+    /// the addresses of the segment outside of its sections
+    pub fills: Vec<Hole>,
+}
+
+/// The address map of an ELF64 file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AddressMap<'a> {
+    /// the PT_LOAD segments in address order
+    pub segments: Vec<MapSegment<'a>>,
+    /// the addresses between two segments
+    pub gaps: Vec<Hole>,
+    /// the allocated sections outside of every segment, all of them in
+    /// relocatable files
+    pub unmapped: Vec<MapSection<'a>>,
+}
+
+/// Map the segments, sections and symbols of the file
+///
+/// The symbols come from `.symtab`, or `.dynsym` in stripped files: the
+/// named functions, objects and untyped symbols defined in a section, the
+/// TLS symbols hold offsets and aren't listed. Fails if the program or
+/// section header table or the symbol table can't be parsed.
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64) -> Result<AddressMap<'a>> {
+    let names = section::names(data, header).unwrap_or_default();
+    let shdrs: Vec<_> = section::from_bytes(data, header)?.collect();
+    let mut phdrs: Vec<_> = program::from_bytes(data, header)?
+        .enumerate()
+        .filter(|(_, phdr)| phdr.p_type == PT::LOAD)
+        .collect();
+    phdrs.sort_by_key(|(_, phdr)| phdr.p_vaddr);
+
+    // the allocated sections by index in the section header table
+    let mut sections: Vec<(usize, MapSection)> = shdrs
+        .iter()
+        .enumerate()
+        .filter(|(_, shdr)| shdr.sh_flags & SHF::ALLOC != 0 && shdr.sh_type != SHT::NULL)
+        .map(|(index, shdr)| {
+            let section = MapSection {
+                name: shdr.name(&names).unwrap_or_default(),
+                address: shdr.sh_addr,
+                size: shdr.sh_size,
+                symbols: Vec::new(),
+            };
+            (index, section)
+        })
+        .collect();
+    let table = [SHT::SYMTAB, SHT::DYNSYM]
+        .iter()
+        .find_map(|sh_type| shdrs.iter().find(|shdr| shdr.sh_type == *sh_type));
+    if let Some(table) = table {
+        let strtab = symbols::strtab(data, header, table)?;
+        for sym in symbols::from_bytes(data, header, table)? {
+            let listed = matches!(
+                sym.kind(),
+                STT::NOTYPE | STT::FUNC | STT::OBJECT | STT::GNU_IFUNC
+            );
+            if !listed || sym.st_shndx == SHN::UNDEF || sym.st_shndx >= SHN::LORESERVE {
+                continue;
+            }
+            let name = match sym.name(&strtab) {
+                Ok(name) if !name.is_empty() => name,
+                _ => continue,
+            };
+            let section = sections
+                .iter_mut()
+                .find(|(index, _)| *index == sym.st_shndx as usize);
+            if let Some((_, section)) = section {
+                section.symbols.push(MapSymbol {
+                    name,
+                    address: sym.st_value,
+                    size: sym.st_size,
+                });
+            }
+        }
+    }
+    let mut sections: Vec<MapSection> = sections
+        .into_iter()
+        .map(|(_, mut section)| {
+            section
+                .symbols
+                .sort_by(|a, b| a.address.cmp(&b.address).then(a.name.cmp(&b.name)));
+            section
+        })
+        .collect();
+    sections.sort_by_key(|section| section.address);
+
+    let mut map = AddressMap::default();
+    let mut previous_end = None;
+    for (index, phdr) in phdrs {
+        let start = phdr.p_vaddr;
+        let end = start.saturating_add(phdr.p_memsz);
+        let (inside, outside) = sections.into_iter().partition(|section: &MapSection| {
+            section.address >= start && section.address.saturating_add(section.size) <= end
+        });
+        sections = outside;
+        let mut segment = MapSegment {
+            index,
+            address: start,
+            size: phdr.p_memsz,
+            flags: phdr.flags_string().trim_end().to_string(),
+            sections: inside,
+            fills: Vec::new(),
+        };
+        let mut covered = start;
+        for section in &segment.sections {
+            if section.address > covered {
+                segment.fills.push(Hole {
+                    address: covered,
+                    size: section.address - covered,
+                });
+            }
+            covered = covered.max(section.address.saturating_add(section.size));
+        }
+        if covered < end {
+            segment.fills.push(Hole {
+                address: covered,
+                size: end - covered,
+            });
+        }
+        match previous_end {
+            Some(previous) if start > previous => map.gaps.push(Hole {
+                address: previous,
+                size: start - previous,
+            }),
+            _ => {}
+        }
+        previous_end = Some(previous_end.map_or(end, |previous: u64| previous.max(end)));
+        map.segments.push(segment);
+    }
+    map.unmapped = sections;
+    Ok(map)
+}
+
+/// Write the sections and the fills of a segment in address order
+fn fmt_sections(
+    f: &mut fmt::Formatter<'_>,
+    sections: &[MapSection],
+    fills: &[Hole],
+) -> fmt::Result {
+    let mut fills = fills.iter().peekable();
+    for section in sections {
+        while let Some(fill) = fills.next_if(|fill| fill.address < section.address) {
+            writeln!(f, "  0x{:016x} 0x{:08x}   *fill*", fill.address, fill.size)?;
+        }
+        writeln!(
+            f,
+            "  0x{:016x} 0x{:08x}   {}",
+            section.address, section.size, section.name
+        )?;
+        for symbol in &section.symbols {
+            writeln!(
+                f,
+                "  0x{:016x} 0x{:08x}     {}",
+                symbol.address, symbol.size, symbol.name
+            )?;
+        }
+    }
+    for fill in fills {
+        writeln!(f, "  0x{:016x} 0x{:08x}   *fill*", fill.address, fill.size)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for AddressMap<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.segments.is_empty() && self.unmapped.is_empty() {
+            return writeln!(
+                f,
+                "There are no loaded segments or allocated sections in this file."
+            );
+        }
+        let sections = self
+            .segments
+            .iter()
+            .map(|segment| segment.sections.len())
+            .sum::<usize>()
+            + self.unmapped.len();
+        writeln!(
+            f,
+            "Address map of {} segments and {} sections:",
+            self.segments.len(),
+            sections
+        )?;
+        writeln!(f, "  Address            Size       Name")?;
+        let mut gaps = self.gaps.iter().peekable();
+        for segment in &self.segments {
+            while let Some(gap) = gaps.next_if(|gap| gap.address < segment.address) {
+                writeln!(f, "  0x{:016x} 0x{:08x} *gap*", gap.address, gap.size)?;
+            }
+            writeln!(
+                f,
+                "  0x{:016x} 0x{:08x} LOAD [{}] {}",
+                segment.address, segment.size, segment.index, segment.flags
+            )?;
+            fmt_sections(f, &segment.sections, &segment.fills)?;
+        }
+        if !self.segments.is_empty() && !self.unmapped.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Allocated sections outside of the segments:")?;
+        }
+        fmt_sections(f, &self.unmapped, &[])
+    }
+}
+
+impl ToJson for Hole {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("address", Json::from(self.address)),
+            ("size", Json::from(self.size)),
+        ])
+    }
+}
+
+impl ToJson for MapSymbol<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("name", Json::from(self.name)),
+            ("address", Json::from(self.address)),
+            ("size", Json::from(self.size)),
+        ])
+    }
+}
+
+impl ToJson for MapSection<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("name", Json::from(self.name)),
+            ("address", Json::from(self.address)),
+            ("size", Json::from(self.size)),
+            (
+                "symbols",
+                Json::array(self.symbols.iter().map(MapSymbol::to_json)),
+            ),
+        ])
+    }
+}
+
+impl ToJson for MapSegment<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("index", Json::from(self.index as u64)),
+            ("address", Json::from(self.address)),
+            ("size", Json::from(self.size)),
+            ("flags", Json::from(self.flags.as_str())),
+            (
+                "sections",
+                Json::array(self.sections.iter().map(MapSection::to_json)),
+            ),
+            ("fills", Json::array(self.fills.iter().map(Hole::to_json))),
+        ])
+    }
+}
+
+impl ToJson for AddressMap<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            (
+                "segments",
+                Json::array(self.segments.iter().map(MapSegment::to_json)),
+            ),
+            ("gaps", Json::array(self.gaps.iter().map(Hole::to_json))),
+            (
+                "unmapped",
+                Json::array(self.unmapped.iter().map(MapSection::to_json)),
+            ),
+        ])
+    }
+}
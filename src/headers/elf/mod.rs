@@ -1,7 +1,167 @@
+use crate::utils::json::{Json, ToJson};
+use crate::{Error, Result};
+use alloc::borrow::Cow;
+use core::fmt;
+
+pub mod auxv;
+pub mod builder;
+pub mod checksec;
 pub mod class;
+pub mod compress;
+pub mod consts;
+pub mod coredump;
 pub mod data;
+pub mod debug;
+pub mod debugdata;
+pub mod debuglink;
+pub mod description;
+pub mod diff;
+pub mod digests;
+#[cfg(feature = "disasm")]
+pub mod disasm;
+pub mod dynamic;
+pub mod editor;
+pub mod ehframe;
+pub mod exidx;
+pub mod find;
+pub mod flags;
+pub mod gaps;
+pub mod hash;
 pub mod identification;
+pub mod image;
+pub mod init;
+pub mod linkage;
+pub mod lint;
+pub mod machine;
+pub mod map;
+pub mod module;
+pub mod notes;
+pub mod object;
 pub mod osabit;
+pub mod plt;
+pub mod process;
+pub mod program;
+pub mod provenance;
+pub mod query;
+pub mod raw;
+#[cfg(feature = "std")]
+pub mod reader;
+pub mod recover;
+pub mod registry;
+pub mod relocations;
+pub mod rpath;
+pub mod section;
+pub mod size;
+pub mod strings;
+pub mod strip;
+pub mod strtab;
+pub mod symbols;
+pub mod symver;
+pub mod table;
+pub mod tls;
+pub mod triage;
 pub mod types;
 pub mod version;
+pub mod visualize;
 pub mod x64;
+pub mod x86;
+pub mod xref;
+
+use class::Class;
+use identification::Indent;
+
+pub use object::{Elf, Warning};
+
+/// ELF file header of either class
+///
+/// Returned by [`from_bytes`] which reads `EI_CLASS` to pick the layout.
+#[derive(Debug, Clone)]
+pub enum ElfHeader<'a> {
+    /// 32 bits object
+    Elf32(Cow<'a, x86::x86>),
+    /// 64 bits object
+    Elf64(Cow<'a, x64::x64>),
+}
+
+impl ElfHeader<'_> {
+    /// Sanity check the header fields against the file they were read from
+    pub fn validate(&self, data: &[u8]) -> Result<()> {
+        match self {
+            ElfHeader::Elf32(header) => header.validate(data),
+            ElfHeader::Elf64(header) => header.validate(data),
+        }
+    }
+
+    /// Identification bytes, common to both classes
+    pub fn e_ident(&self) -> &[u8; 16] {
+        match self {
+            ElfHeader::Elf32(header) => &header.e_ident,
+            ElfHeader::Elf64(header) => &header.e_ident,
+        }
+    }
+}
+
+/// Parse the ELF header, dispatching on the class stored in `e_ident`
+///
+/// Fails if the data is too small, doesn't start with the ELF magic
+/// number or if the class is neither ELF32 nor ELF64.
+pub fn from_bytes(data: &[u8]) -> Result<ElfHeader<'_>> {
+    Indent::check_magic(data)?;
+    let class = *data.get(Indent::CLASS).ok_or(Error::TruncatedHeader {
+        expected: x86::x86::SIZE,
+        found: data.len(),
+    })?;
+    match Class::from(class) {
+        Class::Elf32 => x86::from_bytes(data).map(ElfHeader::Elf32),
+        Class::Elf64 => x64::from_bytes(data).map(ElfHeader::Elf64),
+        _ => Err(Error::UnsupportedClass(class)),
+    }
+}
+
+/// Check that a size field of the header holds the expected value
+pub(crate) fn check_size(field: &'static str, found: u16, expected: usize) -> Result<()> {
+    if found as usize != expected {
+        return Err(Error::InvalidHeaderField {
+            field,
+            expected,
+            found: found as usize,
+        });
+    }
+    Ok(())
+}
+
+impl fmt::Display for ElfHeader<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ElfHeader::Elf32(header) => header.fmt(f),
+            ElfHeader::Elf64(header) => header.fmt(f),
+        }
+    }
+}
+
+/// The file header ready to be printed like `readelf -h`
+pub struct FileHeader<'a> {
+    /// the header of either class
+    pub header: &'a ElfHeader<'a>,
+    /// write the field names with ANSI colors
+    pub color: bool,
+}
+
+impl fmt::Display for FileHeader<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = match self.header {
+            ElfHeader::Elf32(header) => header.rows(),
+            ElfHeader::Elf64(header) => header.rows(),
+        };
+        identification::fmt_header(f, &rows, self.color)
+    }
+}
+
+impl ToJson for ElfHeader<'_> {
+    fn to_json(&self) -> Json {
+        match self {
+            ElfHeader::Elf32(header) => header.to_json(),
+            ElfHeader::Elf64(header) => header.to_json(),
+        }
+    }
+}
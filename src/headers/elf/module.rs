@@ -0,0 +1,219 @@
+//! Loadable kernel modules, the `.ko` files of Linux
+//!
+//! A module is a relocatable object with three more sections: `.modinfo`
+//! holds the `key=value` strings of the MODULE_* macros (license,
+//! vermagic, dependencies, aliases, parameters), `__versions` the CRC of
+//! each kernel symbol the module was built against when CONFIG_MODVERSIONS
+//! is set, and `.gnu.linkonce.this_module` the `struct module` the kernel
+//! links in, with the name of the module and the relocations of its init
+//! and exit functions.
+//! Sources:
+//! * <https://github.com/torvalds/linux/blob/master/include/linux/module.h>
+//! * <https://github.com/torvalds/linux/blob/master/scripts/mod/modpost.c>
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::json::{Json, ToJson};
+use crate::utils::read_u64;
+use crate::Result;
+use core::fmt;
+
+use super::data::Data;
+use super::relocations;
+use super::section::{self, SHT};
+use super::symbols;
+use super::x64::x64;
+
+/// Size of a `struct modversion_info` on 64 bits targets
+const MODVERSION_SIZE: usize = 64;
+/// Offset of `name` in `struct module`, after `state` and `list`
+const MODULE_NAME_OFFSET: usize = 24;
+/// Size of the names of the modules and of the versioned symbols
+const MODULE_NAME_LEN: usize = 56;
+
+/// A kernel symbol and the CRC of its prototype
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModVersion<'a> {
+    /// CRC computed by genksyms
+    pub crc: u64,
+    /// name of the symbol
+    pub name: &'a str,
+}
+
+/// The kernel module specifics of an ELF64 file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KernelModule<'a> {
+    /// name of the module, from `.gnu.linkonce.this_module`
+    pub name: Option<&'a str>,
+    /// the `key=value` strings of `.modinfo`, in file order
+    pub info: Vec<(&'a str, &'a str)>,
+    /// the entries of `__versions`
+    pub versions: Vec<ModVersion<'a>>,
+    /// the symbols relocated in the module structure, `init_module` and
+    /// `cleanup_module`
+    pub functions: Vec<ByteStr<'a>>,
+}
+
+impl<'a> KernelModule<'a> {
+    /// The first value of a `.modinfo` key
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        self.info
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| *value)
+    }
+
+    /// License of the module, `GPL` for instance
+    pub fn license(&self) -> Option<&'a str> {
+        self.get("license")
+    }
+
+    /// Kernel release and options the module was built for
+    pub fn vermagic(&self) -> Option<&'a str> {
+        self.get("vermagic")
+    }
+
+    /// The modules that must be loaded first
+    pub fn depends(&self) -> Vec<&'a str> {
+        self.get("depends")
+            .map(|depends| depends.split(',').filter(|name| !name.is_empty()).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Read a NUL padded name
+fn name(bytes: &[u8]) -> Option<&str> {
+    let end = bytes
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(bytes.len());
+    core::str::from_utf8(&bytes[..end]).ok()
+}
+
+/// Decode the kernel module sections of the file
+///
+/// Returns None when there is neither `.modinfo` nor
+/// `.gnu.linkonce.this_module`. Fails if the section header table can't
+/// be parsed, the invalid strings and entries are skipped.
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64) -> Result<Option<KernelModule<'a>>> {
+    let big_endian = Data::is_big_endian(&header.e_ident);
+    let names = section::names(data, header).unwrap_or_default();
+    let shdrs: Vec<_> = section::from_bytes(data, header)?.collect();
+    let find = |wanted: &str| {
+        shdrs
+            .iter()
+            .position(|shdr| shdr.name(&names).is_ok_and(|name| name == wanted))
+    };
+    let (modinfo, this_module) = (find(".modinfo"), find(".gnu.linkonce.this_module"));
+    if modinfo.is_none() && this_module.is_none() {
+        return Ok(None);
+    }
+    let mut module = KernelModule::default();
+    if let Some(bytes) = modinfo.and_then(|index| shdrs[index].data(data)) {
+        module.info = bytes
+            .split(|byte| *byte == 0)
+            .filter_map(|text| core::str::from_utf8(text).ok())
+            .filter_map(|text| text.split_once('='))
+            .collect();
+    }
+    if let Some(bytes) = find("__versions").and_then(|index| shdrs[index].data(data)) {
+        module.versions = bytes
+            .chunks_exact(MODVERSION_SIZE)
+            .filter_map(|entry| {
+                Some(ModVersion {
+                    crc: read_u64(entry, 0, big_endian)?,
+                    name: name(&entry[8..8 + MODULE_NAME_LEN])?,
+                })
+            })
+            .collect();
+    }
+    let Some(this_module) = this_module else {
+        return Ok(Some(module));
+    };
+    module.name = shdrs[this_module]
+        .data(data)
+        .and_then(|bytes| bytes.get(MODULE_NAME_OFFSET..MODULE_NAME_OFFSET + MODULE_NAME_LEN))
+        .and_then(name);
+    let rela = shdrs.iter().find(|shdr| {
+        matches!(shdr.sh_type, SHT::REL | SHT::RELA) && shdr.sh_info as usize == this_module
+    });
+    let symtab = rela.and_then(|rela| shdrs.get(rela.sh_link as usize));
+    if let (Some(rela), Some(symtab)) = (rela, symtab) {
+        let strtab = symbols::strtab(data, header, symtab)?;
+        let syms: Vec<_> = symbols::from_bytes(data, header, symtab)?.collect();
+        let mut relocations = relocations::from_bytes(data, header, rela)?;
+        relocations.sort_by_key(|relocation| relocation.r_offset);
+        for relocation in relocations {
+            let name = syms
+                .get(relocation.r_sym as usize)
+                .and_then(|sym| sym.name(&strtab).ok());
+            if let Some(name) = name.filter(|name| !name.is_empty()) {
+                module.functions.push(name);
+            }
+        }
+    }
+    Ok(Some(module))
+}
+
+impl fmt::Display for KernelModule<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name {
+            Some(name) => writeln!(f, "Kernel module '{}':", name)?,
+            None => writeln!(f, "Kernel module:")?,
+        }
+        let width = self
+            .info
+            .iter()
+            .map(|(key, _)| key.len())
+            .max()
+            .unwrap_or(0);
+        for (key, value) in &self.info {
+            writeln!(f, "  {:<width$}  {}", key, value, width = width)?;
+        }
+        if !self.functions.is_empty() {
+            let functions: Vec<String> = self.functions.iter().map(ByteStr::to_string).collect();
+            writeln!(f, "  Functions: {}", functions.join(", "))?;
+        }
+        if !self.versions.is_empty() {
+            writeln!(f)?;
+            writeln!(
+                f,
+                "Symbol versions (__versions) of {} symbols:",
+                self.versions.len()
+            )?;
+            writeln!(f, "  CRC         Symbol")?;
+            for version in &self.versions {
+                writeln!(f, "  0x{:08x}  {}", version.crc, version.name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for ModVersion<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("crc", Json::from(self.crc)),
+            ("name", Json::from(self.name)),
+        ])
+    }
+}
+
+impl ToJson for KernelModule<'_> {
+    fn to_json(&self) -> Json {
+        let info = self.info.iter().map(|(key, value)| {
+            Json::object([("key", Json::from(*key)), ("value", Json::from(*value))])
+        });
+        Json::object([
+            ("name", Json::from(self.name)),
+            ("license", Json::from(self.license())),
+            ("vermagic", Json::from(self.vermagic())),
+            ("depends", Json::from(self.depends())),
+            ("info", Json::array(info)),
+            ("functions", Json::from(self.functions.clone())),
+            (
+                "versions",
+                Json::array(self.versions.iter().map(ModVersion::to_json)),
+            ),
+        ])
+    }
+}
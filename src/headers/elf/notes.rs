@@ -0,0 +1,493 @@
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::json::{Json, ToJson};
+use crate::utils::table::{Align, Table as TextTable};
+use crate::utils::{read_u32, read_u64};
+use alloc::borrow::Cow;
+use core::fmt;
+
+use super::data::Data;
+use super::program::{self, PT};
+use super::section::{self, SHT};
+use super::x64::x64;
+
+/// Define the types of the notes owned by `GNU`
+#[allow(non_camel_case_types)]
+pub struct NT_GNU {}
+impl NT_GNU {
+    /// ABI version tag
+    pub const ABI_TAG: u32 = 1;
+    /// Hardware capabilities
+    pub const HWCAP: u32 = 2;
+    /// Unique build ID bitstring
+    pub const BUILD_ID: u32 = 3;
+    /// Version of the gold linker
+    pub const GOLD_VERSION: u32 = 4;
+    /// Program property
+    pub const PROPERTY_TYPE_0: u32 = 5;
+}
+
+/// Define the types of the notes owned by `CORE` in core files
+#[allow(non_camel_case_types)]
+pub struct NT_CORE {}
+impl NT_CORE {
+    /// Process status, one per thread
+    pub const PRSTATUS: u32 = 1;
+    /// Floating point registers
+    pub const FPREGSET: u32 = 2;
+    /// Process information
+    pub const PRPSINFO: u32 = 3;
+    /// Task structure
+    pub const TASKSTRUCT: u32 = 4;
+    /// Auxiliary vector
+    pub const AUXV: u32 = 6;
+    /// Extended floating point registers of x86
+    pub const X86_XSTATE: u32 = 0x202;
+    /// Signal information
+    pub const SIGINFO: u32 = 0x5349_4749;
+    /// Files mapped in memory
+    pub const FILE: u32 = 0x4649_4c45;
+    /// SSE registers of i386
+    pub const PRXFPREG: u32 = 0x46e6_2b7f;
+}
+
+/// Define the operating systems of the NT_GNU_ABI_TAG note
+#[allow(non_camel_case_types)]
+pub struct ELF_NOTE_OS {}
+impl ELF_NOTE_OS {
+    pub const LINUX: u32 = 0;
+    pub const GNU: u32 = 1;
+    pub const SOLARIS2: u32 = 2;
+    pub const FREEBSD: u32 = 3;
+}
+
+/// Define the types of the properties stored in NT_GNU_PROPERTY_TYPE_0
+#[allow(non_camel_case_types)]
+pub struct GNU_PROPERTY {}
+impl GNU_PROPERTY {
+    /// Stack size
+    pub const STACK_SIZE: u32 = 1;
+    /// No copy relocation on protected data symbol
+    pub const NO_COPY_ON_PROTECTED: u32 = 2;
+    /// AArch64 features, bitmask of `AARCH64_FEATURE_1_*`
+    pub const AARCH64_FEATURE_1_AND: u32 = 0xc000_0000;
+    /// x86 features, bitmask of `X86_FEATURE_1_*`
+    pub const X86_FEATURE_1_AND: u32 = 0xc000_0002;
+    /// x86 instruction set used
+    pub const X86_ISA_1_USED: u32 = 0xc001_0002;
+    /// x86 instruction set needed
+    pub const X86_ISA_1_NEEDED: u32 = 0xc000_8002;
+    /// Indirect branch tracking
+    pub const X86_FEATURE_1_IBT: u32 = 0x1;
+    /// Shadow stack
+    pub const X86_FEATURE_1_SHSTK: u32 = 0x2;
+    /// Branch target identification
+    pub const AARCH64_FEATURE_1_BTI: u32 = 0x1;
+    /// Pointer authentication
+    pub const AARCH64_FEATURE_1_PAC: u32 = 0x2;
+}
+
+/// A note stored in a SHT_NOTE section or PT_NOTE segment
+///
+/// Each note is made of a header of three words (name size, descriptor
+/// size and type) followed by the name and the descriptor, both padded to
+/// the alignment of the note section.
+/// Sources:
+/// * <https://www.man7.org/linux/man-pages/man5/elf.5.html>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Note<'a> {
+    /// owner of the note, without the ending nul byte
+    pub name: &'a [u8],
+    /// note type, its meaning depends of the owner
+    pub n_type: u32,
+    /// note descriptor
+    pub desc: &'a [u8],
+    big_endian: bool,
+}
+
+/// Property stored in a NT_GNU_PROPERTY_TYPE_0 note
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Property<'a> {
+    /// property type, one of the `GNU_PROPERTY` values
+    pub pr_type: u32,
+    /// property data
+    pub data: &'a [u8],
+    big_endian: bool,
+}
+
+/// Typed content of a note
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NoteData<'a> {
+    /// NT_GNU_BUILD_ID
+    BuildId(&'a [u8]),
+    /// NT_GNU_ABI_TAG
+    AbiTag {
+        os: u32,
+        major: u32,
+        minor: u32,
+        subminor: u32,
+    },
+    /// NT_GNU_GOLD_VERSION
+    GoldVersion(&'a str),
+    /// NT_GNU_PROPERTY_TYPE_0
+    Properties(Vec<Property<'a>>),
+    /// Any note of a core file, owned by `CORE` or `LINUX`
+    Core(u32, &'a [u8]),
+    /// Any other note
+    Unknown,
+}
+
+fn align_up(value: usize, align: usize) -> Option<usize> {
+    Some(value.checked_add(align - 1)? & !(align - 1))
+}
+
+impl<'a> Note<'a> {
+    /// Owner of the note as a string, ex: `GNU`
+    pub fn owner(&self) -> &'a str {
+        core::str::from_utf8(self.name).unwrap_or("<corrupt>")
+    }
+
+    /// Decode the descriptor of the well known notes
+    pub fn decode(&self) -> NoteData<'a> {
+        let word = |index: usize| read_u32(self.desc, index * 4, self.big_endian);
+        match (self.name, self.n_type) {
+            (b"GNU", NT_GNU::BUILD_ID) => NoteData::BuildId(self.desc),
+            (b"GNU", NT_GNU::ABI_TAG) => match (word(0), word(1), word(2), word(3)) {
+                (Some(os), Some(major), Some(minor), Some(subminor)) => NoteData::AbiTag {
+                    os,
+                    major,
+                    minor,
+                    subminor,
+                },
+                _ => NoteData::Unknown,
+            },
+            (b"GNU", NT_GNU::GOLD_VERSION) => {
+                let end = self.desc.iter().position(|b| *b == 0);
+                match core::str::from_utf8(&self.desc[..end.unwrap_or(self.desc.len())]) {
+                    Ok(version) => NoteData::GoldVersion(version),
+                    Err(_) => NoteData::Unknown,
+                }
+            }
+            (b"GNU", NT_GNU::PROPERTY_TYPE_0) => NoteData::Properties(self.properties()),
+            (b"CORE", n_type) | (b"LINUX", n_type) => NoteData::Core(n_type, self.desc),
+            _ => NoteData::Unknown,
+        }
+    }
+
+    /// Properties of a NT_GNU_PROPERTY_TYPE_0 note
+    ///
+    /// Each property is padded to 8 bytes, as produced for ELF64 files.
+    fn properties(&self) -> Vec<Property<'a>> {
+        let mut properties = Vec::new();
+        let mut offset = 0;
+        while let (Some(pr_type), Some(size)) = (
+            read_u32(self.desc, offset, self.big_endian),
+            read_u32(self.desc, offset + 4, self.big_endian),
+        ) {
+            let start = offset + 8;
+            let data = match self.desc.get(start..start + size as usize) {
+                Some(data) => data,
+                None => break,
+            };
+            properties.push(Property {
+                pr_type,
+                data,
+                big_endian: self.big_endian,
+            });
+            offset = match align_up(start + size as usize, 8) {
+                Some(offset) => offset,
+                None => break,
+            };
+        }
+        properties
+    }
+
+    /// Name of the note type as printed by readelf
+    pub fn type_name(&self) -> Cow<'static, str> {
+        let name = match (self.name, self.n_type) {
+            (b"GNU", NT_GNU::ABI_TAG) => "NT_GNU_ABI_TAG (ABI version tag)",
+            (b"GNU", NT_GNU::HWCAP) => "NT_GNU_HWCAP (DSO-supplied software HWCAP info)",
+            (b"GNU", NT_GNU::BUILD_ID) => "NT_GNU_BUILD_ID (unique build ID bitstring)",
+            (b"GNU", NT_GNU::GOLD_VERSION) => "NT_GNU_GOLD_VERSION (gold version)",
+            (b"GNU", NT_GNU::PROPERTY_TYPE_0) => "NT_GNU_PROPERTY_TYPE_0",
+            (b"CORE", NT_CORE::PRSTATUS) => "NT_PRSTATUS (prstatus structure)",
+            (b"CORE", NT_CORE::FPREGSET) => "NT_FPREGSET (floating point registers)",
+            (b"CORE", NT_CORE::PRPSINFO) => "NT_PRPSINFO (prpsinfo structure)",
+            (b"CORE", NT_CORE::TASKSTRUCT) => "NT_TASKSTRUCT (task structure)",
+            (b"CORE", NT_CORE::AUXV) => "NT_AUXV (auxiliary vector)",
+            (b"CORE", NT_CORE::SIGINFO) => "NT_SIGINFO (siginfo_t data)",
+            (b"CORE", NT_CORE::FILE) => "NT_FILE (mapped files)",
+            (b"LINUX", NT_CORE::X86_XSTATE) => "NT_X86_XSTATE (x86 XSAVE extended state)",
+            (b"LINUX", NT_CORE::PRXFPREG) => "NT_PRXFPREG (user_xfpregs structure)",
+            _ => return Cow::Owned(format!("Unknown note type: ({:#010x})", self.n_type)),
+        };
+        Cow::Borrowed(name)
+    }
+}
+
+impl Property<'_> {
+    /// The first word of the data, the bitmask of the `*_AND` properties
+    pub fn value(&self) -> Option<u32> {
+        read_u32(self.data, 0, self.big_endian)
+    }
+}
+
+impl fmt::Display for Property<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = read_u32(self.data, 0, self.big_endian);
+        let bits = |names: &[(u32, &str)], value: u32| -> String {
+            let words: Vec<&str> = names
+                .iter()
+                .filter(|(bit, _)| value & bit != 0)
+                .map(|(_, name)| *name)
+                .collect();
+            if words.is_empty() {
+                "<None>".to_string()
+            } else {
+                words.join(", ")
+            }
+        };
+        match (self.pr_type, value) {
+            (GNU_PROPERTY::STACK_SIZE, _) => match read_u64(self.data, 0, self.big_endian) {
+                Some(size) => write!(f, "stack size: {:#x}", size),
+                None => write!(f, "stack size: <corrupt>"),
+            },
+            (GNU_PROPERTY::NO_COPY_ON_PROTECTED, _) => write!(f, "no copy on protected"),
+            (GNU_PROPERTY::X86_FEATURE_1_AND, Some(value)) => write!(
+                f,
+                "x86 feature: {}",
+                bits(
+                    &[
+                        (GNU_PROPERTY::X86_FEATURE_1_IBT, "IBT"),
+                        (GNU_PROPERTY::X86_FEATURE_1_SHSTK, "SHSTK"),
+                    ],
+                    value
+                )
+            ),
+            (GNU_PROPERTY::AARCH64_FEATURE_1_AND, Some(value)) => write!(
+                f,
+                "AArch64 feature: {}",
+                bits(
+                    &[
+                        (GNU_PROPERTY::AARCH64_FEATURE_1_BTI, "BTI"),
+                        (GNU_PROPERTY::AARCH64_FEATURE_1_PAC, "PAC"),
+                    ],
+                    value
+                )
+            ),
+            (GNU_PROPERTY::X86_ISA_1_NEEDED, Some(value))
+            | (GNU_PROPERTY::X86_ISA_1_USED, Some(value)) => {
+                let names = [
+                    (1, "x86-64-baseline"),
+                    (2, "x86-64-v2"),
+                    (4, "x86-64-v3"),
+                    (8, "x86-64-v4"),
+                ];
+                let kind = if self.pr_type == GNU_PROPERTY::X86_ISA_1_NEEDED {
+                    "needed"
+                } else {
+                    "used"
+                };
+                write!(f, "x86 ISA {}: {}", kind, bits(&names, value))
+            }
+            _ => write!(f, "<unknown type {:#x}>", self.pr_type),
+        }
+    }
+}
+
+impl fmt::Display for NoteData<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NoteData::BuildId(id) => write!(
+                f,
+                "    Build ID: {}",
+                id.iter().map(|b| format!("{:02x}", b)).collect::<String>()
+            ),
+            NoteData::AbiTag {
+                os,
+                major,
+                minor,
+                subminor,
+            } => {
+                let os = match *os {
+                    ELF_NOTE_OS::LINUX => "Linux",
+                    ELF_NOTE_OS::GNU => "Hurd",
+                    ELF_NOTE_OS::SOLARIS2 => "Solaris",
+                    ELF_NOTE_OS::FREEBSD => "FreeBSD",
+                    _ => "Unknown",
+                };
+                write!(f, "    OS: {}, ABI: {}.{}.{}", os, major, minor, subminor)
+            }
+            NoteData::GoldVersion(version) => write!(f, "    Version: {}", version),
+            NoteData::Properties(properties) => {
+                let properties: Vec<String> = properties.iter().map(|p| p.to_string()).collect();
+                write!(f, "      Properties: {}", properties.join(", "))
+            }
+            NoteData::Core(..) | NoteData::Unknown => Ok(()),
+        }
+    }
+}
+
+/// Iterator over the notes of a note section or segment
+#[derive(Debug, Clone)]
+pub struct Notes<'a> {
+    data: &'a [u8],
+    offset: usize,
+    align: usize,
+    big_endian: bool,
+}
+
+impl<'a> Notes<'a> {
+    /// Iterate over the notes stored in `data`
+    ///
+    /// `align` is the alignment of the section or segment, notes are
+    /// padded to 4 bytes unless it is 8.
+    pub fn new(data: &'a [u8], align: u64, big_endian: bool) -> Self {
+        Notes {
+            data,
+            offset: 0,
+            align: if align == 8 { 8 } else { 4 },
+            big_endian,
+        }
+    }
+}
+
+impl<'a> Iterator for Notes<'a> {
+    type Item = Note<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let namesz = read_u32(self.data, self.offset, self.big_endian)? as usize;
+        let descsz = read_u32(self.data, self.offset + 4, self.big_endian)? as usize;
+        let n_type = read_u32(self.data, self.offset + 8, self.big_endian)?;
+        let name_start = self.offset + 12;
+        let name = self.data.get(name_start..name_start.checked_add(namesz)?)?;
+        let desc_start = align_up(name_start + namesz, self.align)?;
+        let desc = self.data.get(desc_start..desc_start.checked_add(descsz)?)?;
+        self.offset = align_up(desc_start + descsz, self.align)?;
+        let name = match name.iter().position(|b| *b == 0) {
+            Some(end) => &name[..end],
+            None => name,
+        };
+        Some(Note {
+            name,
+            n_type,
+            desc,
+            big_endian: self.big_endian,
+        })
+    }
+}
+
+/// Note areas of the file with the name of their section if any
+///
+/// SHT_NOTE sections are used when present, otherwise PT_NOTE segments.
+pub fn areas<'a>(data: &'a [u8], header: &x64) -> Vec<(Option<ByteStr<'a>>, u64, Notes<'a>)> {
+    let big_endian = Data::is_big_endian(&header.e_ident);
+    let names = section::names(data, header).unwrap_or_default();
+    let mut areas: Vec<(Option<ByteStr<'a>>, u64, Notes<'a>)> = section::from_bytes(data, header)
+        .into_iter()
+        .flatten()
+        .filter(|shdr| shdr.sh_type == SHT::NOTE)
+        .filter_map(|shdr| {
+            let bytes = shdr.data(data)?;
+            let notes = Notes::new(bytes, shdr.sh_addralign, big_endian);
+            Some((shdr.name(&names).ok(), shdr.sh_offset, notes))
+        })
+        .collect();
+    if areas.is_empty() {
+        areas = program::from_bytes(data, header)
+            .into_iter()
+            .flatten()
+            .filter(|phdr| phdr.p_type == PT::NOTE)
+            .filter_map(|phdr| {
+                let bytes = phdr.data(data)?;
+                Some((
+                    None,
+                    phdr.p_offset,
+                    Notes::new(bytes, phdr.p_align, big_endian),
+                ))
+            })
+            .collect();
+    }
+    areas
+}
+
+/// Returns the content of the NT_GNU_BUILD_ID note if the file has one
+pub fn build_id<'a>(data: &'a [u8], header: &x64) -> Option<&'a [u8]> {
+    areas(data, header)
+        .into_iter()
+        .flat_map(|(_, _, notes)| notes)
+        .find_map(|note| match note.decode() {
+            NoteData::BuildId(id) => Some(id),
+            _ => None,
+        })
+}
+
+/// Notes ready to be printed like `readelf -n`
+pub struct NoteTable<'a> {
+    /// the whole file
+    pub data: &'a [u8],
+    /// the file header
+    pub header: &'a x64,
+}
+
+impl fmt::Display for NoteTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, offset, notes) in areas(self.data, self.header) {
+            writeln!(f)?;
+            match name {
+                Some(name) => writeln!(f, "Displaying notes found in: {}", name)?,
+                None => writeln!(
+                    f,
+                    "Displaying notes found at file offset {:#010x} with length {:#010x}:",
+                    offset,
+                    notes.data.len()
+                )?,
+            }
+            let mut table = TextTable::new(false)
+                .indent(2)
+                .column("Owner", Align::Left, None)
+                .column("Data size", Align::Left, None)
+                .column("Description", Align::Left, None)
+                .column("", Align::Left, None);
+            for note in notes {
+                table.row([
+                    note.owner().to_string(),
+                    format!("{:#010x}", note.desc.len()),
+                    note.type_name().into_owned(),
+                    note.decode().to_string().trim_start().to_string(),
+                ]);
+            }
+            write!(f, "{}", table)?;
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for Note<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("owner", Json::from(self.owner())),
+            ("n_type", Json::from(self.n_type)),
+            ("type", Json::from(self.type_name())),
+            ("size", Json::from(self.desc.len())),
+            ("description", Json::from(self.decode().to_string())),
+        ])
+    }
+}
+
+impl ToJson for NoteTable<'_> {
+    fn to_json(&self) -> Json {
+        Json::array(
+            areas(self.data, self.header)
+                .into_iter()
+                .map(|(name, offset, notes)| {
+                    Json::object([
+                        ("section", Json::from(name)),
+                        ("offset", Json::from(offset)),
+                        ("size", Json::from(notes.data.len())),
+                        ("notes", Json::array(notes.map(|note| note.to_json()))),
+                    ])
+                }),
+        )
+    }
+}
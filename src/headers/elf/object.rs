@@ -0,0 +1,730 @@
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::digest::Algorithm;
+use crate::utils::yaml::Yaml;
+use crate::{Error, Result};
+use alloc::borrow::Cow;
+use core::cell::OnceCell;
+use core::fmt;
+
+use super::auxv::{self, Auxv};
+use super::checksec::{self, Checksec};
+use super::class::Class;
+use super::compress;
+use super::coredump::{self, Core};
+use super::data::Data;
+use super::debug::{self, DebugLine, LineProgram, Location, StringSections};
+use super::debugdata;
+use super::debuglink::{self, DebugLink};
+use super::description;
+use super::diff::{self, Diff};
+use super::digests::{self, Digests};
+#[cfg(feature = "disasm")]
+use super::disasm::{self, Disassembly};
+use super::dynamic::{self, Dynamic, DT};
+use super::editor::ElfEditor;
+use super::ehframe::{self, EhFrame, EhFrameHdr};
+use super::find::{self, Matches, Pattern};
+use super::gaps::{self, Gaps};
+use super::hash::{self, HashSection};
+use super::identification::Indent;
+use super::image::{self, LoadImage};
+use super::init::{self, InitFini};
+use super::linkage::{self, Exports, Imports};
+use super::lint::{self, Lint};
+use super::map::{self, AddressMap};
+use super::module::{self, KernelModule};
+use super::notes::{self, Notes};
+use super::plt::{self, Plt};
+use super::program::{self, Elf64_Phdr, ProgramHeaders, PT};
+use super::provenance::{self, Provenance};
+use super::query::{AllSymbols, SourceTable};
+use super::recover::{self, Recovered};
+use super::relocations::{self, Relocation};
+use super::rpath::{self, SearchPaths};
+use super::section::{self, Elf64_Shdr, SectionFlags, SectionHeaders, SectionType, SHT};
+use super::size::{self, Sizes};
+use super::strings::{self, Encoding, Strings};
+use super::strip;
+use super::strtab::StrTab;
+use super::symbols::{self, Elf64_Sym, Symbols};
+use super::symver::{self, SymbolVersions};
+use super::tls::{self, Tls};
+use super::triage::{self, Triage};
+use super::x64::{self as header, x64};
+use super::xref::{self, References};
+
+/// An ELF64 file borrowed from its bytes
+///
+/// Only the file header is parsed when the object is created, every other
+/// table is parsed on demand by its accessor. The section headers, the
+/// section names, the symbol tables and the dynamic section are kept
+/// once parsed, the lookups by name or address reuse them:
+///
+/// ```no_run
+/// use readbin::headers::elf::Elf;
+///
+/// let data = std::fs::read("/bin/ls").unwrap();
+/// let elf = Elf::parse(&data).unwrap();
+/// for phdr in elf.program_headers().unwrap() {
+///     println!("{}", phdr);
+/// }
+/// let text = elf.section_bytes(".text");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Elf<'data> {
+    data: &'data [u8],
+    header: Cow<'data, x64>,
+    cache: Cache<'data>,
+}
+
+/// The tables parsed by the accessors, at their first call
+#[derive(Debug, Clone, Default)]
+struct Cache<'data> {
+    sections: OnceCell<Result<Vec<Cow<'data, Elf64_Shdr>>>>,
+    section_names: OnceCell<Result<StrTab<'data>>>,
+    symbols: OnceCell<Result<Option<SymbolSection<'data>>>>,
+    dynamic_symbols: OnceCell<Result<Option<SymbolSection<'data>>>>,
+    dynamic: OnceCell<Result<Option<Dynamic<'data>>>>,
+}
+
+/// A symbol table section together with its linked string table
+#[derive(Debug, Clone)]
+pub struct SymbolSection<'data> {
+    /// the SHT_SYMTAB or SHT_DYNSYM section header
+    pub section: Cow<'data, Elf64_Shdr>,
+    /// the symbols of the section
+    pub symbols: Symbols<'data>,
+    /// the string table holding the symbol names
+    pub strtab: StrTab<'data>,
+}
+
+/// A problem of a table that doesn't prevent reading the rest of the file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// the table or section the problem was found in
+    pub context: String,
+    /// what is wrong with it
+    pub error: Error,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.context, self.error)
+    }
+}
+
+impl<'data> SymbolSection<'data> {
+    /// Resolve the name of a symbol of this table
+    pub fn name(&self, symbol: &Elf64_Sym) -> Result<ByteStr<'data>> {
+        symbol.name(&self.strtab)
+    }
+}
+
+impl<'data> Elf<'data> {
+    /// Parse the file header of an ELF64 file
+    ///
+    /// Fails if the data isn't an ELF64 file.
+    pub fn parse(data: &'data [u8]) -> Result<Self> {
+        let header = header::from_bytes(data)?;
+        let class = header.e_ident[Indent::CLASS];
+        if Class::from(class) != Class::Elf64 {
+            return Err(Error::UnsupportedClass(class));
+        }
+        Ok(Elf {
+            data,
+            header,
+            cache: Cache::default(),
+        })
+    }
+
+    /// Parse the file header and check every table the accessors read
+    ///
+    /// Only fails if the data isn't an ELF64 file. The problems of the
+    /// other tables are returned as warnings, the accessors of those
+    /// tables fail the same way on the returned object.
+    pub fn parse_lenient(data: &'data [u8]) -> Result<(Self, Vec<Warning>)> {
+        let elf = Self::parse(data)?;
+        let mut warnings = Vec::new();
+        let mut check = |context: &str, result: Result<()>| {
+            if let Err(error) = result {
+                warnings.push(Warning {
+                    context: context.to_string(),
+                    error,
+                });
+            }
+        };
+        check("file header", elf.header.validate(data));
+        check("program headers", elf.program_headers().map(drop));
+        if let Ok(phdrs) = elf.program_headers() {
+            for (index, phdr) in phdrs.enumerate() {
+                if phdr.p_filesz != 0 && phdr.data(data).is_none() {
+                    let error = Error::OutOfBounds {
+                        offset: phdr.p_offset,
+                        size: phdr.p_filesz,
+                    };
+                    check(&format!("segment {}", index), Err(error));
+                }
+            }
+        }
+        check("section headers", elf.sections().map(drop));
+        if elf.header.shnum(data) != 0 {
+            check("section names", elf.section_names().map(drop));
+        }
+        let names = elf.section_names().unwrap_or_default();
+        if let Ok(shdrs) = elf.sections() {
+            for (index, shdr) in shdrs.enumerate() {
+                let context = match shdr.name(&names) {
+                    Ok(name) if !name.is_empty() => format!("section {}", name),
+                    _ => format!("section [{}]", index),
+                };
+                if shdr.sh_type != SHT::NOBITS && shdr.sh_size != 0 && shdr.data(data).is_none() {
+                    let error = Error::OutOfBounds {
+                        offset: shdr.sh_offset,
+                        size: shdr.sh_size,
+                    };
+                    check(&context, Err(error));
+                }
+                if shdr.sh_type == SHT::REL || shdr.sh_type == SHT::RELA {
+                    check(
+                        &context,
+                        relocations::from_bytes(data, &elf.header, &shdr).map(drop),
+                    );
+                }
+            }
+        }
+        check("symbol table", elf.symbols().map(drop));
+        check("dynamic symbol table", elf.dynamic_symbols().map(drop));
+        check("dynamic section", elf.dynamic().map(drop));
+        check("symbol versions", elf.symbol_versions().map(drop));
+        check("hash tables", elf.hash_tables().map(drop));
+        check("call frame information", elf.eh_frame().map(drop));
+        check("call frame index", elf.eh_frame_hdr().map(drop));
+        Ok((elf, warnings))
+    }
+
+    /// Parse the file header of a memory mapped ELF64 file
+    ///
+    /// The mapping is page aligned so every table is borrowed from it.
+    #[cfg(all(unix, feature = "mmap"))]
+    pub fn from_mmap(map: &'data crate::utils::mmap::Mmap) -> Result<Self> {
+        Self::parse(map)
+    }
+
+    /// The bytes of the whole file
+    pub fn data(&self) -> &'data [u8] {
+        self.data
+    }
+
+    /// The file header
+    pub fn header(&self) -> &x64 {
+        &self.header
+    }
+
+    /// The program header table
+    pub fn program_headers(&self) -> Result<ProgramHeaders<'data>> {
+        program::from_bytes(self.data, &self.header)
+    }
+
+    /// The section header table
+    ///
+    /// The headers are read from the file as the iterator goes, see
+    /// [`Elf::section_headers`] for the kept ones.
+    pub fn sections(&self) -> Result<SectionHeaders<'data>> {
+        section::from_bytes(self.data, &self.header)
+    }
+
+    /// The section headers, parsed at the first call
+    pub fn section_headers(&self) -> Result<&[Cow<'data, Elf64_Shdr>]> {
+        self.cache
+            .sections
+            .get_or_init(|| self.sections().map(Iterator::collect))
+            .as_deref()
+            .map_err(Clone::clone)
+    }
+
+    /// The section name string table
+    pub fn section_names(&self) -> Result<StrTab<'data>> {
+        self.cache
+            .section_names
+            .get_or_init(|| section::names(self.data, &self.header))
+            .clone()
+    }
+
+    /// Resolve the name of a section of this file
+    pub fn section_name(&self, shdr: &Elf64_Shdr) -> Result<ByteStr<'data>> {
+        shdr.name(&self.section_names()?)
+    }
+
+    /// Returns the first section with the given name, text or bytes
+    pub fn section_by_name(&self, name: impl AsRef<[u8]>) -> Option<Cow<'data, Elf64_Shdr>> {
+        let name = name.as_ref();
+        let names = self.section_names().ok()?;
+        self.section_headers()
+            .ok()?
+            .iter()
+            .find(|shdr| shdr.name(&names).is_ok_and(|found| found == name))
+            .cloned()
+    }
+
+    /// Returns the bytes of the first section with the given name
+    ///
+    /// Returns None if there is no such section, if it is SHT_NOBITS or if
+    /// it lies outside of the file.
+    pub fn section_bytes(&self, name: &str) -> Option<&'data [u8]> {
+        self.section_by_name(name)?.data(self.data)
+    }
+
+    /// The content of a section, decompressed if it has the SHF_COMPRESSED
+    /// flag or is a `.zdebug_*` section
+    ///
+    /// The decoded bytes are borrowed from the file when the section isn't
+    /// compressed, empty for SHT_NOBITS sections. Fails if the section
+    /// lies outside of the file or can't be decompressed, see
+    /// [`compress::decompress`].
+    pub fn section_contents(&self, shdr: &Elf64_Shdr) -> Result<Cow<'data, [u8]>> {
+        if shdr.section_type() == SectionType::Nobits {
+            return Ok(Cow::Borrowed(&[]));
+        }
+        let bytes = shdr.data(self.data).ok_or(Error::OutOfBounds {
+            offset: shdr.sh_offset,
+            size: shdr.sh_size,
+        })?;
+        let name = self.section_name(shdr).unwrap_or_default();
+        let name = name.to_str().unwrap_or_default();
+        let big_endian = Data::is_big_endian(&self.header.e_ident);
+        compress::decompress(name, shdr, bytes, big_endian)
+    }
+
+    /// The decompressed content of a debug section, `.debug_info` being
+    /// found as `.zdebug_info` too
+    ///
+    /// Returns None if the file has no such section.
+    pub fn debug_section(&self, name: &str) -> Result<Option<Cow<'data, [u8]>>> {
+        let shdr = self.section_by_name(name).or_else(|| {
+            let rest = name.strip_prefix(".debug")?;
+            self.section_by_name(format!(".zdebug{}", rest))
+        });
+        shdr.map(|shdr| self.section_contents(&shdr)).transpose()
+    }
+
+    /// The allocated section whose addresses contain `vaddr`, with its index
+    ///
+    /// `.tbss` is skipped, it takes no room in the address space of the
+    /// program and overlaps the sections following it.
+    pub fn section_containing(
+        &self,
+        vaddr: u64,
+    ) -> Result<Option<(usize, Cow<'data, Elf64_Shdr>)>> {
+        let found = self
+            .section_headers()?
+            .iter()
+            .enumerate()
+            .find(|(_, shdr)| {
+                shdr.flags().contains(SectionFlags::ALLOC)
+                    && !(shdr.section_type() == SectionType::Nobits
+                        && shdr.flags().contains(SectionFlags::TLS))
+                    && vaddr >= shdr.sh_addr
+                    && vaddr - shdr.sh_addr < shdr.sh_size
+            });
+        Ok(found.map(|(index, shdr)| (index, shdr.clone())))
+    }
+
+    /// The PT_LOAD segment whose memory image contains `vaddr`, with its index
+    pub fn segment_containing(
+        &self,
+        vaddr: u64,
+    ) -> Result<Option<(usize, Cow<'data, Elf64_Phdr>)>> {
+        Ok(self.program_headers()?.enumerate().find(|(_, phdr)| {
+            phdr.p_type == PT::LOAD && vaddr >= phdr.p_vaddr && vaddr - phdr.p_vaddr < phdr.p_memsz
+        }))
+    }
+
+    /// The file offset of the byte loaded at `vaddr`
+    ///
+    /// Returns None if the address isn't backed by the file, like the
+    /// `.bss` part of a segment.
+    pub fn file_offset_for_vaddr(&self, vaddr: u64) -> Option<u64> {
+        program::vaddr_to_offset(self.data, &self.header, vaddr)
+    }
+
+    /// The static symbol table, `.symtab`
+    pub fn symbols(&self) -> Result<Option<SymbolSection<'data>>> {
+        self.cache
+            .symbols
+            .get_or_init(|| self.symbol_section(SHT::SYMTAB))
+            .clone()
+    }
+
+    /// The dynamic symbol table, `.dynsym`
+    ///
+    /// Found through PT_DYNAMIC like the loader does when the file has no
+    /// `.dynsym` section, see [`symbols::from_dynamic`].
+    pub fn dynamic_symbols(&self) -> Result<Option<SymbolSection<'data>>> {
+        self.cache
+            .dynamic_symbols
+            .get_or_init(|| self.symbol_section(SHT::DYNSYM))
+            .clone()
+    }
+
+    /// Every symbol of `.symtab` then of `.dynsym`, with their names
+    ///
+    /// The dynamic symbols are usually also in `.symtab`, check
+    /// [`Symbol::dynamic`](super::query::Symbol::dynamic) to keep one
+    /// table. The filters of [`SymbolFilter`](super::query::SymbolFilter)
+    /// apply to the returned iterator. Fails if a table can't be parsed.
+    pub fn all_symbols(&self) -> Result<AllSymbols<'data>> {
+        let mut tables = Vec::new();
+        for (section, dynamic) in [(self.symbols()?, false), (self.dynamic_symbols()?, true)] {
+            if let Some(section) = section {
+                tables.push(SourceTable {
+                    dynamic,
+                    symbols: section.symbols,
+                    strtab: section.strtab,
+                });
+            }
+        }
+        let names = self.section_names().unwrap_or_else(|_| StrTab::new(&[]));
+        Ok(AllSymbols::new(tables, self.section_headers()?, names))
+    }
+
+    fn symbol_section(&self, sh_type: u32) -> Result<Option<SymbolSection<'data>>> {
+        let shdr = self
+            .section_headers()?
+            .iter()
+            .find(|shdr| shdr.sh_type == sh_type);
+        let section = match shdr {
+            Some(section) => section.clone(),
+            None if sh_type == SHT::DYNSYM => {
+                return match symbols::from_dynamic(self.data, &self.header) {
+                    Some((section, strtab)) => Ok(Some(SymbolSection {
+                        symbols: symbols::from_bytes(self.data, &self.header, &section)?,
+                        section: Cow::Owned(section),
+                        strtab,
+                    })),
+                    None => Ok(None),
+                }
+            }
+            None => return Ok(None),
+        };
+        let symbols = symbols::from_bytes(self.data, &self.header, &section)?;
+        let strtab = symbols::strtab(self.data, &self.header, &section)?;
+        Ok(Some(SymbolSection {
+            section,
+            symbols,
+            strtab,
+        }))
+    }
+
+    /// The memory image of the PT_LOAD segments, `.bss` included
+    pub fn load_image(&self) -> Result<LoadImage> {
+        image::from_bytes(self.data, &self.header)
+    }
+
+    /// Move a loaded image to `base` and apply its dynamic relocations
+    ///
+    /// See [`image::apply_relocations`], returns the relocations that
+    /// weren't applied.
+    pub fn apply_relocations<F>(
+        &self,
+        image: &mut LoadImage,
+        base: u64,
+        resolve: F,
+    ) -> Result<Vec<Relocation>>
+    where
+        F: FnMut(&str) -> Option<u64>,
+    {
+        image::apply_relocations(image, self.data, &self.header, base, resolve)
+    }
+
+    /// The x86_64 code of the executable sections, or of the section named
+    /// `section`, with the symbols naming its addresses
+    #[cfg(feature = "disasm")]
+    pub fn disassemble(&self, section: Option<&str>) -> Result<Disassembly<'data>> {
+        disasm::from_bytes(self.data, &self.header, section)
+    }
+
+    /// The `.hash` and `.gnu.hash` sections
+    pub fn hash_tables(&self) -> Result<Vec<HashSection<'data>>> {
+        hash::from_bytes(self.data, &self.header)
+    }
+
+    /// Find a dynamic symbol by name with the hash table of the file
+    ///
+    /// Uses `.gnu.hash` when present like the dynamic linker, `.hash`
+    /// otherwise. Returns None if the symbol isn't found or the file has
+    /// no hash table.
+    pub fn lookup(&self, name: &str) -> Result<Option<(usize, Cow<'data, Elf64_Sym>)>> {
+        let tables = self.hash_tables()?;
+        let table = tables
+            .iter()
+            .find(|table| matches!(table, HashSection::Gnu(..)))
+            .or_else(|| tables.first());
+        match table {
+            Some(table) => table.lookup(self.data, &self.header, name),
+            None => Ok(None),
+        }
+    }
+
+    /// The GNU versions of the dynamic symbols, None without `.gnu.version`
+    pub fn symbol_versions(&self) -> Result<Option<SymbolVersions<'data>>> {
+        symver::from_bytes(self.data, &self.header)
+    }
+
+    /// The defined global and weak dynamic symbols, with their version
+    pub fn exports(&self) -> Result<Exports<'data>> {
+        linkage::exports(self.data, &self.header)
+    }
+
+    /// The undefined dynamic symbols, with the library of their version
+    ///
+    /// [`Imports::resolve`] finds the others in the needed libraries.
+    pub fn imports(&self) -> Result<Imports<'data>> {
+        linkage::imports(self.data, &self.header)
+    }
+
+    /// The dynamic section, None for statically linked files
+    pub fn dynamic(&self) -> Result<Option<&Dynamic<'data>>> {
+        self.cache
+            .dynamic
+            .get_or_init(|| dynamic::from_bytes(self.data, &self.header))
+            .as_ref()
+            .map(Option::as_ref)
+            .map_err(Clone::clone)
+    }
+
+    /// Path of the program interpreter, from the PT_INTERP segment
+    pub fn interpreter(&self) -> Option<ByteStr<'data>> {
+        self.program_headers()
+            .ok()?
+            .find_map(|phdr| phdr.interpreter(self.data))
+    }
+
+    /// Libraries the file depends on, from the DT_NEEDED entries
+    ///
+    /// Empty for statically linked files, the names are kept whatever
+    /// their encoding.
+    pub fn needed_libraries(&self) -> Result<Vec<ByteStr<'data>>> {
+        let dynamic = match self.dynamic()? {
+            Some(dynamic) => dynamic,
+            None => return Ok(Vec::new()),
+        };
+        Ok(dynamic
+            .entries
+            .iter()
+            .filter(|entry| entry.d_tag == DT::NEEDED)
+            .filter_map(|entry| dynamic.name(entry.d_val).ok())
+            .collect())
+    }
+
+    /// The DT_RPATH and DT_RUNPATH directories, `$ORIGIN` replaced by
+    /// `origin` when given, and their issues found without the file system
+    pub fn search_paths(&self, origin: Option<&str>) -> Result<SearchPaths<'data>> {
+        rpath::from_bytes(self.data, &self.header, origin)
+    }
+
+    /// The relocation sections with their relocations
+    pub fn relocations(&self) -> Result<Vec<(Cow<'data, Elf64_Shdr>, Vec<Relocation>)>> {
+        self.section_headers()?
+            .iter()
+            .filter(|shdr| shdr.sh_type == SHT::REL || shdr.sh_type == SHT::RELA)
+            .map(|shdr| {
+                let relocations = relocations::from_bytes(self.data, &self.header, shdr)?;
+                Ok((shdr.clone(), relocations))
+            })
+            .collect()
+    }
+
+    /// The bytes of the file outside of the headers, segments and sections
+    pub fn gaps(&self) -> Result<Gaps> {
+        gaps::from_bytes(self.data, &self.header)
+    }
+
+    /// The segments, sections and symbols of the file in address order
+    pub fn address_map(&self) -> Result<AddressMap<'data>> {
+        map::from_bytes(self.data, &self.header)
+    }
+
+    /// The sizes of the sections and of the functions, largest first
+    pub fn sizes(&self) -> Result<Sizes<'data>> {
+        size::from_bytes(self.data, &self.header)
+    }
+
+    /// A copy of the file with its damaged or missing section or program
+    /// header table rebuilt from the other one, None if both are valid
+    pub fn recover(&self) -> Option<Recovered> {
+        recover::from_bytes(self.data, &self.header)
+    }
+
+    /// A YAML description of every field and byte of the file, built back
+    /// by [`description::build`]
+    pub fn describe(&self) -> Result<Yaml> {
+        description::describe(self.data, &self.header)
+    }
+
+    /// The `.modinfo`, `__versions` and module structure of a kernel module
+    pub fn kernel_module(&self) -> Result<Option<KernelModule<'data>>> {
+        module::from_bytes(self.data, &self.header)
+    }
+
+    /// The compilers, linker, build id and build properties of the file
+    pub fn provenance(&self) -> Provenance<'data> {
+        provenance::from_bytes(self.data, &self.header)
+    }
+
+    /// The hardening features of the file: RELRO, canary, NX, PIE...
+    pub fn checksec(&self) -> Result<Checksec> {
+        checksec::from_bytes(self.data, &self.header)
+    }
+
+    /// The entropy of the sections and segments and the signs of a packer
+    pub fn triage(&self) -> Result<Triage<'data>> {
+        triage::from_bytes(self.data, &self.header)
+    }
+
+    /// The digests of the file, of its allocated sections and of its code
+    pub fn digests(&self, algorithm: Algorithm) -> Result<Digests<'data>> {
+        digests::from_bytes(self.data, &self.header, algorithm)
+    }
+
+    /// The W^X and segment permission problems of the file, with their severity
+    pub fn lint(&self) -> Result<Lint<'data>> {
+        lint::from_bytes(self.data, &self.header)
+    }
+
+    /// The functions run at load and exit time: DT_INIT, DT_FINI and the arrays
+    pub fn init_fini(&self) -> Result<InitFini<'data>> {
+        init::from_bytes(self.data, &self.header)
+    }
+
+    /// The runs of at least `min_len` printable characters, with their section
+    pub fn strings(&self, min_len: usize, encoding: Encoding) -> Result<Strings<'data>> {
+        strings::from_bytes(self.data, &self.header, min_len, encoding)
+    }
+
+    /// The matches of a byte pattern or of a string anywhere in the file
+    pub fn find(&self, pattern: &Pattern) -> Result<Matches<'data>> {
+        find::from_bytes(self.data, &self.header, pattern)
+    }
+
+    /// The PLT stubs and GOT slots with the functions they resolve to
+    pub fn plt(&self) -> Result<Plt<'data>> {
+        plt::from_bytes(self.data, &self.header)
+    }
+
+    /// The relocations and PLT stubs referencing a symbol, see [`xref::from_bytes`]
+    pub fn references(&self, name: &str) -> Result<References<'data>> {
+        xref::from_bytes(self.data, &self.header, name)
+    }
+
+    /// The PT_TLS template, the TLS sections, symbols and relocations
+    pub fn tls(&self) -> Result<Tls<'data>> {
+        tls::from_bytes(self.data, &self.header)
+    }
+
+    /// The notes of the file, grouped by section or segment
+    pub fn notes(&self) -> Vec<Notes<'data>> {
+        notes::areas(self.data, &self.header)
+            .into_iter()
+            .map(|(_, _, notes)| notes)
+            .collect()
+    }
+
+    /// The auxiliary vector saved in the NT_AUXV note of a core file
+    pub fn auxv(&self) -> Result<Option<Auxv<'data>>> {
+        auxv::from_bytes(self.data, &self.header)
+    }
+
+    /// The process state stored in an ET_CORE file
+    pub fn core(&self) -> Result<Core<'data>> {
+        coredump::from_bytes(self.data, &self.header)
+    }
+
+    /// A copy of the file without `.symtab`, `.strtab` and the debug sections
+    ///
+    /// See [`strip::strip`] for the details of the new layout.
+    pub fn strip(&self) -> Result<Vec<u8>> {
+        strip::strip(self.data, &self.header)
+    }
+
+    /// An editor of a copy of the file: symbol names, sections and
+    /// dependencies
+    pub fn editor(&self) -> ElfEditor<'data> {
+        ElfEditor::new(self.data, &self.header)
+    }
+
+    /// The line number programs of `.debug_line`, empty without debug info
+    ///
+    /// The programs borrow the file, they are only found in uncompressed
+    /// sections: use [`Elf::debug_line`] for the compressed ones.
+    pub fn line_programs(&self) -> Result<Vec<LineProgram<'data>>> {
+        let debug_line = match self.section_bytes(".debug_line") {
+            Some(debug_line) => debug_line,
+            None => return Ok(Vec::new()),
+        };
+        let strings = StringSections {
+            debug_str: self.section_bytes(".debug_str").unwrap_or_default(),
+            debug_line_str: self.section_bytes(".debug_line_str").unwrap_or_default(),
+        };
+        let big_endian = Data::is_big_endian(&self.header.e_ident);
+        debug::from_bytes(debug_line, strings, big_endian)
+    }
+
+    /// The sections of the line number programs, decompressed if needed,
+    /// None without `.debug_line`
+    pub fn debug_line(&self) -> Result<Option<DebugLine<'data>>> {
+        let debug_line = match self.debug_section(".debug_line")? {
+            Some(debug_line) => debug_line,
+            None => return Ok(None),
+        };
+        Ok(Some(DebugLine {
+            debug_line,
+            debug_str: self.debug_section(".debug_str")?.unwrap_or_default(),
+            debug_line_str: self.debug_section(".debug_line_str")?.unwrap_or_default(),
+            big_endian: Data::is_big_endian(&self.header.e_ident),
+        }))
+    }
+
+    /// Source file and line of the instruction at `address`
+    ///
+    /// Returns None if no line number program covers the address.
+    pub fn addr_to_line(&self, address: u64) -> Result<Option<Location>> {
+        match self.debug_line()? {
+            Some(debug_line) => Ok(debug::find(&debug_line.programs()?, address)),
+            None => Ok(None),
+        }
+    }
+
+    /// The call frame information of `.eh_frame`, None without the section
+    pub fn eh_frame(&self) -> Result<Option<EhFrame<'data>>> {
+        ehframe::from_bytes(self.data, &self.header)
+    }
+
+    /// The FDE lookup table of `.eh_frame_hdr`, None without the section
+    pub fn eh_frame_hdr(&self) -> Result<Option<EhFrameHdr>> {
+        ehframe::hdr_from_bytes(self.data, &self.header)
+    }
+
+    /// The differences between this file and `other`, see [`diff::diff`]
+    pub fn diff(&self, other: &Elf) -> Result<Diff> {
+        diff::diff(self, other)
+    }
+
+    /// The name and CRC of the separate debug file, from `.gnu_debuglink`
+    pub fn debug_link(&self) -> Result<Option<DebugLink<'data>>> {
+        debuglink::from_bytes(self.data, &self.header)
+    }
+
+    /// The ELF file of the MiniDebugInfo, decoded from `.gnu_debugdata`
+    ///
+    /// Its `.symtab` holds the local functions of a stripped file, see
+    /// [`debugdata::from_bytes`].
+    pub fn mini_debug_info(&self) -> Result<Option<Vec<u8>>> {
+        debugdata::from_bytes(self.data, &self.header)
+    }
+
+    /// The content of the NT_GNU_BUILD_ID note
+    pub fn build_id(&self) -> Option<&'data [u8]> {
+        notes::build_id(self.data, &self.header)
+    }
+}
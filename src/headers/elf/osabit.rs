@@ -1,36 +1,106 @@
-pub struct OSABIT {}
+use core::fmt;
 
 /// Define the target operating system application binary interface
 ///
-/// OSABIT define the possible values for the target operation system
-/// Its store in the seventh byte of the identifaction 16 bits called `e_ident`
-impl OSABIT {
+/// OsAbi define the possible values of the eighth byte of the
+/// identification bytes `e_ident`. Decoding never fails, the values not
+/// known by this crate are kept in `Unknown`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OsAbi {
     /// UNIX System V ABI
-    pub const SYSV: u8 = 0;
+    SysV,
     /// HP-UX
-    pub const HPUX: u8 = 1;
+    HpUx,
     /// NetBSD.
-    pub const NETBSD: u8 = 2;
+    NetBsd,
     /// Object use GNU ELF extensions
-    pub const GNU: u8 = 3;
+    Gnu,
     /// Sun Solaris
-    pub const SOLARIS: u8 = 6;
+    Solaris,
     /// IBM AIX
-    pub const AIX: u8 = 7;
+    Aix,
     /// SGI Irix
-    pub const IRIX: u8 = 8;
+    Irix,
     /// FreeBSD
-    pub const FREEBSD: u8 = 9;
+    FreeBsd,
     /// Compaq tru64 unix
-    pub const TRU64: u8 = 10;
+    Tru64,
     /// Novell Modesto
-    pub const MODESTO: u8 = 11;
+    Modesto,
     /// OpenBSD
-    pub const OPENBSD: u8 = 12;
+    OpenBsd,
     /// ARM EABI
-    pub const ARM_AEABI: u8 = 64;
+    ArmAeabi,
     /// ARM
-    pub const ARM: u8 = 97;
+    Arm,
     /// Standalone embedded application
-    pub const STANDALONE: u8 = 255;
+    Standalone,
+    /// Value not known by this crate
+    Unknown(u8),
+}
+
+impl From<u8> for OsAbi {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => OsAbi::SysV,
+            1 => OsAbi::HpUx,
+            2 => OsAbi::NetBsd,
+            3 => OsAbi::Gnu,
+            6 => OsAbi::Solaris,
+            7 => OsAbi::Aix,
+            8 => OsAbi::Irix,
+            9 => OsAbi::FreeBsd,
+            10 => OsAbi::Tru64,
+            11 => OsAbi::Modesto,
+            12 => OsAbi::OpenBsd,
+            64 => OsAbi::ArmAeabi,
+            97 => OsAbi::Arm,
+            255 => OsAbi::Standalone,
+            value => OsAbi::Unknown(value),
+        }
+    }
+}
+
+impl From<OsAbi> for u8 {
+    fn from(osabi: OsAbi) -> Self {
+        match osabi {
+            OsAbi::SysV => 0,
+            OsAbi::HpUx => 1,
+            OsAbi::NetBsd => 2,
+            OsAbi::Gnu => 3,
+            OsAbi::Solaris => 6,
+            OsAbi::Aix => 7,
+            OsAbi::Irix => 8,
+            OsAbi::FreeBsd => 9,
+            OsAbi::Tru64 => 10,
+            OsAbi::Modesto => 11,
+            OsAbi::OpenBsd => 12,
+            OsAbi::ArmAeabi => 64,
+            OsAbi::Arm => 97,
+            OsAbi::Standalone => 255,
+            OsAbi::Unknown(value) => value,
+        }
+    }
+}
+
+impl fmt::Display for OsAbi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OsAbi::SysV => f.write_str("UNIX System V ABI"),
+            OsAbi::HpUx => f.write_str("HP-UX"),
+            OsAbi::NetBsd => f.write_str("NetBSD"),
+            OsAbi::Gnu => f.write_str("Object use GNU ELF extensions"),
+            OsAbi::Solaris => f.write_str("Sun Solaris"),
+            OsAbi::Aix => f.write_str("IBM AIX"),
+            OsAbi::Irix => f.write_str("SGI Irix"),
+            OsAbi::FreeBsd => f.write_str("FreeBSD"),
+            OsAbi::Tru64 => f.write_str("Compaq tru64 unix"),
+            OsAbi::Modesto => f.write_str("Novell Modesto"),
+            OsAbi::OpenBsd => f.write_str("OpenBSD"),
+            OsAbi::ArmAeabi => f.write_str("ARM AEABI"),
+            OsAbi::Arm => f.write_str("ARM"),
+            OsAbi::Standalone => f.write_str("Standalone embedded application"),
+            OsAbi::Unknown(value) => write!(f, "<unknown: {:x}>", value),
+        }
+    }
 }
@@ -0,0 +1,423 @@
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::json::{Json, ToJson};
+use crate::utils::{read_u32, read_u64};
+use crate::Result;
+use core::fmt;
+
+use super::data::Data;
+use super::machine::Machine;
+use super::relocations::{self, Relocation, R_AARCH64, R_X86_64};
+use super::section::{self, Elf64_Shdr, SHT};
+use super::symbols;
+use super::x64::x64;
+
+/// The sections holding PLT stubs
+const PLT_SECTIONS: [&str; 3] = [".plt", ".plt.sec", ".plt.got"];
+/// The sections holding GOT slots
+const GOT_SECTIONS: [&str; 2] = [".got", ".got.plt"];
+
+/// What a PLT stub or a GOT slot ends up calling or pointing to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target<'a> {
+    /// the dynamic symbol of the relocation of the slot
+    Symbol(ByteStr<'a>),
+    /// an IRELATIVE relocation, the address of the ifunc resolver
+    Ifunc(u64),
+    /// a relocation without symbol, relative to the file itself, and its addend
+    Local(i64),
+    /// the lazy binding entry of the dynamic linker, jumped to by PLT0
+    Resolver,
+    /// the slot has no relocation naming a symbol
+    Unknown,
+}
+
+/// A PLT stub jumping through a GOT slot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PltEntry<'a> {
+    /// section of the stub: `.plt`, `.plt.sec` or `.plt.got`
+    pub section: &'a str,
+    /// address of the stub
+    pub address: u64,
+    /// address of the GOT slot the stub jumps through
+    pub slot: u64,
+    /// the relocation filling the slot
+    pub relocation: Option<Relocation>,
+    /// the function called
+    pub target: Target<'a>,
+}
+
+/// A slot of `.got` or `.got.plt`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GotEntry<'a> {
+    /// section of the slot
+    pub section: &'a str,
+    /// address of the slot
+    pub address: u64,
+    /// value stored in the file, before relocation
+    pub value: u64,
+    /// the relocation filling the slot
+    pub relocation: Option<Relocation>,
+    /// what the slot points to once relocated
+    pub target: Target<'a>,
+}
+
+/// The PLT stubs and GOT slots of an ELF64 file
+#[derive(Debug, Clone, Default)]
+pub struct Plt<'a> {
+    /// machine of the file, gives the meaning of the relocation types
+    pub machine: u16,
+    /// the PLT stubs in address order, empty for unsupported machines
+    pub entries: Vec<PltEntry<'a>>,
+    /// the GOT slots in address order
+    pub got: Vec<GotEntry<'a>>,
+}
+
+/// Decode the indirect jumps of the x86_64 PLT stubs
+///
+/// Lazy stubs are `jmp *slot(%rip); push index; jmp PLT0`, PLT0 is
+/// `push GOT+8(%rip); jmp *GOT+16(%rip)`. With IBT the stubs of `.plt`
+/// only push the index and `.plt.sec` holds the jumps, `endbr64` and
+/// `bnd` prefixes may precede them.
+fn x86_64_stubs(address: u64, bytes: &[u8], entsize: u64) -> Vec<(u64, u64)> {
+    let entsize = match entsize {
+        0..=7 => 16,
+        entsize => entsize as usize,
+    };
+    let mut stubs = Vec::new();
+    for (index, entry) in bytes.chunks(entsize).enumerate() {
+        let mut at = 0;
+        let mut skip = |bytes: &[u8], size| {
+            let found = entry.get(at..).is_some_and(|rest| rest.starts_with(bytes));
+            if found {
+                at += size;
+            }
+            found
+        };
+        skip(&[0xf3, 0x0f, 0x1e, 0xfa], 4);
+        skip(&[0xff, 0x35], 6);
+        skip(&[0xf2], 1);
+        if !skip(&[0xff, 0x25], 0) {
+            continue;
+        }
+        let disp = match read_u32(entry, at + 2, false) {
+            Some(disp) => disp as i32,
+            None => continue,
+        };
+        let stub = address.wrapping_add((index * entsize) as u64);
+        let next = stub.wrapping_add(at as u64 + 6);
+        stubs.push((stub, next.wrapping_add(disp as i64 as u64)));
+    }
+    stubs
+}
+
+/// Decode the `adrp x16, slot; ldr x17, [x16, slot]` pairs of the AArch64 stubs
+///
+/// A stub starts at the `adrp`, or at the `bti c` right before it.
+fn aarch64_stubs(address: u64, bytes: &[u8], big_endian: bool) -> Vec<(u64, u64)> {
+    const BTI_C: u32 = 0xd503_245f;
+    let words: Vec<u32> = (0..bytes.len() / 4)
+        .filter_map(|index| read_u32(bytes, index * 4, big_endian))
+        .collect();
+    let mut stubs = Vec::new();
+    let mut index = 0;
+    while index + 1 < words.len() {
+        let (adrp, ldr) = (words[index], words[index + 1]);
+        let register = adrp & 0x1f;
+        if adrp & 0x9f00_0000 != 0x9000_0000
+            || ldr & 0xffc0_0000 != 0xf940_0000
+            || (ldr >> 5) & 0x1f != register
+        {
+            index += 1;
+            continue;
+        }
+        let pc = address.wrapping_add(index as u64 * 4);
+        let immediate = ((adrp >> 29) & 0x3) as u64 | (((adrp >> 5) & 0x7_ffff) as u64) << 2;
+        // sign extend the 21 bits page offset
+        let pages = ((immediate << 43) as i64 >> 43) as u64;
+        let page = (pc & !0xfff).wrapping_add(pages << 12);
+        let slot = page.wrapping_add(((ldr >> 10) & 0xfff) as u64 * 8);
+        let stub = match index {
+            0 => pc,
+            _ if words[index - 1] == BTI_C => pc.wrapping_sub(4),
+            _ => pc,
+        };
+        stubs.push((stub, slot));
+        index += 2;
+    }
+    stubs
+}
+
+/// The relocations filling GOT slots, by slot address, with their symbol names
+fn slot_relocations<'a>(
+    data: &'a [u8],
+    header: &x64,
+    shdrs: &[Elf64_Shdr],
+) -> Vec<(Relocation, Option<ByteStr<'a>>)> {
+    let mut slots = Vec::new();
+    for shdr in shdrs
+        .iter()
+        .filter(|shdr| shdr.sh_type == SHT::REL || shdr.sh_type == SHT::RELA)
+    {
+        let relocations = match relocations::from_bytes(data, header, shdr) {
+            Ok(relocations) => relocations,
+            Err(_) => continue,
+        };
+        let symtab = section::get(data, header, shdr.sh_link).ok();
+        let symbols = symtab
+            .as_ref()
+            .and_then(|symtab| symbols::from_bytes(data, header, symtab).ok());
+        let strtab = symtab
+            .as_ref()
+            .and_then(|symtab| symbols::strtab(data, header, symtab).ok())
+            .unwrap_or_default();
+        for relocation in relocations {
+            let name = match relocation.r_sym {
+                0 => None,
+                index => symbols
+                    .as_ref()
+                    .and_then(|symbols| symbols.get(index as usize))
+                    .and_then(|symbol| symbol.name(&strtab).ok()),
+            };
+            slots.push((relocation, name));
+        }
+    }
+    slots
+}
+
+/// What the relocation of a slot makes it point to
+fn target<'a>(machine: u16, relocation: &Relocation, name: Option<ByteStr<'a>>) -> Target<'a> {
+    let irelative = match Machine::from_u16(machine) {
+        Machine::X86_64 => Some(R_X86_64::IRELATIVE),
+        Machine::Aarch64 => Some(R_AARCH64::IRELATIVE),
+        _ => None,
+    };
+    let addend = relocation.r_addend.unwrap_or_default();
+    match name {
+        Some(name) if !name.is_empty() => Target::Symbol(name),
+        _ if Some(relocation.r_type) == irelative => Target::Ifunc(addend as u64),
+        _ if relocation.r_sym == 0 => Target::Local(addend),
+        _ => Target::Unknown,
+    }
+}
+
+/// Decode the PLT stubs and the GOT slots of the file
+///
+/// Fails if the section header table can't be parsed, the stubs are
+/// only decoded for x86_64 and AArch64.
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64) -> Result<Plt<'a>> {
+    let names = section::names(data, header).unwrap_or_default();
+    let shdrs: Vec<Elf64_Shdr> = section::from_bytes(data, header)?
+        .map(|shdr| *shdr)
+        .collect();
+    let named = |wanted: &[&str]| -> Vec<(&'a str, Elf64_Shdr)> {
+        shdrs
+            .iter()
+            .filter_map(|shdr| match shdr.name(&names).map(|name| name.to_str()) {
+                Ok(Some(name)) if wanted.contains(&name) => Some((name, *shdr)),
+                _ => None,
+            })
+            .collect()
+    };
+    let relocations = slot_relocations(data, header, &shdrs);
+    let relocation = |slot: u64| {
+        relocations
+            .iter()
+            .find(|(relocation, _)| relocation.r_offset == slot)
+    };
+    let resolver = named(&[".got.plt"])
+        .first()
+        .map(|(_, shdr)| shdr.sh_addr.wrapping_add(16));
+    let slot_target = |slot: u64| match relocation(slot) {
+        Some((relocation, name)) => (
+            Some(*relocation),
+            target(header.e_machine, relocation, *name),
+        ),
+        None if Some(slot) == resolver => (None, Target::Resolver),
+        None => (None, Target::Unknown),
+    };
+
+    let big_endian = Data::is_big_endian(&header.e_ident);
+    let mut entries = Vec::new();
+    for (name, shdr) in named(&PLT_SECTIONS) {
+        let bytes = match shdr.data(data) {
+            Some(bytes) => bytes,
+            None => continue,
+        };
+        let stubs = match Machine::from_u16(header.e_machine) {
+            Machine::X86_64 => x86_64_stubs(shdr.sh_addr, bytes, shdr.sh_entsize),
+            Machine::Aarch64 => aarch64_stubs(shdr.sh_addr, bytes, big_endian),
+            _ => Vec::new(),
+        };
+        for (address, slot) in stubs {
+            let (relocation, target) = slot_target(slot);
+            entries.push(PltEntry {
+                section: name,
+                address,
+                slot,
+                relocation,
+                target,
+            });
+        }
+    }
+    entries.sort_by_key(|entry| entry.address);
+
+    let mut got = Vec::new();
+    for (name, shdr) in named(&GOT_SECTIONS) {
+        let bytes = match shdr.data(data) {
+            Some(bytes) => bytes,
+            None => continue,
+        };
+        for index in 0..bytes.len() / 8 {
+            let address = shdr.sh_addr.wrapping_add(index as u64 * 8);
+            let (relocation, mut target) = slot_target(address);
+            // the first slot of .got.plt holds the address of the dynamic section
+            if name == ".got.plt" && index == 0 && relocation.is_none() {
+                target = Target::Symbol(ByteStr::new(b"_DYNAMIC"));
+            }
+            got.push(GotEntry {
+                section: name,
+                address,
+                value: read_u64(bytes, index * 8, big_endian).unwrap_or_default(),
+                relocation,
+                target,
+            });
+        }
+    }
+    got.sort_by_key(|entry| entry.address);
+
+    Ok(Plt {
+        machine: header.e_machine,
+        entries,
+        got,
+    })
+}
+
+impl fmt::Display for Target<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::Symbol(name) => write!(f, "{}", name),
+            Target::Ifunc(resolver) => write!(f, "<ifunc {:#x}>", resolver),
+            Target::Local(addend) if *addend < 0 => {
+                write!(f, "<local -{:#x}>", addend.unsigned_abs())
+            }
+            Target::Local(addend) => write!(f, "<local {:#x}>", addend),
+            Target::Resolver => f.write_str("<lazy resolver>"),
+            Target::Unknown => f.write_str("<unknown>"),
+        }
+    }
+}
+
+impl fmt::Display for Plt<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.entries.is_empty() && self.got.is_empty() {
+            return writeln!(f, "There are no PLT or GOT sections in this file.");
+        }
+        let relocation = |relocation: &Option<Relocation>| match relocation {
+            Some(relocation) => relocations::type_name(self.machine, relocation.r_type),
+            None => "".into(),
+        };
+        if !self.entries.is_empty() {
+            writeln!(f, "PLT entries:")?;
+            writeln!(
+                f,
+                "  Address          Section  GOT slot         Type                 Target"
+            )?;
+            for entry in &self.entries {
+                writeln!(
+                    f,
+                    "  {:016x} {:<8} {:016x} {:<20} {}",
+                    entry.address,
+                    entry.section,
+                    entry.slot,
+                    relocation(&entry.relocation),
+                    entry.target
+                )?;
+            }
+        }
+        if !self.got.is_empty() {
+            if !self.entries.is_empty() {
+                writeln!(f)?;
+            }
+            writeln!(f, "GOT entries:")?;
+            writeln!(
+                f,
+                "  Address          Section  Value            Type                 Target"
+            )?;
+            for entry in &self.got {
+                writeln!(
+                    f,
+                    "  {:016x} {:<8} {:016x} {:<20} {}",
+                    entry.address,
+                    entry.section,
+                    entry.value,
+                    relocation(&entry.relocation),
+                    entry.target
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for Target<'_> {
+    fn to_json(&self) -> Json {
+        match self {
+            Target::Symbol(name) => {
+                Json::object([("kind", Json::from("symbol")), ("name", Json::from(*name))])
+            }
+            Target::Ifunc(resolver) => Json::object([
+                ("kind", Json::from("ifunc")),
+                ("resolver", Json::from(*resolver)),
+            ]),
+            Target::Local(addend) => Json::object([
+                ("kind", Json::from("local")),
+                ("addend", Json::from(*addend)),
+            ]),
+            Target::Resolver => Json::object([("kind", Json::from("resolver"))]),
+            Target::Unknown => Json::object([("kind", Json::from("unknown"))]),
+        }
+    }
+}
+
+impl ToJson for Plt<'_> {
+    fn to_json(&self) -> Json {
+        let relocation = |relocation: &Option<Relocation>| {
+            Json::from(relocation.as_ref().map(|relocation| {
+                Json::object([
+                    (
+                        "type",
+                        Json::from(relocations::type_name(self.machine, relocation.r_type)),
+                    ),
+                    ("relocation", relocation.to_json()),
+                ])
+            }))
+        };
+        Json::object([
+            (
+                "plt",
+                Json::array(self.entries.iter().map(|entry| {
+                    Json::object([
+                        ("section", Json::from(entry.section)),
+                        ("address", Json::from(entry.address)),
+                        ("slot", Json::from(entry.slot)),
+                        ("relocation", relocation(&entry.relocation)),
+                        ("target", entry.target.to_json()),
+                    ])
+                })),
+            ),
+            (
+                "got",
+                Json::array(self.got.iter().map(|entry| {
+                    Json::object([
+                        ("section", Json::from(entry.section)),
+                        ("address", Json::from(entry.address)),
+                        ("value", Json::from(entry.value)),
+                        ("relocation", relocation(&entry.relocation)),
+                        ("target", entry.target.to_json()),
+                    ])
+                })),
+            ),
+        ])
+    }
+}
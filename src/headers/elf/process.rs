@@ -0,0 +1,507 @@
+//! The ELF files mapped in a running process
+//!
+//! `/proc/<pid>/maps` lists the regions of the address space with the
+//! file they map and its offset. The region mapping the start of an ELF
+//! file holds its header, the program headers then give the load bias
+//! between the addresses of the file and those of the process, and the
+//! PT_DYNAMIC segment whose entries the loader relocated in memory. In the
+//! main executable DT_DEBUG points to the `r_debug` structure of the
+//! loader, whose link map lists the libraries in load order.
+//!
+//! The memory is read through a function, `from_pid` reads
+//! `/proc/<pid>/mem` on Linux with the `proc` feature.
+//! Sources:
+//! * <https://man7.org/linux/man-pages/man5/proc_pid_maps.5.html>
+//! * <https://sourceware.org/git/?p=glibc.git;a=blob;f=elf/link.h>
+use crate::alloc_prelude::*;
+use crate::utils::json::{Json, ToJson};
+use crate::utils::read_u64;
+use crate::{Error, Result};
+use core::fmt;
+
+use super::data::Data;
+use super::dynamic::{Elf64_Dyn, DT};
+use super::program::{Elf64_Phdr, PT};
+use super::table::Table;
+use super::types::Type;
+use super::x64::{self as header, x64};
+
+/// Most entries read from the dynamic section and the link map, more
+/// means a loop or garbage
+const MAX_ENTRIES: usize = 4096;
+
+/// Smallest page size, the mappings start on one
+const PAGE: u64 = 0x1000;
+
+/// Longest string read from the memory of the process
+const MAX_STRING: usize = 4096;
+
+/// A line of `/proc/<pid>/maps`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region<'a> {
+    /// address of the first byte
+    pub start: u64,
+    /// address following the last byte
+    pub end: u64,
+    /// permissions, like `r-xp`
+    pub perms: &'a str,
+    /// offset of the region in the mapped file
+    pub offset: u64,
+    /// inode of the mapped file, 0 for anonymous memory
+    pub inode: u64,
+    /// path of the mapped file, a name like `[vdso]` or empty
+    pub path: &'a str,
+}
+
+/// Parse the lines of `/proc/<pid>/maps`
+///
+/// Fails with the number of the first line that can't be parsed.
+pub fn regions(maps: &str) -> Result<Vec<Region<'_>>> {
+    maps.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| region(line).ok_or(Error::InvalidMaps(index + 1)))
+        .collect()
+}
+
+fn region(line: &str) -> Option<Region<'_>> {
+    let mut fields = line.splitn(6, ' ');
+    let (start, end) = fields.next()?.split_once('-')?;
+    let perms = fields.next()?;
+    let offset = fields.next()?;
+    let _device = fields.next()?;
+    let inode = fields.next()?;
+    Some(Region {
+        start: u64::from_str_radix(start, 16).ok()?,
+        end: u64::from_str_radix(end, 16).ok()?,
+        perms,
+        offset: u64::from_str_radix(offset, 16).ok()?,
+        inode: inode.parse().ok()?,
+        path: fields.next().unwrap_or_default().trim_start(),
+    })
+}
+
+/// An ELF file mapped in the process
+#[derive(Debug, Clone)]
+pub struct Module {
+    /// path of the file, or `[vdso]`
+    pub path: String,
+    /// lowest address of the regions mapping the file
+    pub start: u64,
+    /// address following the highest region mapping the file
+    pub end: u64,
+    /// what the process adds to the virtual addresses of the file
+    pub bias: u64,
+    /// the file header, read from memory
+    pub header: x64,
+    /// the program headers, with the addresses of the file
+    pub segments: Vec<Elf64_Phdr>,
+    /// the dynamic entries, as the loader left them in memory
+    pub dynamic: Vec<Elf64_Dyn>,
+    /// DT_NEEDED libraries
+    pub needed: Vec<String>,
+    /// DT_SONAME
+    pub soname: Option<String>,
+    /// true for the executable of the process
+    pub main: bool,
+}
+
+impl Module {
+    /// The address in the process of a virtual address of the file
+    pub fn relocate(&self, vaddr: u64) -> u64 {
+        vaddr.wrapping_add(self.bias)
+    }
+
+    /// The address in the process of a dynamic entry pointer
+    ///
+    /// glibc relocates them in the dynamic section, other loaders and
+    /// the read only dynamic sections of some machines keep the value of
+    /// the file: the values outside of the module are relocated here.
+    pub fn pointer(&self, d_ptr: u64) -> u64 {
+        if (self.start..self.end).contains(&d_ptr) {
+            d_ptr
+        } else {
+            self.relocate(d_ptr)
+        }
+    }
+
+    /// The entry point in the process, None for the libraries without one
+    pub fn entry(&self) -> Option<u64> {
+        Some(self.header.e_entry)
+            .filter(|entry| *entry != 0)
+            .map(|entry| self.relocate(entry))
+    }
+
+    /// The address of the dynamic section in the process
+    pub fn dynamic_address(&self) -> Option<u64> {
+        self.segments
+            .iter()
+            .find(|phdr| phdr.p_type == PT::DYNAMIC)
+            .map(|phdr| self.relocate(phdr.p_vaddr))
+    }
+
+    fn file_type(&self) -> &'static str {
+        Type::from(self.header.e_type).as_str()
+    }
+}
+
+/// A library of the link map of the loader
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkMapEntry {
+    /// `l_addr`, the load bias of the library
+    pub bias: u64,
+    /// `l_name`, empty for the main executable
+    pub name: String,
+    /// `l_ld`, the address of its dynamic section
+    pub dynamic: u64,
+}
+
+/// The ELF files mapped in a process
+#[derive(Debug, Clone)]
+pub struct Process {
+    pub pid: u32,
+    /// path of the executable, from `/proc/<pid>/exe`
+    pub exe: String,
+    /// the files in address order
+    pub modules: Vec<Module>,
+    /// the libraries of the link map, empty if the executable has no
+    /// DT_DEBUG or the loader hasn't filled it
+    pub link_map: Vec<LinkMapEntry>,
+}
+
+/// Read a nul-terminated string, without crossing unreadable pages
+fn read_string<F>(read: &mut F, address: u64) -> Option<String>
+where
+    F: FnMut(u64, usize) -> Option<Vec<u8>>,
+{
+    let mut bytes = Vec::new();
+    let mut at = address;
+    while bytes.len() < MAX_STRING {
+        // the chunks end on 64 bytes boundaries, inside a page
+        let len = 64 - (at % 64) as usize;
+        let chunk = read(at, len)?;
+        match chunk.iter().position(|b| *b == 0) {
+            Some(end) => {
+                bytes.extend_from_slice(&chunk[..end]);
+                return Some(String::from_utf8_lossy(&bytes).into_owned());
+            }
+            None => bytes.extend_from_slice(&chunk),
+        }
+        at = at.checked_add(len as u64)?;
+    }
+    None
+}
+
+/// Read the module whose header is mapped by `regions[first]`
+fn module<F>(regions: &[Region], first: usize, exe: &str, read: &mut F) -> Option<Module>
+where
+    F: FnMut(u64, usize) -> Option<Vec<u8>>,
+{
+    let region = &regions[first];
+    let bytes = read(region.start, x64::SIZE)?;
+    let header = header::from_bytes(&bytes).ok()?.into_owned();
+    let big_endian = Data::is_big_endian(&header.e_ident);
+    let phnum = u64::from(header.e_phnum);
+    let entsize = u64::from(header.e_phentsize);
+    let size = usize::try_from(phnum.checked_mul(entsize)?).ok()?;
+    let phdrs = read(region.start.checked_add(header.e_phoff)?, size)?;
+    let segments: Vec<Elf64_Phdr> =
+        Table::<Elf64_Phdr>::parse(&phdrs, 0, entsize, phnum, big_endian)
+            .ok()?
+            .map(|phdr| phdr.into_owned())
+            .collect();
+    let load = segments.iter().find(|phdr| phdr.p_type == PT::LOAD)?;
+    // the first segment is mapped from the start of its page
+    let page = |value: u64| value & !(PAGE - 1);
+    let bias = region
+        .start
+        .wrapping_add(page(load.p_offset))
+        .wrapping_sub(page(load.p_vaddr));
+    // the regions of the same file following the header, .bss included
+    let mut end = region.end;
+    for next in &regions[first + 1..] {
+        let same_file = next.path == region.path && next.inode == region.inode;
+        let bss = next.inode == 0 && next.path.is_empty() && next.start == end;
+        if !(same_file || bss) || next.offset == 0 && same_file {
+            break;
+        }
+        end = next.end;
+    }
+    let mut module = Module {
+        path: region.path.to_string(),
+        start: region.start,
+        end,
+        bias,
+        header,
+        segments,
+        dynamic: Vec::new(),
+        needed: Vec::new(),
+        soname: None,
+        main: !exe.is_empty() && region.path == exe,
+    };
+    if let Some(address) = module.dynamic_address() {
+        let mut at = address;
+        while module.dynamic.len() < MAX_ENTRIES {
+            let entry = match read(at, Elf64_Dyn::SIZE) {
+                Some(entry) => entry,
+                None => break,
+            };
+            let dyn_entry = Elf64_Dyn {
+                d_tag: read_u64(&entry, 0, big_endian)? as i64,
+                d_val: read_u64(&entry, 8, big_endian)?,
+            };
+            if dyn_entry.d_tag == DT::NULL {
+                break;
+            }
+            module.dynamic.push(dyn_entry);
+            at += Elf64_Dyn::SIZE as u64;
+        }
+    }
+    let strtab = module
+        .dynamic
+        .iter()
+        .find(|entry| entry.d_tag == DT::STRTAB)
+        .map(|entry| module.pointer(entry.d_val));
+    if let Some(strtab) = strtab {
+        for entry in &module.dynamic {
+            let mut name = || read_string(read, strtab.checked_add(entry.d_val)?);
+            match entry.d_tag {
+                DT::NEEDED => module.needed.extend(name()),
+                DT::SONAME => module.soname = name(),
+                _ => {}
+            }
+        }
+    }
+    Some(module)
+}
+
+/// Walk the link map of `r_debug`, from DT_DEBUG of the executable
+fn link_map<F>(main: &Module, read: &mut F) -> Vec<LinkMapEntry>
+where
+    F: FnMut(u64, usize) -> Option<Vec<u8>>,
+{
+    let big_endian = Data::is_big_endian(&main.header.e_ident);
+    let word = |read: &mut F, address: u64| {
+        read(address, 8).and_then(|bytes| read_u64(&bytes, 0, big_endian))
+    };
+    let mut entries = Vec::new();
+    let r_debug = main
+        .dynamic
+        .iter()
+        .find(|entry| entry.d_tag == DT::DEBUG)
+        .map_or(0, |entry| entry.d_val);
+    // r_version then r_map, after the padding of the int
+    let mut next = match r_debug {
+        0 => return entries,
+        _ => word(read, r_debug + 8).unwrap_or(0),
+    };
+    while next != 0 && entries.len() < MAX_ENTRIES {
+        // l_addr, l_name, l_ld, l_next
+        let fields: Option<Vec<u64>> = (0..4).map(|index| word(read, next + index * 8)).collect();
+        let fields = match fields {
+            Some(fields) => fields,
+            None => break,
+        };
+        entries.push(LinkMapEntry {
+            bias: fields[0],
+            name: read_string(read, fields[1]).unwrap_or_default(),
+            dynamic: fields[2],
+        });
+        next = fields[3];
+    }
+    entries
+}
+
+/// Find the ELF files of a process from its memory maps
+///
+/// `read(address, len)` returns the `len` bytes of the process at
+/// `address`, None if they can't be read. The regions mapping something
+/// else than an ELF header are skipped. Fails if the maps can't be
+/// parsed.
+pub fn from_maps<F>(pid: u32, maps: &str, exe: &str, mut read: F) -> Result<Process>
+where
+    F: FnMut(u64, usize) -> Option<Vec<u8>>,
+{
+    let regions = regions(maps)?;
+    let modules: Vec<Module> = regions
+        .iter()
+        .enumerate()
+        .filter(|(_, region)| {
+            region.offset == 0
+                && region.perms.starts_with('r')
+                && (region.inode != 0 || region.path == "[vdso]")
+        })
+        .filter_map(|(index, _)| module(&regions, index, exe, &mut read))
+        .collect();
+    let link_map = modules
+        .iter()
+        .find(|module| module.main)
+        .map(|main| link_map(main, &mut read))
+        .unwrap_or_default();
+    Ok(Process {
+        pid,
+        exe: exe.to_string(),
+        modules,
+        link_map,
+    })
+}
+
+/// Read the ELF files mapped by the process `pid`
+///
+/// Reading the memory of another process needs the permission to trace
+/// it, see `ptrace(2)`: the regions that can't be read are skipped.
+#[cfg(all(target_os = "linux", feature = "proc"))]
+pub fn from_pid(pid: u32) -> Result<Process> {
+    use std::os::unix::fs::FileExt;
+
+    let proc = format!("/proc/{}", pid);
+    let maps = std::fs::read_to_string(format!("{}/maps", proc))?;
+    let exe = std::fs::read_link(format!("{}/exe", proc))
+        .map(|exe| exe.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mem = std::fs::File::open(format!("{}/mem", proc))?;
+    from_maps(pid, &maps, &exe, |address, len| {
+        let mut bytes = vec![0; len];
+        mem.read_exact_at(&mut bytes, address).ok()?;
+        Some(bytes)
+    })
+}
+
+impl fmt::Display for Process {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Process {}: {}", self.pid, self.exe)?;
+        writeln!(f)?;
+        writeln!(f, "ELF files mapped: {}", self.modules.len())?;
+        writeln!(
+            f,
+            "  Start              End                Load Bias          Type Path"
+        )?;
+        for module in &self.modules {
+            writeln!(
+                f,
+                "  0x{:016x} 0x{:016x} 0x{:016x} {:<4} {}{}",
+                module.start,
+                module.end,
+                module.bias,
+                module.file_type(),
+                module.path,
+                if module.main { " (main)" } else { "" }
+            )?;
+        }
+        for module in &self.modules {
+            writeln!(f)?;
+            writeln!(f, "{}:", module.path)?;
+            if let Some(entry) = module.entry() {
+                writeln!(f, "  Entry point: 0x{:x}", entry)?;
+            }
+            if let Some(address) = module.dynamic_address() {
+                writeln!(
+                    f,
+                    "  Dynamic section at 0x{:x}: {} entries",
+                    address,
+                    module.dynamic.len()
+                )?;
+            }
+            if let Some(soname) = &module.soname {
+                writeln!(f, "  Soname: {}", soname)?;
+            }
+            for needed in &module.needed {
+                writeln!(f, "  Needed: {}", needed)?;
+            }
+            writeln!(f, "  Loaded segments:")?;
+            writeln!(
+                f,
+                "    VirtAddr           MemSiz             Flg  File VirtAddr"
+            )?;
+            for phdr in module
+                .segments
+                .iter()
+                .filter(|phdr| phdr.p_type == PT::LOAD)
+            {
+                writeln!(
+                    f,
+                    "    0x{:016x} 0x{:016x} {}  0x{:x}",
+                    module.relocate(phdr.p_vaddr),
+                    phdr.p_memsz,
+                    phdr.flags_string(),
+                    phdr.p_vaddr
+                )?;
+            }
+        }
+        if !self.link_map.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Link map of the loader:")?;
+            writeln!(f, "  Load Bias          Dynamic            Name")?;
+            for entry in &self.link_map {
+                writeln!(
+                    f,
+                    "  0x{:016x} 0x{:016x} {}",
+                    entry.bias, entry.dynamic, entry.name
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for Process {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("pid", Json::from(self.pid)),
+            ("exe", Json::from(self.exe.as_str())),
+            (
+                "modules",
+                Json::array(self.modules.iter().map(|module| {
+                    Json::object([
+                        ("path", Json::from(module.path.as_str())),
+                        ("start", Json::from(module.start)),
+                        ("end", Json::from(module.end)),
+                        ("bias", Json::from(module.bias)),
+                        ("type", Json::from(module.file_type())),
+                        ("main", Json::from(module.main)),
+                        ("entry", module.entry().map_or(Json::Null, Json::from)),
+                        (
+                            "dynamic",
+                            module.dynamic_address().map_or(Json::Null, Json::from),
+                        ),
+                        (
+                            "soname",
+                            module.soname.as_deref().map_or(Json::Null, Json::from),
+                        ),
+                        (
+                            "needed",
+                            Json::array(module.needed.iter().map(|name| Json::from(name.as_str()))),
+                        ),
+                        (
+                            "segments",
+                            Json::array(
+                                module
+                                    .segments
+                                    .iter()
+                                    .filter(|phdr| phdr.p_type == PT::LOAD)
+                                    .map(|phdr| {
+                                        Json::object([
+                                            ("vaddr", Json::from(module.relocate(phdr.p_vaddr))),
+                                            ("memsz", Json::from(phdr.p_memsz)),
+                                            ("flags", Json::from(phdr.flags_string().trim_end())),
+                                            ("file_vaddr", Json::from(phdr.p_vaddr)),
+                                        ])
+                                    }),
+                            ),
+                        ),
+                    ])
+                })),
+            ),
+            (
+                "link_map",
+                Json::array(self.link_map.iter().map(|entry| {
+                    Json::object([
+                        ("bias", Json::from(entry.bias)),
+                        ("dynamic", Json::from(entry.dynamic)),
+                        ("name", Json::from(entry.name.as_str())),
+                    ])
+                })),
+            ),
+        ])
+    }
+}
@@ -0,0 +1,447 @@
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::json::{Json, ToJson};
+use crate::utils::template::{Record, Value};
+use crate::utils::{impl_fields, impl_swap_bytes, impl_to_bytes};
+use crate::Result;
+use alloc::borrow::Cow;
+use core::fmt;
+use core::mem::size_of;
+
+use super::data::Data;
+use super::registry;
+use super::section::{self, Elf64_Shdr, SHF, SHT};
+use super::strtab::{StrTab, CORRUPT};
+use super::table::Table;
+use super::x64::x64;
+
+/// Define the segment type of a program header entry
+///
+/// PT define possible values for the `p_type` field
+pub struct PT {}
+impl PT {
+    /// Unused entry
+    pub const NULL: u32 = 0;
+    /// Loadable segment
+    pub const LOAD: u32 = 1;
+    /// Dynamic linking information
+    pub const DYNAMIC: u32 = 2;
+    /// Path to the program interpreter
+    pub const INTERP: u32 = 3;
+    /// Auxiliary information
+    pub const NOTE: u32 = 4;
+    /// Reserved
+    pub const SHLIB: u32 = 5;
+    /// Program header table itself
+    pub const PHDR: u32 = 6;
+    /// Thread local storage template
+    pub const TLS: u32 = 7;
+    /// Start of the OS specific range
+    pub const LOOS: u32 = 0x6000_0000;
+    /// `.eh_frame_hdr` segment
+    pub const GNU_EH_FRAME: u32 = 0x6474_e550;
+    /// Stack executability
+    pub const GNU_STACK: u32 = 0x6474_e551;
+    /// Read only after relocation
+    pub const GNU_RELRO: u32 = 0x6474_e552;
+    /// `.note.gnu.property` segment
+    pub const GNU_PROPERTY: u32 = 0x6474_e553;
+    /// `.sframe` segment
+    pub const GNU_SFRAME: u32 = 0x6474_e554;
+    /// Start of the memory binding range
+    pub const GNU_MBIND_LO: u32 = 0x6474_e555;
+    /// End of the memory binding range
+    pub const GNU_MBIND_HI: u32 = 0x6474_f554;
+    /// End of the OS specific range
+    pub const HIOS: u32 = 0x6fff_ffff;
+    /// Start of the processor specific range
+    pub const LOPROC: u32 = 0x7000_0000;
+    /// End of the processor specific range
+    pub const HIPROC: u32 = 0x7fff_ffff;
+}
+
+/// Define the permissions of a segment
+///
+/// PF define the bits of the `p_flags` field
+pub struct PF {}
+impl PF {
+    /// Execute permission
+    pub const X: u32 = 1;
+    /// Write permission
+    pub const W: u32 = 2;
+    /// Read permission
+    pub const R: u32 = 4;
+}
+
+/// Program header table entry of an ELF64 file
+///
+/// An executable or shared object file's program header table is an
+/// array of structures, each describing a segment or other information
+/// the system needs to prepare the program for execution.
+/// Sources:
+/// * <https://www.man7.org/linux/man-pages/man5/elf.5.html>
+/// * <https://uclibc.org/docs/elf-64-gen.pdf>
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct Elf64_Phdr {
+    /// segment type
+    pub p_type: u32,
+    /// segment flags
+    pub p_flags: u32,
+    /// segment offset in the file
+    pub p_offset: u64,
+    /// virtual address of the segment in memory
+    pub p_vaddr: u64,
+    /// physical address of the segment
+    pub p_paddr: u64,
+    /// size of the segment in the file
+    pub p_filesz: u64,
+    /// size of the segment in memory
+    pub p_memsz: u64,
+    /// segment alignment
+    pub p_align: u64,
+}
+
+impl_swap_bytes!(
+    Elf64_Phdr, p_type, p_flags, p_offset, p_vaddr, p_paddr, p_filesz, p_memsz, p_align
+);
+
+impl_to_bytes!(
+    Elf64_Phdr, p_type, p_flags, p_offset, p_vaddr, p_paddr, p_filesz, p_memsz, p_align,
+);
+
+impl_fields!(Elf64_Phdr, p_type, p_flags, p_offset, p_vaddr, p_paddr, p_filesz, p_memsz, p_align,);
+
+impl Elf64_Phdr {
+    pub const SIZE: usize = size_of::<Self>();
+
+    /// Name of the segment type as printed by readelf
+    pub fn type_name(&self) -> Cow<'static, str> {
+        let name = match self.p_type {
+            PT::NULL => "NULL",
+            PT::LOAD => "LOAD",
+            PT::DYNAMIC => "DYNAMIC",
+            PT::INTERP => "INTERP",
+            PT::NOTE => "NOTE",
+            PT::SHLIB => "SHLIB",
+            PT::PHDR => "PHDR",
+            PT::TLS => "TLS",
+            PT::GNU_EH_FRAME => "GNU_EH_FRAME",
+            PT::GNU_STACK => "GNU_STACK",
+            PT::GNU_RELRO => "GNU_RELRO",
+            PT::GNU_PROPERTY => "GNU_PROPERTY",
+            PT::GNU_SFRAME => "GNU_SFRAME",
+            PT::LOOS..=PT::HIOS => {
+                return Cow::Owned(format!("LOOS+{:#x}", self.p_type - PT::LOOS))
+            }
+            PT::LOPROC..=PT::HIPROC => {
+                return Cow::Owned(format!("LOPROC+{:#x}", self.p_type - PT::LOPROC))
+            }
+            _ => return Cow::Owned(format!("<unknown>: {:#x}", self.p_type)),
+        };
+        Cow::Borrowed(name)
+    }
+
+    /// Permissions of the segment as printed by readelf, ex: `R E`
+    pub fn flags_string(&self) -> String {
+        let flag = |bit, c| if self.p_flags & bit != 0 { c } else { ' ' };
+        [flag(PF::R, 'R'), flag(PF::W, 'W'), flag(PF::X, 'E')]
+            .iter()
+            .collect()
+    }
+
+    /// Bytes of the segment stored in the file
+    ///
+    /// Returns None if the segment lies outside of the given data.
+    pub fn data<'a>(&self, data: &'a [u8]) -> Option<&'a [u8]> {
+        let start = usize::try_from(self.p_offset).ok()?;
+        let size = usize::try_from(self.p_filesz).ok()?;
+        data.get(start..start.checked_add(size)?)
+    }
+
+    /// Path of the program interpreter if this is a PT_INTERP segment,
+    /// whatever its encoding
+    pub fn interpreter<'a>(&self, data: &'a [u8]) -> Option<ByteStr<'a>> {
+        if self.p_type != PT::INTERP {
+            return None;
+        }
+        StrTab::new(self.data(data)?).name(0).ok()
+    }
+
+    /// The section lies in this segment, by file offset and by address
+    ///
+    /// Same rules as the section to segment mapping of `readelf -l`: TLS
+    /// sections only belong to PT_TLS, PT_LOAD and PT_GNU_RELRO, the
+    /// loaded segments only hold SHF_ALLOC sections and empty sections at
+    /// the edges of PT_DYNAMIC and PT_NOTE are left out.
+    pub fn contains_section(&self, shdr: &Elf64_Shdr) -> bool {
+        let tls = shdr.sh_flags & SHF::TLS != 0;
+        let alloc = shdr.sh_flags & SHF::ALLOC != 0;
+        let nobits = shdr.sh_type == SHT::NOBITS;
+        // .tbss takes no room outside of the TLS template
+        if tls && nobits && self.p_type != PT::TLS {
+            return false;
+        }
+        let kind = match tls {
+            true => matches!(self.p_type, PT::TLS | PT::GNU_RELRO | PT::LOAD),
+            false => self.p_type != PT::TLS && self.p_type != PT::PHDR,
+        };
+        let loaded = matches!(
+            self.p_type,
+            PT::LOAD
+                | PT::DYNAMIC
+                | PT::GNU_EH_FRAME
+                | PT::GNU_STACK
+                | PT::GNU_RELRO
+                | PT::GNU_SFRAME
+                | PT::GNU_MBIND_LO..=PT::GNU_MBIND_HI
+        );
+        if !kind || (!alloc && loaded) {
+            return false;
+        }
+        // the sizes wrap like the unsigned arithmetic of binutils
+        let size = shdr.sh_size;
+        let offset = shdr.sh_offset.wrapping_sub(self.p_offset);
+        let in_file = nobits
+            || (shdr.sh_offset >= self.p_offset
+                && offset <= self.p_filesz.wrapping_sub(1)
+                && offset.wrapping_add(size) <= self.p_filesz);
+        let addr = shdr.sh_addr.wrapping_sub(self.p_vaddr);
+        let in_memory = !alloc
+            || (shdr.sh_addr >= self.p_vaddr
+                && addr <= self.p_memsz.wrapping_sub(1)
+                && addr.wrapping_add(size) <= self.p_memsz);
+        if !in_file || !in_memory {
+            return false;
+        }
+        if (self.p_type != PT::DYNAMIC && self.p_type != PT::NOTE) || size != 0 || self.p_memsz == 0
+        {
+            return true;
+        }
+        (nobits || (shdr.sh_offset > self.p_offset && offset < self.p_filesz))
+            && (!alloc || (shdr.sh_addr > self.p_vaddr && addr < self.p_memsz))
+    }
+
+    /// Indices of the sections lying in this segment, see [`Self::contains_section`]
+    pub fn sections(&self, data: &[u8], header: &x64) -> Result<Vec<usize>> {
+        Ok(section::from_bytes(data, header)?
+            .enumerate()
+            .skip(1)
+            .filter(|(_, shdr)| self.contains_section(shdr))
+            .map(|(index, _)| index)
+            .collect())
+    }
+}
+
+/// Iterator over the program header table of an ELF64 file
+///
+/// The table is located with `e_phoff`, each entry is `e_phentsize`
+/// bytes long and there are `e_phnum` of them, or `sh_info` of the first
+/// section header when `e_phnum` is PN_XNUM.
+pub type ProgramHeaders<'a> = Table<'a, Elf64_Phdr>;
+
+/// Returns an iterator over the program headers described by `header`
+///
+/// Fails if the table doesn't fit in the given data, if its offset is
+/// misaligned or if the entry size is smaller than `Elf64_Phdr`.
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64) -> Result<ProgramHeaders<'a>> {
+    Table::parse(
+        data,
+        header.e_phoff,
+        header.e_phentsize as u64,
+        header.phnum(data),
+        Data::is_big_endian(&header.e_ident),
+    )
+}
+
+/// A program header with its index, a line of a `--format` listing
+#[derive(Debug, Clone)]
+pub struct SegmentEntry<'a> {
+    /// index of the program header
+    pub index: usize,
+    /// the header, in the byte order of the host
+    pub phdr: Cow<'a, Elf64_Phdr>,
+    /// name of the type in the file, ex: `MIPS_ABIFLAGS`
+    pub type_name: Cow<'static, str>,
+}
+
+/// The segments of a file with their index
+///
+/// Fails if the program header table can't be parsed.
+pub fn entries<'a>(data: &'a [u8], header: &x64) -> Result<Vec<SegmentEntry<'a>>> {
+    Ok(from_bytes(data, header)?
+        .enumerate()
+        .map(|(index, phdr)| SegmentEntry {
+            index,
+            type_name: registry::segment_type_name(header, phdr.p_type),
+            phdr,
+        })
+        .collect())
+}
+
+impl Record for SegmentEntry<'_> {
+    const FIELDS: &'static [&'static str] = &[
+        "index", "type", "offset", "vaddr", "paddr", "filesz", "memsz", "size", "flags", "align",
+    ];
+
+    fn field(&self, name: &str) -> Option<Value<'_>> {
+        Some(match name {
+            "index" => Value::from(self.index as u64),
+            "type" => Value::from(self.type_name.as_ref()),
+            "offset" => Value::from(self.phdr.p_offset),
+            "vaddr" => Value::from(self.phdr.p_vaddr),
+            "paddr" => Value::from(self.phdr.p_paddr),
+            "filesz" => Value::from(self.phdr.p_filesz),
+            "memsz" | "size" => Value::from(self.phdr.p_memsz),
+            "flags" => Value::from(self.phdr.flags_string()),
+            "align" => Value::from(self.phdr.p_align),
+            _ => return None,
+        })
+    }
+}
+
+/// Translate a virtual address into a file offset using the PT_LOAD segments
+///
+/// Returns None if the address isn't backed by the file, or if its offset
+/// doesn't fit in 64 bits.
+pub fn vaddr_to_offset(data: &[u8], header: &x64, vaddr: u64) -> Option<u64> {
+    from_bytes(data, header)
+        .ok()?
+        .filter(|phdr| phdr.p_type == PT::LOAD)
+        .find_map(|phdr| {
+            let delta = vaddr
+                .checked_sub(phdr.p_vaddr)
+                .filter(|delta| *delta < phdr.p_filesz)?;
+            delta.checked_add(phdr.p_offset)
+        })
+}
+
+impl Elf64_Phdr {
+    /// Write the header as a line of `readelf -l` with the given type name
+    fn write_row(&self, f: &mut fmt::Formatter<'_>, type_name: &str) -> fmt::Result {
+        write!(
+            f,
+            "  {:<14} {:#08x} {:#018x} {:#018x} {:#08x} {:#08x} {} {:#x}",
+            type_name,
+            self.p_offset,
+            self.p_vaddr,
+            self.p_paddr,
+            self.p_filesz,
+            self.p_memsz,
+            self.flags_string(),
+            self.p_align
+        )
+    }
+}
+
+impl fmt::Display for Elf64_Phdr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_row(f, &self.type_name())
+    }
+}
+
+/// Program header table ready to be printed like `readelf -l`
+pub struct ProgramHeaderTable<'a> {
+    /// the whole file, used to resolve the interpreter path
+    pub data: &'a [u8],
+    /// the file header
+    pub header: &'a x64,
+}
+
+impl fmt::Display for ProgramHeaderTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let phdrs = match from_bytes(self.data, self.header) {
+            Ok(phdrs) => phdrs,
+            Err(err) => return writeln!(f, "Warning: invalid program header table: {}", err),
+        };
+        let phnum = self.header.phnum(self.data);
+        if phnum == 0 {
+            return writeln!(f, "There are no program headers in this file.");
+        }
+        writeln!(
+            f,
+            "There are {} program headers, starting at offset {}",
+            phnum, self.header.e_phoff
+        )?;
+        writeln!(f)?;
+        writeln!(f, "Program Headers:")?;
+        writeln!(
+            f,
+            "  Type           Offset   VirtAddr           PhysAddr           FileSiz  MemSiz   Flg Align"
+        )?;
+        for phdr in phdrs.clone() {
+            phdr.write_row(f, &registry::segment_type_name(self.header, phdr.p_type))?;
+            writeln!(f)?;
+            if let Some(interp) = phdr.interpreter(self.data) {
+                writeln!(f, "      [Requesting program interpreter: {}]", interp)?;
+            }
+        }
+
+        let shdrs: Vec<_> = match section::from_bytes(self.data, self.header) {
+            Ok(shdrs) if shdrs.len() != 0 => shdrs.collect(),
+            _ => return Ok(()),
+        };
+        let names = section::names(self.data, self.header).unwrap_or_default();
+        writeln!(f)?;
+        writeln!(f, " Section to Segment mapping:")?;
+        writeln!(f, "  Segment Sections...")?;
+        for (index, phdr) in phdrs.enumerate() {
+            write!(f, "   {:02}     ", index)?;
+            for shdr in shdrs.iter().skip(1) {
+                if phdr.contains_section(shdr) {
+                    write!(f, "{} ", shdr.name(&names).unwrap_or(CORRUPT))?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for Elf64_Phdr {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("p_type", Json::from(self.p_type)),
+            ("type", Json::from(self.type_name())),
+            ("p_flags", Json::from(self.p_flags)),
+            ("flags", Json::from(self.flags_string())),
+            ("p_offset", Json::from(self.p_offset)),
+            ("p_vaddr", Json::from(self.p_vaddr)),
+            ("p_paddr", Json::from(self.p_paddr)),
+            ("p_filesz", Json::from(self.p_filesz)),
+            ("p_memsz", Json::from(self.p_memsz)),
+            ("p_align", Json::from(self.p_align)),
+        ])
+    }
+}
+
+impl ToJson for ProgramHeaderTable<'_> {
+    fn to_json(&self) -> Json {
+        let shdrs: Vec<_> = section::from_bytes(self.data, self.header)
+            .map(|shdrs| shdrs.collect())
+            .unwrap_or_default();
+        let names = section::names(self.data, self.header).unwrap_or_default();
+        match from_bytes(self.data, self.header) {
+            Ok(phdrs) => Json::array(phdrs.map(|phdr| {
+                let mut json = phdr.to_json();
+                if let Json::Object(members) = &mut json {
+                    if let Some((_, kind)) = members.iter_mut().find(|(key, _)| key == "type") {
+                        *kind = Json::from(registry::segment_type_name(self.header, phdr.p_type));
+                    }
+                    if let Some(interp) = phdr.interpreter(self.data) {
+                        members.push(("interpreter".to_string(), Json::from(interp)));
+                    }
+                    let sections = shdrs
+                        .iter()
+                        .skip(1)
+                        .filter(|shdr| phdr.contains_section(shdr))
+                        .map(|shdr| shdr.name(&names).unwrap_or(CORRUPT));
+                    members.push(("sections".to_string(), Json::array(sections)));
+                }
+                json
+            })),
+            Err(err) => Json::error(err),
+        }
+    }
+}
@@ -0,0 +1,302 @@
+//! What built a file: compilers, linker, build id and build properties
+//!
+//! The compilers and assemblers write their version in `.comment`, gold
+//! its own in a NT_GNU_GOLD_VERSION note, and the linkers merge the
+//! NT_GNU_PROPERTY_TYPE_0 notes of the objects into `.note.gnu.property`:
+//! the control flow protections (IBT and SHSTK on x86, BTI and PAC on
+//! AArch64) and the instruction set levels. Go writes its version and the
+//! modules of the program in `.go.buildinfo`.
+//! Sources:
+//! * <https://github.com/golang/go/blob/master/src/debug/buildinfo/buildinfo.go>
+use crate::alloc_prelude::*;
+use crate::utils::json::{Json, ToJson};
+use crate::utils::{read_u32, read_u64};
+use alloc::borrow::Cow;
+use core::fmt;
+
+use super::notes::{self, NoteData, Property, GNU_PROPERTY};
+use super::program;
+use super::section;
+use super::x64::x64;
+
+/// Bytes at the start of the Go build information
+const GO_MAGIC: &[u8; 14] = b"\xff Go buildinf:";
+
+/// Bytes around the module information of Go 1.13 and later
+const GO_MODINFO_START: &[u8; 16] = b"0w\xaf\x0c\x92t\x08\x02A\xe1\xc1\x07\xe6\xd6\x18\xe6";
+const GO_MODINFO_END: &[u8; 16] = b"\xf92C1\x86\x18 r\x00\x82B\x10A\x16\xd8\xf2";
+
+/// The build information of a Go program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoBuildInfo<'a> {
+    /// version of the toolchain, ex: `go1.22.1`
+    pub version: Cow<'a, str>,
+    /// the lines of the module information split at their first tab:
+    /// `path`, `mod`, `dep` and `build` entries
+    pub modules: Vec<(Cow<'a, str>, Cow<'a, str>)>,
+}
+
+/// The toolchain information of an ELF64 file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Provenance<'a> {
+    /// the strings of `.comment`, once each
+    pub comments: Vec<&'a str>,
+    /// version of the gold linker, from its note
+    pub gold_version: Option<&'a str>,
+    /// content of the NT_GNU_BUILD_ID note
+    pub build_id: Option<&'a [u8]>,
+    /// the properties of the NT_GNU_PROPERTY_TYPE_0 notes
+    pub properties: Vec<Property<'a>>,
+    /// the build information of a Go program
+    pub go: Option<GoBuildInfo<'a>>,
+}
+
+impl Provenance<'_> {
+    /// The control flow protections every object was built with:
+    /// `IBT`, `SHSTK`, `BTI` and `PAC`
+    pub fn features(&self) -> Vec<&'static str> {
+        let mut features = Vec::new();
+        for property in &self.properties {
+            let value = property.value().unwrap_or(0);
+            let known: &[(u32, &str)] = match property.pr_type {
+                GNU_PROPERTY::X86_FEATURE_1_AND => &[
+                    (GNU_PROPERTY::X86_FEATURE_1_IBT, "IBT"),
+                    (GNU_PROPERTY::X86_FEATURE_1_SHSTK, "SHSTK"),
+                ],
+                GNU_PROPERTY::AARCH64_FEATURE_1_AND => &[
+                    (GNU_PROPERTY::AARCH64_FEATURE_1_BTI, "BTI"),
+                    (GNU_PROPERTY::AARCH64_FEATURE_1_PAC, "PAC"),
+                ],
+                _ => &[],
+            };
+            features.extend(
+                known
+                    .iter()
+                    .filter(|(bit, _)| value & bit != 0)
+                    .map(|(_, name)| *name),
+            );
+        }
+        features
+    }
+}
+
+/// Read an unsigned LEB128 number, as written by `encoding/binary` of Go
+fn uvarint(bytes: &[u8], offset: &mut usize) -> Option<usize> {
+    let mut value = 0usize;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*offset)?;
+        *offset += 1;
+        value |= ((byte & 0x7f) as usize).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Read a Go string through its `(pointer, length)` header at `vaddr`
+fn go_string<'a>(
+    data: &'a [u8],
+    header: &x64,
+    vaddr: u64,
+    pointer_size: usize,
+    big_endian: bool,
+) -> Option<&'a [u8]> {
+    let read = |vaddr: u64| -> Option<u64> {
+        let offset = usize::try_from(program::vaddr_to_offset(data, header, vaddr)?).ok()?;
+        match pointer_size {
+            8 => read_u64(data, offset, big_endian),
+            _ => read_u32(data, offset, big_endian).map(u64::from),
+        }
+    };
+    let start = read(vaddr)?;
+    let len = usize::try_from(read(vaddr.checked_add(pointer_size as u64)?)?).ok()?;
+    let offset = usize::try_from(program::vaddr_to_offset(data, header, start)?).ok()?;
+    data.get(offset..offset.checked_add(len)?)
+}
+
+/// Decode the build information starting with [`GO_MAGIC`]
+///
+/// Go 1.18 and later write the strings inline after a 32 bytes header,
+/// the older versions write pointers to the string headers.
+fn go_build_info<'a>(data: &'a [u8], header: &x64, info: &'a [u8]) -> Option<GoBuildInfo<'a>> {
+    let pointer_size = *info.get(14)? as usize;
+    let flags = *info.get(15)?;
+    let big_endian = flags & 1 != 0;
+    let (version, modinfo) = if flags & 2 != 0 {
+        let mut offset = 32;
+        let len = uvarint(info, &mut offset)?;
+        let version = info.get(offset..offset.checked_add(len)?)?;
+        offset += len;
+        let len = uvarint(info, &mut offset)?;
+        let modinfo = info
+            .get(offset..offset.checked_add(len)?)
+            .unwrap_or_default();
+        (version, modinfo)
+    } else {
+        let pointer = |index: usize| -> Option<u64> {
+            let offset = 16 + index * pointer_size;
+            match pointer_size {
+                8 => read_u64(info, offset, big_endian),
+                4 => read_u32(info, offset, big_endian).map(u64::from),
+                _ => None,
+            }
+        };
+        let version = go_string(data, header, pointer(0)?, pointer_size, big_endian)?;
+        let modinfo = pointer(1)
+            .and_then(|vaddr| go_string(data, header, vaddr, pointer_size, big_endian))
+            .unwrap_or_default();
+        (version, modinfo)
+    };
+    let modinfo = match (modinfo.strip_prefix(GO_MODINFO_START), modinfo.len() >= 32) {
+        (Some(rest), true) => &rest[..rest.len() - GO_MODINFO_END.len()],
+        _ => modinfo,
+    };
+    let modules = modinfo
+        .split(|byte| *byte == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let tab = line.iter().position(|byte| *byte == b'\t');
+            let (key, value) = line.split_at(tab.unwrap_or(line.len()));
+            let value = value.strip_prefix(b"\t").unwrap_or(value);
+            (String::from_utf8_lossy(key), String::from_utf8_lossy(value))
+        })
+        .collect();
+    Some(GoBuildInfo {
+        version: String::from_utf8_lossy(version),
+        modules,
+    })
+}
+
+/// Collect the toolchain information of the file
+///
+/// The Go build information is read from `.go.buildinfo`, or found by its
+/// magic at a 16 bytes boundary in the files without section headers.
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64) -> Provenance<'a> {
+    let mut provenance = Provenance::default();
+    let names = section::names(data, header).unwrap_or_default();
+    let shdrs: Vec<_> = section::from_bytes(data, header)
+        .into_iter()
+        .flatten()
+        .collect();
+    let bytes = |name: &str| {
+        shdrs
+            .iter()
+            .find(|shdr| shdr.name(&names).is_ok_and(|found| found == name))
+            .and_then(|shdr| shdr.data(data))
+    };
+    if let Some(comment) = bytes(".comment") {
+        for text in comment.split(|byte| *byte == 0) {
+            match core::str::from_utf8(text) {
+                Ok(text) if !text.is_empty() && !provenance.comments.contains(&text) => {
+                    provenance.comments.push(text)
+                }
+                _ => {}
+            }
+        }
+    }
+    for note in notes::areas(data, header)
+        .into_iter()
+        .flat_map(|(_, _, notes)| notes)
+    {
+        match note.decode() {
+            NoteData::BuildId(id) => provenance.build_id = Some(id),
+            NoteData::GoldVersion(version) => provenance.gold_version = Some(version),
+            NoteData::Properties(properties) => provenance.properties.extend(properties),
+            _ => {}
+        }
+    }
+    let info = bytes(".go.buildinfo").or_else(|| {
+        let start = (0..data.len().saturating_sub(GO_MAGIC.len()))
+            .step_by(16)
+            .find(|offset| data[*offset..].starts_with(GO_MAGIC))?;
+        data.get(start..)
+    });
+    provenance.go = info
+        .filter(|info| info.starts_with(GO_MAGIC))
+        .and_then(|info| go_build_info(data, header, info));
+    provenance
+}
+
+impl fmt::Display for Provenance<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.comments.is_empty()
+            && self.gold_version.is_none()
+            && self.build_id.is_none()
+            && self.properties.is_empty()
+            && self.go.is_none()
+        {
+            return writeln!(f, "There is no toolchain information in this file.");
+        }
+        if !self.comments.is_empty() {
+            writeln!(f, "Compilers (.comment):")?;
+            for comment in &self.comments {
+                writeln!(f, "  {}", comment)?;
+            }
+        }
+        if let Some(version) = self.gold_version {
+            writeln!(f, "Linker: gold {}", version)?;
+        }
+        if let Some(id) = self.build_id {
+            let id: String = id.iter().map(|byte| format!("{:02x}", byte)).collect();
+            writeln!(f, "Build ID: {}", id)?;
+        }
+        if !self.properties.is_empty() {
+            writeln!(f, "Properties (.note.gnu.property):")?;
+            for property in &self.properties {
+                writeln!(f, "  {}", property)?;
+            }
+            let features = self.features();
+            if !features.is_empty() {
+                writeln!(f, "Control flow protection: {}", features.join(", "))?;
+            }
+        }
+        if let Some(go) = &self.go {
+            writeln!(f, "Go build information:")?;
+            writeln!(f, "  version {}", go.version)?;
+            for (key, value) in &go.modules {
+                writeln!(f, "  {:<7} {}", key, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for GoBuildInfo<'_> {
+    fn to_json(&self) -> Json {
+        let modules = self.modules.iter().map(|(key, value)| {
+            Json::object([
+                ("key", Json::from(key.as_ref())),
+                ("value", Json::from(value.as_ref())),
+            ])
+        });
+        Json::object([
+            ("version", Json::from(self.version.as_ref())),
+            ("modules", Json::array(modules)),
+        ])
+    }
+}
+
+impl ToJson for Provenance<'_> {
+    fn to_json(&self) -> Json {
+        let build_id = self.build_id.map(|id| {
+            id.iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<String>()
+        });
+        Json::object([
+            ("comments", Json::from(self.comments.clone())),
+            ("gold_version", Json::from(self.gold_version)),
+            ("build_id", Json::from(build_id)),
+            (
+                "properties",
+                Json::array(self.properties.iter().map(|property| property.to_string())),
+            ),
+            ("features", Json::from(self.features())),
+            (
+                "go",
+                self.go.as_ref().map_or(Json::Null, GoBuildInfo::to_json),
+            ),
+        ])
+    }
+}
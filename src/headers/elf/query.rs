@@ -0,0 +1,262 @@
+//! Iterate over the symbols of a file with chained filters
+//!
+//! [`Elf::all_symbols`](super::Elf::all_symbols) returns [`Symbol`]s, the
+//! raw entry together with its name, table and section, and the
+//! [`SymbolFilter`] adaptors select them without decoding `st_info` or
+//! `st_shndx` by hand:
+//!
+//! ```no_run
+//! use readbin::headers::elf::query::SymbolFilter;
+//! use readbin::headers::elf::Elf;
+//!
+//! let data = std::fs::read("/bin/ls").unwrap();
+//! let elf = Elf::parse(&data).unwrap();
+//! let exported = elf
+//!     .all_symbols()
+//!     .unwrap()
+//!     .defined()
+//!     .functions()
+//!     .globals()
+//!     .filter_name(|name| name.starts_with("str"));
+//! for symbol in exported {
+//!     println!("{:016x} {}", symbol.value(), symbol.name);
+//! }
+//! ```
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::demangle::demangle;
+use crate::utils::template::{Record, Value};
+use alloc::borrow::Cow;
+use core::iter::Filter;
+
+use super::section::{Elf64_Shdr, SHN, SHT};
+use super::strtab::StrTab;
+use super::symbols::{Elf64_Sym, Symbols, STB, STT};
+
+/// A symbol with everything needed to use it without the file
+#[derive(Debug, Clone)]
+pub struct Symbol<'data> {
+    /// index of the symbol in its table
+    pub index: usize,
+    /// name of the symbol, empty if it can't be read, not always UTF-8
+    pub name: ByteStr<'data>,
+    /// true for the symbols of `.dynsym`, false for the ones of `.symtab`
+    pub dynamic: bool,
+    /// name of the section the symbol is defined in, None for the
+    /// undefined, absolute and common symbols
+    pub section: Option<ByteStr<'data>>,
+    /// the raw entry, in the byte order of the host
+    pub sym: Cow<'data, Elf64_Sym>,
+}
+
+impl Symbol<'_> {
+    /// Value of the symbol, its address in executables and shared objects
+    pub fn value(&self) -> u64 {
+        self.sym.st_value
+    }
+
+    /// Size of the object or function, 0 if unknown
+    pub fn size(&self) -> u64 {
+        self.sym.st_size
+    }
+
+    /// Type of the symbol, one of the `STT` values
+    pub fn kind(&self) -> u8 {
+        self.sym.kind()
+    }
+
+    /// Binding of the symbol, one of the `STB` values
+    pub fn bind(&self) -> u8 {
+        self.sym.bind()
+    }
+
+    /// Visibility of the symbol, one of the `STV` values
+    pub fn visibility(&self) -> u8 {
+        self.sym.visibility()
+    }
+
+    /// The C++ or Rust name of the symbol, the name itself if it isn't mangled
+    pub fn demangled(&self) -> Cow<'_, str> {
+        match self.name.to_str() {
+            Some(name) => demangle(name),
+            None => self.name.to_str_lossy(),
+        }
+    }
+
+    /// Returns true if the symbol is defined by this file
+    pub fn is_defined(&self) -> bool {
+        self.sym.st_shndx != SHN::UNDEF
+    }
+
+    /// Returns true for code, plain and indirect functions
+    pub fn is_function(&self) -> bool {
+        matches!(self.kind(), STT::FUNC | STT::GNU_IFUNC)
+    }
+
+    /// Returns true for data, including common and thread local objects
+    pub fn is_object(&self) -> bool {
+        matches!(self.kind(), STT::OBJECT | STT::COMMON | STT::TLS)
+    }
+
+    /// Returns true if the symbol is visible to other files: global, weak
+    /// or unique
+    pub fn is_global(&self) -> bool {
+        matches!(self.bind(), STB::GLOBAL | STB::WEAK | STB::GNU_UNIQUE)
+    }
+}
+
+impl Record for Symbol<'_> {
+    const FIELDS: &'static [&'static str] = &[
+        "index",
+        "name",
+        "demangled",
+        "value",
+        "vaddr",
+        "size",
+        "type",
+        "bind",
+        "visibility",
+        "ndx",
+        "section",
+        "table",
+    ];
+
+    fn field(&self, name: &str) -> Option<Value<'_>> {
+        Some(match name {
+            "index" => Value::from(self.index as u64),
+            "name" => Value::from(self.name),
+            "demangled" => Value::from(self.demangled()),
+            "value" | "vaddr" => Value::from(self.value()),
+            "size" => Value::from(self.size()),
+            "type" => Value::from(self.sym.type_name()),
+            "bind" => Value::from(self.sym.bind_name()),
+            "visibility" => Value::from(self.sym.visibility_name()),
+            "ndx" => Value::from(self.sym.index_name()),
+            "section" => Value::from(self.section.unwrap_or_default()),
+            "table" => Value::from(if self.dynamic { ".dynsym" } else { ".symtab" }),
+            _ => return None,
+        })
+    }
+}
+
+/// The predicate of the filters without parameters
+pub type Predicate<'data> = fn(&Symbol<'data>) -> bool;
+
+/// Filters over any iterator of [`Symbol`]
+///
+/// Every method keeps the symbols matching one condition and can be
+/// chained with the others and with the adaptors of `Iterator`.
+pub trait SymbolFilter<'data>: Iterator<Item = Symbol<'data>> + Sized {
+    /// Keep the symbols defined by this file
+    fn defined(self) -> Filter<Self, Predicate<'data>> {
+        self.filter(Symbol::is_defined)
+    }
+
+    /// Keep the symbols imported from other files
+    fn undefined(self) -> Filter<Self, Predicate<'data>> {
+        self.filter(|symbol| !symbol.is_defined())
+    }
+
+    /// Keep the functions, see [`Symbol::is_function`]
+    fn functions(self) -> Filter<Self, Predicate<'data>> {
+        self.filter(Symbol::is_function)
+    }
+
+    /// Keep the data objects, see [`Symbol::is_object`]
+    fn objects(self) -> Filter<Self, Predicate<'data>> {
+        self.filter(Symbol::is_object)
+    }
+
+    /// Keep the symbols visible to other files, see [`Symbol::is_global`]
+    fn globals(self) -> Filter<Self, Predicate<'data>> {
+        self.filter(Symbol::is_global)
+    }
+
+    /// Keep the symbols whose name matches `predicate`, the names that
+    /// aren't UTF-8 are given with U+FFFD for their invalid bytes
+    fn filter_name<F>(self, mut predicate: F) -> Filter<Self, impl FnMut(&Symbol<'data>) -> bool>
+    where
+        F: FnMut(&str) -> bool,
+    {
+        self.filter(move |symbol| predicate(&symbol.name.to_str_lossy()))
+    }
+}
+
+impl<'data, I: Iterator<Item = Symbol<'data>>> SymbolFilter<'data> for I {}
+
+/// A symbol table to iterate over, with its string table
+#[derive(Debug, Clone)]
+pub(crate) struct SourceTable<'data> {
+    pub(crate) dynamic: bool,
+    pub(crate) symbols: Symbols<'data>,
+    pub(crate) strtab: StrTab<'data>,
+}
+
+/// Iterator over the symbols of several tables, see [`Elf::all_symbols`](super::Elf::all_symbols)
+#[derive(Debug, Clone)]
+pub struct AllSymbols<'data> {
+    tables: alloc::vec::IntoIter<SourceTable<'data>>,
+    current: Option<(SourceTable<'data>, usize)>,
+    // the name of each section, resolved once for all the symbols
+    sections: Vec<Option<ByteStr<'data>>>,
+}
+
+impl<'data> AllSymbols<'data> {
+    pub(crate) fn new(
+        tables: Vec<SourceTable<'data>>,
+        sections: &[Cow<'data, Elf64_Shdr>],
+        names: StrTab<'data>,
+    ) -> Self {
+        // the sections symbols can't be defined in
+        let sections = sections
+            .iter()
+            .map(|shdr| match shdr.sh_type {
+                SHT::NULL => None,
+                _ => shdr.name(&names).ok(),
+            })
+            .collect();
+        AllSymbols {
+            tables: tables.into_iter(),
+            current: None,
+            sections,
+        }
+    }
+
+    fn section_name(&self, shndx: u16) -> Option<ByteStr<'data>> {
+        if shndx == SHN::UNDEF || shndx >= SHN::LORESERVE {
+            return None;
+        }
+        *self.sections.get(shndx as usize)?
+    }
+}
+
+impl<'data> Iterator for AllSymbols<'data> {
+    type Item = Symbol<'data>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.current.is_none() {
+                self.current = Some((self.tables.next()?, 0));
+            }
+            let (table, index) = self.current.as_mut()?;
+            let sym = match table.symbols.next() {
+                Some(sym) => sym,
+                None => {
+                    self.current = None;
+                    continue;
+                }
+            };
+            let symbol_index = *index;
+            *index += 1;
+            let name = sym.name(&table.strtab).unwrap_or_default();
+            let dynamic = table.dynamic;
+            return Some(Symbol {
+                index: symbol_index,
+                name,
+                dynamic,
+                section: self.section_name(sym.st_shndx),
+                sym,
+            });
+        }
+    }
+}
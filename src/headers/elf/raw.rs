@@ -0,0 +1,91 @@
+//! The headers printed field by field with their raw values
+//!
+//! Nothing is decoded: every field is written under its name in the
+//! specification with its value in hexadecimal, so the output can be
+//! checked against a hexdump or read by scripts.
+use crate::alloc_prelude::*;
+use crate::utils::Fields;
+use core::fmt;
+
+use super::x64::x64;
+use super::{program, section, ElfHeader};
+
+/// Write `title` followed by one `name value` line per field
+fn fmt_fields(f: &mut fmt::Formatter<'_>, title: &str, fields: &[(&str, String)]) -> fmt::Result {
+    writeln!(f, "{}:", title)?;
+    let width = fields.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    for (name, value) in fields {
+        writeln!(f, "  {:<width$} {}", name, value)?;
+    }
+    Ok(())
+}
+
+/// The fields of the file header
+pub struct RawFileHeader<'a> {
+    /// the file header, of either class
+    pub header: &'a ElfHeader<'a>,
+}
+
+impl fmt::Display for RawFileHeader<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields = match self.header {
+            ElfHeader::Elf32(header) => header.fields(),
+            ElfHeader::Elf64(header) => header.fields(),
+        };
+        fmt_fields(f, "ELF Header", &fields)
+    }
+}
+
+/// The fields of each entry of the program header table
+pub struct RawProgramHeaders<'a> {
+    /// the whole file
+    pub data: &'a [u8],
+    /// the file header
+    pub header: &'a x64,
+}
+
+impl fmt::Display for RawProgramHeaders<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let phdrs = match program::from_bytes(self.data, self.header) {
+            Ok(phdrs) => phdrs,
+            Err(err) => return writeln!(f, "Warning: invalid program header table: {}", err),
+        };
+        if phdrs.len() == 0 {
+            return writeln!(f, "There are no program headers in this file.");
+        }
+        for (index, phdr) in phdrs.enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            fmt_fields(f, &format!("Program Header {}", index), &phdr.fields())?;
+        }
+        Ok(())
+    }
+}
+
+/// The fields of each entry of the section header table
+pub struct RawSectionHeaders<'a> {
+    /// the whole file
+    pub data: &'a [u8],
+    /// the file header
+    pub header: &'a x64,
+}
+
+impl fmt::Display for RawSectionHeaders<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shdrs = match section::from_bytes(self.data, self.header) {
+            Ok(shdrs) => shdrs,
+            Err(err) => return writeln!(f, "Warning: invalid section header table: {}", err),
+        };
+        if shdrs.len() == 0 {
+            return writeln!(f, "There are no sections in this file.");
+        }
+        for (index, shdr) in shdrs.enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            fmt_fields(f, &format!("Section Header {}", index), &shdr.fields())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+//! Parsing of an ELF64 file read on demand from a `Read + Seek` source
+//!
+//! Only the file header is read when the reader is created, the tables
+//! and the section contents are read when they are asked for, so big
+//! files can be inspected without loading them in memory. Only available
+//! with the `std` feature.
+use crate::utils::{Pod, SwapBytes};
+use crate::{Error, Result};
+use alloc::borrow::Cow;
+use core::mem::{align_of, size_of};
+use std::io::{Read, Seek, SeekFrom};
+
+use super::class::Class;
+use super::data::Data;
+use super::identification::Indent;
+use super::program::Elf64_Phdr;
+use super::section::{Elf64_Shdr, SHN, SHT};
+use super::strtab::StrTab;
+use super::table::{check_table, Table};
+use super::x64::{self as header, x64};
+
+/// An ELF64 file read from a `Read + Seek` source
+///
+/// ```no_run
+/// use readbin::headers::elf::reader::ElfReader;
+///
+/// let file = std::fs::File::open("/bin/ls").unwrap();
+/// let mut elf = ElfReader::new(file).unwrap();
+/// if let Some(shdr) = elf.section_by_name(".interp").unwrap() {
+///     let interp = elf.section_data(&shdr).unwrap();
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ElfReader<R> {
+    reader: R,
+    header: x64,
+    size: u64,
+}
+
+impl<R: Read + Seek> ElfReader<R> {
+    /// Read the file header of an ELF64 file
+    ///
+    /// Fails if the source can't be read or isn't an ELF64 file.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let size = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(0))?;
+        let mut bytes = [0; x64::SIZE];
+        let found = usize::try_from(size).map_or(x64::SIZE, |size| size.min(x64::SIZE));
+        reader.read_exact(&mut bytes[..found])?;
+        let header = header::from_bytes(&bytes[..found])?.into_owned();
+        let class = header.e_ident[Indent::CLASS];
+        if Class::from(class) != Class::Elf64 {
+            return Err(Error::UnsupportedClass(class));
+        }
+        Ok(ElfReader {
+            reader,
+            header,
+            size,
+        })
+    }
+
+    /// The file header
+    pub fn header(&self) -> &x64 {
+        &self.header
+    }
+
+    /// Size of the source, found by seeking to its end
+    pub fn file_size(&self) -> u64 {
+        self.size
+    }
+
+    /// Give back the source
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Read `size` bytes at `offset`
+    ///
+    /// Fails if the range lies outside of the source.
+    pub fn read_at(&mut self, offset: u64, size: u64) -> Result<Vec<u8>> {
+        let out_of_bounds = Error::OutOfBounds { offset, size };
+        match offset.checked_add(size) {
+            Some(end) if end <= self.size => {}
+            _ => return Err(out_of_bounds),
+        }
+        let mut bytes = vec![0; usize::try_from(size).map_err(|_| out_of_bounds)?];
+        self.reader.seek(SeekFrom::Start(offset))?;
+        self.reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Read a table of `num` entries of `entsize` bytes at `offset`
+    fn table<T: Pod + SwapBytes + Clone>(
+        &mut self,
+        offset: u64,
+        entsize: u64,
+        num: u64,
+    ) -> Result<Vec<T>> {
+        let len = usize::try_from(self.size).unwrap_or(usize::MAX);
+        check_table(len, offset, entsize, num, size_of::<T>(), align_of::<T>())?;
+        let bytes = self.read_at(offset, entsize * num)?;
+        let big_endian = Data::is_big_endian(&self.header.e_ident);
+        Ok(Table::<T>::parse(&bytes, 0, entsize, num, big_endian)?
+            .map(Cow::into_owned)
+            .collect())
+    }
+
+    /// The first section header, only read when the file header says it
+    /// holds one of the counts, see [`x64::initial_section`]
+    fn initial_section(&mut self) -> Option<Elf64_Shdr> {
+        let header = self.header;
+        let escaped = header.e_phnum == x64::PN_XNUM
+            || header.e_shnum == 0
+            || header.e_shstrndx == SHN::XINDEX;
+        if !escaped || header.e_shoff == 0 {
+            return None;
+        }
+        self.table(header.e_shoff, header.e_shentsize as u64, 1)
+            .ok()?
+            .pop()
+    }
+
+    /// The program header table
+    pub fn program_headers(&mut self) -> Result<Vec<Elf64_Phdr>> {
+        let header = self.header;
+        let phnum = header.phnum_with(self.initial_section().as_ref());
+        self.table(header.e_phoff, header.e_phentsize as u64, phnum)
+    }
+
+    /// The section header table
+    pub fn sections(&mut self) -> Result<Vec<Elf64_Shdr>> {
+        let header = self.header;
+        let shnum = header.shnum_with(self.initial_section().as_ref());
+        self.table(header.e_shoff, header.e_shentsize as u64, shnum)
+    }
+
+    /// The bytes of a section, empty for SHT_NOBITS sections
+    pub fn section_data(&mut self, shdr: &Elf64_Shdr) -> Result<Vec<u8>> {
+        if shdr.sh_type == SHT::NOBITS {
+            return Ok(Vec::new());
+        }
+        self.read_at(shdr.sh_offset, shdr.sh_size)
+    }
+
+    /// The bytes of the file image of a segment
+    pub fn segment_data(&mut self, phdr: &Elf64_Phdr) -> Result<Vec<u8>> {
+        self.read_at(phdr.p_offset, phdr.p_filesz)
+    }
+
+    /// The bytes of the section name string table, wrap them in a [`StrTab`]
+    ///
+    /// Empty if the file has no section name string table.
+    pub fn section_names(&mut self) -> Result<Vec<u8>> {
+        let initial = self.initial_section();
+        let index = self.header.shstrndx_with(initial.as_ref());
+        if index == SHN::UNDEF as u32 {
+            return Ok(Vec::new());
+        }
+        let shdr = self
+            .sections()?
+            .get(index as usize)
+            .copied()
+            .ok_or(Error::InvalidSectionIndex(index))?;
+        self.section_data(&shdr)
+    }
+
+    /// Returns the first section with the given name
+    pub fn section_by_name(&mut self, name: &str) -> Result<Option<Elf64_Shdr>> {
+        let names = self.section_names()?;
+        let names = StrTab::new(&names);
+        Ok(self
+            .sections()?
+            .into_iter()
+            .find(|shdr| shdr.name(&names).is_ok_and(|found| found == name)))
+    }
+}
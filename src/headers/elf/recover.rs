@@ -0,0 +1,656 @@
+//! Rebuild a damaged or missing header table from the other one
+//!
+//! The loader only reads the program headers, so `sstrip` drops the
+//! section header table and some samples corrupt `e_shoff` to get in the
+//! way of the analysis tools, while others keep valid sections behind
+//! broken program headers. [`from_bytes`] returns a copy of the file with
+//! the missing table rebuilt and appended to it, every table reads it as
+//! usual:
+//! * the sections are the ones of the PT_INTERP, PT_DYNAMIC, PT_NOTE,
+//!   PT_TLS and PT_GNU_EH_FRAME segments, the tables the dynamic entries
+//!   point to and a `load<N>` section per executable PT_LOAD segment, to
+//!   be disassembled
+//! * the segments are a PT_LOAD per run of allocated sections with the
+//!   same permissions, and the PT_INTERP, PT_DYNAMIC, PT_NOTE, PT_TLS and
+//!   PT_GNU_EH_FRAME of the sections they describe
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::{read_u32, ToBytes};
+use core::fmt;
+
+use super::data::Data;
+use super::dynamic::{self, Elf64_Dyn, DT};
+use super::machine::Machine;
+use super::program::{self, Elf64_Phdr, PF, PT};
+use super::relocations::{Elf64_Rel, Elf64_Rela};
+use super::section::{self, Elf64_Shdr, SHF, SHT};
+use super::symbols::Elf64_Sym;
+use super::symver;
+use super::types::Type;
+use super::x64::x64;
+
+/// Size of the pages the PT_LOAD segments are aligned on
+const PAGE_SIZE: u64 = 0x1000;
+
+/// A copy of a file with a rebuilt header table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recovered {
+    /// the file with the rebuilt table appended and the file header
+    /// pointing to it
+    pub data: Vec<u8>,
+    /// number of section headers rebuilt, 0 if the file's ones are kept
+    pub sections: usize,
+    /// number of program headers rebuilt, 0 if the file's ones are kept
+    pub segments: usize,
+}
+
+impl fmt::Display for Recovered {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.sections != 0 {
+            write!(
+                f,
+                "rebuilt {} section headers from the program headers",
+                self.sections
+            )?;
+        }
+        if self.segments != 0 {
+            write!(
+                f,
+                "rebuilt {} program headers from the section headers",
+                self.segments
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A rebuilt section, linked to the other ones by name
+struct Synthetic {
+    name: String,
+    shdr: Elf64_Shdr,
+    link: Option<&'static str>,
+    info: Option<&'static str>,
+}
+
+impl Synthetic {
+    fn new(name: &str, sh_type: u32, sh_flags: u64) -> Self {
+        Synthetic {
+            name: name.to_string(),
+            shdr: Elf64_Shdr {
+                sh_type,
+                sh_flags,
+                ..Elf64_Shdr::default()
+            },
+            link: None,
+            info: None,
+        }
+    }
+
+    fn place(&mut self, offset: u64, vaddr: u64, size: u64, align: u64) {
+        self.shdr.sh_offset = offset;
+        self.shdr.sh_addr = vaddr;
+        self.shdr.sh_size = size;
+        self.shdr.sh_addralign = align;
+    }
+}
+
+/// The file offset of a virtual address and the bytes after it in its segment
+fn locate<'a>(data: &'a [u8], phdrs: &[Elf64_Phdr], vaddr: u64) -> Option<(u64, &'a [u8])> {
+    let phdr = phdrs.iter().find(|phdr| {
+        phdr.p_type == PT::LOAD && vaddr >= phdr.p_vaddr && vaddr - phdr.p_vaddr < phdr.p_filesz
+    })?;
+    let offset = phdr.p_offset.checked_add(vaddr - phdr.p_vaddr)?;
+    let end = phdr.p_offset.saturating_add(phdr.p_filesz);
+    let bytes = data.get(usize::try_from(offset).ok()?..usize::try_from(end).ok()?)?;
+    Some((offset, bytes))
+}
+
+/// Number of `.dynsym` entries, from the hash tables
+fn symbol_count(
+    data: &[u8],
+    phdrs: &[Elf64_Phdr],
+    dynamic: &dynamic::Dynamic,
+    big_endian: bool,
+) -> Option<u64> {
+    if let Some((_, table)) = dynamic
+        .get(DT::HASH)
+        .and_then(|vaddr| locate(data, phdrs, vaddr))
+    {
+        return read_u32(table, 4, big_endian).map(u64::from);
+    }
+    let (_, table) = locate(data, phdrs, dynamic.get(DT::GNU_HASH)?)?;
+    let nbuckets = read_u32(table, 0, big_endian)? as usize;
+    let symoffset = read_u32(table, 4, big_endian)?;
+    let bloom_size = read_u32(table, 8, big_endian)? as usize;
+    let buckets = bloom_size.checked_mul(8)?.checked_add(16)?;
+    let chains = buckets.checked_add(nbuckets.checked_mul(4)?)?;
+    let last = (0..nbuckets)
+        .filter_map(|bucket| read_u32(table, buckets + bucket * 4, big_endian))
+        .max()
+        .unwrap_or(0);
+    if last < symoffset {
+        return Some(symoffset as u64);
+    }
+    // the chain of the last bucket ends with the last symbol
+    let mut index = last;
+    loop {
+        let position = (index - symoffset) as usize;
+        let hash = read_u32(
+            table,
+            chains.checked_add(position.checked_mul(4)?)?,
+            big_endian,
+        )?;
+        if hash & 1 != 0 {
+            return Some(index as u64 + 1);
+        }
+        index = index.checked_add(1)?;
+    }
+}
+
+/// Size of the version definitions or needs at the start of `bytes`
+fn versions_size(bytes: &[u8], tag: i64, count: u64, big_endian: bool) -> Option<u64> {
+    let count = u32::try_from(count).ok()?;
+    let end = if tag == DT::VERDEF {
+        symver::verdefs(bytes, count, big_endian)
+            .ok()?
+            .iter()
+            .flat_map(|verdef| {
+                let aux = verdef.aux.iter().map(|(offset, _)| offset + 8);
+                aux.chain([verdef.offset + 20])
+            })
+            .max()
+    } else {
+        symver::verneeds(bytes, count, big_endian)
+            .ok()?
+            .iter()
+            .flat_map(|verneed| {
+                let aux = verneed.aux.iter().map(|(offset, _)| offset + 16);
+                aux.chain([verneed.offset + 16])
+            })
+            .max()
+    };
+    end.map(|end| end as u64)
+}
+
+/// Rebuild the sections described by the program headers and the dynamic section
+fn sections_of(data: &[u8], header: &x64, phdrs: &[Elf64_Phdr]) -> Vec<Synthetic> {
+    let big_endian = Data::is_big_endian(&header.e_ident);
+    let mut sections = Vec::new();
+    for (index, phdr) in phdrs.iter().enumerate() {
+        let write = match phdr.p_flags & PF::W {
+            0 => 0,
+            _ => SHF::WRITE,
+        };
+        let (offset, vaddr, size) = (phdr.p_offset, phdr.p_vaddr, phdr.p_filesz);
+        match phdr.p_type {
+            PT::LOAD if phdr.p_flags & PF::X != 0 && size != 0 => {
+                let flags = SHF::ALLOC | SHF::EXECINSTR | write;
+                let mut section = Synthetic::new(&format!("load{}", index), SHT::PROGBITS, flags);
+                section.place(offset, vaddr, size, phdr.p_align);
+                sections.push(section);
+            }
+            PT::INTERP => {
+                let mut section = Synthetic::new(".interp", SHT::PROGBITS, SHF::ALLOC);
+                section.place(offset, vaddr, size, 1);
+                sections.push(section);
+            }
+            PT::NOTE => {
+                let mut section = Synthetic::new(".note", SHT::NOTE, SHF::ALLOC);
+                section.place(offset, vaddr, size, phdr.p_align);
+                sections.push(section);
+            }
+            PT::GNU_EH_FRAME => {
+                let mut section = Synthetic::new(".eh_frame_hdr", SHT::PROGBITS, SHF::ALLOC);
+                section.place(offset, vaddr, size, 4);
+                sections.push(section);
+            }
+            PT::DYNAMIC => {
+                let mut section = Synthetic::new(".dynamic", SHT::DYNAMIC, SHF::ALLOC | write);
+                section.place(offset, vaddr, size, 8);
+                section.shdr.sh_entsize = Elf64_Dyn::SIZE as u64;
+                section.link = Some(".dynstr");
+                sections.push(section);
+            }
+            PT::TLS => {
+                let flags = SHF::ALLOC | SHF::WRITE | SHF::TLS;
+                if size != 0 {
+                    let mut section = Synthetic::new(".tdata", SHT::PROGBITS, flags);
+                    section.place(offset, vaddr, size, phdr.p_align);
+                    sections.push(section);
+                }
+                if phdr.p_memsz > size {
+                    let mut section = Synthetic::new(".tbss", SHT::NOBITS, flags);
+                    section.place(
+                        offset.saturating_add(size),
+                        vaddr.saturating_add(size),
+                        phdr.p_memsz - size,
+                        phdr.p_align,
+                    );
+                    sections.push(section);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let dynamic = match dynamic::from_bytes(data, header) {
+        Ok(Some(dynamic)) => dynamic,
+        _ => return sections,
+    };
+    let nsyms = symbol_count(data, phdrs, &dynamic, big_endian);
+    let (rel_plt, rel_type, rel_size) = match dynamic.get(DT::PLTREL) {
+        Some(value) if value as i64 == DT::REL => (".rel.plt", SHT::REL, Elf64_Rel::SIZE as u64),
+        _ => (".rela.plt", SHT::RELA, Elf64_Rela::SIZE as u64),
+    };
+    let hash = dynamic
+        .get(DT::HASH)
+        .and_then(|vaddr| locate(data, phdrs, vaddr))
+        .and_then(|(_, table)| {
+            let nbucket = read_u32(table, 0, big_endian)? as u64;
+            Some(
+                nbucket
+                    .saturating_add(nsyms?)
+                    .saturating_add(2)
+                    .saturating_mul(4),
+            )
+        });
+    let gnu_hash = dynamic
+        .get(DT::GNU_HASH)
+        .and_then(|vaddr| locate(data, phdrs, vaddr))
+        .and_then(|(_, table)| {
+            let nbuckets = read_u32(table, 0, big_endian)? as u64;
+            let symoffset = read_u32(table, 4, big_endian)? as u64;
+            let bloom_size = read_u32(table, 8, big_endian)? as u64;
+            let chains = nsyms?.saturating_sub(symoffset);
+            Some(16 + bloom_size * 8 + (nbuckets + chains) * 4)
+        });
+    let versions = |tag: i64, num: i64| {
+        let (_, bytes) = locate(data, phdrs, dynamic.get(tag)?)?;
+        versions_size(bytes, tag, dynamic.get(num)?, big_endian)
+    };
+    // the GOT of x86_64 has 3 reserved slots then one per PLT relocation
+    let got = match Machine::from_u16(header.e_machine) {
+        Machine::X86_64 => dynamic
+            .get(DT::PLTRELSZ)
+            .map(|size| (3 + size / rel_size) * 8),
+        _ => None,
+    };
+
+    // the tables pointed to by the dynamic entries and their size
+    let symbol = Elf64_Sym::SIZE as u64;
+    let rela = Elf64_Rela::SIZE as u64;
+    let rel = Elf64_Rel::SIZE as u64;
+    let writable = SHF::ALLOC | SHF::WRITE;
+    let tables = [
+        (
+            ".dynsym",
+            SHT::DYNSYM,
+            SHF::ALLOC,
+            DT::SYMTAB,
+            nsyms.map(|n| n.saturating_mul(symbol)),
+            symbol,
+        ),
+        (
+            ".dynstr",
+            SHT::STRTAB,
+            SHF::ALLOC,
+            DT::STRTAB,
+            dynamic.get(DT::STRSZ),
+            0,
+        ),
+        (".hash", SHT::HASH, SHF::ALLOC, DT::HASH, hash, 4),
+        (
+            ".gnu.hash",
+            SHT::GNU_HASH,
+            SHF::ALLOC,
+            DT::GNU_HASH,
+            gnu_hash,
+            0,
+        ),
+        (
+            ".gnu.version",
+            SHT::GNU_VERSYM,
+            SHF::ALLOC,
+            DT::VERSYM,
+            nsyms.map(|n| n.saturating_mul(2)),
+            2,
+        ),
+        (
+            ".gnu.version_d",
+            SHT::GNU_VERDEF,
+            SHF::ALLOC,
+            DT::VERDEF,
+            versions(DT::VERDEF, DT::VERDEFNUM),
+            0,
+        ),
+        (
+            ".gnu.version_r",
+            SHT::GNU_VERNEED,
+            SHF::ALLOC,
+            DT::VERNEED,
+            versions(DT::VERNEED, DT::VERNEEDNUM),
+            0,
+        ),
+        (
+            ".rela.dyn",
+            SHT::RELA,
+            SHF::ALLOC,
+            DT::RELA,
+            dynamic.get(DT::RELASZ),
+            rela,
+        ),
+        (
+            ".rel.dyn",
+            SHT::REL,
+            SHF::ALLOC,
+            DT::REL,
+            dynamic.get(DT::RELSZ),
+            rel,
+        ),
+        (
+            ".relr.dyn",
+            SHT::RELR,
+            SHF::ALLOC,
+            DT::RELR,
+            dynamic.get(DT::RELRSZ),
+            8,
+        ),
+        (
+            rel_plt,
+            rel_type,
+            SHF::ALLOC | SHF::INFO_LINK,
+            DT::JMPREL,
+            dynamic.get(DT::PLTRELSZ),
+            rel_size,
+        ),
+        (
+            ".init_array",
+            SHT::INIT_ARRAY,
+            writable,
+            DT::INIT_ARRAY,
+            dynamic.get(DT::INIT_ARRAYSZ),
+            8,
+        ),
+        (
+            ".fini_array",
+            SHT::FINI_ARRAY,
+            writable,
+            DT::FINI_ARRAY,
+            dynamic.get(DT::FINI_ARRAYSZ),
+            8,
+        ),
+        (
+            ".preinit_array",
+            SHT::PREINIT_ARRAY,
+            writable,
+            DT::PREINIT_ARRAY,
+            dynamic.get(DT::PREINIT_ARRAYSZ),
+            8,
+        ),
+        (".got.plt", SHT::PROGBITS, writable, DT::PLTGOT, got, 8),
+    ];
+    for (name, sh_type, flags, tag, size, entsize) in tables {
+        let (vaddr, size) = match (dynamic.get(tag), size) {
+            (Some(vaddr), Some(size)) if size != 0 => (vaddr, size),
+            _ => continue,
+        };
+        let offset = match locate(data, phdrs, vaddr) {
+            Some((offset, _)) => offset,
+            None => continue,
+        };
+        let align = match sh_type {
+            SHT::STRTAB => 1,
+            SHT::GNU_VERSYM => 2,
+            _ => 8,
+        };
+        let mut section = Synthetic::new(name, sh_type, flags);
+        section.place(offset, vaddr, size, align);
+        section.shdr.sh_entsize = entsize;
+        match sh_type {
+            SHT::DYNSYM => section.shdr.sh_info = 1,
+            SHT::GNU_VERDEF => {
+                section.shdr.sh_info = dynamic.get(DT::VERDEFNUM).unwrap_or(0) as u32
+            }
+            SHT::GNU_VERNEED => {
+                section.shdr.sh_info = dynamic.get(DT::VERNEEDNUM).unwrap_or(0) as u32
+            }
+            _ => {}
+        }
+        section.link = match sh_type {
+            SHT::DYNSYM | SHT::GNU_VERDEF | SHT::GNU_VERNEED => Some(".dynstr"),
+            SHT::HASH | SHT::GNU_HASH | SHT::GNU_VERSYM | SHT::RELA | SHT::REL => Some(".dynsym"),
+            _ => None,
+        };
+        if tag == DT::JMPREL {
+            section.info = Some(".got.plt");
+        }
+        sections.push(section);
+    }
+    sections
+}
+
+/// Rebuild the segments of the allocated sections
+fn segments_of(data: &[u8], header: &x64) -> Vec<Elf64_Phdr> {
+    let names = section::names(data, header).unwrap_or_default();
+    let mut shdrs: Vec<(ByteStr, Elf64_Shdr)> = match section::from_bytes(data, header) {
+        Ok(shdrs) => shdrs
+            .filter(|shdr| shdr.sh_flags & SHF::ALLOC != 0 && shdr.sh_type != SHT::NULL)
+            .map(|shdr| (shdr.name(&names).unwrap_or_default(), *shdr))
+            .collect(),
+        Err(_) => return Vec::new(),
+    };
+    shdrs.sort_by_key(|(_, shdr)| shdr.sh_addr);
+    let flags = |shdr: &Elf64_Shdr| {
+        let mut flags = PF::R;
+        if shdr.sh_flags & SHF::WRITE != 0 {
+            flags |= PF::W;
+        }
+        if shdr.sh_flags & SHF::EXECINSTR != 0 {
+            flags |= PF::X;
+        }
+        flags
+    };
+    let segment = |p_type: u32, p_flags: u32, shdr: &Elf64_Shdr| Elf64_Phdr {
+        p_type,
+        p_flags,
+        p_offset: shdr.sh_offset,
+        p_vaddr: shdr.sh_addr,
+        p_paddr: shdr.sh_addr,
+        p_filesz: match shdr.sh_type {
+            SHT::NOBITS => 0,
+            _ => shdr.sh_size,
+        },
+        p_memsz: shdr.sh_size,
+        p_align: shdr.sh_addralign,
+    };
+
+    let mut phdrs = Vec::new();
+    if let Some((_, interp)) = shdrs.iter().find(|(name, _)| *name == ".interp") {
+        phdrs.push(segment(PT::INTERP, PF::R, interp));
+    }
+    // a new PT_LOAD starts when the permissions or the distance between
+    // the file offset and the address change
+    let mut load: Option<Elf64_Phdr> = None;
+    let loaded = shdrs
+        .iter()
+        .filter(|(_, shdr)| !(shdr.sh_type == SHT::NOBITS && shdr.sh_flags & SHF::TLS != 0));
+    for (_, shdr) in loaded {
+        let nobits = shdr.sh_type == SHT::NOBITS;
+        let extends = load.as_ref().is_some_and(|phdr| {
+            let file_end = phdr.p_vaddr.saturating_add(phdr.p_filesz);
+            let same_delta = phdr.p_vaddr.wrapping_sub(phdr.p_offset)
+                == shdr.sh_addr.wrapping_sub(shdr.sh_offset);
+            let after_bss = phdr.p_memsz > phdr.p_filesz && !nobits;
+            phdr.p_flags == flags(shdr)
+                && (nobits || same_delta)
+                && !after_bss
+                && shdr.sh_addr >= file_end
+        });
+        match load.as_mut() {
+            Some(phdr) if extends => {
+                let end = shdr.sh_addr.saturating_add(shdr.sh_size);
+                if !nobits {
+                    phdr.p_filesz = end - phdr.p_vaddr;
+                }
+                phdr.p_memsz = phdr.p_memsz.max(end - phdr.p_vaddr);
+            }
+            _ => {
+                phdrs.extend(load.take());
+                load = Some(segment(PT::LOAD, flags(shdr), shdr));
+            }
+        }
+    }
+    phdrs.extend(load);
+    // the linkers map the file header in the first PT_LOAD
+    if let Some(first) = phdrs.iter_mut().find(|phdr| phdr.p_type == PT::LOAD) {
+        let base = first.p_vaddr.wrapping_sub(first.p_offset);
+        if first.p_vaddr >= first.p_offset && base % PAGE_SIZE == 0 {
+            first.p_filesz = first.p_filesz.saturating_add(first.p_offset);
+            first.p_memsz = first.p_memsz.saturating_add(first.p_offset);
+            first.p_offset = 0;
+            first.p_vaddr = base;
+            first.p_paddr = base;
+        }
+    }
+    for phdr in phdrs.iter_mut().filter(|phdr| phdr.p_type == PT::LOAD) {
+        if phdr.p_vaddr % PAGE_SIZE == phdr.p_offset % PAGE_SIZE {
+            phdr.p_align = PAGE_SIZE;
+        }
+    }
+
+    for (_, shdr) in &shdrs {
+        match shdr.sh_type {
+            SHT::DYNAMIC => phdrs.push(segment(PT::DYNAMIC, flags(shdr), shdr)),
+            SHT::NOTE => phdrs.push(segment(PT::NOTE, PF::R, shdr)),
+            _ => {}
+        }
+    }
+    let tls: Vec<&Elf64_Shdr> = shdrs
+        .iter()
+        .map(|(_, shdr)| shdr)
+        .filter(|shdr| shdr.sh_flags & SHF::TLS != 0)
+        .collect();
+    if let (Some(first), Some(last)) = (tls.first(), tls.last()) {
+        let mut phdr = segment(PT::TLS, PF::R, first);
+        let end = last.sh_addr.saturating_add(last.sh_size);
+        phdr.p_memsz = end.saturating_sub(first.sh_addr);
+        phdr.p_filesz = tls
+            .iter()
+            .filter(|shdr| shdr.sh_type != SHT::NOBITS)
+            .map(|shdr| shdr.sh_addr.saturating_add(shdr.sh_size) - first.sh_addr)
+            .max()
+            .unwrap_or(0);
+        phdr.p_align = tls.iter().map(|shdr| shdr.sh_addralign).max().unwrap_or(1);
+        phdrs.push(phdr);
+    }
+    if let Some((_, eh_frame_hdr)) = shdrs.iter().find(|(name, _)| *name == ".eh_frame_hdr") {
+        phdrs.push(segment(PT::GNU_EH_FRAME, PF::R, eh_frame_hdr));
+    }
+    phdrs
+}
+
+/// Pad the file to the alignment of the table appended to it
+fn pad(data: &mut Vec<u8>, align: usize) {
+    data.resize(data.len().next_multiple_of(align), 0);
+}
+
+/// Rebuild the table of the file which can't be read from the other one
+///
+/// Only the executables and shared objects are rebuilt: the section
+/// headers if the table is missing or can't be parsed with its names, else
+/// the program headers if they are missing or can't be parsed. Returns
+/// None if both tables are valid or if nothing could be rebuilt.
+pub fn from_bytes(data: &[u8], header: &x64) -> Option<Recovered> {
+    if !matches!(Type::from(header.e_type), Type::Exec | Type::Dyn) {
+        return None;
+    }
+    let big_endian = Data::is_big_endian(&header.e_ident);
+    let phdrs: Option<Vec<Elf64_Phdr>> = program::from_bytes(data, header)
+        .ok()
+        .map(|phdrs| phdrs.map(|phdr| *phdr).collect());
+    let sections_valid = section::from_bytes(data, header).is_ok()
+        && section::names(data, header).is_ok()
+        && header.shnum(data) != 0;
+    let mut out = data.to_vec();
+    let mut header = *header;
+
+    match phdrs {
+        Some(phdrs) if !phdrs.is_empty() && !sections_valid => {
+            let mut sections = sections_of(data, &header, &phdrs);
+            if sections.is_empty() {
+                return None;
+            }
+            sections.sort_by_key(|section| section.shdr.sh_addr);
+            let mut initial = Elf64_Shdr::default();
+            if header.e_phnum == x64::PN_XNUM {
+                initial.sh_info = phdrs.len() as u32;
+            }
+            let mut shdrs = vec![initial];
+            let mut names = vec![0u8];
+            let index = |name: &str| {
+                sections
+                    .iter()
+                    .position(|section| section.name == name)
+                    .map_or(0, |index| index as u32 + 1)
+            };
+            for section in &sections {
+                let mut shdr = section.shdr;
+                shdr.sh_name = names.len() as u32;
+                names.extend_from_slice(section.name.as_bytes());
+                names.push(0);
+                shdr.sh_link = section.link.map_or(0, index);
+                if let Some(info) = section.info {
+                    shdr.sh_info = index(info);
+                }
+                shdrs.push(shdr);
+            }
+            pad(&mut out, 8);
+            shdrs.push(Elf64_Shdr {
+                sh_name: names.len() as u32,
+                sh_type: SHT::STRTAB,
+                sh_offset: out.len() as u64,
+                sh_size: (names.len() + ".shstrtab\0".len()) as u64,
+                sh_addralign: 1,
+                ..Elf64_Shdr::default()
+            });
+            names.extend_from_slice(b".shstrtab\0");
+            out.extend_from_slice(&names);
+            pad(&mut out, 8);
+            header.e_shoff = out.len() as u64;
+            header.e_shentsize = Elf64_Shdr::SIZE as u16;
+            header.e_shnum = shdrs.len() as u16;
+            header.e_shstrndx = (shdrs.len() - 1) as u16;
+            for shdr in &shdrs {
+                out.extend_from_slice(&shdr.to_bytes(big_endian));
+            }
+            let count = shdrs.len();
+            out[..x64::SIZE].copy_from_slice(&header.to_bytes(big_endian));
+            Some(Recovered {
+                data: out,
+                sections: count,
+                segments: 0,
+            })
+        }
+        Some(phdrs) if !phdrs.is_empty() => None,
+        _ if sections_valid => {
+            let phdrs = segments_of(data, &header);
+            if phdrs.is_empty() {
+                return None;
+            }
+            pad(&mut out, 8);
+            header.e_phoff = out.len() as u64;
+            header.e_phentsize = Elf64_Phdr::SIZE as u16;
+            header.e_phnum = phdrs.len() as u16;
+            for phdr in &phdrs {
+                out.extend_from_slice(&phdr.to_bytes(big_endian));
+            }
+            out[..x64::SIZE].copy_from_slice(&header.to_bytes(big_endian));
+            Some(Recovered {
+                data: out,
+                sections: 0,
+                segments: phdrs.len(),
+            })
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,306 @@
+//! Names of the OS and processor specific section and segment types
+//!
+//! The values between SHT_LOOS and SHT_HIPROC, and between PT_LOOS and
+//! PT_HIPROC, mean different things depending on the OS/ABI and the
+//! machine of the file: 0x70000001 is SHT_ARM_EXIDX on ARM and
+//! SHT_X86_64_UNWIND on x86-64. A `Registry` looks the names up in sets
+//! scoped to an OS/ABI or a machine, the defaults cover GNU, Solaris,
+//! OpenBSD, ARM, AArch64, MIPS, RISC-V and x86-64 and more can be
+//! registered. The names are printed like readelf, without the `SHT_` and
+//! `PT_` prefixes.
+//!
+//! Sources:
+//! * <https://github.com/bminor/binutils-gdb/blob/master/binutils/readelf.c>
+//! * <https://github.com/bminor/binutils-gdb/blob/master/include/elf/common.h>
+use crate::alloc_prelude::*;
+use alloc::borrow::Cow;
+
+use super::identification::Indent;
+use super::machine::Machine;
+use super::osabit::OsAbi;
+use super::program::{Elf64_Phdr, PT};
+use super::section::{SectionType, SHT};
+use super::x64::x64;
+
+/// The files a set of names applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    /// every file
+    Any,
+    /// the files with this `EI_OSABI`
+    OsAbi(OsAbi),
+    /// the files with this `e_machine`
+    Machine(Machine),
+}
+
+impl Scope {
+    /// Whether the file described by `header` is in the scope
+    pub fn matches(&self, header: &x64) -> bool {
+        match *self {
+            Scope::Any => true,
+            Scope::OsAbi(osabi) => OsAbi::from(header.e_ident[Indent::OSABIT]) == osabi,
+            Scope::Machine(machine) => Machine::from_u16(header.e_machine) == machine,
+        }
+    }
+}
+
+/// Names of section and segment types for the files of a scope
+#[derive(Debug, Clone, Copy)]
+pub struct Names {
+    /// the files the names apply to
+    pub scope: Scope,
+    /// `sh_type` values and their names
+    pub sections: &'static [(u32, &'static str)],
+    /// `p_type` values and their names
+    pub segments: &'static [(u32, &'static str)],
+}
+
+impl Names {
+    fn section(&self, sh_type: u32) -> Option<&'static str> {
+        lookup(self.sections, sh_type)
+    }
+
+    fn segment(&self, p_type: u32) -> Option<&'static str> {
+        lookup(self.segments, p_type)
+    }
+}
+
+fn lookup(names: &[(u32, &'static str)], value: u32) -> Option<&'static str> {
+    names
+        .iter()
+        .find(|(known, _)| *known == value)
+        .map(|(_, name)| *name)
+}
+
+/// Types of the GNU and LLVM toolchains, used whatever the OS/ABI
+pub const GNU: Names = Names {
+    scope: Scope::Any,
+    sections: &[
+        (0x6000_0001, "ANDROID_REL"),
+        (0x6000_0002, "ANDROID_RELA"),
+        (0x6fff_4700, "GNU_INCREMENTAL_INPUTS"),
+        (0x6fff_4c00, "LLVM_ODRTAB"),
+        (0x6fff_4c01, "LLVM_LINKER_OPTIONS"),
+        (0x6fff_4c03, "LLVM_ADDRSIG"),
+        (0x6fff_4c04, "LLVM_DEPENDENT_LIBRARIES"),
+        (0x6fff_4c05, "LLVM_SYMPART"),
+        (0x6fff_4c06, "LLVM_PART_EHDR"),
+        (0x6fff_4c07, "LLVM_PART_PHDR"),
+        (0x6fff_4c08, "LLVM_BB_ADDR_MAP_V0"),
+        (0x6fff_4c09, "LLVM_CALL_GRAPH_PROFILE"),
+        (0x6fff_4c0a, "LLVM_BB_ADDR_MAP"),
+        (0x6fff_4c0b, "LLVM_OFFLOADING"),
+        (0x6fff_4c0c, "LLVM_LTO"),
+        (0x6fff_ff00, "ANDROID_RELR"),
+        (0x6fff_fffa, "SUNW_MOVE"),
+        (0x6fff_fffb, "SUNW_COMDAT"),
+        (0x6fff_fffc, "SUNW_SYMINFO"),
+    ],
+    segments: &[(0x6fff_fffa, "SUNWBSS"), (0x6fff_fffb, "SUNWSTACK")],
+};
+
+/// Types of Solaris, some of them reuse the values of GNU types
+pub const SOLARIS: Names = Names {
+    scope: Scope::OsAbi(OsAbi::Solaris),
+    sections: &[
+        (0x6fff_ffef, "SUNW_CAPCHAIN"),
+        (0x6fff_fff0, "SUNW_CAPINFO"),
+        (0x6fff_fff1, "SUNW_SYMSORT"),
+        (0x6fff_fff2, "SUNW_TLSSORT"),
+        (0x6fff_fff3, "SUNW_LDYNSYM"),
+        (0x6fff_fff4, "SUNW_DOF"),
+        (0x6fff_fff5, "SUNW_CAP"),
+        (0x6fff_fff6, "SUNW_SIGNATURE"),
+        (0x6fff_fff7, "SUNW_ANNOTATE"),
+        (0x6fff_fff8, "SUNW_DEBUGSTR"),
+        (0x6fff_fff9, "SUNW_DEBUG"),
+        (0x6fff_fffd, "SUNW_VERDEF"),
+        (0x6fff_fffe, "SUNW_VERNEED"),
+        (0x6fff_ffff, "SUNW_VERSYM"),
+    ],
+    segments: &[(0x6464_e550, "SUNW_UNWIND")],
+};
+
+/// Segment types of OpenBSD
+pub const OPENBSD: Names = Names {
+    scope: Scope::OsAbi(OsAbi::OpenBsd),
+    sections: &[],
+    segments: &[
+        (0x65a3_dbe6, "OPENBSD_RANDOMIZE"),
+        (0x65a3_dbe7, "OPENBSD_WXNEEDED"),
+        (0x65a3_dbe8, "OPENBSD_NOBTCFI"),
+        (0x65a4_1be6, "OPENBSD_BOOTDATA"),
+    ],
+};
+
+/// Types of 32 bit ARM
+pub const ARM: Names = Names {
+    scope: Scope::Machine(Machine::Arm),
+    sections: &[
+        (0x7000_0001, "ARM_EXIDX"),
+        (0x7000_0002, "ARM_PREEMPTMAP"),
+        (0x7000_0003, "ARM_ATTRIBUTES"),
+        (0x7000_0004, "ARM_DEBUGOVERLAY"),
+        (0x7000_0005, "ARM_OVERLAYSECTION"),
+    ],
+    segments: &[(0x7000_0001, "EXIDX")],
+};
+
+/// Types of AArch64
+pub const AARCH64: Names = Names {
+    scope: Scope::Machine(Machine::Aarch64),
+    sections: &[
+        (0x7000_0003, "AARCH64_ATTRIBUTES"),
+        (0x7000_0004, "AARCH64_AUTH_RELR"),
+        (0x7000_0007, "AARCH64_MEMTAG_GLOBALS_STATIC"),
+        (0x7000_0008, "AARCH64_MEMTAG_GLOBALS_DYNAMIC"),
+    ],
+    segments: &[
+        (0x7000_0000, "AARCH64_ARCHEXT"),
+        (0x7000_0002, "AARCH64_MEMTAG_MTE"),
+    ],
+};
+
+/// Types of MIPS
+pub const MIPS: Names = Names {
+    scope: Scope::Machine(Machine::Mips),
+    sections: &[
+        (0x7000_0000, "MIPS_LIBLIST"),
+        (0x7000_0001, "MIPS_MSYM"),
+        (0x7000_0002, "MIPS_CONFLICT"),
+        (0x7000_0003, "MIPS_GPTAB"),
+        (0x7000_0004, "MIPS_UCODE"),
+        (0x7000_0005, "MIPS_DEBUG"),
+        (0x7000_0006, "MIPS_REGINFO"),
+        (0x7000_0007, "MIPS_PACKAGE"),
+        (0x7000_0008, "MIPS_PACKSYM"),
+        (0x7000_0009, "MIPS_RELD"),
+        (0x7000_000b, "MIPS_IFACE"),
+        (0x7000_000c, "MIPS_CONTENT"),
+        (0x7000_000d, "MIPS_OPTIONS"),
+        (0x7000_0010, "MIPS_SHDR"),
+        (0x7000_0011, "MIPS_FDESC"),
+        (0x7000_0012, "MIPS_EXTSYM"),
+        (0x7000_0013, "MIPS_DENSE"),
+        (0x7000_0014, "MIPS_PDESC"),
+        (0x7000_0015, "MIPS_LOCSYM"),
+        (0x7000_0016, "MIPS_AUXSYM"),
+        (0x7000_0017, "MIPS_OPTSYM"),
+        (0x7000_0018, "MIPS_LOCSTR"),
+        (0x7000_0019, "MIPS_LINE"),
+        (0x7000_001a, "MIPS_RFDESC"),
+        (0x7000_001b, "MIPS_DELTASYM"),
+        (0x7000_001c, "MIPS_DELTAINST"),
+        (0x7000_001d, "MIPS_DELTACLASS"),
+        (0x7000_001e, "MIPS_DWARF"),
+        (0x7000_001f, "MIPS_DELTADECL"),
+        (0x7000_0020, "MIPS_SYMBOL_LIB"),
+        (0x7000_0021, "MIPS_EVENTS"),
+        (0x7000_0022, "MIPS_TRANSLATE"),
+        (0x7000_0023, "MIPS_PIXIE"),
+        (0x7000_0024, "MIPS_XLATE"),
+        (0x7000_0025, "MIPS_XLATE_DEBUG"),
+        (0x7000_0026, "MIPS_WHIRL"),
+        (0x7000_0027, "MIPS_EH_REGION"),
+        (0x7000_0028, "MIPS_XLATE_OLD"),
+        (0x7000_0029, "MIPS_PDR_EXCEPTION"),
+        (0x7000_002a, "MIPS_ABIFLAGS"),
+        (0x7000_002b, "MIPS_XHASH"),
+    ],
+    segments: &[
+        (0x7000_0000, "REGINFO"),
+        (0x7000_0001, "RTPROC"),
+        (0x7000_0002, "OPTIONS"),
+        (0x7000_0003, "ABIFLAGS"),
+    ],
+};
+
+/// Types of RISC-V
+pub const RISCV: Names = Names {
+    scope: Scope::Machine(Machine::Riscv),
+    sections: &[(0x7000_0003, "RISCV_ATTRIBUTES")],
+    segments: &[(0x7000_0003, "RISCV_ATTRIBUTES")],
+};
+
+/// Types of x86-64
+pub const X86_64: Names = Names {
+    scope: Scope::Machine(Machine::X86_64),
+    sections: &[(0x7000_0001, "X86_64_UNWIND")],
+    segments: &[],
+};
+
+/// The names known by every registry, looked up in this order
+pub const DEFAULTS: &[Names] = &[SOLARIS, OPENBSD, GNU, ARM, AARCH64, MIPS, RISCV, X86_64];
+
+/// Names of the OS and processor specific types, the defaults and the
+/// registered ones
+///
+/// A registered set is looked up before the sets registered earlier and
+/// before the defaults, so it can rename a default type.
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    names: Vec<Names>,
+}
+
+impl Registry {
+    /// A registry with the default names only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add names for the files of their scope
+    pub fn register(&mut self, names: Names) -> &mut Self {
+        self.names.push(names);
+        self
+    }
+
+    fn scoped<'a>(&'a self, header: &'a x64) -> impl Iterator<Item = &'a Names> + 'a {
+        self.names
+            .iter()
+            .rev()
+            .chain(DEFAULTS)
+            .filter(move |names| names.scope.matches(header))
+    }
+
+    /// Name of a section type in the file described by `header`
+    ///
+    /// The standard types and the unnamed ones are named like
+    /// `Elf64_Shdr::type_name`, ex: `LOPROC+1`.
+    pub fn section_type(&self, header: &x64, sh_type: u32) -> Cow<'static, str> {
+        if (SHT::LOOS..=SHT::HIPROC).contains(&sh_type) {
+            if let Some(name) = self.scoped(header).find_map(|names| names.section(sh_type)) {
+                return Cow::Borrowed(name);
+            }
+        }
+        SectionType::from_u32(sh_type).name()
+    }
+
+    /// Name of a segment type in the file described by `header`
+    ///
+    /// The standard types and the unnamed ones are named like
+    /// `Elf64_Phdr::type_name`, ex: `LOPROC+0x1`.
+    pub fn segment_type(&self, header: &x64, p_type: u32) -> Cow<'static, str> {
+        if (PT::LOOS..=PT::HIPROC).contains(&p_type) {
+            if let Some(name) = self.scoped(header).find_map(|names| names.segment(p_type)) {
+                return Cow::Borrowed(name);
+            }
+        }
+        Elf64_Phdr {
+            p_type,
+            ..Default::default()
+        }
+        .type_name()
+    }
+}
+
+/// Name of a section type in the file described by `header`, with the
+/// default names
+pub fn section_type_name(header: &x64, sh_type: u32) -> Cow<'static, str> {
+    Registry::new().section_type(header, sh_type)
+}
+
+/// Name of a segment type in the file described by `header`, with the
+/// default names
+pub fn segment_type_name(header: &x64, p_type: u32) -> Cow<'static, str> {
+    Registry::new().segment_type(header, p_type)
+}
@@ -0,0 +1,692 @@
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::demangle::demangle;
+use crate::utils::impl_swap_bytes;
+use crate::utils::json::{Json, ToJson};
+use crate::utils::read_u64;
+use crate::utils::table::truncate;
+use crate::{Error, Result};
+use alloc::borrow::Cow;
+use core::fmt::{self, Write};
+use core::mem::size_of;
+
+use super::data::Data;
+use super::machine::Machine;
+use super::section::{self, Elf64_Shdr, SHT};
+use super::strtab::CORRUPT;
+use super::symbols::{self, STT};
+use super::table::Table;
+use super::x64::x64;
+
+/// Define the relocation types of the x86_64 architecture
+///
+/// R_X86_64 define possible values for the type stored in `r_info`
+#[allow(non_camel_case_types)]
+pub struct R_X86_64 {}
+impl R_X86_64 {
+    /// No relocation
+    pub const NONE: u32 = 0;
+    /// Direct 64 bit
+    pub const _64: u32 = 1;
+    /// PC relative 32 bit signed
+    pub const PC32: u32 = 2;
+    /// 32 bit GOT entry
+    pub const GOT32: u32 = 3;
+    /// 32 bit PLT address
+    pub const PLT32: u32 = 4;
+    /// Copy symbol at runtime
+    pub const COPY: u32 = 5;
+    /// Create GOT entry
+    pub const GLOB_DAT: u32 = 6;
+    /// Create PLT entry
+    pub const JUMP_SLOT: u32 = 7;
+    /// Adjust by program base
+    pub const RELATIVE: u32 = 8;
+    /// 32 bit signed PC relative offset to GOT
+    pub const GOTPCREL: u32 = 9;
+    /// Direct 32 bit zero extended
+    pub const _32: u32 = 10;
+    /// Direct 32 bit sign extended
+    pub const _32S: u32 = 11;
+    /// Direct 16 bit zero extended
+    pub const _16: u32 = 12;
+    /// 16 bit sign extended pc relative
+    pub const PC16: u32 = 13;
+    /// Direct 8 bit sign extended
+    pub const _8: u32 = 14;
+    /// 8 bit sign extended pc relative
+    pub const PC8: u32 = 15;
+    /// ID of module containing symbol
+    pub const DTPMOD64: u32 = 16;
+    /// Offset in module's TLS block
+    pub const DTPOFF64: u32 = 17;
+    /// Offset in initial TLS block
+    pub const TPOFF64: u32 = 18;
+    /// 32 bit signed PC relative offset to two GOT entries for GD symbol
+    pub const TLSGD: u32 = 19;
+    /// 32 bit signed PC relative offset to two GOT entries for LD symbol
+    pub const TLSLD: u32 = 20;
+    /// Offset in TLS block
+    pub const DTPOFF32: u32 = 21;
+    /// 32 bit signed PC relative offset to GOT entry for IE symbol
+    pub const GOTTPOFF: u32 = 22;
+    /// Offset in initial TLS block
+    pub const TPOFF32: u32 = 23;
+    /// PC relative 64 bit
+    pub const PC64: u32 = 24;
+    /// 64 bit offset to GOT
+    pub const GOTOFF64: u32 = 25;
+    /// 32 bit signed pc relative offset to GOT
+    pub const GOTPC32: u32 = 26;
+    /// 64 bit GOT entry offset
+    pub const GOT64: u32 = 27;
+    /// 64 bit PC relative offset to GOT entry
+    pub const GOTPCREL64: u32 = 28;
+    /// 64 bit PC relative offset to GOT
+    pub const GOTPC64: u32 = 29;
+    /// like GOT64, says PLT entry needed
+    pub const GOTPLT64: u32 = 30;
+    /// 64 bit GOT relative offset to PLT entry
+    pub const PLTOFF64: u32 = 31;
+    /// Size of symbol plus 32 bit addend
+    pub const SIZE32: u32 = 32;
+    /// Size of symbol plus 64 bit addend
+    pub const SIZE64: u32 = 33;
+    /// GOT offset for TLS descriptor
+    pub const GOTPC32_TLSDESC: u32 = 34;
+    /// Marker for call through TLS descriptor
+    pub const TLSDESC_CALL: u32 = 35;
+    /// TLS descriptor
+    pub const TLSDESC: u32 = 36;
+    /// Adjust indirectly by program base
+    pub const IRELATIVE: u32 = 37;
+    /// 64 bit adjust by program base
+    pub const RELATIVE64: u32 = 38;
+    /// Load from 32 bit signed pc relative offset to GOT entry without REX prefix, relaxable
+    pub const GOTPCRELX: u32 = 41;
+    /// Load from 32 bit signed pc relative offset to GOT entry with REX prefix, relaxable
+    pub const REX_GOTPCRELX: u32 = 42;
+}
+
+/// Define the dynamic relocation types of the AArch64 architecture
+///
+/// Only the types found in the dynamic relocation sections are listed.
+#[allow(non_camel_case_types)]
+pub struct R_AARCH64 {}
+impl R_AARCH64 {
+    /// No relocation
+    pub const NONE: u32 = 0;
+    /// Direct 64 bit
+    pub const ABS64: u32 = 257;
+    /// Copy symbol at runtime
+    pub const COPY: u32 = 1024;
+    /// Create GOT entry
+    pub const GLOB_DAT: u32 = 1025;
+    /// Create PLT entry
+    pub const JUMP_SLOT: u32 = 1026;
+    /// Adjust by program base
+    pub const RELATIVE: u32 = 1027;
+    /// Module number of a TLS symbol
+    pub const TLS_DTPMOD: u32 = 1028;
+    /// Offset of a TLS symbol in its module
+    pub const TLS_DTPREL: u32 = 1029;
+    /// Offset of a TLS symbol from the thread pointer
+    pub const TLS_TPREL: u32 = 1030;
+    /// TLS descriptor
+    pub const TLSDESC: u32 = 1031;
+    /// Adjust indirectly by program base
+    pub const IRELATIVE: u32 = 1032;
+}
+
+/// Name of an AArch64 dynamic relocation type
+fn aarch64_type_name(r_type: u32) -> Option<&'static str> {
+    let name = match r_type {
+        R_AARCH64::NONE => "R_AARCH64_NONE",
+        R_AARCH64::ABS64 => "R_AARCH64_ABS64",
+        R_AARCH64::COPY => "R_AARCH64_COPY",
+        R_AARCH64::GLOB_DAT => "R_AARCH64_GLOB_DAT",
+        R_AARCH64::JUMP_SLOT => "R_AARCH64_JUMP_SLOT",
+        R_AARCH64::RELATIVE => "R_AARCH64_RELATIVE",
+        R_AARCH64::TLS_DTPMOD => "R_AARCH64_TLS_DTPMOD",
+        R_AARCH64::TLS_DTPREL => "R_AARCH64_TLS_DTPREL",
+        R_AARCH64::TLS_TPREL => "R_AARCH64_TLS_TPREL",
+        R_AARCH64::TLSDESC => "R_AARCH64_TLSDESC",
+        R_AARCH64::IRELATIVE => "R_AARCH64_IRELATIVE",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Name of an x86_64 relocation type
+fn x86_64_type_name(r_type: u32) -> Option<&'static str> {
+    let name = match r_type {
+        R_X86_64::NONE => "R_X86_64_NONE",
+        R_X86_64::_64 => "R_X86_64_64",
+        R_X86_64::PC32 => "R_X86_64_PC32",
+        R_X86_64::GOT32 => "R_X86_64_GOT32",
+        R_X86_64::PLT32 => "R_X86_64_PLT32",
+        R_X86_64::COPY => "R_X86_64_COPY",
+        R_X86_64::GLOB_DAT => "R_X86_64_GLOB_DAT",
+        R_X86_64::JUMP_SLOT => "R_X86_64_JUMP_SLOT",
+        R_X86_64::RELATIVE => "R_X86_64_RELATIVE",
+        R_X86_64::GOTPCREL => "R_X86_64_GOTPCREL",
+        R_X86_64::_32 => "R_X86_64_32",
+        R_X86_64::_32S => "R_X86_64_32S",
+        R_X86_64::_16 => "R_X86_64_16",
+        R_X86_64::PC16 => "R_X86_64_PC16",
+        R_X86_64::_8 => "R_X86_64_8",
+        R_X86_64::PC8 => "R_X86_64_PC8",
+        R_X86_64::DTPMOD64 => "R_X86_64_DTPMOD64",
+        R_X86_64::DTPOFF64 => "R_X86_64_DTPOFF64",
+        R_X86_64::TPOFF64 => "R_X86_64_TPOFF64",
+        R_X86_64::TLSGD => "R_X86_64_TLSGD",
+        R_X86_64::TLSLD => "R_X86_64_TLSLD",
+        R_X86_64::DTPOFF32 => "R_X86_64_DTPOFF32",
+        R_X86_64::GOTTPOFF => "R_X86_64_GOTTPOFF",
+        R_X86_64::TPOFF32 => "R_X86_64_TPOFF32",
+        R_X86_64::PC64 => "R_X86_64_PC64",
+        R_X86_64::GOTOFF64 => "R_X86_64_GOTOFF64",
+        R_X86_64::GOTPC32 => "R_X86_64_GOTPC32",
+        R_X86_64::GOT64 => "R_X86_64_GOT64",
+        R_X86_64::GOTPCREL64 => "R_X86_64_GOTPCREL64",
+        R_X86_64::GOTPC64 => "R_X86_64_GOTPC64",
+        R_X86_64::GOTPLT64 => "R_X86_64_GOTPLT64",
+        R_X86_64::PLTOFF64 => "R_X86_64_PLTOFF64",
+        R_X86_64::SIZE32 => "R_X86_64_SIZE32",
+        R_X86_64::SIZE64 => "R_X86_64_SIZE64",
+        R_X86_64::GOTPC32_TLSDESC => "R_X86_64_GOTPC32_TLSDESC",
+        R_X86_64::TLSDESC_CALL => "R_X86_64_TLSDESC_CALL",
+        R_X86_64::TLSDESC => "R_X86_64_TLSDESC",
+        R_X86_64::IRELATIVE => "R_X86_64_IRELATIVE",
+        R_X86_64::RELATIVE64 => "R_X86_64_RELATIVE64",
+        R_X86_64::GOTPCRELX => "R_X86_64_GOTPCRELX",
+        R_X86_64::REX_GOTPCRELX => "R_X86_64_REX_GOTPCRELX",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Name of a relocation type as printed by readelf, ex: `R_X86_64_JUMP_SLOT`
+///
+/// Relocation types are machine specific, `machine` is the `e_machine`
+/// field of the file header. Support for other architectures is added by
+/// giving them their own arm here.
+pub fn type_name(machine: u16, r_type: u32) -> Cow<'static, str> {
+    let name = match Machine::from_u16(machine) {
+        Machine::X86_64 => x86_64_type_name(r_type),
+        Machine::Aarch64 => aarch64_type_name(r_type),
+        _ => None,
+    };
+    match name {
+        Some(name) => Cow::Borrowed(name),
+        None => Cow::Owned(format!("unrecognized: {:x}", r_type)),
+    }
+}
+
+/// True if the relocation type computes a thread local storage address
+///
+/// Only the x86_64 types are known, the other machines have none.
+pub fn is_tls(machine: u16, r_type: u32) -> bool {
+    match Machine::from_u16(machine) {
+        Machine::X86_64 => matches!(
+            r_type,
+            R_X86_64::DTPMOD64
+                | R_X86_64::DTPOFF64
+                | R_X86_64::TPOFF64
+                | R_X86_64::TLSGD
+                | R_X86_64::TLSLD
+                | R_X86_64::DTPOFF32
+                | R_X86_64::GOTTPOFF
+                | R_X86_64::TPOFF32
+                | R_X86_64::GOTPC32_TLSDESC
+                | R_X86_64::TLSDESC_CALL
+                | R_X86_64::TLSDESC
+        ),
+        _ => false,
+    }
+}
+
+/// Relocation entry without addend of an ELF64 file
+///
+/// Sources:
+/// * <https://www.man7.org/linux/man-pages/man5/elf.5.html>
+/// * <https://uclibc.org/docs/elf-64-gen.pdf>
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct Elf64_Rel {
+    /// location at which to apply the relocation
+    pub r_offset: u64,
+    /// symbol table index and relocation type
+    pub r_info: u64,
+}
+
+impl_swap_bytes!(Elf64_Rel, r_offset, r_info);
+
+/// Relocation entry with addend of an ELF64 file
+///
+/// Sources:
+/// * <https://www.man7.org/linux/man-pages/man5/elf.5.html>
+/// * <https://uclibc.org/docs/elf-64-gen.pdf>
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct Elf64_Rela {
+    /// location at which to apply the relocation
+    pub r_offset: u64,
+    /// symbol table index and relocation type
+    pub r_info: u64,
+    /// constant addend used to compute the value
+    pub r_addend: i64,
+}
+
+impl_swap_bytes!(Elf64_Rela, r_offset, r_info, r_addend);
+
+impl Elf64_Rel {
+    pub const SIZE: usize = size_of::<Self>();
+}
+
+impl Elf64_Rela {
+    pub const SIZE: usize = size_of::<Self>();
+}
+
+/// Relocation entry of either kind, with `r_info` split in its two parts
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Relocation {
+    /// location at which to apply the relocation
+    pub r_offset: u64,
+    /// index of the symbol in the linked symbol table
+    pub r_sym: u32,
+    /// machine specific relocation type
+    pub r_type: u32,
+    /// constant addend, only present for SHT_RELA sections
+    pub r_addend: Option<i64>,
+}
+
+impl From<Elf64_Rel> for Relocation {
+    fn from(rel: Elf64_Rel) -> Self {
+        Relocation {
+            r_offset: rel.r_offset,
+            r_sym: (rel.r_info >> 32) as u32,
+            r_type: rel.r_info as u32,
+            r_addend: None,
+        }
+    }
+}
+
+impl From<Elf64_Rela> for Relocation {
+    fn from(rela: Elf64_Rela) -> Self {
+        Relocation {
+            r_offset: rela.r_offset,
+            r_sym: (rela.r_info >> 32) as u32,
+            r_type: rela.r_info as u32,
+            r_addend: Some(rela.r_addend),
+        }
+    }
+}
+
+impl Relocation {
+    /// Rebuild the `r_info` field from the symbol index and the type
+    pub fn r_info(&self) -> u64 {
+        ((self.r_sym as u64) << 32) | self.r_type as u64
+    }
+}
+
+/// Parse the relocations of a SHT_REL or SHT_RELA section
+///
+/// Fails if the section is neither, doesn't fit in the given data or if
+/// its entry size is too small.
+pub fn from_bytes(data: &[u8], header: &x64, shdr: &Elf64_Shdr) -> Result<Vec<Relocation>> {
+    let big_endian = Data::is_big_endian(&header.e_ident);
+    let (expected, num) = match shdr.sh_type {
+        SHT::REL => (Elf64_Rel::SIZE, shdr.sh_size / Elf64_Rel::SIZE as u64),
+        SHT::RELA => (Elf64_Rela::SIZE, shdr.sh_size / Elf64_Rela::SIZE as u64),
+        _ => {
+            return Err(Error::InvalidEntrySize {
+                expected: Elf64_Rel::SIZE,
+                found: shdr.sh_entsize as usize,
+            })
+        }
+    };
+    if shdr.sh_entsize != 0 && shdr.sh_entsize != expected as u64 {
+        return Err(Error::InvalidEntrySize {
+            expected,
+            found: shdr.sh_entsize as usize,
+        });
+    }
+    let entsize = expected as u64;
+    Ok(match shdr.sh_type {
+        SHT::REL => Table::<Elf64_Rel>::parse(data, shdr.sh_offset, entsize, num, big_endian)?
+            .map(|rel| Relocation::from(*rel))
+            .collect(),
+        _ => Table::<Elf64_Rela>::parse(data, shdr.sh_offset, entsize, num, big_endian)?
+            .map(|rela| Relocation::from(*rela))
+            .collect(),
+    })
+}
+
+/// Decode the addresses relocated by a SHT_RELR section
+///
+/// The section packs RELATIVE relocations without addend, the addend
+/// being the word at each address: an even entry is an address, and the
+/// bits 1 to 63 of an odd entry, a bitmap, mark which of the next 63
+/// words are relocated too. Fails if the section isn't SHT_RELR, doesn't
+/// fit in the given data or if its entry size isn't 8.
+pub fn relr_from_bytes(data: &[u8], header: &x64, shdr: &Elf64_Shdr) -> Result<Vec<u64>> {
+    if shdr.sh_type != SHT::RELR || (shdr.sh_entsize != 0 && shdr.sh_entsize != 8) {
+        return Err(Error::InvalidEntrySize {
+            expected: 8,
+            found: shdr.sh_entsize as usize,
+        });
+    }
+    let bytes = shdr.data(data).ok_or(Error::OutOfBounds {
+        offset: shdr.sh_offset,
+        size: shdr.sh_size,
+    })?;
+    let big_endian = Data::is_big_endian(&header.e_ident);
+    let mut addresses = Vec::new();
+    // the address of the first word the next bitmap covers
+    let mut next = 0u64;
+    for offset in (0..bytes.len() / 8).map(|index| index * 8) {
+        let entry = read_u64(bytes, offset, big_endian).unwrap_or_default();
+        if entry & 1 == 0 {
+            addresses.push(entry);
+            next = entry.wrapping_add(8);
+            continue;
+        }
+        for bit in (1..64).filter(|bit| entry >> bit & 1 != 0) {
+            addresses.push(next.wrapping_add((bit - 1) * 8));
+        }
+        next = next.wrapping_add(63 * 8);
+    }
+    Ok(addresses)
+}
+
+/// The RELATIVE relocation type of the machine, the one SHT_RELR packs
+pub fn relative_type(machine: u16) -> Option<u32> {
+    match Machine::from_u16(machine) {
+        Machine::X86_64 => Some(R_X86_64::RELATIVE),
+        Machine::Aarch64 => Some(R_AARCH64::RELATIVE),
+        _ => None,
+    }
+}
+
+/// Relocation sections ready to be printed like `readelf -r`
+pub struct RelocationTable<'a> {
+    /// the whole file, used to resolve the symbols
+    pub data: &'a [u8],
+    /// the file header
+    pub header: &'a x64,
+    /// print the demangled C++ and Rust names like `readelf -C`
+    pub demangle: bool,
+    /// print the symbol names whole like `readelf -W`, instead of cutting
+    /// them to [`NAME_WIDTH`] characters
+    pub wide: bool,
+}
+
+/// Width of the symbol names without `--wide`
+pub const NAME_WIDTH: usize = 22;
+
+impl RelocationTable<'_> {
+    /// Print the addresses of a SHT_RELR section, like readelf 2.40
+    fn fmt_relr(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        shdr: &Elf64_Shdr,
+        name: ByteStr<'_>,
+    ) -> fmt::Result {
+        let addresses = match relr_from_bytes(self.data, self.header, shdr) {
+            Ok(addresses) => addresses,
+            Err(err) => {
+                return writeln!(f, "Warning: invalid relocation section '{}': {}", name, err)
+            }
+        };
+        let entries = shdr.sh_size / 8;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "Relocation section '{}' at offset {:#x} contains {} {}:",
+            name,
+            shdr.sh_offset,
+            entries,
+            if entries == 1 { "entry" } else { "entries" }
+        )?;
+        writeln!(f, "  {} offsets", addresses.len())?;
+        let mut line = String::new();
+        for address in addresses {
+            line.clear();
+            hex(&mut line, address);
+            line.push('\n');
+            f.write_str(&line)?;
+        }
+        Ok(())
+    }
+
+    fn fmt_section(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        shdr: &Elf64_Shdr,
+        name: ByteStr<'_>,
+    ) -> fmt::Result {
+        let relocations = match from_bytes(self.data, self.header, shdr) {
+            Ok(relocations) => relocations,
+            Err(err) => {
+                return writeln!(f, "Warning: invalid relocation section '{}': {}", name, err)
+            }
+        };
+        let names = section::names(self.data, self.header).unwrap_or_default();
+        let shdrs: Vec<_> = section::from_bytes(self.data, self.header)
+            .map(Iterator::collect)
+            .unwrap_or_default();
+        // sh_link points to the symbol table used by the relocations
+        let symtab = section::get(self.data, self.header, shdr.sh_link).ok();
+        let symbols = symtab
+            .as_ref()
+            .and_then(|symtab| symbols::from_bytes(self.data, self.header, symtab).ok());
+        let strtab = symtab
+            .as_ref()
+            .and_then(|symtab| symbols::strtab(self.data, self.header, symtab).ok())
+            .unwrap_or_default();
+
+        writeln!(f)?;
+        writeln!(
+            f,
+            "Relocation section '{}' at offset {:#x} contains {} {}:",
+            name,
+            shdr.sh_offset,
+            relocations.len(),
+            if relocations.len() == 1 {
+                "entry"
+            } else {
+                "entries"
+            }
+        )?;
+        writeln!(
+            f,
+            "    Offset             Info             Type               Symbol's Value  Symbol's Name{}",
+            if shdr.sh_type == SHT::RELA { " + Addend" } else { "" }
+        )?;
+        // each line is written in the same buffer, then all at once
+        let mut line = String::new();
+        for relocation in relocations {
+            line.clear();
+            hex(&mut line, relocation.r_offset);
+            line.push_str("  ");
+            hex(&mut line, relocation.r_info());
+            write!(
+                line,
+                " {:<22}",
+                type_name(self.header.e_machine, relocation.r_type)
+            )?;
+            let symbol = match relocation.r_sym {
+                0 => None,
+                index => symbols
+                    .as_ref()
+                    .and_then(|symbols| symbols.get(index as usize)),
+            };
+            match symbol {
+                None => {
+                    line.push_str("                    ");
+                    if let Some(addend) = relocation.r_addend {
+                        write!(
+                            line,
+                            "{}{:x}",
+                            if addend < 0 { "-" } else { "" },
+                            addend.unsigned_abs()
+                        )?;
+                    }
+                }
+                Some(symbol) => {
+                    let mut name = symbol.name(&strtab);
+                    if symbol.kind() == STT::SECTION && matches!(name, Ok(name) if name.is_empty())
+                    {
+                        let index = symbol.st_shndx as u32;
+                        name = shdrs
+                            .get(index as usize)
+                            .ok_or(Error::InvalidSectionIndex(index))
+                            .and_then(|shdr| shdr.name(&names));
+                    }
+                    let name = name.unwrap_or(CORRUPT);
+                    let name = match name.to_str() {
+                        Some(name) if self.demangle => demangle(name),
+                        _ => name.to_str_lossy(),
+                    };
+                    let name = match self.wide {
+                        true => name,
+                        false => Cow::Owned(truncate(&name, NAME_WIDTH).into_owned()),
+                    };
+                    line.push(' ');
+                    hex(&mut line, symbol.st_value);
+                    line.push(' ');
+                    line.push_str(&name);
+                    if let Some(addend) = relocation.r_addend {
+                        write!(
+                            line,
+                            " {} {:x}",
+                            if addend < 0 { "-" } else { "+" },
+                            addend.unsigned_abs()
+                        )?;
+                    }
+                }
+            }
+            writeln!(line)?;
+            f.write_str(&line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Append the 16 hexadecimal digits of `value`, faster than `{:016x}`
+/// for the millions of relocations of the large programs
+fn hex(line: &mut String, value: u64) {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    for shift in (0..16).rev() {
+        line.push(DIGITS[(value >> (shift * 4)) as usize & 0xf] as char);
+    }
+}
+
+impl fmt::Display for RelocationTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shdrs = match section::from_bytes(self.data, self.header) {
+            Ok(shdrs) => shdrs,
+            Err(err) => return writeln!(f, "Warning: invalid section header table: {}", err),
+        };
+        let names = section::names(self.data, self.header).unwrap_or_default();
+        let mut found = false;
+        for shdr in shdrs.filter(|shdr| matches!(shdr.sh_type, SHT::REL | SHT::RELA | SHT::RELR)) {
+            found = true;
+            let name = shdr.name(&names).unwrap_or(CORRUPT);
+            match shdr.sh_type {
+                SHT::RELR => self.fmt_relr(f, &shdr, name)?,
+                _ => self.fmt_section(f, &shdr, name)?,
+            }
+        }
+        if !found {
+            writeln!(f)?;
+            writeln!(f, "There are no relocations in this file.")?;
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for Relocation {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("r_offset", Json::from(self.r_offset)),
+            ("r_info", Json::from(self.r_info())),
+            ("r_sym", Json::from(self.r_sym)),
+            ("r_type", Json::from(self.r_type)),
+            ("r_addend", Json::from(self.r_addend)),
+        ])
+    }
+}
+
+impl ToJson for RelocationTable<'_> {
+    fn to_json(&self) -> Json {
+        let shdrs = match section::from_bytes(self.data, self.header) {
+            Ok(shdrs) => shdrs,
+            Err(err) => return Json::error(err),
+        };
+        let names = section::names(self.data, self.header).unwrap_or_default();
+        Json::array(
+            shdrs
+                .filter(|shdr| matches!(shdr.sh_type, SHT::REL | SHT::RELA | SHT::RELR))
+                .map(|shdr| {
+                    if shdr.sh_type == SHT::RELR {
+                        return match relr_from_bytes(self.data, self.header, &shdr) {
+                            Ok(addresses) => Json::object([
+                                ("section", Json::from(shdr.name(&names).ok())),
+                                ("offset", Json::from(shdr.sh_offset)),
+                                ("relr", Json::array(addresses)),
+                            ]),
+                            Err(err) => Json::error(err),
+                        };
+                    }
+                    let relocations = match from_bytes(self.data, self.header, &shdr) {
+                        Ok(relocations) => relocations,
+                        Err(err) => return Json::error(err),
+                    };
+                    let symtab = section::get(self.data, self.header, shdr.sh_link).ok();
+                    let symbols = symtab.as_ref().and_then(|symtab| {
+                        symbols::from_bytes(self.data, self.header, symtab).ok()
+                    });
+                    let strtab = symtab
+                        .as_ref()
+                        .and_then(|symtab| symbols::strtab(self.data, self.header, symtab).ok())
+                        .unwrap_or_default();
+                    Json::object([
+                        ("section", Json::from(shdr.name(&names).ok())),
+                        ("offset", Json::from(shdr.sh_offset)),
+                        (
+                            "relocations",
+                            Json::array(relocations.iter().map(|relocation| {
+                                let symbol = match relocation.r_sym {
+                                    0 => None,
+                                    index => symbols
+                                        .as_ref()
+                                        .and_then(|symbols| symbols.get(index as usize)),
+                                };
+                                let mut json = relocation.to_json();
+                                if let Json::Object(members) = &mut json {
+                                    members.push((
+                                        "type".to_string(),
+                                        Json::from(type_name(
+                                            self.header.e_machine,
+                                            relocation.r_type,
+                                        )),
+                                    ));
+                                    members.push((
+                                        "symbol".to_string(),
+                                        Json::from(
+                                            symbol.and_then(|symbol| symbol.name(&strtab).ok()),
+                                        ),
+                                    ));
+                                }
+                                json
+                            })),
+                        ),
+                    ])
+                }),
+        )
+    }
+}
@@ -0,0 +1,368 @@
+//! The library search paths recorded in a file, DT_RPATH and DT_RUNPATH
+//!
+//! Both hold directories separated by colons, searched by the dynamic
+//! linker for the DT_NEEDED libraries and for `dlopen`. They differ in
+//! when they apply: DT_RPATH is searched before `LD_LIBRARY_PATH` and
+//! also for the dependencies of the dependencies, DT_RUNPATH after it and
+//! only for the direct dependencies of the file. When a file has both,
+//! DT_RPATH is ignored.
+//!
+//! The directories may use the `$ORIGIN`, `$LIB` and `$PLATFORM` dynamic
+//! string tokens, `$ORIGIN` being the directory of the file. The
+//! entries resolved from the current directory, an empty entry or a
+//! relative path, let whoever controls it inject libraries.
+//! Sources:
+//! * <https://man7.org/linux/man-pages/man8/ld.so.8.html>
+//! * <https://www.sco.com/developers/gabi/latest/ch5.dynamic.html#substitution>
+use crate::alloc_prelude::*;
+use crate::utils::json::{Json, ToJson};
+use crate::Result;
+use core::fmt;
+
+use super::dynamic::{self, DF, DF_1, DT};
+use super::x64::x64;
+
+/// The dynamic tag an entry comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathKind {
+    /// DT_RPATH
+    Rpath,
+    /// DT_RUNPATH
+    Runpath,
+}
+
+impl PathKind {
+    /// Name of the tag
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PathKind::Rpath => "RPATH",
+            PathKind::Runpath => "RUNPATH",
+        }
+    }
+}
+
+/// Which search order the dynamic linker applies to the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Semantics {
+    /// no search path, only `LD_LIBRARY_PATH` and the default directories
+    None,
+    /// DT_RPATH, before `LD_LIBRARY_PATH` and for the whole dependency tree
+    Rpath,
+    /// DT_RUNPATH, after `LD_LIBRARY_PATH` and for the direct dependencies only
+    Runpath,
+    /// both, DT_RUNPATH applies and DT_RPATH is ignored
+    RunpathOverridesRpath,
+}
+
+impl Semantics {
+    /// What the search order means for the dependencies of the file
+    pub fn description(&self) -> &'static str {
+        match self {
+            Semantics::None => "no search path, LD_LIBRARY_PATH then the default directories",
+            Semantics::Rpath => {
+                "DT_RPATH: searched before LD_LIBRARY_PATH, for the whole dependency tree"
+            }
+            Semantics::Runpath => {
+                "DT_RUNPATH: searched after LD_LIBRARY_PATH, for the direct dependencies only"
+            }
+            Semantics::RunpathOverridesRpath => {
+                "DT_RUNPATH: searched after LD_LIBRARY_PATH, for the direct dependencies only, DT_RPATH is ignored"
+            }
+        }
+    }
+}
+
+/// Why a directory of a search path is unsafe
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    /// an empty entry, the current directory
+    Empty,
+    /// a relative path, resolved from the current directory
+    Relative,
+    /// a dynamic string token that isn't known, kept as is
+    UnknownToken(String),
+    /// the directory can be written by any user
+    WorldWritable,
+    /// the directory doesn't exist, whoever creates it controls it
+    Missing,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::Empty => write!(f, "empty entry, the current directory"),
+            Issue::Relative => write!(f, "relative to the current directory"),
+            Issue::UnknownToken(token) => write!(f, "unknown token ${}", token),
+            Issue::WorldWritable => write!(f, "world writable"),
+            Issue::Missing => write!(f, "missing directory"),
+        }
+    }
+}
+
+/// A directory of DT_RPATH or DT_RUNPATH
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchPath<'a> {
+    /// the tag holding it
+    pub kind: PathKind,
+    /// the directory as written in the file
+    pub raw: &'a str,
+    /// the directory with the tokens replaced, None when it uses
+    /// `$ORIGIN` without a known origin or `$LIB` and `$PLATFORM`, which
+    /// depend on the machine running the file
+    pub expanded: Option<String>,
+    /// the reasons the directory is unsafe, empty if none
+    pub issues: Vec<Issue>,
+    /// the entry is ignored because the file also has DT_RUNPATH
+    pub ignored: bool,
+}
+
+impl SearchPath<'_> {
+    /// Whether the entry is searched and has an issue
+    pub fn is_insecure(&self) -> bool {
+        !self.ignored && !self.issues.is_empty()
+    }
+}
+
+/// The search paths of an ELF64 file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchPaths<'a> {
+    /// the DT_RPATH string
+    pub rpath: Option<&'a str>,
+    /// the DT_RUNPATH string
+    pub runpath: Option<&'a str>,
+    /// the directory `$ORIGIN` was expanded to, if given
+    pub origin: Option<String>,
+    /// the entries of DT_RPATH then of DT_RUNPATH
+    pub entries: Vec<SearchPath<'a>>,
+    /// DF_ORIGIN or DF_1_ORIGIN, the file asks for `$ORIGIN` processing
+    pub uses_origin: bool,
+    /// DF_1_NODEFLIB, the default directories aren't searched
+    pub nodeflib: bool,
+}
+
+impl SearchPaths<'_> {
+    /// Add the issues found on the file system to the entries: the missing
+    /// and the world writable directories
+    ///
+    /// The relative entries are skipped, they depend on the directory the
+    /// program runs in. Only available with the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn check_fs(&mut self) {
+        for entry in &mut self.entries {
+            let dir = entry.expanded.as_deref().filter(|dir| dir.starts_with('/'));
+            if let Some(issue) = dir.and_then(check_directory) {
+                entry.issues.push(issue);
+            }
+        }
+    }
+
+    /// The search order the dynamic linker applies
+    pub fn semantics(&self) -> Semantics {
+        match (self.rpath.is_some(), self.runpath.is_some()) {
+            (false, false) => Semantics::None,
+            (true, false) => Semantics::Rpath,
+            (false, true) => Semantics::Runpath,
+            (true, true) => Semantics::RunpathOverridesRpath,
+        }
+    }
+
+    /// Whether a searched entry has an issue
+    pub fn is_insecure(&self) -> bool {
+        self.entries.iter().any(SearchPath::is_insecure)
+    }
+}
+
+/// Replace the dynamic string tokens of a directory
+///
+/// Returns None when a token can't be replaced, the unknown tokens are
+/// kept and returned.
+fn expand(raw: &str, origin: Option<&str>) -> (Option<String>, Vec<String>) {
+    let mut expanded = Some(String::new());
+    let mut unknown = Vec::new();
+    let mut rest = raw;
+    while let Some(start) = rest.find('$') {
+        let (before, after) = rest.split_at(start);
+        let after = &after[1..];
+        if let Some(expanded) = &mut expanded {
+            expanded.push_str(before);
+        }
+        let (token, tail) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], &braced[end + 1..]),
+                None => (braced, ""),
+            },
+            None => {
+                let end = after
+                    .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                    .unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            }
+        };
+        match token {
+            "ORIGIN" => match (&mut expanded, origin) {
+                (Some(expanded), Some(origin)) => expanded.push_str(origin),
+                _ => expanded = None,
+            },
+            "LIB" | "PLATFORM" => expanded = None,
+            token => {
+                unknown.push(token.to_string());
+                if let Some(expanded) = &mut expanded {
+                    expanded.push('$');
+                    expanded.push_str(token);
+                }
+            }
+        }
+        rest = tail;
+    }
+    if let Some(expanded) = &mut expanded {
+        expanded.push_str(rest);
+    }
+    (expanded, unknown)
+}
+
+/// The issue of a search path directory found on the file system
+#[cfg(feature = "std")]
+fn check_directory(dir: &str) -> Option<Issue> {
+    let metadata = match std::fs::metadata(dir) {
+        Ok(metadata) => metadata,
+        Err(_) => return Some(Issue::Missing),
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o002 != 0 {
+            return Some(Issue::WorldWritable);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = metadata;
+    None
+}
+
+/// Split and expand DT_RPATH and DT_RUNPATH
+///
+/// `origin` is the directory of the file, `$ORIGIN` is kept unexpanded
+/// without it. The issues found without looking at the file system are
+/// filled: empty entries, relative paths and unknown tokens. Returns the
+/// empty search paths for statically linked files, fails if the dynamic
+/// section can't be parsed.
+pub fn from_bytes<'a>(
+    data: &'a [u8],
+    header: &x64,
+    origin: Option<&str>,
+) -> Result<SearchPaths<'a>> {
+    let dynamic = dynamic::from_bytes(data, header)?;
+    let get = |tag| dynamic.as_ref().and_then(|dynamic| dynamic.get(tag));
+    let rpath = dynamic.as_ref().and_then(|dynamic| dynamic.rpath());
+    let runpath = dynamic.as_ref().and_then(|dynamic| dynamic.runpath());
+    let mut entries = Vec::new();
+    for (kind, value) in [(PathKind::Rpath, rpath), (PathKind::Runpath, runpath)] {
+        for raw in value.into_iter().flat_map(|value| value.split(':')) {
+            let (expanded, unknown) = expand(raw, origin);
+            let mut issues = Vec::new();
+            if raw.is_empty() {
+                issues.push(Issue::Empty);
+            } else if !raw.starts_with('/') && !raw.starts_with('$') {
+                issues.push(Issue::Relative);
+            } else if expanded
+                .as_ref()
+                .is_some_and(|expanded| !expanded.starts_with('/'))
+            {
+                // $ORIGIN of a file given with a relative path
+                issues.push(Issue::Relative);
+            }
+            issues.extend(unknown.into_iter().map(Issue::UnknownToken));
+            entries.push(SearchPath {
+                kind,
+                raw,
+                expanded,
+                issues,
+                ignored: kind == PathKind::Rpath && runpath.is_some(),
+            });
+        }
+    }
+    Ok(SearchPaths {
+        rpath,
+        runpath,
+        origin: origin.map(String::from),
+        entries,
+        uses_origin: get(DT::FLAGS).is_some_and(|flags| flags & DF::ORIGIN != 0)
+            || get(DT::FLAGS_1).is_some_and(|flags| flags & DF_1::ORIGIN != 0),
+        nodeflib: get(DT::FLAGS_1).is_some_and(|flags| flags & DF_1::NODEFLIB != 0),
+    })
+}
+
+impl fmt::Display for SearchPaths<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(rpath) = self.rpath {
+            writeln!(f, "Library rpath: [{}]", rpath)?;
+        }
+        if let Some(runpath) = self.runpath {
+            writeln!(f, "Library runpath: [{}]", runpath)?;
+        }
+        writeln!(f, "Semantics: {}", self.semantics().description())?;
+        if let Some(origin) = &self.origin {
+            writeln!(f, "$ORIGIN: {}", origin)?;
+        }
+        if self.nodeflib {
+            writeln!(f, "DF_1_NODEFLIB: the default directories are not searched")?;
+        }
+        if self.entries.is_empty() {
+            return Ok(());
+        }
+        writeln!(f)?;
+        for entry in &self.entries {
+            let raw = match entry.raw {
+                "" => "\"\"",
+                raw => raw,
+            };
+            write!(f, "  {:<7} {}", entry.kind.as_str(), raw)?;
+            match &entry.expanded {
+                Some(expanded) if expanded != entry.raw => write!(f, " => {}", expanded)?,
+                _ => {}
+            }
+            if entry.ignored {
+                write!(f, " (ignored)")?;
+            }
+            writeln!(f)?;
+            for issue in &entry.issues {
+                writeln!(f, "          insecure: {}", issue)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for SearchPaths<'_> {
+    fn to_json(&self) -> Json {
+        let semantics = match self.semantics() {
+            Semantics::None => None,
+            Semantics::Rpath => Some("RPATH"),
+            Semantics::Runpath | Semantics::RunpathOverridesRpath => Some("RUNPATH"),
+        };
+        Json::object([
+            ("rpath", Json::from(self.rpath)),
+            ("runpath", Json::from(self.runpath)),
+            ("semantics", Json::from(semantics)),
+            ("origin", Json::from(self.origin.as_deref())),
+            ("uses_origin", Json::from(self.uses_origin)),
+            ("nodeflib", Json::from(self.nodeflib)),
+            (
+                "entries",
+                Json::array(self.entries.iter().map(|entry| {
+                    Json::object([
+                        ("kind", Json::from(entry.kind.as_str())),
+                        ("raw", Json::from(entry.raw)),
+                        ("expanded", Json::from(entry.expanded.as_deref())),
+                        ("ignored", Json::from(entry.ignored)),
+                        (
+                            "issues",
+                            Json::array(entry.issues.iter().map(|issue| issue.to_string())),
+                        ),
+                    ])
+                })),
+            ),
+            ("insecure", Json::from(self.is_insecure())),
+        ])
+    }
+}
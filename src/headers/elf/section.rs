@@ -0,0 +1,818 @@
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::json::{Json, ToJson};
+use crate::utils::table::{Align, Color, Table as TextTable};
+use crate::utils::template::{Record, Value};
+use crate::utils::{impl_fields, impl_swap_bytes, impl_to_bytes};
+use crate::{Error, Result};
+use alloc::borrow::Cow;
+use core::fmt;
+use core::mem::size_of;
+
+use super::data::Data;
+use super::registry;
+use super::strtab::{StrTab, CORRUPT};
+use super::table::Table;
+use super::x64::x64;
+
+/// Define the content and semantics of a section
+///
+/// SHT define possible values for the `sh_type` field
+pub struct SHT {}
+impl SHT {
+    /// Inactive section header
+    pub const NULL: u32 = 0;
+    /// Information defined by the program
+    pub const PROGBITS: u32 = 1;
+    /// Symbol table
+    pub const SYMTAB: u32 = 2;
+    /// String table
+    pub const STRTAB: u32 = 3;
+    /// Relocation entries with explicit addends
+    pub const RELA: u32 = 4;
+    /// Symbol hash table
+    pub const HASH: u32 = 5;
+    /// Dynamic linking information
+    pub const DYNAMIC: u32 = 6;
+    /// Notes
+    pub const NOTE: u32 = 7;
+    /// Occupies no space in the file
+    pub const NOBITS: u32 = 8;
+    /// Relocation entries without explicit addends
+    pub const REL: u32 = 9;
+    /// Reserved
+    pub const SHLIB: u32 = 10;
+    /// Dynamic linker symbol table
+    pub const DYNSYM: u32 = 11;
+    /// Array of constructors
+    pub const INIT_ARRAY: u32 = 14;
+    /// Array of destructors
+    pub const FINI_ARRAY: u32 = 15;
+    /// Array of pre-constructors
+    pub const PREINIT_ARRAY: u32 = 16;
+    /// Section group
+    pub const GROUP: u32 = 17;
+    /// Extended section indices
+    pub const SYMTAB_SHNDX: u32 = 18;
+    /// Relative relocations
+    pub const RELR: u32 = 19;
+    /// Start of the OS specific range
+    pub const LOOS: u32 = 0x6000_0000;
+    /// Object attributes
+    pub const GNU_ATTRIBUTES: u32 = 0x6fff_fff5;
+    /// GNU style hash table
+    pub const GNU_HASH: u32 = 0x6fff_fff6;
+    /// Prelink library list
+    pub const GNU_LIBLIST: u32 = 0x6fff_fff7;
+    /// Checksum for DSO content
+    pub const CHECKSUM: u32 = 0x6fff_fff8;
+    /// Version definition section
+    pub const GNU_VERDEF: u32 = 0x6fff_fffd;
+    /// Version needs section
+    pub const GNU_VERNEED: u32 = 0x6fff_fffe;
+    /// Version symbol table
+    pub const GNU_VERSYM: u32 = 0x6fff_ffff;
+    /// End of the OS specific range
+    pub const HIOS: u32 = 0x6fff_ffff;
+    /// Start of the processor specific range
+    pub const LOPROC: u32 = 0x7000_0000;
+    /// End of the processor specific range
+    pub const HIPROC: u32 = 0x7fff_ffff;
+    /// Start of the application specific range
+    pub const LOUSER: u32 = 0x8000_0000;
+    /// End of the application specific range
+    pub const HIUSER: u32 = 0xffff_ffff;
+}
+
+/// Define the attributes of a section
+///
+/// SHF define the bits of the `sh_flags` field
+pub struct SHF {}
+impl SHF {
+    /// Writable
+    pub const WRITE: u64 = 0x1;
+    /// Occupies memory during execution
+    pub const ALLOC: u64 = 0x2;
+    /// Executable
+    pub const EXECINSTR: u64 = 0x4;
+    /// Might be merged
+    pub const MERGE: u64 = 0x10;
+    /// Contains nul-terminated strings
+    pub const STRINGS: u64 = 0x20;
+    /// `sh_info` contains a section header index
+    pub const INFO_LINK: u64 = 0x40;
+    /// Preserve order after combining
+    pub const LINK_ORDER: u64 = 0x80;
+    /// Non-standard OS specific handling required
+    pub const OS_NONCONFORMING: u64 = 0x100;
+    /// Section is member of a group
+    pub const GROUP: u64 = 0x200;
+    /// Section hold thread-local data
+    pub const TLS: u64 = 0x400;
+    /// Section with compressed data
+    pub const COMPRESSED: u64 = 0x800;
+    /// OS specific bits
+    pub const MASKOS: u64 = 0x0ff0_0000;
+    /// Processor specific bits
+    pub const MASKPROC: u64 = 0xf000_0000;
+    /// Not to be included in the linked output
+    pub const EXCLUDE: u64 = 0x8000_0000;
+}
+
+/// The `sh_flags` of a section, a set of [`SHF`] bits
+///
+/// The bits are combined with `|` and tested with [`contains`], the
+/// Display implementation writes the letters of readelf, ex: `AX`.
+///
+/// [`contains`]: SectionFlags::contains
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SectionFlags(pub u64);
+
+impl SectionFlags {
+    pub const WRITE: Self = Self(SHF::WRITE);
+    pub const ALLOC: Self = Self(SHF::ALLOC);
+    pub const EXECINSTR: Self = Self(SHF::EXECINSTR);
+    pub const MERGE: Self = Self(SHF::MERGE);
+    pub const STRINGS: Self = Self(SHF::STRINGS);
+    pub const INFO_LINK: Self = Self(SHF::INFO_LINK);
+    pub const LINK_ORDER: Self = Self(SHF::LINK_ORDER);
+    pub const OS_NONCONFORMING: Self = Self(SHF::OS_NONCONFORMING);
+    pub const GROUP: Self = Self(SHF::GROUP);
+    pub const TLS: Self = Self(SHF::TLS);
+    pub const COMPRESSED: Self = Self(SHF::COMPRESSED);
+    pub const EXCLUDE: Self = Self(SHF::EXCLUDE);
+
+    /// The named bits with their readelf letters
+    const BITS: [(&'static str, Self, char); 12] = [
+        ("WRITE", Self::WRITE, 'W'),
+        ("ALLOC", Self::ALLOC, 'A'),
+        ("EXECINSTR", Self::EXECINSTR, 'X'),
+        ("MERGE", Self::MERGE, 'M'),
+        ("STRINGS", Self::STRINGS, 'S'),
+        ("INFO_LINK", Self::INFO_LINK, 'I'),
+        ("LINK_ORDER", Self::LINK_ORDER, 'L'),
+        ("OS_NONCONFORMING", Self::OS_NONCONFORMING, 'O'),
+        ("GROUP", Self::GROUP, 'G'),
+        ("TLS", Self::TLS, 'T'),
+        ("COMPRESSED", Self::COMPRESSED, 'C'),
+        ("EXCLUDE", Self::EXCLUDE, 'E'),
+    ];
+
+    /// The value of `sh_flags`
+    pub const fn bits(self) -> u64 {
+        self.0
+    }
+
+    /// No bit is set
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// All the bits of `other` are set
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Some bit of `other` is set
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// The names of the bits set, ex: `["ALLOC", "EXECINSTR"]`
+    pub fn names(self) -> Vec<&'static str> {
+        Self::BITS
+            .iter()
+            .filter(|(_, bit, _)| self.contains(*bit))
+            .map(|(name, _, _)| *name)
+            .collect()
+    }
+
+    /// The bits without a name, OS, processor specific or unknown
+    pub fn unknown(self) -> u64 {
+        let known = Self::BITS.iter().fold(0, |acc, (_, bit, _)| acc | bit.0);
+        self.0 & !known
+    }
+}
+
+impl core::ops::BitOr for SectionFlags {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl core::ops::BitOrAssign for SectionFlags {
+    fn bitor_assign(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+}
+
+impl core::ops::BitAnd for SectionFlags {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+}
+
+impl core::ops::Not for SectionFlags {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(!self.0)
+    }
+}
+
+impl fmt::Debug for SectionFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names = self.names().join(" | ");
+        if self.unknown() != 0 {
+            if !names.is_empty() {
+                names.push_str(" | ");
+            }
+            names.push_str(&format!("{:#x}", self.unknown()));
+        }
+        write!(f, "SectionFlags({})", names)
+    }
+}
+
+/// The readelf letters, `o`, `p` and `x` for the OS, processor specific
+/// and unknown bits
+impl fmt::Display for SectionFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut letters: String = Self::BITS
+            .iter()
+            .filter(|(_, bit, _)| self.contains(*bit))
+            .map(|(_, _, letter)| *letter)
+            .collect();
+        let rest = self.unknown();
+        if rest & SHF::MASKOS != 0 {
+            letters.push('o');
+        }
+        if rest & SHF::MASKPROC != 0 {
+            letters.push('p');
+        }
+        if rest & !(SHF::MASKOS | SHF::MASKPROC) != 0 {
+            letters.push('x');
+        }
+        f.pad(&letters)
+    }
+}
+
+/// The `sh_type` of a section
+///
+/// The values of the OS, processor and user ranges without a name of
+/// their own are kept in [`SectionType::Os`], [`SectionType::Proc`] and
+/// [`SectionType::User`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SectionType {
+    Null,
+    Progbits,
+    Symtab,
+    Strtab,
+    Rela,
+    Hash,
+    Dynamic,
+    Note,
+    Nobits,
+    Rel,
+    Shlib,
+    Dynsym,
+    InitArray,
+    FiniArray,
+    PreinitArray,
+    Group,
+    SymtabShndx,
+    Relr,
+    GnuAttributes,
+    GnuHash,
+    GnuLiblist,
+    Checksum,
+    GnuVerdef,
+    GnuVerneed,
+    GnuVersym,
+    /// OS specific, between SHT_LOOS and SHT_HIOS
+    Os(u32),
+    /// Processor specific, between SHT_LOPROC and SHT_HIPROC
+    Proc(u32),
+    /// Application specific, between SHT_LOUSER and SHT_HIUSER
+    User(u32),
+    /// A value outside of the defined ranges
+    Unknown(u32),
+}
+
+impl SectionType {
+    /// Decode `sh_type`
+    pub fn from_u32(value: u32) -> Self {
+        match value {
+            SHT::NULL => SectionType::Null,
+            SHT::PROGBITS => SectionType::Progbits,
+            SHT::SYMTAB => SectionType::Symtab,
+            SHT::STRTAB => SectionType::Strtab,
+            SHT::RELA => SectionType::Rela,
+            SHT::HASH => SectionType::Hash,
+            SHT::DYNAMIC => SectionType::Dynamic,
+            SHT::NOTE => SectionType::Note,
+            SHT::NOBITS => SectionType::Nobits,
+            SHT::REL => SectionType::Rel,
+            SHT::SHLIB => SectionType::Shlib,
+            SHT::DYNSYM => SectionType::Dynsym,
+            SHT::INIT_ARRAY => SectionType::InitArray,
+            SHT::FINI_ARRAY => SectionType::FiniArray,
+            SHT::PREINIT_ARRAY => SectionType::PreinitArray,
+            SHT::GROUP => SectionType::Group,
+            SHT::SYMTAB_SHNDX => SectionType::SymtabShndx,
+            SHT::RELR => SectionType::Relr,
+            SHT::GNU_ATTRIBUTES => SectionType::GnuAttributes,
+            SHT::GNU_HASH => SectionType::GnuHash,
+            SHT::GNU_LIBLIST => SectionType::GnuLiblist,
+            SHT::CHECKSUM => SectionType::Checksum,
+            SHT::GNU_VERDEF => SectionType::GnuVerdef,
+            SHT::GNU_VERNEED => SectionType::GnuVerneed,
+            SHT::GNU_VERSYM => SectionType::GnuVersym,
+            SHT::LOOS..=SHT::HIOS => SectionType::Os(value),
+            SHT::LOPROC..=SHT::HIPROC => SectionType::Proc(value),
+            SHT::LOUSER..=SHT::HIUSER => SectionType::User(value),
+            value => SectionType::Unknown(value),
+        }
+    }
+
+    /// The value of `sh_type`
+    pub fn to_u32(self) -> u32 {
+        match self {
+            SectionType::Null => SHT::NULL,
+            SectionType::Progbits => SHT::PROGBITS,
+            SectionType::Symtab => SHT::SYMTAB,
+            SectionType::Strtab => SHT::STRTAB,
+            SectionType::Rela => SHT::RELA,
+            SectionType::Hash => SHT::HASH,
+            SectionType::Dynamic => SHT::DYNAMIC,
+            SectionType::Note => SHT::NOTE,
+            SectionType::Nobits => SHT::NOBITS,
+            SectionType::Rel => SHT::REL,
+            SectionType::Shlib => SHT::SHLIB,
+            SectionType::Dynsym => SHT::DYNSYM,
+            SectionType::InitArray => SHT::INIT_ARRAY,
+            SectionType::FiniArray => SHT::FINI_ARRAY,
+            SectionType::PreinitArray => SHT::PREINIT_ARRAY,
+            SectionType::Group => SHT::GROUP,
+            SectionType::SymtabShndx => SHT::SYMTAB_SHNDX,
+            SectionType::Relr => SHT::RELR,
+            SectionType::GnuAttributes => SHT::GNU_ATTRIBUTES,
+            SectionType::GnuHash => SHT::GNU_HASH,
+            SectionType::GnuLiblist => SHT::GNU_LIBLIST,
+            SectionType::Checksum => SHT::CHECKSUM,
+            SectionType::GnuVerdef => SHT::GNU_VERDEF,
+            SectionType::GnuVerneed => SHT::GNU_VERNEED,
+            SectionType::GnuVersym => SHT::GNU_VERSYM,
+            SectionType::Os(value)
+            | SectionType::Proc(value)
+            | SectionType::User(value)
+            | SectionType::Unknown(value) => value,
+        }
+    }
+
+    /// Name of the type as printed by readelf, ex: `PROGBITS` or
+    /// `LOPROC+1` for an unnamed processor specific type
+    pub fn name(self) -> Cow<'static, str> {
+        let name = match self {
+            SectionType::Null => "NULL",
+            SectionType::Progbits => "PROGBITS",
+            SectionType::Symtab => "SYMTAB",
+            SectionType::Strtab => "STRTAB",
+            SectionType::Rela => "RELA",
+            SectionType::Hash => "HASH",
+            SectionType::Dynamic => "DYNAMIC",
+            SectionType::Note => "NOTE",
+            SectionType::Nobits => "NOBITS",
+            SectionType::Rel => "REL",
+            SectionType::Shlib => "SHLIB",
+            SectionType::Dynsym => "DYNSYM",
+            SectionType::InitArray => "INIT_ARRAY",
+            SectionType::FiniArray => "FINI_ARRAY",
+            SectionType::PreinitArray => "PREINIT_ARRAY",
+            SectionType::Group => "GROUP",
+            SectionType::SymtabShndx => "SYMTAB SECTION INDICES",
+            SectionType::Relr => "RELR",
+            SectionType::GnuAttributes => "GNU_ATTRIBUTES",
+            SectionType::GnuHash => "GNU_HASH",
+            SectionType::GnuLiblist => "GNU_LIBLIST",
+            SectionType::Checksum => "CHECKSUM",
+            SectionType::GnuVerdef => "VERDEF",
+            SectionType::GnuVerneed => "VERNEED",
+            SectionType::GnuVersym => "VERSYM",
+            SectionType::Os(value) => return Cow::Owned(format!("LOOS+{:x}", value - SHT::LOOS)),
+            SectionType::Proc(value) => {
+                return Cow::Owned(format!("LOPROC+{:x}", value - SHT::LOPROC))
+            }
+            SectionType::User(value) => {
+                return Cow::Owned(format!("LOUSER+{:x}", value - SHT::LOUSER))
+            }
+            SectionType::Unknown(value) => return Cow::Owned(format!("{:08x}: <unknown>", value)),
+        };
+        Cow::Borrowed(name)
+    }
+}
+
+impl From<u32> for SectionType {
+    fn from(value: u32) -> Self {
+        SectionType::from_u32(value)
+    }
+}
+
+impl From<SectionType> for u32 {
+    fn from(value: SectionType) -> Self {
+        value.to_u32()
+    }
+}
+
+impl fmt::Display for SectionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&self.name())
+    }
+}
+
+/// Define the reserved section header indexes
+///
+/// SHN define the special values a section index can take
+pub struct SHN {}
+impl SHN {
+    /// Undefined section
+    pub const UNDEF: u16 = 0;
+    /// Start of the reserved indexes
+    pub const LORESERVE: u16 = 0xff00;
+    /// Absolute values
+    pub const ABS: u16 = 0xfff1;
+    /// Common symbols
+    pub const COMMON: u16 = 0xfff2;
+    /// Index is stored elsewhere
+    pub const XINDEX: u16 = 0xffff;
+}
+
+/// Section header table entry of an ELF64 file
+///
+/// A file's section header table lets one locate all the file's
+/// sections. Each entry describes the name, type, attributes, and
+/// location in the file and in memory of a section.
+/// Sources:
+/// * <https://www.man7.org/linux/man-pages/man5/elf.5.html>
+/// * <https://uclibc.org/docs/elf-64-gen.pdf>
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct Elf64_Shdr {
+    /// offset of the section name in the section name string table
+    pub sh_name: u32,
+    /// section type
+    pub sh_type: u32,
+    /// section attributes
+    pub sh_flags: u64,
+    /// virtual address of the section in memory
+    pub sh_addr: u64,
+    /// section offset in the file
+    pub sh_offset: u64,
+    /// size of the section
+    pub sh_size: u64,
+    /// link to another section
+    pub sh_link: u32,
+    /// additional section information
+    pub sh_info: u32,
+    /// section alignment
+    pub sh_addralign: u64,
+    /// size of the entries if the section holds a table
+    pub sh_entsize: u64,
+}
+
+impl_swap_bytes!(
+    Elf64_Shdr,
+    sh_name,
+    sh_type,
+    sh_flags,
+    sh_addr,
+    sh_offset,
+    sh_size,
+    sh_link,
+    sh_info,
+    sh_addralign,
+    sh_entsize
+);
+
+impl_to_bytes!(
+    Elf64_Shdr,
+    sh_name,
+    sh_type,
+    sh_flags,
+    sh_addr,
+    sh_offset,
+    sh_size,
+    sh_link,
+    sh_info,
+    sh_addralign,
+    sh_entsize,
+);
+
+impl_fields!(
+    Elf64_Shdr,
+    sh_name,
+    sh_type,
+    sh_flags,
+    sh_addr,
+    sh_offset,
+    sh_size,
+    sh_link,
+    sh_info,
+    sh_addralign,
+    sh_entsize,
+);
+
+impl Elf64_Shdr {
+    pub const SIZE: usize = size_of::<Self>();
+
+    /// The type of the section
+    pub fn section_type(&self) -> SectionType {
+        SectionType::from_u32(self.sh_type)
+    }
+
+    /// The attributes of the section
+    pub fn flags(&self) -> SectionFlags {
+        SectionFlags(self.sh_flags)
+    }
+
+    /// Name of the section type as printed by readelf
+    pub fn type_name(&self) -> Cow<'static, str> {
+        self.section_type().name()
+    }
+
+    /// Attributes of the section as printed by readelf, ex: `AX`
+    pub fn flags_string(&self) -> String {
+        self.flags().to_string()
+    }
+
+    /// Bytes of the section stored in the file
+    ///
+    /// Returns None for SHT_NOBITS sections or if the section lies
+    /// outside of the given data.
+    pub fn data<'a>(&self, data: &'a [u8]) -> Option<&'a [u8]> {
+        if self.sh_type == SHT::NOBITS {
+            return None;
+        }
+        let start = usize::try_from(self.sh_offset).ok()?;
+        let size = usize::try_from(self.sh_size).ok()?;
+        data.get(start..start.checked_add(size)?)
+    }
+
+    /// Resolve the section name in the given section name string table
+    ///
+    /// The name is the bytes before the nul terminator, whatever their
+    /// encoding.
+    pub fn name<'a>(&self, strtab: &StrTab<'a>) -> Result<ByteStr<'a>> {
+        strtab.name(self.sh_name as usize)
+    }
+}
+
+/// Iterator over the section header table of an ELF64 file
+///
+/// The table is located with `e_shoff`, each entry is `e_shentsize`
+/// bytes long and there are `e_shnum` of them, or `sh_size` of the first
+/// entry when `e_shnum` is 0.
+pub type SectionHeaders<'a> = Table<'a, Elf64_Shdr>;
+
+/// Returns an iterator over the section headers described by `header`
+///
+/// Fails if the table doesn't fit in the given data, if its offset is
+/// misaligned or if the entry size is smaller than `Elf64_Shdr`.
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64) -> Result<SectionHeaders<'a>> {
+    Table::parse(
+        data,
+        header.e_shoff,
+        header.e_shentsize as u64,
+        header.shnum(data),
+        Data::is_big_endian(&header.e_ident),
+    )
+}
+
+/// Returns the section header at `index` in the section header table
+pub fn get<'a>(data: &'a [u8], header: &x64, index: u32) -> Result<Cow<'a, Elf64_Shdr>> {
+    from_bytes(data, header)?
+        .get(index as usize)
+        .ok_or(Error::InvalidSectionIndex(index))
+}
+
+/// Returns the bytes of the section at `index` in the section header table
+///
+/// Fails if the index is invalid or if the section lies outside of the
+/// file. SHT_NOBITS sections are empty.
+pub fn get_data<'a>(data: &'a [u8], header: &x64, index: u32) -> Result<&'a [u8]> {
+    let shdr = get(data, header, index)?;
+    if shdr.sh_type == SHT::NOBITS {
+        return Ok(&[]);
+    }
+    shdr.data(data).ok_or(Error::OutOfBounds {
+        offset: shdr.sh_offset,
+        size: shdr.sh_size,
+    })
+}
+
+/// Returns the section name string table pointed by `e_shstrndx`
+///
+/// The table is empty if the file has no section name string table.
+pub fn names<'a>(data: &'a [u8], header: &x64) -> Result<StrTab<'a>> {
+    match header.shstrndx(data) {
+        0 => Ok(StrTab::default()),
+        index => get_data(data, header, index).map(StrTab::new),
+    }
+}
+
+/// A section header with its index and name, a line of a `--format`
+/// listing
+#[derive(Debug, Clone)]
+pub struct SectionEntry<'a> {
+    /// index of the section
+    pub index: usize,
+    /// name of the section, empty if it can't be read, not always UTF-8
+    pub name: ByteStr<'a>,
+    /// the header, in the byte order of the host
+    pub shdr: Cow<'a, Elf64_Shdr>,
+    /// name of the type in the file, ex: `ARM_EXIDX`
+    pub type_name: Cow<'static, str>,
+}
+
+/// The sections of a file with their index and name
+///
+/// Fails if the section header table can't be parsed.
+pub fn entries<'a>(data: &'a [u8], header: &x64) -> Result<Vec<SectionEntry<'a>>> {
+    let names = names(data, header).unwrap_or_default();
+    Ok(from_bytes(data, header)?
+        .enumerate()
+        .map(|(index, shdr)| SectionEntry {
+            index,
+            name: shdr.name(&names).unwrap_or_default(),
+            type_name: registry::section_type_name(header, shdr.sh_type),
+            shdr,
+        })
+        .collect())
+}
+
+impl Record for SectionEntry<'_> {
+    const FIELDS: &'static [&'static str] = &[
+        "index", "name", "type", "flags", "addr", "vaddr", "offset", "size", "entsize", "link",
+        "info", "align",
+    ];
+
+    fn field(&self, name: &str) -> Option<Value<'_>> {
+        Some(match name {
+            "index" => Value::from(self.index as u64),
+            "name" => Value::from(self.name),
+            "type" => Value::from(self.type_name.as_ref()),
+            "flags" => Value::from(self.shdr.flags_string()),
+            "addr" | "vaddr" => Value::from(self.shdr.sh_addr),
+            "offset" => Value::from(self.shdr.sh_offset),
+            "size" => Value::from(self.shdr.sh_size),
+            "entsize" => Value::from(self.shdr.sh_entsize),
+            "link" => Value::from(self.shdr.sh_link as u64),
+            "info" => Value::from(self.shdr.sh_info as u64),
+            "align" => Value::from(self.shdr.sh_addralign),
+            _ => return None,
+        })
+    }
+}
+
+/// Section header table ready to be printed like `readelf -S`
+pub struct SectionHeaderTable<'a> {
+    /// the whole file, used to resolve the section names
+    pub data: &'a [u8],
+    /// the file header
+    pub header: &'a x64,
+    /// write the names, types and addresses with ANSI colors
+    pub color: bool,
+    /// print the names whole like `readelf -W`, instead of cutting them
+    /// to [`NAME_WIDTH`] characters
+    pub wide: bool,
+}
+
+/// Width of the section names without `--wide`
+pub const NAME_WIDTH: usize = 17;
+
+impl fmt::Display for SectionHeaderTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shdrs = match from_bytes(self.data, self.header) {
+            Ok(shdrs) => shdrs,
+            Err(err) => return writeln!(f, "Warning: invalid section header table: {}", err),
+        };
+        let shnum = self.header.shnum(self.data);
+        if shnum == 0 {
+            return writeln!(f, "There are no sections in this file.");
+        }
+        let strtab = names(self.data, self.header).unwrap_or_default();
+        writeln!(
+            f,
+            "There are {} section headers, starting at offset {:#x}:",
+            shnum, self.header.e_shoff
+        )?;
+        writeln!(f)?;
+        writeln!(f, "Section Headers:")?;
+        let mut table = TextTable::new(self.color)
+            .indent(2)
+            .column("[Nr]", Align::Left, None)
+            .column("Name", Align::Left, Some(Color::Green))
+            .limit((!self.wide).then_some(NAME_WIDTH))
+            .column("Type", Align::Left, Some(Color::Cyan))
+            .column("Address", Align::Left, Some(Color::Yellow))
+            .column("Off", Align::Left, None)
+            .column("Size", Align::Left, None)
+            .column("ES", Align::Left, None)
+            .column("Flg", Align::Right, None)
+            .column("Lk", Align::Right, None)
+            .column("Inf", Align::Right, None)
+            .column("Al", Align::Right, None);
+        for (index, shdr) in shdrs.enumerate() {
+            table.row([
+                format!("[{:>2}]", index),
+                shdr.name(&strtab).unwrap_or(CORRUPT).to_string(),
+                registry::section_type_name(self.header, shdr.sh_type).into_owned(),
+                format!("{:016x}", shdr.sh_addr),
+                format!("{:06x}", shdr.sh_offset),
+                format!("{:06x}", shdr.sh_size),
+                format!("{:02x}", shdr.sh_entsize),
+                shdr.flags().to_string(),
+                shdr.sh_link.to_string(),
+                shdr.sh_info.to_string(),
+                shdr.sh_addralign.to_string(),
+            ]);
+        }
+        write!(f, "{}", table)?;
+        writeln!(f, "Key to Flags:")?;
+        writeln!(
+            f,
+            "  W (write), A (alloc), X (execute), M (merge), S (strings), I (info),"
+        )?;
+        writeln!(
+            f,
+            "  L (link order), O (extra OS processing required), G (group), T (TLS),"
+        )?;
+        writeln!(
+            f,
+            "  C (compressed), x (unknown), o (OS specific), E (exclude),"
+        )?;
+        writeln!(f, "  p (processor specific)")
+    }
+}
+
+impl fmt::Display for Elf64_Shdr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<15} {:016x} {:06x} {:06x} {:02x} {:>3} {:>2} {:>3} {:>2}",
+            self.section_type(),
+            self.sh_addr,
+            self.sh_offset,
+            self.sh_size,
+            self.sh_entsize,
+            self.flags(),
+            self.sh_link,
+            self.sh_info,
+            self.sh_addralign
+        )
+    }
+}
+
+impl ToJson for Elf64_Shdr {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("sh_name", Json::from(self.sh_name)),
+            ("sh_type", Json::from(self.sh_type)),
+            ("type", Json::from(self.type_name())),
+            ("sh_flags", Json::from(self.sh_flags)),
+            ("flags", Json::from(self.flags_string())),
+            ("flag_names", Json::from(self.flags().names())),
+            ("sh_addr", Json::from(self.sh_addr)),
+            ("sh_offset", Json::from(self.sh_offset)),
+            ("sh_size", Json::from(self.sh_size)),
+            ("sh_link", Json::from(self.sh_link)),
+            ("sh_info", Json::from(self.sh_info)),
+            ("sh_addralign", Json::from(self.sh_addralign)),
+            ("sh_entsize", Json::from(self.sh_entsize)),
+        ])
+    }
+}
+
+impl ToJson for SectionHeaderTable<'_> {
+    fn to_json(&self) -> Json {
+        let shdrs = match from_bytes(self.data, self.header) {
+            Ok(shdrs) => shdrs,
+            Err(err) => return Json::error(err),
+        };
+        let strtab = names(self.data, self.header).unwrap_or_default();
+        Json::array(shdrs.map(|shdr| {
+            let mut members = vec![("name".to_string(), Json::from(shdr.name(&strtab).ok()))];
+            if let Json::Object(fields) = shdr.to_json() {
+                members.extend(fields);
+            }
+            if let Some((_, kind)) = members.iter_mut().find(|(key, _)| key == "type") {
+                *kind = Json::from(registry::section_type_name(self.header, shdr.sh_type));
+            }
+            Json::Object(members)
+        }))
+    }
+}
@@ -0,0 +1,256 @@
+//! Where the bytes of a file go, like `size(1)` and a small `bloaty`
+//!
+//! The allocated sections are summed in the text, data and bss columns of
+//! `size -B`, every section is listed with its share of the file and the
+//! functions of the symbol table are sorted from the largest one.
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::json::{Json, ToJson};
+use crate::Result;
+use core::fmt;
+
+use super::section::{self, SHF, SHN, SHT};
+use super::symbols::{self, STB, STT};
+use super::x64::x64;
+
+/// The size of a section in the file and in memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SectionSize<'a> {
+    /// name of the section
+    pub name: ByteStr<'a>,
+    /// bytes in the file, 0 for SHT_NOBITS
+    pub file_size: u64,
+    /// bytes in memory, 0 for the sections that aren't allocated
+    pub vm_size: u64,
+}
+
+/// A function of the symbol table with its size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FunctionSize<'a> {
+    /// name of the symbol
+    pub name: ByteStr<'a>,
+    /// address of the first instruction
+    pub address: u64,
+    /// `st_size` of the symbol
+    pub size: u64,
+    /// name of the section holding the function
+    pub section: ByteStr<'a>,
+}
+
+/// The size report of an ELF64 file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sizes<'a> {
+    /// size of the file
+    pub file_size: u64,
+    /// the allocated sections that can't be written: code and read only data
+    pub text: u64,
+    /// the allocated sections with content that can be written
+    pub data: u64,
+    /// the allocated SHT_NOBITS sections
+    pub bss: u64,
+    /// every section but the null one, from the largest in the file
+    pub sections: Vec<SectionSize<'a>>,
+    /// the functions with a size, from the largest
+    pub functions: Vec<FunctionSize<'a>>,
+}
+
+/// `part` as a percentage of `total` with one decimal
+struct Percent(u64, u64);
+
+impl fmt::Display for Percent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tenths = match self.1 {
+            0 => 0,
+            total => (self.0 as u128 * 1000 / total as u128) as u64,
+        };
+        let text = format!("{}.{}%", tenths / 10, tenths % 10);
+        f.pad(&text)
+    }
+}
+
+/// Measure the sections and the functions of the file
+///
+/// The functions come from `.symtab`, or from `.dynsym` in stripped
+/// files: the defined STT_FUNC and STT_GNU_IFUNC symbols with a non zero
+/// `st_size`, one per address, the global name before the weak and local
+/// aliases. Fails if the section header table or the symbol table can't
+/// be parsed.
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64) -> Result<Sizes<'a>> {
+    let names = section::names(data, header).unwrap_or_default();
+    let shdrs: Vec<_> = section::from_bytes(data, header)?.collect();
+    let section_name = |index: u16| {
+        shdrs
+            .get(index as usize)
+            .and_then(|shdr| shdr.name(&names).ok())
+            .unwrap_or_default()
+    };
+
+    let mut sizes = Sizes {
+        file_size: data.len() as u64,
+        ..Sizes::default()
+    };
+    for shdr in shdrs.iter().filter(|shdr| shdr.sh_type != SHT::NULL) {
+        let allocated = shdr.sh_flags & SHF::ALLOC != 0;
+        match (allocated, shdr.sh_type, shdr.sh_flags & SHF::WRITE != 0) {
+            (false, _, _) => {}
+            (true, SHT::NOBITS, _) => sizes.bss = sizes.bss.saturating_add(shdr.sh_size),
+            (true, _, true) => sizes.data = sizes.data.saturating_add(shdr.sh_size),
+            (true, _, false) => sizes.text = sizes.text.saturating_add(shdr.sh_size),
+        }
+        sizes.sections.push(SectionSize {
+            name: shdr.name(&names).unwrap_or_default(),
+            file_size: match shdr.sh_type {
+                SHT::NOBITS => 0,
+                _ => shdr.sh_size,
+            },
+            vm_size: if allocated { shdr.sh_size } else { 0 },
+        });
+    }
+    sizes
+        .sections
+        .sort_by_key(|section| core::cmp::Reverse((section.file_size, section.vm_size)));
+
+    let table = [SHT::SYMTAB, SHT::DYNSYM]
+        .iter()
+        .find_map(|sh_type| shdrs.iter().find(|shdr| shdr.sh_type == *sh_type));
+    if let Some(table) = table {
+        let strtab = symbols::strtab(data, header, table)?;
+        let mut functions = Vec::new();
+        for sym in symbols::from_bytes(data, header, table)? {
+            let function = matches!(sym.kind(), STT::FUNC | STT::GNU_IFUNC);
+            let defined = sym.st_shndx != SHN::UNDEF && sym.st_shndx < SHN::LORESERVE;
+            if !function || !defined || sym.st_size == 0 {
+                continue;
+            }
+            let rank = match sym.bind() {
+                STB::GLOBAL => 0,
+                STB::WEAK => 1,
+                _ => 2,
+            };
+            let size = FunctionSize {
+                name: sym.name(&strtab).unwrap_or_default(),
+                address: sym.st_value,
+                size: sym.st_size,
+                section: section_name(sym.st_shndx),
+            };
+            functions.push((rank, size));
+        }
+        functions.sort_by_key(|(rank, function)| (function.address, *rank));
+        functions.dedup_by_key(|(_, function)| function.address);
+        sizes.functions = functions
+            .into_iter()
+            .map(|(_, function)| function)
+            .collect();
+        sizes
+            .functions
+            .sort_by(|a, b| b.size.cmp(&a.size).then(a.name.cmp(&b.name)));
+    }
+    Ok(sizes)
+}
+
+impl fmt::Display for Sizes<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.text.saturating_add(self.data).saturating_add(self.bss);
+        writeln!(f, "      text       data        bss        dec        hex")?;
+        writeln!(
+            f,
+            "{:>10} {:>10} {:>10} {:>10} {:>10x}",
+            self.text, self.data, self.bss, total, total
+        )?;
+        writeln!(f)?;
+        if self.sections.is_empty() {
+            writeln!(f, "There are no sections in this file.")?;
+        } else {
+            let sections = self
+                .sections
+                .iter()
+                .fold(0u64, |sum, section| sum.saturating_add(section.file_size));
+            writeln!(
+                f,
+                "Sections: {} bytes of {} in the file",
+                sections, self.file_size
+            )?;
+            writeln!(f, "  Name                   File size    VM size   File %")?;
+            for section in &self.sections {
+                writeln!(
+                    f,
+                    "  {:<20} {:>11} {:>10} {:>8}",
+                    section.name,
+                    section.file_size,
+                    section.vm_size,
+                    Percent(section.file_size, self.file_size)
+                )?;
+            }
+        }
+        writeln!(f)?;
+        if self.functions.is_empty() {
+            return writeln!(f, "There are no sized functions in the symbol table.");
+        }
+        let functions = self
+            .functions
+            .iter()
+            .fold(0u64, |sum, function| sum.saturating_add(function.size));
+        writeln!(
+            f,
+            "Functions: {} functions, {} bytes",
+            self.functions.len(),
+            functions
+        )?;
+        writeln!(
+            f,
+            "        Size  Share  Address          Section          Name"
+        )?;
+        for function in &self.functions {
+            writeln!(
+                f,
+                "  {:>10} {:>6}  {:016x} {:<16} {}",
+                function.size,
+                Percent(function.size, functions),
+                function.address,
+                function.section,
+                function.name
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for SectionSize<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("name", Json::from(self.name)),
+            ("file_size", Json::from(self.file_size)),
+            ("vm_size", Json::from(self.vm_size)),
+        ])
+    }
+}
+
+impl ToJson for FunctionSize<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("name", Json::from(self.name)),
+            ("address", Json::from(self.address)),
+            ("size", Json::from(self.size)),
+            ("section", Json::from(self.section)),
+        ])
+    }
+}
+
+impl ToJson for Sizes<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("file_size", Json::from(self.file_size)),
+            ("text", Json::from(self.text)),
+            ("data", Json::from(self.data)),
+            ("bss", Json::from(self.bss)),
+            (
+                "sections",
+                Json::array(self.sections.iter().map(SectionSize::to_json)),
+            ),
+            (
+                "functions",
+                Json::array(self.functions.iter().map(FunctionSize::to_json)),
+            ),
+        ])
+    }
+}
@@ -0,0 +1,230 @@
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::json::{Json, ToJson};
+use crate::Result;
+use core::fmt;
+
+use super::image::LoadImage;
+use super::program::{self, PT};
+use super::section::{self, SHF, SHT};
+use super::x64::x64;
+
+/// How the characters of the strings are encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    /// one byte per character, `strings -e s`
+    #[default]
+    Ascii,
+    /// two bytes per character, little endian, `strings -e l`
+    Utf16Le,
+}
+
+/// A run of printable characters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hit<'a> {
+    /// file offset of the first byte
+    pub offset: u64,
+    /// virtual address of the first byte, None when it isn't loaded
+    pub address: Option<u64>,
+    /// name of the section holding the string, None outside of the sections
+    pub section: Option<ByteStr<'a>>,
+    /// the characters, without the terminator
+    pub text: String,
+}
+
+/// The printable strings of an ELF64 file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Strings<'a> {
+    /// encoding looked for
+    pub encoding: Encoding,
+    /// the strings in increasing offset order
+    pub hits: Vec<Hit<'a>>,
+}
+
+/// The printable characters of `strings(1)`: ASCII graphic characters,
+/// space and tab
+fn printable(c: u16) -> bool {
+    c == u16::from(b'\t') || (0x20..0x7f).contains(&c)
+}
+
+/// Find the runs of at least `min_len` printable characters in `bytes`,
+/// as (offset in `bytes`, text) pairs
+///
+/// Like strings(1), the scan goes on at the next byte after a character
+/// that isn't printable, so UTF-16 strings are found at any alignment.
+fn scan(bytes: &[u8], min_len: usize, encoding: Encoding) -> Vec<(usize, String)> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    let mut text = String::new();
+    let mut at = 0;
+    while at < bytes.len() {
+        let (c, width) = match encoding {
+            Encoding::Ascii => (u16::from(bytes[at]), 1),
+            Encoding::Utf16Le => match bytes.get(at..at + 2) {
+                Some([low, high]) => (u16::from_le_bytes([*low, *high]), 2),
+                _ => break,
+            },
+        };
+        if printable(c) {
+            if text.is_empty() {
+                start = at;
+            }
+            text.push(c as u8 as char);
+            at += width;
+            continue;
+        }
+        if text.len() >= min_len.max(1) {
+            runs.push((start, core::mem::take(&mut text)));
+        }
+        text.clear();
+        at += 1;
+    }
+    if text.len() >= min_len.max(1) {
+        runs.push((start, text));
+    }
+    runs
+}
+
+/// Extract the printable strings of the file, like `strings -a`
+///
+/// The whole file is scanned, split at the boundaries of the sections so
+/// that every string belongs to at most one of them. The address of a
+/// string is found with its allocated section, or with the PT_LOAD
+/// segment holding it when the file has no section header table. A
+/// `min_len` of 0 is taken as 1.
+pub fn from_bytes<'a>(
+    data: &'a [u8],
+    header: &x64,
+    min_len: usize,
+    encoding: Encoding,
+) -> Result<Strings<'a>> {
+    let file_size = data.len() as u64;
+    let names = section::names(data, header).unwrap_or_default();
+    // (start, end, name, address of start) of the sections holding bytes
+    let sections: Vec<_> = section::from_bytes(data, header)?
+        .filter(|shdr| shdr.sh_type != SHT::NOBITS && shdr.sh_size != 0)
+        .filter(|shdr| shdr.sh_offset < file_size)
+        .map(|shdr| {
+            let end = shdr.sh_offset.saturating_add(shdr.sh_size).min(file_size);
+            let address = (shdr.sh_flags & SHF::ALLOC != 0).then_some(shdr.sh_addr);
+            (shdr.sh_offset, end, shdr.name(&names).ok(), address)
+        })
+        .collect();
+    let segments: Vec<_> = program::from_bytes(data, header)?
+        .filter(|phdr| phdr.p_type == PT::LOAD)
+        .map(|phdr| (phdr.p_offset, phdr.p_filesz, phdr.p_vaddr))
+        .collect();
+
+    let mut boundaries: Vec<u64> = sections
+        .iter()
+        .flat_map(|(start, end, _, _)| [*start, *end])
+        .chain([0, file_size])
+        .collect();
+    boundaries.sort_unstable();
+    boundaries.dedup();
+
+    let mut hits = Vec::new();
+    for range in boundaries.windows(2) {
+        let (start, end) = (range[0], range[1]);
+        let section = sections
+            .iter()
+            .find(|(first, last, _, _)| start >= *first && start < *last);
+        let address = |offset: u64| match section {
+            Some((first, _, _, address)) => {
+                address.map(|address| address.wrapping_add(offset - first))
+            }
+            None if sections.is_empty() => segments
+                .iter()
+                .find(|(first, size, _)| offset >= *first && offset - first < *size)
+                .map(|(first, _, vaddr)| vaddr.wrapping_add(offset - first)),
+            None => None,
+        };
+        let bytes = data.get(start as usize..end as usize).unwrap_or_default();
+        for (at, text) in scan(bytes, min_len, encoding) {
+            let offset = start + at as u64;
+            hits.push(Hit {
+                offset,
+                address: address(offset),
+                section: section.and_then(|(_, _, name, _)| *name),
+                text,
+            });
+        }
+    }
+    Ok(Strings { encoding, hits })
+}
+
+/// Extract the printable strings of a memory image, a raw flash dump or
+/// an Intel HEX file
+///
+/// Each mapping is scanned on its own, the offsets are counted from the
+/// base of the image and the strings have no section.
+pub fn from_image(image: &LoadImage, min_len: usize, encoding: Encoding) -> Strings<'static> {
+    let mut hits = Vec::new();
+    for mapping in &image.mappings {
+        let start = mapping.vaddr.wrapping_sub(image.base);
+        let bytes = image.get(mapping.vaddr, mapping.filesz as usize);
+        for (at, text) in scan(bytes.unwrap_or_default(), min_len, encoding) {
+            hits.push(Hit {
+                offset: start + at as u64,
+                address: Some(mapping.vaddr.wrapping_add(at as u64)),
+                section: None,
+                text,
+            });
+        }
+    }
+    hits.sort_by_key(|hit| hit.offset);
+    Strings { encoding, hits }
+}
+
+impl fmt::Display for Strings<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.hits.is_empty() {
+            return writeln!(f, "There are no strings in this file.");
+        }
+        writeln!(f, "  Offset   Address          Section              String")?;
+        for hit in &self.hits {
+            let address = match hit.address {
+                Some(address) => format!("{:016x}", address),
+                None => String::new(),
+            };
+            writeln!(
+                f,
+                "  {:08x} {:<16} {:<20} {}",
+                hit.offset,
+                address,
+                hit.section.unwrap_or_default(),
+                hit.text
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Encoding::Ascii => "ascii",
+            Encoding::Utf16Le => "utf-16le",
+        })
+    }
+}
+
+impl ToJson for Hit<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("offset", Json::from(self.offset)),
+            ("address", Json::from(self.address)),
+            ("section", Json::from(self.section)),
+            ("string", Json::from(self.text.as_str())),
+        ])
+    }
+}
+
+impl ToJson for Strings<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("encoding", Json::from(self.encoding.to_string())),
+            ("strings", Json::array(self.hits.iter().map(Hit::to_json))),
+        ])
+    }
+}
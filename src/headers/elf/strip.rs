@@ -0,0 +1,192 @@
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::ToBytes;
+use crate::{Error, Result};
+
+use super::data::Data;
+use super::description::{align_up, grow};
+use super::program;
+use super::section::{self, Elf64_Shdr, SHF, SHN, SHT};
+use super::symbols::{self, Elf64_Sym};
+use super::x64::x64;
+
+/// Whether `strip` removes the section with the given name
+///
+/// Only the static symbol table, its strings and the debug sections are
+/// removed, the file keeps everything needed to load and link it.
+pub fn is_removed(name: ByteStr<'_>, shdr: &Elf64_Shdr) -> bool {
+    shdr.sh_flags & SHF::ALLOC == 0
+        && (name == ".symtab" || name == ".strtab" || name.as_bytes().starts_with(b".debug_"))
+}
+
+/// Whether `sh_info` holds a section index
+fn info_is_section(shdr: &Elf64_Shdr) -> bool {
+    shdr.sh_flags & SHF::INFO_LINK != 0 || shdr.sh_type == SHT::REL || shdr.sh_type == SHT::RELA
+}
+
+/// Returns a copy of the file without its static symbols and debug sections
+///
+/// The loaded content of the file stays at the same offsets. The kept
+/// sections that are not loaded are packed after it, followed by the new
+/// section header table. Section indexes are renumbered in the section
+/// headers, in the file header and in the dynamic symbols.
+///
+/// Fails if a kept section still links to a removed one, as the
+/// relocations of an object file do with `.symtab`.
+pub fn strip(data: &[u8], header: &x64) -> Result<Vec<u8>> {
+    if header.shnum(data) == 0 {
+        return Err(Error::InvalidHeaderField {
+            field: "e_shnum",
+            expected: 1,
+            found: 0,
+        });
+    }
+    let shstrndx = header.shstrndx(data);
+    let big_endian = Data::is_big_endian(&header.e_ident);
+    let names = section::names(data, header)?;
+    let sections: Vec<Elf64_Shdr> = section::from_bytes(data, header)?
+        .map(|shdr| *shdr)
+        .collect();
+
+    // old index to new index, None for the removed sections
+    let mut next = 0u32;
+    let indexes: Vec<Option<u32>> = sections
+        .iter()
+        .enumerate()
+        .map(|(index, shdr)| {
+            let name = shdr.name(&names).unwrap_or_default();
+            if index != 0 && index != shstrndx as usize && is_removed(name, shdr) {
+                None
+            } else {
+                next += 1;
+                Some(next - 1)
+            }
+        })
+        .collect();
+    let remap = |by: usize, index: u32| -> Result<u32> {
+        match indexes.get(index as usize) {
+            Some(Some(index)) => Ok(*index),
+            Some(None) => Err(Error::SectionInUse {
+                section: index,
+                by: by as u32,
+            }),
+            None => Err(Error::InvalidSectionIndex(index)),
+        }
+    };
+
+    // everything before the end of the segments and loaded sections stays in place
+    let mut end = (x64::SIZE as u64).max(
+        header
+            .e_phoff
+            .saturating_add(header.phnum(data) * header.e_phentsize as u64),
+    );
+    for phdr in program::from_bytes(data, header)? {
+        end = end.max(phdr.p_offset.saturating_add(phdr.p_filesz));
+    }
+    for shdr in &sections {
+        if shdr.sh_flags & SHF::ALLOC != 0 && shdr.sh_type != SHT::NOBITS {
+            end = end.max(shdr.sh_offset.saturating_add(shdr.sh_size));
+        }
+    }
+    let end = usize::try_from(end)
+        .ok()
+        .filter(|end| *end <= data.len())
+        .ok_or(Error::OutOfBounds {
+            offset: 0,
+            size: end,
+        })?;
+    let mut out = data[..end].to_vec();
+    // the kept sections and headers are packed, they can't take more room
+    // than the file plus their alignment
+    let limit = (data.len() as u64).saturating_mul(2);
+
+    let mut kept = Vec::new();
+    for (index, shdr) in sections.iter().enumerate() {
+        if indexes[index].is_none() {
+            continue;
+        }
+        let mut shdr = *shdr;
+        if shdr.sh_link != 0 {
+            shdr.sh_link = remap(index, shdr.sh_link)?;
+        }
+        if shdr.sh_info != 0 && info_is_section(&shdr) {
+            shdr.sh_info = remap(index, shdr.sh_info)?;
+        }
+        let inside = shdr.sh_offset.saturating_add(shdr.sh_size) <= end as u64;
+        if index != 0 && shdr.sh_type != SHT::NOBITS && !inside {
+            let bytes = shdr.data(data).ok_or(Error::OutOfBounds {
+                offset: shdr.sh_offset,
+                size: shdr.sh_size,
+            })?;
+            let offset = align_up(out.len() as u64, shdr.sh_addralign);
+            grow(&mut out, offset, limit)?;
+            out.try_reserve(bytes.len())
+                .map_err(|_| Error::OutOfBounds {
+                    offset,
+                    size: shdr.sh_size,
+                })?;
+            out.extend_from_slice(bytes);
+            shdr.sh_offset = offset;
+        }
+        if shdr.sh_type == SHT::DYNSYM && shdr.sh_entsize as usize >= Elf64_Sym::SIZE {
+            let symbols = symbols::from_bytes(data, header, &sections[index])?;
+            for (number, symbol) in symbols.enumerate() {
+                let shndx = symbol.st_shndx;
+                if shndx == SHN::UNDEF || shndx >= SHN::LORESERVE {
+                    continue;
+                }
+                let shndx = remap(index, shndx as u32)? as u16;
+                let bytes = if big_endian {
+                    shndx.to_be_bytes()
+                } else {
+                    shndx.to_le_bytes()
+                };
+                // st_shndx is at offset 6 of Elf64_Sym
+                let field = (number as u64)
+                    .checked_mul(shdr.sh_entsize)
+                    .and_then(|at| at.checked_add(shdr.sh_offset)?.checked_add(6))
+                    .and_then(|field| usize::try_from(field).ok());
+                if let Some(field) =
+                    field.and_then(|field| out.get_mut(field..field.checked_add(2)?))
+                {
+                    field.copy_from_slice(&bytes);
+                }
+            }
+        }
+        kept.push(shdr);
+    }
+
+    // the counts too big for the file header go to the first section
+    let mut header = *header;
+    let shstrndx = remap(0, shstrndx)?;
+    header.e_shnum = match kept.len() {
+        shnum if shnum >= SHN::LORESERVE as usize => {
+            kept[0].sh_size = shnum as u64;
+            0
+        }
+        shnum => {
+            kept[0].sh_size = 0;
+            shnum as u16
+        }
+    };
+    header.e_shstrndx = match shstrndx {
+        index if index >= SHN::LORESERVE as u32 => {
+            kept[0].sh_link = index;
+            SHN::XINDEX
+        }
+        index => {
+            kept[0].sh_link = 0;
+            index as u16
+        }
+    };
+
+    let e_shoff = align_up(out.len() as u64, 8);
+    grow(&mut out, e_shoff, limit)?;
+    for shdr in &kept {
+        out.extend_from_slice(&shdr.to_bytes(big_endian));
+    }
+    header.e_shoff = e_shoff;
+    header.e_shentsize = Elf64_Shdr::SIZE as u16;
+    out[..x64::SIZE].copy_from_slice(&header.to_bytes(big_endian));
+    Ok(out)
+}
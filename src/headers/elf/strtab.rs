@@ -0,0 +1,87 @@
+use crate::utils::bytestr::ByteStr;
+use crate::{Error, Result};
+
+/// Printed in place of the names whose offset is outside of their table
+pub const CORRUPT: ByteStr<'static> = ByteStr::new(b"<corrupt>");
+
+/// String table section
+///
+/// String table sections hold null-terminated character sequences.
+/// Section, symbol and dynamic entries reference their names with an
+/// offset into one of those tables.
+/// Sources:
+/// * <https://www.man7.org/linux/man-pages/man5/elf.5.html>
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StrTab<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> StrTab<'a> {
+    /// Wrap the bytes of a string table section
+    pub fn new(data: &'a [u8]) -> Self {
+        StrTab { data }
+    }
+
+    /// Returns the string starting at `offset`, without its ending nul byte
+    ///
+    /// Fails if the offset is outside of the table, if the string isn't
+    /// terminated or if it isn't valid UTF-8.
+    pub fn get(&self, offset: usize) -> Result<&'a str> {
+        let bytes = self.get_bytes(offset)?;
+        core::str::from_utf8(bytes).map_err(|_| Error::InvalidUtf8(offset as u64))
+    }
+
+    /// Returns the name starting at `offset`, whatever its encoding
+    ///
+    /// Fails if the offset is outside of the table or if the name isn't
+    /// terminated, never because of its bytes.
+    pub fn name(&self, offset: usize) -> Result<ByteStr<'a>> {
+        self.get_bytes(offset).map(ByteStr::new)
+    }
+
+    /// Returns the raw bytes of the string starting at `offset`
+    pub fn get_bytes(&self, offset: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(offset..)
+            .ok_or(Error::InvalidStringOffset(offset as u64))?;
+        let end = find_nul(bytes).ok_or(Error::UnterminatedString(offset as u64))?;
+        Ok(&bytes[..end])
+    }
+
+    /// The bytes of the whole table
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Size of the table in bytes
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Whether the table is empty
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+/// Position of the first nul byte, tested 8 bytes at a time
+///
+/// The symbol names of C++ and Rust programs are long, a byte per byte
+/// search was most of the time spent resolving them.
+fn find_nul(bytes: &[u8]) -> Option<usize> {
+    const LOW: u64 = 0x0101_0101_0101_0101;
+    const HIGH: u64 = 0x8080_8080_8080_8080;
+    let mut chunks = bytes.chunks_exact(8);
+    for (index, chunk) in chunks.by_ref().enumerate() {
+        let word = u64::from_le_bytes(chunk.try_into().unwrap_or_default());
+        // sets the high bit of the lowest zero byte, and maybe of later ones
+        let zero = word.wrapping_sub(LOW) & !word & HIGH;
+        if zero != 0 {
+            return Some(index * 8 + zero.trailing_zeros() as usize / 8);
+        }
+    }
+    let rest = chunks.remainder();
+    let position = rest.iter().position(|b| *b == 0)?;
+    Some(bytes.len() - rest.len() + position)
+}
@@ -0,0 +1,496 @@
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::demangle::{demangle, try_demangle};
+use crate::utils::json::{Json, ToJson};
+use crate::utils::table::{Align, Color, Table as TextTable};
+use crate::utils::{impl_swap_bytes, impl_to_bytes};
+use crate::{Error, Result};
+use alloc::borrow::Cow;
+use core::fmt::{self, Write};
+use core::mem::size_of;
+
+use super::data::Data;
+use super::dynamic::{self, DT};
+use super::hash;
+use super::program;
+use super::section::{self, Elf64_Shdr, SHF, SHN, SHT};
+use super::strtab::{StrTab, CORRUPT};
+use super::symver::{self, SymbolVersions};
+use super::table::Table;
+use super::x64::x64;
+
+/// Define the binding of a symbol
+///
+/// STB define possible values for the upper four bits of `st_info`
+pub struct STB {}
+impl STB {
+    /// Not visible outside the object file
+    pub const LOCAL: u8 = 0;
+    /// Visible to all object files being combined
+    pub const GLOBAL: u8 = 1;
+    /// Global symbol of lower precedence
+    pub const WEAK: u8 = 2;
+    /// Unique symbol in the whole process (GNU extension)
+    pub const GNU_UNIQUE: u8 = 10;
+    /// Start of the OS specific range
+    pub const LOOS: u8 = 10;
+    /// End of the OS specific range
+    pub const HIOS: u8 = 12;
+    /// Start of the processor specific range
+    pub const LOPROC: u8 = 13;
+    /// End of the processor specific range
+    pub const HIPROC: u8 = 15;
+}
+
+/// Define the type of a symbol
+///
+/// STT define possible values for the lower four bits of `st_info`
+pub struct STT {}
+impl STT {
+    /// Type not specified
+    pub const NOTYPE: u8 = 0;
+    /// Data object
+    pub const OBJECT: u8 = 1;
+    /// Function or other executable code
+    pub const FUNC: u8 = 2;
+    /// Section
+    pub const SECTION: u8 = 3;
+    /// Source file associated with the object file
+    pub const FILE: u8 = 4;
+    /// Uninitialized common block
+    pub const COMMON: u8 = 5;
+    /// Thread local storage entity
+    pub const TLS: u8 = 6;
+    /// Indirect function (GNU extension)
+    pub const GNU_IFUNC: u8 = 10;
+    /// Start of the OS specific range
+    pub const LOOS: u8 = 10;
+    /// End of the OS specific range
+    pub const HIOS: u8 = 12;
+    /// Start of the processor specific range
+    pub const LOPROC: u8 = 13;
+    /// End of the processor specific range
+    pub const HIPROC: u8 = 15;
+}
+
+/// Define the visibility of a symbol
+///
+/// STV define possible values for the lower two bits of `st_other`
+pub struct STV {}
+impl STV {
+    /// Visibility given by the binding
+    pub const DEFAULT: u8 = 0;
+    /// Processor specific hidden class
+    pub const INTERNAL: u8 = 1;
+    /// Not visible to other components
+    pub const HIDDEN: u8 = 2;
+    /// Visible but not preemptible
+    pub const PROTECTED: u8 = 3;
+}
+
+/// Symbol table entry of an ELF64 file
+///
+/// An object file's symbol table holds information needed to locate and
+/// relocate a program's symbolic definitions and references.
+/// Sources:
+/// * <https://www.man7.org/linux/man-pages/man5/elf.5.html>
+/// * <https://uclibc.org/docs/elf-64-gen.pdf>
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct Elf64_Sym {
+    /// offset of the symbol name in the linked string table
+    pub st_name: u32,
+    /// symbol type and binding
+    pub st_info: u8,
+    /// symbol visibility
+    pub st_other: u8,
+    /// index of the section the symbol is defined in
+    pub st_shndx: u16,
+    /// symbol value, usually an address
+    pub st_value: u64,
+    /// size of the object
+    pub st_size: u64,
+}
+
+impl_swap_bytes!(Elf64_Sym, st_name, st_shndx, st_value, st_size);
+
+impl_to_bytes!(Elf64_Sym, st_name, st_info, st_other, st_shndx, st_value, st_size,);
+
+impl Elf64_Sym {
+    pub const SIZE: usize = size_of::<Self>();
+
+    /// Binding of the symbol, one of the `STB` values
+    pub fn bind(&self) -> u8 {
+        self.st_info >> 4
+    }
+
+    /// Type of the symbol, one of the `STT` values
+    pub fn kind(&self) -> u8 {
+        self.st_info & 0xf
+    }
+
+    /// Visibility of the symbol, one of the `STV` values
+    pub fn visibility(&self) -> u8 {
+        self.st_other & 0x3
+    }
+
+    /// Name of the symbol binding as printed by readelf
+    pub fn bind_name(&self) -> Cow<'static, str> {
+        let name = match self.bind() {
+            STB::LOCAL => "LOCAL",
+            STB::GLOBAL => "GLOBAL",
+            STB::WEAK => "WEAK",
+            STB::GNU_UNIQUE => "UNIQUE",
+            bind @ STB::LOOS..=STB::HIOS => return Cow::Owned(format!("<OS specific>: {}", bind)),
+            bind @ STB::LOPROC..=STB::HIPROC => {
+                return Cow::Owned(format!("<processor specific>: {}", bind))
+            }
+            bind => return Cow::Owned(format!("<unknown>: {}", bind)),
+        };
+        Cow::Borrowed(name)
+    }
+
+    /// Name of the symbol type as printed by readelf
+    pub fn type_name(&self) -> Cow<'static, str> {
+        let name = match self.kind() {
+            STT::NOTYPE => "NOTYPE",
+            STT::OBJECT => "OBJECT",
+            STT::FUNC => "FUNC",
+            STT::SECTION => "SECTION",
+            STT::FILE => "FILE",
+            STT::COMMON => "COMMON",
+            STT::TLS => "TLS",
+            STT::GNU_IFUNC => "IFUNC",
+            kind @ STT::LOOS..=STT::HIOS => return Cow::Owned(format!("<OS specific>: {}", kind)),
+            kind @ STT::LOPROC..=STT::HIPROC => {
+                return Cow::Owned(format!("<processor specific>: {}", kind))
+            }
+            kind => return Cow::Owned(format!("<unknown>: {}", kind)),
+        };
+        Cow::Borrowed(name)
+    }
+
+    /// Name of the symbol visibility as printed by readelf
+    pub fn visibility_name(&self) -> &'static str {
+        match self.visibility() {
+            STV::DEFAULT => "DEFAULT",
+            STV::INTERNAL => "INTERNAL",
+            STV::HIDDEN => "HIDDEN",
+            _ => "PROTECTED",
+        }
+    }
+
+    /// Section index of the symbol as printed by readelf, ex: `UND`, `12`
+    pub fn index_name(&self) -> Cow<'static, str> {
+        let name = match self.st_shndx {
+            SHN::UNDEF => "UND",
+            SHN::ABS => "ABS",
+            SHN::COMMON => "COM",
+            _ => {
+                let mut name = String::new();
+                // writing to a String can't fail
+                let _ = self.write_index_name(&mut name);
+                return Cow::Owned(name);
+            }
+        };
+        Cow::Borrowed(name)
+    }
+
+    /// Write [`Self::index_name`] without allocating it
+    fn write_index_name(&self, out: &mut String) -> fmt::Result {
+        match self.st_shndx {
+            SHN::UNDEF => out.push_str("UND"),
+            SHN::ABS => out.push_str("ABS"),
+            SHN::COMMON => out.push_str("COM"),
+            index @ SHN::LORESERVE.. => write!(out, "RSV[{:#x}]", index)?,
+            index => write!(out, "{:3}", index)?,
+        }
+        Ok(())
+    }
+
+    /// Resolve the symbol name in the linked string table
+    ///
+    /// The name is the bytes before the nul terminator, whatever their
+    /// encoding.
+    pub fn name<'a>(&self, strtab: &StrTab<'a>) -> Result<ByteStr<'a>> {
+        strtab.name(self.st_name as usize)
+    }
+}
+
+/// Iterator over the entries of a symbol table section
+pub type Symbols<'a> = Table<'a, Elf64_Sym>;
+
+/// Returns an iterator over the symbols of the given SYMTAB or DYNSYM section
+///
+/// Fails if the section doesn't fit in the given data or if its entry
+/// size is smaller than `Elf64_Sym`.
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64, shdr: &Elf64_Shdr) -> Result<Symbols<'a>> {
+    if shdr.sh_entsize == 0 {
+        return Err(Error::InvalidEntrySize {
+            expected: Elf64_Sym::SIZE,
+            found: 0,
+        });
+    }
+    Table::parse(
+        data,
+        shdr.sh_offset,
+        shdr.sh_entsize,
+        shdr.sh_size / shdr.sh_entsize,
+        Data::is_big_endian(&header.e_ident),
+    )
+}
+
+/// Returns the string table linked to a symbol table section by `sh_link`
+pub fn strtab<'a>(data: &'a [u8], header: &x64, shdr: &Elf64_Shdr) -> Result<StrTab<'a>> {
+    section::get_data(data, header, shdr.sh_link).map(StrTab::new)
+}
+
+/// Locate the dynamic symbol table like the loader, through PT_DYNAMIC
+///
+/// For the files without section header table: DT_SYMTAB and DT_SYMENT
+/// give the table, [`hash::symbol_count`] its size and DT_STRTAB its
+/// string table. Returns a `.dynsym` section header describing the table,
+/// its `sh_link` is meaningless, and the string table. None if the file
+/// has no dynamic section, DT_SYMTAB or hash table.
+pub fn from_dynamic<'a>(data: &'a [u8], header: &x64) -> Option<(Elf64_Shdr, StrTab<'a>)> {
+    let dynamic = dynamic::from_bytes(data, header).ok()??;
+    let addr = dynamic.get(DT::SYMTAB)?;
+    let entsize = dynamic.get(DT::SYMENT).unwrap_or(Elf64_Sym::SIZE as u64);
+    let count = hash::symbol_count(data, header, &dynamic)?;
+    let shdr = Elf64_Shdr {
+        sh_type: SHT::DYNSYM,
+        sh_flags: SHF::ALLOC,
+        sh_addr: addr,
+        sh_offset: program::vaddr_to_offset(data, header, addr)?,
+        sh_size: count.checked_mul(entsize)?,
+        sh_info: 1,
+        sh_addralign: 8,
+        sh_entsize: entsize,
+        ..Elf64_Shdr::default()
+    };
+    Some((shdr, dynamic.strtab))
+}
+
+/// Returns the symbol table sections of the file, `.symtab` and `.dynsym`
+pub fn tables<'a>(data: &'a [u8], header: &x64) -> Result<Vec<Cow<'a, Elf64_Shdr>>> {
+    Ok(section::from_bytes(data, header)?
+        .filter(|shdr| shdr.sh_type == SHT::SYMTAB || shdr.sh_type == SHT::DYNSYM)
+        .collect())
+}
+
+impl fmt::Display for Elf64_Sym {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:016x} {:>5} {:<7} {:<6} {:<7} {:>4}",
+            self.st_value,
+            self.st_size,
+            self.type_name(),
+            self.bind_name(),
+            self.visibility_name(),
+            self.index_name()
+        )
+    }
+}
+
+/// Symbol tables ready to be printed like `readelf -s`
+pub struct SymbolTable<'a> {
+    /// the whole file, used to resolve the symbol names
+    pub data: &'a [u8],
+    /// the file header
+    pub header: &'a x64,
+    /// print the demangled C++ and Rust names like `readelf -C`
+    pub demangle: bool,
+    /// write the values, types and names with ANSI colors
+    pub color: bool,
+    /// print the names whole like `readelf -W`, instead of cutting them
+    /// to [`NAME_WIDTH`] characters
+    pub wide: bool,
+}
+
+/// Width of the symbol names, with their version, without `--wide`
+pub const NAME_WIDTH: usize = 21;
+
+/// A symbol table with its name and string table, None as name for the
+/// table found through PT_DYNAMIC
+type Source<'a> = (Cow<'a, Elf64_Shdr>, Option<ByteStr<'a>>, StrTab<'a>);
+
+impl<'a> SymbolTable<'a> {
+    /// The symbol table sections, or the dynamic symbol table the loader
+    /// uses when the file has no section headers
+    fn tables(&self) -> Result<Vec<Source<'a>>> {
+        let names = section::names(self.data, self.header).unwrap_or_default();
+        let mut tables: Vec<_> = tables(self.data, self.header)?
+            .into_iter()
+            .map(|shdr| {
+                let strtab = strtab(self.data, self.header, &shdr).unwrap_or_default();
+                let name = Some(shdr.name(&names).unwrap_or(CORRUPT));
+                (shdr, name, strtab)
+            })
+            .collect();
+        if tables.is_empty() {
+            if let Some((shdr, strtab)) = from_dynamic(self.data, self.header) {
+                tables.push((Cow::Owned(shdr), None, strtab));
+            }
+        }
+        Ok(tables)
+    }
+}
+
+impl fmt::Display for SymbolTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tables = match self.tables() {
+            Ok(tables) => tables,
+            Err(err) => return writeln!(f, "Warning: invalid section header table: {}", err),
+        };
+        if tables.is_empty() {
+            return writeln!(
+                f,
+                "Dynamic symbol information is not available for displaying symbols."
+            );
+        }
+        let names = section::names(self.data, self.header).unwrap_or_default();
+        let shdrs: Vec<_> = section::from_bytes(self.data, self.header)
+            .map(Iterator::collect)
+            .unwrap_or_default();
+        let versions = symver::from_bytes(self.data, self.header).ok().flatten();
+        for (shdr, name, strtab) in tables {
+            // only the dynamic symbols are versioned
+            let versions = versions.as_ref().filter(|_| shdr.sh_type == SHT::DYNSYM);
+            let symbols = match from_bytes(self.data, self.header, &shdr) {
+                Ok(symbols) => symbols,
+                Err(err) => {
+                    let name = name.unwrap_or(ByteStr::new(b".dynsym"));
+                    writeln!(f, "Warning: invalid symbol table '{}': {}", name, err)?;
+                    continue;
+                }
+            };
+            writeln!(f)?;
+            match name {
+                Some(name) => writeln!(
+                    f,
+                    "Symbol table '{}' contains {} entries:",
+                    name,
+                    symbols.len()
+                )?,
+                None => writeln!(
+                    f,
+                    "Symbol table for image contains {} entries:",
+                    symbols.len()
+                )?,
+            }
+            let mut table = TextTable::new(self.color)
+                .indent(1)
+                .column("Num:", Align::Right, None)
+                .column("Value", Align::Left, Some(Color::Yellow))
+                .column("Size", Align::Right, None)
+                .column("Type", Align::Left, Some(Color::Cyan))
+                .column("Bind", Align::Left, None)
+                .column("Vis", Align::Left, None)
+                .column("Ndx", Align::Right, None)
+                .column("Name", Align::Left, Some(Color::Green))
+                .limit((!self.wide).then_some(NAME_WIDTH));
+            // the cells are written in the same buffers for every symbol
+            let mut cells: [String; 5] = Default::default();
+            for (index, symbol) in symbols.enumerate() {
+                let mut name = symbol.name(&strtab);
+                // section symbols are usually unnamed, readelf shows the section name
+                if symbol.kind() == STT::SECTION && matches!(name, Ok(name) if name.is_empty()) {
+                    let index = symbol.st_shndx as u32;
+                    name = shdrs
+                        .get(index as usize)
+                        .ok_or(Error::InvalidSectionIndex(index))
+                        .and_then(|shdr| shdr.name(&names));
+                }
+                let name = name.unwrap_or(CORRUPT);
+                cells.iter_mut().for_each(String::clear);
+                let [number, value, size, ndx, label] = &mut cells;
+                write!(number, "{}:", index)?;
+                write!(value, "{:016x}", symbol.st_value)?;
+                write!(size, "{}", symbol.st_size)?;
+                symbol.write_index_name(ndx)?;
+                match name.to_str() {
+                    Some(name) if self.demangle => label.push_str(&demangle(name)),
+                    _ => write!(label, "{}", name)?,
+                }
+                // readelf omits the version of the symbols defining a version
+                match versions.and_then(|versions| versions.get(index, &symbol)) {
+                    Some(version) if name != version.name() => write!(label, "{}", version)?,
+                    _ => {}
+                }
+                table.row([
+                    number.as_str(),
+                    value,
+                    size,
+                    &symbol.type_name(),
+                    &symbol.bind_name(),
+                    symbol.visibility_name(),
+                    ndx,
+                    label,
+                ]);
+            }
+            write!(f, "{}", table)?;
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for Elf64_Sym {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("st_name", Json::from(self.st_name)),
+            ("st_info", Json::from(self.st_info)),
+            ("st_other", Json::from(self.st_other)),
+            ("st_shndx", Json::from(self.st_shndx)),
+            ("st_value", Json::from(self.st_value)),
+            ("st_size", Json::from(self.st_size)),
+            ("bind", Json::from(self.bind_name())),
+            ("type", Json::from(self.type_name())),
+            ("visibility", Json::from(self.visibility_name())),
+            ("index", Json::from(self.index_name())),
+        ])
+    }
+}
+
+impl ToJson for SymbolTable<'_> {
+    fn to_json(&self) -> Json {
+        let tables = match self.tables() {
+            Ok(tables) => tables,
+            Err(err) => return Json::error(err),
+        };
+        let versions = symver::from_bytes(self.data, self.header).ok().flatten();
+        Json::array(tables.into_iter().map(|(shdr, name, strtab)| {
+            let versions: Option<&SymbolVersions> =
+                versions.as_ref().filter(|_| shdr.sh_type == SHT::DYNSYM);
+            let symbols = match from_bytes(self.data, self.header, &shdr) {
+                Ok(symbols) => symbols,
+                Err(err) => return Json::error(err),
+            };
+            Json::object([
+                ("section", Json::from(name)),
+                (
+                    "symbols",
+                    Json::array(symbols.enumerate().map(|(index, symbol)| {
+                        let name = symbol.name(&strtab).ok();
+                        let mut members = vec![("name".to_string(), Json::from(name))];
+                        if self.demangle {
+                            let demangled =
+                                name.and_then(|name| name.to_str()).and_then(try_demangle);
+                            members.push(("demangled".to_string(), Json::from(demangled)));
+                        }
+                        if let Json::Object(fields) = symbol.to_json() {
+                            members.extend(fields);
+                        }
+                        if let Some(version) =
+                            versions.and_then(|versions| versions.get(index, &symbol))
+                        {
+                            members.push(("version".to_string(), version.to_json()));
+                        }
+                        Json::Object(members)
+                    })),
+                ),
+            ])
+        }))
+    }
+}
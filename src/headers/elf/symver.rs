@@ -0,0 +1,683 @@
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::json::{Json, ToJson};
+use crate::utils::{cow_struct_endian, impl_swap_bytes, read_u16, Pod, SwapBytes};
+use crate::{Error, Result};
+use alloc::borrow::Cow;
+use core::fmt;
+use core::mem::size_of;
+
+use super::data::Data;
+use super::section::{self, Elf64_Shdr, SHN, SHT};
+use super::strtab::{StrTab, CORRUPT};
+use super::symbols::Elf64_Sym;
+use super::x64::x64;
+
+/// Define the special values of the `.gnu.version` entries
+///
+/// VERSYM define possible values for the entries of the SHT_GNU_versym
+/// section, one per dynamic symbol.
+pub struct VERSYM {}
+impl VERSYM {
+    /// Symbol is local, not available outside the object
+    pub const LOCAL: u16 = 0;
+    /// Symbol is defined in the object and has no version
+    pub const GLOBAL: u16 = 1;
+    /// Symbol is hidden, it can only be bound with an explicit version
+    pub const HIDDEN: u16 = 0x8000;
+    /// Mask of the version index
+    pub const VERSION: u16 = 0x7fff;
+}
+
+/// Define the flags of version definitions and version needs
+#[allow(non_camel_case_types)]
+pub struct VER_FLG {}
+impl VER_FLG {
+    /// Version definition of the file itself
+    pub const BASE: u16 = 0x1;
+    /// Weak version identifier
+    pub const WEAK: u16 = 0x2;
+    /// Reference exists for informational purposes
+    pub const INFO: u16 = 0x4;
+
+    /// Flags as printed by readelf, ex: `BASE | WEAK`
+    pub fn names(flags: u16) -> String {
+        if flags == 0 {
+            return "none".to_string();
+        }
+        let mut names = Vec::new();
+        for (flag, name) in [
+            (VER_FLG::BASE, "BASE"),
+            (VER_FLG::WEAK, "WEAK"),
+            (VER_FLG::INFO, "INFO"),
+        ] {
+            if flags & flag != 0 {
+                names.push(name);
+            }
+        }
+        if flags & !(VER_FLG::BASE | VER_FLG::WEAK | VER_FLG::INFO) != 0 {
+            names.push("<unknown>");
+        }
+        names.join(" | ")
+    }
+}
+
+/// Version definition entry, in the `.gnu.version_d` section
+///
+/// Sources:
+/// * <https://refspecs.linuxfoundation.org/LSB_5.0.0/LSB-Core-generic/LSB-Core-generic/symversion.html>
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct Elf64_Verdef {
+    /// version revision, always 1
+    pub vd_version: u16,
+    /// version information flags
+    pub vd_flags: u16,
+    /// version index, referenced by `.gnu.version`
+    pub vd_ndx: u16,
+    /// number of associated aux entries
+    pub vd_cnt: u16,
+    /// hash of the version name
+    pub vd_hash: u32,
+    /// offset in bytes to the first aux entry
+    pub vd_aux: u32,
+    /// offset in bytes to the next verdef entry
+    pub vd_next: u32,
+}
+
+impl_swap_bytes!(
+    Elf64_Verdef,
+    vd_version,
+    vd_flags,
+    vd_ndx,
+    vd_cnt,
+    vd_hash,
+    vd_aux,
+    vd_next
+);
+
+/// Auxiliary version definition entry, holding a version name
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct Elf64_Verdaux {
+    /// offset of the version name in the linked string table
+    pub vda_name: u32,
+    /// offset in bytes to the next verdaux entry
+    pub vda_next: u32,
+}
+
+impl_swap_bytes!(Elf64_Verdaux, vda_name, vda_next);
+
+/// Version dependency entry, in the `.gnu.version_r` section
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct Elf64_Verneed {
+    /// version of the structure, always 1
+    pub vn_version: u16,
+    /// number of associated aux entries
+    pub vn_cnt: u16,
+    /// offset of the file name in the linked string table
+    pub vn_file: u32,
+    /// offset in bytes to the first vernaux entry
+    pub vn_aux: u32,
+    /// offset in bytes to the next verneed entry
+    pub vn_next: u32,
+}
+
+impl_swap_bytes!(Elf64_Verneed, vn_version, vn_cnt, vn_file, vn_aux, vn_next);
+
+/// Auxiliary version dependency entry, one per needed version
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct Elf64_Vernaux {
+    /// hash of the version name
+    pub vna_hash: u32,
+    /// dependency information flags
+    pub vna_flags: u16,
+    /// version index, referenced by `.gnu.version`
+    pub vna_other: u16,
+    /// offset of the version name in the linked string table
+    pub vna_name: u32,
+    /// offset in bytes to the next vernaux entry
+    pub vna_next: u32,
+}
+
+impl_swap_bytes!(
+    Elf64_Vernaux,
+    vna_hash,
+    vna_flags,
+    vna_other,
+    vna_name,
+    vna_next
+);
+
+/// A version definition with its aux entries
+///
+/// The first aux entry names the version, the others name its parents.
+#[derive(Debug, Clone)]
+pub struct Verdef {
+    /// offset of the entry in the section
+    pub offset: usize,
+    /// the entry
+    pub verdef: Elf64_Verdef,
+    /// the aux entries with their offset in the section
+    pub aux: Vec<(usize, Elf64_Verdaux)>,
+}
+
+/// The versions needed from a shared library
+#[derive(Debug, Clone)]
+pub struct Verneed {
+    /// offset of the entry in the section
+    pub offset: usize,
+    /// the entry
+    pub verneed: Elf64_Verneed,
+    /// the aux entries with their offset in the section
+    pub aux: Vec<(usize, Elf64_Vernaux)>,
+}
+
+fn get<T: Pod + SwapBytes>(data: &[u8], offset: usize, big_endian: bool) -> Result<T> {
+    let bytes = offset
+        .checked_add(size_of::<T>())
+        .and_then(|end| data.get(offset..end))
+        .ok_or(Error::OutOfBounds {
+            offset: offset as u64,
+            size: size_of::<T>() as u64,
+        })?;
+    cow_struct_endian::<T>(bytes, big_endian)
+        .map(|value| *value)
+        .ok_or(Error::InvalidEntrySize {
+            expected: size_of::<T>(),
+            found: bytes.len(),
+        })
+}
+
+/// Parse the `count` entries of a SHT_GNU_verdef section
+///
+/// Fails if an entry or one of its aux entries lies outside the section.
+pub fn verdefs(data: &[u8], count: u32, big_endian: bool) -> Result<Vec<Verdef>> {
+    let mut entries = Vec::new();
+    let mut offset = 0usize;
+    for _ in 0..count {
+        let verdef: Elf64_Verdef = get(data, offset, big_endian)?;
+        let mut aux = Vec::new();
+        let mut aux_offset = offset + verdef.vd_aux as usize;
+        for _ in 0..verdef.vd_cnt {
+            let verdaux: Elf64_Verdaux = get(data, aux_offset, big_endian)?;
+            aux.push((aux_offset, verdaux));
+            aux_offset += verdaux.vda_next as usize;
+        }
+        entries.push(Verdef {
+            offset,
+            verdef,
+            aux,
+        });
+        if verdef.vd_next == 0 {
+            break;
+        }
+        offset += verdef.vd_next as usize;
+    }
+    Ok(entries)
+}
+
+/// Parse the `count` entries of a SHT_GNU_verneed section
+///
+/// Fails if an entry or one of its aux entries lies outside the section.
+pub fn verneeds(data: &[u8], count: u32, big_endian: bool) -> Result<Vec<Verneed>> {
+    let mut entries = Vec::new();
+    let mut offset = 0usize;
+    for _ in 0..count {
+        let verneed: Elf64_Verneed = get(data, offset, big_endian)?;
+        let mut aux = Vec::new();
+        let mut aux_offset = offset + verneed.vn_aux as usize;
+        for _ in 0..verneed.vn_cnt {
+            let vernaux: Elf64_Vernaux = get(data, aux_offset, big_endian)?;
+            aux.push((aux_offset, vernaux));
+            if vernaux.vna_next == 0 {
+                break;
+            }
+            aux_offset += vernaux.vna_next as usize;
+        }
+        entries.push(Verneed {
+            offset,
+            verneed,
+            aux,
+        });
+        if verneed.vn_next == 0 {
+            break;
+        }
+        offset += verneed.vn_next as usize;
+    }
+    Ok(entries)
+}
+
+/// Version of a dynamic symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolVersion<'a> {
+    /// default version of a defined symbol, `name@@VERSION`
+    Default(&'a str),
+    /// non default version of a defined symbol, `name@VERSION`
+    Hidden(&'a str),
+    /// version needed from a library, `name@VERSION (index)`
+    Needed(&'a str, u16),
+}
+
+impl<'a> SymbolVersion<'a> {
+    /// Name of the version, ex: `GLIBC_2.2.5`
+    pub fn name(&self) -> &'a str {
+        match self {
+            SymbolVersion::Default(name)
+            | SymbolVersion::Hidden(name)
+            | SymbolVersion::Needed(name, _) => name,
+        }
+    }
+}
+
+impl fmt::Display for SymbolVersion<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymbolVersion::Default(name) => write!(f, "@@{}", name),
+            SymbolVersion::Hidden(name) => write!(f, "@{}", name),
+            SymbolVersion::Needed(name, index) => write!(f, "@{} ({})", name, index),
+        }
+    }
+}
+
+/// The version sections of a file, used to resolve the dynamic symbol versions
+#[derive(Debug, Clone, Default)]
+pub struct SymbolVersions<'a> {
+    /// content of `.gnu.version`, one entry per dynamic symbol
+    pub versym: &'a [u8],
+    /// entries of `.gnu.version_d`
+    pub verdefs: Vec<Verdef>,
+    /// entries of `.gnu.version_r`
+    pub verneeds: Vec<Verneed>,
+    /// string table holding the names of the definitions
+    pub verdef_strtab: StrTab<'a>,
+    /// string table holding the names of the dependencies
+    pub verneed_strtab: StrTab<'a>,
+    big_endian: bool,
+}
+
+impl<'a> SymbolVersions<'a> {
+    /// Entry of `.gnu.version` of the dynamic symbol at `index`
+    pub fn versym(&self, index: usize) -> Option<u16> {
+        read_u16(self.versym, index.checked_mul(2)?, self.big_endian)
+    }
+
+    /// Name of the version with the given index, from the definitions or the needs
+    pub fn name(&self, version: u16) -> Option<&'a str> {
+        let version = version & VERSYM::VERSION;
+        self.needed_name(version)
+            .or_else(|| self.defined_name(version))
+    }
+
+    fn defined_name(&self, version: u16) -> Option<&'a str> {
+        let verdef = self
+            .verdefs
+            .iter()
+            .find(|verdef| verdef.verdef.vd_ndx == version)?;
+        let (_, verdaux) = verdef.aux.first()?;
+        self.verdef_strtab.get(verdaux.vda_name as usize).ok()
+    }
+
+    fn needed_name(&self, version: u16) -> Option<&'a str> {
+        self.verneeds
+            .iter()
+            .flat_map(|verneed| verneed.aux.iter())
+            .find(|(_, vernaux)| vernaux.vna_other == version)
+            .and_then(|(_, vernaux)| self.verneed_strtab.get(vernaux.vna_name as usize).ok())
+    }
+
+    /// File name of the library a needed version comes from, ex: `libc.so.6`
+    pub fn library(&self, version: u16) -> Option<&'a str> {
+        let version = version & VERSYM::VERSION;
+        let verneed = self.verneeds.iter().find(|verneed| {
+            verneed
+                .aux
+                .iter()
+                .any(|(_, vernaux)| vernaux.vna_other == version)
+        })?;
+        self.verneed_strtab
+            .get(verneed.verneed.vn_file as usize)
+            .ok()
+    }
+
+    /// Version of the dynamic symbol at `index`, like readelf prints it
+    ///
+    /// Returns None for local and unversioned symbols and for the symbols
+    /// bound to the base version of the file.
+    pub fn get(&self, index: usize, symbol: &Elf64_Sym) -> Option<SymbolVersion<'a>> {
+        let versym = self.versym(index)?;
+        if versym == VERSYM::LOCAL {
+            return None;
+        }
+        let version = versym & VERSYM::VERSION;
+        if symbol.st_shndx != SHN::UNDEF && versym != (VERSYM::HIDDEN | VERSYM::GLOBAL) {
+            if let Some(verdef) = self
+                .verdefs
+                .iter()
+                .find(|verdef| verdef.verdef.vd_ndx == version)
+            {
+                if verdef.verdef.vd_ndx == VERSYM::GLOBAL && verdef.verdef.vd_flags == VER_FLG::BASE
+                {
+                    return None;
+                }
+                let name = self.defined_name(version)?;
+                return Some(if versym & VERSYM::HIDDEN != 0 {
+                    SymbolVersion::Hidden(name)
+                } else {
+                    SymbolVersion::Default(name)
+                });
+            }
+        }
+        let vernaux = self
+            .verneeds
+            .iter()
+            .flat_map(|verneed| verneed.aux.iter())
+            .find(|(_, vernaux)| vernaux.vna_other == version)?;
+        let name = self.needed_name(version)?;
+        Some(SymbolVersion::Needed(name, vernaux.1.vna_other))
+    }
+}
+
+/// Returns the versions of the dynamic symbols
+///
+/// Returns None if the file has no `.gnu.version` section.
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64) -> Result<Option<SymbolVersions<'a>>> {
+    let big_endian = Data::is_big_endian(&header.e_ident);
+    let mut versions = SymbolVersions {
+        big_endian,
+        ..SymbolVersions::default()
+    };
+    let mut found = false;
+    for shdr in section::from_bytes(data, header)? {
+        match shdr.sh_type {
+            SHT::GNU_VERSYM => {
+                versions.versym = shdr.data(data).unwrap_or_default();
+                found = true;
+            }
+            SHT::GNU_VERDEF => {
+                let content = shdr.data(data).unwrap_or_default();
+                versions.verdefs = verdefs(content, shdr.sh_info, big_endian)?;
+                versions.verdef_strtab =
+                    section::get_data(data, header, shdr.sh_link).map(StrTab::new)?;
+            }
+            SHT::GNU_VERNEED => {
+                let content = shdr.data(data).unwrap_or_default();
+                versions.verneeds = verneeds(content, shdr.sh_info, big_endian)?;
+                versions.verneed_strtab =
+                    section::get_data(data, header, shdr.sh_link).map(StrTab::new)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(found.then_some(versions))
+}
+
+/// Version sections ready to be printed like `readelf -V`
+pub struct VersionTable<'a> {
+    /// the whole file
+    pub data: &'a [u8],
+    /// the file header
+    pub header: &'a x64,
+}
+
+fn entries(count: u64) -> &'static str {
+    if count == 1 {
+        "entry"
+    } else {
+        "entries"
+    }
+}
+
+impl VersionTable<'_> {
+    /// The version sections of the file, in the section header table order
+    fn sections(&self) -> Result<Vec<Cow<'_, Elf64_Shdr>>> {
+        Ok(section::from_bytes(self.data, self.header)?
+            .filter(|shdr| {
+                matches!(
+                    shdr.sh_type,
+                    SHT::GNU_VERSYM | SHT::GNU_VERDEF | SHT::GNU_VERNEED
+                )
+            })
+            .collect())
+    }
+
+    fn section_header(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        kind: &str,
+        name: ByteStr<'_>,
+        count: u64,
+        shdr: &Elf64_Shdr,
+    ) -> fmt::Result {
+        let names = section::names(self.data, self.header).unwrap_or_default();
+        let link = section::get(self.data, self.header, shdr.sh_link)
+            .and_then(|link| link.name(&names))
+            .unwrap_or(CORRUPT);
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{} section '{}' contains {} {}:",
+            kind,
+            name,
+            count,
+            entries(count)
+        )?;
+        writeln!(
+            f,
+            " Addr: 0x{:016x}  Offset: 0x{:08x}  Link: {} ({})",
+            shdr.sh_addr, shdr.sh_offset, shdr.sh_link, link
+        )
+    }
+}
+
+/// readelf prints offsets with `%#06x`, which has no prefix for zero
+fn entry_offset(offset: usize) -> String {
+    if offset == 0 {
+        "000000".to_string()
+    } else {
+        format!("{:#06x}", offset)
+    }
+}
+
+impl fmt::Display for VersionTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sections = match self.sections() {
+            Ok(sections) => sections,
+            Err(err) => return writeln!(f, "Warning: invalid section header table: {}", err),
+        };
+        if sections.is_empty() {
+            return writeln!(f, "\nNo version information found in this file.");
+        }
+        let versions = match from_bytes(self.data, self.header) {
+            Ok(versions) => versions.unwrap_or_default(),
+            Err(err) => return writeln!(f, "Warning: invalid version sections: {}", err),
+        };
+        let names = section::names(self.data, self.header).unwrap_or_default();
+        for shdr in sections {
+            let name = shdr.name(&names).unwrap_or(CORRUPT);
+            match shdr.sh_type {
+                SHT::GNU_VERDEF => {
+                    self.section_header(f, "Version definition", name, shdr.sh_info as u64, &shdr)?;
+                    for verdef in &versions.verdefs {
+                        let entry = &verdef.verdef;
+                        write!(
+                            f,
+                            "  {}: Rev: {}  Flags: {}  Index: {}  Cnt: {}  ",
+                            entry_offset(verdef.offset),
+                            entry.vd_version,
+                            VER_FLG::names(entry.vd_flags),
+                            entry.vd_ndx,
+                            entry.vd_cnt
+                        )?;
+                        let mut aux = verdef.aux.iter();
+                        let name = |verdaux: &Elf64_Verdaux| {
+                            versions
+                                .verdef_strtab
+                                .name(verdaux.vda_name as usize)
+                                .unwrap_or(CORRUPT)
+                        };
+                        match aux.next() {
+                            Some((_, verdaux)) => writeln!(f, "Name: {}", name(verdaux))?,
+                            None => writeln!(f)?,
+                        }
+                        for (parent, (offset, verdaux)) in aux.enumerate() {
+                            writeln!(
+                                f,
+                                "  {}: Parent {}: {}",
+                                entry_offset(*offset),
+                                parent + 1,
+                                name(verdaux)
+                            )?;
+                        }
+                    }
+                }
+                SHT::GNU_VERNEED => {
+                    self.section_header(f, "Version needs", name, shdr.sh_info as u64, &shdr)?;
+                    for verneed in &versions.verneeds {
+                        let entry = &verneed.verneed;
+                        let file = versions
+                            .verneed_strtab
+                            .get(entry.vn_file as usize)
+                            .map(Cow::Borrowed)
+                            .unwrap_or_else(|_| Cow::Owned(format!("{:x}", entry.vn_file)));
+                        writeln!(
+                            f,
+                            "  {}: Version: {}  File: {}  Cnt: {}",
+                            entry_offset(verneed.offset),
+                            entry.vn_version,
+                            file,
+                            entry.vn_cnt
+                        )?;
+                        for (offset, vernaux) in &verneed.aux {
+                            writeln!(
+                                f,
+                                "  {}:   Name: {}  Flags: {}  Version: {}",
+                                entry_offset(*offset),
+                                versions
+                                    .verneed_strtab
+                                    .name(vernaux.vna_name as usize)
+                                    .unwrap_or(CORRUPT),
+                                VER_FLG::names(vernaux.vna_flags),
+                                vernaux.vna_other
+                            )?;
+                        }
+                    }
+                }
+                _ => {
+                    let total = versions.versym.len() / 2;
+                    self.section_header(f, "Version symbols", name, total as u64, &shdr)?;
+                    for start in (0..total).step_by(4) {
+                        write!(f, "  {:03x}:", start)?;
+                        for index in start..total.min(start + 4) {
+                            let versym = versions.versym(index).unwrap_or_default();
+                            let entry = match versym {
+                                VERSYM::LOCAL => "   0 (*local*)    ".to_string(),
+                                VERSYM::GLOBAL => "   1 (*global*)   ".to_string(),
+                                _ => {
+                                    let hidden = if versym & VERSYM::HIDDEN != 0 {
+                                        'h'
+                                    } else {
+                                        ' '
+                                    };
+                                    let mut entry =
+                                        format!("{:4x}{}", versym & VERSYM::VERSION, hidden);
+                                    if let Some(name) = versions.name(versym) {
+                                        // readelf pads the closing parenthesis to 12 - len(name)
+                                        let width = (12 - name.len() as isize).unsigned_abs();
+                                        entry.push_str(&format!(
+                                            "({}{:<width$}",
+                                            name,
+                                            ")",
+                                            width = width
+                                        ));
+                                    }
+                                    format!("{:<18}", entry)
+                                }
+                            };
+                            write!(f, "{}", entry)?;
+                        }
+                        writeln!(f)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for SymbolVersion<'_> {
+    fn to_json(&self) -> Json {
+        let mut members = vec![("name", Json::from(self.name()))];
+        match self {
+            SymbolVersion::Default(_) => members.push(("default", Json::from(true))),
+            SymbolVersion::Hidden(_) => members.push(("default", Json::from(false))),
+            SymbolVersion::Needed(_, index) => members.push(("needed", Json::from(*index))),
+        }
+        Json::object(members)
+    }
+}
+
+impl ToJson for VersionTable<'_> {
+    fn to_json(&self) -> Json {
+        let versions = match from_bytes(self.data, self.header) {
+            Ok(Some(versions)) => versions,
+            Ok(None) => return Json::Null,
+            Err(err) => return Json::error(err),
+        };
+        let name = |strtab: &StrTab, offset: u32| Json::from(strtab.get(offset as usize).ok());
+        Json::object([
+            (
+                "definitions",
+                Json::array(versions.verdefs.iter().map(|verdef| {
+                    let mut names = verdef
+                        .aux
+                        .iter()
+                        .map(|(_, verdaux)| name(&versions.verdef_strtab, verdaux.vda_name));
+                    Json::object([
+                        ("index", Json::from(verdef.verdef.vd_ndx)),
+                        ("flags", Json::from(VER_FLG::names(verdef.verdef.vd_flags))),
+                        ("name", names.next().unwrap_or(Json::Null)),
+                        ("parents", Json::array(names)),
+                    ])
+                })),
+            ),
+            (
+                "needs",
+                Json::array(versions.verneeds.iter().map(|verneed| {
+                    Json::object([
+                        (
+                            "file",
+                            name(&versions.verneed_strtab, verneed.verneed.vn_file),
+                        ),
+                        (
+                            "versions",
+                            Json::array(verneed.aux.iter().map(|(_, vernaux)| {
+                                Json::object([
+                                    ("name", name(&versions.verneed_strtab, vernaux.vna_name)),
+                                    ("index", Json::from(vernaux.vna_other)),
+                                    ("flags", Json::from(VER_FLG::names(vernaux.vna_flags))),
+                                ])
+                            })),
+                        ),
+                    ])
+                })),
+            ),
+            (
+                "symbols",
+                Json::array((0..versions.versym.len() / 2).map(|index| {
+                    let versym = versions.versym(index).unwrap_or_default();
+                    Json::object([
+                        ("index", Json::from(versym & VERSYM::VERSION)),
+                        ("hidden", Json::from(versym & VERSYM::HIDDEN != 0)),
+                    ])
+                })),
+            ),
+        ])
+    }
+}
@@ -0,0 +1,126 @@
+use crate::utils::{cow_struct_endian, Pod, SwapBytes};
+use crate::{Error, Result};
+use alloc::borrow::Cow;
+use core::marker::PhantomData;
+use core::mem::{align_of, size_of};
+
+/// Iterator over a table of fixed size entries stored in the file
+///
+/// Used for every ELF table: program headers, section headers, symbols,
+/// dynamic entries, relocations... The entries are read in the byte
+/// order of the file and exposed in the host one.
+#[derive(Debug)]
+pub struct Table<'a, T> {
+    data: &'a [u8],
+    offset: usize,
+    entsize: usize,
+    index: usize,
+    num: usize,
+    big_endian: bool,
+    entry: PhantomData<T>,
+}
+
+// Derived Clone would require T: Clone
+impl<T> Clone for Table<'_, T> {
+    fn clone(&self) -> Self {
+        Table { ..*self }
+    }
+}
+
+impl<'a, T: Pod + SwapBytes> Table<'a, T> {
+    /// Locate a table of `num` entries of `entsize` bytes at `offset`
+    ///
+    /// Fails if the table doesn't fit in the given data, if its offset is
+    /// misaligned or if the entry size is smaller than `T`.
+    pub fn parse(
+        data: &'a [u8],
+        offset: u64,
+        entsize: u64,
+        num: u64,
+        big_endian: bool,
+    ) -> Result<Self> {
+        let offset = check_table(
+            data.len(),
+            offset,
+            entsize,
+            num,
+            size_of::<T>(),
+            align_of::<T>(),
+        )?;
+        Ok(Table {
+            data,
+            offset,
+            entsize: entsize as usize,
+            index: 0,
+            num: num as usize,
+            big_endian,
+            entry: PhantomData,
+        })
+    }
+
+    /// Returns the entry at `index` from the start of the table
+    pub fn get(&self, index: usize) -> Option<Cow<'a, T>> {
+        if index >= self.num {
+            return None;
+        }
+        let start = self.offset + index * self.entsize;
+        cow_struct_endian::<T>(
+            self.data.get(start..start + size_of::<T>())?,
+            self.big_endian,
+        )
+    }
+}
+
+impl<'a, T: Pod + SwapBytes> Iterator for Table<'a, T> {
+    type Item = Cow<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.get(self.index)?;
+        self.index += 1;
+        Some(entry)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.index = self.index.saturating_add(n);
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.num.saturating_sub(self.index);
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T: Pod + SwapBytes> ExactSizeIterator for Table<'_, T> {}
+
+/// Check that a table of `num` entries of `entsize` bytes at `offset`
+/// fits in `len` bytes and can hold entries of `expected` bytes aligned
+/// on `align`
+///
+/// Returns the offset of the table converted to usize.
+pub(crate) fn check_table(
+    len: usize,
+    offset: u64,
+    entsize: u64,
+    num: u64,
+    expected: usize,
+    align: usize,
+) -> Result<usize> {
+    if num == 0 {
+        return Ok(0);
+    }
+    if entsize < expected as u64 {
+        return Err(Error::InvalidEntrySize {
+            expected,
+            found: entsize as usize,
+        });
+    }
+    if !offset.is_multiple_of(align as u64) {
+        return Err(Error::MisalignedOffset { offset, align });
+    }
+    let size = entsize.saturating_mul(num);
+    match offset.checked_add(size) {
+        Some(end) if end <= len as u64 => Ok(offset as usize),
+        _ => Err(Error::OutOfBounds { offset, size }),
+    }
+}
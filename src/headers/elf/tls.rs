@@ -0,0 +1,278 @@
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::json::{Json, ToJson};
+use crate::Result;
+use alloc::borrow::Cow;
+use core::fmt;
+
+use super::dynamic::{self, DF, DT};
+use super::program::{self, Elf64_Phdr, PT};
+use super::relocations::{self, Relocation};
+use super::section::{self, Elf64_Shdr, SHF, SHT};
+use super::strtab::CORRUPT;
+use super::symbols::{self, Elf64_Sym, STT};
+use super::x64::x64;
+
+/// A relocation computing the address or the offset of a TLS variable
+#[derive(Debug, Clone)]
+pub struct TlsRelocation<'a> {
+    /// name of the relocation section
+    pub section: ByteStr<'a>,
+    /// the relocation entry
+    pub relocation: Relocation,
+    /// name of the referenced symbol, None for the TLS of the module itself
+    pub symbol: Option<ByteStr<'a>>,
+}
+
+/// Thread local storage of an ELF64 file
+///
+/// The PT_TLS segment is the template copied in the TLS block of each
+/// thread: `p_filesz` bytes from `.tdata` then zeroes up to `p_memsz`
+/// for `.tbss`.
+#[derive(Debug, Clone)]
+pub struct Tls<'a> {
+    /// machine of the file, gives the meaning of the relocation types
+    pub machine: u16,
+    /// the PT_TLS segment, None if the file has no TLS
+    pub segment: Option<Cow<'a, Elf64_Phdr>>,
+    /// the SHF_TLS sections, `.tdata` and `.tbss`, with their index and name
+    pub sections: Vec<(usize, ByteStr<'a>, Cow<'a, Elf64_Shdr>)>,
+    /// the STT_TLS symbols of `.symtab`, or of `.dynsym` when stripped
+    pub symbols: Vec<(ByteStr<'a>, Cow<'a, Elf64_Sym>)>,
+    /// DF_STATIC_TLS is set, the module can't be loaded with `dlopen`
+    pub static_tls: bool,
+    /// DT_TLSDESC_PLT, the PLT entry resolving the lazy TLS descriptors
+    pub tlsdesc_plt: Option<u64>,
+    /// DT_TLSDESC_GOT, the GOT entry used by that PLT entry
+    pub tlsdesc_got: Option<u64>,
+    /// the TLS relocations of every relocation section
+    pub relocations: Vec<TlsRelocation<'a>>,
+}
+
+impl Tls<'_> {
+    /// Size of the TLS block of a thread
+    pub fn template_size(&self) -> u64 {
+        self.segment.as_ref().map_or(0, |phdr| phdr.p_memsz)
+    }
+
+    /// Size of the initialized part of the template, the `.tdata` bytes
+    pub fn init_size(&self) -> u64 {
+        self.segment.as_ref().map_or(0, |phdr| phdr.p_filesz)
+    }
+
+    /// Alignment of the TLS block
+    pub fn alignment(&self) -> u64 {
+        self.segment.as_ref().map_or(0, |phdr| phdr.p_align)
+    }
+}
+
+/// Collect the TLS information of the file
+///
+/// Fails if the program or section header tables can't be parsed, the
+/// symbols and relocations that can't be read are skipped.
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64) -> Result<Tls<'a>> {
+    let segment = program::from_bytes(data, header)?.find(|phdr| phdr.p_type == PT::TLS);
+    let names = section::names(data, header).unwrap_or_default();
+    let shdrs: Vec<Cow<Elf64_Shdr>> = section::from_bytes(data, header)?.collect();
+    let name = |shdr: &Elf64_Shdr| shdr.name(&names).unwrap_or(CORRUPT);
+    let sections = shdrs
+        .iter()
+        .enumerate()
+        .filter(|(_, shdr)| shdr.sh_flags & SHF::TLS != 0)
+        .map(|(index, shdr)| (index, name(shdr), shdr.clone()))
+        .collect();
+
+    let symtab = shdrs
+        .iter()
+        .find(|shdr| shdr.sh_type == SHT::SYMTAB)
+        .or_else(|| shdrs.iter().find(|shdr| shdr.sh_type == SHT::DYNSYM));
+    let mut tls_symbols = Vec::new();
+    if let Some(symtab) = symtab {
+        let strtab = symbols::strtab(data, header, symtab).unwrap_or_default();
+        if let Ok(symbols) = symbols::from_bytes(data, header, symtab) {
+            tls_symbols = symbols
+                .filter(|symbol| symbol.kind() == STT::TLS)
+                .map(|symbol| (symbol.name(&strtab).unwrap_or(CORRUPT), symbol))
+                .collect();
+        }
+    }
+
+    let dynamic = dynamic::from_bytes(data, header).ok().flatten();
+    let get = |tag| dynamic.as_ref().and_then(|dynamic| dynamic.get(tag));
+    let static_tls = get(DT::FLAGS).is_some_and(|flags| flags & DF::STATIC_TLS != 0);
+
+    let mut tls_relocations = Vec::new();
+    for shdr in shdrs
+        .iter()
+        .filter(|shdr| shdr.sh_type == SHT::REL || shdr.sh_type == SHT::RELA)
+    {
+        let relocations = match relocations::from_bytes(data, header, shdr) {
+            Ok(relocations) => relocations,
+            Err(_) => continue,
+        };
+        let symtab = section::get(data, header, shdr.sh_link).ok();
+        let symbols = symtab
+            .as_ref()
+            .and_then(|symtab| symbols::from_bytes(data, header, symtab).ok());
+        let strtab = symtab
+            .as_ref()
+            .and_then(|symtab| symbols::strtab(data, header, symtab).ok())
+            .unwrap_or_default();
+        for relocation in relocations
+            .into_iter()
+            .filter(|relocation| relocations::is_tls(header.e_machine, relocation.r_type))
+        {
+            let symbol = match relocation.r_sym {
+                0 => None,
+                index => symbols
+                    .as_ref()
+                    .and_then(|symbols| symbols.get(index as usize))
+                    .map(|symbol| symbol.name(&strtab).unwrap_or(CORRUPT)),
+            };
+            tls_relocations.push(TlsRelocation {
+                section: name(shdr),
+                relocation,
+                symbol,
+            });
+        }
+    }
+
+    Ok(Tls {
+        machine: header.e_machine,
+        segment,
+        sections,
+        symbols: tls_symbols,
+        static_tls,
+        tlsdesc_plt: get(DT::TLSDESC_PLT),
+        tlsdesc_got: get(DT::TLSDESC_GOT),
+        relocations: tls_relocations,
+    })
+}
+
+impl fmt::Display for Tls<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let phdr = match &self.segment {
+            Some(phdr) => phdr,
+            None => return writeln!(f, "There is no TLS segment in this file."),
+        };
+        writeln!(f, "TLS segment:")?;
+        writeln!(f, "  Template address:   {:#x}", phdr.p_vaddr)?;
+        writeln!(f, "  File offset:        {:#x}", phdr.p_offset)?;
+        writeln!(
+            f,
+            "  Template size:      {:#x} ({:#x} initialized, {:#x} zeroed)",
+            self.template_size(),
+            self.init_size(),
+            self.template_size().saturating_sub(self.init_size())
+        )?;
+        writeln!(f, "  Alignment:          {:#x}", self.alignment())?;
+        writeln!(
+            f,
+            "  Static TLS model:   {}",
+            if self.static_tls { "yes" } else { "no" }
+        )?;
+        if let Some(plt) = self.tlsdesc_plt {
+            writeln!(f, "  TLSDESC_PLT:        {:#x}", plt)?;
+        }
+        if let Some(got) = self.tlsdesc_got {
+            writeln!(f, "  TLSDESC_GOT:        {:#x}", got)?;
+        }
+        if !self.sections.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "TLS sections:")?;
+            writeln!(f, "  [Nr] Name      Type      Address          Size")?;
+            for (index, name, shdr) in &self.sections {
+                writeln!(
+                    f,
+                    "  [{:>2}] {:<9} {:<9} {:016x} {:#x}",
+                    index,
+                    name,
+                    shdr.type_name(),
+                    shdr.sh_addr,
+                    shdr.sh_size
+                )?;
+            }
+        }
+        if !self.symbols.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "TLS symbols:")?;
+            writeln!(f, "  Offset           Size Bind   Name")?;
+            for (name, symbol) in &self.symbols {
+                writeln!(
+                    f,
+                    "  {:016x} {:>4} {:<6} {}",
+                    symbol.st_value,
+                    symbol.st_size,
+                    symbol.bind_name(),
+                    name
+                )?;
+            }
+        }
+        if !self.relocations.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "TLS relocations:")?;
+            writeln!(
+                f,
+                "  Section      Offset           Type                 Symbol"
+            )?;
+            for tls in &self.relocations {
+                writeln!(
+                    f,
+                    "  {:<12} {:016x} {:<20} {}",
+                    tls.section,
+                    tls.relocation.r_offset,
+                    relocations::type_name(self.machine, tls.relocation.r_type),
+                    tls.symbol.unwrap_or(ByteStr::new(b"<local>"))
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for Tls<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            (
+                "segment",
+                Json::from(self.segment.as_ref().map(|phdr| phdr.to_json())),
+            ),
+            ("template_size", Json::from(self.template_size())),
+            ("init_size", Json::from(self.init_size())),
+            ("alignment", Json::from(self.alignment())),
+            ("static_tls", Json::from(self.static_tls)),
+            ("tlsdesc_plt", Json::from(self.tlsdesc_plt)),
+            ("tlsdesc_got", Json::from(self.tlsdesc_got)),
+            (
+                "sections",
+                Json::array(self.sections.iter().map(|(index, name, shdr)| {
+                    Json::object([
+                        ("index", Json::from(*index)),
+                        ("name", Json::from(*name)),
+                        ("section", shdr.to_json()),
+                    ])
+                })),
+            ),
+            (
+                "symbols",
+                Json::array(self.symbols.iter().map(|(name, symbol)| {
+                    Json::object([("name", Json::from(*name)), ("symbol", symbol.to_json())])
+                })),
+            ),
+            (
+                "relocations",
+                Json::array(self.relocations.iter().map(|tls| {
+                    Json::object([
+                        ("section", Json::from(tls.section)),
+                        (
+                            "type",
+                            Json::from(relocations::type_name(self.machine, tls.relocation.r_type)),
+                        ),
+                        ("symbol", Json::from(tls.symbol)),
+                        ("relocation", tls.relocation.to_json()),
+                    ])
+                })),
+            ),
+        ])
+    }
+}
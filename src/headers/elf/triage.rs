@@ -0,0 +1,349 @@
+//! Entropy of the sections and segments and the signs of a packed file
+//!
+//! Compressed or encrypted bytes have an entropy close to 8 bits per
+//! byte, code and data are usually well below 7. Packers like UPX also
+//! leave their magic in the file, strip the section headers and unpack
+//! the program into a segment much larger in memory than in the file, or
+//! start in a stub outside of `.text`. Each of these signs is reported
+//! as a finding, none of them is a proof on its own.
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::json::{Json, ToJson};
+use crate::utils::table::{Align, Table as TextTable};
+use crate::Result;
+use core::fmt;
+
+use super::program::{self, PF, PT};
+use super::section::{self, SHT};
+use super::strtab::CORRUPT;
+use super::x64::x64;
+
+/// Entropy above which bytes look compressed or encrypted, in bits per byte
+pub const HIGH_ENTROPY: f64 = 7.2;
+/// Smaller sections are too short for their entropy to mean anything
+pub const MIN_SIZE: u64 = 256;
+/// Magic written by UPX in the files it packs
+pub const UPX_MAGIC: &[u8; 4] = b"UPX!";
+
+/// Base 2 logarithm of a positive number, core has none without std
+fn log2(value: f64) -> f64 {
+    let bits = value.to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as i64 - 1023;
+    // value = mantissa * 2^exponent with the mantissa in [1, 2)
+    let mantissa = f64::from_bits((bits & 0x000f_ffff_ffff_ffff) | 0x3ff0_0000_0000_0000);
+    // ln(m) = 2 atanh((m - 1) / (m + 1)), the series converges fast below 1/3
+    let z = (mantissa - 1.0) / (mantissa + 1.0);
+    let (mut term, mut sum) = (z, 0.0);
+    for k in 0..24 {
+        sum += term / (2 * k + 1) as f64;
+        term *= z * z;
+    }
+    exponent as f64 + 2.0 * sum / core::f64::consts::LN_2
+}
+
+/// Shannon entropy of the bytes, from 0 to 8 bits per byte
+pub fn entropy(bytes: &[u8]) -> f64 {
+    if bytes.is_empty() {
+        return 0.0;
+    }
+    let mut counts = [0u64; 256];
+    for byte in bytes {
+        counts[*byte as usize] += 1;
+    }
+    let total = bytes.len() as f64;
+    let sum: f64 = counts
+        .iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let p = *count as f64 / total;
+            p * log2(p)
+        })
+        .sum();
+    // 0.0 - 0.0 isn't negative zero
+    0.0 - sum
+}
+
+/// Entropy of the bytes of a section or a segment
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region<'a> {
+    /// index of the section or segment
+    pub index: usize,
+    /// name of the section, or type of the segment
+    pub name: ByteStr<'a>,
+    /// offset of the bytes in the file
+    pub offset: u64,
+    /// number of bytes
+    pub size: u64,
+    /// entropy, in bits per byte
+    pub entropy: f64,
+}
+
+/// A sign that the file is packed
+#[derive(Debug, Clone, PartialEq)]
+pub enum Finding<'a> {
+    /// the UPX magic is in the file at this offset
+    UpxMagic(u64),
+    /// few sections and a loadable executable segment larger in memory
+    /// than twice its size in the file
+    UnpackedSegment {
+        /// number of sections, without the null one
+        sections: usize,
+        /// index of the segment
+        segment: usize,
+        /// its size in the file
+        filesz: u64,
+        /// its size in memory
+        memsz: u64,
+    },
+    /// the entry point isn't in `.text`
+    EntryOutsideText {
+        /// the entry point
+        entry: u64,
+        /// the section holding it, if any
+        section: Option<ByteStr<'a>>,
+    },
+    /// a section or segment looks compressed or encrypted
+    HighEntropy {
+        /// name of the section or type of the segment
+        name: ByteStr<'a>,
+        /// its entropy, in bits per byte
+        entropy: f64,
+    },
+}
+
+impl Finding<'_> {
+    /// Short name of the finding, used in the JSON output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Finding::UpxMagic(_) => "upx-magic",
+            Finding::UnpackedSegment { .. } => "unpacked-segment",
+            Finding::EntryOutsideText { .. } => "entry-outside-text",
+            Finding::HighEntropy { .. } => "high-entropy",
+        }
+    }
+}
+
+/// The entropy and packer findings of an ELF64 file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Triage<'a> {
+    /// size of the file
+    pub size: u64,
+    /// entropy of the whole file, in bits per byte
+    pub entropy: f64,
+    /// the sections with bytes in the file
+    pub sections: Vec<Region<'a>>,
+    /// the PT_LOAD segments with bytes in the file
+    pub segments: Vec<Region<'a>>,
+    /// the signs of a packed file, empty if none was found
+    pub findings: Vec<Finding<'a>>,
+}
+
+/// Compute the entropy of the file and look for the signs of a packer
+///
+/// Fails if the program or section header table can't be parsed. A file
+/// without sections is triaged from its segments.
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64) -> Result<Triage<'a>> {
+    let shdrs: Vec<_> = section::from_bytes(data, header)?.collect();
+    let phdrs: Vec<_> = program::from_bytes(data, header)?.collect();
+    let names = section::names(data, header).unwrap_or_default();
+    let region = |index, name, offset: u64, size: u64| {
+        let start = usize::try_from(offset).ok()?;
+        let bytes = data.get(start..start.checked_add(usize::try_from(size).ok()?)?)?;
+        Some(Region {
+            index,
+            name,
+            offset,
+            size,
+            entropy: entropy(bytes),
+        })
+    };
+    let sections: Vec<_> = shdrs
+        .iter()
+        .enumerate()
+        .filter(|(_, shdr)| !matches!(shdr.sh_type, SHT::NULL | SHT::NOBITS) && shdr.sh_size > 0)
+        .filter_map(|(index, shdr)| {
+            let name = shdr.name(&names).unwrap_or(CORRUPT);
+            region(index, name, shdr.sh_offset, shdr.sh_size)
+        })
+        .collect();
+    let segments: Vec<_> = phdrs
+        .iter()
+        .enumerate()
+        .filter(|(_, phdr)| phdr.p_type == PT::LOAD && phdr.p_filesz > 0)
+        .filter_map(|(index, phdr)| {
+            region(index, ByteStr::new(b"LOAD"), phdr.p_offset, phdr.p_filesz)
+        })
+        .collect();
+
+    let mut findings = Vec::new();
+    if let Some(offset) = data.windows(4).position(|bytes| bytes == UPX_MAGIC) {
+        findings.push(Finding::UpxMagic(offset as u64));
+    }
+    let count = shdrs.len().saturating_sub(1);
+    if count < 4 {
+        let unpacked = phdrs.iter().enumerate().find(|(_, phdr)| {
+            phdr.p_type == PT::LOAD && phdr.p_flags & PF::X != 0 && phdr.p_memsz / 2 > phdr.p_filesz
+        });
+        if let Some((segment, phdr)) = unpacked {
+            findings.push(Finding::UnpackedSegment {
+                sections: count,
+                segment,
+                filesz: phdr.p_filesz,
+                memsz: phdr.p_memsz,
+            });
+        }
+    }
+    let entry = header.e_entry;
+    let contains = |shdr: &section::Elf64_Shdr| {
+        shdr.sh_addr != 0 && entry.wrapping_sub(shdr.sh_addr) < shdr.sh_size
+    };
+    if entry != 0 {
+        let holder = shdrs.iter().find(|shdr| contains(shdr));
+        let section = holder.map(|shdr| shdr.name(&names).unwrap_or(CORRUPT));
+        if section.is_none_or(|section| section != ".text") {
+            findings.push(Finding::EntryOutsideText { entry, section });
+        }
+    }
+    // without sections the segments are all there is
+    let regions = if sections.is_empty() {
+        &segments
+    } else {
+        &sections
+    };
+    for region in regions {
+        if region.size >= MIN_SIZE && region.entropy > HIGH_ENTROPY {
+            findings.push(Finding::HighEntropy {
+                name: region.name,
+                entropy: region.entropy,
+            });
+        }
+    }
+
+    Ok(Triage {
+        size: data.len() as u64,
+        entropy: entropy(data),
+        sections,
+        segments,
+        findings,
+    })
+}
+
+impl fmt::Display for Finding<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Finding::UpxMagic(offset) => write!(f, "UPX magic at offset {:#x}", offset),
+            Finding::UnpackedSegment {
+                sections,
+                segment,
+                filesz,
+                memsz,
+            } => write!(
+                f,
+                "{} sections and the executable segment {} takes {:#x} bytes in memory for {:#x} in the file",
+                sections, segment, memsz, filesz
+            ),
+            Finding::EntryOutsideText {
+                entry,
+                section: Some(section),
+            } => write!(f, "entry point {:#x} is in {}, not in .text", entry, section),
+            Finding::EntryOutsideText {
+                entry,
+                section: None,
+            } => write!(f, "entry point {:#x} is in no section", entry),
+            Finding::HighEntropy { name, entropy } => write!(
+                f,
+                "{} has an entropy of {:.2}, compressed or encrypted",
+                name, entropy
+            ),
+        }
+    }
+}
+
+/// The entropy of some regions as a table
+fn regions(f: &mut fmt::Formatter<'_>, title: &str, regions: &[Region<'_>]) -> fmt::Result {
+    if regions.is_empty() {
+        return Ok(());
+    }
+    writeln!(f)?;
+    writeln!(f, "{}:", title)?;
+    let mut table = TextTable::new(false)
+        .indent(2)
+        .column("[Nr]", Align::Left, None)
+        .column("Name", Align::Left, None)
+        .column("Offset", Align::Left, None)
+        .column("Size", Align::Left, None)
+        .column("Entropy", Align::Right, None);
+    for region in regions {
+        table.row([
+            format!("[{:>2}]", region.index),
+            region.name.to_string(),
+            format!("{:#08x}", region.offset),
+            format!("{:#08x}", region.size),
+            format!("{:.2}", region.entropy),
+        ]);
+    }
+    write!(f, "{}", table)
+}
+
+impl fmt::Display for Triage<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Entropy:  {:.2} bits per byte over {} bytes",
+            self.entropy, self.size
+        )?;
+        regions(f, "Sections", &self.sections)?;
+        regions(f, "Segments", &self.segments)?;
+        writeln!(f)?;
+        if self.findings.is_empty() {
+            return writeln!(f, "No sign of a packer found.");
+        }
+        writeln!(f, "Findings:")?;
+        for finding in &self.findings {
+            writeln!(f, "  {}", finding)?;
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for Region<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("index", Json::from(self.index)),
+            ("name", Json::from(self.name)),
+            ("offset", Json::from(self.offset)),
+            ("size", Json::from(self.size)),
+            ("entropy", Json::from(self.entropy)),
+        ])
+    }
+}
+
+impl ToJson for Finding<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("kind", Json::from(self.kind())),
+            ("description", Json::from(self.to_string())),
+        ])
+    }
+}
+
+impl ToJson for Triage<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("size", Json::from(self.size)),
+            ("entropy", Json::from(self.entropy)),
+            (
+                "sections",
+                Json::array(self.sections.iter().map(ToJson::to_json)),
+            ),
+            (
+                "segments",
+                Json::array(self.segments.iter().map(ToJson::to_json)),
+            ),
+            (
+                "findings",
+                Json::array(self.findings.iter().map(ToJson::to_json)),
+            ),
+        ])
+    }
+}
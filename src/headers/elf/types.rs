@@ -1,15 +1,75 @@
-/// Define the object file type
-pub struct TYPE {}
+use core::fmt;
 
-impl TYPE {
+/// Define the object file type
+///
+/// Type define the possible values of the `e_type` field. Decoding never
+/// fails, the values not known by this crate are kept in `Unknown`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Type {
     /// No file type
-    pub const NONE: u16 = 0;
+    None,
     /// Relocatable file
-    pub const REL: u16 = 1;
+    Rel,
     /// Executable file
-    pub const EXEC: u16 = 2;
+    Exec,
     /// Share object file
-    pub const DYN: u16 = 3;
+    Dyn,
     /// Core file
-    pub const CORE: u16 = 4;
+    Core,
+    /// Value not known by this crate
+    Unknown(u16),
+}
+
+impl Type {
+    /// Short name of the type as used by readelf, `DYN` for ET_DYN
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Type::None => "NONE",
+            Type::Rel => "REL",
+            Type::Exec => "EXEC",
+            Type::Dyn => "DYN",
+            Type::Core => "CORE",
+            Type::Unknown(_) => "<unknown>",
+        }
+    }
+}
+
+impl From<u16> for Type {
+    fn from(value: u16) -> Self {
+        match value {
+            0 => Type::None,
+            1 => Type::Rel,
+            2 => Type::Exec,
+            3 => Type::Dyn,
+            4 => Type::Core,
+            value => Type::Unknown(value),
+        }
+    }
+}
+
+impl From<Type> for u16 {
+    fn from(e_type: Type) -> Self {
+        match e_type {
+            Type::None => 0,
+            Type::Rel => 1,
+            Type::Exec => 2,
+            Type::Dyn => 3,
+            Type::Core => 4,
+            Type::Unknown(value) => value,
+        }
+    }
+}
+
+/// Describe the object file type as printed by readelf
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::None => f.write_str("NONE (No file type)"),
+            Type::Rel => f.write_str("REL (Relocatable file)"),
+            Type::Exec => f.write_str("EXEC (Executable file)"),
+            Type::Dyn => f.write_str("DYN (Share object file)"),
+            Type::Core => f.write_str("CORE (Core file)"),
+            Type::Unknown(value) => write!(f, "<unknown>: {:#x}", value),
+        }
+    }
 }
@@ -1,11 +1,46 @@
+use core::fmt;
+
 /// Define the version number of the elf specification
 ///
-/// Version define possible values for the version number
-/// Its store in the seventh byte of the identifaction 16 bits called `e_ident`
-pub struct VERSION {}
-impl VERSION {
+/// Version define the possible values of the seventh byte of the
+/// identification bytes `e_ident`. Decoding never fails, the values not
+/// known by this crate are kept in `Unknown`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Version {
     /// invalid version
-    pub const NONE: u8 = 0;
+    None,
     /// current elf version
-    pub const CURRENT: u8 = 1;
+    Current,
+    /// Value not known by this crate
+    Unknown(u8),
+}
+
+impl From<u8> for Version {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Version::None,
+            1 => Version::Current,
+            value => Version::Unknown(value),
+        }
+    }
+}
+
+impl From<Version> for u8 {
+    fn from(version: Version) -> Self {
+        match version {
+            Version::None => 0,
+            Version::Current => 1,
+            Version::Unknown(value) => value,
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Version::None => f.write_str("Invalid version"),
+            Version::Current => f.write_str("1 (current)"),
+            Version::Unknown(value) => write!(f, "{} <unknown>", value),
+        }
+    }
 }
@@ -0,0 +1,345 @@
+//! A picture of the address space of a file, as SVG or as an HTML report
+//!
+//! Each PT_LOAD segment of the [`AddressMap`] is drawn as a bar, in
+//! address order, with its sections scaled to their size in the segment
+//! and colored by the permissions of the segment. The segments themselves
+//! aren't scaled to each other, a 4 KiB segment would vanish next to a
+//! few MiB of code: each bar has the same width, and the gaps between
+//! them are written with their size. Hovering a section shows its name,
+//! address and size.
+use crate::alloc_prelude::*;
+use core::fmt::{self, Write};
+
+use super::map::{AddressMap, Hole, MapSection};
+
+/// Width of the whole picture
+const WIDTH: u64 = 960;
+/// Room left of the bars for the addresses
+const LEFT: u64 = 170;
+/// Width of a bar
+const BAR: u64 = 620;
+/// Height of a bar
+const ROW: u64 = 48;
+/// Height of the line between two bars
+const SPACE: u64 = 24;
+/// Height of the title
+const TOP: u64 = 40;
+/// Width of a character of the labels, roughly, to know if a name fits
+const CHAR: u64 = 7;
+
+/// The colors of the sections of a segment, lighter for every other
+/// section so the neighbours can be told apart
+fn colors(flags: &str) -> [&'static str; 2] {
+    if flags.contains('E') {
+        ["#f08a7e", "#f6b5ad"]
+    } else if flags.contains('W') {
+        ["#7fc97f", "#b3e2b3"]
+    } else {
+        ["#80a9e0", "#b5cdf0"]
+    }
+}
+
+/// Replace the characters with a meaning in XML
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Sizes in bytes, KiB or MiB, like `ls -h`
+fn human(size: u64) -> String {
+    match size {
+        0..1024 => format!("{} B", size),
+        1024..0x100000 => format!("{:.1} KiB", size as f64 / 1024.0),
+        _ => format!("{:.1} MiB", size as f64 / (1024.0 * 1024.0)),
+    }
+}
+
+/// The address space of an ELF64 file ready to be drawn
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagram<'a> {
+    /// written above the picture, usually the file name
+    pub title: String,
+    /// the segments, sections and gaps drawn
+    pub map: AddressMap<'a>,
+}
+
+impl<'a> Diagram<'a> {
+    /// A diagram of `map` titled `title`
+    pub fn new(title: &str, map: AddressMap<'a>) -> Self {
+        Diagram {
+            title: title.to_string(),
+            map,
+        }
+    }
+
+    /// Draw the sections of a bar spanning `start..start + size` at `y`
+    fn bar(
+        &self,
+        out: &mut String,
+        y: u64,
+        (start, size): (u64, u64),
+        flags: &str,
+        sections: &[MapSection],
+        fills: &[Hole],
+    ) -> fmt::Result {
+        let x = |address: u64| match size {
+            0 => LEFT,
+            size => {
+                LEFT + ((address.saturating_sub(start)) as u128 * BAR as u128 / size as u128) as u64
+            }
+        };
+        writeln!(
+            out,
+            r##"<rect x="{}" y="{}" width="{}" height="{}" fill="#ffffff" stroke="#444444"/>"##,
+            LEFT, y, BAR, ROW
+        )?;
+        for fill in fills {
+            let (left, right) = (x(fill.address), x(fill.address.saturating_add(fill.size)));
+            writeln!(
+                out,
+                r##"<rect x="{}" y="{}" width="{}" height="{}" fill="url(#fill)"><title>fill 0x{:x}, {}</title></rect>"##,
+                left,
+                y,
+                right.saturating_sub(left).max(1),
+                ROW,
+                fill.address,
+                human(fill.size)
+            )?;
+        }
+        let colors = colors(flags);
+        for (index, section) in sections.iter().enumerate() {
+            let left = x(section.address);
+            let width = x(section.address.saturating_add(section.size))
+                .saturating_sub(left)
+                .max(1);
+            let name = escape(&section.name.to_str_lossy());
+            writeln!(
+                out,
+                r##"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" stroke="#444444" stroke-width="0.5"><title>{} 0x{:x}, {}</title></rect>"##,
+                left,
+                y,
+                width,
+                ROW,
+                colors[index % 2],
+                name,
+                section.address,
+                human(section.size)
+            )?;
+            if width >= section.name.len() as u64 * CHAR + 4 {
+                writeln!(
+                    out,
+                    r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#,
+                    left + width / 2,
+                    y + ROW / 2 + 4,
+                    name
+                )?;
+            }
+        }
+        writeln!(
+            out,
+            r#"<text x="{}" y="{}" text-anchor="end" class="address">0x{:x}</text>"#,
+            LEFT - 8,
+            y + 12,
+            start
+        )?;
+        writeln!(
+            out,
+            r#"<text x="{}" y="{}" text-anchor="end" class="address">0x{:x}</text>"#,
+            LEFT - 8,
+            y + ROW - 2,
+            start.saturating_add(size)
+        )
+    }
+
+    /// The picture as a standalone SVG document
+    pub fn svg(&self) -> String {
+        let mut body = String::new();
+        // writing to a String doesn't fail
+        let _ = self.svg_body(&mut body);
+        body
+    }
+
+    fn svg_body(&self, out: &mut String) -> fmt::Result {
+        let unmapped = !self.map.unmapped.is_empty();
+        let rows = self.map.segments.len() as u64 + unmapped as u64;
+        let gaps = self.map.gaps.len() as u64 + unmapped as u64;
+        let height = TOP + rows.max(1) * (ROW + SPACE) + gaps * SPACE;
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}" font-family="monospace" font-size="12">"#,
+            WIDTH, height
+        )?;
+        writeln!(
+            out,
+            r##"<defs><pattern id="fill" width="6" height="6" patternUnits="userSpaceOnUse" patternTransform="rotate(45)"><rect width="6" height="6" fill="#eeeeee"/><line x1="0" y1="0" x2="0" y2="6" stroke="#bbbbbb" stroke-width="2"/></pattern></defs>"##
+        )?;
+        writeln!(
+            out,
+            "<style>.address {{ fill: #555555; font-size: 11px; }}</style>"
+        )?;
+        writeln!(
+            out,
+            r#"<text x="{}" y="24" font-size="16" font-weight="bold">{}</text>"#,
+            LEFT,
+            escape(&self.title)
+        )?;
+        let mut y = TOP;
+        if self.map.segments.is_empty() && !unmapped {
+            return writeln!(
+                out,
+                r#"<text x="{}" y="{}">no loaded segments or allocated sections</text></svg>"#,
+                LEFT,
+                y + ROW / 2
+            );
+        }
+        let mut gaps = self.map.gaps.iter().peekable();
+        for segment in &self.map.segments {
+            while let Some(gap) = gaps.next_if(|gap| gap.address < segment.address) {
+                writeln!(
+                    out,
+                    r##"<text x="{}" y="{}" text-anchor="middle" fill="#777777">gap of {}</text>"##,
+                    LEFT + BAR / 2,
+                    y + SPACE / 2,
+                    human(gap.size)
+                )?;
+                y += SPACE;
+            }
+            self.bar(
+                out,
+                y,
+                (segment.address, segment.size),
+                &segment.flags,
+                &segment.sections,
+                &segment.fills,
+            )?;
+            writeln!(
+                out,
+                r#"<text x="{}" y="{}">LOAD [{}] {}</text>"#,
+                LEFT + BAR + 10,
+                y + ROW / 2 - 2,
+                segment.index,
+                escape(&segment.flags)
+            )?;
+            writeln!(
+                out,
+                r#"<text x="{}" y="{}" class="address">{}</text>"#,
+                LEFT + BAR + 10,
+                y + ROW / 2 + 12,
+                human(segment.size)
+            )?;
+            y += ROW + SPACE;
+        }
+        if unmapped {
+            // often all at 0 in relocatable files, drawn one after the other
+            let mut span = 0u64;
+            let sections: Vec<MapSection> = self
+                .map
+                .unmapped
+                .iter()
+                .map(|section| {
+                    let placed = MapSection {
+                        address: span,
+                        ..section.clone()
+                    };
+                    span = span.saturating_add(section.size);
+                    placed
+                })
+                .collect();
+            writeln!(
+                out,
+                r#"<text x="{}" y="{}">allocated sections outside of the segments, by size</text>"#,
+                LEFT,
+                y + SPACE / 2 + 4
+            )?;
+            y += SPACE;
+            self.bar(out, y, (0, span), "R", &sections, &[])?;
+        }
+        writeln!(out, "</svg>")
+    }
+
+    /// A self-contained HTML page with the picture and the table of the
+    /// segments and sections
+    pub fn html(&self) -> String {
+        let mut out = String::new();
+        let _ = self.html_body(&mut out);
+        out
+    }
+
+    fn html_body(&self, out: &mut String) -> fmt::Result {
+        let title = escape(&self.title);
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html>")?;
+        writeln!(out, "<head>")?;
+        writeln!(out, r#"<meta charset="utf-8">"#)?;
+        writeln!(out, "<title>Address space of {}</title>", title)?;
+        writeln!(
+            out,
+            "<style>body {{ font-family: sans-serif; margin: 2em; }} table {{ border-collapse: collapse; font-family: monospace; }} td, th {{ border: 1px solid #cccccc; padding: 2px 8px; text-align: left; }} .segment {{ background: #f0f0f0; font-weight: bold; }}</style>"
+        )?;
+        writeln!(out, "</head>")?;
+        writeln!(out, "<body>")?;
+        writeln!(out, "<h1>Address space of {}</h1>", title)?;
+        self.svg_body(out)?;
+        writeln!(out, "<table>")?;
+        writeln!(
+            out,
+            "<tr><th>Address</th><th>Size</th><th>Name</th><th>Flags</th></tr>"
+        )?;
+        let row =
+            |out: &mut String, class: &str, address: u64, size: u64, name: &str, flags: &str| {
+                writeln!(
+                    out,
+                    r#"<tr{}><td>0x{:016x}</td><td>{}</td><td>{}</td><td>{}</td></tr>"#,
+                    class,
+                    address,
+                    human(size),
+                    escape(name),
+                    escape(flags)
+                )
+            };
+        for segment in &self.map.segments {
+            let name = format!("LOAD [{}]", segment.index);
+            row(
+                out,
+                r#" class="segment""#,
+                segment.address,
+                segment.size,
+                &name,
+                &segment.flags,
+            )?;
+            for section in &segment.sections {
+                row(
+                    out,
+                    "",
+                    section.address,
+                    section.size,
+                    &section.name.to_str_lossy(),
+                    "",
+                )?;
+            }
+        }
+        for section in &self.map.unmapped {
+            row(
+                out,
+                "",
+                section.address,
+                section.size,
+                &section.name.to_str_lossy(),
+                "",
+            )?;
+        }
+        writeln!(out, "</table>")?;
+        writeln!(out, "</body>")?;
+        writeln!(out, "</html>")
+    }
+}
@@ -1,14 +1,20 @@
-use crate::utils::cow_struct;
-use std::borrow::Cow;
-use std::fmt;
-use std::mem::size_of;
-
-use super::class::Class;
-use super::data::DATA;
-use super::identification::Indent;
-use super::osabit::OSABIT;
-use super::types::TYPE;
-use super::version::VERSION;
+use crate::alloc_prelude::*;
+use crate::utils::json::{Json, ToJson};
+use crate::utils::{cow_struct_endian, impl_fields, impl_swap_bytes, impl_to_bytes};
+use crate::{Error, Result};
+use alloc::borrow::Cow;
+use core::fmt;
+use core::mem::{align_of, size_of};
+
+use super::check_size;
+use super::data::Data;
+use super::flags;
+use super::identification::{fmt_header, ident_json, ident_rows, Indent};
+use super::machine::Machine;
+use super::program::Elf64_Phdr;
+use super::section::{Elf64_Shdr, SHN};
+use super::table::{check_table, Table};
+use super::types::Type;
 
 /// Format of Executable and Linking Format (ELF64) files
 ///
@@ -23,8 +29,8 @@ use super::version::VERSION;
 /// offset in the file are defined in the ELF header.  The two tables
 /// describe the rest of the particularities of the file.
 /// Sources:
-/// * https://www.man7.org/linux/man-pages/man5/elf.5.html
-/// * https://uclibc.org/docs/elf-64-gen.pdf
+/// * <https://www.man7.org/linux/man-pages/man5/elf.5.html>
+/// * <https://uclibc.org/docs/elf-64-gen.pdf>
 #[derive(Debug, Default, Clone, Copy)]
 #[repr(C)]
 pub struct x64 {
@@ -60,98 +66,245 @@ pub struct x64 {
 
 impl x64 {
     pub const SIZE: usize = size_of::<Self>();
+    /// `e_phnum` of the files with at least 0xffff program headers, the
+    /// number is in the `sh_info` field of the first section header
+    pub const PN_XNUM: u16 = 0xffff;
+
+    /// The first entry of the section header table
+    ///
+    /// Files with too many sections or segments for the 16 bits fields of
+    /// the file header store the counts in its `sh_size`, `sh_link` and
+    /// `sh_info` fields.
+    pub fn initial_section(&self, data: &[u8]) -> Option<Elf64_Shdr> {
+        if self.e_shoff == 0 {
+            return None;
+        }
+        let big_endian = Data::is_big_endian(&self.e_ident);
+        Table::<Elf64_Shdr>::parse(data, self.e_shoff, self.e_shentsize as u64, 1, big_endian)
+            .ok()?
+            .next()
+            .map(Cow::into_owned)
+    }
+
+    /// Number of program headers, `sh_info` of the first section when
+    /// `e_phnum` is PN_XNUM
+    pub fn phnum(&self, data: &[u8]) -> u64 {
+        self.phnum_with(self.initial_section(data).as_ref())
+    }
+
+    /// Number of section headers, `sh_size` of the first section when
+    /// `e_shnum` is 0 and the table exists
+    pub fn shnum(&self, data: &[u8]) -> u64 {
+        self.shnum_with(self.initial_section(data).as_ref())
+    }
+
+    /// Index of the section name string table, `sh_link` of the first
+    /// section when `e_shstrndx` is SHN_XINDEX
+    pub fn shstrndx(&self, data: &[u8]) -> u32 {
+        self.shstrndx_with(self.initial_section(data).as_ref())
+    }
+
+    pub(crate) fn phnum_with(&self, initial: Option<&Elf64_Shdr>) -> u64 {
+        match (self.e_phnum, initial) {
+            (x64::PN_XNUM, Some(shdr)) if shdr.sh_info != 0 => shdr.sh_info as u64,
+            (phnum, _) => phnum as u64,
+        }
+    }
+
+    pub(crate) fn shnum_with(&self, initial: Option<&Elf64_Shdr>) -> u64 {
+        match (self.e_shnum, initial) {
+            (0, Some(shdr)) => shdr.sh_size,
+            (shnum, _) => shnum as u64,
+        }
+    }
+
+    pub(crate) fn shstrndx_with(&self, initial: Option<&Elf64_Shdr>) -> u32 {
+        match (self.e_shstrndx, initial) {
+            (SHN::XINDEX, Some(shdr)) => shdr.sh_link,
+            (index, _) => index as u32,
+        }
+    }
+
+    /// Sanity check the header fields against the file they were read from
+    ///
+    /// Verifies that the header and table entry sizes match the
+    /// specification and that both tables fit inside `data`.
+    pub fn validate(&self, data: &[u8]) -> Result<()> {
+        check_size("e_ehsize", self.e_ehsize, x64::SIZE)?;
+        if self.e_phnum != 0 {
+            check_size("e_phentsize", self.e_phentsize, Elf64_Phdr::SIZE)?;
+        }
+        let initial = self.initial_section(data);
+        if self.shnum_with(initial.as_ref()) != 0 {
+            check_size("e_shentsize", self.e_shentsize, Elf64_Shdr::SIZE)?;
+        }
+        check_table(
+            data.len(),
+            self.e_phoff,
+            self.e_phentsize as u64,
+            self.phnum_with(initial.as_ref()),
+            Elf64_Phdr::SIZE,
+            align_of::<Elf64_Phdr>(),
+        )?;
+        check_table(
+            data.len(),
+            self.e_shoff,
+            self.e_shentsize as u64,
+            self.shnum_with(initial.as_ref()),
+            Elf64_Shdr::SIZE,
+            align_of::<Elf64_Shdr>(),
+        )?;
+        Ok(())
+    }
 }
 
-pub fn from_bytes(data: &[u8]) -> Option<Cow<x64>> {
+impl_swap_bytes!(
+    x64,
+    e_type,
+    e_machine,
+    e_version,
+    e_entry,
+    e_phoff,
+    e_shoff,
+    e_flags,
+    e_ehsize,
+    e_phentsize,
+    e_phnum,
+    e_shentsize,
+    e_shnum,
+    e_shstrndx
+);
+
+impl_to_bytes!(
+    x64,
+    e_ident,
+    e_type,
+    e_machine,
+    e_version,
+    e_entry,
+    e_phoff,
+    e_shoff,
+    e_flags,
+    e_ehsize,
+    e_phentsize,
+    e_phnum,
+    e_shentsize,
+    e_shnum,
+    e_shstrndx,
+);
+
+impl_fields!(
+    x64,
+    e_ident,
+    e_type,
+    e_machine,
+    e_version,
+    e_entry,
+    e_phoff,
+    e_shoff,
+    e_flags,
+    e_ehsize,
+    e_phentsize,
+    e_phnum,
+    e_shentsize,
+    e_shnum,
+    e_shstrndx,
+);
+
+pub fn from_bytes(data: &[u8]) -> Result<Cow<'_, x64>> {
+    let truncated = Error::TruncatedHeader {
+        expected: x64::SIZE,
+        found: data.len(),
+    };
     if data.len() < x64::SIZE {
-        return None;
+        return Err(truncated);
     }
+    Indent::check_magic(data)?;
     let (header_bytes, _data) = data.split_at(x64::SIZE);
-    cow_struct::<x64>(header_bytes)
+    let big_endian = Data::from(header_bytes[Indent::DATA]) == Data::Be;
+    cow_struct_endian::<x64>(header_bytes, big_endian).ok_or(truncated)
 }
 
-impl fmt::Display for x64 {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "ELF Hearder:").unwrap();
-
-        // Write indent
-        writeln!(
-            f,
-            "  Magic:  {}",
-            self.e_ident
-                .iter()
-                .map(|hex| format!("{:02X?} ", hex))
-                .collect::<String>()
-        )
-        .unwrap();
-
-        // Write class
-        let class = match self.e_ident[Indent::CLASS] {
-            Class::NONE => "Invalid class",
-            Class::ELF32 => "ELF32",
-            Class::ELF64 => "ELF64",
-            _ => "Warning: unknown class",
-        };
-        writeln!(f, "  Class:\t\t\t\t{},", class).unwrap();
-
-        // write data encoding
-        let data_encoding = match self.e_ident[Indent::DATA] {
-            DATA::NONE => "Unknown data encoding",
-            DATA::BE => "2's complement, big endian",
-            DATA::LE => "2's complement, little endian",
-            _ => "Warning: unknow data encoding",
-        };
-        writeln!(f, "  Data:\t\t\t\t\t{}", data_encoding).unwrap();
-
-        // write current number version of elf specification
-        let current = format!("{} (current)", self.e_ident[Indent::VERSION]);
-        let version = match self.e_ident[Indent::VERSION] {
-            VERSION::NONE => "Invalid version",
-            VERSION::CURRENT => current.as_str(),
-            _ => "Warning: unknow version",
-        };
-        writeln!(f, "  Version:\t\t\t\t{}", version).unwrap();
-
-        // write target os application binary interface
-        let osabit = match self.e_ident[Indent::OSABIT] {
-            OSABIT::SYSV => "UNIX System V ABI",
-            OSABIT::HPUX => "HP-UX",
-            OSABIT::NETBSD => "NetBSD",
-            OSABIT::GNU => "Object use GNU ELF extensions",
-            OSABIT::SOLARIS => "Sun Solaris",
-            OSABIT::AIX => "IBM AIX",
-            OSABIT::IRIX => "SGI Irix",
-            OSABIT::FREEBSD => "FreeBSD",
-            OSABIT::TRU64 => "Compaq tru64 unix",
-            OSABIT::MODESTO => "Novell Modesto",
-            OSABIT::OPENBSD => "OpenBSD",
-            OSABIT::ARM_AEABI => "ARM AEABI",
-            OSABIT::ARM => "ARM",
-            OSABIT::STANDALONE => "Standalone embedded application",
-            _ => "Warning: unknow operating system target",
-        };
-        writeln!(f, "  OS/ABI:\t\t\t\t{}", osabit).unwrap();
+impl x64 {
+    /// The flags and the names of their bits for the machine of the file
+    pub fn flags_string(&self) -> String {
+        flags::describe(self.e_machine, self.e_flags)
+    }
 
-        let abi_version_message = match self.e_ident[Indent::ABIVERSION] {
-            0 => "0",
-            _ => "Warning: Not compatible with the specification",
-        };
-        writeln!(f, "  ABI Version:\t\t\t\t{}", abi_version_message).unwrap();
-
-        // write object file type
-        let obj_type = match self.e_type {
-            TYPE::NONE => "NONE (No file type)",
-            TYPE::REL => "REL (Relocatable file)",
-            TYPE::EXEC => "EXEC (Executable file)",
-            TYPE::DYN => "DYN (Share object file)",
-            TYPE::CORE => "CORE (Core file)",
-            _ => "Warning: unknow object file type",
+    /// The fields printed by `readbin -h`, as (name, value) rows
+    pub(crate) fn rows(&self) -> Vec<(&'static str, String)> {
+        let mut rows = ident_rows(&self.e_ident);
+        rows.push(("Type:", Type::from(self.e_type).to_string()));
+        let machine = match Machine::from_u16(self.e_machine) {
+            Machine::Unknown(value) => format!("<unknown>: {:#x}", value),
+            machine => machine.as_str().to_string(),
         };
-        writeln!(f, "  Type: \t\t\t\t{}", obj_type).unwrap();
+        rows.push(("Machine:", machine));
+        rows.push(("Version:", format!("{:#x}", self.e_version)));
+        rows.push(("Entry point address:", format!("{:#x}", self.e_entry)));
+        rows.extend([
+            (
+                "Start of program headers:",
+                format!("{} (bytes into file)", self.e_phoff),
+            ),
+            (
+                "Start of section headers:",
+                format!("{} (bytes into file)", self.e_shoff),
+            ),
+            ("Flags:", self.flags_string()),
+            ("Size of this header:", format!("{} (bytes)", self.e_ehsize)),
+            (
+                "Size of program headers:",
+                format!("{} (bytes)", self.e_phentsize),
+            ),
+            ("Number of program headers:", self.e_phnum.to_string()),
+            (
+                "Size of section headers:",
+                format!("{} (bytes)", self.e_shentsize),
+            ),
+            ("Number of section headers:", self.e_shnum.to_string()),
+            (
+                "Section header string table index:",
+                self.e_shstrndx.to_string(),
+            ),
+        ]);
+        rows
+    }
+}
 
-        // TODO add machine
+impl fmt::Display for x64 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_header(f, &self.rows(), false)
+    }
+}
 
-        // write current number version of elf specification
-        return writeln!(f, "  Version:\t\t\t\t{:#x}", self.e_version);
+impl ToJson for x64 {
+    fn to_json(&self) -> Json {
+        let mut members = ident_json(&self.e_ident);
+        members.extend([
+            ("e_type", Json::from(self.e_type)),
+            ("type", Json::from(Type::from(self.e_type).to_string())),
+            ("e_machine", Json::from(self.e_machine)),
+            (
+                "machine",
+                Json::from(Machine::from_u16(self.e_machine).as_str()),
+            ),
+            ("e_version", Json::from(self.e_version)),
+            ("e_entry", Json::from(self.e_entry)),
+            ("e_phoff", Json::from(self.e_phoff)),
+            ("e_shoff", Json::from(self.e_shoff)),
+            ("e_flags", Json::from(self.e_flags)),
+            (
+                "flags",
+                Json::array(flags::names(self.e_machine, self.e_flags)),
+            ),
+            ("e_ehsize", Json::from(self.e_ehsize)),
+            ("e_phentsize", Json::from(self.e_phentsize)),
+            ("e_phnum", Json::from(self.e_phnum)),
+            ("e_shentsize", Json::from(self.e_shentsize)),
+            ("e_shnum", Json::from(self.e_shnum)),
+            ("e_shstrndx", Json::from(self.e_shstrndx)),
+        ]);
+        Json::object(members)
     }
 }
@@ -0,0 +1,704 @@
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::demangle::{demangle, try_demangle};
+use crate::utils::json::{Json, ToJson};
+use crate::utils::table::{Align, Color, Table as TextTable};
+use crate::utils::{cow_struct_endian, impl_fields, impl_swap_bytes, impl_to_bytes};
+use crate::{Error, Result};
+use alloc::borrow::Cow;
+use core::fmt;
+use core::mem::size_of;
+
+use super::check_size;
+use super::data::Data;
+use super::flags;
+use super::identification::{fmt_header, ident_json, ident_rows, Indent};
+use super::machine::Machine;
+use super::registry;
+use super::section::{self, Elf64_Shdr, SHT};
+use super::strtab::{StrTab, CORRUPT};
+use super::symbols::{self, Elf64_Sym, STT};
+use super::table::{check_table, Table};
+use super::types::Type;
+use super::x64::x64;
+
+/// Format of Executable and Linking Format (ELF32) files
+///
+/// Same header as the ELF64 one, except that addresses and offsets
+/// are stored on 32 bits.
+/// Sources:
+/// * <https://www.man7.org/linux/man-pages/man5/elf.5.html>
+/// * <https://refspecs.linuxfoundation.org/elf/elf.pdf>
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct x86 {
+    /// ELF identifaction
+    pub e_ident: [u8; 16],
+    /// object file type
+    pub e_type: u16,
+    /// machine type
+    pub e_machine: u16,
+    /// object file version
+    pub e_version: u32,
+    /// Entry point address
+    pub e_entry: u32,
+    /// program header offset
+    pub e_phoff: u32,
+    /// section header offset
+    pub e_shoff: u32,
+    /// processor specific flags
+    pub e_flags: u32,
+    /// elf header size
+    pub e_ehsize: u16,
+    /// Size of program header entry
+    pub e_phentsize: u16,
+    /// numbers of program header entries
+    pub e_phnum: u16,
+    /// size of section header entry
+    pub e_shentsize: u16,
+    /// number of section header entries
+    pub e_shnum: u16,
+    /// section name string table index
+    pub e_shstrndx: u16,
+}
+
+impl x86 {
+    pub const SIZE: usize = size_of::<Self>();
+    /// Size of an ELF32 program header entry
+    pub const PHENTSIZE: usize = 32;
+    /// Size of an ELF32 section header entry
+    pub const SHENTSIZE: usize = 40;
+
+    /// Sanity check the header fields against the file they were read from
+    ///
+    /// Verifies that the header and table entry sizes match the
+    /// specification and that both tables fit inside `data`.
+    pub fn validate(&self, data: &[u8]) -> Result<()> {
+        check_size("e_ehsize", self.e_ehsize, x86::SIZE)?;
+        if self.e_phnum != 0 {
+            check_size("e_phentsize", self.e_phentsize, x86::PHENTSIZE)?;
+        }
+        if self.e_shnum != 0 {
+            check_size("e_shentsize", self.e_shentsize, x86::SHENTSIZE)?;
+        }
+        check_table(
+            data.len(),
+            self.e_phoff as u64,
+            self.e_phentsize as u64,
+            self.e_phnum as u64,
+            x86::PHENTSIZE,
+            4,
+        )?;
+        check_table(
+            data.len(),
+            self.e_shoff as u64,
+            self.e_shentsize as u64,
+            self.e_shnum as u64,
+            x86::SHENTSIZE,
+            4,
+        )?;
+        Ok(())
+    }
+}
+
+impl_swap_bytes!(
+    x86,
+    e_type,
+    e_machine,
+    e_version,
+    e_entry,
+    e_phoff,
+    e_shoff,
+    e_flags,
+    e_ehsize,
+    e_phentsize,
+    e_phnum,
+    e_shentsize,
+    e_shnum,
+    e_shstrndx
+);
+
+impl_to_bytes!(
+    x86,
+    e_ident,
+    e_type,
+    e_machine,
+    e_version,
+    e_entry,
+    e_phoff,
+    e_shoff,
+    e_flags,
+    e_ehsize,
+    e_phentsize,
+    e_phnum,
+    e_shentsize,
+    e_shnum,
+    e_shstrndx,
+);
+
+impl_fields!(
+    x86,
+    e_ident,
+    e_type,
+    e_machine,
+    e_version,
+    e_entry,
+    e_phoff,
+    e_shoff,
+    e_flags,
+    e_ehsize,
+    e_phentsize,
+    e_phnum,
+    e_shentsize,
+    e_shnum,
+    e_shstrndx,
+);
+
+pub fn from_bytes(data: &[u8]) -> Result<Cow<'_, x86>> {
+    let truncated = Error::TruncatedHeader {
+        expected: x86::SIZE,
+        found: data.len(),
+    };
+    if data.len() < x86::SIZE {
+        return Err(truncated);
+    }
+    Indent::check_magic(data)?;
+    let (header_bytes, _data) = data.split_at(x86::SIZE);
+    let big_endian = Data::from(header_bytes[Indent::DATA]) == Data::Be;
+    cow_struct_endian::<x86>(header_bytes, big_endian).ok_or(truncated)
+}
+
+impl x86 {
+    /// The flags and the names of their bits for the machine of the file
+    pub fn flags_string(&self) -> String {
+        flags::describe(self.e_machine, self.e_flags)
+    }
+
+    /// The fields printed by `readbin -h`, as (name, value) rows
+    pub(crate) fn rows(&self) -> Vec<(&'static str, String)> {
+        let mut rows = ident_rows(&self.e_ident);
+        rows.push(("Type:", Type::from(self.e_type).to_string()));
+        let machine = match Machine::from_u16(self.e_machine) {
+            Machine::Unknown(value) => format!("<unknown>: {:#x}", value),
+            machine => machine.as_str().to_string(),
+        };
+        rows.push(("Machine:", machine));
+        rows.push(("Version:", format!("{:#x}", self.e_version)));
+        rows.push(("Entry point address:", format!("{:#x}", self.e_entry)));
+        rows.extend([
+            (
+                "Start of program headers:",
+                format!("{} (bytes into file)", self.e_phoff),
+            ),
+            (
+                "Start of section headers:",
+                format!("{} (bytes into file)", self.e_shoff),
+            ),
+            ("Flags:", self.flags_string()),
+            ("Size of this header:", format!("{} (bytes)", self.e_ehsize)),
+            (
+                "Size of program headers:",
+                format!("{} (bytes)", self.e_phentsize),
+            ),
+            ("Number of program headers:", self.e_phnum.to_string()),
+            (
+                "Size of section headers:",
+                format!("{} (bytes)", self.e_shentsize),
+            ),
+            ("Number of section headers:", self.e_shnum.to_string()),
+            (
+                "Section header string table index:",
+                self.e_shstrndx.to_string(),
+            ),
+        ]);
+        rows
+    }
+}
+
+impl fmt::Display for x86 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_header(f, &self.rows(), false)
+    }
+}
+
+impl ToJson for x86 {
+    fn to_json(&self) -> Json {
+        let mut members = ident_json(&self.e_ident);
+        members.extend([
+            ("e_type", Json::from(self.e_type)),
+            ("type", Json::from(Type::from(self.e_type).to_string())),
+            ("e_machine", Json::from(self.e_machine)),
+            (
+                "machine",
+                Json::from(Machine::from_u16(self.e_machine).as_str()),
+            ),
+            ("e_version", Json::from(self.e_version)),
+            ("e_entry", Json::from(self.e_entry)),
+            ("e_phoff", Json::from(self.e_phoff)),
+            ("e_shoff", Json::from(self.e_shoff)),
+            ("e_flags", Json::from(self.e_flags)),
+            (
+                "flags",
+                Json::array(flags::names(self.e_machine, self.e_flags)),
+            ),
+            ("e_ehsize", Json::from(self.e_ehsize)),
+            ("e_phentsize", Json::from(self.e_phentsize)),
+            ("e_phnum", Json::from(self.e_phnum)),
+            ("e_shentsize", Json::from(self.e_shentsize)),
+            ("e_shnum", Json::from(self.e_shnum)),
+            ("e_shstrndx", Json::from(self.e_shstrndx)),
+        ]);
+        Json::object(members)
+    }
+}
+
+/// Section header table entry of an ELF32 file
+///
+/// Same fields as `Elf64_Shdr`, with 32 bits flags, addresses and sizes.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct Elf32_Shdr {
+    /// offset of the section name in the section name string table
+    pub sh_name: u32,
+    /// section type
+    pub sh_type: u32,
+    /// section attributes
+    pub sh_flags: u32,
+    /// virtual address of the section in memory
+    pub sh_addr: u32,
+    /// offset of the section in the file
+    pub sh_offset: u32,
+    /// size of the section in bytes
+    pub sh_size: u32,
+    /// link to another section
+    pub sh_link: u32,
+    /// additional information
+    pub sh_info: u32,
+    /// address alignment boundary
+    pub sh_addralign: u32,
+    /// size of the entries if the section holds a table
+    pub sh_entsize: u32,
+}
+
+impl_swap_bytes!(
+    Elf32_Shdr,
+    sh_name,
+    sh_type,
+    sh_flags,
+    sh_addr,
+    sh_offset,
+    sh_size,
+    sh_link,
+    sh_info,
+    sh_addralign,
+    sh_entsize
+);
+
+impl Elf32_Shdr {
+    /// Bytes of the section stored in the file
+    ///
+    /// Returns None for SHT_NOBITS sections or if the section lies
+    /// outside of the given data.
+    pub fn data<'a>(&self, data: &'a [u8]) -> Option<&'a [u8]> {
+        if self.sh_type == SHT::NOBITS {
+            return None;
+        }
+        let start = self.sh_offset as usize;
+        data.get(start..start.checked_add(self.sh_size as usize)?)
+    }
+
+    /// Resolve the section name in the given section name string table
+    ///
+    /// The name is the bytes before the nul terminator, whatever their
+    /// encoding.
+    pub fn name<'a>(&self, strtab: &StrTab<'a>) -> Result<ByteStr<'a>> {
+        strtab.name(self.sh_name as usize)
+    }
+}
+
+/// Symbol table entry of an ELF32 file
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct Elf32_Sym {
+    /// offset of the name in the string table
+    pub st_name: u32,
+    /// value of the symbol, an address in linked files
+    pub st_value: u32,
+    /// size of the object
+    pub st_size: u32,
+    /// type and binding
+    pub st_info: u8,
+    /// visibility
+    pub st_other: u8,
+    /// index of the section the symbol is defined in
+    pub st_shndx: u16,
+}
+
+impl_swap_bytes!(Elf32_Sym, st_name, st_value, st_size, st_info, st_other, st_shndx);
+
+/// Relocation entry without addend of an ELF32 file
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct Elf32_Rel {
+    /// offset of the relocated word in its section
+    pub r_offset: u32,
+    /// symbol index and type
+    pub r_info: u32,
+}
+
+impl_swap_bytes!(Elf32_Rel, r_offset, r_info);
+
+impl Elf32_Rel {
+    /// Index of the symbol in the linked symbol table
+    pub fn sym(&self) -> u32 {
+        self.r_info >> 8
+    }
+
+    /// Type of the relocation, depending on the machine
+    pub fn r_type(&self) -> u32 {
+        self.r_info & 0xff
+    }
+}
+
+/// Returns an iterator over the section headers of an ELF32 file
+///
+/// The number of sections is read from the first header when `e_shnum`
+/// is 0, like for ELF64 files.
+pub fn section_headers<'a>(data: &'a [u8], header: &x86) -> Result<Table<'a, Elf32_Shdr>> {
+    let big_endian = Data::is_big_endian(&header.e_ident);
+    let entsize = header.e_shentsize as u64;
+    let mut shnum = header.e_shnum as u64;
+    if shnum == 0 && header.e_shoff != 0 {
+        shnum = Table::<Elf32_Shdr>::parse(data, header.e_shoff as u64, entsize, 1, big_endian)?
+            .get(0)
+            .map_or(0, |first| first.sh_size as u64);
+    }
+    Table::parse(data, header.e_shoff as u64, entsize, shnum, big_endian)
+}
+
+/// Returns the section name string table of an ELF32 file
+///
+/// The table is empty if the file has no section name string table.
+pub fn section_names<'a>(data: &'a [u8], header: &x86) -> Result<StrTab<'a>> {
+    if header.e_shstrndx == 0 {
+        return Ok(StrTab::default());
+    }
+    let index = header.e_shstrndx as u32;
+    let shdr = section_headers(data, header)?
+        .get(index as usize)
+        .ok_or(Error::InvalidSectionIndex(index))?;
+    shdr.data(data).map(StrTab::new).ok_or(Error::OutOfBounds {
+        offset: shdr.sh_offset as u64,
+        size: shdr.sh_size as u64,
+    })
+}
+
+impl From<Elf32_Shdr> for Elf64_Shdr {
+    fn from(shdr: Elf32_Shdr) -> Self {
+        Elf64_Shdr {
+            sh_name: shdr.sh_name,
+            sh_type: shdr.sh_type,
+            sh_flags: u64::from(shdr.sh_flags),
+            sh_addr: u64::from(shdr.sh_addr),
+            sh_offset: u64::from(shdr.sh_offset),
+            sh_size: u64::from(shdr.sh_size),
+            sh_link: shdr.sh_link,
+            sh_info: shdr.sh_info,
+            sh_addralign: u64::from(shdr.sh_addralign),
+            sh_entsize: u64::from(shdr.sh_entsize),
+        }
+    }
+}
+
+impl From<Elf32_Sym> for Elf64_Sym {
+    fn from(symbol: Elf32_Sym) -> Self {
+        Elf64_Sym {
+            st_name: symbol.st_name,
+            st_info: symbol.st_info,
+            st_other: symbol.st_other,
+            st_shndx: symbol.st_shndx,
+            st_value: u64::from(symbol.st_value),
+            st_size: u64::from(symbol.st_size),
+        }
+    }
+}
+
+/// The fields of the header the names of the OS and processor specific
+/// section types depend on
+fn scope(header: &x86) -> x64 {
+    x64 {
+        e_ident: header.e_ident,
+        e_machine: header.e_machine,
+        ..x64::default()
+    }
+}
+
+/// The section headers of an ELF32 file, widened to `Elf64_Shdr`
+fn sections(data: &[u8], header: &x86) -> Result<Vec<Elf64_Shdr>> {
+    Ok(section_headers(data, header)?
+        .map(|shdr| Elf64_Shdr::from(*shdr))
+        .collect())
+}
+
+/// The symbols of a SHT_SYMTAB or SHT_DYNSYM section, widened to
+/// `Elf64_Sym`
+fn symbols(data: &[u8], header: &x86, shdr: &Elf64_Shdr) -> Result<Vec<Elf64_Sym>> {
+    let entsize = match shdr.sh_entsize {
+        0 => size_of::<Elf32_Sym>() as u64,
+        entsize => entsize,
+    };
+    let big_endian = Data::is_big_endian(&header.e_ident);
+    let num = shdr.sh_size / entsize;
+    Ok(
+        Table::<Elf32_Sym>::parse(data, shdr.sh_offset, entsize, num, big_endian)?
+            .map(|symbol| Elf64_Sym::from(*symbol))
+            .collect(),
+    )
+}
+
+/// Section header table of an ELF32 file ready to be printed like
+/// `readelf -S`
+pub struct SectionHeaderTable<'a> {
+    /// the whole file, used to resolve the section names
+    pub data: &'a [u8],
+    /// the file header
+    pub header: &'a x86,
+    /// write the names, types and addresses with ANSI colors
+    pub color: bool,
+    /// print the names whole like `readelf -W`, instead of cutting them
+    /// to [`section::NAME_WIDTH`] characters
+    pub wide: bool,
+}
+
+impl fmt::Display for SectionHeaderTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shdrs = match sections(self.data, self.header) {
+            Ok(shdrs) => shdrs,
+            Err(err) => return writeln!(f, "Warning: invalid section header table: {}", err),
+        };
+        if shdrs.is_empty() {
+            return writeln!(f, "There are no sections in this file.");
+        }
+        let strtab = section_names(self.data, self.header).unwrap_or_default();
+        let scope = scope(self.header);
+        writeln!(
+            f,
+            "There are {} section headers, starting at offset {:#x}:",
+            shdrs.len(),
+            self.header.e_shoff
+        )?;
+        writeln!(f)?;
+        writeln!(f, "Section Headers:")?;
+        let mut table = TextTable::new(self.color)
+            .indent(2)
+            .column("[Nr]", Align::Left, None)
+            .column("Name", Align::Left, Some(Color::Green))
+            .limit((!self.wide).then_some(section::NAME_WIDTH))
+            .column("Type", Align::Left, Some(Color::Cyan))
+            .column("Addr", Align::Left, Some(Color::Yellow))
+            .column("Off", Align::Left, None)
+            .column("Size", Align::Left, None)
+            .column("ES", Align::Left, None)
+            .column("Flg", Align::Right, None)
+            .column("Lk", Align::Right, None)
+            .column("Inf", Align::Right, None)
+            .column("Al", Align::Right, None);
+        for (index, shdr) in shdrs.iter().enumerate() {
+            table.row([
+                format!("[{:>2}]", index),
+                shdr.name(&strtab).unwrap_or(CORRUPT).to_string(),
+                registry::section_type_name(&scope, shdr.sh_type).into_owned(),
+                format!("{:08x}", shdr.sh_addr),
+                format!("{:06x}", shdr.sh_offset),
+                format!("{:06x}", shdr.sh_size),
+                format!("{:02x}", shdr.sh_entsize),
+                shdr.flags().to_string(),
+                shdr.sh_link.to_string(),
+                shdr.sh_info.to_string(),
+                shdr.sh_addralign.to_string(),
+            ]);
+        }
+        write!(f, "{}", table)?;
+        writeln!(f, "Key to Flags:")?;
+        writeln!(
+            f,
+            "  W (write), A (alloc), X (execute), M (merge), S (strings), I (info),"
+        )?;
+        writeln!(
+            f,
+            "  L (link order), O (extra OS processing required), G (group), T (TLS),"
+        )?;
+        writeln!(
+            f,
+            "  C (compressed), x (unknown), o (OS specific), E (exclude),"
+        )?;
+        writeln!(f, "  p (processor specific)")
+    }
+}
+
+impl ToJson for SectionHeaderTable<'_> {
+    fn to_json(&self) -> Json {
+        let shdrs = match sections(self.data, self.header) {
+            Ok(shdrs) => shdrs,
+            Err(err) => return Json::error(err),
+        };
+        let strtab = section_names(self.data, self.header).unwrap_or_default();
+        let scope = scope(self.header);
+        Json::array(shdrs.iter().map(|shdr| {
+            let mut members = vec![("name".to_string(), Json::from(shdr.name(&strtab).ok()))];
+            if let Json::Object(fields) = shdr.to_json() {
+                members.extend(fields);
+            }
+            if let Some((_, kind)) = members.iter_mut().find(|(key, _)| key == "type") {
+                *kind = Json::from(registry::section_type_name(&scope, shdr.sh_type));
+            }
+            Json::Object(members)
+        }))
+    }
+}
+
+/// Symbol tables of an ELF32 file ready to be printed like `readelf -s`
+///
+/// The symbol versions aren't read, only the names of the symbols.
+pub struct SymbolTable<'a> {
+    /// the whole file, used to resolve the symbol names
+    pub data: &'a [u8],
+    /// the file header
+    pub header: &'a x86,
+    /// print the demangled C++ and Rust names like `readelf -C`
+    pub demangle: bool,
+    /// write the values, types and names with ANSI colors
+    pub color: bool,
+    /// print the names whole like `readelf -W`, instead of cutting them
+    /// to [`symbols::NAME_WIDTH`] characters
+    pub wide: bool,
+}
+
+impl<'a> SymbolTable<'a> {
+    /// The symbol table sections with their name and string table
+    fn tables(&self, shdrs: &[Elf64_Shdr]) -> Vec<(Elf64_Shdr, ByteStr<'a>, StrTab<'a>)> {
+        let names = section_names(self.data, self.header).unwrap_or_default();
+        shdrs
+            .iter()
+            .filter(|shdr| matches!(shdr.sh_type, SHT::SYMTAB | SHT::DYNSYM))
+            .map(|shdr| {
+                let strtab = shdrs
+                    .get(shdr.sh_link as usize)
+                    .and_then(|strtab| strtab.data(self.data))
+                    .map(StrTab::new)
+                    .unwrap_or_default();
+                (*shdr, shdr.name(&names).unwrap_or(CORRUPT), strtab)
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for SymbolTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shdrs = match sections(self.data, self.header) {
+            Ok(shdrs) => shdrs,
+            Err(err) => return writeln!(f, "Warning: invalid section header table: {}", err),
+        };
+        let tables = self.tables(&shdrs);
+        if tables.is_empty() {
+            return writeln!(
+                f,
+                "Dynamic symbol information is not available for displaying symbols."
+            );
+        }
+        let names = section_names(self.data, self.header).unwrap_or_default();
+        for (shdr, name, strtab) in tables {
+            let symbols = match symbols(self.data, self.header, &shdr) {
+                Ok(symbols) => symbols,
+                Err(err) => {
+                    writeln!(f, "Warning: invalid symbol table '{}': {}", name, err)?;
+                    continue;
+                }
+            };
+            writeln!(f)?;
+            writeln!(
+                f,
+                "Symbol table '{}' contains {} entries:",
+                name,
+                symbols.len()
+            )?;
+            let mut table = TextTable::new(self.color)
+                .indent(1)
+                .column("Num:", Align::Right, None)
+                .column("Value", Align::Left, Some(Color::Yellow))
+                .column("Size", Align::Right, None)
+                .column("Type", Align::Left, Some(Color::Cyan))
+                .column("Bind", Align::Left, None)
+                .column("Vis", Align::Left, None)
+                .column("Ndx", Align::Right, None)
+                .column("Name", Align::Left, Some(Color::Green))
+                .limit((!self.wide).then_some(symbols::NAME_WIDTH));
+            for (index, symbol) in symbols.iter().enumerate() {
+                let mut name = symbol.name(&strtab);
+                // section symbols are usually unnamed, readelf shows the section name
+                if symbol.kind() == STT::SECTION && matches!(name, Ok(name) if name.is_empty()) {
+                    let index = symbol.st_shndx as u32;
+                    name = shdrs
+                        .get(index as usize)
+                        .ok_or(Error::InvalidSectionIndex(index))
+                        .and_then(|shdr| shdr.name(&names));
+                }
+                let name = name.unwrap_or(CORRUPT);
+                let label = match name.to_str() {
+                    Some(name) if self.demangle => demangle(name).into_owned(),
+                    _ => name.to_string(),
+                };
+                table.row([
+                    format!("{}:", index),
+                    format!("{:08x}", symbol.st_value),
+                    symbol.st_size.to_string(),
+                    symbol.type_name().into_owned(),
+                    symbol.bind_name().into_owned(),
+                    symbol.visibility_name().to_string(),
+                    symbol.index_name().into_owned(),
+                    label,
+                ]);
+            }
+            write!(f, "{}", table)?;
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for SymbolTable<'_> {
+    fn to_json(&self) -> Json {
+        let shdrs = match sections(self.data, self.header) {
+            Ok(shdrs) => shdrs,
+            Err(err) => return Json::error(err),
+        };
+        Json::array(self.tables(&shdrs).into_iter().map(|(shdr, name, strtab)| {
+            let symbols = match symbols(self.data, self.header, &shdr) {
+                Ok(symbols) => symbols,
+                Err(err) => return Json::error(err),
+            };
+            Json::object([
+                ("section", Json::from(name)),
+                (
+                    "symbols",
+                    Json::array(symbols.iter().map(|symbol| {
+                        let name = symbol.name(&strtab).ok();
+                        let mut members = vec![("name".to_string(), Json::from(name))];
+                        if self.demangle {
+                            let demangled =
+                                name.and_then(|name| name.to_str()).and_then(try_demangle);
+                            members.push(("demangled".to_string(), Json::from(demangled)));
+                        }
+                        if let Json::Object(fields) = symbol.to_json() {
+                            members.extend(fields);
+                        }
+                        Json::Object(members)
+                    })),
+                ),
+            ])
+        }))
+    }
+}
@@ -0,0 +1,266 @@
+//! The places of a file referencing a symbol
+//!
+//! Every relocation of the SHT_REL and SHT_RELA sections naming the
+//! symbol is a reference: a call or a load to patch in a relocatable
+//! file, a GOT slot or a pointer filled by the dynamic linker in a linked
+//! one. The PLT stubs jumping through the slot of the symbol are listed
+//! as well, they are where the calls of the code land.
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::utils::demangle::try_demangle;
+use crate::utils::json::{Json, ToJson};
+use crate::Result;
+use core::fmt;
+
+use super::plt::{self, Target};
+use super::relocations::{self, Relocation};
+use super::section::{self, Elf64_Shdr, SHF, SHT};
+use super::strtab::CORRUPT;
+use super::symbols;
+use super::types::Type;
+use super::x64::x64;
+
+/// What references the symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// a relocation naming the symbol
+    Relocation(Relocation),
+    /// a PLT stub jumping to the symbol
+    Plt,
+}
+
+/// A place referencing the symbol
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference<'a> {
+    /// the relocation or the stub
+    pub kind: Kind,
+    /// the relocation section, or the section of the stub
+    pub table: ByteStr<'a>,
+    /// name of the section holding the place, None outside of the sections
+    pub section: Option<ByteStr<'a>>,
+    /// offset of the place in its section
+    pub offset: u64,
+    /// virtual address of the place, None in relocatable files
+    pub address: Option<u64>,
+}
+
+/// The references to a symbol in an ELF64 file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct References<'a> {
+    /// the name looked for
+    pub symbol: String,
+    /// machine of the file, gives the meaning of the relocation types
+    pub machine: u16,
+    /// the references, the relocations in section order then the stubs
+    pub references: Vec<Reference<'a>>,
+}
+
+/// Whether a symbol name is the one looked for, or demangles to it
+fn is_named(name: ByteStr<'_>, wanted: &str) -> bool {
+    !name.is_empty()
+        && (name == wanted
+            || name
+                .to_str()
+                .and_then(try_demangle)
+                .is_some_and(|demangled| demangled == wanted))
+}
+
+/// List the relocations and the PLT stubs referencing the symbol `name`
+///
+/// `name` is matched against the symbol names and their demangled form.
+/// The offset of a place is counted from the start of the section the
+/// relocations apply to in relocatable files, from the allocated section
+/// holding its address otherwise. Fails if the section header table
+/// can't be parsed, the relocation sections that can't be are skipped.
+pub fn from_bytes<'a>(data: &'a [u8], header: &x64, name: &str) -> Result<References<'a>> {
+    let names = section::names(data, header).unwrap_or_default();
+    let shdrs: Vec<Elf64_Shdr> = section::from_bytes(data, header)?
+        .map(|shdr| *shdr)
+        .collect();
+    let relocatable = Type::from(header.e_type) == Type::Rel;
+    let name_of = |shdr: &Elf64_Shdr| shdr.name(&names).ok();
+    // the allocated section holding an address
+    let place = |address: u64| {
+        let shdr = shdrs.iter().find(|shdr| {
+            shdr.sh_flags & SHF::ALLOC != 0
+                && address >= shdr.sh_addr
+                && address - shdr.sh_addr < shdr.sh_size
+        });
+        match shdr {
+            Some(shdr) => (name_of(shdr), address - shdr.sh_addr),
+            None => (None, address),
+        }
+    };
+
+    let mut references = Vec::new();
+    for shdr in shdrs
+        .iter()
+        .filter(|shdr| shdr.sh_type == SHT::REL || shdr.sh_type == SHT::RELA)
+    {
+        let relocations = match relocations::from_bytes(data, header, shdr) {
+            Ok(relocations) => relocations,
+            Err(_) => continue,
+        };
+        let symtab = match shdrs.get(shdr.sh_link as usize) {
+            Some(symtab) => symtab,
+            None => continue,
+        };
+        let symbols = match symbols::from_bytes(data, header, symtab) {
+            Ok(symbols) => symbols,
+            Err(_) => continue,
+        };
+        let strtab = symbols::strtab(data, header, symtab).unwrap_or_default();
+        // sh_info is the section patched by the relocations of an object
+        let target = shdrs
+            .get(shdr.sh_info as usize)
+            .filter(|_| relocatable && shdr.sh_info != 0);
+        for relocation in relocations {
+            let named = symbols
+                .get(relocation.r_sym as usize)
+                .filter(|_| relocation.r_sym != 0)
+                .and_then(|symbol| symbol.name(&strtab).ok())
+                .is_some_and(|symbol| is_named(symbol, name));
+            if !named {
+                continue;
+            }
+            let (section, offset, address) = match target {
+                Some(target) => (name_of(target), relocation.r_offset, None),
+                None if relocatable => (None, relocation.r_offset, None),
+                None => {
+                    let (section, offset) = place(relocation.r_offset);
+                    (section, offset, Some(relocation.r_offset))
+                }
+            };
+            references.push(Reference {
+                kind: Kind::Relocation(relocation),
+                table: name_of(shdr).unwrap_or(CORRUPT),
+                section,
+                offset,
+                address,
+            });
+        }
+    }
+
+    // the stubs of an object aren't built yet
+    let stubs = match relocatable {
+        true => Vec::new(),
+        false => plt::from_bytes(data, header)
+            .map(|plt| plt.entries)
+            .unwrap_or_default(),
+    };
+    for entry in stubs {
+        if !matches!(entry.target, Target::Symbol(symbol) if is_named(symbol, name)) {
+            continue;
+        }
+        let (section, offset) = place(entry.address);
+        references.push(Reference {
+            kind: Kind::Plt,
+            table: ByteStr::from(entry.section),
+            section,
+            offset,
+            address: Some(entry.address),
+        });
+    }
+    Ok(References {
+        symbol: name.to_string(),
+        machine: header.e_machine,
+        references,
+    })
+}
+
+impl References<'_> {
+    /// Name of the relocation type of a reference, `PLT` for the stubs
+    fn type_name(&self, reference: &Reference) -> String {
+        match reference.kind {
+            Kind::Relocation(relocation) => {
+                relocations::type_name(self.machine, relocation.r_type).into_owned()
+            }
+            Kind::Plt => "PLT".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for References<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.references.is_empty() {
+            return writeln!(
+                f,
+                "Symbol '{}' is not referenced in this file.",
+                self.symbol
+            );
+        }
+        let times = match self.references.len() {
+            1 => "time",
+            _ => "times",
+        };
+        writeln!(
+            f,
+            "Symbol '{}' is referenced {} {}:",
+            self.symbol,
+            self.references.len(),
+            times
+        )?;
+        writeln!(
+            f,
+            "  {:<22} {:<16} {:<20} {:<10} {:<16} Addend",
+            "Type", "Address", "Section", "Offset", "Table"
+        )?;
+        for reference in &self.references {
+            let address = match reference.address {
+                Some(address) => format!("{:016x}", address),
+                None => String::new(),
+            };
+            let addend = match reference.kind {
+                Kind::Relocation(Relocation {
+                    r_addend: Some(addend),
+                    ..
+                }) => format!(
+                    "{}{:x}",
+                    if addend < 0 { "-" } else { "+" },
+                    addend.unsigned_abs()
+                ),
+                _ => String::new(),
+            };
+            writeln!(
+                f,
+                "  {:<22} {:<16} {:<20} {:<10} {:<16} {}",
+                self.type_name(reference),
+                address,
+                reference.section.unwrap_or_default(),
+                format!("{:#x}", reference.offset),
+                reference.table,
+                addend
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for References<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("symbol", Json::from(self.symbol.as_str())),
+            (
+                "references",
+                Json::array(self.references.iter().map(|reference| {
+                    let (kind, relocation) = match reference.kind {
+                        Kind::Relocation(relocation) => ("relocation", Some(relocation)),
+                        Kind::Plt => ("plt", None),
+                    };
+                    Json::object([
+                        ("kind", Json::from(kind)),
+                        ("type", Json::from(self.type_name(reference))),
+                        ("table", Json::from(reference.table)),
+                        ("section", Json::from(reference.section)),
+                        ("offset", Json::from(reference.offset)),
+                        ("address", Json::from(reference.address)),
+                        (
+                            "relocation",
+                            relocation.map_or(Json::Null, |relocation| relocation.to_json()),
+                        ),
+                    ])
+                })),
+            ),
+        ])
+    }
+}
@@ -0,0 +1,106 @@
+//! Intel HEX files, the `.hex` flash images of microcontrollers
+//!
+//! Each line is a record `:LLAAAATT<data>CC` written in hexadecimal: the
+//! number of data bytes, a 16 bits address, the record type, the data and
+//! a checksum making the sum of the bytes of the record zero. The
+//! extended segment (02) and linear (04) address records set the upper
+//! bits of the following addresses, the end of file record (01) ends the
+//! file.
+//! Sources:
+//! * <https://en.wikipedia.org/wiki/Intel_HEX>
+use crate::alloc_prelude::*;
+use crate::headers::elf::image::{self, LoadImage};
+use crate::headers::elf::program::PF;
+use crate::{Error, Result};
+
+/// Record types
+pub struct RECORD {}
+
+impl RECORD {
+    pub const DATA: u8 = 0;
+    pub const END_OF_FILE: u8 = 1;
+    pub const EXTENDED_SEGMENT_ADDRESS: u8 = 2;
+    pub const START_SEGMENT_ADDRESS: u8 = 3;
+    pub const EXTENDED_LINEAR_ADDRESS: u8 = 4;
+    pub const START_LINEAR_ADDRESS: u8 = 5;
+}
+
+/// Returns true if the data looks like an Intel HEX file, a first record
+/// starting with `:` and hexadecimal digits
+pub fn is_ihex(data: &[u8]) -> bool {
+    data.starts_with(b":")
+        && data.len() > 11
+        && data[1..11].iter().all(|byte| byte.is_ascii_hexdigit())
+}
+
+/// Decode the hexadecimal bytes of a record and check its checksum
+fn record(text: &str, number: usize) -> Result<Vec<u8>> {
+    let digits = text
+        .strip_prefix(':')
+        .filter(|digits| digits.len() >= 10 && digits.len().is_multiple_of(2))
+        .ok_or(Error::InvalidIntelHex(number))?;
+    let bytes = (0..digits.len())
+        .step_by(2)
+        .map(|at| u8::from_str_radix(digits.get(at..at + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()
+        .ok_or(Error::InvalidIntelHex(number))?;
+    let valid = bytes.len() == bytes[0] as usize + 5
+        && bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)) == 0;
+    match valid {
+        true => Ok(bytes),
+        false => Err(Error::InvalidIntelHex(number)),
+    }
+}
+
+/// Load the data records of an Intel HEX file in a memory image
+///
+/// The consecutive records are merged in a single mapping, readable,
+/// writable and executable as the file doesn't tell. The start address
+/// records are skipped. Fails on a record that isn't valid hexadecimal,
+/// has a wrong length or checksum or an unknown type, or if the image
+/// can't be allocated.
+pub fn from_bytes(data: &[u8]) -> Result<LoadImage> {
+    let text = core::str::from_utf8(data).map_err(|err| {
+        let line = data[..err.valid_up_to()]
+            .iter()
+            .filter(|byte| **byte == b'\n')
+            .count();
+        Error::InvalidIntelHex(line + 1)
+    })?;
+    let mut upper = 0u64;
+    let mut blocks: Vec<(u64, Vec<u8>)> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let (line, number) = (line.trim(), index + 1);
+        if line.is_empty() {
+            continue;
+        }
+        let bytes = record(line, number)?;
+        let payload = &bytes[4..bytes.len() - 1];
+        let offset = u16::from_be_bytes([bytes[1], bytes[2]]) as u64;
+        match (bytes[3], payload.len()) {
+            (RECORD::DATA, _) => {
+                let address = upper + offset;
+                match blocks.last_mut() {
+                    Some((start, block)) if *start + block.len() as u64 == address => {
+                        block.extend_from_slice(payload)
+                    }
+                    _ => blocks.push((address, payload.to_vec())),
+                }
+            }
+            (RECORD::END_OF_FILE, 0) => break,
+            (RECORD::EXTENDED_SEGMENT_ADDRESS, 2) => {
+                upper = (u16::from_be_bytes([payload[0], payload[1]]) as u64) << 4
+            }
+            (RECORD::EXTENDED_LINEAR_ADDRESS, 2) => {
+                upper = (u16::from_be_bytes([payload[0], payload[1]]) as u64) << 16
+            }
+            (RECORD::START_SEGMENT_ADDRESS | RECORD::START_LINEAR_ADDRESS, 4) => {}
+            _ => return Err(Error::InvalidIntelHex(number)),
+        }
+    }
+    let blocks: Vec<(u64, &[u8])> = blocks
+        .iter()
+        .map(|(address, block)| (*address, &block[..]))
+        .collect();
+    image::from_blocks(&blocks, PF::R | PF::W | PF::X)
+}
@@ -0,0 +1,662 @@
+use crate::alloc_prelude::*;
+use crate::utils::{cow_struct_endian, impl_swap_bytes, read_u32, Pod, SwapBytes};
+use core::fmt;
+use core::mem::size_of;
+
+/// Define the load command types
+///
+/// LC define possible values for the `cmd` field of a load command
+pub struct LC {}
+impl LC {
+    /// Flag of the commands the dynamic linker must understand
+    pub const REQ_DYLD: u32 = 0x8000_0000;
+
+    /// 32 bits segment of the file to be mapped
+    pub const SEGMENT: u32 = 0x1;
+    /// Link-edit stab symbol table info
+    pub const SYMTAB: u32 = 0x2;
+    /// Thread state
+    pub const THREAD: u32 = 0x4;
+    /// Unix thread, includes a stack
+    pub const UNIXTHREAD: u32 = 0x5;
+    /// Dynamic link-edit symbol table info
+    pub const DYSYMTAB: u32 = 0xb;
+    /// Load a dynamically linked shared library
+    pub const LOAD_DYLIB: u32 = 0xc;
+    /// Dynamically linked shared library identification
+    pub const ID_DYLIB: u32 = 0xd;
+    /// Load a dynamic linker
+    pub const LOAD_DYLINKER: u32 = 0xe;
+    /// Dynamic linker identification
+    pub const ID_DYLINKER: u32 = 0xf;
+    /// Load a dynamically linked shared library that is allowed to be missing
+    pub const LOAD_WEAK_DYLIB: u32 = 0x18 | Self::REQ_DYLD;
+    /// 64 bits segment of the file to be mapped
+    pub const SEGMENT_64: u32 = 0x19;
+    /// The uuid of the image
+    pub const UUID: u32 = 0x1b;
+    /// Runpath additions
+    pub const RPATH: u32 = 0x1c | Self::REQ_DYLD;
+    /// Local of the code signature
+    pub const CODE_SIGNATURE: u32 = 0x1d;
+    /// Local of the info to split segments
+    pub const SEGMENT_SPLIT_INFO: u32 = 0x1e;
+    /// Load and re-export a dylib
+    pub const REEXPORT_DYLIB: u32 = 0x1f | Self::REQ_DYLD;
+    /// Encrypted segment information
+    pub const ENCRYPTION_INFO: u32 = 0x21;
+    /// Compressed dyld information
+    pub const DYLD_INFO: u32 = 0x22;
+    /// Compressed dyld information only
+    pub const DYLD_INFO_ONLY: u32 = 0x22 | Self::REQ_DYLD;
+    /// Build for MacOSX min OS version
+    pub const VERSION_MIN_MACOSX: u32 = 0x24;
+    /// Build for iPhoneOS min OS version
+    pub const VERSION_MIN_IPHONEOS: u32 = 0x25;
+    /// Compressed table of function start addresses
+    pub const FUNCTION_STARTS: u32 = 0x26;
+    /// String for dyld to treat like an environment variable
+    pub const DYLD_ENVIRONMENT: u32 = 0x27;
+    /// Replacement for LC_UNIXTHREAD
+    pub const MAIN: u32 = 0x28 | Self::REQ_DYLD;
+    /// Table of non-instructions in `__text`
+    pub const DATA_IN_CODE: u32 = 0x29;
+    /// Source version used to build the binary
+    pub const SOURCE_VERSION: u32 = 0x2a;
+    /// 64 bits encrypted segment information
+    pub const ENCRYPTION_INFO_64: u32 = 0x2c;
+    /// Linker options in MH_OBJECT files
+    pub const LINKER_OPTION: u32 = 0x2d;
+    /// Build for platform min OS version
+    pub const BUILD_VERSION: u32 = 0x32;
+    /// Used with linkedit_data_command, payload is trie
+    pub const DYLD_EXPORTS_TRIE: u32 = 0x33 | Self::REQ_DYLD;
+    /// Used with linkedit_data_command
+    pub const DYLD_CHAINED_FIXUPS: u32 = 0x34 | Self::REQ_DYLD;
+
+    /// Name of a load command as printed by otool
+    pub fn name(cmd: u32) -> Option<&'static str> {
+        let name = match cmd {
+            LC::SEGMENT => "LC_SEGMENT",
+            LC::SYMTAB => "LC_SYMTAB",
+            LC::THREAD => "LC_THREAD",
+            LC::UNIXTHREAD => "LC_UNIXTHREAD",
+            LC::DYSYMTAB => "LC_DYSYMTAB",
+            LC::LOAD_DYLIB => "LC_LOAD_DYLIB",
+            LC::ID_DYLIB => "LC_ID_DYLIB",
+            LC::LOAD_DYLINKER => "LC_LOAD_DYLINKER",
+            LC::ID_DYLINKER => "LC_ID_DYLINKER",
+            LC::LOAD_WEAK_DYLIB => "LC_LOAD_WEAK_DYLIB",
+            LC::SEGMENT_64 => "LC_SEGMENT_64",
+            LC::UUID => "LC_UUID",
+            LC::RPATH => "LC_RPATH",
+            LC::CODE_SIGNATURE => "LC_CODE_SIGNATURE",
+            LC::SEGMENT_SPLIT_INFO => "LC_SEGMENT_SPLIT_INFO",
+            LC::REEXPORT_DYLIB => "LC_REEXPORT_DYLIB",
+            LC::ENCRYPTION_INFO => "LC_ENCRYPTION_INFO",
+            LC::DYLD_INFO => "LC_DYLD_INFO",
+            LC::DYLD_INFO_ONLY => "LC_DYLD_INFO_ONLY",
+            LC::VERSION_MIN_MACOSX => "LC_VERSION_MIN_MACOSX",
+            LC::VERSION_MIN_IPHONEOS => "LC_VERSION_MIN_IPHONEOS",
+            LC::FUNCTION_STARTS => "LC_FUNCTION_STARTS",
+            LC::DYLD_ENVIRONMENT => "LC_DYLD_ENVIRONMENT",
+            LC::MAIN => "LC_MAIN",
+            LC::DATA_IN_CODE => "LC_DATA_IN_CODE",
+            LC::SOURCE_VERSION => "LC_SOURCE_VERSION",
+            LC::ENCRYPTION_INFO_64 => "LC_ENCRYPTION_INFO_64",
+            LC::LINKER_OPTION => "LC_LINKER_OPTION",
+            LC::BUILD_VERSION => "LC_BUILD_VERSION",
+            LC::DYLD_EXPORTS_TRIE => "LC_DYLD_EXPORTS_TRIE",
+            LC::DYLD_CHAINED_FIXUPS => "LC_DYLD_CHAINED_FIXUPS",
+            _ => return None,
+        };
+        Some(name)
+    }
+}
+
+/// Fixed part of a 32 bits segment load command
+///
+/// Sources:
+/// * <https://github.com/apple-oss-distributions/xnu/blob/main/EXTERNAL_HEADERS/mach-o/loader.h>
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct segment_command {
+    /// LC_SEGMENT
+    pub cmd: u32,
+    /// includes the size of the section structures
+    pub cmdsize: u32,
+    /// segment name
+    pub segname: [u8; 16],
+    /// memory address of this segment
+    pub vmaddr: u32,
+    /// memory size of this segment
+    pub vmsize: u32,
+    /// file offset of this segment
+    pub fileoff: u32,
+    /// amount to map from the file
+    pub filesize: u32,
+    /// maximum VM protection
+    pub maxprot: i32,
+    /// initial VM protection
+    pub initprot: i32,
+    /// number of sections in segment
+    pub nsects: u32,
+    /// flags
+    pub flags: u32,
+}
+
+impl_swap_bytes!(
+    segment_command,
+    cmd,
+    cmdsize,
+    vmaddr,
+    vmsize,
+    fileoff,
+    filesize,
+    maxprot,
+    initprot,
+    nsects,
+    flags
+);
+
+/// Fixed part of a 64 bits segment load command
+///
+/// Sources:
+/// * <https://github.com/apple-oss-distributions/xnu/blob/main/EXTERNAL_HEADERS/mach-o/loader.h>
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct segment_command_64 {
+    /// LC_SEGMENT_64
+    pub cmd: u32,
+    /// includes the size of the section_64 structures
+    pub cmdsize: u32,
+    /// segment name
+    pub segname: [u8; 16],
+    /// memory address of this segment
+    pub vmaddr: u64,
+    /// memory size of this segment
+    pub vmsize: u64,
+    /// file offset of this segment
+    pub fileoff: u64,
+    /// amount to map from the file
+    pub filesize: u64,
+    /// maximum VM protection
+    pub maxprot: i32,
+    /// initial VM protection
+    pub initprot: i32,
+    /// number of sections in segment
+    pub nsects: u32,
+    /// flags
+    pub flags: u32,
+}
+
+impl_swap_bytes!(
+    segment_command_64,
+    cmd,
+    cmdsize,
+    vmaddr,
+    vmsize,
+    fileoff,
+    filesize,
+    maxprot,
+    initprot,
+    nsects,
+    flags
+);
+
+impl From<segment_command> for segment_command_64 {
+    fn from(segment: segment_command) -> Self {
+        segment_command_64 {
+            cmd: segment.cmd,
+            cmdsize: segment.cmdsize,
+            segname: segment.segname,
+            vmaddr: segment.vmaddr as u64,
+            vmsize: segment.vmsize as u64,
+            fileoff: segment.fileoff as u64,
+            filesize: segment.filesize as u64,
+            maxprot: segment.maxprot,
+            initprot: segment.initprot,
+            nsects: segment.nsects,
+            flags: segment.flags,
+        }
+    }
+}
+
+/// Section of a 32 bits segment
+///
+/// Sources:
+/// * <https://github.com/apple-oss-distributions/xnu/blob/main/EXTERNAL_HEADERS/mach-o/loader.h>
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct section {
+    /// name of this section
+    pub sectname: [u8; 16],
+    /// segment this section goes in
+    pub segname: [u8; 16],
+    /// memory address of this section
+    pub addr: u32,
+    /// size in bytes of this section
+    pub size: u32,
+    /// file offset of this section
+    pub offset: u32,
+    /// section alignment, power of 2
+    pub align: u32,
+    /// file offset of relocation entries
+    pub reloff: u32,
+    /// number of relocation entries
+    pub nreloc: u32,
+    /// flags, section type and attributes
+    pub flags: u32,
+    /// reserved, for offset or index
+    pub reserved1: u32,
+    /// reserved, for count or sizeof
+    pub reserved2: u32,
+}
+
+impl_swap_bytes!(section, addr, size, offset, align, reloff, nreloc, flags, reserved1, reserved2);
+
+/// Section of a 64 bits segment
+///
+/// Sources:
+/// * <https://github.com/apple-oss-distributions/xnu/blob/main/EXTERNAL_HEADERS/mach-o/loader.h>
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct section_64 {
+    /// name of this section
+    pub sectname: [u8; 16],
+    /// segment this section goes in
+    pub segname: [u8; 16],
+    /// memory address of this section
+    pub addr: u64,
+    /// size in bytes of this section
+    pub size: u64,
+    /// file offset of this section
+    pub offset: u32,
+    /// section alignment, power of 2
+    pub align: u32,
+    /// file offset of relocation entries
+    pub reloff: u32,
+    /// number of relocation entries
+    pub nreloc: u32,
+    /// flags, section type and attributes
+    pub flags: u32,
+    /// reserved, for offset or index
+    pub reserved1: u32,
+    /// reserved, for count or sizeof
+    pub reserved2: u32,
+    /// reserved
+    pub reserved3: u32,
+}
+
+impl_swap_bytes!(
+    section_64, addr, size, offset, align, reloff, nreloc, flags, reserved1, reserved2, reserved3
+);
+
+impl From<section> for section_64 {
+    fn from(section: section) -> Self {
+        section_64 {
+            sectname: section.sectname,
+            segname: section.segname,
+            addr: section.addr as u64,
+            size: section.size as u64,
+            offset: section.offset,
+            align: section.align,
+            reloff: section.reloff,
+            nreloc: section.nreloc,
+            flags: section.flags,
+            reserved1: section.reserved1,
+            reserved2: section.reserved2,
+            reserved3: 0,
+        }
+    }
+}
+
+/// Name stored in a fixed size, nul padded field
+pub fn fixed_name(name: &[u8; 16]) -> String {
+    let end = name.iter().position(|b| *b == 0).unwrap_or(name.len());
+    String::from_utf8_lossy(&name[..end]).into_owned()
+}
+
+impl section_64 {
+    /// Name of the section without its nul padding
+    pub fn name(&self) -> String {
+        fixed_name(&self.sectname)
+    }
+
+    /// Name of the segment of the section without its nul padding
+    pub fn segment_name(&self) -> String {
+        fixed_name(&self.segname)
+    }
+}
+
+/// Segment load command with its sections, widened to the 64 bits layout
+#[derive(Debug, Clone)]
+pub struct Segment {
+    /// fixed part of the command
+    pub command: segment_command_64,
+    /// sections following the fixed part
+    pub sections: Vec<section_64>,
+}
+
+impl Segment {
+    /// Name of the segment without its nul padding
+    pub fn name(&self) -> String {
+        fixed_name(&self.command.segname)
+    }
+}
+
+/// Typed content of a load command
+#[derive(Debug, Clone)]
+pub enum Command<'a> {
+    /// LC_SEGMENT and LC_SEGMENT_64
+    Segment(Segment),
+    /// LC_LOAD_DYLIB, LC_ID_DYLIB, LC_LOAD_WEAK_DYLIB and LC_REEXPORT_DYLIB
+    Dylib {
+        name: &'a str,
+        timestamp: u32,
+        current_version: u32,
+        compatibility_version: u32,
+    },
+    /// LC_LOAD_DYLINKER, LC_ID_DYLINKER and LC_DYLD_ENVIRONMENT
+    Dylinker(&'a str),
+    /// LC_RPATH
+    Rpath(&'a str),
+    /// LC_MAIN
+    Main { entryoff: u64, stacksize: u64 },
+    /// LC_UUID
+    Uuid([u8; 16]),
+    /// LC_SYMTAB
+    Symtab {
+        symoff: u32,
+        nsyms: u32,
+        stroff: u32,
+        strsize: u32,
+    },
+    /// LC_BUILD_VERSION
+    BuildVersion {
+        platform: u32,
+        minos: u32,
+        sdk: u32,
+        ntools: u32,
+    },
+    /// Any other command, or a command too small for its type
+    Unknown,
+}
+
+/// Load command following the Mach-O header
+///
+/// Every load command starts with its type and its size, the size
+/// includes those two fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadCommand<'a> {
+    /// command type, one of the `LC` values
+    pub cmd: u32,
+    /// size of the command in bytes
+    pub cmdsize: u32,
+    /// bytes of the whole command
+    pub data: &'a [u8],
+    big_endian: bool,
+}
+
+/// Format a packed `xxxx.yy.zz` version
+fn version_string(version: u32) -> String {
+    format!(
+        "{}.{}.{}",
+        version >> 16,
+        (version >> 8) & 0xff,
+        version & 0xff
+    )
+}
+
+impl<'a> LoadCommand<'a> {
+    /// Name of the command type as printed by otool
+    pub fn name(&self) -> String {
+        match LC::name(self.cmd) {
+            Some(name) => name.to_string(),
+            None => format!("{:#x}", self.cmd),
+        }
+    }
+
+    fn u32_at(&self, offset: usize) -> Option<u32> {
+        read_u32(self.data, offset, self.big_endian)
+    }
+
+    fn get<T: Pod + SwapBytes>(&self, offset: usize) -> Option<T> {
+        let bytes = self.data.get(offset..offset.checked_add(size_of::<T>())?)?;
+        cow_struct_endian::<T>(bytes, self.big_endian).map(|value| *value)
+    }
+
+    /// nul terminated string stored at the offset found at `field`
+    fn string_at(&self, field: usize) -> Option<&'a str> {
+        let offset = self.u32_at(field)? as usize;
+        let bytes = self.data.get(offset..)?;
+        let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        core::str::from_utf8(&bytes[..end]).ok()
+    }
+
+    fn segment<S, T>(&self) -> Option<Segment>
+    where
+        S: Pod + SwapBytes + Into<segment_command_64>,
+        T: Pod + SwapBytes + Into<section_64>,
+    {
+        let command: segment_command_64 = self.get::<S>(0)?.into();
+        let sections = (0..command.nsects as usize)
+            .map(|index| {
+                self.get::<T>(size_of::<S>() + index * size_of::<T>())
+                    .map(Into::into)
+            })
+            .collect::<Option<Vec<section_64>>>()?;
+        Some(Segment { command, sections })
+    }
+
+    fn decode_checked(&self) -> Option<Command<'a>> {
+        let command = match self.cmd {
+            LC::SEGMENT => Command::Segment(self.segment::<segment_command, section>()?),
+            LC::SEGMENT_64 => Command::Segment(self.segment::<segment_command_64, section_64>()?),
+            LC::LOAD_DYLIB | LC::ID_DYLIB | LC::LOAD_WEAK_DYLIB | LC::REEXPORT_DYLIB => {
+                Command::Dylib {
+                    name: self.string_at(8)?,
+                    timestamp: self.u32_at(12)?,
+                    current_version: self.u32_at(16)?,
+                    compatibility_version: self.u32_at(20)?,
+                }
+            }
+            LC::LOAD_DYLINKER | LC::ID_DYLINKER | LC::DYLD_ENVIRONMENT => {
+                Command::Dylinker(self.string_at(8)?)
+            }
+            LC::RPATH => Command::Rpath(self.string_at(8)?),
+            LC::MAIN => {
+                let read_u64 = |offset| crate::utils::read_u64(self.data, offset, self.big_endian);
+                Command::Main {
+                    entryoff: read_u64(8)?,
+                    stacksize: read_u64(16)?,
+                }
+            }
+            LC::UUID => Command::Uuid(self.data.get(8..24)?.try_into().ok()?),
+            LC::SYMTAB => Command::Symtab {
+                symoff: self.u32_at(8)?,
+                nsyms: self.u32_at(12)?,
+                stroff: self.u32_at(16)?,
+                strsize: self.u32_at(20)?,
+            },
+            LC::BUILD_VERSION => Command::BuildVersion {
+                platform: self.u32_at(8)?,
+                minos: self.u32_at(12)?,
+                sdk: self.u32_at(16)?,
+                ntools: self.u32_at(20)?,
+            },
+            _ => Command::Unknown,
+        };
+        Some(command)
+    }
+
+    /// Decode the content of the command according to its type
+    pub fn decode(&self) -> Command<'a> {
+        self.decode_checked().unwrap_or(Command::Unknown)
+    }
+}
+
+/// Iterator over the load commands of a Mach-O file
+///
+/// Stops at the first command whose size is too small or that doesn't
+/// fit in the `sizeofcmds` bytes following the header.
+#[derive(Debug, Clone)]
+pub struct LoadCommands<'a> {
+    data: &'a [u8],
+    offset: usize,
+    remaining: u32,
+    big_endian: bool,
+}
+
+impl<'a> LoadCommands<'a> {
+    /// Iterate over `ncmds` commands stored in `data`
+    pub fn new(data: &'a [u8], ncmds: u32, big_endian: bool) -> Self {
+        LoadCommands {
+            data,
+            offset: 0,
+            remaining: ncmds,
+            big_endian,
+        }
+    }
+}
+
+impl<'a> Iterator for LoadCommands<'a> {
+    type Item = LoadCommand<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let cmd = read_u32(self.data, self.offset, self.big_endian)?;
+        let cmdsize = read_u32(self.data, self.offset + 4, self.big_endian)?;
+        if cmdsize < 8 {
+            self.remaining = 0;
+            return None;
+        }
+        let data = self
+            .data
+            .get(self.offset..self.offset.checked_add(cmdsize as usize)?)?;
+        self.offset += cmdsize as usize;
+        self.remaining -= 1;
+        Some(LoadCommand {
+            cmd,
+            cmdsize,
+            data,
+            big_endian: self.big_endian,
+        })
+    }
+}
+
+impl fmt::Display for Command<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Segment(segment) => {
+                let command = &segment.command;
+                writeln!(f, "  segname {}", segment.name())?;
+                writeln!(f, "   vmaddr {:#018x}", command.vmaddr)?;
+                writeln!(f, "   vmsize {:#018x}", command.vmsize)?;
+                writeln!(f, "  fileoff {}", command.fileoff)?;
+                writeln!(f, " filesize {}", command.filesize)?;
+                writeln!(f, "  maxprot {:#010x}", command.maxprot)?;
+                writeln!(f, " initprot {:#010x}", command.initprot)?;
+                writeln!(f, "   nsects {}", command.nsects)?;
+                writeln!(f, "    flags {:#x}", command.flags)?;
+                for section in &segment.sections {
+                    writeln!(f, "Section")?;
+                    writeln!(f, "  sectname {}", section.name())?;
+                    writeln!(f, "   segname {}", section.segment_name())?;
+                    writeln!(f, "      addr {:#018x}", section.addr)?;
+                    writeln!(f, "      size {:#018x}", section.size)?;
+                    writeln!(f, "    offset {}", section.offset)?;
+                    writeln!(
+                        f,
+                        "     align 2^{} ({})",
+                        section.align,
+                        1u64.checked_shl(section.align).unwrap_or(0)
+                    )?;
+                    writeln!(f, "    reloff {}", section.reloff)?;
+                    writeln!(f, "    nreloc {}", section.nreloc)?;
+                    writeln!(f, "     flags {:#010x}", section.flags)?;
+                    writeln!(f, " reserved1 {}", section.reserved1)?;
+                    writeln!(f, " reserved2 {}", section.reserved2)?;
+                }
+                Ok(())
+            }
+            Command::Dylib {
+                name,
+                timestamp,
+                current_version,
+                compatibility_version,
+            } => {
+                writeln!(f, "         name {}", name)?;
+                writeln!(f, "   time stamp {}", timestamp)?;
+                writeln!(
+                    f,
+                    "      current version {}",
+                    version_string(*current_version)
+                )?;
+                writeln!(
+                    f,
+                    "compatibility version {}",
+                    version_string(*compatibility_version)
+                )
+            }
+            Command::Dylinker(name) => writeln!(f, "         name {}", name),
+            Command::Rpath(path) => writeln!(f, "         path {}", path),
+            Command::Main {
+                entryoff,
+                stacksize,
+            } => {
+                writeln!(f, "  entryoff {}", entryoff)?;
+                writeln!(f, " stacksize {}", stacksize)
+            }
+            Command::Uuid(uuid) => {
+                let hex: Vec<String> = uuid.iter().map(|b| format!("{:02X}", b)).collect();
+                writeln!(
+                    f,
+                    "    uuid {}-{}-{}-{}-{}",
+                    hex[..4].concat(),
+                    hex[4..6].concat(),
+                    hex[6..8].concat(),
+                    hex[8..10].concat(),
+                    hex[10..].concat()
+                )
+            }
+            Command::Symtab {
+                symoff,
+                nsyms,
+                stroff,
+                strsize,
+            } => {
+                writeln!(f, "  symoff {}", symoff)?;
+                writeln!(f, "   nsyms {}", nsyms)?;
+                writeln!(f, "  stroff {}", stroff)?;
+                writeln!(f, " strsize {}", strsize)
+            }
+            Command::BuildVersion {
+                platform,
+                minos,
+                sdk,
+                ntools,
+            } => {
+                writeln!(f, " platform {}", platform)?;
+                writeln!(f, "      sdk {}", version_string(*sdk))?;
+                writeln!(f, "    minos {}", version_string(*minos))?;
+                writeln!(f, "   ntools {}", ntools)
+            }
+            Command::Unknown => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for LoadCommand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "      cmd {}", self.name())?;
+        writeln!(f, "  cmdsize {}", self.cmdsize)?;
+        write!(f, "{}", self.decode())
+    }
+}
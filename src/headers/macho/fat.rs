@@ -0,0 +1,152 @@
+use crate::alloc_prelude::*;
+use crate::headers::elf::table::Table;
+use crate::utils::{impl_swap_bytes, read_u32};
+use crate::{Error, Result};
+use core::fmt;
+use core::mem::size_of;
+
+use super::header::CPU_TYPE;
+
+/// Define the magic numbers of universal binaries
+///
+/// FAT define possible values for the `magic` field, the fat header and
+/// its entries are always stored in big endian.
+pub struct FAT {}
+impl FAT {
+    /// Universal binary with 32 bits offsets
+    pub const MAGIC: u32 = 0xcafe_babe;
+    /// Universal binary with 64 bits offsets
+    pub const MAGIC_64: u32 = 0xcafe_babf;
+
+    /// Upper bound on the number of architectures
+    ///
+    /// Java class files share FAT::MAGIC, their version number would be
+    /// read as a huge architecture count.
+    pub const MAX_ARCHS: u32 = 64;
+}
+
+/// Entry of a universal binary with 32 bits offsets
+///
+/// Sources:
+/// * <https://github.com/apple-oss-distributions/xnu/blob/main/EXTERNAL_HEADERS/mach-o/fat.h>
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct fat_arch {
+    /// cpu specifier
+    pub cputype: i32,
+    /// machine specifier
+    pub cpusubtype: i32,
+    /// file offset of this object file
+    pub offset: u32,
+    /// size of this object file
+    pub size: u32,
+    /// alignment as a power of 2
+    pub align: u32,
+}
+
+impl_swap_bytes!(fat_arch, cputype, cpusubtype, offset, size, align);
+
+/// Entry of a universal binary with 64 bits offsets
+///
+/// Sources:
+/// * <https://github.com/apple-oss-distributions/xnu/blob/main/EXTERNAL_HEADERS/mach-o/fat.h>
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct fat_arch_64 {
+    /// cpu specifier
+    pub cputype: i32,
+    /// machine specifier
+    pub cpusubtype: i32,
+    /// file offset of this object file
+    pub offset: u64,
+    /// size of this object file
+    pub size: u64,
+    /// alignment as a power of 2
+    pub align: u32,
+    /// reserved
+    pub reserved: u32,
+}
+
+impl_swap_bytes!(
+    fat_arch_64,
+    cputype,
+    cpusubtype,
+    offset,
+    size,
+    align,
+    reserved
+);
+
+impl From<fat_arch> for fat_arch_64 {
+    fn from(arch: fat_arch) -> Self {
+        fat_arch_64 {
+            cputype: arch.cputype,
+            cpusubtype: arch.cpusubtype,
+            offset: arch.offset as u64,
+            size: arch.size as u64,
+            align: arch.align,
+            reserved: 0,
+        }
+    }
+}
+
+impl fat_arch_64 {
+    /// Bytes of the object file for this architecture
+    ///
+    /// Returns None if the object lies outside of the given data.
+    pub fn data<'a>(&self, data: &'a [u8]) -> Option<&'a [u8]> {
+        let start = usize::try_from(self.offset).ok()?;
+        let size = usize::try_from(self.size).ok()?;
+        data.get(start..start.checked_add(size)?)
+    }
+}
+
+/// Whether the data starts like a universal binary
+pub fn is_fat(data: &[u8]) -> bool {
+    match read_u32(data, 0, true) {
+        Some(FAT::MAGIC) | Some(FAT::MAGIC_64) => {
+            read_u32(data, 4, true).is_some_and(|nfat_arch| nfat_arch <= FAT::MAX_ARCHS)
+        }
+        _ => false,
+    }
+}
+
+/// Parse the architectures of a universal binary, widened to 64 bits
+///
+/// Fails if the data isn't a universal binary or if the architecture
+/// table doesn't fit in the file.
+pub fn from_bytes(data: &[u8]) -> Result<Vec<fat_arch_64>> {
+    if !is_fat(data) {
+        let mut magic = [0; 4];
+        let len = data.len().min(magic.len());
+        magic[..len].copy_from_slice(&data[..len]);
+        return Err(Error::BadMagic(magic));
+    }
+    let nfat_arch = read_u32(data, 4, true).unwrap_or_default() as u64;
+    if read_u32(data, 0, true) == Some(FAT::MAGIC_64) {
+        let archs =
+            Table::<fat_arch_64>::parse(data, 8, size_of::<fat_arch_64>() as u64, nfat_arch, true)?;
+        Ok(archs.map(|arch| *arch).collect())
+    } else {
+        let archs =
+            Table::<fat_arch>::parse(data, 8, size_of::<fat_arch>() as u64, nfat_arch, true)?;
+        Ok(archs.map(|arch| (*arch).into()).collect())
+    }
+}
+
+impl fmt::Display for fat_arch_64 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "    cputype {}", CPU_TYPE::name(self.cputype))?;
+        writeln!(f, "    cpusubtype {}", self.cpusubtype & 0x00ff_ffff)?;
+        writeln!(f, "    offset {}", self.offset)?;
+        writeln!(f, "    size {}", self.size)?;
+        writeln!(
+            f,
+            "    align 2^{} ({})",
+            self.align,
+            1u64.checked_shl(self.align).unwrap_or(0)
+        )
+    }
+}
@@ -0,0 +1,350 @@
+use crate::alloc_prelude::*;
+use crate::utils::{cow_struct_endian, impl_swap_bytes};
+use crate::{Error, Result};
+use core::fmt;
+use core::mem::size_of;
+
+/// Define the magic numbers, file types and flags of a Mach-O header
+///
+/// MH define possible values for the `magic`, `filetype` and `flags` fields
+pub struct MH {}
+impl MH {
+    /// 32 bits file in the byte order of the host
+    pub const MAGIC: u32 = 0xfeed_face;
+    /// 32 bits file in the opposite byte order
+    pub const CIGAM: u32 = 0xcefa_edfe;
+    /// 64 bits file in the byte order of the host
+    pub const MAGIC_64: u32 = 0xfeed_facf;
+    /// 64 bits file in the opposite byte order
+    pub const CIGAM_64: u32 = 0xcffa_edfe;
+
+    /// Relocatable object file
+    pub const OBJECT: u32 = 0x1;
+    /// Demand paged executable file
+    pub const EXECUTE: u32 = 0x2;
+    /// Fixed VM shared library file
+    pub const FVMLIB: u32 = 0x3;
+    /// Core file
+    pub const CORE: u32 = 0x4;
+    /// Preloaded executable file
+    pub const PRELOAD: u32 = 0x5;
+    /// Dynamically bound shared library
+    pub const DYLIB: u32 = 0x6;
+    /// Dynamic link editor
+    pub const DYLINKER: u32 = 0x7;
+    /// Dynamically bound bundle file
+    pub const BUNDLE: u32 = 0x8;
+    /// Shared library stub for static linking only
+    pub const DYLIB_STUB: u32 = 0x9;
+    /// Companion file with only debug sections
+    pub const DSYM: u32 = 0xa;
+    /// x86_64 kext
+    pub const KEXT_BUNDLE: u32 = 0xb;
+    /// File set of Mach-O files
+    pub const FILESET: u32 = 0xc;
+
+    /// The object file has no undefined references
+    pub const NOUNDEFS: u32 = 0x1;
+    /// The object file is the output of an incremental link
+    pub const INCRLINK: u32 = 0x2;
+    /// The object file is input for the dynamic linker
+    pub const DYLDLINK: u32 = 0x4;
+    /// The undefined references are bound by the dynamic linker when loaded
+    pub const BINDATLOAD: u32 = 0x8;
+    /// The file has its dynamic undefined references prebound
+    pub const PREBOUND: u32 = 0x10;
+    /// The file has its read-only and read-write segments split
+    pub const SPLIT_SEGS: u32 = 0x20;
+    /// The image uses two-level name space bindings
+    pub const TWOLEVEL: u32 = 0x80;
+    /// The executable is forcing all images to use flat name space bindings
+    pub const FORCE_FLAT: u32 = 0x100;
+    /// The code was linked for use in an application extension
+    pub const NO_REEXPORTED_DYLIBS: u32 = 0x10_0000;
+    /// The sections of the object file can be divided into individual blocks
+    pub const SUBSECTIONS_VIA_SYMBOLS: u32 = 0x2000;
+    /// The binary uses weak symbols
+    pub const WEAK_DEFINES: u32 = 0x8000;
+    /// The final linked image uses weak symbols
+    pub const BINDS_TO_WEAK: u32 = 0x1_0000;
+    /// The main executable is loaded at a random address
+    pub const PIE: u32 = 0x20_0000;
+    /// The image has a `__thread_vars` section
+    pub const HAS_TLV_DESCRIPTORS: u32 = 0x80_0000;
+    /// The image doesn't need an executable stack
+    pub const NO_HEAP_EXECUTION: u32 = 0x100_0000;
+
+    /// Name of the flags as printed by otool, by bit
+    pub const FLAG_NAMES: [(u32, &'static str); 15] = [
+        (Self::NOUNDEFS, "NOUNDEFS"),
+        (Self::INCRLINK, "INCRLINK"),
+        (Self::DYLDLINK, "DYLDLINK"),
+        (Self::BINDATLOAD, "BINDATLOAD"),
+        (Self::PREBOUND, "PREBOUND"),
+        (Self::SPLIT_SEGS, "SPLIT_SEGS"),
+        (Self::TWOLEVEL, "TWOLEVEL"),
+        (Self::FORCE_FLAT, "FORCE_FLAT"),
+        (Self::SUBSECTIONS_VIA_SYMBOLS, "SUBSECTIONS_VIA_SYMBOLS"),
+        (Self::WEAK_DEFINES, "WEAK_DEFINES"),
+        (Self::BINDS_TO_WEAK, "BINDS_TO_WEAK"),
+        (Self::NO_REEXPORTED_DYLIBS, "NO_REEXPORTED_DYLIBS"),
+        (Self::PIE, "PIE"),
+        (Self::HAS_TLV_DESCRIPTORS, "HAS_TLV_DESCRIPTORS"),
+        (Self::NO_HEAP_EXECUTION, "NO_HEAP_EXECUTION"),
+    ];
+
+    /// Name of a file type as printed by otool
+    pub fn filetype_name(filetype: u32) -> &'static str {
+        match filetype {
+            MH::OBJECT => "OBJECT",
+            MH::EXECUTE => "EXECUTE",
+            MH::FVMLIB => "FVMLIB",
+            MH::CORE => "CORE",
+            MH::PRELOAD => "PRELOAD",
+            MH::DYLIB => "DYLIB",
+            MH::DYLINKER => "DYLINKER",
+            MH::BUNDLE => "BUNDLE",
+            MH::DYLIB_STUB => "DYLIB_STUB",
+            MH::DSYM => "DSYM",
+            MH::KEXT_BUNDLE => "KEXT_BUNDLE",
+            MH::FILESET => "FILESET",
+            _ => "<unknown>",
+        }
+    }
+}
+
+/// Define the processor types a Mach-O file can target
+///
+/// CPU_TYPE define possible values for the `cputype` field
+#[allow(non_camel_case_types)]
+pub struct CPU_TYPE {}
+impl CPU_TYPE {
+    /// Flag of the 64 bits variants
+    pub const ARCH_ABI64: i32 = 0x0100_0000;
+    /// Flag of the ILP32 variants running on 64 bits processors
+    pub const ARCH_ABI64_32: i32 = 0x0200_0000;
+
+    /// Any processor
+    pub const ANY: i32 = -1;
+    /// Motorola 680x0
+    pub const MC680X0: i32 = 6;
+    /// Intel x86
+    pub const X86: i32 = 7;
+    /// Intel x86_64
+    pub const X86_64: i32 = Self::X86 | Self::ARCH_ABI64;
+    /// ARM
+    pub const ARM: i32 = 12;
+    /// ARM64
+    pub const ARM64: i32 = Self::ARM | Self::ARCH_ABI64;
+    /// ARM64 with 32 bits pointers
+    pub const ARM64_32: i32 = Self::ARM | Self::ARCH_ABI64_32;
+    /// PowerPC
+    pub const POWERPC: i32 = 18;
+    /// PowerPC 64 bits
+    pub const POWERPC64: i32 = Self::POWERPC | Self::ARCH_ABI64;
+
+    /// Name of a processor type as printed by lipo
+    pub fn name(cputype: i32) -> &'static str {
+        match cputype {
+            CPU_TYPE::ANY => "any",
+            CPU_TYPE::MC680X0 => "m68k",
+            CPU_TYPE::X86 => "i386",
+            CPU_TYPE::X86_64 => "x86_64",
+            CPU_TYPE::ARM => "arm",
+            CPU_TYPE::ARM64 => "arm64",
+            CPU_TYPE::ARM64_32 => "arm64_32",
+            CPU_TYPE::POWERPC => "ppc",
+            CPU_TYPE::POWERPC64 => "ppc64",
+            _ => "<unknown>",
+        }
+    }
+}
+
+/// Header of a 32 bits Mach-O file
+///
+/// Sources:
+/// * <https://github.com/apple-oss-distributions/xnu/blob/main/EXTERNAL_HEADERS/mach-o/loader.h>
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct mach_header {
+    /// mach magic number identifier
+    pub magic: u32,
+    /// cpu specifier
+    pub cputype: i32,
+    /// machine specifier
+    pub cpusubtype: i32,
+    /// type of file
+    pub filetype: u32,
+    /// number of load commands
+    pub ncmds: u32,
+    /// size of all the load commands
+    pub sizeofcmds: u32,
+    /// flags
+    pub flags: u32,
+}
+
+impl_swap_bytes!(
+    mach_header,
+    magic,
+    cputype,
+    cpusubtype,
+    filetype,
+    ncmds,
+    sizeofcmds,
+    flags
+);
+
+impl mach_header {
+    pub const SIZE: usize = size_of::<Self>();
+}
+
+/// Header of a 64 bits Mach-O file
+///
+/// Same as [`mach_header`] with an extra reserved field, which keeps the
+/// load commands 8 bytes aligned.
+/// Sources:
+/// * <https://github.com/apple-oss-distributions/xnu/blob/main/EXTERNAL_HEADERS/mach-o/loader.h>
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct mach_header_64 {
+    /// mach magic number identifier
+    pub magic: u32,
+    /// cpu specifier
+    pub cputype: i32,
+    /// machine specifier
+    pub cpusubtype: i32,
+    /// type of file
+    pub filetype: u32,
+    /// number of load commands
+    pub ncmds: u32,
+    /// size of all the load commands
+    pub sizeofcmds: u32,
+    /// flags
+    pub flags: u32,
+    /// reserved
+    pub reserved: u32,
+}
+
+impl_swap_bytes!(
+    mach_header_64,
+    magic,
+    cputype,
+    cpusubtype,
+    filetype,
+    ncmds,
+    sizeofcmds,
+    flags,
+    reserved
+);
+
+impl mach_header_64 {
+    pub const SIZE: usize = size_of::<Self>();
+}
+
+impl From<mach_header> for mach_header_64 {
+    fn from(header: mach_header) -> Self {
+        mach_header_64 {
+            magic: header.magic,
+            cputype: header.cputype,
+            cpusubtype: header.cpusubtype,
+            filetype: header.filetype,
+            ncmds: header.ncmds,
+            sizeofcmds: header.sizeofcmds,
+            flags: header.flags,
+            reserved: 0,
+        }
+    }
+}
+
+/// Mach-O header of either size, in the byte order of the host
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MachHeader {
+    /// fields of the header, widened to the 64 bits layout
+    pub header: mach_header_64,
+    /// whether the file uses the 64 bits layout
+    pub is_64: bool,
+    /// whether the file is stored in big endian
+    pub big_endian: bool,
+}
+
+impl MachHeader {
+    /// Size of the header in the file, the load commands follow it
+    pub fn size(&self) -> usize {
+        if self.is_64 {
+            mach_header_64::SIZE
+        } else {
+            mach_header::SIZE
+        }
+    }
+}
+
+/// Parse the Mach-O header at the start of `data`
+///
+/// Fails if the data is too small or doesn't start with one of the
+/// Mach-O magic numbers, in either byte order.
+pub fn from_bytes(data: &[u8]) -> Result<MachHeader> {
+    let mut magic = [0; 4];
+    let len = data.len().min(magic.len());
+    magic[..len].copy_from_slice(&data[..len]);
+    let (is_64, big_endian) = match u32::from_be_bytes(magic) {
+        MH::MAGIC => (false, true),
+        MH::CIGAM => (false, false),
+        MH::MAGIC_64 => (true, true),
+        MH::CIGAM_64 => (true, false),
+        _ => return Err(Error::BadMagic(magic)),
+    };
+    let size = if is_64 {
+        mach_header_64::SIZE
+    } else {
+        mach_header::SIZE
+    };
+    let truncated = Error::TruncatedHeader {
+        expected: size,
+        found: data.len(),
+    };
+    let bytes = data.get(..size).ok_or(truncated.clone())?;
+    let header = if is_64 {
+        *cow_struct_endian::<mach_header_64>(bytes, big_endian).ok_or(truncated)?
+    } else {
+        (*cow_struct_endian::<mach_header>(bytes, big_endian).ok_or(truncated)?).into()
+    };
+    Ok(MachHeader {
+        header,
+        is_64,
+        big_endian,
+    })
+}
+
+impl fmt::Display for MachHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let header = &self.header;
+        writeln!(f, "Mach header")?;
+        writeln!(
+            f,
+            "      magic  cputype cpusubtype  caps    filetype ncmds sizeofcmds      flags"
+        )?;
+        writeln!(
+            f,
+            " {:#010x} {:>8} {:>10}  {:#04x} {:>11} {:>5} {:>10} {:#010x}",
+            header.magic,
+            CPU_TYPE::name(header.cputype),
+            header.cpusubtype & 0x00ff_ffff,
+            (header.cpusubtype as u32) >> 24,
+            MH::filetype_name(header.filetype),
+            header.ncmds,
+            header.sizeofcmds,
+            header.flags
+        )?;
+        let flags: Vec<&str> = MH::FLAG_NAMES
+            .iter()
+            .filter(|(bit, _)| header.flags & bit != 0)
+            .map(|(_, name)| *name)
+            .collect();
+        if !flags.is_empty() {
+            writeln!(f, "  flags: {}", flags.join(" "))?;
+        }
+        Ok(())
+    }
+}
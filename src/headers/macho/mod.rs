@@ -0,0 +1,242 @@
+use crate::alloc_prelude::*;
+use crate::utils::json::{Json, ToJson};
+use crate::{Error, Result};
+use core::fmt;
+
+pub mod commands;
+pub mod fat;
+pub mod header;
+
+use commands::{Command, LoadCommands, Segment};
+use fat::fat_arch_64;
+use header::{MachHeader, CPU_TYPE, MH};
+
+/// A thin Mach-O file, borrowed from its bytes
+///
+/// The header is followed by `ncmds` load commands describing the
+/// segments, the libraries to load, the entry point...
+/// Sources:
+/// * <https://github.com/apple-oss-distributions/xnu/blob/main/EXTERNAL_HEADERS/mach-o/loader.h>
+#[derive(Debug, Clone)]
+pub struct MachO<'a> {
+    /// bytes of the whole file
+    pub data: &'a [u8],
+    /// Mach-O header
+    pub header: MachHeader,
+}
+
+impl<'a> MachO<'a> {
+    /// Parse the header of a thin Mach-O file
+    ///
+    /// Fails if the data isn't a Mach-O file or if the load commands
+    /// don't fit in it.
+    pub fn parse(data: &'a [u8]) -> Result<Self> {
+        let header = header::from_bytes(data)?;
+        let size = header.header.sizeofcmds as u64;
+        if data.len() as u64 - (header.size() as u64) < size {
+            return Err(Error::OutOfBounds {
+                offset: header.size() as u64,
+                size,
+            });
+        }
+        Ok(MachO { data, header })
+    }
+
+    /// Iterator over the load commands
+    pub fn commands(&self) -> LoadCommands<'a> {
+        let start = self.header.size();
+        let end = start + self.header.header.sizeofcmds as usize;
+        LoadCommands::new(
+            &self.data[start..end],
+            self.header.header.ncmds,
+            self.header.big_endian,
+        )
+    }
+
+    /// The LC_SEGMENT and LC_SEGMENT_64 commands with their sections
+    pub fn segments(&self) -> Vec<Segment> {
+        self.commands()
+            .filter_map(|command| match command.decode() {
+                Command::Segment(segment) => Some(segment),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Install names of the libraries loaded by the file
+    pub fn libraries(&self) -> Vec<&'a str> {
+        self.commands()
+            .filter(|command| command.cmd != commands::LC::ID_DYLIB)
+            .filter_map(|command| match command.decode() {
+                Command::Dylib { name, .. } => Some(name),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The LC_UUID of the file
+    pub fn uuid(&self) -> Option<[u8; 16]> {
+        self.commands().find_map(|command| match command.decode() {
+            Command::Uuid(uuid) => Some(uuid),
+            _ => None,
+        })
+    }
+
+    /// Offset of the entry point from the `__TEXT` segment, from LC_MAIN
+    pub fn entry_offset(&self) -> Option<u64> {
+        self.commands().find_map(|command| match command.decode() {
+            Command::Main { entryoff, .. } => Some(entryoff),
+            _ => None,
+        })
+    }
+}
+
+/// A Mach-O file, either thin or universal
+#[derive(Debug, Clone)]
+pub enum MachOFile<'a> {
+    /// single architecture file
+    Thin(MachO<'a>),
+    /// universal binary with one file per architecture
+    Fat(Vec<(fat_arch_64, Result<MachO<'a>>)>),
+}
+
+/// Parse a thin or universal Mach-O file
+///
+/// Each architecture of a universal binary is parsed independently so a
+/// corrupted one doesn't hide the others.
+pub fn from_bytes(data: &[u8]) -> Result<MachOFile<'_>> {
+    if !fat::is_fat(data) {
+        return MachO::parse(data).map(MachOFile::Thin);
+    }
+    let archs = fat::from_bytes(data)?
+        .into_iter()
+        .map(|arch| {
+            let macho = arch
+                .data(data)
+                .ok_or(Error::OutOfBounds {
+                    offset: arch.offset,
+                    size: arch.size,
+                })
+                .and_then(MachO::parse);
+            (arch, macho)
+        })
+        .collect();
+    Ok(MachOFile::Fat(archs))
+}
+
+impl fmt::Display for MachO<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.header)?;
+        for (index, command) in self.commands().enumerate() {
+            writeln!(f, "Load command {}", index)?;
+            write!(f, "{}", command)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for MachOFile<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MachOFile::Thin(macho) => write!(f, "{}", macho),
+            MachOFile::Fat(archs) => {
+                writeln!(f, "Fat headers")?;
+                writeln!(f, "nfat_arch {}", archs.len())?;
+                for (index, (arch, _)) in archs.iter().enumerate() {
+                    writeln!(f, "architecture {}", index)?;
+                    write!(f, "{}", arch)?;
+                }
+                for (arch, macho) in archs {
+                    writeln!(f)?;
+                    writeln!(f, "(architecture {}):", CPU_TYPE::name(arch.cputype))?;
+                    match macho {
+                        Ok(macho) => write!(f, "{}", macho)?,
+                        Err(err) => writeln!(f, "Warning: invalid object file: {}", err)?,
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl ToJson for MachO<'_> {
+    fn to_json(&self) -> Json {
+        let header = &self.header.header;
+        Json::object([
+            (
+                "header",
+                Json::object([
+                    ("magic", Json::from(header.magic)),
+                    ("cputype", Json::from(header.cputype)),
+                    ("cpu", Json::from(CPU_TYPE::name(header.cputype))),
+                    ("cpusubtype", Json::from(header.cpusubtype)),
+                    ("filetype", Json::from(header.filetype)),
+                    ("type", Json::from(MH::filetype_name(header.filetype))),
+                    ("ncmds", Json::from(header.ncmds)),
+                    ("sizeofcmds", Json::from(header.sizeofcmds)),
+                    ("flags", Json::from(header.flags)),
+                ]),
+            ),
+            (
+                "commands",
+                Json::array(self.commands().map(|command| {
+                    Json::object([
+                        ("cmd", Json::from(command.cmd)),
+                        ("name", Json::from(command.name())),
+                        ("cmdsize", Json::from(command.cmdsize)),
+                    ])
+                })),
+            ),
+            (
+                "segments",
+                Json::array(self.segments().iter().map(|segment| {
+                    Json::object([
+                        ("name", Json::from(segment.name())),
+                        ("vmaddr", Json::from(segment.command.vmaddr)),
+                        ("vmsize", Json::from(segment.command.vmsize)),
+                        ("fileoff", Json::from(segment.command.fileoff)),
+                        ("filesize", Json::from(segment.command.filesize)),
+                        (
+                            "sections",
+                            Json::array(segment.sections.iter().map(|section| {
+                                Json::object([
+                                    ("name", Json::from(section.name())),
+                                    ("addr", Json::from(section.addr)),
+                                    ("size", Json::from(section.size)),
+                                    ("offset", Json::from(section.offset)),
+                                ])
+                            })),
+                        ),
+                    ])
+                })),
+            ),
+            ("libraries", Json::array(self.libraries())),
+        ])
+    }
+}
+
+impl ToJson for MachOFile<'_> {
+    fn to_json(&self) -> Json {
+        match self {
+            MachOFile::Thin(macho) => macho.to_json(),
+            MachOFile::Fat(archs) => Json::object([(
+                "architectures",
+                Json::array(archs.iter().map(|(arch, macho)| {
+                    Json::object([
+                        ("cpu", Json::from(CPU_TYPE::name(arch.cputype))),
+                        ("offset", Json::from(arch.offset)),
+                        ("size", Json::from(arch.size)),
+                        (
+                            "object",
+                            match macho {
+                                Ok(macho) => macho.to_json(),
+                                Err(err) => Json::error(err),
+                            },
+                        ),
+                    ])
+                })),
+            )]),
+        }
+    }
+}
@@ -1 +1,6 @@
+pub mod archive;
 pub mod elf;
+pub mod ihex;
+pub mod macho;
+pub mod pe;
+pub mod wasm;
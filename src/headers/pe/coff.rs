@@ -0,0 +1,204 @@
+use crate::utils::{cow_struct_endian, impl_swap_bytes};
+use crate::{Error, Result};
+use alloc::borrow::Cow;
+use core::fmt;
+use core::mem::size_of;
+
+/// Define the target machine of a PE file
+///
+/// MACHINE define possible values for the `machine` field
+pub struct MACHINE {}
+impl MACHINE {
+    /// Applicable to any machine type
+    pub const UNKNOWN: u16 = 0;
+    /// Intel 386 or later
+    pub const I386: u16 = 0x14c;
+    /// MIPS little endian
+    pub const R4000: u16 = 0x166;
+    /// ARM little endian
+    pub const ARM: u16 = 0x1c0;
+    /// ARM Thumb-2 little endian
+    pub const ARMNT: u16 = 0x1c4;
+    /// Intel Itanium
+    pub const IA64: u16 = 0x200;
+    /// EFI byte code
+    pub const EBC: u16 = 0xebc;
+    /// RISC-V 32 bits
+    pub const RISCV32: u16 = 0x5032;
+    /// RISC-V 64 bits
+    pub const RISCV64: u16 = 0x5064;
+    /// LoongArch 64 bits
+    pub const LOONGARCH64: u16 = 0x6264;
+    /// x64
+    pub const AMD64: u16 = 0x8664;
+    /// ARM64 little endian
+    pub const ARM64: u16 = 0xaa64;
+
+    /// Name of the machine as printed by dumpbin
+    pub fn name(machine: u16) -> &'static str {
+        match machine {
+            MACHINE::UNKNOWN => "Unknown",
+            MACHINE::I386 => "x86",
+            MACHINE::R4000 => "R4000",
+            MACHINE::ARM => "ARM",
+            MACHINE::ARMNT => "ARMNT",
+            MACHINE::IA64 => "IA64",
+            MACHINE::EBC => "EBC",
+            MACHINE::RISCV32 => "RISCV32",
+            MACHINE::RISCV64 => "RISCV64",
+            MACHINE::LOONGARCH64 => "LOONGARCH64",
+            MACHINE::AMD64 => "x64",
+            MACHINE::ARM64 => "ARM64",
+            _ => "Unknown",
+        }
+    }
+}
+
+/// Define the attributes of a PE file
+///
+/// CHARACTERISTICS define the bits of the `characteristics` field
+pub struct CHARACTERISTICS {}
+impl CHARACTERISTICS {
+    /// No base relocations, the image must be loaded at its preferred address
+    pub const RELOCS_STRIPPED: u16 = 0x0001;
+    /// The image is valid and can be run
+    pub const EXECUTABLE_IMAGE: u16 = 0x0002;
+    /// COFF line numbers have been removed, deprecated
+    pub const LINE_NUMS_STRIPPED: u16 = 0x0004;
+    /// COFF local symbols have been removed, deprecated
+    pub const LOCAL_SYMS_STRIPPED: u16 = 0x0008;
+    /// Aggressively trim working set, obsolete
+    pub const AGGRESSIVE_WS_TRIM: u16 = 0x0010;
+    /// The application can handle addresses above 2 GB
+    pub const LARGE_ADDRESS_AWARE: u16 = 0x0020;
+    /// Little endian, deprecated
+    pub const BYTES_REVERSED_LO: u16 = 0x0080;
+    /// The machine is based on a 32 bits word architecture
+    pub const MACHINE_32BIT: u16 = 0x0100;
+    /// Debugging information is removed from the image file
+    pub const DEBUG_STRIPPED: u16 = 0x0200;
+    /// Copy the image to swap when it is on removable media
+    pub const REMOVABLE_RUN_FROM_SWAP: u16 = 0x0400;
+    /// Copy the image to swap when it is on network media
+    pub const NET_RUN_FROM_SWAP: u16 = 0x0800;
+    /// The image file is a system file
+    pub const SYSTEM: u16 = 0x1000;
+    /// The image file is a dynamic link library
+    pub const DLL: u16 = 0x2000;
+    /// The file should only be run on an uniprocessor machine
+    pub const UP_SYSTEM_ONLY: u16 = 0x4000;
+    /// Big endian, deprecated
+    pub const BYTES_REVERSED_HI: u16 = 0x8000;
+
+    /// Description of each bit as printed by dumpbin
+    pub const NAMES: [(u16, &'static str); 15] = [
+        (Self::RELOCS_STRIPPED, "Relocations stripped"),
+        (Self::EXECUTABLE_IMAGE, "Executable"),
+        (Self::LINE_NUMS_STRIPPED, "Line numbers stripped"),
+        (Self::LOCAL_SYMS_STRIPPED, "Symbols stripped"),
+        (Self::AGGRESSIVE_WS_TRIM, "Aggressively trim working set"),
+        (
+            Self::LARGE_ADDRESS_AWARE,
+            "Application can handle large (>2GB) addresses",
+        ),
+        (Self::BYTES_REVERSED_LO, "Bytes reversed (low)"),
+        (Self::MACHINE_32BIT, "32 bit word machine"),
+        (Self::DEBUG_STRIPPED, "Debug information stripped"),
+        (
+            Self::REMOVABLE_RUN_FROM_SWAP,
+            "Run from swap if on removable media",
+        ),
+        (Self::NET_RUN_FROM_SWAP, "Run from swap if on network media"),
+        (Self::SYSTEM, "System"),
+        (Self::DLL, "DLL"),
+        (Self::UP_SYSTEM_ONLY, "Uniprocessor only"),
+        (Self::BYTES_REVERSED_HI, "Bytes reversed (high)"),
+    ];
+}
+
+/// COFF file header, following the `PE\0\0` signature
+///
+/// Sources:
+/// * <https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#coff-file-header-object-and-image>
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct CoffHeader {
+    /// target machine
+    pub machine: u16,
+    /// number of entries in the section table
+    pub number_of_sections: u16,
+    /// creation time, seconds since the unix epoch
+    pub time_date_stamp: u32,
+    /// file offset of the COFF symbol table, deprecated
+    pub pointer_to_symbol_table: u32,
+    /// number of entries in the COFF symbol table, deprecated
+    pub number_of_symbols: u32,
+    /// size of the optional header
+    pub size_of_optional_header: u16,
+    /// attributes of the file
+    pub characteristics: u16,
+}
+
+impl_swap_bytes!(
+    CoffHeader,
+    machine,
+    number_of_sections,
+    time_date_stamp,
+    pointer_to_symbol_table,
+    number_of_symbols,
+    size_of_optional_header,
+    characteristics
+);
+
+impl CoffHeader {
+    pub const SIZE: usize = size_of::<Self>();
+}
+
+/// Parse the COFF header located at `offset`
+///
+/// Fails if the header doesn't fit in the given data.
+pub fn from_bytes(data: &[u8], offset: usize) -> Result<Cow<'_, CoffHeader>> {
+    let bytes = offset
+        .checked_add(CoffHeader::SIZE)
+        .and_then(|end| data.get(offset..end))
+        .ok_or(Error::OutOfBounds {
+            offset: offset as u64,
+            size: CoffHeader::SIZE as u64,
+        })?;
+    cow_struct_endian::<CoffHeader>(bytes, false).ok_or(Error::TruncatedHeader {
+        expected: CoffHeader::SIZE,
+        found: bytes.len(),
+    })
+}
+
+impl fmt::Display for CoffHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "FILE HEADER VALUES")?;
+        writeln!(
+            f,
+            "{:>16X} machine ({})",
+            self.machine,
+            MACHINE::name(self.machine)
+        )?;
+        writeln!(f, "{:>16X} number of sections", self.number_of_sections)?;
+        writeln!(f, "{:>16X} time date stamp", self.time_date_stamp)?;
+        writeln!(
+            f,
+            "{:>16X} file pointer to symbol table",
+            self.pointer_to_symbol_table
+        )?;
+        writeln!(f, "{:>16X} number of symbols", self.number_of_symbols)?;
+        writeln!(
+            f,
+            "{:>16X} size of optional header",
+            self.size_of_optional_header
+        )?;
+        writeln!(f, "{:>16X} characteristics", self.characteristics)?;
+        for (bit, name) in CHARACTERISTICS::NAMES {
+            if self.characteristics & bit != 0 {
+                writeln!(f, "{:>19}{}", "", name)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+use crate::utils::{cow_struct_endian, impl_swap_bytes};
+use crate::{Error, Result};
+use alloc::borrow::Cow;
+use core::fmt;
+use core::mem::size_of;
+
+/// MS-DOS stub header found at the start of every PE file
+///
+/// Only `e_magic` and `e_lfanew` matter for PE files, the other fields
+/// describe the DOS program printing "This program cannot be run in DOS
+/// mode".
+/// Sources:
+/// * <https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#ms-dos-stub-image-only>
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct DosHeader {
+    /// magic number, `MZ`
+    pub e_magic: u16,
+    /// bytes on last page of file
+    pub e_cblp: u16,
+    /// pages in file
+    pub e_cp: u16,
+    /// relocations
+    pub e_crlc: u16,
+    /// size of header in paragraphs
+    pub e_cparhdr: u16,
+    /// minimum extra paragraphs needed
+    pub e_minalloc: u16,
+    /// maximum extra paragraphs needed
+    pub e_maxalloc: u16,
+    /// initial (relative) SS value
+    pub e_ss: u16,
+    /// initial SP value
+    pub e_sp: u16,
+    /// checksum
+    pub e_csum: u16,
+    /// initial IP value
+    pub e_ip: u16,
+    /// initial (relative) CS value
+    pub e_cs: u16,
+    /// file address of relocation table
+    pub e_lfarlc: u16,
+    /// overlay number
+    pub e_ovno: u16,
+    /// reserved words
+    pub e_res: [u16; 4],
+    /// OEM identifier
+    pub e_oemid: u16,
+    /// OEM information, e_oemid specific
+    pub e_oeminfo: u16,
+    /// reserved words
+    pub e_res2: [u16; 10],
+    /// file offset of the PE signature
+    pub e_lfanew: u32,
+}
+
+impl_swap_bytes!(
+    DosHeader, e_magic, e_cblp, e_cp, e_crlc, e_cparhdr, e_minalloc, e_maxalloc, e_ss, e_sp,
+    e_csum, e_ip, e_cs, e_lfarlc, e_ovno, e_oemid, e_oeminfo, e_lfanew
+);
+
+impl DosHeader {
+    pub const SIZE: usize = size_of::<Self>();
+
+    /// `MZ` stored in the first two bytes of the file
+    pub const MAGIC: [u8; 2] = *b"MZ";
+}
+
+/// Parse the DOS header at the start of the file
+///
+/// Fails if the data is too small or doesn't start with `MZ`.
+pub fn from_bytes(data: &[u8]) -> Result<Cow<'_, DosHeader>> {
+    if !data.starts_with(&DosHeader::MAGIC) {
+        let mut magic = [0; 4];
+        let len = data.len().min(magic.len());
+        magic[..len].copy_from_slice(&data[..len]);
+        return Err(Error::BadMagic(magic));
+    }
+    let bytes = data.get(..DosHeader::SIZE).ok_or(Error::TruncatedHeader {
+        expected: DosHeader::SIZE,
+        found: data.len(),
+    })?;
+    // PE files are always little endian
+    cow_struct_endian::<DosHeader>(bytes, false).ok_or(Error::TruncatedHeader {
+        expected: DosHeader::SIZE,
+        found: data.len(),
+    })
+}
+
+impl fmt::Display for DosHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "DOS HEADER VALUES")?;
+        writeln!(f, "{:>16X} magic # (MZ)", self.e_magic)?;
+        writeln!(f, "{:>16X} file address of new exe header", self.e_lfanew)
+    }
+}
@@ -0,0 +1,253 @@
+use crate::alloc_prelude::*;
+use crate::utils::json::{Json, ToJson};
+use crate::{Error, Result};
+use alloc::borrow::Cow;
+use core::fmt;
+
+pub mod coff;
+pub mod dos;
+pub mod optional;
+pub mod section;
+
+use coff::{CoffHeader, CHARACTERISTICS};
+use dos::DosHeader;
+use optional::{DataDirectories, DataDirectory, OptionalHeader};
+use section::{SectionHeader, SectionHeaders};
+
+/// Signature found at `e_lfanew`, right before the COFF header
+pub const SIGNATURE: [u8; 4] = *b"PE\0\0";
+
+/// Headers of a PE/COFF image
+///
+/// A PE file starts with a DOS stub whose `e_lfanew` field points to the
+/// `PE\0\0` signature, followed by the COFF file header, the optional
+/// header with its data directories and the section table.
+/// Sources:
+/// * <https://learn.microsoft.com/en-us/windows/win32/debug/pe-format>
+#[derive(Debug, Clone)]
+pub struct PeHeaders<'a> {
+    /// MS-DOS stub header
+    pub dos: Cow<'a, DosHeader>,
+    /// COFF file header
+    pub coff: Cow<'a, CoffHeader>,
+    /// optional header, required for images
+    pub optional: Option<OptionalHeader<'a>>,
+    /// file offset of the COFF header
+    coff_offset: usize,
+}
+
+impl PeHeaders<'_> {
+    /// File offset of the optional header
+    fn optional_offset(&self) -> usize {
+        self.coff_offset + CoffHeader::SIZE
+    }
+
+    /// The data directories following the optional header
+    ///
+    /// Empty when the file has no optional header.
+    pub fn directories<'a>(&self, data: &'a [u8]) -> Result<DataDirectories<'a>> {
+        match &self.optional {
+            Some(optional) => optional::directories(
+                data,
+                self.optional_offset(),
+                self.coff.size_of_optional_header as usize,
+                optional,
+            ),
+            None => DataDirectories::parse(data, 0, DataDirectory::SIZE as u64, 0, false),
+        }
+    }
+
+    /// The section table, located right after the optional header
+    pub fn sections<'a>(&self, data: &'a [u8]) -> Result<SectionHeaders<'a>> {
+        SectionHeaders::parse(
+            data,
+            (self.optional_offset() + self.coff.size_of_optional_header as usize) as u64,
+            SectionHeader::SIZE as u64,
+            self.coff.number_of_sections as u64,
+            false,
+        )
+    }
+
+    /// Kind of file as printed by dumpbin
+    pub fn file_type(&self) -> &'static str {
+        if self.coff.characteristics & CHARACTERISTICS::DLL != 0 {
+            "DLL"
+        } else if self.coff.characteristics & CHARACTERISTICS::EXECUTABLE_IMAGE != 0 {
+            "EXECUTABLE IMAGE"
+        } else {
+            "COFF OBJECT"
+        }
+    }
+}
+
+/// Parse the headers of a PE image
+///
+/// Fails if the file doesn't start with a DOS header, if `e_lfanew`
+/// doesn't point to the PE signature or if a header is truncated.
+pub fn from_bytes(data: &[u8]) -> Result<PeHeaders<'_>> {
+    let dos = dos::from_bytes(data)?;
+    let signature_offset = dos.e_lfanew as usize;
+    let signature = signature_offset
+        .checked_add(SIGNATURE.len())
+        .and_then(|end| data.get(signature_offset..end))
+        .ok_or(Error::OutOfBounds {
+            offset: signature_offset as u64,
+            size: SIGNATURE.len() as u64,
+        })?;
+    if signature != SIGNATURE {
+        let mut magic = [0; 4];
+        magic.copy_from_slice(signature);
+        return Err(Error::BadMagic(magic));
+    }
+    let coff_offset = signature_offset + SIGNATURE.len();
+    let coff = coff::from_bytes(data, coff_offset)?;
+    let optional = match coff.size_of_optional_header as usize {
+        0 => None,
+        size => Some(optional::from_bytes(
+            data,
+            coff_offset + CoffHeader::SIZE,
+            size,
+        )?),
+    };
+    Ok(PeHeaders {
+        dos,
+        coff,
+        optional,
+        coff_offset,
+    })
+}
+
+impl fmt::Display for PeHeaders<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "PE signature found")?;
+        writeln!(f)?;
+        writeln!(f, "File Type: {}", self.file_type())?;
+        writeln!(f)?;
+        write!(f, "{}", self.coff)?;
+        if let Some(optional) = &self.optional {
+            writeln!(f)?;
+            write!(f, "{}", optional)?;
+        }
+        Ok(())
+    }
+}
+
+/// Headers ready to be printed like `dumpbin /headers`
+pub struct PeHeaderTable<'a> {
+    /// the whole file, used to read the directories and sections
+    pub data: &'a [u8],
+    /// the parsed headers
+    pub headers: &'a PeHeaders<'a>,
+}
+
+impl fmt::Display for PeHeaderTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.headers)?;
+        match self.headers.directories(self.data) {
+            Ok(directories) => {
+                for (index, directory) in directories.enumerate() {
+                    let name = DataDirectory::NAMES.get(index).unwrap_or(&"Unknown");
+                    writeln!(f, "{} {}", directory, name)?;
+                }
+            }
+            Err(err) => writeln!(f, "Warning: invalid data directories: {}", err)?,
+        }
+        let sections = match self.headers.sections(self.data) {
+            Ok(sections) => sections,
+            Err(err) => return writeln!(f, "Warning: invalid section table: {}", err),
+        };
+        for (index, section) in sections.enumerate() {
+            writeln!(f)?;
+            writeln!(f, "SECTION HEADER #{}", index + 1)?;
+            write!(f, "{}", section)?;
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for PeHeaderTable<'_> {
+    fn to_json(&self) -> Json {
+        let coff = &self.headers.coff;
+        let mut members = vec![
+            ("file_type", Json::from(self.headers.file_type())),
+            (
+                "coff",
+                Json::object([
+                    ("machine", Json::from(coff.machine)),
+                    (
+                        "machine_name",
+                        Json::from(coff::MACHINE::name(coff.machine)),
+                    ),
+                    ("number_of_sections", Json::from(coff.number_of_sections)),
+                    ("time_date_stamp", Json::from(coff.time_date_stamp)),
+                    (
+                        "pointer_to_symbol_table",
+                        Json::from(coff.pointer_to_symbol_table),
+                    ),
+                    ("number_of_symbols", Json::from(coff.number_of_symbols)),
+                    (
+                        "size_of_optional_header",
+                        Json::from(coff.size_of_optional_header),
+                    ),
+                    ("characteristics", Json::from(coff.characteristics)),
+                ]),
+            ),
+        ];
+        if let Some(optional) = &self.headers.optional {
+            let header = optional.as_pe32_plus();
+            members.push((
+                "optional",
+                Json::object([
+                    ("magic", Json::from(header.magic)),
+                    (
+                        "address_of_entry_point",
+                        Json::from(header.address_of_entry_point),
+                    ),
+                    ("image_base", Json::from(header.image_base)),
+                    ("section_alignment", Json::from(header.section_alignment)),
+                    ("file_alignment", Json::from(header.file_alignment)),
+                    ("size_of_image", Json::from(header.size_of_image)),
+                    ("size_of_headers", Json::from(header.size_of_headers)),
+                    ("check_sum", Json::from(header.check_sum)),
+                    ("subsystem", Json::from(header.subsystem)),
+                    (
+                        "dll_characteristics",
+                        Json::from(header.dll_characteristics),
+                    ),
+                ]),
+            ));
+        }
+        members.push((
+            "directories",
+            match self.headers.directories(self.data) {
+                Ok(directories) => Json::array(directories.map(|directory| {
+                    Json::object([
+                        ("virtual_address", Json::from(directory.virtual_address)),
+                        ("size", Json::from(directory.size)),
+                    ])
+                })),
+                Err(err) => Json::error(err),
+            },
+        ));
+        members.push((
+            "sections",
+            match self.headers.sections(self.data) {
+                Ok(sections) => Json::array(sections.map(|section| {
+                    Json::object([
+                        ("name", Json::from(section.name())),
+                        ("virtual_size", Json::from(section.virtual_size)),
+                        ("virtual_address", Json::from(section.virtual_address)),
+                        ("size_of_raw_data", Json::from(section.size_of_raw_data)),
+                        (
+                            "pointer_to_raw_data",
+                            Json::from(section.pointer_to_raw_data),
+                        ),
+                        ("characteristics", Json::from(section.characteristics)),
+                    ])
+                })),
+                Err(err) => Json::error(err),
+            },
+        ));
+        Json::object(members)
+    }
+}
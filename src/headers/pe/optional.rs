@@ -0,0 +1,609 @@
+use crate::headers::elf::table::Table;
+use crate::utils::{cow_struct_endian, impl_swap_bytes};
+use crate::{Error, Result};
+use alloc::borrow::Cow;
+use core::fmt;
+use core::mem::size_of;
+
+/// Define the kind of optional header
+///
+/// MAGIC define possible values for the `magic` field
+pub struct MAGIC {}
+impl MAGIC {
+    /// 32 bits image
+    pub const PE32: u16 = 0x10b;
+    /// 64 bits image
+    pub const PE32_PLUS: u16 = 0x20b;
+    /// ROM image
+    pub const ROM: u16 = 0x107;
+}
+
+/// Define the subsystem required to run the image
+///
+/// SUBSYSTEM define possible values for the `subsystem` field
+pub struct SUBSYSTEM {}
+impl SUBSYSTEM {
+    /// Unknown subsystem
+    pub const UNKNOWN: u16 = 0;
+    /// Device drivers and native Windows processes
+    pub const NATIVE: u16 = 1;
+    /// Windows graphical user interface
+    pub const WINDOWS_GUI: u16 = 2;
+    /// Windows character subsystem
+    pub const WINDOWS_CUI: u16 = 3;
+    /// OS/2 character subsystem
+    pub const OS2_CUI: u16 = 5;
+    /// Posix character subsystem
+    pub const POSIX_CUI: u16 = 7;
+    /// Native Win9x driver
+    pub const NATIVE_WINDOWS: u16 = 8;
+    /// Windows CE
+    pub const WINDOWS_CE_GUI: u16 = 9;
+    /// EFI application
+    pub const EFI_APPLICATION: u16 = 10;
+    /// EFI driver with boot services
+    pub const EFI_BOOT_SERVICE_DRIVER: u16 = 11;
+    /// EFI driver with run-time services
+    pub const EFI_RUNTIME_DRIVER: u16 = 12;
+    /// EFI ROM image
+    pub const EFI_ROM: u16 = 13;
+    /// XBOX
+    pub const XBOX: u16 = 14;
+    /// Windows boot application
+    pub const WINDOWS_BOOT_APPLICATION: u16 = 16;
+
+    /// Name of the subsystem as printed by dumpbin
+    pub fn name(subsystem: u16) -> &'static str {
+        match subsystem {
+            SUBSYSTEM::NATIVE => "Native",
+            SUBSYSTEM::WINDOWS_GUI => "Windows GUI",
+            SUBSYSTEM::WINDOWS_CUI => "Windows CUI",
+            SUBSYSTEM::OS2_CUI => "OS/2 CUI",
+            SUBSYSTEM::POSIX_CUI => "Posix CUI",
+            SUBSYSTEM::NATIVE_WINDOWS => "Native Win9x driver",
+            SUBSYSTEM::WINDOWS_CE_GUI => "Windows CE GUI",
+            SUBSYSTEM::EFI_APPLICATION => "EFI Application",
+            SUBSYSTEM::EFI_BOOT_SERVICE_DRIVER => "EFI Boot Service Driver",
+            SUBSYSTEM::EFI_RUNTIME_DRIVER => "EFI Runtime Driver",
+            SUBSYSTEM::EFI_ROM => "EFI ROM",
+            SUBSYSTEM::XBOX => "Xbox",
+            SUBSYSTEM::WINDOWS_BOOT_APPLICATION => "Windows Boot Application",
+            _ => "Unknown",
+        }
+    }
+}
+
+/// Define the DLL characteristics of the image
+///
+/// DLL_CHARACTERISTICS define the bits of the `dll_characteristics` field
+#[allow(non_camel_case_types)]
+pub struct DLL_CHARACTERISTICS {}
+impl DLL_CHARACTERISTICS {
+    /// The image can handle a high entropy 64 bits address space
+    pub const HIGH_ENTROPY_VA: u16 = 0x0020;
+    /// The DLL can be relocated at load time
+    pub const DYNAMIC_BASE: u16 = 0x0040;
+    /// Code integrity checks are enforced
+    pub const FORCE_INTEGRITY: u16 = 0x0080;
+    /// The image is NX compatible
+    pub const NX_COMPAT: u16 = 0x0100;
+    /// Isolation aware, but do not isolate the image
+    pub const NO_ISOLATION: u16 = 0x0200;
+    /// Does not use structured exception handling
+    pub const NO_SEH: u16 = 0x0400;
+    /// Do not bind the image
+    pub const NO_BIND: u16 = 0x0800;
+    /// The image must execute in an AppContainer
+    pub const APPCONTAINER: u16 = 0x1000;
+    /// A WDM driver
+    pub const WDM_DRIVER: u16 = 0x2000;
+    /// The image supports Control Flow Guard
+    pub const GUARD_CF: u16 = 0x4000;
+    /// Terminal Server aware
+    pub const TERMINAL_SERVER_AWARE: u16 = 0x8000;
+
+    /// Description of each bit as printed by dumpbin
+    pub const NAMES: [(u16, &'static str); 11] = [
+        (Self::HIGH_ENTROPY_VA, "High Entropy Virtual Addresses"),
+        (Self::DYNAMIC_BASE, "Dynamic base"),
+        (Self::FORCE_INTEGRITY, "Force integrity"),
+        (Self::NX_COMPAT, "NX compatible"),
+        (Self::NO_ISOLATION, "No isolation"),
+        (Self::NO_SEH, "No structured exception handler"),
+        (Self::NO_BIND, "No binding"),
+        (Self::APPCONTAINER, "App container"),
+        (Self::WDM_DRIVER, "WDM driver"),
+        (Self::GUARD_CF, "Control Flow Guard"),
+        (Self::TERMINAL_SERVER_AWARE, "Terminal Server Aware"),
+    ];
+}
+
+/// Optional header of a PE32 image
+///
+/// Despite its name the optional header is required for images, only
+/// object files don't have one.
+/// Sources:
+/// * <https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#optional-header-image-only>
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct OptionalHeader32 {
+    /// kind of image, MAGIC::PE32
+    pub magic: u16,
+    /// linker major version number
+    pub major_linker_version: u8,
+    /// linker minor version number
+    pub minor_linker_version: u8,
+    /// size of the code sections
+    pub size_of_code: u32,
+    /// size of the initialized data sections
+    pub size_of_initialized_data: u32,
+    /// size of the uninitialized data sections
+    pub size_of_uninitialized_data: u32,
+    /// entry point address relative to the image base
+    pub address_of_entry_point: u32,
+    /// address of the beginning of the code section relative to the image base
+    pub base_of_code: u32,
+    /// address of the beginning of the data section relative to the image base
+    pub base_of_data: u32,
+    /// preferred address of the image when loaded in memory
+    pub image_base: u32,
+    /// alignment of the sections in memory
+    pub section_alignment: u32,
+    /// alignment of the sections in the file
+    pub file_alignment: u32,
+    /// required operating system major version
+    pub major_operating_system_version: u16,
+    /// required operating system minor version
+    pub minor_operating_system_version: u16,
+    /// image major version
+    pub major_image_version: u16,
+    /// image minor version
+    pub minor_image_version: u16,
+    /// subsystem major version
+    pub major_subsystem_version: u16,
+    /// subsystem minor version
+    pub minor_subsystem_version: u16,
+    /// reserved, must be zero
+    pub win32_version_value: u32,
+    /// size of the image in memory
+    pub size_of_image: u32,
+    /// combined size of the headers rounded up to `file_alignment`
+    pub size_of_headers: u32,
+    /// image checksum
+    pub check_sum: u32,
+    /// subsystem required to run the image
+    pub subsystem: u16,
+    /// DLL characteristics
+    pub dll_characteristics: u16,
+    /// size of the stack to reserve
+    pub size_of_stack_reserve: u32,
+    /// size of the stack to commit
+    pub size_of_stack_commit: u32,
+    /// size of the local heap to reserve
+    pub size_of_heap_reserve: u32,
+    /// size of the local heap to commit
+    pub size_of_heap_commit: u32,
+    /// reserved, must be zero
+    pub loader_flags: u32,
+    /// number of data directory entries following the header
+    pub number_of_rva_and_sizes: u32,
+}
+
+impl_swap_bytes!(
+    OptionalHeader32,
+    magic,
+    size_of_code,
+    size_of_initialized_data,
+    size_of_uninitialized_data,
+    address_of_entry_point,
+    base_of_code,
+    base_of_data,
+    image_base,
+    section_alignment,
+    file_alignment,
+    major_operating_system_version,
+    minor_operating_system_version,
+    major_image_version,
+    minor_image_version,
+    major_subsystem_version,
+    minor_subsystem_version,
+    win32_version_value,
+    size_of_image,
+    size_of_headers,
+    check_sum,
+    subsystem,
+    dll_characteristics,
+    size_of_stack_reserve,
+    size_of_stack_commit,
+    size_of_heap_reserve,
+    size_of_heap_commit,
+    loader_flags,
+    number_of_rva_and_sizes
+);
+
+impl OptionalHeader32 {
+    pub const SIZE: usize = size_of::<Self>();
+}
+
+/// Optional header of a PE32+ image
+///
+/// Same as [`OptionalHeader32`] without `base_of_data` and with 64 bits
+/// image base and stack/heap sizes.
+/// Sources:
+/// * <https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#optional-header-image-only>
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct OptionalHeader64 {
+    /// kind of image, MAGIC::PE32_PLUS
+    pub magic: u16,
+    /// linker major version number
+    pub major_linker_version: u8,
+    /// linker minor version number
+    pub minor_linker_version: u8,
+    /// size of the code sections
+    pub size_of_code: u32,
+    /// size of the initialized data sections
+    pub size_of_initialized_data: u32,
+    /// size of the uninitialized data sections
+    pub size_of_uninitialized_data: u32,
+    /// entry point address relative to the image base
+    pub address_of_entry_point: u32,
+    /// address of the beginning of the code section relative to the image base
+    pub base_of_code: u32,
+    /// preferred address of the image when loaded in memory
+    pub image_base: u64,
+    /// alignment of the sections in memory
+    pub section_alignment: u32,
+    /// alignment of the sections in the file
+    pub file_alignment: u32,
+    /// required operating system major version
+    pub major_operating_system_version: u16,
+    /// required operating system minor version
+    pub minor_operating_system_version: u16,
+    /// image major version
+    pub major_image_version: u16,
+    /// image minor version
+    pub minor_image_version: u16,
+    /// subsystem major version
+    pub major_subsystem_version: u16,
+    /// subsystem minor version
+    pub minor_subsystem_version: u16,
+    /// reserved, must be zero
+    pub win32_version_value: u32,
+    /// size of the image in memory
+    pub size_of_image: u32,
+    /// combined size of the headers rounded up to `file_alignment`
+    pub size_of_headers: u32,
+    /// image checksum
+    pub check_sum: u32,
+    /// subsystem required to run the image
+    pub subsystem: u16,
+    /// DLL characteristics
+    pub dll_characteristics: u16,
+    /// size of the stack to reserve
+    pub size_of_stack_reserve: u64,
+    /// size of the stack to commit
+    pub size_of_stack_commit: u64,
+    /// size of the local heap to reserve
+    pub size_of_heap_reserve: u64,
+    /// size of the local heap to commit
+    pub size_of_heap_commit: u64,
+    /// reserved, must be zero
+    pub loader_flags: u32,
+    /// number of data directory entries following the header
+    pub number_of_rva_and_sizes: u32,
+}
+
+impl_swap_bytes!(
+    OptionalHeader64,
+    magic,
+    size_of_code,
+    size_of_initialized_data,
+    size_of_uninitialized_data,
+    address_of_entry_point,
+    base_of_code,
+    image_base,
+    section_alignment,
+    file_alignment,
+    major_operating_system_version,
+    minor_operating_system_version,
+    major_image_version,
+    minor_image_version,
+    major_subsystem_version,
+    minor_subsystem_version,
+    win32_version_value,
+    size_of_image,
+    size_of_headers,
+    check_sum,
+    subsystem,
+    dll_characteristics,
+    size_of_stack_reserve,
+    size_of_stack_commit,
+    size_of_heap_reserve,
+    size_of_heap_commit,
+    loader_flags,
+    number_of_rva_and_sizes
+);
+
+impl OptionalHeader64 {
+    pub const SIZE: usize = size_of::<Self>();
+}
+
+impl From<OptionalHeader32> for OptionalHeader64 {
+    fn from(header: OptionalHeader32) -> Self {
+        OptionalHeader64 {
+            magic: header.magic,
+            major_linker_version: header.major_linker_version,
+            minor_linker_version: header.minor_linker_version,
+            size_of_code: header.size_of_code,
+            size_of_initialized_data: header.size_of_initialized_data,
+            size_of_uninitialized_data: header.size_of_uninitialized_data,
+            address_of_entry_point: header.address_of_entry_point,
+            base_of_code: header.base_of_code,
+            image_base: header.image_base as u64,
+            section_alignment: header.section_alignment,
+            file_alignment: header.file_alignment,
+            major_operating_system_version: header.major_operating_system_version,
+            minor_operating_system_version: header.minor_operating_system_version,
+            major_image_version: header.major_image_version,
+            minor_image_version: header.minor_image_version,
+            major_subsystem_version: header.major_subsystem_version,
+            minor_subsystem_version: header.minor_subsystem_version,
+            win32_version_value: header.win32_version_value,
+            size_of_image: header.size_of_image,
+            size_of_headers: header.size_of_headers,
+            check_sum: header.check_sum,
+            subsystem: header.subsystem,
+            dll_characteristics: header.dll_characteristics,
+            size_of_stack_reserve: header.size_of_stack_reserve as u64,
+            size_of_stack_commit: header.size_of_stack_commit as u64,
+            size_of_heap_reserve: header.size_of_heap_reserve as u64,
+            size_of_heap_commit: header.size_of_heap_commit as u64,
+            loader_flags: header.loader_flags,
+            number_of_rva_and_sizes: header.number_of_rva_and_sizes,
+        }
+    }
+}
+
+/// Address and size of a table used by the loader
+///
+/// Sources:
+/// * <https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#optional-header-data-directories-image-only>
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct DataDirectory {
+    /// address of the table relative to the image base
+    pub virtual_address: u32,
+    /// size of the table in bytes
+    pub size: u32,
+}
+
+impl_swap_bytes!(DataDirectory, virtual_address, size);
+
+impl DataDirectory {
+    pub const SIZE: usize = size_of::<Self>();
+
+    /// Names of the data directories as printed by dumpbin, by index
+    pub const NAMES: [&'static str; 16] = [
+        "Export Directory",
+        "Import Directory",
+        "Resource Directory",
+        "Exception Directory",
+        "Certificates Directory",
+        "Base Relocation Directory",
+        "Debug Directory",
+        "Architecture Directory",
+        "Global Pointer Directory",
+        "Thread Storage Directory",
+        "Load Configuration Directory",
+        "Bound Import Directory",
+        "Import Address Table Directory",
+        "Delay Import Directory",
+        "COM Descriptor Directory",
+        "Reserved Directory",
+    ];
+}
+
+/// Iterator over the data directories following the optional header
+pub type DataDirectories<'a> = Table<'a, DataDirectory>;
+
+/// Optional header of either kind
+#[derive(Debug, Clone)]
+pub enum OptionalHeader<'a> {
+    /// 32 bits image
+    Pe32(Cow<'a, OptionalHeader32>),
+    /// 64 bits image
+    Pe32Plus(Cow<'a, OptionalHeader64>),
+}
+
+impl OptionalHeader<'_> {
+    /// Size of the header without the data directories
+    pub fn size(&self) -> usize {
+        match self {
+            OptionalHeader::Pe32(_) => OptionalHeader32::SIZE,
+            OptionalHeader::Pe32Plus(_) => OptionalHeader64::SIZE,
+        }
+    }
+
+    /// Fields of the header widened to the PE32+ layout
+    pub fn as_pe32_plus(&self) -> OptionalHeader64 {
+        match self {
+            OptionalHeader::Pe32(header) => OptionalHeader64::from(**header),
+            OptionalHeader::Pe32Plus(header) => **header,
+        }
+    }
+}
+
+/// Parse the optional header of `size` bytes located at `offset`
+///
+/// Fails if the header doesn't fit in `size` or in the data, or if its
+/// magic is neither PE32 nor PE32+.
+pub fn from_bytes(data: &[u8], offset: usize, size: usize) -> Result<OptionalHeader<'_>> {
+    let bytes = offset
+        .checked_add(size)
+        .and_then(|end| data.get(offset..end))
+        .ok_or(Error::OutOfBounds {
+            offset: offset as u64,
+            size: size as u64,
+        })?;
+    let magic = bytes
+        .get(..2)
+        .map(|magic| u16::from_le_bytes([magic[0], magic[1]]))
+        .ok_or(Error::TruncatedHeader {
+            expected: OptionalHeader32::SIZE,
+            found: bytes.len(),
+        })?;
+    let (expected, header) = match magic {
+        MAGIC::PE32 => (
+            OptionalHeader32::SIZE,
+            bytes
+                .get(..OptionalHeader32::SIZE)
+                .and_then(|bytes| cow_struct_endian(bytes, false))
+                .map(OptionalHeader::Pe32),
+        ),
+        MAGIC::PE32_PLUS => (
+            OptionalHeader64::SIZE,
+            bytes
+                .get(..OptionalHeader64::SIZE)
+                .and_then(|bytes| cow_struct_endian(bytes, false))
+                .map(OptionalHeader::Pe32Plus),
+        ),
+        _ => {
+            return Err(Error::InvalidHeaderField {
+                field: "optional header magic",
+                expected: MAGIC::PE32_PLUS as usize,
+                found: magic as usize,
+            })
+        }
+    };
+    header.ok_or(Error::TruncatedHeader {
+        expected,
+        found: bytes.len(),
+    })
+}
+
+/// Returns the data directories following the optional header
+///
+/// `optional_size` is the `size_of_optional_header` field of the COFF
+/// header, the directories are cut to fit in it.
+pub fn directories<'a>(
+    data: &'a [u8],
+    offset: usize,
+    optional_size: usize,
+    header: &OptionalHeader,
+) -> Result<DataDirectories<'a>> {
+    let space = optional_size.saturating_sub(header.size()) / DataDirectory::SIZE;
+    let num = (header.as_pe32_plus().number_of_rva_and_sizes as usize).min(space);
+    Table::parse(
+        data,
+        (offset + header.size()) as u64,
+        DataDirectory::SIZE as u64,
+        num as u64,
+        false,
+    )
+}
+
+impl fmt::Display for OptionalHeader<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let header = self.as_pe32_plus();
+        let kind = match self {
+            OptionalHeader::Pe32(_) => "PE32",
+            OptionalHeader::Pe32Plus(_) => "PE32+",
+        };
+        writeln!(f, "OPTIONAL HEADER VALUES")?;
+        writeln!(f, "{:>16X} magic # ({})", header.magic, kind)?;
+        writeln!(
+            f,
+            "{:>13}.{:02} linker version",
+            header.major_linker_version, header.minor_linker_version
+        )?;
+        writeln!(f, "{:>16X} size of code", header.size_of_code)?;
+        writeln!(
+            f,
+            "{:>16X} size of initialized data",
+            header.size_of_initialized_data
+        )?;
+        writeln!(
+            f,
+            "{:>16X} size of uninitialized data",
+            header.size_of_uninitialized_data
+        )?;
+        writeln!(
+            f,
+            "{:>16X} entry point ({:0width$X})",
+            header.address_of_entry_point,
+            header.image_base + header.address_of_entry_point as u64,
+            width = if kind == "PE32" { 8 } else { 16 }
+        )?;
+        writeln!(f, "{:>16X} base of code", header.base_of_code)?;
+        if let OptionalHeader::Pe32(header) = self {
+            writeln!(f, "{:>16X} base of data", header.base_of_data)?;
+        }
+        writeln!(f, "{:>16X} image base", header.image_base)?;
+        writeln!(f, "{:>16X} section alignment", header.section_alignment)?;
+        writeln!(f, "{:>16X} file alignment", header.file_alignment)?;
+        writeln!(
+            f,
+            "{:>13}.{:02} operating system version",
+            header.major_operating_system_version, header.minor_operating_system_version
+        )?;
+        writeln!(
+            f,
+            "{:>13}.{:02} image version",
+            header.major_image_version, header.minor_image_version
+        )?;
+        writeln!(
+            f,
+            "{:>13}.{:02} subsystem version",
+            header.major_subsystem_version, header.minor_subsystem_version
+        )?;
+        writeln!(f, "{:>16X} Win32 version", header.win32_version_value)?;
+        writeln!(f, "{:>16X} size of image", header.size_of_image)?;
+        writeln!(f, "{:>16X} size of headers", header.size_of_headers)?;
+        writeln!(f, "{:>16X} checksum", header.check_sum)?;
+        writeln!(
+            f,
+            "{:>16X} subsystem ({})",
+            header.subsystem,
+            SUBSYSTEM::name(header.subsystem)
+        )?;
+        writeln!(f, "{:>16X} DLL characteristics", header.dll_characteristics)?;
+        for (bit, name) in DLL_CHARACTERISTICS::NAMES {
+            if header.dll_characteristics & bit != 0 {
+                writeln!(f, "{:>19}{}", "", name)?;
+            }
+        }
+        writeln!(
+            f,
+            "{:>16X} size of stack reserve",
+            header.size_of_stack_reserve
+        )?;
+        writeln!(
+            f,
+            "{:>16X} size of stack commit",
+            header.size_of_stack_commit
+        )?;
+        writeln!(
+            f,
+            "{:>16X} size of heap reserve",
+            header.size_of_heap_reserve
+        )?;
+        writeln!(f, "{:>16X} size of heap commit", header.size_of_heap_commit)?;
+        writeln!(f, "{:>16X} loader flags", header.loader_flags)?;
+        writeln!(
+            f,
+            "{:>16X} number of directories",
+            header.number_of_rva_and_sizes
+        )
+    }
+}
+
+impl fmt::Display for DataDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>16X} [{:>8X}] RVA [size]",
+            self.virtual_address, self.size
+        )
+    }
+}
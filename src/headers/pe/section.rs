@@ -0,0 +1,173 @@
+use crate::alloc_prelude::*;
+use crate::headers::elf::table::Table;
+use crate::utils::impl_swap_bytes;
+use core::fmt;
+use core::mem::size_of;
+
+/// Define the attributes of a section
+///
+/// SCN define the bits of the `characteristics` field
+pub struct SCN {}
+impl SCN {
+    /// The section contains executable code
+    pub const CNT_CODE: u32 = 0x0000_0020;
+    /// The section contains initialized data
+    pub const CNT_INITIALIZED_DATA: u32 = 0x0000_0040;
+    /// The section contains uninitialized data
+    pub const CNT_UNINITIALIZED_DATA: u32 = 0x0000_0080;
+    /// The section contains comments or other information, object files only
+    pub const LNK_INFO: u32 = 0x0000_0200;
+    /// The section won't become part of the image, object files only
+    pub const LNK_REMOVE: u32 = 0x0000_0800;
+    /// The section contains COMDAT data, object files only
+    pub const LNK_COMDAT: u32 = 0x0000_1000;
+    /// The section contains data referenced through the global pointer
+    pub const GPREL: u32 = 0x0000_8000;
+    /// Mask of the alignment of the section data, object files only
+    pub const ALIGN_MASK: u32 = 0x00f0_0000;
+    /// The section contains extended relocations
+    pub const LNK_NRELOC_OVFL: u32 = 0x0100_0000;
+    /// The section can be discarded as needed
+    pub const MEM_DISCARDABLE: u32 = 0x0200_0000;
+    /// The section cannot be cached
+    pub const MEM_NOT_CACHED: u32 = 0x0400_0000;
+    /// The section is not pageable
+    pub const MEM_NOT_PAGED: u32 = 0x0800_0000;
+    /// The section can be shared in memory
+    pub const MEM_SHARED: u32 = 0x1000_0000;
+    /// The section can be executed as code
+    pub const MEM_EXECUTE: u32 = 0x2000_0000;
+    /// The section can be read
+    pub const MEM_READ: u32 = 0x4000_0000;
+    /// The section can be written to
+    pub const MEM_WRITE: u32 = 0x8000_0000;
+
+    /// Description of each bit as printed by dumpbin
+    pub const NAMES: [(u32, &'static str); 15] = [
+        (Self::CNT_CODE, "Code"),
+        (Self::CNT_INITIALIZED_DATA, "Initialized Data"),
+        (Self::CNT_UNINITIALIZED_DATA, "Uninitialized Data"),
+        (Self::LNK_INFO, "Comments"),
+        (Self::LNK_REMOVE, "Remove"),
+        (Self::LNK_COMDAT, "Communal"),
+        (Self::GPREL, "GP Relative"),
+        (Self::LNK_NRELOC_OVFL, "Extended relocations"),
+        (Self::MEM_DISCARDABLE, "Discardable"),
+        (Self::MEM_NOT_CACHED, "Not Cached"),
+        (Self::MEM_NOT_PAGED, "Not Paged"),
+        (Self::MEM_SHARED, "Shared"),
+        (Self::MEM_EXECUTE, "Execute"),
+        (Self::MEM_READ, "Read"),
+        (Self::MEM_WRITE, "Write"),
+    ];
+}
+
+/// Section table entry of a PE file
+///
+/// Sources:
+/// * <https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#section-table-section-headers>
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct SectionHeader {
+    /// nul padded name, longer names are stored in the string table
+    pub name: [u8; 8],
+    /// size of the section in memory
+    pub virtual_size: u32,
+    /// address of the section relative to the image base
+    pub virtual_address: u32,
+    /// size of the initialized data in the file
+    pub size_of_raw_data: u32,
+    /// file offset of the section data
+    pub pointer_to_raw_data: u32,
+    /// file offset of the relocations, object files only
+    pub pointer_to_relocations: u32,
+    /// file offset of the COFF line numbers, deprecated
+    pub pointer_to_linenumbers: u32,
+    /// number of relocations, object files only
+    pub number_of_relocations: u16,
+    /// number of COFF line numbers, deprecated
+    pub number_of_linenumbers: u16,
+    /// attributes of the section
+    pub characteristics: u32,
+}
+
+impl_swap_bytes!(
+    SectionHeader,
+    virtual_size,
+    virtual_address,
+    size_of_raw_data,
+    pointer_to_raw_data,
+    pointer_to_relocations,
+    pointer_to_linenumbers,
+    number_of_relocations,
+    number_of_linenumbers,
+    characteristics
+);
+
+impl SectionHeader {
+    pub const SIZE: usize = size_of::<Self>();
+
+    /// Name of the section without its nul padding
+    ///
+    /// Long names of object files are shown as their `/offset` reference.
+    pub fn name(&self) -> String {
+        let end = self
+            .name
+            .iter()
+            .position(|b| *b == 0)
+            .unwrap_or(self.name.len());
+        String::from_utf8_lossy(&self.name[..end]).into_owned()
+    }
+
+    /// Bytes of the section stored in the file
+    ///
+    /// Returns None if the section lies outside of the given data.
+    pub fn data<'a>(&self, data: &'a [u8]) -> Option<&'a [u8]> {
+        let start = self.pointer_to_raw_data as usize;
+        data.get(start..start.checked_add(self.size_of_raw_data as usize)?)
+    }
+}
+
+/// Iterator over the section table of a PE file
+pub type SectionHeaders<'a> = Table<'a, SectionHeader>;
+
+impl fmt::Display for SectionHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:>8} name", self.name())?;
+        writeln!(f, "{:>8X} virtual size", self.virtual_size)?;
+        writeln!(f, "{:>8X} virtual address", self.virtual_address)?;
+        writeln!(f, "{:>8X} size of raw data", self.size_of_raw_data)?;
+        writeln!(
+            f,
+            "{:>8X} file pointer to raw data",
+            self.pointer_to_raw_data
+        )?;
+        writeln!(
+            f,
+            "{:>8X} file pointer to relocation table",
+            self.pointer_to_relocations
+        )?;
+        writeln!(
+            f,
+            "{:>8X} file pointer to line numbers",
+            self.pointer_to_linenumbers
+        )?;
+        writeln!(
+            f,
+            "{:>8X} number of relocations",
+            self.number_of_relocations
+        )?;
+        writeln!(
+            f,
+            "{:>8X} number of line numbers",
+            self.number_of_linenumbers
+        )?;
+        writeln!(f, "{:>8X} flags", self.characteristics)?;
+        for (bit, name) in SCN::NAMES {
+            if self.characteristics & bit != 0 {
+                writeln!(f, "{:>9}{}", "", name)?;
+            }
+        }
+        Ok(())
+    }
+}
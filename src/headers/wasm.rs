@@ -0,0 +1,535 @@
+//! WebAssembly modules, `.wasm` files
+//!
+//! A module starts with `\0asm` and a 4 bytes little endian version,
+//! followed by sections made of an id byte, a LEB128 size and their
+//! payload. Custom sections (id 0) start with a name and may appear
+//! anywhere, the others appear at most once and in id order.
+//! Sources:
+//! * <https://webassembly.github.io/spec/core/binary/modules.html>
+use crate::alloc_prelude::*;
+use crate::format::Format;
+use crate::headers::elf::debug::Reader;
+use crate::utils::json::{Json, ToJson};
+use crate::utils::read_u32;
+use crate::{Error, Result};
+use core::fmt;
+
+/// Section ids
+pub struct SECTION {}
+
+impl SECTION {
+    pub const CUSTOM: u8 = 0;
+    pub const TYPE: u8 = 1;
+    pub const IMPORT: u8 = 2;
+    pub const FUNCTION: u8 = 3;
+    pub const TABLE: u8 = 4;
+    pub const MEMORY: u8 = 5;
+    pub const GLOBAL: u8 = 6;
+    pub const EXPORT: u8 = 7;
+    pub const START: u8 = 8;
+    pub const ELEMENT: u8 = 9;
+    pub const CODE: u8 = 10;
+    pub const DATA: u8 = 11;
+    pub const DATA_COUNT: u8 = 12;
+    pub const TAG: u8 = 13;
+
+    /// Name of a section id as printed by `wasm-objdump -h`
+    pub fn name(id: u8) -> &'static str {
+        match id {
+            Self::CUSTOM => "Custom",
+            Self::TYPE => "Type",
+            Self::IMPORT => "Import",
+            Self::FUNCTION => "Function",
+            Self::TABLE => "Table",
+            Self::MEMORY => "Memory",
+            Self::GLOBAL => "Global",
+            Self::EXPORT => "Export",
+            Self::START => "Start",
+            Self::ELEMENT => "Elem",
+            Self::CODE => "Code",
+            Self::DATA => "Data",
+            Self::DATA_COUNT => "DataCount",
+            Self::TAG => "Tag",
+            _ => "Unknown",
+        }
+    }
+}
+
+/// Kinds of imported and exported entities
+pub struct EXTERNAL {}
+
+impl EXTERNAL {
+    pub const FUNC: u8 = 0;
+    pub const TABLE: u8 = 1;
+    pub const MEMORY: u8 = 2;
+    pub const GLOBAL: u8 = 3;
+    pub const TAG: u8 = 4;
+
+    pub fn name(kind: u8) -> &'static str {
+        match kind {
+            Self::FUNC => "func",
+            Self::TABLE => "table",
+            Self::MEMORY => "memory",
+            Self::GLOBAL => "global",
+            Self::TAG => "tag",
+            _ => "unknown",
+        }
+    }
+}
+
+/// Name of a value or reference type
+pub fn type_name(value: u8) -> &'static str {
+    match value {
+        0x7f => "i32",
+        0x7e => "i64",
+        0x7d => "f32",
+        0x7c => "f64",
+        0x7b => "v128",
+        0x70 => "funcref",
+        0x6f => "externref",
+        _ => "unknown",
+    }
+}
+
+/// A section of the module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section<'a> {
+    /// section id, see [`SECTION`]
+    pub id: u8,
+    /// file offset of the payload
+    pub offset: usize,
+    /// the payload, after the name for custom sections
+    pub data: &'a [u8],
+    /// name of a custom section
+    pub name: Option<&'a str>,
+}
+
+impl Section<'_> {
+    /// Number of entries of the sections holding a vector
+    ///
+    /// The start section holds the index of the start function instead,
+    /// returned as well.
+    pub fn count(&self) -> Option<u32> {
+        match self.id {
+            SECTION::CUSTOM => None,
+            id if id > SECTION::TAG => None,
+            _ => Reader::new(self.data, false)
+                .uleb128()
+                .and_then(|count| u32::try_from(count).ok()),
+        }
+    }
+
+    /// File offset of the end of the payload
+    fn end(&self) -> usize {
+        self.offset + self.data.len()
+    }
+}
+
+/// Bounds of a memory or of a table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// initial size, in 64 KiB pages for memories
+    pub min: u64,
+    /// maximum size, unbounded if None
+    pub max: Option<u64>,
+}
+
+/// What an import brings in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportKind {
+    /// a function with the signature at this index of the type section
+    Func(u32),
+    /// a table of references
+    Table { element: u8, limits: Limits },
+    /// a linear memory
+    Memory(Limits),
+    /// a global variable
+    Global { value: u8, mutable: bool },
+    /// an exception tag with the signature at this index
+    Tag(u32),
+}
+
+/// An entry of the import section
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Import<'a> {
+    /// module the entity is imported from
+    pub module: &'a str,
+    /// name of the entity in that module
+    pub name: &'a str,
+    /// the imported entity
+    pub kind: ImportKind,
+}
+
+impl Import<'_> {
+    /// Kind of the entity, see [`EXTERNAL`]
+    pub fn external(&self) -> u8 {
+        match self.kind {
+            ImportKind::Func(_) => EXTERNAL::FUNC,
+            ImportKind::Table { .. } => EXTERNAL::TABLE,
+            ImportKind::Memory(_) => EXTERNAL::MEMORY,
+            ImportKind::Global { .. } => EXTERNAL::GLOBAL,
+            ImportKind::Tag(_) => EXTERNAL::TAG,
+        }
+    }
+}
+
+/// An entry of the export section
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Export<'a> {
+    /// name the entity is exported as
+    pub name: &'a str,
+    /// kind of the entity, see [`EXTERNAL`]
+    pub kind: u8,
+    /// index of the entity in the index space of its kind
+    pub index: u32,
+}
+
+/// A WebAssembly module borrowed from its bytes
+#[derive(Debug, Clone)]
+pub struct Wasm<'a> {
+    /// bytes of the whole file
+    pub data: &'a [u8],
+    /// binary format version, 1 for the current core specification
+    pub version: u32,
+    /// the sections in file order
+    pub sections: Vec<Section<'a>>,
+}
+
+/// Cursor over a section payload reporting errors with file offsets
+struct Payload<'a> {
+    reader: Reader<'a>,
+    base: usize,
+}
+
+impl<'a> Payload<'a> {
+    fn new(section: &Section<'a>) -> Self {
+        Payload {
+            reader: Reader::new(section.data, false),
+            base: section.offset,
+        }
+    }
+
+    fn error(&self) -> Error {
+        Error::InvalidWasm((self.base + self.reader.offset) as u64)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        self.reader.u8().ok_or_else(|| self.error())
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let error = self.error();
+        self.reader
+            .uleb128()
+            .and_then(|value| u32::try_from(value).ok())
+            .ok_or(error)
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        self.reader.uleb128().ok_or_else(|| self.error())
+    }
+
+    /// A name, its UTF-8 length then its bytes
+    fn name(&mut self) -> Result<&'a str> {
+        let offset = (self.base + self.reader.offset) as u64;
+        let len = self.u32()? as usize;
+        let start = self.reader.offset;
+        let bytes = start
+            .checked_add(len)
+            .and_then(|end| self.reader.data.get(start..end))
+            .ok_or_else(|| self.error())?;
+        self.reader.offset += len;
+        core::str::from_utf8(bytes).map_err(|_| Error::InvalidUtf8(offset))
+    }
+
+    fn limits(&mut self) -> Result<Limits> {
+        let flags = self.u8()?;
+        let min = self.u64()?;
+        let max = match flags & 1 {
+            0 => None,
+            _ => Some(self.u64()?),
+        };
+        Ok(Limits { min, max })
+    }
+}
+
+impl<'a> Wasm<'a> {
+    /// Parse the header and the section index of a module
+    ///
+    /// Fails if the data doesn't start with the magic or if a section
+    /// doesn't fit in the file.
+    pub fn parse(data: &'a [u8]) -> Result<Self> {
+        if data.len() < 8 {
+            return Err(Error::TruncatedHeader {
+                expected: 8,
+                found: data.len(),
+            });
+        }
+        if !data.starts_with(&Format::WASM_MAGIC) {
+            let mut magic = [0; 4];
+            magic.copy_from_slice(&data[..4]);
+            return Err(Error::BadMagic(magic));
+        }
+        let version = read_u32(data, 4, false).unwrap_or_default();
+        let mut reader = Reader::new(data, false);
+        reader.offset = 8;
+        let mut sections = Vec::new();
+        while reader.offset < data.len() {
+            let error = Error::InvalidWasm(reader.offset as u64);
+            let id = reader.u8().ok_or(error.clone())?;
+            let size = reader.uleb128().ok_or(error)?;
+            let offset = reader.offset;
+            let payload = usize::try_from(size)
+                .ok()
+                .and_then(|size| data.get(offset..offset.checked_add(size)?))
+                .ok_or(Error::OutOfBounds {
+                    offset: offset as u64,
+                    size,
+                })?;
+            reader.offset += payload.len();
+            let mut section = Section {
+                id,
+                offset,
+                data: payload,
+                name: None,
+            };
+            if id == SECTION::CUSTOM {
+                let mut payload = Payload::new(&section);
+                let name = payload.name()?;
+                let skip = payload.reader.offset;
+                section = Section {
+                    id,
+                    offset: offset + skip,
+                    data: &section.data[skip..],
+                    name: Some(name),
+                };
+            }
+            sections.push(section);
+        }
+        Ok(Wasm {
+            data,
+            version,
+            sections,
+        })
+    }
+
+    /// The first section with this id
+    pub fn section(&self, id: u8) -> Option<&Section<'a>> {
+        self.sections.iter().find(|section| section.id == id)
+    }
+
+    /// The custom section with this name, like `name` or `producers`
+    pub fn custom_section(&self, name: &str) -> Option<&Section<'a>> {
+        self.sections
+            .iter()
+            .find(|section| section.id == SECTION::CUSTOM && section.name == Some(name))
+    }
+
+    /// The entries of the import section, empty without one
+    pub fn imports(&self) -> Result<Vec<Import<'a>>> {
+        let section = match self.section(SECTION::IMPORT) {
+            Some(section) => section,
+            None => return Ok(Vec::new()),
+        };
+        let mut payload = Payload::new(section);
+        let count = payload.u32()?;
+        let mut imports = Vec::new();
+        for _ in 0..count {
+            let module = payload.name()?;
+            let name = payload.name()?;
+            let kind = match payload.u8()? {
+                EXTERNAL::FUNC => ImportKind::Func(payload.u32()?),
+                EXTERNAL::TABLE => ImportKind::Table {
+                    element: payload.u8()?,
+                    limits: payload.limits()?,
+                },
+                EXTERNAL::MEMORY => ImportKind::Memory(payload.limits()?),
+                EXTERNAL::GLOBAL => ImportKind::Global {
+                    value: payload.u8()?,
+                    mutable: payload.u8()? != 0,
+                },
+                EXTERNAL::TAG => {
+                    payload.u8()?;
+                    ImportKind::Tag(payload.u32()?)
+                }
+                _ => return Err(payload.error()),
+            };
+            imports.push(Import { module, name, kind });
+        }
+        Ok(imports)
+    }
+
+    /// The entries of the export section, empty without one
+    pub fn exports(&self) -> Result<Vec<Export<'a>>> {
+        let section = match self.section(SECTION::EXPORT) {
+            Some(section) => section,
+            None => return Ok(Vec::new()),
+        };
+        let mut payload = Payload::new(section);
+        let count = payload.u32()?;
+        let mut exports = Vec::new();
+        for _ in 0..count {
+            exports.push(Export {
+                name: payload.name()?,
+                kind: payload.u8()?,
+                index: payload.u32()?,
+            });
+        }
+        Ok(exports)
+    }
+}
+
+/// Parse the header and the section index of a WebAssembly module
+pub fn from_bytes(data: &[u8]) -> Result<Wasm<'_>> {
+    Wasm::parse(data)
+}
+
+impl fmt::Display for Limits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "initial={}", self.min)?;
+        if let Some(max) = self.max {
+            write!(f, " max={}", max)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Wasm<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "WebAssembly module, version {}", self.version)?;
+        writeln!(f)?;
+        writeln!(f, "Sections:")?;
+        for section in &self.sections {
+            write!(
+                f,
+                "{:>9} start={:#010x} end={:#010x} (size={:#010x})",
+                SECTION::name(section.id),
+                section.offset,
+                section.end(),
+                section.data.len()
+            )?;
+            match (section.name, section.count()) {
+                (Some(name), _) => writeln!(f, " \"{}\"", name)?,
+                (None, Some(index)) if section.id == SECTION::START => {
+                    writeln!(f, " start: {}", index)?
+                }
+                (None, Some(count)) => writeln!(f, " count: {}", count)?,
+                (None, None) => writeln!(f)?,
+            }
+        }
+
+        match self.imports() {
+            Ok(imports) if imports.is_empty() => {}
+            Ok(imports) => {
+                writeln!(f)?;
+                writeln!(f, "Import[{}]:", imports.len())?;
+                let mut indices = [0u32; 5];
+                for import in imports {
+                    let kind = import.external();
+                    let index = &mut indices[kind as usize];
+                    write!(f, " - {}[{}]", EXTERNAL::name(kind), index)?;
+                    *index += 1;
+                    match import.kind {
+                        ImportKind::Func(sig) | ImportKind::Tag(sig) => write!(f, " sig={}", sig)?,
+                        ImportKind::Table { element, limits } => {
+                            write!(f, " type={} {}", type_name(element), limits)?
+                        }
+                        ImportKind::Memory(limits) => write!(f, " pages: {}", limits)?,
+                        ImportKind::Global { value, mutable } => {
+                            write!(f, " {} mutable={}", type_name(value), mutable as u8)?
+                        }
+                    }
+                    writeln!(f, " <- {}.{}", import.module, import.name)?;
+                }
+            }
+            Err(err) => writeln!(f, "Warning: invalid import section: {}", err)?,
+        }
+
+        match self.exports() {
+            Ok(exports) if exports.is_empty() => {}
+            Ok(exports) => {
+                writeln!(f)?;
+                writeln!(f, "Export[{}]:", exports.len())?;
+                for export in exports {
+                    writeln!(
+                        f,
+                        " - {}[{}] -> \"{}\"",
+                        EXTERNAL::name(export.kind),
+                        export.index,
+                        export.name
+                    )?;
+                }
+            }
+            Err(err) => writeln!(f, "Warning: invalid export section: {}", err)?,
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for Import<'_> {
+    fn to_json(&self) -> Json {
+        let mut members = vec![
+            ("module", Json::from(self.module)),
+            ("name", Json::from(self.name)),
+            ("kind", Json::from(EXTERNAL::name(self.external()))),
+        ];
+        let limits = |limits: Limits| {
+            Json::object([
+                ("min", Json::from(limits.min)),
+                ("max", Json::from(limits.max)),
+            ])
+        };
+        match self.kind {
+            ImportKind::Func(sig) | ImportKind::Tag(sig) => members.push(("sig", Json::from(sig))),
+            ImportKind::Table { element, limits: l } => {
+                members.push(("element", Json::from(type_name(element))));
+                members.push(("limits", limits(l)));
+            }
+            ImportKind::Memory(l) => members.push(("limits", limits(l))),
+            ImportKind::Global { value, mutable } => {
+                members.push(("type", Json::from(type_name(value))));
+                members.push(("mutable", Json::from(mutable)));
+            }
+        }
+        Json::object(members)
+    }
+}
+
+impl ToJson for Export<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("name", Json::from(self.name)),
+            ("kind", Json::from(EXTERNAL::name(self.kind))),
+            ("index", Json::from(self.index)),
+        ])
+    }
+}
+
+impl ToJson for Wasm<'_> {
+    fn to_json(&self) -> Json {
+        let sections = self.sections.iter().map(|section| {
+            Json::object([
+                ("id", Json::from(section.id)),
+                ("kind", Json::from(SECTION::name(section.id))),
+                ("name", Json::from(section.name)),
+                ("offset", Json::from(section.offset)),
+                ("size", Json::from(section.data.len())),
+                ("count", Json::from(section.count())),
+            ])
+        });
+        let imports = match self.imports() {
+            Ok(imports) => Json::array(imports.iter().map(Import::to_json)),
+            Err(err) => Json::error(err),
+        };
+        let exports = match self.exports() {
+            Ok(exports) => Json::array(exports.iter().map(Export::to_json)),
+            Err(err) => Json::error(err),
+        };
+        Json::object([
+            ("version", Json::from(self.version)),
+            ("sections", Json::array(sections)),
+            ("imports", imports),
+            ("exports", exports),
+        ])
+    }
+}
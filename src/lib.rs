@@ -1,2 +1,66 @@
+//! Parsers of the ELF, PE and Mach-O headers
+//!
+//! The crate is `no_std` without the default `std` feature, the parsers
+//! only need `alloc` so they can validate images in a bootloader.
+//!
+//! ```
+//! use readbin::prelude::*;
+//!
+//! let data = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+//! match readbin::parse(&data) {
+//!     Ok(Binary::Elf(ElfHeader::Elf64(_))) => {
+//!         let elf = Elf::parse(&data).unwrap();
+//!         assert!(elf.section_by_name(".text").is_some());
+//!     }
+//!     Ok(binary) => println!("{} file", binary.format()),
+//!     Err(err) => println!("{}", err),
+//! }
+//! ```
+//!
+//! # Layout
+//!
+//! - [`parse`] and [`identify`] detect the format of a file, [`Binary`]
+//!   holds its parsed headers.
+//! - [`headers`] has a module per format, [`headers::elf`] being the most
+//!   complete: [`Elf`] gives access to every table of an ELF64 file, each
+//!   table has its own module with its structures and constants.
+//! - [`utils`] has the helpers shared by the parsers: byte order aware
+//!   reads, JSON and YAML values, demangling, hexdumps and text tables.
+//! - [`summary`] describes files of any format in a line, as `readbin
+//!   summary` lists them.
+//! - [`prelude`] re-exports the types most programs start with.
+//!
+//! The `readbin` program in `src/bin/main.rs` only uses this public API,
+//! it formats what the library returns.
+//!
+//! # Stability
+//!
+//! The crate follows semantic versioning, a 0.x release counting as major
+//! for the minor version: the items of [`prelude`] and the paths of the
+//! public modules only change in a 0.(x+1) release. [`Error`] and
+//! [`Format`] are `#[non_exhaustive]`, new errors and formats are added
+//! in patch releases, so match them with a wildcard arm. The text written
+//! by the `Display` implementations follows the tools they imitate and
+//! may change at any time, use the fields or [`utils::json::ToJson`] to
+//! read values programmatically.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod error;
+pub mod format;
 pub mod headers;
+pub mod prelude;
+pub mod summary;
 pub mod utils;
+
+pub use error::{Error, Result};
+pub use format::{identify, parse, Binary, Format};
+pub use headers::elf::Elf;
+
+/// The `alloc` items found in the std prelude, for the no_std builds
+mod alloc_prelude {
+    pub use alloc::string::{String, ToString};
+    pub use alloc::vec::Vec;
+    pub use alloc::{format, vec};
+}
@@ -0,0 +1,9 @@
+//! The types most programs start with, `use readbin::prelude::*;`
+//!
+//! Only stable items are re-exported here, see the stability notes of the
+//! crate documentation.
+pub use crate::error::{Error, Result};
+pub use crate::format::{identify, parse, Binary, Format};
+pub use crate::headers::elf::image::LoadImage;
+pub use crate::headers::elf::{Elf, ElfHeader, Warning};
+pub use crate::utils::json::{Json, ToJson};
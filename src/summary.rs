@@ -0,0 +1,93 @@
+//! One line descriptions of files of any format, like `readbin summary`
+//!
+//! Every format has the format column, the ELF files the class, machine
+//! and type ones, and the ELF64 files the PIE, stripped and build ID ones
+//! too. The walk of the directories is only available with the `std`
+//! feature.
+use crate::alloc_prelude::*;
+use crate::format::{self, Binary};
+use crate::headers::elf::checksec::Pie;
+use crate::headers::elf::machine::Machine;
+use crate::headers::elf::types::Type;
+use crate::headers::elf::{Elf, ElfHeader};
+use crate::Result;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "std")]
+use std::{fs, io};
+
+/// The columns of the summary of a file, None when they don't apply
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Summary {
+    /// short name of the format
+    pub format: &'static str,
+    /// ELF32 or ELF64
+    pub class: Option<&'static str>,
+    /// name of the machine
+    pub machine: Option<&'static str>,
+    /// type of the file, like EXEC or DYN
+    pub kind: Option<&'static str>,
+    /// true for the position independent executables
+    pub pie: Option<bool>,
+    /// true without a static symbol table
+    pub stripped: Option<bool>,
+    /// the NT_GNU_BUILD_ID note in hex
+    pub build_id: Option<String>,
+}
+
+impl Summary {
+    /// Identify a file and read the columns its format has
+    ///
+    /// Fails like [`format::parse`] if the format isn't recognized or its
+    /// headers are invalid.
+    pub fn from_bytes(data: &[u8]) -> Result<Summary> {
+        let binary = format::parse(data)?;
+        let mut summary = Summary {
+            format: binary.format().name(),
+            ..Summary::default()
+        };
+        let (e_type, e_machine) = match &binary {
+            Binary::Elf(ElfHeader::Elf32(header)) => (header.e_type, header.e_machine),
+            Binary::Elf(ElfHeader::Elf64(header)) => (header.e_type, header.e_machine),
+            _ => return Ok(summary),
+        };
+        let is_64 = matches!(binary, Binary::Elf(ElfHeader::Elf64(_)));
+        summary.class = Some(if is_64 { "ELF64" } else { "ELF32" });
+        summary.machine = Some(Machine::from_u16(e_machine).as_str());
+        summary.kind = Some(Type::from(e_type).as_str());
+        if let Some(elf) = Elf::parse(data).ok().filter(|_| is_64) {
+            if let Ok(checksec) = elf.checksec() {
+                summary.pie = match checksec.pie {
+                    Pie::Pie => Some(true),
+                    Pie::No => Some(false),
+                    _ => None,
+                };
+                summary.stripped = Some(checksec.stripped);
+            }
+            summary.build_id = elf
+                .build_id()
+                .map(|id| id.iter().map(|byte| format!("{:02x}", byte)).collect());
+        }
+        Ok(summary)
+    }
+}
+
+/// Add the regular files under `path` in name order
+///
+/// The symbolic links to directories aren't followed, so a loop of links
+/// can't walk forever.
+#[cfg(feature = "std")]
+pub fn walk(path: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    for entry in entries {
+        if fs::symlink_metadata(&entry)?.is_dir() {
+            walk(&entry, files)?;
+        } else if fs::metadata(&entry).is_ok_and(|metadata| metadata.is_file()) {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
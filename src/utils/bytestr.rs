@@ -0,0 +1,132 @@
+//! Names that are bytes first and text when they can be
+//!
+//! Nothing in ELF requires the section and symbol names to be UTF-8, they
+//! are whatever bytes the toolchain wrote before the nul terminator.
+//! [`ByteStr`] keeps those bytes, compares and orders them as bytes, and
+//! only turns them into text to be printed, replacing the invalid
+//! sequences with U+FFFD like [`String::from_utf8_lossy`].
+use crate::alloc_prelude::*;
+use alloc::borrow::Cow;
+use core::fmt::{self, Write};
+
+/// A borrowed name, not necessarily valid UTF-8
+///
+/// ```
+/// use readbin::utils::bytestr::ByteStr;
+///
+/// let name = ByteStr::new(b"caf\xe9");
+/// assert_eq!(name.to_str(), None);
+/// assert_eq!(name.to_string(), "caf\u{fffd}");
+/// assert_eq!(name, &b"caf\xe9"[..]);
+/// assert_ne!(name, "caf\u{fffd}");
+/// assert_eq!(ByteStr::new(b".text"), ".text");
+/// ```
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteStr<'a>(&'a [u8]);
+
+impl<'a> ByteStr<'a> {
+    /// Wrap the bytes of a name, without its nul terminator
+    pub const fn new(bytes: &'a [u8]) -> Self {
+        ByteStr(bytes)
+    }
+
+    /// The bytes of the name
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+
+    /// The name if it is valid UTF-8
+    pub fn to_str(&self) -> Option<&'a str> {
+        core::str::from_utf8(self.0).ok()
+    }
+
+    /// The name as text, borrowed when it is valid UTF-8
+    pub fn to_str_lossy(&self) -> Cow<'a, str> {
+        String::from_utf8_lossy(self.0)
+    }
+
+    /// Length of the name in bytes
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether the name is empty
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<'a> From<&'a str> for ByteStr<'a> {
+    fn from(name: &'a str) -> Self {
+        ByteStr(name.as_bytes())
+    }
+}
+
+impl<'a> From<&'a [u8]> for ByteStr<'a> {
+    fn from(bytes: &'a [u8]) -> Self {
+        ByteStr(bytes)
+    }
+}
+
+impl PartialEq<str> for ByteStr<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other.as_bytes()
+    }
+}
+
+impl PartialEq<&str> for ByteStr<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == other.as_bytes()
+    }
+}
+
+impl PartialEq<[u8]> for ByteStr<'_> {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&[u8]> for ByteStr<'_> {
+    fn eq(&self, other: &&[u8]) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<ByteStr<'_>> for str {
+    fn eq(&self, other: &ByteStr<'_>) -> bool {
+        self.as_bytes() == other.0
+    }
+}
+
+impl PartialEq<ByteStr<'_>> for &str {
+    fn eq(&self, other: &ByteStr<'_>) -> bool {
+        self.as_bytes() == other.0
+    }
+}
+
+/// The name with the invalid sequences replaced by U+FFFD, padded like a
+/// `str` when a width is given
+impl fmt::Display for ByteStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = self.to_str() {
+            return f.pad(name);
+        }
+        if f.width().is_some() || f.precision().is_some() {
+            return f.pad(&self.to_str_lossy());
+        }
+        for chunk in self.0.utf8_chunks() {
+            f.write_str(chunk.valid())?;
+            if !chunk.invalid().is_empty() {
+                f.write_char(char::REPLACEMENT_CHARACTER)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The name quoted, with the bytes that aren't printable ASCII escaped
+impl fmt::Debug for ByteStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{}\"", self.0.escape_ascii())
+    }
+}
@@ -0,0 +1,1781 @@
+//! Itanium C++ ABI demangler, used by GCC and Clang on every ELF platform
+//!
+//! The mangled name is parsed into a tree which is then printed the way
+//! `c++filt` does, substitutions and template parameters are resolved
+//! while parsing. Names using an unsupported construct are rejected and
+//! left mangled.
+//! Sources:
+//! * <https://itanium-cxx-abi.github.io/cxx-abi/abi.html#mangling>
+//! * libiberty's cp-demangle.c for the output format
+use crate::alloc_prelude::*;
+use alloc::rc::Rc;
+
+type P = Rc<Node>;
+
+/// Operators of `<operator-name>`: code, printed name and number of operands
+const OPERATORS: &[(&str, &str, u8)] = &[
+    ("aN", "&=", 2),
+    ("aS", "=", 2),
+    ("aa", "&&", 2),
+    ("ad", "&", 1),
+    ("an", "&", 2),
+    ("at", "alignof ", 1),
+    ("aw", "co_await ", 1),
+    ("az", "alignof ", 1),
+    ("cc", "const_cast", 2),
+    ("cl", "()", 2),
+    ("cm", ",", 2),
+    ("co", "~", 1),
+    ("dV", "/=", 2),
+    ("da", "delete[] ", 1),
+    ("dc", "dynamic_cast", 2),
+    ("de", "*", 1),
+    ("dl", "delete ", 1),
+    ("ds", ".*", 2),
+    ("dt", ".", 2),
+    ("dv", "/", 2),
+    ("eO", "^=", 2),
+    ("eo", "^", 2),
+    ("eq", "==", 2),
+    ("ge", ">=", 2),
+    ("gs", "::", 1),
+    ("gt", ">", 2),
+    ("ix", "[]", 2),
+    ("lS", "<<=", 2),
+    ("le", "<=", 2),
+    ("ls", "<<", 2),
+    ("lt", "<", 2),
+    ("mI", "-=", 2),
+    ("mL", "*=", 2),
+    ("mi", "-", 2),
+    ("ml", "*", 2),
+    ("mm", "--", 1),
+    ("na", "new[]", 3),
+    ("ne", "!=", 2),
+    ("ng", "-", 1),
+    ("nt", "!", 1),
+    ("nw", "new", 3),
+    ("nx", "noexcept", 1),
+    ("oR", "|=", 2),
+    ("oo", "||", 2),
+    ("or", "|", 2),
+    ("pL", "+=", 2),
+    ("pl", "+", 2),
+    ("pm", "->*", 2),
+    ("pp", "++", 1),
+    ("ps", "+", 1),
+    ("pt", "->", 2),
+    ("qu", "?", 3),
+    ("rM", "%=", 2),
+    ("rS", ">>=", 2),
+    ("rc", "reinterpret_cast", 2),
+    ("rm", "%", 2),
+    ("rs", ">>", 2),
+    ("sc", "static_cast", 2),
+    ("ss", "<=>", 2),
+    ("st", "sizeof ", 1),
+    ("sz", "sizeof ", 1),
+    ("tw", "throw ", 1),
+];
+
+/// Builtin types of a single letter
+fn builtin(code: u8) -> Option<&'static str> {
+    Some(match code {
+        b'a' => "signed char",
+        b'b' => "bool",
+        b'c' => "char",
+        b'd' => "double",
+        b'e' => "long double",
+        b'f' => "float",
+        b'g' => "__float128",
+        b'h' => "unsigned char",
+        b'i' => "int",
+        b'j' => "unsigned int",
+        b'l' => "long",
+        b'm' => "unsigned long",
+        b'n' => "__int128",
+        b'o' => "unsigned __int128",
+        b's' => "short",
+        b't' => "unsigned short",
+        b'v' => "void",
+        b'w' => "wchar_t",
+        b'x' => "long long",
+        b'y' => "unsigned long long",
+        b'z' => "...",
+        _ => return None,
+    })
+}
+
+/// Suffix printed after the integer literals of a builtin type, None to print a cast
+fn literal_suffix(ty: &str) -> Option<&'static str> {
+    Some(match ty {
+        "int" => "",
+        "unsigned int" => "u",
+        "long" => "l",
+        "unsigned long" => "ul",
+        "long long" => "ll",
+        "unsigned long long" => "ull",
+        _ => return None,
+    })
+}
+
+#[derive(Debug)]
+enum Node {
+    /// text printed as is: identifiers, builtin types, `std`
+    Name(String),
+    /// `scope::name`
+    Qual(P, P),
+    /// `name<args>`
+    Template(P, Vec<P>),
+    /// `operator+`, the name keeps its trailing space for the expressions
+    Operator(&'static str),
+    /// `operator int`
+    Conversion(P),
+    /// `operator"" _suffix`
+    LiteralOperator(String),
+    /// `name[abi:tag]`
+    AbiTag(P, String),
+    /// `function::entity`, the function has no return type
+    Local(P, P),
+    /// `{default arg#N}::entity`
+    DefaultArg(u64, P),
+    /// a function: name, return type, parameters and qualifiers of `this`
+    Typed(P, Option<P>, Vec<P>, String),
+    /// `vtable for X` and the other special names
+    Special(String, P),
+    /// `construction vtable for base-in-derived`
+    CtorVtable(P, P),
+    /// `encoding [clone .suffix]`
+    Clone(P, String),
+    /// a template parameter, its index and its argument
+    TemplateParam(usize, P),
+    /// `type const`, qualifiers in print order
+    Qualified(P, String),
+    Pointer(P),
+    LRef(P),
+    RRef(P),
+    /// function type: return type, parameters and qualifiers
+    Function(P, Vec<P>, String),
+    Array(P, String),
+    /// pointer to member: class and member type
+    PtrMem(P, P),
+    Vector(P, String),
+    VendorQualified(P, String),
+    /// template argument pack, printed as its comma separated elements
+    Pack(Vec<P>),
+    /// `Dp`, a pack expanded in a parameter list
+    PackExpansion(P),
+    Decltype(P),
+    Lambda(Vec<P>, u64),
+    UnnamedType(u64),
+    // expressions
+    Literal(P, String, bool),
+    FunctionParam(u64),
+    Unary(&'static str, P),
+    Binary(&'static str, P, P),
+    Call(P, Vec<P>),
+    Cast(P, Vec<P>),
+    SizeofType(P),
+}
+
+impl Node {
+    fn is_ctor_dtor_or_conversion(&self) -> bool {
+        match self {
+            Node::Qual(_, name) | Node::Local(_, name) => name.is_ctor_dtor_or_conversion(),
+            Node::Conversion(_) => true,
+            Node::Name(name) => name.starts_with('~') || name.ends_with('\u{0}'),
+            _ => false,
+        }
+    }
+
+    /// Template arguments of the entity, used by the template parameters
+    fn template_args(&self) -> Option<&[P]> {
+        match self {
+            Node::Template(_, args) => Some(args),
+            Node::Local(_, name) => name.template_args(),
+            _ => None,
+        }
+    }
+}
+
+/// Parser state, the name is parsed in one pass
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+    substitutions: Vec<P>,
+    template_args: Vec<P>,
+    /// last source name, used by the constructors and destructors
+    last_name: String,
+    /// nested types and expressions are refused past this depth
+    depth: u32,
+    /// parse `sr` with the old scheme
+    old_unresolved: bool,
+    /// set when an `sr` could have used the old scheme
+    ambiguous_unresolved: bool,
+    /// set in the parameters of a lambda, where the template parameters are `auto`
+    lambda_params: bool,
+}
+
+/// Marker appended to constructor names so they are recognized, stripped when printing
+const CTOR: char = '\u{0}';
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> u8 {
+        self.input.get(self.pos).copied().unwrap_or(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> u8 {
+        self.input.get(self.pos + offset).copied().unwrap_or(0)
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = *self.input.get(self.pos)?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        if self.peek() == byte {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        self.eat(byte).then_some(())
+    }
+
+    fn starts_with(&self, prefix: &[u8]) -> bool {
+        self.input[self.pos..].starts_with(prefix)
+    }
+
+    /// `[n] <digits>`, the text of the number
+    fn number_text(&mut self) -> Option<(bool, &'a str)> {
+        let negative = self.eat(b'n');
+        let start = self.pos;
+        while self.peek().is_ascii_digit() {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return None;
+        }
+        let digits = core::str::from_utf8(&self.input[start..self.pos]).ok()?;
+        Some((negative, digits))
+    }
+
+    fn number(&mut self) -> Option<u64> {
+        let (negative, digits) = self.number_text()?;
+        if negative {
+            return None;
+        }
+        digits.parse().ok()
+    }
+
+    /// `_` for 0 or `<number> _` for number + 1
+    fn compact_number(&mut self) -> Option<u64> {
+        if self.eat(b'_') {
+            return Some(0);
+        }
+        let number = self.number()?;
+        self.expect(b'_')?;
+        Some(number + 1)
+    }
+
+    /// `<seq-id> _`, base 36 with upper case letters, `_` alone is 0
+    fn seq_id(&mut self) -> Option<usize> {
+        if self.eat(b'_') {
+            return Some(0);
+        }
+        let mut value = 0usize;
+        loop {
+            let digit = match self.next()? {
+                digit @ b'0'..=b'9' => digit - b'0',
+                digit @ b'A'..=b'Z' => digit - b'A' + 10,
+                b'_' => return value.checked_add(1),
+                _ => return None,
+            };
+            value = value.checked_mul(36)?.checked_add(digit as usize)?;
+        }
+    }
+
+    fn add_substitution(&mut self, node: &P) {
+        self.substitutions.push(node.clone());
+    }
+
+    fn source_name(&mut self) -> Option<P> {
+        let length = self.number()? as usize;
+        let end = self.pos.checked_add(length)?;
+        let name = core::str::from_utf8(self.input.get(self.pos..end)?).ok()?;
+        self.pos = end;
+        let name = if name.len() >= 10
+            && name.starts_with("_GLOBAL_")
+            && matches!(name.as_bytes()[8], b'.' | b'_' | b'$')
+            && name.as_bytes()[9] == b'N'
+        {
+            "(anonymous namespace)"
+        } else {
+            name
+        };
+        self.last_name = name.to_string();
+        Some(Rc::new(Node::Name(name.to_string())))
+    }
+
+    /// `<discriminator> ::= _ <digit> | __ <number> _`, not printed
+    ///
+    /// Like libiberty, the digits are optional.
+    fn discriminator(&mut self) -> Option<()> {
+        if !self.eat(b'_') {
+            return Some(());
+        }
+        let underscores = self.eat(b'_');
+        let number = if self.peek().is_ascii_digit() {
+            self.number()?
+        } else {
+            0
+        };
+        if underscores && number >= 10 {
+            self.expect(b'_')?;
+        }
+        Some(())
+    }
+
+    fn encoding(&mut self) -> Option<P> {
+        if matches!(self.peek(), b'G' | b'T') {
+            return self.special_name();
+        }
+        let saved = core::mem::take(&mut self.template_args);
+        let (name, quals) = self.name_quals()?;
+        if let Some(args) = name.template_args() {
+            self.template_args = args.to_vec();
+        }
+        let node = if matches!(self.peek(), 0 | b'E' | b'.') {
+            name
+        } else {
+            let has_return = name.template_args().is_some() && !self.template_is_ctor(&name);
+            let mut types = self.parameters()?;
+            let ret = if has_return {
+                if types.is_empty() {
+                    return None;
+                }
+                Some(types.remove(0))
+            } else {
+                None
+            };
+            Rc::new(Node::Typed(name, ret, types, quals))
+        };
+        self.template_args = saved;
+        Some(node)
+    }
+
+    fn template_is_ctor(&self, name: &P) -> bool {
+        match &**name {
+            Node::Template(name, _) => name.is_ctor_dtor_or_conversion(),
+            Node::Local(_, name) => self.template_is_ctor(name),
+            _ => false,
+        }
+    }
+
+    /// The types of a bare function type, an only `void` gives no parameters
+    fn parameters(&mut self) -> Option<Vec<P>> {
+        let mut types = Vec::new();
+        loop {
+            match self.peek() {
+                0 | b'E' | b'.' => break,
+                b'R' | b'O' if self.peek_at(1) == b'E' => break,
+                _ => types.push(self.ty()?),
+            }
+        }
+        Some(types)
+    }
+
+    fn call_offset(&mut self) -> Option<()> {
+        match self.next()? {
+            b'h' => {
+                self.number_text()?;
+            }
+            b'v' => {
+                self.number_text()?;
+                self.expect(b'_')?;
+                self.number_text()?;
+            }
+            _ => return None,
+        }
+        self.expect(b'_')
+    }
+
+    fn special_name(&mut self) -> Option<P> {
+        let special =
+            |prefix: &str, node: P| Some(Rc::new(Node::Special(prefix.to_string(), node)));
+        match (self.next()?, self.next()?) {
+            (b'T', b'V') => special("vtable for ", self.ty()?),
+            (b'T', b'T') => special("VTT for ", self.ty()?),
+            (b'T', b'I') => special("typeinfo for ", self.ty()?),
+            (b'T', b'S') => special("typeinfo name for ", self.ty()?),
+            (b'T', b'F') => special("typeinfo fn for ", self.ty()?),
+            (b'T', b'H') => special("TLS init function for ", self.name()?),
+            (b'T', b'W') => special("TLS wrapper function for ", self.name()?),
+            (b'T', b'A') => special("template parameter object for ", self.template_arg()?),
+            (b'T', kind @ (b'h' | b'v')) => {
+                self.pos -= 1;
+                self.call_offset()?;
+                let prefix = if kind == b'h' {
+                    "non-virtual thunk to "
+                } else {
+                    "virtual thunk to "
+                };
+                special(prefix, self.encoding()?)
+            }
+            (b'T', b'c') => {
+                self.call_offset()?;
+                self.call_offset()?;
+                special("covariant return thunk to ", self.encoding()?)
+            }
+            (b'T', b'C') => {
+                let derived = self.ty()?;
+                self.number()?;
+                self.expect(b'_')?;
+                let base = self.ty()?;
+                Some(Rc::new(Node::CtorVtable(base, derived)))
+            }
+            (b'G', b'V') => special("guard variable for ", self.name()?),
+            (b'G', b'R') => {
+                let name = self.name()?;
+                let number = if self.peek().is_ascii_digit() {
+                    self.number()?
+                } else {
+                    0
+                };
+                special(&format!("reference temporary #{} for ", number), name)
+            }
+            (b'G', b'A') => special("hidden alias for ", self.encoding()?),
+            (b'G', b'T') => match self.next()? {
+                b't' => special("transaction clone for ", self.encoding()?),
+                b'n' => special("non-transaction clone for ", self.encoding()?),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn name(&mut self) -> Option<P> {
+        self.name_quals().map(|(name, _)| name)
+    }
+
+    /// A name and the qualifiers of `this` given by a nested name
+    fn name_quals(&mut self) -> Option<(P, String)> {
+        match self.peek() {
+            b'N' => self.nested_name(),
+            b'Z' => self.local_name(),
+            b'S' if self.peek_at(1) != b't' => {
+                let name = self.substitution(false)?;
+                if self.peek() == b'I' {
+                    let args = self.template_args()?;
+                    return Some((Rc::new(Node::Template(name, args)), String::new()));
+                }
+                Some((name, String::new()))
+            }
+            _ => {
+                let mut name = if self.starts_with(b"St") {
+                    self.pos += 2;
+                    let std = Rc::new(Node::Name("std".to_string()));
+                    Rc::new(Node::Qual(std, self.unqualified_name()?))
+                } else {
+                    self.unqualified_name()?
+                };
+                if self.peek() == b'I' {
+                    self.add_substitution(&name);
+                    let args = self.template_args()?;
+                    name = Rc::new(Node::Template(name, args));
+                }
+                Some((name, String::new()))
+            }
+        }
+    }
+
+    /// `r`, `V` and `K`, printed in the reverse order
+    fn cv_qualifiers(&mut self) -> String {
+        let mut quals = Vec::new();
+        loop {
+            match self.peek() {
+                b'r' => quals.push(" restrict"),
+                b'V' => quals.push(" volatile"),
+                b'K' => quals.push(" const"),
+                _ => break,
+            }
+            self.pos += 1;
+        }
+        quals.iter().rev().copied().collect()
+    }
+
+    fn ref_qualifier(&mut self) -> &'static str {
+        if self.eat(b'R') {
+            " &"
+        } else if self.eat(b'O') {
+            " &&"
+        } else {
+            ""
+        }
+    }
+
+    fn nested_name(&mut self) -> Option<(P, String)> {
+        self.expect(b'N')?;
+        let mut quals = self.cv_qualifiers();
+        quals.push_str(self.ref_qualifier());
+        let name = self.prefix(true)?;
+        self.expect(b'E')?;
+        Some((name, quals))
+    }
+
+    /// The components of a nested name, up to the `E` ending it
+    fn prefix(&mut self, substitutable: bool) -> Option<P> {
+        let mut name: Option<P> = None;
+        loop {
+            let peek = self.peek();
+            if peek == b'E' {
+                return name;
+            }
+            let template = peek == b'I';
+            let component = match peek {
+                0 => return None,
+                b'I' => {
+                    let args = self.template_args()?;
+                    Rc::new(Node::Template(name.clone()?, args))
+                }
+                b'T' => self.template_param()?,
+                b'S' => self.substitution(true)?,
+                b'D' if matches!(self.peek_at(1), b'T' | b't') => self.ty()?,
+                b'M' => {
+                    // initializer scope of a lambda, already a substitution
+                    self.pos += 1;
+                    continue;
+                }
+                _ => self.unqualified_name()?,
+            };
+            let combined = match name {
+                Some(scope) if !template => Rc::new(Node::Qual(scope, component)),
+                _ => component,
+            };
+            if substitutable && peek != b'S' && self.peek() != b'E' {
+                self.add_substitution(&combined);
+            }
+            name = Some(combined);
+        }
+    }
+
+    /// A local entity and the qualifiers of `this` when it is a method
+    fn local_name(&mut self) -> Option<(P, String)> {
+        self.expect(b'Z')?;
+        let function = self.encoding()?;
+        self.expect(b'E')?;
+        // the return type of the enclosing function would look like the entity's
+        let function = match &*function {
+            Node::Typed(name, Some(_), params, quals) => Rc::new(Node::Typed(
+                name.clone(),
+                None,
+                params.clone(),
+                quals.clone(),
+            )),
+            _ => function,
+        };
+        let mut quals = String::new();
+        let entity = if self.eat(b's') {
+            self.discriminator()?;
+            Rc::new(Node::Name("string literal".to_string()))
+        } else {
+            let default = if self.eat(b'd') {
+                Some(self.compact_number()?)
+            } else {
+                None
+            };
+            let (name, name_quals) = self.name_quals()?;
+            quals = name_quals;
+            if !matches!(&*name, Node::Lambda(..) | Node::UnnamedType(_)) {
+                self.discriminator()?;
+            }
+            match default {
+                Some(number) => Rc::new(Node::DefaultArg(number, name)),
+                None => name,
+            }
+        };
+        Some((Rc::new(Node::Local(function, entity)), quals))
+    }
+
+    fn unqualified_name(&mut self) -> Option<P> {
+        let peek = self.peek();
+        let name = if peek.is_ascii_digit() {
+            self.source_name()?
+        } else if peek.is_ascii_lowercase() {
+            self.operator_name()?
+        } else if peek == b'C' || (peek == b'D' && matches!(self.peek_at(1), b'0'..=b'5')) {
+            self.ctor_dtor_name()?
+        } else if peek == b'L' {
+            self.pos += 1;
+            let name = self.source_name()?;
+            self.discriminator()?;
+            name
+        } else if peek == b'U' {
+            match self.peek_at(1) {
+                b'l' => self.lambda()?,
+                b't' => {
+                    self.pos += 2;
+                    let number = self.compact_number()?;
+                    Rc::new(Node::UnnamedType(number))
+                }
+                _ => return None,
+            }
+        } else if self.starts_with(b"DC") {
+            self.pos += 2;
+            let mut names = Vec::new();
+            while !self.eat(b'E') {
+                match &*self.source_name()? {
+                    Node::Name(name) => names.push(name.clone()),
+                    _ => return None,
+                }
+            }
+            Rc::new(Node::Name(format!("[{}]", names.join(", "))))
+        } else {
+            return None;
+        };
+        self.abi_tags(name)
+    }
+
+    fn abi_tags(&mut self, mut name: P) -> Option<P> {
+        let last_name = self.last_name.clone();
+        while self.eat(b'B') {
+            let tag = match &*self.source_name()? {
+                Node::Name(tag) => tag.clone(),
+                _ => return None,
+            };
+            name = Rc::new(Node::AbiTag(name, tag));
+        }
+        self.last_name = last_name;
+        Some(name)
+    }
+
+    fn lambda(&mut self) -> Option<P> {
+        self.pos += 2;
+        let lambda_params = core::mem::replace(&mut self.lambda_params, true);
+        let params = self.parameters();
+        self.lambda_params = lambda_params;
+        let mut params = params?;
+        self.expect(b'E')?;
+        if params.len() == 1 && matches!(&*params[0], Node::Name(name) if name == "void") {
+            params.clear();
+        }
+        let number = self.compact_number()?;
+        Some(Rc::new(Node::Lambda(params, number)))
+    }
+
+    fn ctor_dtor_name(&mut self) -> Option<P> {
+        let name = self.last_name.clone();
+        match (self.next()?, self.next()?) {
+            (b'C', b'I') => {
+                self.next()?;
+                self.ty()?;
+            }
+            (b'C', b'1'..=b'5') => (),
+            (b'D', _) => return Some(Rc::new(Node::Name(format!("~{}", name)))),
+            _ => return None,
+        }
+        Some(Rc::new(Node::Name(format!("{}{}", name, CTOR))))
+    }
+
+    fn operator_name(&mut self) -> Option<P> {
+        let code = self.input.get(self.pos..self.pos + 2)?;
+        if code == b"cv" {
+            self.pos += 2;
+            return Some(Rc::new(Node::Conversion(self.ty()?)));
+        }
+        if code == b"li" {
+            self.pos += 2;
+            return match &*self.source_name()? {
+                Node::Name(name) => Some(Rc::new(Node::LiteralOperator(name.clone()))),
+                _ => None,
+            };
+        }
+        if code[0] == b'v' && code[1].is_ascii_digit() {
+            self.pos += 2;
+            let name = self.source_name()?;
+            return match &*name {
+                Node::Name(name) => Some(Rc::new(Node::Name(format!("operator {}", name)))),
+                _ => None,
+            };
+        }
+        let (_, name, _) = OPERATORS.iter().find(|(op, _, _)| op.as_bytes() == code)?;
+        self.pos += 2;
+        Some(Rc::new(Node::Operator(name)))
+    }
+
+    fn substitution(&mut self, prefix: bool) -> Option<P> {
+        self.expect(b'S')?;
+        let peek = self.peek();
+        if peek == b'_' || peek.is_ascii_digit() || peek.is_ascii_uppercase() {
+            let index = self.seq_id()?;
+            let node = self.substitutions.get(index)?.clone();
+            return Some(self.rebind(&node).unwrap_or(node));
+        }
+        self.pos += 1;
+        // constructors and destructors use the complete name
+        let verbose = prefix && matches!(self.peek(), b'C' | b'D');
+        let (simple, full, last) = match peek {
+            b't' => ("std", "std", None),
+            b'a' => ("std::allocator", "std::allocator", Some("allocator")),
+            b'b' => (
+                "std::basic_string",
+                "std::basic_string",
+                Some("basic_string"),
+            ),
+            b's' => (
+                "std::string",
+                "std::basic_string<char, std::char_traits<char>, std::allocator<char> >",
+                Some("basic_string"),
+            ),
+            b'i' => (
+                "std::istream",
+                "std::basic_istream<char, std::char_traits<char> >",
+                Some("basic_istream"),
+            ),
+            b'o' => (
+                "std::ostream",
+                "std::basic_ostream<char, std::char_traits<char> >",
+                Some("basic_ostream"),
+            ),
+            b'd' => (
+                "std::iostream",
+                "std::basic_iostream<char, std::char_traits<char> >",
+                Some("basic_iostream"),
+            ),
+            _ => return None,
+        };
+        if let Some(last) = last {
+            self.last_name = last.to_string();
+        }
+        let name = if verbose { full } else { simple };
+        Some(Rc::new(Node::Name(name.to_string())))
+    }
+
+    fn template_param(&mut self) -> Option<P> {
+        self.expect(b'T')?;
+        let index = self.compact_number()? as usize;
+        if self.lambda_params {
+            let auto = Rc::new(Node::Name(format!("auto:{}", index + 1)));
+            return Some(Rc::new(Node::TemplateParam(index, auto)));
+        }
+        let arg = self.template_args.get(index)?.clone();
+        Some(Rc::new(Node::TemplateParam(index, arg)))
+    }
+
+    /// Resolve again the template parameters of a substitution, they refer
+    /// to the arguments of where it is used. None if nothing changed.
+    fn rebind(&self, node: &P) -> Option<P> {
+        let rebind = |node: &P| self.rebind(node);
+        let rebind_all = |nodes: &[P]| -> Option<Vec<P>> {
+            let rebound: Vec<Option<P>> = nodes.iter().map(|node| self.rebind(node)).collect();
+            if rebound.iter().all(Option::is_none) {
+                return None;
+            }
+            Some(
+                rebound
+                    .into_iter()
+                    .zip(nodes)
+                    .map(|(rebound, node)| rebound.unwrap_or_else(|| node.clone()))
+                    .collect(),
+            )
+        };
+        let or = |rebound: Option<P>, node: &P| rebound.unwrap_or_else(|| node.clone());
+        Some(Rc::new(match &**node {
+            Node::TemplateParam(index, arg) => {
+                let new = self.template_args.get(*index)?;
+                if Rc::ptr_eq(new, arg) {
+                    return None;
+                }
+                Node::TemplateParam(*index, new.clone())
+            }
+            Node::Pointer(inner) => Node::Pointer(rebind(inner)?),
+            Node::LRef(inner) => Node::LRef(rebind(inner)?),
+            Node::RRef(inner) => Node::RRef(rebind(inner)?),
+            Node::PackExpansion(inner) => Node::PackExpansion(rebind(inner)?),
+            Node::Qualified(inner, quals) => Node::Qualified(rebind(inner)?, quals.clone()),
+            Node::VendorQualified(inner, qualifier) => {
+                Node::VendorQualified(rebind(inner)?, qualifier.clone())
+            }
+            Node::Array(inner, dimension) => Node::Array(rebind(inner)?, dimension.clone()),
+            Node::Vector(inner, dimension) => Node::Vector(rebind(inner)?, dimension.clone()),
+            Node::Pack(args) => Node::Pack(rebind_all(args)?),
+            Node::Template(name, args) => {
+                let (new_name, new_args) = (rebind(name), rebind_all(args));
+                if new_name.is_none() && new_args.is_none() {
+                    return None;
+                }
+                Node::Template(or(new_name, name), new_args.unwrap_or_else(|| args.clone()))
+            }
+            Node::Qual(scope, name) => Node::Qual(rebind(scope)?, name.clone()),
+            Node::PtrMem(class, member) => {
+                let (new_class, new_member) = (rebind(class), rebind(member));
+                if new_class.is_none() && new_member.is_none() {
+                    return None;
+                }
+                Node::PtrMem(or(new_class, class), or(new_member, member))
+            }
+            Node::Function(ret, params, quals) => {
+                let (new_ret, new_params) = (rebind(ret), rebind_all(params));
+                if new_ret.is_none() && new_params.is_none() {
+                    return None;
+                }
+                Node::Function(
+                    or(new_ret, ret),
+                    new_params.unwrap_or_else(|| params.clone()),
+                    quals.clone(),
+                )
+            }
+            _ => return None,
+        }))
+    }
+
+    fn template_args(&mut self) -> Option<Vec<P>> {
+        self.expect(b'I')?;
+        let last_name = self.last_name.clone();
+        let mut args = Vec::new();
+        while !self.eat(b'E') {
+            args.push(self.template_arg()?);
+        }
+        self.last_name = last_name;
+        Some(args)
+    }
+
+    fn template_arg(&mut self) -> Option<P> {
+        match self.peek() {
+            b'X' => {
+                self.pos += 1;
+                let expression = self.expression()?;
+                self.expect(b'E')?;
+                Some(expression)
+            }
+            b'L' => self.expr_primary(),
+            b'J' => {
+                self.pos += 1;
+                let mut args = Vec::new();
+                while !self.eat(b'E') {
+                    args.push(self.template_arg()?);
+                }
+                Some(Rc::new(Node::Pack(args)))
+            }
+            _ => self.ty(),
+        }
+    }
+
+    fn expr_primary(&mut self) -> Option<P> {
+        self.expect(b'L')?;
+        if self.peek() == b'_' || self.peek() == b'Z' {
+            // external name, `L_Z <encoding> E`
+            self.eat(b'_');
+            self.expect(b'Z')?;
+            let encoding = self.encoding()?;
+            self.expect(b'E')?;
+            return Some(encoding);
+        }
+        let ty = self.ty()?;
+        let negative = self.eat(b'n');
+        let start = self.pos;
+        while self.peek() != b'E' {
+            self.next()?;
+        }
+        let value = core::str::from_utf8(&self.input[start..self.pos]).ok()?;
+        self.pos += 1;
+        Some(Rc::new(Node::Literal(ty, value.to_string(), negative)))
+    }
+
+    fn expression(&mut self) -> Option<P> {
+        self.depth += 1;
+        if self.depth > 64 {
+            return None;
+        }
+        let expression = self.expression_inner();
+        self.depth -= 1;
+        expression
+    }
+
+    fn expression_inner(&mut self) -> Option<P> {
+        let peek = self.peek();
+        if peek == b'L' {
+            return self.expr_primary();
+        }
+        if peek == b'T' {
+            return self.template_param();
+        }
+        if self.starts_with(b"sr") {
+            self.pos += 2;
+            // `sr <prefix> E <name>` is ambiguous with the older
+            // `sr <type> <name>`, which is tried when the first fails
+            let peek = self.peek();
+            let scope = if !self.old_unresolved
+                && (peek.is_ascii_digit()
+                    || peek.is_ascii_lowercase()
+                    || matches!(peek, b'C' | b'U' | b'L'))
+            {
+                self.ambiguous_unresolved = true;
+                let scope = self.prefix(false)?;
+                self.eat(b'E');
+                scope
+            } else {
+                self.ty()?
+            };
+            let name = Rc::new(Node::Qual(scope, self.unqualified_name()?));
+            if self.peek() == b'I' {
+                return Some(Rc::new(Node::Template(name, self.template_args()?)));
+            }
+            return Some(name);
+        }
+        if self.starts_with(b"sp") {
+            self.pos += 2;
+            return Some(Rc::new(Node::PackExpansion(self.expression()?)));
+        }
+        if self.starts_with(b"fp") {
+            self.pos += 2;
+            if self.eat(b'T') {
+                return Some(Rc::new(Node::Name("this".to_string())));
+            }
+            self.cv_qualifiers();
+            let index = self.compact_number()?;
+            return Some(Rc::new(Node::FunctionParam(index + 1)));
+        }
+        if peek.is_ascii_digit() || self.starts_with(b"on") {
+            if peek == b'o' {
+                self.pos += 2;
+            }
+            let name = self.unqualified_name()?;
+            if self.peek() == b'I' {
+                return Some(Rc::new(Node::Template(name, self.template_args()?)));
+            }
+            return Some(name);
+        }
+        if self.starts_with(b"cv") {
+            self.pos += 2;
+            let ty = self.ty()?;
+            let args = if self.eat(b'_') {
+                let mut args = Vec::new();
+                while !self.eat(b'E') {
+                    args.push(self.expression()?);
+                }
+                args
+            } else {
+                vec![self.expression()?]
+            };
+            return Some(Rc::new(Node::Cast(ty, args)));
+        }
+        if self.starts_with(b"st") {
+            self.pos += 2;
+            return Some(Rc::new(Node::SizeofType(self.ty()?)));
+        }
+        let code = self.input.get(self.pos..self.pos + 2)?;
+        let (code, name, arity) = *OPERATORS.iter().find(|(op, _, _)| op.as_bytes() == code)?;
+        self.pos += 2;
+        match arity {
+            1 => {
+                if matches!(code, "pp" | "mm") && !self.eat(b'_') {
+                    return None;
+                }
+                Some(Rc::new(Node::Unary(name, self.expression()?)))
+            }
+            2 => {
+                if matches!(code, "sc" | "dc" | "cc" | "rc") {
+                    let ty = self.ty()?;
+                    let operand = self.expression()?;
+                    return Some(Rc::new(Node::Binary(name, ty, operand)));
+                }
+                let left = self.expression()?;
+                if code == "cl" {
+                    let mut args = Vec::new();
+                    while !self.eat(b'E') {
+                        args.push(self.expression()?);
+                    }
+                    return Some(Rc::new(Node::Call(left, args)));
+                }
+                let right = if matches!(code, "dt" | "pt")
+                    && !self.starts_with(b"gs")
+                    && !self.starts_with(b"sr")
+                {
+                    let mut name = self.unqualified_name()?;
+                    if self.peek() == b'I' {
+                        name = Rc::new(Node::Template(name, self.template_args()?));
+                    }
+                    name
+                } else {
+                    self.expression()?
+                };
+                Some(Rc::new(Node::Binary(name, left, right)))
+            }
+            _ => None,
+        }
+    }
+
+    fn ty(&mut self) -> Option<P> {
+        self.depth += 1;
+        if self.depth > 256 {
+            return None;
+        }
+        let ty = self.ty_inner();
+        self.depth -= 1;
+        ty
+    }
+
+    fn ty_inner(&mut self) -> Option<P> {
+        let peek = self.peek();
+        if matches!(peek, b'r' | b'V' | b'K') {
+            let quals = self.cv_qualifiers();
+            // qualifiers before a function type apply to `this`
+            let node = if self.peek() == b'F' {
+                match &*self.function_type()? {
+                    Node::Function(ret, params, ref_quals) => Rc::new(Node::Function(
+                        ret.clone(),
+                        params.clone(),
+                        format!("{}{}", quals, ref_quals),
+                    )),
+                    _ => return None,
+                }
+            } else {
+                Rc::new(Node::Qualified(self.ty()?, quals))
+            };
+            self.add_substitution(&node);
+            return Some(node);
+        }
+        if let Some(name) = builtin(peek) {
+            self.pos += 1;
+            return Some(Rc::new(Node::Name(name.to_string())));
+        }
+        let node = match peek {
+            b'u' => {
+                self.pos += 1;
+                self.source_name()?
+            }
+            b'F' => self.function_type()?,
+            b'0'..=b'9' | b'N' | b'Z' => self.name()?,
+            b'A' => self.array_type()?,
+            b'M' => {
+                self.pos += 1;
+                let class = self.ty()?;
+                let member = self.ty()?;
+                Rc::new(Node::PtrMem(class, member))
+            }
+            b'T' => {
+                let param = self.template_param()?;
+                if self.peek() == b'I' {
+                    self.add_substitution(&param);
+                    let args = self.template_args()?;
+                    Rc::new(Node::Template(param, args))
+                } else {
+                    param
+                }
+            }
+            b'P' => {
+                self.pos += 1;
+                Rc::new(Node::Pointer(self.ty()?))
+            }
+            b'R' | b'O' => {
+                self.pos += 1;
+                let inner = self.ty()?;
+                if peek == b'R' {
+                    Rc::new(Node::LRef(inner))
+                } else {
+                    Rc::new(Node::RRef(inner))
+                }
+            }
+            b'C' => {
+                self.pos += 1;
+                Rc::new(Node::VendorQualified(self.ty()?, "_Complex".to_string()))
+            }
+            b'G' => {
+                self.pos += 1;
+                Rc::new(Node::VendorQualified(self.ty()?, "_Imaginary".to_string()))
+            }
+            b'U' => {
+                self.pos += 1;
+                let qualifier = match &*self.source_name()? {
+                    Node::Name(name) => name.clone(),
+                    _ => return None,
+                };
+                if self.peek() == b'I' {
+                    self.template_args()?;
+                }
+                Rc::new(Node::VendorQualified(self.ty()?, qualifier))
+            }
+            b'S' => {
+                let next = self.peek_at(1);
+                if next.is_ascii_digit() || next == b'_' || next.is_ascii_uppercase() {
+                    let node = self.substitution(false)?;
+                    if self.peek() != b'I' {
+                        return Some(node);
+                    }
+                    let args = self.template_args()?;
+                    Rc::new(Node::Template(node, args))
+                } else {
+                    let std = !self.starts_with(b"St");
+                    let node = self.name()?;
+                    // a complete special substitution is not a new candidate
+                    if std && matches!(&*node, Node::Name(_)) {
+                        return Some(node);
+                    }
+                    node
+                }
+            }
+            b'D' if matches!(self.peek_at(1), b'o' | b'O' | b'w') => {
+                self.pos += 1;
+                let spec = match self.next()? {
+                    b'o' => " noexcept".to_string(),
+                    b'O' => {
+                        let mut out = String::new();
+                        Printer::default().expression(&self.expression()?, &mut out);
+                        self.expect(b'E')?;
+                        format!(" noexcept({})", out)
+                    }
+                    _ => {
+                        let mut types = Vec::new();
+                        while !self.eat(b'E') {
+                            types.push(self.ty()?);
+                        }
+                        let mut out = String::new();
+                        Printer::default().list(&types, &mut out);
+                        format!(" throw({})", out)
+                    }
+                };
+                match &*self.function_type()? {
+                    Node::Function(ret, params, quals) => Rc::new(Node::Function(
+                        ret.clone(),
+                        params.clone(),
+                        format!("{}{}", quals, spec),
+                    )),
+                    _ => return None,
+                }
+            }
+            b'D' => {
+                self.pos += 1;
+                let node = match self.next()? {
+                    b'T' | b't' => {
+                        let expression = self.expression()?;
+                        self.expect(b'E')?;
+                        Rc::new(Node::Decltype(expression))
+                    }
+                    b'p' => Rc::new(Node::PackExpansion(self.ty()?)),
+                    b'v' => {
+                        let dimension = match self.number() {
+                            Some(number) => number.to_string(),
+                            None => return None,
+                        };
+                        self.expect(b'_')?;
+                        Rc::new(Node::Vector(self.ty()?, dimension))
+                    }
+                    code => {
+                        let name = match code {
+                            b'a' => "auto",
+                            b'c' => "decltype(auto)",
+                            b'd' => "decimal64",
+                            b'e' => "decimal128",
+                            b'f' => "decimal32",
+                            b'h' => "half",
+                            b'i' => "char32_t",
+                            b's' => "char16_t",
+                            b'u' => "char8_t",
+                            b'n' => "decltype(nullptr)",
+                            b'F' => {
+                                let bits = self.number()?;
+                                let suffix = if self.eat(b'x') { "x" } else { "" };
+                                self.expect(b'_')?;
+                                return Some(Rc::new(Node::Name(format!(
+                                    "_Float{}{}",
+                                    bits, suffix
+                                ))));
+                            }
+                            _ => return None,
+                        };
+                        return Some(Rc::new(Node::Name(name.to_string())));
+                    }
+                };
+                node
+            }
+            _ => return None,
+        };
+        self.add_substitution(&node);
+        Some(node)
+    }
+
+    fn function_type(&mut self) -> Option<P> {
+        self.expect(b'F')?;
+        self.eat(b'Y');
+        let ret = self.ty()?;
+        let params = self.parameters()?;
+        let quals = self.ref_qualifier().to_string();
+        self.expect(b'E')?;
+        Some(Rc::new(Node::Function(ret, params, quals)))
+    }
+
+    fn array_type(&mut self) -> Option<P> {
+        self.expect(b'A')?;
+        let dimension = if self.peek() == b'_' {
+            String::new()
+        } else if self.peek().is_ascii_digit() {
+            self.number()?.to_string()
+        } else {
+            let mut out = String::new();
+            Printer::default().expression(&self.expression()?, &mut out);
+            out
+        };
+        self.expect(b'_')?;
+        Some(Rc::new(Node::Array(self.ty()?, dimension)))
+    }
+}
+
+/// Printer of the parsed tree
+#[derive(Default)]
+struct Printer {
+    /// element printed for the packs of a `Dp` expansion
+    pack_index: Option<usize>,
+}
+
+fn push_template_args(printer: &mut Printer, args: &[P], out: &mut String) {
+    if out.ends_with('<') {
+        out.push(' ');
+    }
+    out.push('<');
+    // like libiberty, no space is added after an elided empty pack
+    let elided = printer.list(args, out);
+    if out.ends_with('>') && !elided {
+        out.push(' ');
+    }
+    out.push('>');
+}
+
+/// A modifier applied to a type, printed after it or inside the parentheses
+/// of a function or array type
+enum Modifier<'a> {
+    Text(&'a str),
+    /// cv-qualifiers, printed with the element of an array
+    Cv(&'a str),
+    PtrMem(&'a P),
+}
+
+impl Printer {
+    /// Print comma separated nodes, true if a trailing separator was removed
+    ///
+    /// Empty packs print nothing, the separators before the ones ending
+    /// the list are removed but not the other ones, like libiberty does.
+    fn list(&mut self, nodes: &[P], out: &mut String) -> bool {
+        let mut trailing = None;
+        for (index, node) in nodes.iter().enumerate() {
+            let start = out.len();
+            if index > 0 {
+                out.push_str(", ");
+            }
+            let before = out.len();
+            self.node(node, out);
+            if out.len() != before {
+                trailing = None;
+            } else if trailing.is_none() && index > 0 {
+                trailing = Some(start);
+            }
+        }
+        match trailing {
+            Some(start) => {
+                out.truncate(start);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn params(&mut self, params: &[P], out: &mut String) {
+        out.push('(');
+        let void = params.len() == 1 && matches!(&*params[0], Node::Name(name) if name == "void");
+        if !void {
+            self.list(params, out);
+        }
+        out.push(')');
+    }
+
+    fn node(&mut self, node: &P, out: &mut String) {
+        match &**node {
+            Node::Name(name) => out.push_str(name.trim_end_matches(CTOR)),
+            Node::Qual(scope, name) => {
+                self.node(scope, out);
+                out.push_str("::");
+                self.node(name, out);
+            }
+            Node::Template(name, args) => {
+                self.node(name, out);
+                push_template_args(self, args, out);
+            }
+            Node::Operator(name) => {
+                out.push_str("operator");
+                if name.as_bytes()[0].is_ascii_lowercase() {
+                    out.push(' ');
+                }
+                out.push_str(name.trim_end());
+            }
+            Node::Conversion(ty) => {
+                out.push_str("operator ");
+                self.node(ty, out);
+            }
+            Node::LiteralOperator(name) => {
+                out.push_str("operator\"\" ");
+                out.push_str(name);
+            }
+            Node::AbiTag(name, tag) => {
+                self.node(name, out);
+                out.push_str("[abi:");
+                out.push_str(tag);
+                out.push(']');
+            }
+            Node::Local(function, entity) => {
+                self.node(function, out);
+                out.push_str("::");
+                self.node(entity, out);
+            }
+            Node::DefaultArg(number, entity) => {
+                out.push_str(&format!("{{default arg#{}}}::", number + 1));
+                self.node(entity, out);
+            }
+            Node::Typed(name, ret, params, quals) => {
+                if let Some(ret) = ret {
+                    self.node(ret, out);
+                    out.push(' ');
+                }
+                self.node(name, out);
+                self.params(params, out);
+                out.push_str(quals);
+            }
+            Node::Special(prefix, node) => {
+                out.push_str(prefix);
+                self.node(node, out);
+            }
+            Node::CtorVtable(base, derived) => {
+                out.push_str("construction vtable for ");
+                self.node(base, out);
+                out.push_str("-in-");
+                self.node(derived, out);
+            }
+            Node::Clone(node, suffix) => {
+                self.node(node, out);
+                out.push_str(" [clone ");
+                out.push_str(suffix);
+                out.push(']');
+            }
+            Node::TemplateParam(_, arg) => match (&**arg, self.pack_index) {
+                (Node::Pack(args), Some(index)) => {
+                    if let Some(arg) = args.get(index) {
+                        let saved = self.pack_index.take();
+                        self.node(arg, out);
+                        self.pack_index = saved;
+                    }
+                }
+                _ => self.node(arg, out),
+            },
+            Node::Qualified(..)
+            | Node::Pointer(_)
+            | Node::LRef(_)
+            | Node::RRef(_)
+            | Node::PtrMem(..)
+            | Node::Function(..)
+            | Node::Array(..)
+            | Node::VendorQualified(..) => self.modified(node, out),
+            Node::Vector(ty, dimension) => {
+                self.node(ty, out);
+                out.push_str(" __vector(");
+                out.push_str(dimension);
+                out.push(')');
+            }
+            Node::Pack(args) => {
+                self.list(args, out);
+            }
+            Node::PackExpansion(ty) => match pack_len(ty) {
+                Some(len) => {
+                    let saved = self.pack_index;
+                    for index in 0..len {
+                        if index > 0 {
+                            out.push_str(", ");
+                        }
+                        self.pack_index = Some(index);
+                        self.node(ty, out);
+                    }
+                    self.pack_index = saved;
+                }
+                None => {
+                    self.node(ty, out);
+                    out.push_str("...");
+                }
+            },
+            Node::Decltype(expression) => {
+                out.push_str("decltype (");
+                self.expression(expression, out);
+                out.push(')');
+            }
+            Node::Lambda(params, number) => {
+                out.push_str("{lambda");
+                self.params(params, out);
+                out.push_str(&format!("#{}}}", number + 1));
+            }
+            Node::UnnamedType(number) => out.push_str(&format!("{{unnamed type#{}}}", number + 1)),
+            Node::Literal(..)
+            | Node::FunctionParam(_)
+            | Node::Unary(..)
+            | Node::Binary(..)
+            | Node::Call(..)
+            | Node::Cast(..)
+            | Node::SizeofType(_) => self.expression(node, out),
+        }
+    }
+
+    /// Print a type with its modifiers, moving them inside parentheses for
+    /// the function and array types: `void (*)(int)`
+    fn modified(&mut self, node: &P, out: &mut String) {
+        let mut modifiers = Vec::new();
+        let mut base = node;
+        loop {
+            match &**base {
+                Node::Pointer(inner) => {
+                    modifiers.push(Modifier::Text("*"));
+                    base = inner;
+                }
+                Node::LRef(inner) | Node::RRef(inner) => {
+                    // a reference to a substituted reference collapses,
+                    // `T&&` is `int&` for `T = int&`
+                    let mut lvalue = matches!(&**base, Node::LRef(_));
+                    let mut inner = inner;
+                    loop {
+                        match &**self.resolve(inner) {
+                            Node::LRef(next) => {
+                                lvalue = true;
+                                inner = next;
+                            }
+                            Node::RRef(next) => inner = next,
+                            _ => break,
+                        }
+                    }
+                    modifiers.push(Modifier::Text(if lvalue { "&" } else { "&&" }));
+                    base = inner;
+                }
+                Node::Qualified(inner, quals) => {
+                    // qualifiers the substituted type already has are printed once
+                    let repeated = matches!(&**self.resolve(inner), Node::Qualified(_, inner) if inner == quals);
+                    if !repeated {
+                        modifiers.push(Modifier::Cv(quals));
+                    }
+                    base = inner;
+                }
+                Node::VendorQualified(inner, qualifier) => {
+                    modifiers.push(Modifier::Text(qualifier));
+                    base = inner;
+                }
+                Node::PtrMem(class, member) => {
+                    modifiers.push(Modifier::PtrMem(class));
+                    base = member;
+                }
+                Node::TemplateParam(..) if !modifiers.is_empty() => {
+                    let resolved = self.resolve(base);
+                    if Rc::ptr_eq(resolved, base) {
+                        break;
+                    }
+                    base = resolved;
+                }
+                _ => break,
+            }
+        }
+        let print_modifiers = |printer: &mut Printer, modifiers: &[Modifier], out: &mut String| {
+            for modifier in modifiers.iter().rev() {
+                match modifier {
+                    Modifier::Cv(text) => out.push_str(text),
+                    Modifier::Text(text) if text.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+                        out.push(' ');
+                        out.push_str(text);
+                    }
+                    Modifier::Text(text) => out.push_str(text),
+                    Modifier::PtrMem(class) => {
+                        if !out.ends_with('(') {
+                            out.push(' ');
+                        }
+                        printer.node(class, out);
+                        out.push_str("::*");
+                    }
+                }
+            }
+        };
+        match &**base {
+            Node::Function(ret, params, quals) => {
+                self.node(ret, out);
+                out.push(' ');
+                if !modifiers.is_empty() {
+                    out.push('(');
+                    print_modifiers(self, &modifiers, out);
+                    out.push(')');
+                }
+                self.params(params, out);
+                out.push_str(quals);
+            }
+            Node::Array(element, dimension) => {
+                // the qualifiers of the array are the ones of its elements
+                let cv = modifiers
+                    .iter()
+                    .rposition(|modifier| !matches!(modifier, Modifier::Cv(_)))
+                    .map_or(0, |last| last + 1);
+                let mut dimensions = vec![dimension];
+                let mut element = element;
+                while let Node::Array(inner, dimension) = &**self.resolve(element) {
+                    dimensions.push(dimension);
+                    element = inner;
+                }
+                self.node(element, out);
+                print_modifiers(self, &modifiers[cv..], out);
+                out.push(' ');
+                if cv > 0 {
+                    out.push('(');
+                    print_modifiers(self, &modifiers[..cv], out);
+                    out.push_str(") ");
+                }
+                for dimension in dimensions {
+                    out.push('[');
+                    out.push_str(dimension);
+                    out.push(']');
+                }
+            }
+            _ => {
+                self.node(base, out);
+                print_modifiers(self, &modifiers, out);
+            }
+        }
+    }
+
+    /// The argument of a template parameter, the current element for a pack
+    fn resolve<'n>(&self, mut node: &'n P) -> &'n P {
+        while let Node::TemplateParam(_, arg) = &**node {
+            node = match (&**arg, self.pack_index) {
+                (Node::Pack(args), Some(index)) => match args.get(index) {
+                    Some(arg) => arg,
+                    None => return node,
+                },
+                (Node::Pack(_), None) => return node,
+                _ => arg,
+            };
+        }
+        node
+    }
+
+    /// Print an operand, in parentheses unless it is a name
+    fn subexpression(&mut self, node: &P, out: &mut String) {
+        let simple = matches!(
+            &**node,
+            Node::Name(_) | Node::Qual(..) | Node::FunctionParam(_)
+        );
+        if !simple {
+            out.push('(');
+        }
+        self.expression(node, out);
+        if !simple {
+            out.push(')');
+        }
+    }
+
+    fn expression(&mut self, node: &P, out: &mut String) {
+        match &**node {
+            Node::Literal(ty, value, negative) => {
+                let suffix = match &**ty {
+                    Node::Name(name) => literal_suffix(name),
+                    _ => None,
+                };
+                match (suffix, &**ty) {
+                    (Some(suffix), _) => {
+                        if *negative {
+                            out.push('-');
+                        }
+                        out.push_str(value);
+                        out.push_str(suffix);
+                    }
+                    (None, Node::Name(name)) if name == "bool" && !negative && value == "0" => {
+                        out.push_str("false")
+                    }
+                    (None, Node::Name(name)) if name == "bool" && !negative && value == "1" => {
+                        out.push_str("true")
+                    }
+                    _ => {
+                        out.push('(');
+                        self.node(ty, out);
+                        out.push(')');
+                        if *negative {
+                            out.push('-');
+                        }
+                        out.push_str(value);
+                    }
+                }
+            }
+            Node::FunctionParam(index) => out.push_str(&format!("{{parm#{}}}", index)),
+            Node::Unary(name, operand) => {
+                out.push_str(name);
+                // the address of a method is printed without its parameters
+                if let (&"&", Node::Typed(function, _, _, quals)) = (name, &**operand) {
+                    if matches!(&**function, Node::Qual(..)) && quals.is_empty() {
+                        self.subexpression(function, out);
+                        return;
+                    }
+                }
+                if *name == "::" {
+                    self.expression(operand, out);
+                } else if *name == "noexcept" {
+                    out.push('(');
+                    self.expression(operand, out);
+                    out.push(')');
+                } else {
+                    self.subexpression(operand, out);
+                }
+            }
+            Node::SizeofType(ty) => {
+                out.push_str("sizeof (");
+                self.node(ty, out);
+                out.push(')');
+            }
+            Node::Binary(name, left, right) => {
+                if matches!(
+                    *name,
+                    "static_cast" | "dynamic_cast" | "const_cast" | "reinterpret_cast"
+                ) {
+                    out.push_str(name);
+                    out.push('<');
+                    self.node(left, out);
+                    out.push_str(">(");
+                    self.expression(right, out);
+                    out.push(')');
+                    return;
+                }
+                if *name == ">" {
+                    out.push('(');
+                }
+                self.subexpression(left, out);
+                if *name == "[]" {
+                    out.push('[');
+                    self.expression(right, out);
+                    out.push(']');
+                } else {
+                    out.push_str(name);
+                    self.subexpression(right, out);
+                }
+                if *name == ">" {
+                    out.push(')');
+                }
+            }
+            Node::Call(function, args) => {
+                self.subexpression(function, out);
+                out.push('(');
+                let args: Vec<P> = args.clone();
+                for (index, arg) in args.iter().enumerate() {
+                    if index > 0 {
+                        out.push_str(", ");
+                    }
+                    self.expression(arg, out);
+                }
+                out.push(')');
+            }
+            Node::Cast(ty, args) => {
+                if args.len() == 1 {
+                    out.push('(');
+                    self.node(ty, out);
+                    out.push(')');
+                    self.subexpression(&args[0], out);
+                } else {
+                    self.node(ty, out);
+                    out.push('(');
+                    for (index, arg) in args.iter().enumerate() {
+                        if index > 0 {
+                            out.push_str(", ");
+                        }
+                        self.expression(arg, out);
+                    }
+                    out.push(')');
+                }
+            }
+            _ => self.node(node, out),
+        }
+    }
+}
+
+/// Number of elements of the first pack found in a type
+fn pack_len(node: &P) -> Option<usize> {
+    match &**node {
+        Node::TemplateParam(_, arg) => match &**arg {
+            Node::Pack(args) => Some(args.len()),
+            _ => None,
+        },
+        Node::Pointer(inner)
+        | Node::LRef(inner)
+        | Node::RRef(inner)
+        | Node::Qualified(inner, _)
+        | Node::VendorQualified(inner, _)
+        | Node::Decltype(inner)
+        | Node::Vector(inner, _)
+        | Node::Array(inner, _)
+        | Node::Unary(_, inner)
+        | Node::SizeofType(inner) => pack_len(inner),
+        Node::PtrMem(left, right) | Node::Qual(left, right) | Node::Binary(_, left, right) => {
+            pack_len(left).or_else(|| pack_len(right))
+        }
+        Node::Template(name, args) => pack_len(name).or_else(|| args.iter().find_map(pack_len)),
+        Node::Function(ret, params, _) => {
+            pack_len(ret).or_else(|| params.iter().find_map(pack_len))
+        }
+        Node::Call(function, args) | Node::Cast(function, args) => {
+            pack_len(function).or_else(|| args.iter().find_map(pack_len))
+        }
+        _ => None,
+    }
+}
+
+/// Demangle a `_Z` name, None if it isn't a valid Itanium mangled name
+pub fn demangle(name: &str) -> Option<String> {
+    let mangled = name.strip_prefix("_Z")?;
+    match parse(mangled, false) {
+        Err(true) => parse(mangled, true).ok(),
+        result => result.ok(),
+    }
+}
+
+/// Parse and print the name, the error tells if the old `sr` scheme may help
+fn parse(mangled: &str, old_unresolved: bool) -> Result<String, bool> {
+    let mut parser = Parser {
+        input: mangled.as_bytes(),
+        pos: 0,
+        substitutions: Vec::new(),
+        template_args: Vec::new(),
+        last_name: String::new(),
+        depth: 0,
+        old_unresolved,
+        ambiguous_unresolved: false,
+        lambda_params: false,
+    };
+    let ambiguous = |parser: &Parser| parser.ambiguous_unresolved;
+    let Some(mut node) = parser.encoding() else {
+        return Err(ambiguous(&parser));
+    };
+    // compiler generated copies of the function, ex: `.cold` or `.isra.0`
+    while parser.peek() == b'.' {
+        let next = parser.peek_at(1);
+        if !(next.is_ascii_lowercase() || next.is_ascii_digit() || next == b'_') {
+            break;
+        }
+        let start = parser.pos;
+        parser.pos += 2;
+        while matches!(parser.peek(), b'a'..=b'z' | b'0'..=b'9' | b'_') {
+            parser.pos += 1;
+        }
+        while parser.peek() == b'.' && parser.peek_at(1).is_ascii_digit() {
+            parser.pos += 2;
+            while parser.peek().is_ascii_digit() {
+                parser.pos += 1;
+            }
+        }
+        let suffix = mangled[start..parser.pos].to_string();
+        node = Rc::new(Node::Clone(node, suffix));
+    }
+    if parser.pos != parser.input.len() {
+        return Err(ambiguous(&parser));
+    }
+    let mut out = String::new();
+    Printer::default().node(&node, &mut out);
+    Ok(out)
+}
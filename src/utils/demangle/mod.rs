@@ -0,0 +1,34 @@
+//! Demangling of the C++ and Rust symbol names
+//!
+//! Names are printed like `readelf -C` does, a name that can't be
+//! demangled is kept as is.
+use crate::alloc_prelude::*;
+use alloc::borrow::Cow;
+
+mod itanium;
+mod rust;
+
+/// Demangle a symbol name, None if it isn't a mangled name
+///
+/// The Rust legacy names also follow the C++ scheme so they are tried first.
+pub fn try_demangle(name: &str) -> Option<String> {
+    if name.starts_with("_R") {
+        return rust::demangle_v0(name);
+    }
+    rust::demangle_legacy(name).or_else(|| itanium::demangle(name))
+}
+
+/// Demangle a symbol name, returning the name unchanged if it isn't mangled
+///
+/// ```
+/// use readbin::utils::demangle::demangle;
+///
+/// assert_eq!(demangle("_ZNSt6vectorIiSaIiEE9push_backERKi"), "std::vector<int, std::allocator<int> >::push_back(int const&)");
+/// assert_eq!(demangle("main"), "main");
+/// ```
+pub fn demangle(name: &str) -> Cow<'_, str> {
+    match try_demangle(name) {
+        Some(demangled) => Cow::Owned(demangled),
+        None => Cow::Borrowed(name),
+    }
+}
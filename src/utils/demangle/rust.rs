@@ -0,0 +1,703 @@
+//! Rust symbol demangler, for the legacy and the v0 manglings
+//!
+//! The output follows libiberty's one with the hashes and the crate
+//! disambiguators left out, like `readelf -C` does.
+//! Sources:
+//! * <https://doc.rust-lang.org/rustc/symbol-mangling/v0.html>
+//! * libiberty's rust-demangle.c
+
+use crate::alloc_prelude::*;
+
+/// Maximum nesting of paths and types, deeper names are left mangled
+const MAX_DEPTH: u32 = 500;
+
+/// Demangle a legacy `_ZN...17h<hash>E` name, None if it isn't one
+pub fn demangle_legacy(name: &str) -> Option<String> {
+    let body = name.strip_prefix("_ZN")?;
+    if !body
+        .bytes()
+        .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'$' | b'.' | b':'))
+    {
+        return None;
+    }
+    let body = body.strip_suffix('E')?.as_bytes();
+    let mut idents = Vec::new();
+    let mut pos = 0;
+    while pos < body.len() {
+        let start = pos;
+        while body.get(pos).is_some_and(u8::is_ascii_digit) {
+            pos += 1;
+        }
+        let len: usize = core::str::from_utf8(&body[start..pos]).ok()?.parse().ok()?;
+        let end = pos.checked_add(len)?;
+        idents.push(core::str::from_utf8(body.get(pos..end)?).ok()?);
+        pos = end;
+    }
+    // rustc always ends the path with the hash of the symbol
+    if !idents.last().is_some_and(|hash| is_legacy_hash(hash)) {
+        return None;
+    }
+    idents.pop();
+    let mut out = String::new();
+    for (index, ident) in idents.iter().enumerate() {
+        if index > 0 {
+            out.push_str("::");
+        }
+        unescape_legacy(ident, &mut out);
+    }
+    Some(out)
+}
+
+/// `h` and 16 hex digits, with enough distinct digits to not be a word
+fn is_legacy_hash(ident: &str) -> bool {
+    let Some(hash) = ident.strip_prefix('h') else {
+        return false;
+    };
+    if hash.len() != 16
+        || !hash
+            .bytes()
+            .all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+    {
+        return false;
+    }
+    let seen = hash.chars().fold(0u16, |seen, digit| {
+        seen | 1 << digit.to_digit(16).unwrap_or(0)
+    });
+    seen.count_ones() >= 5
+}
+
+fn unescape_legacy(ident: &str, out: &mut String) {
+    // the mangler prefixes an underscore so names never start with a `$`
+    let mut rest = match ident.strip_prefix("_$") {
+        Some(_) => &ident[1..],
+        None => ident,
+    };
+    while !rest.is_empty() {
+        if rest.starts_with('$') {
+            let decoded = rest[1..].find('$').and_then(|end| {
+                let escape = &rest[1..end + 1];
+                let byte = match escape {
+                    "SP" => '@',
+                    "BP" => '*',
+                    "RF" => '&',
+                    "LT" => '<',
+                    "GT" => '>',
+                    "LP" => '(',
+                    "RP" => ')',
+                    "C" => ',',
+                    _ => {
+                        let code = u32::from_str_radix(escape.strip_prefix('u')?, 16).ok()?;
+                        char::from_u32(code).filter(|c| !c.is_control())?
+                    }
+                };
+                Some((byte, end + 2))
+            });
+            match decoded {
+                Some((byte, len)) => {
+                    out.push(byte);
+                    rest = &rest[len..];
+                }
+                None => {
+                    out.push_str(rest);
+                    return;
+                }
+            }
+        } else if let Some(tail) = rest.strip_prefix("..") {
+            out.push_str("::");
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix('.') {
+            out.push('.');
+            rest = tail;
+        } else {
+            let len = rest.find(['$', '.']).unwrap_or(rest.len());
+            out.push_str(&rest[..len]);
+            rest = &rest[len..];
+        }
+    }
+}
+
+/// An identifier, its ASCII part and its punycode encoded part
+#[derive(Clone, Copy)]
+struct Ident<'a> {
+    ascii: &'a str,
+    punycode: &'a str,
+}
+
+impl Ident<'_> {
+    fn is_empty(&self) -> bool {
+        self.ascii.is_empty() && self.punycode.is_empty()
+    }
+}
+
+/// Decode the punycode of an identifier, RFC 3492 with `_` as delimiter
+fn punycode(ident: Ident, out: &mut String) -> Option<()> {
+    const BASE: u32 = 36;
+    const T_MIN: u32 = 1;
+    const T_MAX: u32 = 26;
+    let mut chars: Vec<char> = ident.ascii.chars().collect();
+    let mut input = ident.punycode.bytes().peekable();
+    let (mut n, mut i, mut bias) = (0x80u32, 0u32, 72u32);
+    while input.peek().is_some() {
+        let old_i = i;
+        let mut weight = 1u32;
+        let mut k = BASE;
+        loop {
+            let digit = match input.next()? {
+                byte @ b'a'..=b'z' => byte - b'a',
+                byte @ b'0'..=b'9' => byte - b'0' + 26,
+                _ => return None,
+            } as u32;
+            i = i.checked_add(digit.checked_mul(weight)?)?;
+            let t = k.saturating_sub(bias).clamp(T_MIN, T_MAX);
+            if digit < t {
+                break;
+            }
+            weight = weight.checked_mul(BASE - t)?;
+            k += BASE;
+        }
+        let len = chars.len() as u32 + 1;
+        let mut delta = (i - old_i) / if old_i == 0 { 700 } else { 2 };
+        delta += delta / len;
+        let mut k = 0;
+        while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+            delta /= BASE - T_MIN;
+            k += BASE;
+        }
+        bias = k + ((BASE - T_MIN + 1) * delta) / (delta + 38);
+        n = n.checked_add(i / len)?;
+        i %= len;
+        chars.insert(i as usize, char::from_u32(n)?);
+        i += 1;
+    }
+    out.extend(chars);
+    Some(())
+}
+
+struct Demangler<'a> {
+    sym: &'a [u8],
+    next: usize,
+    out: String,
+    /// set while parsing the parts that aren't printed
+    skipping: bool,
+    bound_lifetime_depth: u64,
+    depth: u32,
+}
+
+impl<'a> Demangler<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.sym.get(self.next).copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        if self.peek() == Some(byte) {
+            self.next += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.next += 1;
+        Some(byte)
+    }
+
+    fn print(&mut self, text: &str) {
+        if !self.skipping {
+            self.out.push_str(text);
+        }
+    }
+
+    fn print_ident(&mut self, ident: Ident) -> Option<()> {
+        if self.skipping {
+            return Some(());
+        }
+        if ident.punycode.is_empty() {
+            self.out.push_str(ident.ascii);
+            Some(())
+        } else {
+            punycode(ident, &mut self.out)
+        }
+    }
+
+    /// `_` for 0 or a base 62 number followed by `_` for number + 1
+    fn integer_62(&mut self) -> Option<u64> {
+        if self.eat(b'_') {
+            return Some(0);
+        }
+        let mut value = 0u64;
+        while !self.eat(b'_') {
+            let digit = match self.next()? {
+                byte @ b'0'..=b'9' => byte - b'0',
+                byte @ b'a'..=b'z' => byte - b'a' + 10,
+                byte @ b'A'..=b'Z' => byte - b'A' + 36,
+                _ => return None,
+            };
+            value = value.checked_mul(62)?.checked_add(digit as u64)?;
+        }
+        value.checked_add(1)
+    }
+
+    fn opt_integer_62(&mut self, tag: u8) -> Option<u64> {
+        if !self.eat(tag) {
+            return Some(0);
+        }
+        self.integer_62()?.checked_add(1)
+    }
+
+    fn disambiguator(&mut self) -> Option<u64> {
+        self.opt_integer_62(b's')
+    }
+
+    fn ident(&mut self) -> Option<Ident<'a>> {
+        let is_punycode = self.eat(b'u');
+        let first = self.next().filter(u8::is_ascii_digit)?;
+        let mut len = (first - b'0') as usize;
+        if first != b'0' {
+            while let Some(digit) = self.peek().filter(u8::is_ascii_digit) {
+                len = len.checked_mul(10)?.checked_add((digit - b'0') as usize)?;
+                self.next += 1;
+            }
+        }
+        self.eat(b'_');
+        let end = self.next.checked_add(len)?;
+        let text = core::str::from_utf8(self.sym.get(self.next..end)?).ok()?;
+        self.next = end;
+        if !is_punycode {
+            return Some(Ident {
+                ascii: text,
+                punycode: "",
+            });
+        }
+        let (ascii, punycode) = match text.rfind('_') {
+            Some(split) => (&text[..split], &text[split + 1..]),
+            None => ("", text),
+        };
+        if punycode.is_empty() {
+            return None;
+        }
+        Some(Ident { ascii, punycode })
+    }
+
+    /// Follow a backreference, parsing the target unless it isn't printed
+    fn backref(&mut self, parse: impl FnOnce(&mut Self) -> Option<()>) -> Option<()> {
+        let start = self.next - 1;
+        let target = self.integer_62()? as usize;
+        if target >= start {
+            return None;
+        }
+        if self.skipping {
+            return Some(());
+        }
+        let saved = core::mem::replace(&mut self.next, target);
+        parse(self)?;
+        self.next = saved;
+        Some(())
+    }
+
+    fn enter(&mut self) -> Option<()> {
+        self.depth += 1;
+        (self.depth <= MAX_DEPTH).then_some(())
+    }
+
+    fn path(&mut self, in_value: bool) -> Option<()> {
+        self.enter()?;
+        match self.next()? {
+            b'C' => {
+                self.disambiguator()?;
+                let name = self.ident()?;
+                self.print_ident(name)?;
+            }
+            b'N' => {
+                let namespace = self.next().filter(u8::is_ascii_alphabetic)?;
+                self.path(in_value)?;
+                let disambiguator = self.disambiguator()?;
+                let name = self.ident()?;
+                if namespace.is_ascii_uppercase() {
+                    // closures and shims
+                    self.print("::{");
+                    match namespace {
+                        b'C' => self.print("closure"),
+                        b'S' => self.print("shim"),
+                        _ => self.print(&(namespace as char).to_string()),
+                    }
+                    if !name.is_empty() {
+                        self.print(":");
+                        self.print_ident(name)?;
+                    }
+                    self.print(&format!("#{}}}", disambiguator));
+                } else if !name.is_empty() {
+                    self.print("::");
+                    self.print_ident(name)?;
+                }
+            }
+            tag @ (b'M' | b'X' | b'Y') => {
+                if tag != b'Y' {
+                    // the path of the impl itself isn't printed
+                    self.disambiguator()?;
+                    let skipping = core::mem::replace(&mut self.skipping, true);
+                    self.path(in_value)?;
+                    self.skipping = skipping;
+                }
+                self.print("<");
+                self.ty()?;
+                if tag != b'M' {
+                    self.print(" as ");
+                    self.path(false)?;
+                }
+                self.print(">");
+            }
+            b'I' => {
+                self.path(in_value)?;
+                if in_value {
+                    self.print("::");
+                }
+                self.print("<");
+                self.generic_args()?;
+                self.print(">");
+            }
+            b'B' => self.backref(|this| this.path(in_value))?,
+            _ => return None,
+        }
+        self.depth -= 1;
+        Some(())
+    }
+
+    fn generic_args(&mut self) -> Option<()> {
+        let mut index = 0;
+        while !self.eat(b'E') {
+            if index > 0 {
+                self.print(", ");
+            }
+            self.generic_arg()?;
+            index += 1;
+        }
+        Some(())
+    }
+
+    fn generic_arg(&mut self) -> Option<()> {
+        if self.eat(b'L') {
+            let lifetime = self.integer_62()?;
+            self.lifetime(lifetime)
+        } else if self.eat(b'K') {
+            self.constant()
+        } else {
+            self.ty()
+        }
+    }
+
+    fn lifetime(&mut self, lifetime: u64) -> Option<()> {
+        if lifetime == 0 {
+            self.print("'_");
+            return Some(());
+        }
+        let depth = self.bound_lifetime_depth.checked_sub(lifetime)?;
+        if depth < 26 {
+            self.print(&format!("'{}", (b'a' + depth as u8) as char));
+        } else {
+            self.print(&format!("'_{}", depth));
+        }
+        Some(())
+    }
+
+    fn binder(&mut self) -> Option<()> {
+        let lifetimes = self.opt_integer_62(b'G')?;
+        if lifetimes > 0 {
+            self.print("for<");
+            for index in 0..lifetimes {
+                if index > 0 {
+                    self.print(", ");
+                }
+                self.bound_lifetime_depth += 1;
+                self.lifetime(1)?;
+            }
+            self.print("> ");
+        }
+        Some(())
+    }
+
+    fn ty(&mut self) -> Option<()> {
+        self.enter()?;
+        let tag = self.next()?;
+        if let Some(basic) = basic_type(tag) {
+            self.print(basic);
+            self.depth -= 1;
+            return Some(());
+        }
+        match tag {
+            b'R' | b'Q' => {
+                self.print("&");
+                if self.eat(b'L') {
+                    let lifetime = self.integer_62()?;
+                    if lifetime != 0 {
+                        self.lifetime(lifetime)?;
+                        self.print(" ");
+                    }
+                }
+                if tag == b'Q' {
+                    self.print("mut ");
+                }
+                self.ty()?;
+            }
+            b'P' | b'O' => {
+                self.print(if tag == b'P' { "*const " } else { "*mut " });
+                self.ty()?;
+            }
+            b'A' | b'S' => {
+                self.print("[");
+                self.ty()?;
+                if tag == b'A' {
+                    self.print("; ");
+                    self.constant()?;
+                }
+                self.print("]");
+            }
+            b'T' => {
+                self.print("(");
+                let mut count = 0;
+                while !self.eat(b'E') {
+                    if count > 0 {
+                        self.print(", ");
+                    }
+                    self.ty()?;
+                    count += 1;
+                }
+                if count == 1 {
+                    self.print(",");
+                }
+                self.print(")");
+            }
+            b'F' => {
+                let depth = self.bound_lifetime_depth;
+                self.binder()?;
+                if self.eat(b'U') {
+                    self.print("unsafe ");
+                }
+                if self.eat(b'K') {
+                    let abi = if self.eat(b'C') {
+                        "C"
+                    } else {
+                        let abi = self.ident()?;
+                        if !abi.punycode.is_empty() {
+                            return None;
+                        }
+                        abi.ascii
+                    };
+                    // `-` in the ABI names are mangled as `_`
+                    self.print(&format!("extern \"{}\" ", abi.replace('_', "-")));
+                }
+                self.print("fn(");
+                let mut count = 0;
+                while !self.eat(b'E') {
+                    if count > 0 {
+                        self.print(", ");
+                    }
+                    self.ty()?;
+                    count += 1;
+                }
+                self.print(")");
+                if !self.eat(b'u') {
+                    self.print(" -> ");
+                    self.ty()?;
+                }
+                self.bound_lifetime_depth = depth;
+            }
+            b'D' => {
+                self.print("dyn ");
+                let depth = self.bound_lifetime_depth;
+                self.binder()?;
+                let mut count = 0;
+                while !self.eat(b'E') {
+                    if count > 0 {
+                        self.print(" + ");
+                    }
+                    self.dyn_trait()?;
+                    count += 1;
+                }
+                self.bound_lifetime_depth = depth;
+                if !self.eat(b'L') {
+                    return None;
+                }
+                let lifetime = self.integer_62()?;
+                if lifetime != 0 {
+                    self.print(" + ");
+                    self.lifetime(lifetime)?;
+                }
+            }
+            b'B' => self.backref(Self::ty)?,
+            _ => {
+                self.next -= 1;
+                self.path(false)?;
+            }
+        }
+        self.depth -= 1;
+        Some(())
+    }
+
+    fn dyn_trait(&mut self) -> Option<()> {
+        let mut open = self.path_maybe_open_generics()?;
+        while self.eat(b'p') {
+            self.print(if open { ", " } else { "<" });
+            open = true;
+            let name = self.ident()?;
+            self.print_ident(name)?;
+            self.print(" = ");
+            self.ty()?;
+        }
+        if open {
+            self.print(">");
+        }
+        Some(())
+    }
+
+    /// Print a path, leaving its generic arguments list open for the
+    /// associated types of a `dyn` trait
+    fn path_maybe_open_generics(&mut self) -> Option<bool> {
+        if self.eat(b'B') {
+            let mut open = false;
+            self.backref(|this| {
+                open = this.path_maybe_open_generics()?;
+                Some(())
+            })?;
+            Some(open)
+        } else if self.eat(b'I') {
+            self.path(false)?;
+            self.print("<");
+            let mut index = 0;
+            while !self.eat(b'E') {
+                if index > 0 {
+                    self.print(", ");
+                }
+                self.generic_arg()?;
+                index += 1;
+            }
+            Some(true)
+        } else {
+            self.path(false)?;
+            Some(false)
+        }
+    }
+
+    /// Hex digits up to `_`, their count and their value
+    fn hex_nibbles(&mut self) -> Option<(usize, u64, usize)> {
+        let start = self.next;
+        let mut value = 0u64;
+        loop {
+            let digit = match self.next()? {
+                byte @ b'0'..=b'9' => byte - b'0',
+                byte @ b'a'..=b'f' => byte - b'a' + 10,
+                b'_' => break,
+                _ => return None,
+            };
+            value = value.wrapping_mul(16) | digit as u64;
+        }
+        Some((self.next - start - 1, value, start))
+    }
+
+    fn constant(&mut self) -> Option<()> {
+        if self.eat(b'B') {
+            return self.backref(Self::constant);
+        }
+        match self.next()? {
+            b'p' => self.print("_"),
+            b'h' | b't' | b'm' | b'y' | b'o' | b'j' => self.constant_uint()?,
+            b'a' | b's' | b'l' | b'x' | b'n' | b'i' => {
+                if self.eat(b'n') {
+                    self.print("-");
+                }
+                self.constant_uint()?;
+            }
+            b'b' => match self.hex_nibbles()? {
+                (1, 0, _) => self.print("false"),
+                (1, 1, _) => self.print("true"),
+                _ => return None,
+            },
+            b'c' => {
+                let (len, value, _) = self.hex_nibbles()?;
+                if len == 0 || len > 8 {
+                    return None;
+                }
+                let escaped = match char::from_u32(value as u32)? {
+                    '\t' => "\\t".to_string(),
+                    '\r' => "\\r".to_string(),
+                    '\n' => "\\n".to_string(),
+                    '\\' => "\\\\".to_string(),
+                    '\'' => "\\'".to_string(),
+                    c if c.is_ascii_graphic() || c == ' ' => c.to_string(),
+                    _ => format!("\\u{{{:x}}}", value),
+                };
+                self.print(&format!("'{}'", escaped));
+            }
+            _ => return None,
+        }
+        Some(())
+    }
+
+    fn constant_uint(&mut self) -> Option<()> {
+        let (len, value, start) = self.hex_nibbles()?;
+        if len > 16 {
+            // too large for an u64, printed as is
+            let digits = core::str::from_utf8(&self.sym[start..start + len]).ok()?;
+            self.print(&format!("0x{}", digits));
+        } else {
+            self.print(&value.to_string());
+        }
+        Some(())
+    }
+}
+
+fn basic_type(tag: u8) -> Option<&'static str> {
+    Some(match tag {
+        b'a' => "i8",
+        b'b' => "bool",
+        b'c' => "char",
+        b'd' => "f64",
+        b'e' => "str",
+        b'f' => "f32",
+        b'h' => "u8",
+        b'i' => "isize",
+        b'j' => "usize",
+        b'l' => "i32",
+        b'm' => "u32",
+        b'n' => "i128",
+        b'o' => "u128",
+        b'p' => "_",
+        b's' => "i16",
+        b't' => "u16",
+        b'u' => "()",
+        b'v' => "...",
+        b'x' => "i64",
+        b'y' => "u64",
+        b'z' => "!",
+        _ => return None,
+    })
+}
+
+/// Demangle a v0 `_R` name, None if it isn't one
+pub fn demangle_v0(name: &str) -> Option<String> {
+    let body = name.strip_prefix("_R")?;
+    // the suffixes added by LLVM, ex: `.llvm.1234`, aren't printed
+    let body = body.split('.').next()?;
+    if !body
+        .bytes()
+        .all(|byte| byte.is_ascii_alphanumeric() || byte == b'_')
+    {
+        return None;
+    }
+    let mut demangler = Demangler {
+        sym: body.as_bytes(),
+        next: 0,
+        out: String::new(),
+        skipping: false,
+        bound_lifetime_depth: 0,
+        depth: 0,
+    };
+    demangler.path(true)?;
+    // the instantiating crate isn't printed
+    if demangler.next < demangler.sym.len() {
+        demangler.skipping = true;
+        demangler.path(false)?;
+    }
+    (demangler.next == demangler.sym.len()).then_some(demangler.out)
+}
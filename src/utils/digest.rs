@@ -0,0 +1,516 @@
+//! Message digests of bytes, MD5, SHA-1, SHA-256 and the CRC-32 of zlib
+//!
+//! The digests are only meant to compare the contents of files, for
+//! example two builds of the same sources, not for anything where an
+//! attacker could pick the bytes.
+use crate::alloc_prelude::*;
+use crate::headers::elf::debuglink::crc32_update;
+use core::fmt;
+use core::str::FromStr;
+
+/// The supported digest algorithms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Algorithm {
+    Crc32,
+    Md5,
+    Sha1,
+    #[default]
+    Sha256,
+}
+
+impl Algorithm {
+    /// Every algorithm, from the shortest digest
+    pub const ALL: [Algorithm; 4] = [
+        Algorithm::Crc32,
+        Algorithm::Md5,
+        Algorithm::Sha1,
+        Algorithm::Sha256,
+    ];
+
+    /// Name of the algorithm, as accepted by `from_str`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Crc32 => "crc32",
+            Algorithm::Md5 => "md5",
+            Algorithm::Sha1 => "sha1",
+            Algorithm::Sha256 => "sha256",
+        }
+    }
+
+    /// The digest of the bytes, the CRC-32 in big endian as `crc32(1)` prints it
+    pub fn digest(&self, bytes: &[u8]) -> Vec<u8> {
+        self.digest_all(&[bytes])
+    }
+
+    /// The digest of the parts one after the other, without copying them
+    pub fn digest_all(&self, parts: &[&[u8]]) -> Vec<u8> {
+        match self {
+            Algorithm::Crc32 => {
+                let crc = parts.iter().fold(!0, |crc, part| crc32_update(crc, part));
+                (!crc).to_be_bytes().to_vec()
+            }
+            Algorithm::Md5 => {
+                let mut state = Md5::default();
+                parts.iter().for_each(|part| state.update(part));
+                state.finish().to_vec()
+            }
+            Algorithm::Sha1 => {
+                let mut state = Sha1::default();
+                parts.iter().for_each(|part| state.update(part));
+                state.finish().to_vec()
+            }
+            Algorithm::Sha256 => {
+                let mut state = Sha256::default();
+                parts.iter().for_each(|part| state.update(part));
+                state.finish().to_vec()
+            }
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(name: &str) -> core::result::Result<Self, Self::Err> {
+        Algorithm::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                let names: Vec<_> = Algorithm::ALL.iter().map(Algorithm::name).collect();
+                format!(
+                    "unknown algorithm '{}', expected {}",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// A digest in lower case hexadecimal
+pub fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Blocks of 64 bytes with the message length in bits in the padding, the
+/// layout shared by MD5 and the SHA family
+#[derive(Clone)]
+struct Blocks {
+    buffer: [u8; 64],
+    filled: usize,
+    length: u64,
+}
+
+impl Default for Blocks {
+    fn default() -> Self {
+        Blocks {
+            buffer: [0; 64],
+            filled: 0,
+            length: 0,
+        }
+    }
+}
+
+impl Blocks {
+    /// Call `compress` on each full block of the bytes
+    fn update(&mut self, mut bytes: &[u8], mut compress: impl FnMut(&[u8; 64])) {
+        self.length = self.length.wrapping_add(bytes.len() as u64);
+        while !bytes.is_empty() {
+            let count = (64 - self.filled).min(bytes.len());
+            self.buffer[self.filled..self.filled + count].copy_from_slice(&bytes[..count]);
+            self.filled += count;
+            bytes = &bytes[count..];
+            if self.filled == 64 {
+                compress(&self.buffer);
+                self.filled = 0;
+            }
+        }
+    }
+
+    /// Pad the last block with the length in the given byte order
+    fn finish(&mut self, big_endian: bool, mut compress: impl FnMut(&[u8; 64])) {
+        let bits = self.length.wrapping_mul(8);
+        let mut padding = [0u8; 72];
+        padding[0] = 0x80;
+        let count = if self.filled < 56 {
+            56 - self.filled
+        } else {
+            120 - self.filled
+        };
+        let length = if big_endian {
+            bits.to_be_bytes()
+        } else {
+            bits.to_le_bytes()
+        };
+        padding[count..count + 8].copy_from_slice(&length);
+        let length = self.length;
+        self.update(&padding[..count + 8], &mut compress);
+        self.length = length;
+    }
+}
+
+/// MD5, RFC 1321
+#[derive(Clone)]
+struct Md5 {
+    state: [u32; 4],
+    blocks: Blocks,
+}
+
+impl Default for Md5 {
+    fn default() -> Self {
+        Md5 {
+            state: [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476],
+            blocks: Blocks::default(),
+        }
+    }
+}
+
+const MD5_SHIFTS: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+
+/// floor(abs(sin(i + 1)) * 2^32)
+const MD5_CONSTANTS: [u32; 64] = [
+    0xd76a_a478,
+    0xe8c7_b756,
+    0x2420_70db,
+    0xc1bd_ceee,
+    0xf57c_0faf,
+    0x4787_c62a,
+    0xa830_4613,
+    0xfd46_9501,
+    0x6980_98d8,
+    0x8b44_f7af,
+    0xffff_5bb1,
+    0x895c_d7be,
+    0x6b90_1122,
+    0xfd98_7193,
+    0xa679_438e,
+    0x49b4_0821,
+    0xf61e_2562,
+    0xc040_b340,
+    0x265e_5a51,
+    0xe9b6_c7aa,
+    0xd62f_105d,
+    0x0244_1453,
+    0xd8a1_e681,
+    0xe7d3_fbc8,
+    0x21e1_cde6,
+    0xc337_07d6,
+    0xf4d5_0d87,
+    0x455a_14ed,
+    0xa9e3_e905,
+    0xfcef_a3f8,
+    0x676f_02d9,
+    0x8d2a_4c8a,
+    0xfffa_3942,
+    0x8771_f681,
+    0x6d9d_6122,
+    0xfde5_380c,
+    0xa4be_ea44,
+    0x4bde_cfa9,
+    0xf6bb_4b60,
+    0xbebf_bc70,
+    0x289b_7ec6,
+    0xeaa1_27fa,
+    0xd4ef_3085,
+    0x0488_1d05,
+    0xd9d4_d039,
+    0xe6db_99e5,
+    0x1fa2_7cf8,
+    0xc4ac_5665,
+    0xf429_2244,
+    0x432a_ff97,
+    0xab94_23a7,
+    0xfc93_a039,
+    0x655b_59c3,
+    0x8f0c_cc92,
+    0xffef_f47d,
+    0x8584_5dd1,
+    0x6fa8_7e4f,
+    0xfe2c_e6e0,
+    0xa301_4314,
+    0x4e08_11a1,
+    0xf753_7e82,
+    0xbd3a_f235,
+    0x2ad7_d2bb,
+    0xeb86_d391,
+];
+
+fn md5_compress(state: &mut [u32; 4], block: &[u8; 64]) {
+    let mut words = [0u32; 16];
+    for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    let [mut a, mut b, mut c, mut d] = *state;
+    for i in 0..64 {
+        let (f, g) = match i / 16 {
+            0 => ((b & c) | (!b & d), i),
+            1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+            2 => (b ^ c ^ d, (3 * i + 5) % 16),
+            _ => (c ^ (b | !d), (7 * i) % 16),
+        };
+        let shift = MD5_SHIFTS[(i / 16) * 4 + i % 4];
+        let sum = a
+            .wrapping_add(f)
+            .wrapping_add(MD5_CONSTANTS[i])
+            .wrapping_add(words[g]);
+        a = d;
+        d = c;
+        c = b;
+        b = b.wrapping_add(sum.rotate_left(shift));
+    }
+    for (value, new) in state.iter_mut().zip([a, b, c, d]) {
+        *value = value.wrapping_add(new);
+    }
+}
+
+impl Md5 {
+    fn update(&mut self, bytes: &[u8]) {
+        let state = &mut self.state;
+        self.blocks
+            .update(bytes, |block| md5_compress(state, block));
+    }
+
+    fn finish(mut self) -> [u8; 16] {
+        let state = &mut self.state;
+        self.blocks
+            .finish(false, |block| md5_compress(state, block));
+        let mut out = [0; 16];
+        for (bytes, word) in out.chunks_exact_mut(4).zip(self.state) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        out
+    }
+}
+
+/// SHA-1, FIPS 180-4
+#[derive(Clone)]
+struct Sha1 {
+    state: [u32; 5],
+    blocks: Blocks,
+}
+
+impl Default for Sha1 {
+    fn default() -> Self {
+        Sha1 {
+            state: [
+                0x6745_2301,
+                0xefcd_ab89,
+                0x98ba_dcfe,
+                0x1032_5476,
+                0xc3d2_e1f0,
+            ],
+            blocks: Blocks::default(),
+        }
+    }
+}
+
+fn sha1_compress(state: &mut [u32; 5], block: &[u8; 64]) {
+    let mut words = [0u32; 80];
+    for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    for i in 16..80 {
+        words[i] = (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1);
+    }
+    let [mut a, mut b, mut c, mut d, mut e] = *state;
+    for (i, word) in words.iter().enumerate() {
+        let (f, k) = match i / 20 {
+            0 => ((b & c) | (!b & d), 0x5a82_7999),
+            1 => (b ^ c ^ d, 0x6ed9_eba1),
+            2 => ((b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
+            _ => (b ^ c ^ d, 0xca62_c1d6),
+        };
+        let temp = a
+            .rotate_left(5)
+            .wrapping_add(f)
+            .wrapping_add(e)
+            .wrapping_add(k)
+            .wrapping_add(*word);
+        e = d;
+        d = c;
+        c = b.rotate_left(30);
+        b = a;
+        a = temp;
+    }
+    for (value, new) in state.iter_mut().zip([a, b, c, d, e]) {
+        *value = value.wrapping_add(new);
+    }
+}
+
+impl Sha1 {
+    fn update(&mut self, bytes: &[u8]) {
+        let state = &mut self.state;
+        self.blocks
+            .update(bytes, |block| sha1_compress(state, block));
+    }
+
+    fn finish(mut self) -> [u8; 20] {
+        let state = &mut self.state;
+        self.blocks
+            .finish(true, |block| sha1_compress(state, block));
+        let mut out = [0; 20];
+        for (bytes, word) in out.chunks_exact_mut(4).zip(self.state) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+        out
+    }
+}
+
+/// SHA-256, FIPS 180-4
+#[derive(Clone)]
+struct Sha256 {
+    state: [u32; 8],
+    blocks: Blocks,
+}
+
+impl Default for Sha256 {
+    fn default() -> Self {
+        Sha256 {
+            state: [
+                0x6a09_e667,
+                0xbb67_ae85,
+                0x3c6e_f372,
+                0xa54f_f53a,
+                0x510e_527f,
+                0x9b05_688c,
+                0x1f83_d9ab,
+                0x5be0_cd19,
+            ],
+            blocks: Blocks::default(),
+        }
+    }
+}
+
+/// First 32 bits of the fractional parts of the cube roots of the first
+/// 64 primes
+const SHA256_CONSTANTS: [u32; 64] = [
+    0x428a_2f98,
+    0x7137_4491,
+    0xb5c0_fbcf,
+    0xe9b5_dba5,
+    0x3956_c25b,
+    0x59f1_11f1,
+    0x923f_82a4,
+    0xab1c_5ed5,
+    0xd807_aa98,
+    0x1283_5b01,
+    0x2431_85be,
+    0x550c_7dc3,
+    0x72be_5d74,
+    0x80de_b1fe,
+    0x9bdc_06a7,
+    0xc19b_f174,
+    0xe49b_69c1,
+    0xefbe_4786,
+    0x0fc1_9dc6,
+    0x240c_a1cc,
+    0x2de9_2c6f,
+    0x4a74_84aa,
+    0x5cb0_a9dc,
+    0x76f9_88da,
+    0x983e_5152,
+    0xa831_c66d,
+    0xb003_27c8,
+    0xbf59_7fc7,
+    0xc6e0_0bf3,
+    0xd5a7_9147,
+    0x06ca_6351,
+    0x1429_2967,
+    0x27b7_0a85,
+    0x2e1b_2138,
+    0x4d2c_6dfc,
+    0x5338_0d13,
+    0x650a_7354,
+    0x766a_0abb,
+    0x81c2_c92e,
+    0x9272_2c85,
+    0xa2bf_e8a1,
+    0xa81a_664b,
+    0xc24b_8b70,
+    0xc76c_51a3,
+    0xd192_e819,
+    0xd699_0624,
+    0xf40e_3585,
+    0x106a_a070,
+    0x19a4_c116,
+    0x1e37_6c08,
+    0x2748_774c,
+    0x34b0_bcb5,
+    0x391c_0cb3,
+    0x4ed8_aa4a,
+    0x5b9c_ca4f,
+    0x682e_6ff3,
+    0x748f_82ee,
+    0x78a5_636f,
+    0x84c8_7814,
+    0x8cc7_0208,
+    0x90be_fffa,
+    0xa450_6ceb,
+    0xbef9_a3f7,
+    0xc671_78f2,
+];
+
+fn sha256_compress(state: &mut [u32; 8], block: &[u8; 64]) {
+    let mut words = [0u32; 64];
+    for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    for i in 16..64 {
+        let s0 =
+            words[i - 15].rotate_right(7) ^ words[i - 15].rotate_right(18) ^ (words[i - 15] >> 3);
+        let s1 =
+            words[i - 2].rotate_right(17) ^ words[i - 2].rotate_right(19) ^ (words[i - 2] >> 10);
+        words[i] = words[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(words[i - 7])
+            .wrapping_add(s1);
+    }
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for (word, constant) in words.iter().zip(SHA256_CONSTANTS) {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let choice = (e & f) ^ (!e & g);
+        let temp1 = h
+            .wrapping_add(s1)
+            .wrapping_add(choice)
+            .wrapping_add(constant)
+            .wrapping_add(*word);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let majority = (a & b) ^ (a & c) ^ (b & c);
+        let temp2 = s0.wrapping_add(majority);
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.wrapping_add(temp2);
+    }
+    for (value, new) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *value = value.wrapping_add(new);
+    }
+}
+
+impl Sha256 {
+    fn update(&mut self, bytes: &[u8]) {
+        let state = &mut self.state;
+        self.blocks
+            .update(bytes, |block| sha256_compress(state, block));
+    }
+
+    fn finish(mut self) -> [u8; 32] {
+        let state = &mut self.state;
+        self.blocks
+            .finish(true, |block| sha256_compress(state, block));
+        let mut out = [0; 32];
+        for (bytes, word) in out.chunks_exact_mut(4).zip(self.state) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+        out
+    }
+}
@@ -0,0 +1,42 @@
+//! Disassembly of machine code
+//!
+//! Only x86_64 is decoded, with the syntax of `objdump -d -M intel`. The
+//! decoder knows the instructions compilers emit, the general purpose
+//! ones and the common SSE ones, the others are printed as `(bad)`.
+use crate::alloc_prelude::*;
+use core::fmt;
+
+pub mod x86_64;
+
+/// An address an instruction refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reference {
+    /// target of a relative call or jump
+    Branch(u64),
+    /// a RIP relative memory operand
+    Memory(u64),
+}
+
+/// A decoded instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    /// address of the first byte
+    pub address: u64,
+    /// number of bytes of the instruction
+    pub len: usize,
+    /// the mnemonic, with its printed prefixes, ex: `rep stos`
+    pub mnemonic: String,
+    /// the operands separated by commas, the target address for branches
+    pub operands: String,
+    /// the address a branch or a memory operand refers to
+    pub reference: Option<Reference>,
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.operands.is_empty() {
+            true => f.write_str(&self.mnemonic),
+            false => write!(f, "{:<6} {}", self.mnemonic, self.operands),
+        }
+    }
+}
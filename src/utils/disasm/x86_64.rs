@@ -0,0 +1,1261 @@
+//! Decoder of the x86_64 instructions
+//!
+//! The instructions are printed like `objdump -d -M intel`: the mnemonic,
+//! the destination first, the immediates in hexadecimal in the size of
+//! their operand and the memory operands with their size, ex:
+//! `mov    DWORD PTR [rbp-0x4],edi`.
+//!
+//! ```
+//! use readbin::utils::disasm::x86_64::decode;
+//!
+//! let instruction = decode(&[0x48, 0x83, 0xec, 0x10], 0x1000).unwrap();
+//! assert_eq!(instruction.len, 4);
+//! assert_eq!(instruction.to_string(), "sub    rsp,0x10");
+//! ```
+use crate::alloc_prelude::*;
+use core::fmt::Write;
+
+use super::{Instruction, Reference};
+
+/// The longest instruction accepted by the processors
+const MAX_LEN: usize = 15;
+
+const REG64: [&str; 16] = [
+    "rax", "rcx", "rdx", "rbx", "rsp", "rbp", "rsi", "rdi", "r8", "r9", "r10", "r11", "r12", "r13",
+    "r14", "r15",
+];
+const REG32: [&str; 16] = [
+    "eax", "ecx", "edx", "ebx", "esp", "ebp", "esi", "edi", "r8d", "r9d", "r10d", "r11d", "r12d",
+    "r13d", "r14d", "r15d",
+];
+const REG16: [&str; 16] = [
+    "ax", "cx", "dx", "bx", "sp", "bp", "si", "di", "r8w", "r9w", "r10w", "r11w", "r12w", "r13w",
+    "r14w", "r15w",
+];
+/// The byte registers with a REX prefix
+const REG8: [&str; 16] = [
+    "al", "cl", "dl", "bl", "spl", "bpl", "sil", "dil", "r8b", "r9b", "r10b", "r11b", "r12b",
+    "r13b", "r14b", "r15b",
+];
+/// The byte registers without REX prefix, 4 to 7 are the high bytes
+const REG8_LEGACY: [&str; 8] = ["al", "cl", "dl", "bl", "ah", "ch", "dh", "bh"];
+const XMM: [&str; 16] = [
+    "xmm0", "xmm1", "xmm2", "xmm3", "xmm4", "xmm5", "xmm6", "xmm7", "xmm8", "xmm9", "xmm10",
+    "xmm11", "xmm12", "xmm13", "xmm14", "xmm15",
+];
+
+/// Suffixes of the jcc, setcc and cmovcc mnemonics
+const CONDITIONS: [&str; 16] = [
+    "o", "no", "b", "ae", "e", "ne", "be", "a", "s", "ns", "p", "np", "l", "ge", "le", "g",
+];
+/// The operations of 0x00 to 0x3f and of the 0x80 group
+const ARITHMETIC: [&str; 8] = ["add", "or", "adc", "sbb", "and", "sub", "xor", "cmp"];
+/// The operations of the 0xc0, 0xd0 groups
+const SHIFTS: [&str; 8] = ["rol", "ror", "rcl", "rcr", "shl", "shr", "sal", "sar"];
+/// The operations of the 0xf6 group
+const UNARY: [&str; 8] = ["test", "test", "not", "neg", "mul", "imul", "div", "idiv"];
+/// The x87 arithmetic of 0xd8 and 0xdc, also the integer ones of 0xda
+/// and 0xde with an `i`
+const X87_ARITHMETIC: [&str; 8] = ["add", "mul", "com", "comp", "sub", "subr", "div", "divr"];
+/// The x87 instructions without operand of 0xd9 0xe0 to 0xff
+const X87_CONSTANTS: [&str; 32] = [
+    "fchs", "fabs", "", "", "ftst", "fxam", "", "", "fld1", "fldl2t", "fldl2e", "fldpi", "fldlg2",
+    "fldln2", "fldz", "", "f2xm1", "fyl2x", "fptan", "fpatan", "fxtract", "fprem1", "fdecstp",
+    "fincstp", "fprem", "fyl2xp1", "fsqrt", "fsincos", "frndint", "fscale", "fsin", "fcos",
+];
+/// Predicates of the SSE comparisons
+const SSE_PREDICATES: [&str; 8] = ["eq", "lt", "le", "unord", "neq", "nlt", "nle", "ord"];
+
+/// Size of an operand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Size {
+    Byte,
+    Word,
+    Dword,
+    Qword,
+    /// the 80 bits of the x87 extended floats
+    Tbyte,
+    Xmm,
+}
+
+impl Size {
+    fn ptr(self) -> &'static str {
+        match self {
+            Size::Byte => "BYTE PTR",
+            Size::Word => "WORD PTR",
+            Size::Dword => "DWORD PTR",
+            Size::Qword => "QWORD PTR",
+            Size::Tbyte => "TBYTE PTR",
+            Size::Xmm => "XMMWORD PTR",
+        }
+    }
+
+    fn mask(self) -> u64 {
+        match self {
+            Size::Byte => 0xff,
+            Size::Word => 0xffff,
+            Size::Dword => 0xffff_ffff,
+            Size::Qword | Size::Tbyte | Size::Xmm => u64::MAX,
+        }
+    }
+}
+
+/// A decoded ModRM byte, with its SIB byte and displacement
+struct ModRm {
+    /// the reg field, extended by REX.R
+    reg: u8,
+    /// the register of the rm field extended by REX.B, when it isn't memory
+    rm: u8,
+    /// the memory operand, without its size
+    memory: Option<String>,
+}
+
+/// The SSE scalar and packed variants selected by the mandatory prefix
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sse {
+    /// no prefix, packed singles
+    Ps,
+    /// 0x66, packed doubles
+    Pd,
+    /// 0xf3, scalar single
+    Ss,
+    /// 0xf2, scalar double
+    Sd,
+}
+
+impl Sse {
+    fn suffix(self) -> &'static str {
+        match self {
+            Sse::Ps => "ps",
+            Sse::Pd => "pd",
+            Sse::Ss => "ss",
+            Sse::Sd => "sd",
+        }
+    }
+
+    /// Size of the memory operand
+    fn size(self) -> Size {
+        match self {
+            Sse::Ps | Sse::Pd => Size::Xmm,
+            Sse::Ss => Size::Dword,
+            Sse::Sd => Size::Qword,
+        }
+    }
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    at: usize,
+    /// the REX byte, 0 without
+    rex: u8,
+    /// number of 0x66 prefixes
+    opsize: usize,
+    addrsize: bool,
+    lock: bool,
+    /// the last of the 0xf2 and 0xf3 prefixes
+    rep: Option<u8>,
+    /// the segment override prefix
+    segment: Option<u8>,
+    /// the prefixes consumed as part of the opcode
+    mandatory: bool,
+    /// the segment prefix was printed in an operand
+    segment_used: bool,
+    /// displacement of a RIP relative operand, from the next instruction
+    rip: Option<i64>,
+    /// offset of a relative branch, from the next instruction
+    branch: Option<i64>,
+}
+
+/// Decode the instruction at the start of `bytes`, located at `address`
+///
+/// Bytes that don't start a known instruction, or a truncated one, are
+/// decoded as a one byte `(bad)` instruction so the caller can go on
+/// with the next byte. Returns None if `bytes` is empty.
+pub fn decode(bytes: &[u8], address: u64) -> Option<Instruction> {
+    if bytes.is_empty() {
+        return None;
+    }
+    let mut decoder = Decoder {
+        bytes: &bytes[..bytes.len().min(MAX_LEN)],
+        at: 0,
+        rex: 0,
+        opsize: 0,
+        addrsize: false,
+        lock: false,
+        rep: None,
+        segment: None,
+        mandatory: false,
+        segment_used: false,
+        rip: None,
+        branch: None,
+    };
+    let (mnemonic, operands) = match decoder.instruction() {
+        Some(instruction) => instruction,
+        None => {
+            return Some(Instruction {
+                address,
+                len: 1,
+                mnemonic: "(bad)".to_string(),
+                operands: String::new(),
+                reference: None,
+            })
+        }
+    };
+    let len = decoder.at;
+    let next = address.wrapping_add(len as u64);
+    let (operands, reference) = match (decoder.branch, decoder.rip) {
+        (Some(offset), _) => {
+            let target = next.wrapping_add(offset as u64);
+            (format!("{:x}", target), Some(Reference::Branch(target)))
+        }
+        (None, Some(offset)) => (
+            operands,
+            Some(Reference::Memory(next.wrapping_add(offset as u64))),
+        ),
+        (None, None) => (operands, None),
+    };
+    Some(Instruction {
+        address,
+        len,
+        mnemonic: decoder.prefixes() + &mnemonic,
+        operands,
+        reference,
+    })
+}
+
+impl Decoder<'_> {
+    fn byte(&mut self) -> Option<u8> {
+        let byte = *self.bytes.get(self.at)?;
+        self.at += 1;
+        Some(byte)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.at).copied()
+    }
+
+    /// A little endian immediate of `size` bytes, sign extended
+    fn signed(&mut self, size: usize) -> Option<i64> {
+        let bytes = self.bytes.get(self.at..self.at + size)?;
+        self.at += size;
+        let value = bytes
+            .iter()
+            .rev()
+            .fold(0u64, |value, byte| (value << 8) | u64::from(*byte));
+        let shift = 64 - 8 * size as u32;
+        Some(((value << shift) as i64) >> shift)
+    }
+
+    fn rex_w(&self) -> bool {
+        self.rex & 8 != 0
+    }
+
+    /// Size of the operands without the byte opcodes
+    fn size(&self) -> Size {
+        if self.rex_w() {
+            Size::Qword
+        } else if self.opsize > 0 && !self.mandatory {
+            Size::Word
+        } else {
+            Size::Dword
+        }
+    }
+
+    /// The immediate of an instruction of `size`, 32 bits for 64 bits operands
+    fn immediate(&mut self, size: Size) -> Option<String> {
+        let value = match size {
+            Size::Byte => self.signed(1)?,
+            Size::Word => self.signed(2)?,
+            _ => self.signed(4)?,
+        };
+        Some(format!("{:#x}", value as u64 & size.mask()))
+    }
+
+    /// An 8 bits immediate sign extended to `size`
+    fn immediate8(&mut self, size: Size) -> Option<String> {
+        let value = self.signed(1)?;
+        Some(format!("{:#x}", value as u64 & size.mask()))
+    }
+
+    fn register(&self, number: u8, size: Size) -> &'static str {
+        let number = (number & 15) as usize;
+        match size {
+            Size::Byte if self.rex == 0 => REG8_LEGACY[number & 7],
+            Size::Byte => REG8[number],
+            Size::Word => REG16[number],
+            Size::Dword => REG32[number],
+            Size::Qword | Size::Tbyte => REG64[number],
+            Size::Xmm => XMM[number],
+        }
+    }
+
+    fn modrm(&mut self) -> Option<ModRm> {
+        let byte = self.byte()?;
+        let mode = byte >> 6;
+        let reg = ((byte >> 3) & 7) | ((self.rex & 4) << 1);
+        let rm = byte & 7;
+        if mode == 3 {
+            return Some(ModRm {
+                reg,
+                rm: rm | ((self.rex & 1) << 3),
+                memory: None,
+            });
+        }
+        let names = if self.addrsize { &REG32 } else { &REG64 };
+        let mut base = None;
+        let mut index = None;
+        let mut displacement = None;
+        let mut rip = false;
+        if rm == 4 {
+            let sib = self.byte()?;
+            let scale = 1 << (sib >> 6);
+            let number = ((sib >> 3) & 7) | ((self.rex & 2) << 2);
+            if number != 4 {
+                index = Some((names[number as usize], scale));
+            }
+            match (sib & 7, mode) {
+                (5, 0) => displacement = Some(self.signed(4)?),
+                (number, _) => base = Some(names[(number | ((self.rex & 1) << 3)) as usize]),
+            }
+        } else if rm == 5 && mode == 0 {
+            rip = true;
+            displacement = Some(self.signed(4)?);
+        } else {
+            base = Some(names[(rm | ((self.rex & 1) << 3)) as usize]);
+        }
+        match mode {
+            1 => displacement = Some(self.signed(1)?),
+            2 => displacement = Some(self.signed(4)?),
+            _ => {}
+        }
+
+        let segment = match self.segment {
+            Some(0x64) => "fs:",
+            Some(0x65) => "gs:",
+            _ => "",
+        };
+        self.segment_used |= !segment.is_empty();
+        if base.is_none() && index.is_none() && !rip {
+            let offset = displacement.unwrap_or_default() as u64;
+            let segment = if segment.is_empty() { "ds:" } else { segment };
+            return Some(ModRm {
+                reg,
+                rm: 0,
+                memory: Some(format!("{}{:#x}", segment, offset)),
+            });
+        }
+        let mut memory = format!("{}[", segment);
+        if rip {
+            memory.push_str(if self.addrsize { "eip" } else { "rip" });
+            self.rip = displacement;
+        } else if let Some(base) = base {
+            memory.push_str(base);
+        }
+        if let Some((index, scale)) = index {
+            if base.is_some() {
+                memory.push('+');
+            }
+            let _ = write!(memory, "{}*{}", index, scale);
+        }
+        match displacement {
+            // objdump prints the RIP offsets as unsigned
+            Some(offset) if rip => {
+                let _ = write!(memory, "+{:#x}", offset as u64);
+            }
+            Some(offset) if offset < 0 => {
+                let _ = write!(memory, "-{:#x}", offset.unsigned_abs());
+            }
+            Some(offset) => {
+                let _ = write!(memory, "+{:#x}", offset);
+            }
+            None => {}
+        }
+        memory.push(']');
+        Some(ModRm {
+            reg,
+            rm: 0,
+            memory: Some(memory),
+        })
+    }
+
+    /// The rm operand, a register or memory of `size`
+    fn rm(&self, modrm: &ModRm, size: Size) -> String {
+        match &modrm.memory {
+            Some(memory) => format!("{} {}", size.ptr(), memory),
+            None => self.register(modrm.rm, size).to_string(),
+        }
+    }
+
+    /// The reg operand
+    fn reg(&self, modrm: &ModRm, size: Size) -> &'static str {
+        self.register(modrm.reg, size)
+    }
+
+    /// The prefixes printed before the mnemonic, the ones without effect
+    fn prefixes(&self) -> String {
+        let mut prefixes = String::new();
+        let opsize_used = usize::from(self.opsize > 0);
+        for _ in opsize_used..self.opsize {
+            prefixes.push_str("data16 ");
+        }
+        if !self.segment_used {
+            match self.segment {
+                Some(0x26) => prefixes.push_str("es "),
+                Some(0x2e) => prefixes.push_str("cs "),
+                Some(0x36) => prefixes.push_str("ss "),
+                Some(0x3e) => prefixes.push_str("ds "),
+                Some(0x64) => prefixes.push_str("fs "),
+                Some(0x65) => prefixes.push_str("gs "),
+                _ => {}
+            }
+        }
+        if self.lock {
+            prefixes.push_str("lock ");
+        }
+        prefixes
+    }
+
+    /// `fmt` of the Ev,Gv or Gv,Ev forms
+    fn binary(&mut self, size: Size, reg_first: bool) -> Option<String> {
+        let modrm = self.modrm()?;
+        let (rm, reg) = (self.rm(&modrm, size), self.reg(&modrm, size));
+        Some(match reg_first {
+            true => format!("{},{}", reg, rm),
+            false => format!("{},{}", rm, reg),
+        })
+    }
+
+    /// A relative branch with an offset of `size` bytes
+    fn relative(&mut self, mnemonic: &str, size: usize) -> Option<(String, String)> {
+        self.branch = Some(self.signed(size)?);
+        let mnemonic = match self.rep {
+            Some(0xf2) => format!("bnd {}", mnemonic),
+            _ => mnemonic.to_string(),
+        };
+        Some((mnemonic, String::new()))
+    }
+
+    /// The string instructions, with their implicit operands
+    fn string(&mut self, opcode: u8) -> Option<(String, String)> {
+        let size = match opcode & 1 {
+            0 => Size::Byte,
+            _ => self.size(),
+        };
+        self.segment_used = true;
+        let destination = format!("{} es:[rdi]", size.ptr());
+        let source = format!("{} ds:[rsi]", size.ptr());
+        let accumulator = self.register(0, size);
+        let (mnemonic, operands, compares) = match opcode {
+            0xa4 | 0xa5 => ("movs", format!("{},{}", destination, source), false),
+            0xa6 | 0xa7 => ("cmps", format!("{},{}", source, destination), true),
+            0xaa | 0xab => ("stos", format!("{},{}", destination, accumulator), false),
+            0xac | 0xad => ("lods", format!("{},{}", accumulator, source), false),
+            _ => ("scas", format!("{},{}", accumulator, destination), true),
+        };
+        let prefix = match (self.rep, compares) {
+            (Some(0xf3), false) => "rep ",
+            (Some(0xf3), true) => "repz ",
+            (Some(_), _) => "repnz ",
+            (None, _) => "",
+        };
+        Some((format!("{}{}", prefix, mnemonic), operands))
+    }
+
+    fn instruction(&mut self) -> Option<(String, String)> {
+        let mut opcode = self.byte()?;
+        loop {
+            match opcode {
+                0x66 => self.opsize += 1,
+                0x67 => self.addrsize = true,
+                0xf0 => self.lock = true,
+                0xf2 | 0xf3 => self.rep = Some(opcode),
+                0x26 | 0x2e | 0x36 | 0x3e | 0x64 | 0x65 => self.segment = Some(opcode),
+                _ => break,
+            }
+            opcode = self.byte()?;
+        }
+        if opcode & 0xf0 == 0x40 {
+            self.rex = opcode;
+            opcode = self.byte()?;
+        }
+        let size = self.size();
+        let text = |mnemonic: &str, operands: String| Some((mnemonic.to_string(), operands));
+        match opcode {
+            0x00..=0x3f if opcode & 7 < 6 => {
+                let mnemonic = ARITHMETIC[(opcode >> 3) as usize];
+                let operands = match opcode & 7 {
+                    0 => self.binary(Size::Byte, false)?,
+                    1 => self.binary(size, false)?,
+                    2 => self.binary(Size::Byte, true)?,
+                    3 => self.binary(size, true)?,
+                    4 => format!("al,{}", self.immediate(Size::Byte)?),
+                    _ => format!("{},{}", self.register(0, size), self.immediate(size)?),
+                };
+                text(mnemonic, operands)
+            }
+            0x50..=0x5f => {
+                let size = if self.opsize > 0 {
+                    Size::Word
+                } else {
+                    Size::Qword
+                };
+                let register = self.register((opcode & 7) | ((self.rex & 1) << 3), size);
+                let mnemonic = if opcode < 0x58 { "push" } else { "pop" };
+                text(mnemonic, register.to_string())
+            }
+            0x63 => {
+                let modrm = self.modrm()?;
+                let operands = format!(
+                    "{},{}",
+                    self.reg(&modrm, size),
+                    self.rm(&modrm, Size::Dword)
+                );
+                text("movsxd", operands)
+            }
+            0x68 => text("push", self.immediate(Size::Qword)?),
+            0x6a => text("push", self.immediate8(Size::Qword)?),
+            0x69 | 0x6b => {
+                let operands = self.binary(size, true)?;
+                let immediate = match opcode {
+                    0x69 => self.immediate(size)?,
+                    _ => self.immediate8(size)?,
+                };
+                text("imul", format!("{},{}", operands, immediate))
+            }
+            0x70..=0x7f => self.relative(&format!("j{}", CONDITIONS[(opcode & 15) as usize]), 1),
+            0x80 | 0x81 | 0x83 => {
+                let size = if opcode == 0x80 { Size::Byte } else { size };
+                let modrm = self.modrm()?;
+                let destination = self.rm(&modrm, size);
+                let immediate = match opcode {
+                    0x81 => self.immediate(size)?,
+                    _ => self.immediate8(size)?,
+                };
+                let mnemonic = ARITHMETIC[(modrm.reg & 7) as usize];
+                text(mnemonic, format!("{},{}", destination, immediate))
+            }
+            0x84 => text("test", self.binary(Size::Byte, false)?),
+            0x85 => text("test", self.binary(size, false)?),
+            0x86 => text("xchg", self.binary(Size::Byte, false)?),
+            0x87 => text("xchg", self.binary(size, false)?),
+            0x88 => text("mov", self.binary(Size::Byte, false)?),
+            0x89 => text("mov", self.binary(size, false)?),
+            0x8a => text("mov", self.binary(Size::Byte, true)?),
+            0x8b => text("mov", self.binary(size, true)?),
+            0x8d => {
+                let modrm = self.modrm()?;
+                let memory = modrm.memory.as_ref()?;
+                text("lea", format!("{},{}", self.reg(&modrm, size), memory))
+            }
+            0x8f => {
+                let modrm = self.modrm()?;
+                if modrm.reg & 7 != 0 {
+                    return None;
+                }
+                text("pop", self.rm(&modrm, Size::Qword))
+            }
+            0x90 if self.rex & 1 != 0 => text("xchg", format!("r8,{}", self.register(0, size))),
+            0x90 if self.opsize > 0 => text("xchg", "ax,ax".to_string()),
+            0x90 if self.rep == Some(0xf3) => text("pause", String::new()),
+            0x90 => text("nop", String::new()),
+            0x91..=0x97 => {
+                let register = self.register((opcode & 7) | ((self.rex & 1) << 3), size);
+                text("xchg", format!("{},{}", register, self.register(0, size)))
+            }
+            0x98 => text(
+                match size {
+                    Size::Qword => "cdqe",
+                    Size::Word => "cbw",
+                    _ => "cwde",
+                },
+                String::new(),
+            ),
+            0x99 => text(
+                match size {
+                    Size::Qword => "cqo",
+                    Size::Word => "cwd",
+                    _ => "cdq",
+                },
+                String::new(),
+            ),
+            0xa4..=0xa7 | 0xaa..=0xaf => self.string(opcode),
+            0xa8 => text("test", format!("al,{}", self.immediate(Size::Byte)?)),
+            0xa9 => {
+                let register = self.register(0, size);
+                text("test", format!("{},{}", register, self.immediate(size)?))
+            }
+            0xb0..=0xb7 => {
+                let register = self.register((opcode & 7) | ((self.rex & 1) << 3), Size::Byte);
+                text(
+                    "mov",
+                    format!("{},{}", register, self.immediate(Size::Byte)?),
+                )
+            }
+            0xb8..=0xbf => {
+                let register = self.register((opcode & 7) | ((self.rex & 1) << 3), size);
+                match size {
+                    Size::Qword => {
+                        let value = self.signed(8)?;
+                        text("movabs", format!("{},{:#x}", register, value as u64))
+                    }
+                    _ => text("mov", format!("{},{}", register, self.immediate(size)?)),
+                }
+            }
+            0xc0 | 0xc1 | 0xd0..=0xd3 => {
+                let size = if opcode & 1 == 0 { Size::Byte } else { size };
+                let modrm = self.modrm()?;
+                let destination = self.rm(&modrm, size);
+                let count = match opcode {
+                    0xc0 | 0xc1 => format!("{:#x}", self.signed(1)? as u8),
+                    0xd0 | 0xd1 => "1".to_string(),
+                    _ => "cl".to_string(),
+                };
+                let mnemonic = SHIFTS[(modrm.reg & 7) as usize];
+                text(mnemonic, format!("{},{}", destination, count))
+            }
+            0xc2 => text("ret", format!("{:#x}", self.signed(2)? as u16)),
+            0xc3 => text(
+                match self.rep {
+                    Some(0xf3) => "repz ret",
+                    Some(_) => "bnd ret",
+                    None => "ret",
+                },
+                String::new(),
+            ),
+            0xc6 | 0xc7 => {
+                let size = if opcode == 0xc6 { Size::Byte } else { size };
+                let modrm = self.modrm()?;
+                if modrm.reg & 7 != 0 {
+                    return None;
+                }
+                let destination = self.rm(&modrm, size);
+                text("mov", format!("{},{}", destination, self.immediate(size)?))
+            }
+            0xc9 => text("leave", String::new()),
+            0xcc => text("int3", String::new()),
+            0xcd => text("int", format!("{:#x}", self.signed(1)? as u8)),
+            0xe0 => self.relative("loopne", 1),
+            0xe1 => self.relative("loope", 1),
+            0xe2 => self.relative("loop", 1),
+            0xe3 => self.relative("jrcxz", 1),
+            0xe8 => self.relative("call", 4),
+            0xe9 => self.relative("jmp", 4),
+            0xeb => self.relative("jmp", 1),
+            0xf4 => text("hlt", String::new()),
+            0xf5 => text("cmc", String::new()),
+            0xf8 => text("clc", String::new()),
+            0xf9 => text("stc", String::new()),
+            0xfc => text("cld", String::new()),
+            0xfd => text("std", String::new()),
+            0xf6 | 0xf7 => {
+                let size = if opcode == 0xf6 { Size::Byte } else { size };
+                let modrm = self.modrm()?;
+                let operand = self.rm(&modrm, size);
+                let mnemonic = UNARY[(modrm.reg & 7) as usize];
+                match modrm.reg & 7 {
+                    0 | 1 => text(mnemonic, format!("{},{}", operand, self.immediate(size)?)),
+                    _ => text(mnemonic, operand),
+                }
+            }
+            0xfe => {
+                let modrm = self.modrm()?;
+                let mnemonic = match modrm.reg & 7 {
+                    0 => "inc",
+                    1 => "dec",
+                    _ => return None,
+                };
+                text(mnemonic, self.rm(&modrm, Size::Byte))
+            }
+            0xff => {
+                let modrm = self.modrm()?;
+                let (mnemonic, size) = match modrm.reg & 7 {
+                    0 => ("inc", size),
+                    1 => ("dec", size),
+                    2 => ("call", Size::Qword),
+                    4 => ("jmp", Size::Qword),
+                    6 => ("push", Size::Qword),
+                    _ => return None,
+                };
+                let mut mnemonic = mnemonic.to_string();
+                if matches!(modrm.reg & 7, 2 | 4) {
+                    if self.segment == Some(0x3e) {
+                        self.segment_used = true;
+                        mnemonic.insert_str(0, "notrack ");
+                    }
+                    if self.rep == Some(0xf2) {
+                        mnemonic.insert_str(0, "bnd ");
+                    }
+                }
+                Some((mnemonic, self.rm(&modrm, size)))
+            }
+            0xd8..=0xdf => self.x87(opcode),
+            0x0f => self.two_bytes(size),
+            _ => None,
+        }
+    }
+
+    /// The x87 floating point instructions
+    fn x87(&mut self, opcode: u8) -> Option<(String, String)> {
+        let modrm = self.modrm()?;
+        let operation = (modrm.reg & 7) as usize;
+        if modrm.memory.is_some() {
+            let (mnemonic, size) = match (opcode, operation) {
+                (0xd8 | 0xdc, _) => {
+                    let size = if opcode == 0xd8 {
+                        Size::Dword
+                    } else {
+                        Size::Qword
+                    };
+                    (format!("f{}", X87_ARITHMETIC[operation]), size)
+                }
+                (0xda | 0xde, _) => {
+                    let size = if opcode == 0xda {
+                        Size::Dword
+                    } else {
+                        Size::Word
+                    };
+                    (format!("fi{}", X87_ARITHMETIC[operation]), size)
+                }
+                (0xd9, 0) => ("fld".to_string(), Size::Dword),
+                (0xd9, 2) => ("fst".to_string(), Size::Dword),
+                (0xd9, 3) => ("fstp".to_string(), Size::Dword),
+                (0xd9, 5) => ("fldcw".to_string(), Size::Word),
+                (0xd9, 7) => ("fnstcw".to_string(), Size::Word),
+                (0xdb, 0) => ("fild".to_string(), Size::Dword),
+                (0xdb, 1) => ("fisttp".to_string(), Size::Dword),
+                (0xdb, 2) => ("fist".to_string(), Size::Dword),
+                (0xdb, 3) => ("fistp".to_string(), Size::Dword),
+                (0xdb, 5) => ("fld".to_string(), Size::Tbyte),
+                (0xdb, 7) => ("fstp".to_string(), Size::Tbyte),
+                (0xdd, 0) => ("fld".to_string(), Size::Qword),
+                (0xdd, 1) => ("fisttp".to_string(), Size::Qword),
+                (0xdd, 2) => ("fst".to_string(), Size::Qword),
+                (0xdd, 3) => ("fstp".to_string(), Size::Qword),
+                (0xdd, 7) => ("fnstsw".to_string(), Size::Word),
+                (0xdf, 0) => ("fild".to_string(), Size::Word),
+                (0xdf, 1) => ("fisttp".to_string(), Size::Word),
+                (0xdf, 2) => ("fist".to_string(), Size::Word),
+                (0xdf, 3) => ("fistp".to_string(), Size::Word),
+                (0xdf, 4) => ("fbld".to_string(), Size::Tbyte),
+                (0xdf, 5) => ("fild".to_string(), Size::Qword),
+                (0xdf, 6) => ("fbstp".to_string(), Size::Tbyte),
+                (0xdf, 7) => ("fistp".to_string(), Size::Qword),
+                _ => return None,
+            };
+            return Some((mnemonic, self.rm(&modrm, size)));
+        }
+        let index = modrm.rm & 7;
+        let st = format!("st({})", index);
+        let to_st = format!("st,st({})", index);
+        let from_st = format!("st({}),st", index);
+        let text = |mnemonic: &str, operands: String| Some((mnemonic.to_string(), operands));
+        match (opcode, operation) {
+            (0xd8, 2 | 3) => Some((format!("f{}", X87_ARITHMETIC[operation]), st)),
+            (0xd8, _) => Some((format!("f{}", X87_ARITHMETIC[operation]), to_st)),
+            (0xd9, 0) => text("fld", st),
+            (0xd9, 1) => text("fxch", st),
+            (0xd9, 2) if index == 0 => text("fnop", String::new()),
+            (0xd9, 4..=7) => {
+                let mnemonic = X87_CONSTANTS[(operation - 4) * 8 + index as usize];
+                match mnemonic.is_empty() {
+                    true => None,
+                    false => text(mnemonic, String::new()),
+                }
+            }
+            (0xda, 0) => text("fcmovb", to_st),
+            (0xda, 1) => text("fcmove", to_st),
+            (0xda, 2) => text("fcmovbe", to_st),
+            (0xda, 3) => text("fcmovu", to_st),
+            (0xda, 5) if index == 1 => text("fucompp", String::new()),
+            (0xdb, 0) => text("fcmovnb", to_st),
+            (0xdb, 1) => text("fcmovne", to_st),
+            (0xdb, 2) => text("fcmovnbe", to_st),
+            (0xdb, 3) => text("fcmovnu", to_st),
+            (0xdb, 4) if index == 2 => text("fnclex", String::new()),
+            (0xdb, 4) if index == 3 => text("fninit", String::new()),
+            (0xdb, 5) => text("fucomi", to_st),
+            (0xdb, 6) => text("fcomi", to_st),
+            (0xdc, 2 | 3) => None,
+            // the reversed forms swap their names when st(i) is the destination
+            (0xdc, 4..=7) => Some((format!("f{}", X87_ARITHMETIC[operation ^ 1]), from_st)),
+            (0xdc, _) => Some((format!("f{}", X87_ARITHMETIC[operation]), from_st)),
+            (0xdd, 0) => text("ffree", st),
+            (0xdd, 2) => text("fst", st),
+            (0xdd, 3) => text("fstp", st),
+            (0xdd, 4) => text("fucom", st),
+            (0xdd, 5) => text("fucomp", st),
+            (0xde, 3) if index == 1 => text("fcompp", String::new()),
+            (0xde, 2 | 3) => None,
+            (0xde, 4..=7) => Some((format!("f{}p", X87_ARITHMETIC[operation ^ 1]), from_st)),
+            (0xde, _) => Some((format!("f{}p", X87_ARITHMETIC[operation]), from_st)),
+            (0xdf, 4) if index == 0 => text("fnstsw", "ax".to_string()),
+            (0xdf, 5) => text("fucomip", to_st),
+            (0xdf, 6) => text("fcomip", to_st),
+            _ => None,
+        }
+    }
+
+    /// The SSE variant selected by the prefixes, which then aren't
+    /// operand size or repeat prefixes
+    fn sse(&mut self) -> Sse {
+        self.mandatory = true;
+        match (self.rep, self.opsize > 0) {
+            (Some(0xf3), _) => Sse::Ss,
+            (Some(_), _) => Sse::Sd,
+            (None, true) => Sse::Pd,
+            (None, false) => Sse::Ps,
+        }
+    }
+
+    /// `xmm,xmm/m` operands, reversed when `store` is set
+    fn xmm(&mut self, size: Size, store: bool) -> Option<String> {
+        let modrm = self.modrm()?;
+        let rm = match &modrm.memory {
+            Some(memory) => format!("{} {}", size.ptr(), memory),
+            None => XMM[modrm.rm as usize].to_string(),
+        };
+        let reg = XMM[modrm.reg as usize];
+        Some(match store {
+            true => format!("{},{}", rm, reg),
+            false => format!("{},{}", reg, rm),
+        })
+    }
+
+    fn two_bytes(&mut self, size: Size) -> Option<(String, String)> {
+        let opcode = self.byte()?;
+        let text = |mnemonic: &str, operands: String| Some((mnemonic.to_string(), operands));
+        // the 32 or 64 bits size of the general registers of SSE instructions
+        let wide = if self.rex_w() {
+            Size::Qword
+        } else {
+            Size::Dword
+        };
+        match opcode {
+            0x05 => text("syscall", String::new()),
+            0x0b => text("ud2", String::new()),
+            0x31 => text("rdtsc", String::new()),
+            0xa2 => text("cpuid", String::new()),
+            0x1e if self.rep == Some(0xf3) && self.peek() == Some(0xfa) => {
+                self.at += 1;
+                self.mandatory = true;
+                text("endbr64", String::new())
+            }
+            0x18 => {
+                let modrm = self.modrm()?;
+                let memory = modrm.memory.as_ref()?;
+                let mnemonic = match modrm.reg & 7 {
+                    0 => "prefetchnta",
+                    1 => "prefetcht0",
+                    2 => "prefetcht1",
+                    3 => "prefetcht2",
+                    _ => return None,
+                };
+                text(mnemonic, format!("BYTE PTR {}", memory))
+            }
+            0x1e | 0x1f => {
+                let modrm = self.modrm()?;
+                text("nop", self.rm(&modrm, size))
+            }
+            0x10 | 0x11 => {
+                let sse = self.sse();
+                let mnemonic = match sse {
+                    Sse::Ps => "movups",
+                    Sse::Pd => "movupd",
+                    Sse::Ss => "movss",
+                    Sse::Sd => "movsd",
+                };
+                text(mnemonic, self.xmm(sse.size(), opcode == 0x11)?)
+            }
+            0x14 | 0x15 | 0x28 | 0x29 | 0x54..=0x57 | 0xc6 => {
+                let sse = self.sse();
+                let name = match opcode {
+                    0x14 => "unpckl",
+                    0x15 => "unpckh",
+                    0x28 | 0x29 => "mova",
+                    0x54 => "and",
+                    0x55 => "andn",
+                    0x56 => "or",
+                    0x57 => "xor",
+                    _ => "shuf",
+                };
+                if matches!(sse, Sse::Ss | Sse::Sd) {
+                    return None;
+                }
+                let mut operands = self.xmm(Size::Xmm, opcode == 0x29)?;
+                if opcode == 0xc6 {
+                    let _ = write!(operands, ",{:#x}", self.signed(1)? as u8);
+                }
+                Some((format!("{}{}", name, sse.suffix()), operands))
+            }
+            0x12 | 0x13 | 0x16 | 0x17 => {
+                let sse = self.sse();
+                let high = opcode >= 0x16;
+                let modrm = self.modrm()?;
+                let reg = XMM[modrm.reg as usize];
+                let (mnemonic, operands) = match (&modrm.memory, sse) {
+                    (None, Sse::Ps) if opcode & 1 == 0 => {
+                        let mnemonic = if high { "movlhps" } else { "movhlps" };
+                        (
+                            mnemonic.to_string(),
+                            format!("{},{}", reg, XMM[modrm.rm as usize]),
+                        )
+                    }
+                    (Some(memory), Sse::Ps | Sse::Pd) => {
+                        let half = if high { "h" } else { "l" };
+                        let memory = format!("QWORD PTR {}", memory);
+                        let operands = match opcode & 1 {
+                            0 => format!("{},{}", reg, memory),
+                            _ => format!("{},{}", memory, reg),
+                        };
+                        (format!("mov{}{}", half, sse.suffix()), operands)
+                    }
+                    _ => return None,
+                };
+                Some((mnemonic, operands))
+            }
+            0xc2 => {
+                let sse = self.sse();
+                let operands = self.xmm(sse.size(), false)?;
+                let predicate = self.byte()?;
+                match SSE_PREDICATES.get(predicate as usize) {
+                    Some(name) => Some((format!("cmp{}{}", name, sse.suffix()), operands)),
+                    None => Some((
+                        format!("cmp{}", sse.suffix()),
+                        format!("{},{:#x}", operands, predicate),
+                    )),
+                }
+            }
+            0x71..=0x73 if self.opsize > 0 && self.rep.is_none() => {
+                self.mandatory = true;
+                let modrm = self.modrm()?;
+                if modrm.memory.is_some() {
+                    return None;
+                }
+                let mnemonic = match (opcode, modrm.reg & 7) {
+                    (0x71, 2) => "psrlw",
+                    (0x71, 4) => "psraw",
+                    (0x71, 6) => "psllw",
+                    (0x72, 2) => "psrld",
+                    (0x72, 4) => "psrad",
+                    (0x72, 6) => "pslld",
+                    (0x73, 2) => "psrlq",
+                    (0x73, 3) => "psrldq",
+                    (0x73, 6) => "psllq",
+                    (0x73, 7) => "pslldq",
+                    _ => return None,
+                };
+                let count = self.byte()?;
+                text(mnemonic, format!("{},{:#x}", XMM[modrm.rm as usize], count))
+            }
+            0xc4 if self.opsize > 0 && self.rep.is_none() => {
+                self.mandatory = true;
+                let modrm = self.modrm()?;
+                let source = match &modrm.memory {
+                    Some(memory) => format!("WORD PTR {}", memory),
+                    None => REG32[modrm.rm as usize].to_string(),
+                };
+                let operands = format!("{},{}", XMM[modrm.reg as usize], source);
+                text("pinsrw", format!("{},{:#x}", operands, self.byte()?))
+            }
+            0xc5 if self.opsize > 0 && self.rep.is_none() => {
+                self.mandatory = true;
+                let modrm = self.modrm()?;
+                if modrm.memory.is_some() {
+                    return None;
+                }
+                let operands = format!("{},{}", REG32[modrm.reg as usize], XMM[modrm.rm as usize]);
+                text("pextrw", format!("{},{:#x}", operands, self.byte()?))
+            }
+            0x2a => {
+                let sse = self.sse();
+                if !matches!(sse, Sse::Ss | Sse::Sd) {
+                    return None;
+                }
+                let modrm = self.modrm()?;
+                let source = self.rm(&modrm, wide);
+                let mnemonic = format!("cvtsi2{}", sse.suffix());
+                Some((mnemonic, format!("{},{}", XMM[modrm.reg as usize], source)))
+            }
+            0x2c | 0x2d => {
+                let sse = self.sse();
+                if !matches!(sse, Sse::Ss | Sse::Sd) {
+                    return None;
+                }
+                let modrm = self.modrm()?;
+                let source = match &modrm.memory {
+                    Some(memory) => format!("{} {}", sse.size().ptr(), memory),
+                    None => XMM[modrm.rm as usize].to_string(),
+                };
+                let truncate = if opcode == 0x2c { "t" } else { "" };
+                let mnemonic = format!("cvt{}{}2si", truncate, sse.suffix());
+                let destination = self.reg(&modrm, wide);
+                Some((mnemonic, format!("{},{}", destination, source)))
+            }
+            0x2e | 0x2f => {
+                let (suffix, size) = match self.sse() {
+                    Sse::Ps => ("s", Size::Dword),
+                    Sse::Pd => ("d", Size::Qword),
+                    _ => return None,
+                };
+                let name = if opcode == 0x2e { "ucomis" } else { "comis" };
+                let operands = self.xmm(size, false)?;
+                Some((format!("{}{}", name, suffix), operands))
+            }
+            0x40..=0x4f => {
+                let mnemonic = format!("cmov{}", CONDITIONS[(opcode & 15) as usize]);
+                Some((mnemonic, self.binary(size, true)?))
+            }
+            0x51 | 0x58 | 0x59 | 0x5c..=0x5f => {
+                let sse = self.sse();
+                let name = match opcode {
+                    0x51 => "sqrt",
+                    0x58 => "add",
+                    0x59 => "mul",
+                    0x5c => "sub",
+                    0x5d => "min",
+                    0x5e => "div",
+                    _ => "max",
+                };
+                let operands = self.xmm(sse.size(), false)?;
+                Some((format!("{}{}", name, sse.suffix()), operands))
+            }
+            0x5a => {
+                let sse = self.sse();
+                let (mnemonic, size) = match sse {
+                    Sse::Ps => ("cvtps2pd", Size::Qword),
+                    Sse::Pd => ("cvtpd2ps", Size::Xmm),
+                    Sse::Ss => ("cvtss2sd", Size::Dword),
+                    Sse::Sd => ("cvtsd2ss", Size::Qword),
+                };
+                text(mnemonic, self.xmm(size, false)?)
+            }
+            0x5b => {
+                let mnemonic = match self.sse() {
+                    Sse::Ps => "cvtdq2ps",
+                    Sse::Pd => "cvtps2dq",
+                    Sse::Ss => "cvttps2dq",
+                    Sse::Sd => return None,
+                };
+                text(mnemonic, self.xmm(Size::Xmm, false)?)
+            }
+            0x60..=0x6d
+            | 0x74..=0x76
+            | 0xd1..=0xd5
+            | 0xd8..=0xdf
+            | 0xe0..=0xe5
+            | 0xe8..=0xef
+            | 0xf1..=0xf6
+            | 0xf8..=0xfe
+                if self.opsize > 0 && self.rep.is_none() =>
+            {
+                self.mandatory = true;
+                let mnemonic = match opcode {
+                    0x60 => "punpcklbw",
+                    0x61 => "punpcklwd",
+                    0x62 => "punpckldq",
+                    0x63 => "packsswb",
+                    0x64 => "pcmpgtb",
+                    0x65 => "pcmpgtw",
+                    0x66 => "pcmpgtd",
+                    0x67 => "packuswb",
+                    0x68 => "punpckhbw",
+                    0x69 => "punpckhwd",
+                    0x6a => "punpckhdq",
+                    0x6b => "packssdw",
+                    0x6c => "punpcklqdq",
+                    0x6d => "punpckhqdq",
+                    0x74 => "pcmpeqb",
+                    0x75 => "pcmpeqw",
+                    0x76 => "pcmpeqd",
+                    0xd1 => "psrlw",
+                    0xd2 => "psrld",
+                    0xd3 => "psrlq",
+                    0xd4 => "paddq",
+                    0xd5 => "pmullw",
+                    0xd8 => "psubusb",
+                    0xd9 => "psubusw",
+                    0xda => "pminub",
+                    0xdb => "pand",
+                    0xdc => "paddusb",
+                    0xdd => "paddusw",
+                    0xde => "pmaxub",
+                    0xdf => "pandn",
+                    0xe0 => "pavgb",
+                    0xe1 => "psraw",
+                    0xe2 => "psrad",
+                    0xe3 => "pavgw",
+                    0xe4 => "pmulhuw",
+                    0xe5 => "pmulhw",
+                    0xe8 => "psubsb",
+                    0xe9 => "psubsw",
+                    0xea => "pminsw",
+                    0xeb => "por",
+                    0xec => "paddsb",
+                    0xed => "paddsw",
+                    0xee => "pmaxsw",
+                    0xef => "pxor",
+                    0xf1 => "psllw",
+                    0xf2 => "pslld",
+                    0xf3 => "psllq",
+                    0xf4 => "pmuludq",
+                    0xf5 => "pmaddwd",
+                    0xf6 => "psadbw",
+                    0xf8 => "psubb",
+                    0xf9 => "psubw",
+                    0xfa => "psubd",
+                    0xfb => "psubq",
+                    0xfc => "paddb",
+                    0xfd => "paddw",
+                    _ => "paddd",
+                };
+                text(mnemonic, self.xmm(Size::Xmm, false)?)
+            }
+            0x6e | 0x7e if self.opsize > 0 && self.rep.is_none() => {
+                self.mandatory = true;
+                let modrm = self.modrm()?;
+                let general = self.rm(&modrm, wide);
+                let mnemonic = if self.rex_w() { "movq" } else { "movd" };
+                let xmm = XMM[modrm.reg as usize];
+                let operands = match opcode {
+                    0x6e => format!("{},{}", xmm, general),
+                    _ => format!("{},{}", general, xmm),
+                };
+                text(mnemonic, operands)
+            }
+            0x7e if self.rep == Some(0xf3) => {
+                self.mandatory = true;
+                text("movq", self.xmm(Size::Qword, false)?)
+            }
+            0xd6 if self.opsize > 0 && self.rep.is_none() => {
+                self.mandatory = true;
+                text("movq", self.xmm(Size::Qword, true)?)
+            }
+            0x6f | 0x7f if self.opsize > 0 || self.rep == Some(0xf3) => {
+                let mnemonic = match self.sse() {
+                    Sse::Pd => "movdqa",
+                    Sse::Ss => "movdqu",
+                    _ => return None,
+                };
+                text(mnemonic, self.xmm(Size::Xmm, opcode == 0x7f)?)
+            }
+            0x70 if self.opsize > 0 || self.rep.is_some() => {
+                let mnemonic = match self.sse() {
+                    Sse::Pd => "pshufd",
+                    Sse::Ss => "pshufhw",
+                    _ => "pshuflw",
+                };
+                let operands = self.xmm(Size::Xmm, false)?;
+                text(
+                    mnemonic,
+                    format!("{},{:#x}", operands, self.signed(1)? as u8),
+                )
+            }
+            0x50 if self.rep.is_none() => {
+                let sse = self.sse();
+                let modrm = self.modrm()?;
+                if modrm.memory.is_some() {
+                    return None;
+                }
+                let operands = format!("{},{}", REG32[modrm.reg as usize], XMM[modrm.rm as usize]);
+                Some((format!("movmsk{}", sse.suffix()), operands))
+            }
+            0xd7 if self.opsize > 0 && self.rep.is_none() => {
+                self.mandatory = true;
+                let modrm = self.modrm()?;
+                if modrm.memory.is_some() {
+                    return None;
+                }
+                let operands = format!("{},{}", REG32[modrm.reg as usize], XMM[modrm.rm as usize]);
+                text("pmovmskb", operands)
+            }
+            0x80..=0x8f => self.relative(&format!("j{}", CONDITIONS[(opcode & 15) as usize]), 4),
+            0x90..=0x9f => {
+                let modrm = self.modrm()?;
+                let mnemonic = format!("set{}", CONDITIONS[(opcode & 15) as usize]);
+                Some((mnemonic, self.rm(&modrm, Size::Byte)))
+            }
+            0xa3 | 0xab | 0xb3 | 0xbb => {
+                let mnemonic = match opcode {
+                    0xa3 => "bt",
+                    0xab => "bts",
+                    0xb3 => "btr",
+                    _ => "btc",
+                };
+                text(mnemonic, self.binary(size, false)?)
+            }
+            0xba => {
+                let modrm = self.modrm()?;
+                let mnemonic = match modrm.reg & 7 {
+                    4 => "bt",
+                    5 => "bts",
+                    6 => "btr",
+                    7 => "btc",
+                    _ => return None,
+                };
+                let operand = self.rm(&modrm, size);
+                text(
+                    mnemonic,
+                    format!("{},{:#x}", operand, self.signed(1)? as u8),
+                )
+            }
+            0xa4 | 0xa5 | 0xac | 0xad => {
+                let mnemonic = if opcode < 0xa8 { "shld" } else { "shrd" };
+                let operands = self.binary(size, false)?;
+                let count = match opcode & 1 {
+                    0 => format!("{:#x}", self.signed(1)? as u8),
+                    _ => "cl".to_string(),
+                };
+                text(mnemonic, format!("{},{}", operands, count))
+            }
+            0xae => {
+                let at = self.at;
+                let modrm = self.modrm()?;
+                match (modrm.memory.is_some(), modrm.reg & 7) {
+                    (false, 5) => text("lfence", String::new()),
+                    (false, 6) => text("mfence", String::new()),
+                    (false, 7) => text("sfence", String::new()),
+                    (true, 7) => text("clflush", self.rm(&modrm, Size::Byte)),
+                    _ => {
+                        self.at = at;
+                        None
+                    }
+                }
+            }
+            0xaf => text("imul", self.binary(size, true)?),
+            0xb0 => text("cmpxchg", self.binary(Size::Byte, false)?),
+            0xb1 => text("cmpxchg", self.binary(size, false)?),
+            0xc0 => text("xadd", self.binary(Size::Byte, false)?),
+            0xc1 => text("xadd", self.binary(size, false)?),
+            0xb6 | 0xb7 | 0xbe | 0xbf => {
+                let source = if opcode & 1 == 0 {
+                    Size::Byte
+                } else {
+                    Size::Word
+                };
+                let modrm = self.modrm()?;
+                let mnemonic = if opcode < 0xb8 { "movzx" } else { "movsx" };
+                let operands = format!("{},{}", self.reg(&modrm, size), self.rm(&modrm, source));
+                text(mnemonic, operands)
+            }
+            0xb8 if self.rep == Some(0xf3) => {
+                self.mandatory = true;
+                text("popcnt", self.binary(self.size(), true)?)
+            }
+            0xbc | 0xbd => {
+                let mnemonic = match (self.rep, opcode) {
+                    (Some(0xf3), 0xbc) => "tzcnt",
+                    (Some(0xf3), _) => "lzcnt",
+                    (_, 0xbc) => "bsf",
+                    _ => "bsr",
+                };
+                self.mandatory = self.rep == Some(0xf3);
+                text(mnemonic, self.binary(self.size(), true)?)
+            }
+            0xc8..=0xcf => {
+                let register = self.register((opcode & 7) | ((self.rex & 1) << 3), size);
+                text("bswap", register.to_string())
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,346 @@
+//! Filter expressions, to keep some symbols, sections or segments
+//!
+//! An expression compares the fields of a [`Record`], the same fields the
+//! output templates print, with literals:
+//! `type==FUNC && size>4096 && section==".text"`. The operators are `==`,
+//! `!=`, `<`, `<=`, `>`, `>=` and `~` (contains) or `!~`, combined with
+//! `&&`, `||`, `!` and parentheses. A literal is a number, decimal or
+//! with a `0x`, `0o` or `0b` prefix, a word, or text in double quotes
+//! with `\"` and `\\` escapes. A field alone is true when it isn't 0 or
+//! empty.
+//!
+//! Numbers are compared as numbers, also with the text fields holding a
+//! number like `ndx`. Text is compared byte by byte, a number field is
+//! never equal to a word.
+//!
+//! ```
+//! use readbin::utils::filter::Filter;
+//! use readbin::utils::template::{Record, Value};
+//!
+//! struct Entry(&'static str, u64);
+//!
+//! impl Record for Entry {
+//!     const FIELDS: &'static [&'static str] = &["name", "size"];
+//!
+//!     fn field(&self, name: &str) -> Option<Value<'_>> {
+//!         match name {
+//!             "name" => Some(Value::from(self.0)),
+//!             "size" => Some(Value::from(self.1)),
+//!             _ => None,
+//!         }
+//!     }
+//! }
+//!
+//! let filter = Filter::parse("size >= 0x10 && !(name ~ init)").unwrap();
+//! assert!(filter.check::<Entry>().is_ok());
+//! assert!(filter.matches(&Entry("main", 32)));
+//! assert!(!filter.matches(&Entry("_init", 32)));
+//! assert!(!filter.matches(&Entry("main", 8)));
+//! ```
+use crate::alloc_prelude::*;
+use crate::utils::template::{Record, Value};
+use crate::{Error, Result};
+use alloc::boxed::Box;
+use core::cmp::Ordering;
+
+/// How a field is compared with a literal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    Excludes,
+}
+
+/// A literal, with its value when it is a number
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Literal {
+    text: String,
+    number: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Field(String),
+    Compare(String, Op, Literal),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+/// A parsed filter expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    expr: Expr,
+}
+
+/// Parse a decimal number or one with a `0x`, `0o` or `0b` prefix
+fn number(text: &str) -> Option<u64> {
+    let (digits, radix) = match text.get(..2) {
+        Some("0x" | "0X") => (&text[2..], 16),
+        Some("0o" | "0O") => (&text[2..], 8),
+        Some("0b" | "0B") => (&text[2..], 2),
+        _ => (text, 10),
+    };
+    // from_str_radix accepts a sign
+    if !digits.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    u64::from_str_radix(digits, radix).ok()
+}
+
+/// Characters ending a word
+fn special(c: char) -> bool {
+    c.is_whitespace() || "()!=<>~&|\"".contains(c)
+}
+
+/// Split an expression into tokens with the index of their first character
+fn tokens(text: &str) -> Result<Vec<(usize, Token)>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let mut next = |expected: char| chars.next_if(|(_, c)| *c == expected).is_some();
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '=' if next('=') => Token::Op(Op::Eq),
+            '!' if next('=') => Token::Op(Op::Ne),
+            '!' if next('~') => Token::Op(Op::Excludes),
+            '!' => Token::Not,
+            '<' if next('=') => Token::Op(Op::Le),
+            '<' => Token::Op(Op::Lt),
+            '>' if next('=') => Token::Op(Op::Ge),
+            '>' => Token::Op(Op::Gt),
+            '~' => Token::Op(Op::Contains),
+            '&' if next('&') => Token::And,
+            '|' if next('|') => Token::Or,
+            '"' => {
+                let mut quoted = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, c @ ('"' | '\\'))) => quoted.push(c),
+                            Some((at, _)) => return Err(Error::InvalidFilter(at)),
+                            None => return Err(Error::InvalidFilter(text.len())),
+                        },
+                        Some((_, c)) => quoted.push(c),
+                        None => return Err(Error::InvalidFilter(index)),
+                    }
+                }
+                Token::Quoted(quoted)
+            }
+            c if special(c) => return Err(Error::InvalidFilter(index)),
+            c => {
+                let mut word = String::from(c);
+                while let Some((_, c)) = chars.next_if(|(_, c)| !special(*c)) {
+                    word.push(c);
+                }
+                Token::Word(word)
+            }
+        };
+        tokens.push((index, token));
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent over the tokens, `||` binds looser than `&&`
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    at: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.at).map(|(_, token)| token)
+    }
+
+    /// Index of the character of the current token, for the errors
+    fn index(&self) -> usize {
+        self.tokens
+            .get(self.at)
+            .map_or(self.end, |(index, _)| *index)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.peek() == Some(token);
+        self.at += found as usize;
+        found
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.eat(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.not()?;
+        while self.eat(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr> {
+        if self.eat(&Token::Not) {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        if self.eat(&Token::Open) {
+            let expr = self.or()?;
+            if !self.eat(&Token::Close) {
+                return Err(Error::InvalidFilter(self.index()));
+            }
+            return Ok(expr);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr> {
+        let field = match self.peek() {
+            Some(Token::Word(word))
+                if word.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+            {
+                word.clone()
+            }
+            _ => return Err(Error::InvalidFilter(self.index())),
+        };
+        self.at += 1;
+        let op = match self.peek() {
+            Some(Token::Op(op)) => *op,
+            _ => return Ok(Expr::Field(field)),
+        };
+        self.at += 1;
+        let literal = match self.peek() {
+            Some(Token::Word(word)) => Literal {
+                number: number(word),
+                text: word.clone(),
+            },
+            Some(Token::Quoted(text)) => Literal {
+                number: None,
+                text: text.clone(),
+            },
+            _ => return Err(Error::InvalidFilter(self.index())),
+        };
+        self.at += 1;
+        Ok(Expr::Compare(field, op, literal))
+    }
+}
+
+/// Compare a field with a literal
+fn compare(value: &Value<'_>, op: Op, literal: &Literal) -> bool {
+    let (ordering, contains) = match (value, literal.number) {
+        (Value::Number(number), Some(expected)) => (number.cmp(&expected), false),
+        // words are never numbers
+        (Value::Number(_), None) => return op == Op::Ne || op == Op::Excludes,
+        (Value::Text(text), expected) => match (number(text.trim()), expected) {
+            (Some(number), Some(expected)) => (number.cmp(&expected), false),
+            _ => (
+                text.as_bytes().cmp(literal.text.as_bytes()),
+                text.contains(literal.text.as_str()),
+            ),
+        },
+    };
+    match op {
+        Op::Eq => ordering == Ordering::Equal,
+        Op::Ne => ordering != Ordering::Equal,
+        Op::Lt => ordering == Ordering::Less,
+        Op::Le => ordering != Ordering::Greater,
+        Op::Gt => ordering == Ordering::Greater,
+        Op::Ge => ordering != Ordering::Less,
+        Op::Contains => contains || ordering == Ordering::Equal,
+        Op::Excludes => !contains && ordering != Ordering::Equal,
+    }
+}
+
+impl Expr {
+    fn fields<'a>(&'a self, fields: &mut Vec<&'a str>) {
+        match self {
+            Expr::Field(name) | Expr::Compare(name, ..) => fields.push(name),
+            Expr::Not(expr) => expr.fields(fields),
+            Expr::And(left, right) | Expr::Or(left, right) => {
+                left.fields(fields);
+                right.fields(fields);
+            }
+        }
+    }
+
+    fn matches<R: Record>(&self, record: &R) -> bool {
+        match self {
+            Expr::Field(name) => match record.field(name) {
+                Some(Value::Number(number)) => number != 0,
+                Some(Value::Text(text)) => !text.is_empty(),
+                None => false,
+            },
+            Expr::Compare(name, op, literal) => record
+                .field(name)
+                .is_some_and(|value| compare(&value, *op, literal)),
+            Expr::Not(expr) => !expr.matches(record),
+            Expr::And(left, right) => left.matches(record) && right.matches(record),
+            Expr::Or(left, right) => left.matches(record) || right.matches(record),
+        }
+    }
+}
+
+impl Filter {
+    /// Parse an expression
+    ///
+    /// Fails with the index of the character where the expression stops
+    /// making sense, the length of the text if it ends too early.
+    pub fn parse(text: &str) -> Result<Filter> {
+        let mut parser = Parser {
+            tokens: tokens(text)?,
+            at: 0,
+            end: text.len(),
+        };
+        let expr = parser.or()?;
+        if parser.at < parser.tokens.len() {
+            return Err(Error::InvalidFilter(parser.index()));
+        }
+        Ok(Filter { expr })
+    }
+
+    /// The names of the fields used, in order
+    pub fn fields(&self) -> Vec<&str> {
+        let mut fields = Vec::new();
+        self.expr.fields(&mut fields);
+        fields
+    }
+
+    /// Check that every field is one of the fields of `R`
+    pub fn check<R: Record>(&self) -> Result<()> {
+        match self
+            .fields()
+            .into_iter()
+            .find(|name| !R::FIELDS.contains(name))
+        {
+            Some(name) => Err(Error::UnknownField(name.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether the record is kept, false if it has no field of a name
+    pub fn matches<R: Record>(&self, record: &R) -> bool {
+        self.expr.matches(record)
+    }
+}
@@ -0,0 +1,55 @@
+use core::fmt;
+
+/// Canonical dump of some bytes, like `readelf -x`
+///
+/// Each line holds the address of its first byte, 16 bytes in four
+/// groups of four and their printable ASCII characters:
+///
+/// ```text
+///   0x00000318 2f6c6962 36342f6c 642d6c69 6e75782d /lib64/ld-linux-
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct HexDump<'a> {
+    /// the bytes to dump
+    pub data: &'a [u8],
+    /// address of the first byte
+    pub address: u64,
+}
+
+impl<'a> HexDump<'a> {
+    /// Number of bytes per line
+    pub const LINE: usize = 16;
+
+    /// Dump the bytes as if they were loaded at `address`
+    pub fn new(data: &'a [u8], address: u64) -> Self {
+        HexDump { data, address }
+    }
+}
+
+impl fmt::Display for HexDump<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, line) in self.data.chunks(Self::LINE).enumerate() {
+            let address = self.address.wrapping_add((index * Self::LINE) as u64);
+            write!(f, "  0x{:08x} ", address)?;
+            for group in 0..Self::LINE / 4 {
+                for byte in 0..4 {
+                    match line.get(group * 4 + byte) {
+                        Some(value) => write!(f, "{:02x}", value)?,
+                        None => write!(f, "  ")?,
+                    }
+                }
+                write!(f, " ")?;
+            }
+            for byte in line {
+                let c = if byte.is_ascii_graphic() || *byte == b' ' {
+                    *byte as char
+                } else {
+                    '.'
+                };
+                write!(f, "{}", c)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
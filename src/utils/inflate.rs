@@ -0,0 +1,384 @@
+//! A DEFLATE decoder for the zlib streams of compressed sections
+//!
+//! A zlib stream is a 2 bytes header, DEFLATE blocks and the Adler-32 of
+//! the decoded bytes. Each block is stored, or compressed with the fixed
+//! or with its own Huffman codes: literal bytes and `(length, distance)`
+//! pairs copying earlier output. The codes are decoded bit by bit from
+//! their lengths, like `puff.c` of zlib.
+//! Sources:
+//! * <https://www.rfc-editor.org/rfc/rfc1950>
+//! * <https://www.rfc-editor.org/rfc/rfc1951>
+use crate::alloc_prelude::*;
+use crate::{Error, Result};
+
+/// Base of the lengths of the symbols 257 to 285
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+
+/// Extra bits of the lengths of the symbols 257 to 285
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+/// Base of the distances of the symbols 0 to 29
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+
+/// Extra bits of the distances of the symbols 0 to 29
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Order of the code lengths of the code lengths alphabet
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Maximum length of a code
+const MAX_BITS: usize = 15;
+
+/// Reads the bits of a stream, least significant first
+struct Bits<'a> {
+    data: &'a [u8],
+    offset: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl<'a> Bits<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Bits {
+            data,
+            offset: 0,
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    fn error(&self) -> Error {
+        Error::InvalidCompressedData(self.offset as u64)
+    }
+
+    fn bits(&mut self, count: u32) -> Result<u32> {
+        while self.count < count {
+            let byte = *self.data.get(self.offset).ok_or_else(|| self.error())?;
+            self.buffer |= (byte as u32) << self.count;
+            self.offset += 1;
+            self.count += 8;
+        }
+        let value = self.buffer & ((1u64 << count) - 1) as u32;
+        self.buffer = self.buffer.checked_shr(count).unwrap_or(0);
+        self.count -= count;
+        Ok(value)
+    }
+
+    /// Skip to the next byte boundary
+    fn align(&mut self) {
+        self.buffer = 0;
+        self.count = 0;
+    }
+}
+
+/// A canonical Huffman code, the number of codes of each length and the
+/// symbols ordered by code
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    /// Build the code from the length of the code of each symbol, 0 for
+    /// the unused symbols. Fails if the lengths describe too many codes.
+    fn new(lengths: &[u8]) -> Option<Self> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for length in lengths {
+            counts[*length as usize] += 1;
+        }
+        let mut left = 1i32;
+        for count in &counts[1..] {
+            left = (left << 1) - *count as i32;
+            if left < 0 {
+                return None;
+            }
+        }
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for length in 1..=MAX_BITS {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; offsets[MAX_BITS + 1] as usize];
+        for (symbol, length) in lengths.iter().enumerate() {
+            if *length != 0 {
+                let offset = &mut offsets[*length as usize];
+                symbols[*offset as usize] = symbol as u16;
+                *offset += 1;
+            }
+        }
+        Some(Huffman { counts, symbols })
+    }
+
+    fn decode(&self, bits: &mut Bits) -> Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for count in &self.counts[1..] {
+            code |= bits.bits(1)? as i32;
+            let count = *count as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(bits.error())
+    }
+}
+
+/// The codes of the blocks compressed with the fixed codes
+fn fixed() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    let literals = Huffman::new(&lengths).expect("complete code");
+    let distances = Huffman::new(&[5; 30]).expect("complete code");
+    (literals, distances)
+}
+
+/// Read the codes of a block compressed with its own codes
+fn dynamic(bits: &mut Bits) -> Result<(Huffman, Huffman)> {
+    let literals = bits.bits(5)? as usize + 257;
+    let distances = bits.bits(5)? as usize + 1;
+    let code_lengths = bits.bits(4)? as usize + 4;
+    if literals > 286 || distances > 30 {
+        return Err(bits.error());
+    }
+    let mut lengths = [0u8; 19];
+    for index in &CODE_LENGTH_ORDER[..code_lengths] {
+        lengths[*index] = bits.bits(3)? as u8;
+    }
+    let code = Huffman::new(&lengths).ok_or_else(|| bits.error())?;
+    let mut lengths = vec![0u8; literals + distances];
+    let mut index = 0;
+    while index < lengths.len() {
+        let symbol = code.decode(bits)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 if index > 0 => (lengths[index - 1], 3 + bits.bits(2)? as usize),
+            17 => (0, 3 + bits.bits(3)? as usize),
+            18 => (0, 11 + bits.bits(7)? as usize),
+            _ => return Err(bits.error()),
+        };
+        let end = index + repeat;
+        lengths
+            .get_mut(index..end)
+            .ok_or_else(|| bits.error())?
+            .fill(value);
+        index = end;
+    }
+    if lengths[256] == 0 {
+        return Err(bits.error());
+    }
+    let literal = Huffman::new(&lengths[..literals]).ok_or_else(|| bits.error())?;
+    let distance = Huffman::new(&lengths[literals..]).ok_or_else(|| bits.error())?;
+    Ok((literal, distance))
+}
+
+/// Decode the symbols of a compressed block up to its end of block
+fn codes(
+    bits: &mut Bits,
+    out: &mut Vec<u8>,
+    limit: usize,
+    (literals, distances): &(Huffman, Huffman),
+) -> Result<()> {
+    loop {
+        let symbol = literals.decode(bits)? as usize;
+        match symbol {
+            0..=255 if out.len() < limit => out.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let index = symbol - 257;
+                let length =
+                    LENGTH_BASE[index] as usize + bits.bits(LENGTH_EXTRA[index] as u32)? as usize;
+                let index = distances.decode(bits)? as usize;
+                if index >= DISTANCE_BASE.len() {
+                    return Err(bits.error());
+                }
+                let distance = DISTANCE_BASE[index] as usize
+                    + bits.bits(DISTANCE_EXTRA[index] as u32)? as usize;
+                if distance > out.len() || out.len() + length > limit {
+                    return Err(bits.error());
+                }
+                let start = out.len() - distance;
+                for at in start..start + length {
+                    out.push(out[at]);
+                }
+            }
+            _ => return Err(bits.error()),
+        }
+    }
+}
+
+/// Decode a raw DEFLATE stream, at most `limit` bytes
+///
+/// Returns the number of bytes of the stream.
+pub fn inflate(data: &[u8], out: &mut Vec<u8>, limit: usize) -> Result<usize> {
+    let mut bits = Bits::new(data);
+    loop {
+        let last = bits.bits(1)? == 1;
+        match bits.bits(2)? {
+            0 => {
+                bits.align();
+                let header = data
+                    .get(bits.offset..bits.offset + 4)
+                    .ok_or_else(|| bits.error())?;
+                let length = u16::from_le_bytes([header[0], header[1]]);
+                let complement = u16::from_le_bytes([header[2], header[3]]);
+                if length != !complement {
+                    return Err(bits.error());
+                }
+                let start = bits.offset + 4;
+                let stored = data
+                    .get(start..start + length as usize)
+                    .filter(|stored| out.len() + stored.len() <= limit)
+                    .ok_or_else(|| bits.error())?;
+                out.extend_from_slice(stored);
+                bits.offset = start + stored.len();
+            }
+            1 => codes(&mut bits, out, limit, &fixed())?,
+            2 => {
+                let tables = dynamic(&mut bits)?;
+                codes(&mut bits, out, limit, &tables)?
+            }
+            _ => return Err(bits.error()),
+        }
+        if last {
+            return Ok(bits.offset);
+        }
+    }
+}
+
+/// The Adler-32 checksum of zlib streams
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+/// Decode a zlib stream of `size` bytes
+///
+/// Fails if the header isn't a DEFLATE one without preset dictionary, if
+/// the stream is invalid or truncated, if it doesn't decode to `size`
+/// bytes or if the checksum doesn't match.
+pub fn zlib(data: &[u8], size: usize) -> Result<Vec<u8>> {
+    let valid = data.len() >= 2
+        && data[0] & 0x0f == 8
+        && data[0] >> 4 <= 7
+        && data[1] & 0x20 == 0
+        && u16::from_be_bytes([data[0], data[1]]).is_multiple_of(31);
+    if !valid {
+        return Err(Error::InvalidCompressedData(0));
+    }
+    // the size comes from the file, only trust it up to a ratio
+    let mut out = Vec::with_capacity(size.min(data.len().saturating_mul(64)));
+    let end = 2 + inflate(&data[2..], &mut out, size).map_err(|err| match err {
+        Error::InvalidCompressedData(offset) => Error::InvalidCompressedData(offset + 2),
+        err => err,
+    })?;
+    let checksum = data
+        .get(end..end + 4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+    match checksum {
+        _ if out.len() != size => Err(Error::InvalidCompressedData(end as u64)),
+        Some(checksum) if checksum == adler32(&out) => Ok(out),
+        _ => Err(Error::InvalidCompressedData(end as u64)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `readbin\n` in a stored block
+    const STORED: &[u8] = &[
+        0x78, 0x01, 0x01, 0x08, 0x00, 0xf7, 0xff, 0x72, 0x65, 0x61, 0x64, 0x62, 0x69, 0x6e, 0x0a,
+        0x0e, 0x3e, 0x02, 0xe0,
+    ];
+
+    /// `abcabcabcabcabc readbin` with the fixed codes
+    const FIXED: &[u8] = &[
+        0x78, 0xda, 0x4b, 0x4c, 0x4a, 0x4e, 0x44, 0x42, 0x0a, 0x45, 0xa9, 0x89, 0x29, 0x49, 0x99,
+        0x79, 0x00, 0x68, 0x44, 0x08, 0xb4,
+    ];
+
+    /// 40 `a` with the fixed codes, a copy overlapping its own output
+    const RUN: &[u8] = &[
+        0x78, 0xda, 0x4b, 0x4c, 0x24, 0x0e, 0x00, 0x00, 0x36, 0xeb, 0x0f, 0x29,
+    ];
+
+    /// [`TEXT`] with the codes of the block
+    const DYNAMIC: &[u8] = &[
+        0x78, 0xda, 0xb5, 0x8c, 0xc9, 0x11, 0x80, 0x30, 0x0c, 0xc4, 0x5a, 0x59, 0xfe, 0x0c, 0x3d,
+        0x05, 0x70, 0x12, 0x73, 0x39, 0x37, 0x47, 0xf5, 0x78, 0xe8, 0x81, 0xe7, 0x8e, 0xa4, 0x2d,
+        0x9e, 0x10, 0x2b, 0x4f, 0x2b, 0xc6, 0x24, 0xe7, 0x01, 0x2b, 0x17, 0x96, 0xba, 0x87, 0x0c,
+        0x69, 0x94, 0x50, 0x14, 0x6f, 0xe6, 0xb9, 0x31, 0x8b, 0x1b, 0xbe, 0xf5, 0x8f, 0x1c, 0x8c,
+        0x7a, 0xfb, 0x8d, 0x51, 0xa5, 0x93, 0x8b, 0x87, 0xe5, 0x46, 0x8a, 0x1e, 0x3a, 0xb0, 0x71,
+        0xac, 0x92, 0xb4, 0x75, 0xb9, 0xff, 0x1e, 0xba, 0x17, 0x6d, 0x07, 0x41, 0x88,
+    ];
+
+    /// A text with repeated words, for which zlib builds its own codes
+    fn text() -> Vec<u8> {
+        let mut text = b"the quick brown fox jumps over the lazy dog. ".repeat(3);
+        text.extend(b"pack my box with five dozen liquor jugs, jump!");
+        text
+    }
+
+    #[test]
+    fn stored_block() {
+        assert_eq!(zlib(STORED, 8), Ok(b"readbin\n".to_vec()));
+    }
+
+    #[test]
+    fn fixed_codes() {
+        assert_eq!(zlib(FIXED, 23), Ok(b"abcabcabcabcabc readbin".to_vec()));
+        assert_eq!(zlib(RUN, 40), Ok(vec![b'a'; 40]));
+    }
+
+    #[test]
+    fn dynamic_codes() {
+        let text = text();
+        assert_eq!(text.len(), 181);
+        assert_eq!(zlib(DYNAMIC, text.len()), Ok(text));
+    }
+
+    #[test]
+    fn invalid_streams() {
+        let err = |offset| Err(Error::InvalidCompressedData(offset));
+        let mut data = DYNAMIC.to_vec();
+        data[DYNAMIC.len() - 1] ^= 1;
+        assert_eq!(zlib(&data, 181), err(84));
+        assert!(zlib(&DYNAMIC[..40], 181).is_err());
+        // the size of the section must be the decoded one
+        assert_eq!(zlib(STORED, 7), err(3));
+        assert_eq!(zlib(STORED, 9), err(15));
+        // a stored block whose length isn't the complement of the next one
+        let mut data = STORED.to_vec();
+        data[5] ^= 1;
+        assert_eq!(zlib(&data, 8), err(3));
+        // the reserved block type
+        assert_eq!(zlib(&[0x78, 0x01, 0x07], 0), err(3));
+        assert_eq!(zlib(&STORED[1..], 8), err(0));
+    }
+}
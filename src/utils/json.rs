@@ -0,0 +1,173 @@
+use crate::alloc_prelude::*;
+use core::fmt;
+
+/// Minimal JSON document
+///
+/// Built by the `ToJson` implementations of the parsed structures and
+/// serialized with its Display implementation.
+/// Sources:
+/// * <https://www.rfc-editor.org/rfc/rfc8259>
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    /// written as null when it isn't finite
+    Float(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// members are kept in insertion order
+    Object(Vec<(String, Json)>),
+}
+
+/// Conversion of a parsed structure into a JSON document
+pub trait ToJson {
+    fn to_json(&self) -> Json;
+}
+
+impl Json {
+    /// Build an object from its members
+    pub fn object<I, K>(members: I) -> Json
+    where
+        I: IntoIterator<Item = (K, Json)>,
+        K: Into<String>,
+    {
+        Json::Object(
+            members
+                .into_iter()
+                .map(|(key, value)| (key.into(), value))
+                .collect(),
+        )
+    }
+
+    /// Build an array from its values
+    pub fn array<I, T>(values: I) -> Json
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Json>,
+    {
+        Json::Array(values.into_iter().map(Into::into).collect())
+    }
+
+    /// Object describing an error, used in place of a table that can't be parsed
+    pub fn error(err: impl fmt::Display) -> Json {
+        Json::object([("error", Json::from(err.to_string()))])
+    }
+}
+
+macro_rules! from_int {
+    ($variant:ident, $target:ty, $($source:ty),*) => {
+        $(
+            impl From<$source> for Json {
+                fn from(value: $source) -> Self {
+                    Json::$variant(value as $target)
+                }
+            }
+        )*
+    };
+}
+
+from_int!(UInt, u64, u8, u16, u32, u64, usize);
+from_int!(Int, i64, i8, i16, i32, i64);
+
+impl From<f64> for Json {
+    fn from(value: f64) -> Self {
+        Json::Float(value)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Json::Bool(value)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Self {
+        Json::String(value.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(value: String) -> Self {
+        Json::String(value)
+    }
+}
+
+impl From<alloc::borrow::Cow<'_, str>> for Json {
+    fn from(value: alloc::borrow::Cow<'_, str>) -> Self {
+        Json::String(value.into_owned())
+    }
+}
+
+/// Names that aren't valid UTF-8 become lossy strings
+impl From<crate::utils::bytestr::ByteStr<'_>> for Json {
+    fn from(value: crate::utils::bytestr::ByteStr<'_>) -> Self {
+        Json::String(value.to_string())
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Json::Null, Into::into)
+    }
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(value: Vec<T>) -> Self {
+        Json::array(value)
+    }
+}
+
+/// Write a JSON string literal, escaping quotes, backslashes and control characters
+fn write_string(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Int(value) => write!(f, "{}", value),
+            Json::UInt(value) => write!(f, "{}", value),
+            Json::Float(value) if value.is_finite() => write!(f, "{}", value),
+            Json::Float(_) => write!(f, "null"),
+            Json::String(value) => write_string(f, value),
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in members.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Read-only memory mapping of files
+//!
+//! Only available on unix with the `mmap` feature. The mapping is made
+//! through the libc `mmap` and `munmap` functions directly so the crate
+//! stays free of dependencies.
+use std::fs::File;
+use std::os::raw::{c_int, c_long, c_void};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::{io, ops, ptr, slice};
+
+const PROT_READ: c_int = 1;
+const MAP_PRIVATE: c_int = 2;
+const MAP_FAILED: *mut c_void = !0 as *mut c_void;
+
+extern "C" {
+    fn mmap(
+        addr: *mut c_void,
+        len: usize,
+        prot: c_int,
+        flags: c_int,
+        fd: c_int,
+        offset: c_long,
+    ) -> *mut c_void;
+    fn munmap(addr: *mut c_void, len: usize) -> c_int;
+}
+
+/// A private, read-only mapping of a whole file
+///
+/// The mapping dereferences to the bytes of the file. It is page aligned,
+/// so the headers parsed on top of it are borrowed instead of copied.
+/// Modifying the file while it is mapped is undefined behavior, as with
+/// any memory mapping.
+#[derive(Debug)]
+pub struct Mmap {
+    ptr: *mut c_void,
+    len: usize,
+}
+
+// The mapping is read-only and owned by this value
+unsafe impl Send for Mmap {}
+unsafe impl Sync for Mmap {}
+
+impl Mmap {
+    /// Map the whole file in memory
+    pub fn map(file: &File) -> io::Result<Self> {
+        let len = usize::try_from(file.metadata()?.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "file too large to map"))?;
+        // mmap rejects empty mappings
+        if len == 0 {
+            return Ok(Mmap {
+                ptr: ptr::null_mut(),
+                len,
+            });
+        }
+        let ptr = unsafe {
+            mmap(
+                ptr::null_mut(),
+                len,
+                PROT_READ,
+                MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Mmap { ptr, len })
+    }
+}
+
+impl ops::Deref for Mmap {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        if self.ptr.is_null() {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(self.ptr as *const u8, self.len) }
+        }
+    }
+}
+
+impl AsRef<[u8]> for Mmap {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        if !self.ptr.is_null() {
+            unsafe { munmap(self.ptr, self.len) };
+        }
+    }
+}
+
+/// Open a file and map it in memory
+///
+/// ```no_run
+/// use readbin::headers::elf::Elf;
+/// use readbin::utils::mmap;
+///
+/// let map = mmap::open_path("/bin/ls").unwrap();
+/// let elf = Elf::from_mmap(&map).unwrap();
+/// ```
+pub fn open_path<P: AsRef<Path>>(path: P) -> io::Result<Mmap> {
+    Mmap::map(&File::open(path)?)
+}
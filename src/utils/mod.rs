@@ -1,28 +1,308 @@
 /// Sources from https://github.com/Lsh0x/EagleEyes/blob/main/src/utils.rs
-use std::borrow::Cow;
-use std::{mem, slice};
+use crate::alloc_prelude::*;
+use alloc::borrow::Cow;
+use core::{mem, ptr};
+#[cfg(feature = "std")]
+use std::io;
 
-/// Gives a mutable slice of the bytes of the given element.
-#[inline]
-pub fn bytes_of_mut<T: 'static + Copy>(elem: &mut T) -> &mut [u8] {
-    let slice = slice::from_mut(elem);
-    let new_len = mem::size_of_val(slice);
-    unsafe { slice::from_raw_parts_mut(slice.as_ptr() as *mut u8, new_len) }
+pub mod bytestr;
+pub mod demangle;
+pub mod digest;
+#[cfg(feature = "disasm")]
+pub mod disasm;
+pub mod filter;
+pub mod hexdump;
+#[cfg(feature = "zlib")]
+pub mod inflate;
+pub mod json;
+#[cfg(all(unix, feature = "mmap"))]
+pub mod mmap;
+pub mod table;
+pub mod template;
+#[cfg(feature = "xz")]
+pub mod xz;
+pub mod yaml;
+#[cfg(feature = "zstd")]
+pub mod zstd;
+
+/// Plain old data types that can be read from any bytes.
+///
+/// # Safety
+///
+/// Implementors must be `#[repr(C)]` (or primitive) types made only of
+/// integers and arrays of integers, so that every bit pattern of the
+/// right size is a valid value.
+pub unsafe trait Pod: 'static + Copy + Default {}
+
+macro_rules! impl_pod {
+    ($($ty:ty),*) => {
+        $(unsafe impl Pod for $ty {})*
+    };
 }
+impl_pod!(u8, u16, u32, u64, i8, i16, i32, i64);
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] where [T; N]: Default {}
 
 /// Returns either a borrowed version of the struct if target bytes are well aligned
 /// and backups on an owned version that involves copying the bytes.
 ///
+/// The bytes are only reinterpreted in place when their address respects
+/// the alignment of `T`, a misaligned slice is always copied with an
+/// unaligned read. Callers can therefore pass any subslice of a file,
+/// whatever the alignment of the buffer holding it.
+///
 /// Returns None in case the number of bytes doesn't match the struct size.
 #[inline]
-pub fn cow_struct<T: 'static + Copy + Default>(bytes: &[u8]) -> Option<Cow<T>> {
+pub fn cow_struct<T: Pod>(bytes: &[u8]) -> Option<Cow<'_, T>> {
     if bytes.len() != mem::size_of::<T>() {
         None
-    } else if (bytes.as_ptr() as usize) % mem::align_of::<T>() != 0 {
-        let mut elem = T::default();
-        bytes_of_mut(&mut elem).copy_from_slice(bytes);
-        Some(Cow::Owned(elem))
+    } else if !(bytes.as_ptr() as usize).is_multiple_of(mem::align_of::<T>()) {
+        // SAFETY: the length was checked above and T is valid for any bytes
+        Some(Cow::Owned(unsafe {
+            ptr::read_unaligned(bytes.as_ptr() as *const T)
+        }))
     } else {
+        // SAFETY: the pointer is aligned, the length was checked above and
+        // T is valid for any bytes
         Some(Cow::Borrowed(unsafe { &*(bytes.as_ptr() as *const T) }))
     }
 }
+
+/// Types read from a file whose byte order may differ from the host one.
+///
+/// Implementors reverse the byte order of each of their multi-byte fields.
+pub trait SwapBytes {
+    fn swap_bytes(&mut self);
+}
+
+/// Implements [`SwapBytes`] by swapping the listed integer fields.
+///
+/// The type is also marked as [`Pod`], it must only be used on
+/// `#[repr(C)]` structs made of integers and arrays of integers.
+macro_rules! impl_swap_bytes {
+    ($ty:ty, $($field:ident),* $(,)?) => {
+        unsafe impl $crate::utils::Pod for $ty {}
+        impl $crate::utils::SwapBytes for $ty {
+            fn swap_bytes(&mut self) {
+                $(self.$field = self.$field.swap_bytes();)*
+            }
+        }
+    };
+}
+pub(crate) use impl_swap_bytes;
+
+/// Same as [`cow_struct`] for bytes stored in the given byte order.
+///
+/// When the byte order differs from the host one, the struct is copied and
+/// its fields swapped so callers always get a native-endian view.
+#[inline]
+pub fn cow_struct_endian<T: Pod + SwapBytes>(bytes: &[u8], big_endian: bool) -> Option<Cow<'_, T>> {
+    let mut elem = cow_struct::<T>(bytes)?;
+    if big_endian != cfg!(target_endian = "big") {
+        elem.to_mut().swap_bytes();
+    }
+    Some(elem)
+}
+
+/// Fields that can be serialized in a given byte order.
+pub trait WriteBytes {
+    /// Append the bytes of the field to `out`
+    fn write_bytes(&self, out: &mut Vec<u8>, big_endian: bool);
+}
+
+macro_rules! impl_write_bytes {
+    ($($ty:ty),*) => {
+        $(impl WriteBytes for $ty {
+            #[inline]
+            fn write_bytes(&self, out: &mut Vec<u8>, big_endian: bool) {
+                if big_endian {
+                    out.extend_from_slice(&self.to_be_bytes());
+                } else {
+                    out.extend_from_slice(&self.to_le_bytes());
+                }
+            }
+        })*
+    };
+}
+impl_write_bytes!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl<const N: usize> WriteBytes for [u8; N] {
+    #[inline]
+    fn write_bytes(&self, out: &mut Vec<u8>, _big_endian: bool) {
+        out.extend_from_slice(self);
+    }
+}
+
+/// Structures that can be written back to a file in a given byte order.
+///
+/// This is the reverse of [`cow_struct_endian`], the bytes produced by
+/// `to_bytes` parse back to the same value.
+pub trait ToBytes {
+    /// Serialize the structure in the given byte order
+    fn to_bytes(&self, big_endian: bool) -> Vec<u8>;
+
+    /// Serialize the structure in the given byte order into `writer`
+    #[cfg(feature = "std")]
+    fn write_to<W: io::Write>(&self, writer: &mut W, big_endian: bool) -> io::Result<()> {
+        writer.write_all(&self.to_bytes(big_endian))
+    }
+}
+
+/// Implements [`ToBytes`] by writing the listed fields one after the other.
+///
+/// Every field must be listed in declaration order, the structure can't
+/// have any padding.
+macro_rules! impl_to_bytes {
+    ($ty:ty, $($field:ident),* $(,)?) => {
+        impl $crate::utils::ToBytes for $ty {
+            fn to_bytes(&self, big_endian: bool) -> Vec<u8> {
+                let mut out = Vec::with_capacity(core::mem::size_of::<Self>());
+                $($crate::utils::WriteBytes::write_bytes(&self.$field, &mut out, big_endian);)*
+                debug_assert_eq!(out.len(), core::mem::size_of::<Self>());
+                out
+            }
+        }
+    };
+}
+pub(crate) use impl_to_bytes;
+
+/// Fields printed without interpretation, like in a hexdump.
+pub trait RawValue {
+    /// The value in hexadecimal, arrays as space separated bytes
+    fn raw(&self) -> String;
+}
+
+macro_rules! raw_int {
+    ($($ty:ty),*) => {
+        $(impl RawValue for $ty {
+            fn raw(&self) -> String {
+                format!("{:#x}", self)
+            }
+        })*
+    };
+}
+
+raw_int!(u8, u16, u32, u64, i32, i64);
+
+impl<const N: usize> RawValue for [u8; N] {
+    fn raw(&self) -> String {
+        let bytes: Vec<String> = self.iter().map(|byte| format!("{:02x}", byte)).collect();
+        bytes.join(" ")
+    }
+}
+
+/// Structures listing their fields by name, in declaration order.
+pub trait Fields {
+    /// The name of each field with its [`RawValue`]
+    fn fields(&self) -> Vec<(&'static str, String)>;
+}
+
+/// Implements [`Fields`] with the listed fields, named as in the structure.
+macro_rules! impl_fields {
+    ($ty:ty, $($field:ident),* $(,)?) => {
+        impl $crate::utils::Fields for $ty {
+            fn fields(&self) -> Vec<(&'static str, String)> {
+                alloc::vec![$((stringify!($field), $crate::utils::RawValue::raw(&self.$field))),*]
+            }
+        }
+    };
+}
+pub(crate) use impl_fields;
+
+macro_rules! read_int {
+    ($name:ident, $ty:ty) => {
+        /// Read an integer stored with the given byte order at `offset`
+        ///
+        /// Returns None if the bytes are out of bounds.
+        #[inline]
+        pub fn $name(bytes: &[u8], offset: usize, big_endian: bool) -> Option<$ty> {
+            let end = offset.checked_add(mem::size_of::<$ty>())?;
+            let word = bytes.get(offset..end)?.try_into().ok()?;
+            Some(if big_endian {
+                <$ty>::from_be_bytes(word)
+            } else {
+                <$ty>::from_le_bytes(word)
+            })
+        }
+    };
+}
+read_int!(read_u16, u16);
+read_int!(read_u32, u32);
+read_int!(read_u64, u64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    struct Pair {
+        low: u32,
+        high: u64,
+    }
+
+    impl_swap_bytes!(Pair, low, high);
+
+    impl Pair {
+        const SIZE: usize = mem::size_of::<Self>();
+    }
+
+    /// Buffer aligned for `Pair` so tests can pick the alignment of slices
+    #[repr(C, align(8))]
+    struct Aligned([u8; 32]);
+
+    fn buffer() -> Aligned {
+        let mut bytes = [0; 32];
+        for (index, byte) in bytes.iter_mut().enumerate() {
+            *byte = index as u8;
+        }
+        Aligned(bytes)
+    }
+
+    fn expected(bytes: &[u8]) -> Pair {
+        Pair {
+            low: u32::from_ne_bytes(bytes[0..4].try_into().unwrap()),
+            high: u64::from_ne_bytes(bytes[8..16].try_into().unwrap()),
+        }
+    }
+
+    #[test]
+    fn aligned_bytes_are_borrowed() {
+        let data = buffer();
+        let bytes = &data.0[..Pair::SIZE];
+        let pair = cow_struct::<Pair>(bytes).unwrap();
+        assert!(matches!(pair, Cow::Borrowed(_)));
+        assert_eq!(*pair, expected(bytes));
+    }
+
+    #[test]
+    fn unaligned_bytes_are_copied() {
+        let data = buffer();
+        for shift in 1..mem::align_of::<Pair>() {
+            let bytes = &data.0[shift..shift + Pair::SIZE];
+            let pair = cow_struct::<Pair>(bytes).unwrap();
+            assert!(matches!(pair, Cow::Owned(_)));
+            assert_eq!(*pair, expected(bytes));
+        }
+    }
+
+    #[test]
+    fn wrong_size_is_rejected() {
+        let data = buffer();
+        assert!(cow_struct::<Pair>(&data.0[..Pair::SIZE - 1]).is_none());
+        assert!(cow_struct::<Pair>(&data.0[1..Pair::SIZE + 2]).is_none());
+        assert!(cow_struct::<Pair>(&[]).is_none());
+    }
+
+    #[test]
+    fn unaligned_foreign_endian_bytes_are_swapped() {
+        let data = buffer();
+        let bytes = &data.0[3..3 + Pair::SIZE];
+        let foreign = !cfg!(target_endian = "big");
+        let pair = cow_struct_endian::<Pair>(bytes, foreign).unwrap();
+        let native = expected(bytes);
+        assert_eq!(pair.low, native.low.swap_bytes());
+        assert_eq!(pair.high, native.high.swap_bytes());
+        let pair = cow_struct_endian::<Pair>(bytes, !foreign).unwrap();
+        assert_eq!(*pair, native);
+    }
+}
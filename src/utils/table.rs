@@ -0,0 +1,248 @@
+use crate::alloc_prelude::*;
+use alloc::borrow::Cow;
+use core::fmt;
+
+/// Marker ending the truncated cells, like readelf without `-W`
+pub const ELLIPSIS: &str = "[...]";
+
+/// Side of the column the cells are pushed against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// ANSI style of a column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Bold,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+}
+
+impl Color {
+    /// The SGR parameter selecting the style
+    fn code(&self) -> &'static str {
+        match self {
+            Color::Bold => "1",
+            Color::Red => "31",
+            Color::Green => "32",
+            Color::Yellow => "33",
+            Color::Blue => "34",
+            Color::Magenta => "35",
+            Color::Cyan => "36",
+        }
+    }
+}
+
+/// Write `text` in `color`, or as is when `color` is None or `text` is empty
+pub fn paint(out: &mut impl fmt::Write, text: &str, color: Option<Color>) -> fmt::Result {
+    match color {
+        Some(color) if !text.is_empty() => write!(out, "\x1b[{}m{}\x1b[0m", color.code(), text),
+        _ => out.write_str(text),
+    }
+}
+
+/// Cut `text` to `width` characters, the last ones replaced by [`ELLIPSIS`]
+///
+/// ```
+/// use readbin::utils::table::truncate;
+///
+/// assert_eq!(truncate(".note.gnu.property", 17), ".note.gnu.pr[...]");
+/// assert_eq!(truncate(".text", 17), ".text");
+/// ```
+pub fn truncate(text: &str, width: usize) -> Cow<'_, str> {
+    if text.chars().count() <= width {
+        return Cow::Borrowed(text);
+    }
+    let kept = width.saturating_sub(ELLIPSIS.len());
+    let mut cut: String = text.chars().take(kept).collect();
+    cut.push_str(ELLIPSIS);
+    Cow::Owned(cut)
+}
+
+#[derive(Debug, Clone)]
+struct Column {
+    title: &'static str,
+    align: Align,
+    color: Option<Color>,
+    limit: Option<usize>,
+}
+
+/// Rows of text printed in aligned columns
+///
+/// The width of a column is the width of its longest cell, the columns
+/// are separated by a space and the last one isn't padded. The titles are
+/// printed on a first line unless they are all empty. Colors are only
+/// written when enabled, so the same table can go to a terminal or to a
+/// file:
+///
+/// ```
+/// use readbin::utils::table::{Align, Color, Table};
+///
+/// let mut table = Table::new(false)
+///     .indent(2)
+///     .column("Name", Align::Left, Some(Color::Green))
+///     .column("Size", Align::Right, None);
+/// table.row([".text", "0x1a2"]);
+/// table.row([".interp", "0x1c"]);
+/// assert_eq!(
+///     table.to_string(),
+///     "  Name     Size\n  .text   0x1a2\n  .interp  0x1c\n"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Table {
+    columns: Vec<Column>,
+    // the cells of all the rows one after the other, with the end of each
+    // cell in `text` and its width, so a row allocates nothing
+    text: String,
+    cells: Vec<(usize, usize)>,
+    widths: Vec<usize>,
+    indent: usize,
+    color: bool,
+}
+
+impl Table {
+    /// An empty table, writing ANSI colors if `color` is set
+    pub fn new(color: bool) -> Self {
+        Table {
+            columns: Vec::new(),
+            text: String::new(),
+            cells: Vec::new(),
+            widths: Vec::new(),
+            indent: 0,
+            color,
+        }
+    }
+
+    /// Number of spaces written before each line
+    pub fn indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Add a column, the titles are printed in bold with colors
+    pub fn column(mut self, title: &'static str, align: Align, color: Option<Color>) -> Self {
+        self.columns.push(Column {
+            title,
+            align,
+            color,
+            limit: None,
+        });
+        self.widths.push(title.chars().count());
+        self
+    }
+
+    /// Truncate the cells of the last added column to `width` characters
+    /// with [`truncate`], None keeps them whole
+    pub fn limit(mut self, width: Option<usize>) -> Self {
+        if let Some(column) = self.columns.last_mut() {
+            column.limit = width;
+        }
+        self
+    }
+
+    /// Add a row, missing cells are left empty and extra ones are dropped
+    pub fn row<I>(&mut self, cells: I)
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut cells = cells.into_iter();
+        for (column, width) in self.columns.iter().zip(&mut self.widths) {
+            let cell = cells.next();
+            let cell = cell.as_ref().map_or("", AsRef::as_ref);
+            let cell = match column.limit {
+                Some(limit) => truncate(cell, limit),
+                None => Cow::Borrowed(cell),
+            };
+            let count = cell.chars().count();
+            self.text.push_str(&cell);
+            self.cells.push((self.text.len(), count));
+            *width = (*width).max(count);
+        }
+    }
+
+    /// Number of rows, without the titles
+    pub fn len(&self) -> usize {
+        self.cells
+            .len()
+            .checked_div(self.columns.len())
+            .unwrap_or(0)
+    }
+
+    /// Returns true if no row was added
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    fn line<'a>(
+        &self,
+        line: &mut String,
+        cells: impl Iterator<Item = (&'a str, usize)>,
+        title: bool,
+    ) -> fmt::Result {
+        line.clear();
+        pad(line, self.indent);
+        for (index, ((column, width), (cell, count))) in
+            self.columns.iter().zip(&self.widths).zip(cells).enumerate()
+        {
+            let padding = width - count;
+            if index > 0 {
+                line.push(' ');
+            }
+            if column.align == Align::Right {
+                pad(line, padding);
+            }
+            let color = match (self.color, title) {
+                (false, _) => None,
+                (true, true) => Some(Color::Bold),
+                (true, false) => column.color,
+            };
+            paint(line, cell, color)?;
+            if column.align == Align::Left {
+                pad(line, padding);
+            }
+        }
+        // empty or left aligned last cells would leave trailing spaces
+        line.truncate(line.trim_end().len());
+        line.push('\n');
+        Ok(())
+    }
+}
+
+/// Append `count` spaces to `line`
+fn pad(line: &mut String, count: usize) {
+    line.extend(core::iter::repeat_n(' ', count));
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut line = String::new();
+        if self.columns.iter().any(|column| !column.title.is_empty()) {
+            let titles = self
+                .columns
+                .iter()
+                .map(|column| (column.title, column.title.chars().count()));
+            self.line(&mut line, titles, true)?;
+            f.write_str(&line)?;
+        }
+        let mut start = 0;
+        let cells = self.cells.iter().map(|(end, count)| {
+            let cell = &self.text[start..*end];
+            start = *end;
+            (cell, *count)
+        });
+        let mut cells = cells.peekable();
+        while cells.peek().is_some() {
+            self.line(&mut line, cells.by_ref().take(self.columns.len()), false)?;
+            f.write_str(&line)?;
+        }
+        Ok(())
+    }
+}
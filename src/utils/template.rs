@@ -0,0 +1,303 @@
+//! Output templates, one line per symbol, section or segment
+//!
+//! A template is text with fields in braces, formatted like the
+//! arguments of `format!`: `{name}`, `{vaddr:x}`, `{size:>8}` or
+//! `{offset:#010x}`. After the colon come an optional fill character
+//! and alignment `<`, `>` or `^`, `#` for the `0x`, `0o` or `0b` prefix,
+//! `0` to pad a number with zeros, the width and the type of a number:
+//! `x`, `X`, `o`, `b` or `d`. Text is aligned left and numbers right by
+//! default, `{{` and `}}` are literal braces.
+use crate::alloc_prelude::*;
+use crate::utils::bytestr::ByteStr;
+use crate::{Error, Result};
+use alloc::borrow::Cow;
+
+/// The value of a field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value<'a> {
+    Number(u64),
+    Text(Cow<'a, str>),
+}
+
+impl From<u64> for Value<'_> {
+    fn from(value: u64) -> Self {
+        Value::Number(value)
+    }
+}
+
+impl<'a> From<&'a str> for Value<'a> {
+    fn from(value: &'a str) -> Self {
+        Value::Text(Cow::Borrowed(value))
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for Value<'a> {
+    fn from(value: Cow<'a, str>) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl<'a> From<ByteStr<'a>> for Value<'a> {
+    fn from(value: ByteStr<'a>) -> Self {
+        Value::Text(value.to_str_lossy())
+    }
+}
+
+impl From<String> for Value<'_> {
+    fn from(value: String) -> Self {
+        Value::Text(Cow::Owned(value))
+    }
+}
+
+/// Entries of a listing whose fields a template can print
+pub trait Record {
+    /// Names of the fields, in the order of the listing
+    const FIELDS: &'static [&'static str];
+
+    /// The value of a field, None if it has no such field
+    fn field(&self, name: &str) -> Option<Value<'_>>;
+}
+
+/// Alignment of a field in its width
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Alignment {
+    Left,
+    Right,
+    Center,
+}
+
+/// How a field is written, the part after the colon
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Spec {
+    fill: char,
+    align: Option<Alignment>,
+    alternate: bool,
+    zero: bool,
+    width: usize,
+    radix: char,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Field(String, Spec),
+}
+
+/// A parsed template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+fn alignment(c: char) -> Option<Alignment> {
+    match c {
+        '<' => Some(Alignment::Left),
+        '>' => Some(Alignment::Right),
+        '^' => Some(Alignment::Center),
+        _ => None,
+    }
+}
+
+/// Parse the part of a field after the colon, `at` is its index
+fn spec(text: &str, at: usize) -> Result<Spec> {
+    let mut spec = Spec {
+        fill: ' ',
+        align: None,
+        alternate: false,
+        zero: false,
+        width: 0,
+        radix: 'd',
+    };
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut rest = &chars[..];
+    match rest {
+        [(_, fill), (_, align), ..] if alignment(*align).is_some() => {
+            spec.fill = *fill;
+            spec.align = alignment(*align);
+            rest = &rest[2..];
+        }
+        [(_, align), ..] if alignment(*align).is_some() => {
+            spec.align = alignment(*align);
+            rest = &rest[1..];
+        }
+        _ => {}
+    }
+    if let [(_, '#'), ..] = rest {
+        spec.alternate = true;
+        rest = &rest[1..];
+    }
+    if let [(_, '0'), ..] = rest {
+        spec.zero = true;
+        rest = &rest[1..];
+    }
+    while let [(_, digit @ '0'..='9'), ..] = rest {
+        spec.width = spec
+            .width
+            .saturating_mul(10)
+            .saturating_add(*digit as usize - '0' as usize);
+        rest = &rest[1..];
+    }
+    match rest {
+        [] => {}
+        [(_, radix @ ('x' | 'X' | 'o' | 'b' | 'd'))] => spec.radix = *radix,
+        [(index, _), ..] => return Err(Error::InvalidTemplate(at + index)),
+    }
+    Ok(spec)
+}
+
+impl Template {
+    /// Parse a template
+    ///
+    /// Fails with the index of the character of an unclosed brace, of a
+    /// lone `}` or of an invalid format.
+    pub fn parse(text: &str) -> Result<Template> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = text.char_indices().peekable();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '{' if chars.next_if(|(_, c)| *c == '{').is_some() => literal.push('{'),
+                '}' if chars.next_if(|(_, c)| *c == '}').is_some() => literal.push('}'),
+                '}' => return Err(Error::InvalidTemplate(index)),
+                '{' => {
+                    let start = index + 1;
+                    let end = text[start..]
+                        .find('}')
+                        .map(|end| start + end)
+                        .ok_or(Error::InvalidTemplate(index))?;
+                    let field = &text[start..end];
+                    let (name, format) = match field.split_once(':') {
+                        Some((name, format)) => (name, spec(format, start + name.len() + 1)?),
+                        None => (field, spec("", end)?),
+                    };
+                    let valid = !name.is_empty()
+                        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                    if !valid {
+                        return Err(Error::InvalidTemplate(start));
+                    }
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(core::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field(name.to_string(), format));
+                    while chars.next_if(|(index, _)| *index <= end).is_some() {}
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Template { parts })
+    }
+
+    /// The names of the fields used, in order
+    pub fn fields(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Field(name, _) => Some(name.as_str()),
+            Part::Literal(_) => None,
+        })
+    }
+
+    /// Check that every field is one of the fields of `R`
+    pub fn check<R: Record>(&self) -> Result<()> {
+        match self.fields().find(|name| !R::FIELDS.contains(name)) {
+            Some(name) => Err(Error::UnknownField(name.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Write a record with the template, without ending the line
+    ///
+    /// Fails if the record has no field of a name.
+    pub fn render<R: Record>(&self, record: &R) -> Result<String> {
+        let mut out = String::new();
+        for part in &self.parts {
+            let (name, spec) = match part {
+                Part::Literal(text) => {
+                    out.push_str(text);
+                    continue;
+                }
+                Part::Field(name, spec) => (name, spec),
+            };
+            let value = record
+                .field(name)
+                .ok_or_else(|| Error::UnknownField(name.clone()))?;
+            let (prefix, body, default) = match value {
+                Value::Number(number) => {
+                    let (prefix, body) = match spec.radix {
+                        'x' => ("0x", format!("{:x}", number)),
+                        'X' => ("0x", format!("{:X}", number)),
+                        'o' => ("0o", format!("{:o}", number)),
+                        'b' => ("0b", format!("{:b}", number)),
+                        _ => ("", number.to_string()),
+                    };
+                    let prefix = if spec.alternate { prefix } else { "" };
+                    (prefix, Cow::Owned(body), Alignment::Right)
+                }
+                Value::Text(text) => ("", text, Alignment::Left),
+            };
+            let len = prefix.len() + body.chars().count();
+            let padding = spec.width.saturating_sub(len);
+            if spec.zero && spec.align.is_none() {
+                out.push_str(prefix);
+                out.extend(core::iter::repeat_n('0', padding));
+                out.push_str(&body);
+                continue;
+            }
+            let (before, after) = match spec.align.unwrap_or(default) {
+                Alignment::Left => (0, padding),
+                Alignment::Right => (padding, 0),
+                Alignment::Center => (padding / 2, padding - padding / 2),
+            };
+            out.extend(core::iter::repeat_n(spec.fill, before));
+            out.push_str(prefix);
+            out.push_str(&body);
+            out.extend(core::iter::repeat_n(spec.fill, after));
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Entry;
+
+    impl Record for Entry {
+        const FIELDS: &'static [&'static str] = &["name", "vaddr"];
+
+        fn field(&self, name: &str) -> Option<Value<'_>> {
+            match name {
+                "name" => Some(Value::from("main")),
+                "vaddr" => Some(Value::from(0x1139)),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn render_fields() {
+        let render = |text| Template::parse(text).and_then(|template| template.render(&Entry));
+        assert_eq!(render("{name} {vaddr:x}"), Ok("main 1139".to_string()));
+        assert_eq!(
+            render("{vaddr:#010x}|{vaddr:>6}|{name:*^8}|{{}}"),
+            Ok("0x00001139|  4409|**main**|{}".to_string())
+        );
+        assert_eq!(
+            render("{name:<6}|{vaddr:08X}"),
+            Ok("main  |00001139".to_string())
+        );
+        assert_eq!(
+            render("{size}"),
+            Err(Error::UnknownField("size".to_string()))
+        );
+        assert_eq!(Template::parse("{name"), Err(Error::InvalidTemplate(0)));
+        assert_eq!(Template::parse("a}"), Err(Error::InvalidTemplate(1)));
+        assert_eq!(Template::parse("{vaddr:q}"), Err(Error::InvalidTemplate(7)));
+        assert!(Template::parse("{name} {vaddr}")
+            .expect("valid template")
+            .check::<Entry>()
+            .is_ok());
+    }
+}
@@ -0,0 +1,672 @@
+//! An xz decoder for the MiniDebugInfo of `.gnu_debugdata`
+//!
+//! An xz stream is a header, blocks and an index of their sizes. The
+//! blocks are compressed with LZMA2: chunks of raw bytes and of LZMA
+//! data, a range coder over adaptive bit probabilities writing literals
+//! and matches in a sliding dictionary, here the whole output. Only the
+//! LZMA2 filter is supported, it is the only one `xz` uses by default.
+//! The CRC32 and CRC64 checks are verified, the SHA-256 ones skipped.
+//! Sources:
+//! * <https://tukaani.org/xz/xz-file-format.txt>
+//! * <https://github.com/tukaani-project/xz-embedded/blob/master/linux/lib/xz/xz_dec_lzma2.c>
+use crate::alloc_prelude::*;
+use crate::headers::elf::debuglink::crc32;
+use crate::{Error, Result};
+
+/// Magic bytes of the stream header
+const HEADER_MAGIC: [u8; 6] = [0xfd, b'7', b'z', b'X', b'Z', 0];
+
+/// Magic bytes ending the stream footer
+const FOOTER_MAGIC: [u8; 2] = [b'Y', b'Z'];
+
+/// Filter id of LZMA2
+pub const LZMA2: u32 = 0x21;
+
+/// Check types with a verified value
+const CHECK_CRC32: u8 = 1;
+const CHECK_CRC64: u8 = 4;
+
+/// Number of states of the LZMA state machine
+const STATES: usize = 12;
+
+/// Number of literal states, the states below it follow a literal
+const LIT_STATES: usize = 7;
+
+/// Maximum number of position states, `1 << pb`
+const POS_STATES: usize = 16;
+
+/// Number of distance slots for each length state
+const DIST_SLOTS: usize = 64;
+
+/// First distance slot coded with direct bits and the align bits
+const DIST_MODEL_END: usize = 14;
+
+/// Number of probabilities of the distances below slot 14
+const FULL_DISTANCES: usize = 128;
+
+/// Initial probability of a bit being 0, out of 2048
+const PROB_INIT: u16 = 1024;
+
+/// A position in the input, where a decoding error is reported
+type Offset = usize;
+
+fn invalid(offset: Offset) -> Error {
+    Error::InvalidCompressedData(offset as u64)
+}
+
+const CRC64_TABLE: [u64; 256] = {
+    let mut table = [0u64; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut crc = index as u64;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xc96c_5795_d787_0f42
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+};
+
+/// The CRC-64 of the xz checks, ECMA-182 with reflected bits
+fn crc64(data: &[u8]) -> u64 {
+    !data.iter().fold(!0, |crc, byte| {
+        CRC64_TABLE[((crc ^ *byte as u64) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Read a variable length number of at most 9 bytes, 7 bits per byte
+fn multibyte(data: &[u8], at: &mut usize) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in 0..9 {
+        let byte = *data.get(*at).ok_or_else(|| invalid(*at))?;
+        *at += 1;
+        value |= ((byte & 0x7f) as u64) << (shift * 7);
+        if byte & 0x80 == 0 {
+            // a 0 continuation byte would be a second encoding
+            if shift > 0 && byte == 0 {
+                return Err(invalid(*at - 1));
+            }
+            return Ok(value);
+        }
+    }
+    Err(invalid(*at))
+}
+
+/// Size of the check of a check type
+fn check_size(check: u8) -> usize {
+    match check {
+        0 => 0,
+        1..=3 => 4,
+        4..=6 => 8,
+        7..=9 => 16,
+        10..=12 => 32,
+        _ => 64,
+    }
+}
+
+/// The range decoder of an LZMA chunk
+struct RangeDecoder<'a> {
+    data: &'a [u8],
+    at: usize,
+    range: u32,
+    code: u32,
+    /// offset of the data in the stream, for the errors
+    offset: Offset,
+}
+
+impl<'a> RangeDecoder<'a> {
+    fn new(data: &'a [u8], offset: Offset) -> Result<Self> {
+        match data {
+            [0, a, b, c, d, ..] => Ok(RangeDecoder {
+                data,
+                at: 5,
+                range: u32::MAX,
+                code: u32::from_be_bytes([*a, *b, *c, *d]),
+                offset,
+            }),
+            _ => Err(invalid(offset)),
+        }
+    }
+
+    fn error(&self) -> Error {
+        invalid(self.offset + self.at)
+    }
+
+    fn normalize(&mut self) -> Result<()> {
+        if self.range < 1 << 24 {
+            let byte = *self.data.get(self.at).ok_or_else(|| self.error())?;
+            self.at += 1;
+            self.range <<= 8;
+            self.code = (self.code << 8) | byte as u32;
+        }
+        Ok(())
+    }
+
+    /// Decode a bit with an adaptive probability
+    fn bit(&mut self, prob: &mut u16) -> Result<u32> {
+        self.normalize()?;
+        let bound = (self.range >> 11) * *prob as u32;
+        if self.code < bound {
+            self.range = bound;
+            *prob += (2048 - *prob) >> 5;
+            Ok(0)
+        } else {
+            self.range -= bound;
+            self.code -= bound;
+            *prob -= *prob >> 5;
+            Ok(1)
+        }
+    }
+
+    /// Decode `count` bits of a binary tree, most significant first
+    fn tree(&mut self, probs: &mut [u16], count: u32) -> Result<u32> {
+        let mut symbol = 1;
+        while symbol < 1 << count {
+            symbol = (symbol << 1) | self.bit(&mut probs[symbol as usize])?;
+        }
+        Ok(symbol - (1 << count))
+    }
+
+    /// Decode `count` bits of a binary tree, least significant first
+    ///
+    /// The nodes start at 1, the probability of node `n` is `probs[n - 1]`.
+    fn reverse_tree(&mut self, probs: &mut [u16], count: u32) -> Result<u32> {
+        let mut symbol = 1;
+        let mut value = 0;
+        for index in 0..count {
+            let bit = self.bit(&mut probs[symbol - 1])?;
+            symbol = (symbol << 1) | bit as usize;
+            value |= bit << index;
+        }
+        Ok(value)
+    }
+
+    /// Decode `count` bits of equal probabilities
+    fn direct(&mut self, count: u32) -> Result<u32> {
+        let mut value = 0u32;
+        for _ in 0..count {
+            self.normalize()?;
+            self.range >>= 1;
+            let bit = (self.code >= self.range) as u32;
+            if bit == 1 {
+                self.code -= self.range;
+            }
+            value = (value << 1) | bit;
+        }
+        Ok(value)
+    }
+
+    /// Whether the chunk ends where its data does, once the last byte
+    /// of the range is read
+    fn is_finished(&mut self) -> bool {
+        self.normalize().is_ok() && self.at == self.data.len() && self.code == 0
+    }
+}
+
+/// The probabilities of a match length
+struct LengthDecoder {
+    choice: u16,
+    choice2: u16,
+    low: [[u16; 8]; POS_STATES],
+    mid: [[u16; 8]; POS_STATES],
+    high: [u16; 256],
+}
+
+impl LengthDecoder {
+    fn new() -> Self {
+        LengthDecoder {
+            choice: PROB_INIT,
+            choice2: PROB_INIT,
+            low: [[PROB_INIT; 8]; POS_STATES],
+            mid: [[PROB_INIT; 8]; POS_STATES],
+            high: [PROB_INIT; 256],
+        }
+    }
+
+    /// Decode a length, from 2 to 273
+    fn decode(&mut self, rc: &mut RangeDecoder, pos_state: usize) -> Result<usize> {
+        let length = if rc.bit(&mut self.choice)? == 0 {
+            rc.tree(&mut self.low[pos_state], 3)?
+        } else if rc.bit(&mut self.choice2)? == 0 {
+            8 + rc.tree(&mut self.mid[pos_state], 3)?
+        } else {
+            16 + rc.tree(&mut self.high, 8)?
+        };
+        Ok(2 + length as usize)
+    }
+}
+
+/// The state of the LZMA decoder kept between the chunks of a block
+struct Lzma {
+    lc: u32,
+    lp: u32,
+    pb: u32,
+    state: usize,
+    reps: [usize; 4],
+    is_match: [[u16; POS_STATES]; STATES],
+    is_rep: [u16; STATES],
+    is_rep0: [u16; STATES],
+    is_rep1: [u16; STATES],
+    is_rep2: [u16; STATES],
+    is_rep0_long: [[u16; POS_STATES]; STATES],
+    dist_slot: [[u16; DIST_SLOTS]; 4],
+    dist_special: [u16; FULL_DISTANCES - DIST_MODEL_END],
+    dist_align: [u16; 15],
+    match_len: LengthDecoder,
+    rep_len: LengthDecoder,
+    literal: Vec<u16>,
+}
+
+impl Lzma {
+    /// A decoder with the `lc`, `lp` and `pb` of a properties byte
+    fn new(properties: u8) -> Option<Self> {
+        let (lc, rest) = (properties % 9, properties / 9);
+        let (lp, pb) = (rest % 5, rest / 5);
+        if pb > 4 || lc + lp > 4 {
+            return None;
+        }
+        Some(Lzma {
+            lc: lc as u32,
+            lp: lp as u32,
+            pb: pb as u32,
+            state: 0,
+            reps: [0; 4],
+            is_match: [[PROB_INIT; POS_STATES]; STATES],
+            is_rep: [PROB_INIT; STATES],
+            is_rep0: [PROB_INIT; STATES],
+            is_rep1: [PROB_INIT; STATES],
+            is_rep2: [PROB_INIT; STATES],
+            is_rep0_long: [[PROB_INIT; POS_STATES]; STATES],
+            dist_slot: [[PROB_INIT; DIST_SLOTS]; 4],
+            dist_special: [PROB_INIT; FULL_DISTANCES - DIST_MODEL_END],
+            dist_align: [PROB_INIT; 15],
+            match_len: LengthDecoder::new(),
+            rep_len: LengthDecoder::new(),
+            literal: vec![PROB_INIT; 0x300 << (lc + lp)],
+        })
+    }
+
+    /// Reset the probabilities and the state, keeping the properties
+    fn reset(&mut self) {
+        *self =
+            Lzma::new((self.pb * 5 + self.lp) as u8 * 9 + self.lc as u8).expect("valid properties");
+    }
+
+    /// Decode an LZMA chunk of `size` bytes into `out`
+    ///
+    /// `start` is where the dictionary was last reset, matches can't
+    /// reach before it.
+    fn chunk(
+        &mut self,
+        rc: &mut RangeDecoder,
+        out: &mut Vec<u8>,
+        start: usize,
+        size: usize,
+    ) -> Result<()> {
+        let end = out.len() + size;
+        while out.len() < end {
+            let pos = out.len() - start;
+            let pos_state = pos & ((1 << self.pb) - 1);
+            if rc.bit(&mut self.is_match[self.state][pos_state])? == 0 {
+                self.literal(rc, out, start)?;
+                continue;
+            }
+            let length = if rc.bit(&mut self.is_rep[self.state])? == 0 {
+                self.state = if self.state < LIT_STATES { 7 } else { 10 };
+                self.reps = [0, self.reps[0], self.reps[1], self.reps[2]];
+                let length = self.match_len.decode(rc, pos_state)?;
+                self.reps[0] = self.distance(rc, length)?;
+                length
+            } else if rc.bit(&mut self.is_rep0[self.state])? == 0 {
+                if rc.bit(&mut self.is_rep0_long[self.state][pos_state])? == 0 {
+                    self.state = if self.state < LIT_STATES { 9 } else { 11 };
+                    1
+                } else {
+                    self.state = if self.state < LIT_STATES { 8 } else { 11 };
+                    self.rep_len.decode(rc, pos_state)?
+                }
+            } else {
+                let distance = if rc.bit(&mut self.is_rep1[self.state])? == 0 {
+                    self.reps[1]
+                } else if rc.bit(&mut self.is_rep2[self.state])? == 0 {
+                    let distance = self.reps[2];
+                    self.reps[2] = self.reps[1];
+                    distance
+                } else {
+                    let distance = self.reps[3];
+                    self.reps[3] = self.reps[2];
+                    self.reps[2] = self.reps[1];
+                    distance
+                };
+                self.reps[1] = self.reps[0];
+                self.reps[0] = distance;
+                self.state = if self.state < LIT_STATES { 8 } else { 11 };
+                self.rep_len.decode(rc, pos_state)?
+            };
+            // a match can't cross the end of its chunk
+            let distance = self.reps[0] + 1;
+            if distance > pos || out.len() + length > end {
+                return Err(rc.error());
+            }
+            let from = out.len() - distance;
+            for index in 0..length {
+                let byte = out[from + index];
+                out.push(byte);
+            }
+        }
+        Ok(())
+    }
+
+    /// Decode a literal byte
+    fn literal(&mut self, rc: &mut RangeDecoder, out: &mut Vec<u8>, start: usize) -> Result<()> {
+        let pos = out.len() - start;
+        let previous = match pos {
+            0 => 0,
+            _ => out[out.len() - 1] as usize,
+        };
+        let context = ((pos & ((1 << self.lp) - 1)) << self.lc) + (previous >> (8 - self.lc));
+        let probs = &mut self.literal[0x300 * context..0x300 * (context + 1)];
+        let mut symbol = 1usize;
+        if self.state < LIT_STATES {
+            while symbol < 0x100 {
+                symbol = (symbol << 1) | rc.bit(&mut probs[symbol])? as usize;
+            }
+        } else {
+            // after a match, the byte at the last distance guides the coding
+            let distance = self.reps[0] + 1;
+            if distance > pos {
+                return Err(rc.error());
+            }
+            let mut matched = (out[out.len() - distance] as usize) << 1;
+            let mut offset = 0x100;
+            while symbol < 0x100 {
+                let matched_bit = matched & offset;
+                matched <<= 1;
+                let bit = rc.bit(&mut probs[offset + matched_bit + symbol])? as usize;
+                symbol = (symbol << 1) | bit;
+                offset &= if bit == 1 { matched_bit } else { !matched_bit };
+            }
+        }
+        out.push(symbol as u8);
+        self.state = match self.state {
+            0..=3 => 0,
+            4..=9 => self.state - 3,
+            _ => self.state - 6,
+        };
+        Ok(())
+    }
+
+    /// Decode the distance of a match of `length` bytes, minus 1
+    fn distance(&mut self, rc: &mut RangeDecoder, length: usize) -> Result<usize> {
+        let length_state = (length - 2).min(3);
+        let slot = rc.tree(&mut self.dist_slot[length_state], 6)? as usize;
+        if slot < 4 {
+            return Ok(slot);
+        }
+        let count = (slot as u32 >> 1) - 1;
+        let mut distance = (2 | (slot & 1) as u32) << count;
+        if slot < DIST_MODEL_END {
+            let base = distance as usize - slot;
+            distance += rc.reverse_tree(&mut self.dist_special[base..], count)?;
+        } else {
+            distance += rc.direct(count - 4)? << 4;
+            distance += rc.reverse_tree(&mut self.dist_align, 4)?;
+        }
+        // the end marker isn't allowed in LZMA2
+        match distance {
+            u32::MAX => Err(rc.error()),
+            distance => Ok(distance as usize),
+        }
+    }
+}
+
+/// Decode the LZMA2 data of a block, returns its size
+fn lzma2(data: &[u8], out: &mut Vec<u8>, limit: usize, offset: Offset) -> Result<usize> {
+    let mut at = 0;
+    let mut lzma: Option<Lzma> = None;
+    let mut start = out.len();
+    let mut need_reset = true;
+    loop {
+        let control = *data.get(at).ok_or_else(|| invalid(offset + at))?;
+        let error = invalid(offset + at);
+        at += 1;
+        match control {
+            0 => return Ok(at),
+            1 | 2 => {
+                if need_reset && control != 1 {
+                    return Err(error);
+                }
+                if control == 1 {
+                    start = out.len();
+                    need_reset = false;
+                }
+                let header = data.get(at..at + 2).ok_or_else(|| invalid(offset + at))?;
+                let size = u16::from_be_bytes([header[0], header[1]]) as usize + 1;
+                at += 2;
+                let bytes = data
+                    .get(at..at + size)
+                    .filter(|_| out.len() + size <= limit)
+                    .ok_or_else(|| invalid(offset + at))?;
+                out.extend_from_slice(bytes);
+                at += size;
+            }
+            0x80.. => {
+                let header = data.get(at..at + 4).ok_or_else(|| invalid(offset + at))?;
+                let size = (((control & 0x1f) as usize) << 16
+                    | u16::from_be_bytes([header[0], header[1]]) as usize)
+                    + 1;
+                let packed = u16::from_be_bytes([header[2], header[3]]) as usize + 1;
+                at += 4;
+                let reset = (control >> 5) & 3;
+                if reset == 3 {
+                    start = out.len();
+                    need_reset = false;
+                }
+                if need_reset {
+                    return Err(error);
+                }
+                if reset >= 2 {
+                    let properties = *data.get(at).ok_or_else(|| invalid(offset + at))?;
+                    lzma = Some(Lzma::new(properties).ok_or_else(|| invalid(offset + at))?);
+                    at += 1;
+                }
+                let lzma = lzma.as_mut().ok_or(error)?;
+                if reset == 1 {
+                    lzma.reset();
+                }
+                if out.len() + size > limit {
+                    return Err(invalid(offset + at));
+                }
+                let bytes = data
+                    .get(at..at + packed)
+                    .ok_or_else(|| invalid(offset + at))?;
+                let mut rc = RangeDecoder::new(bytes, offset + at)?;
+                lzma.chunk(&mut rc, out, start, size)?;
+                if !rc.is_finished() {
+                    return Err(rc.error());
+                }
+                at += packed;
+            }
+            _ => return Err(error),
+        }
+    }
+}
+
+/// Decode a block, returns its unpadded size and its uncompressed size
+fn block(
+    data: &[u8],
+    out: &mut Vec<u8>,
+    limit: usize,
+    check: u8,
+    offset: Offset,
+) -> Result<(u64, u64)> {
+    let header_size = (*data.first().ok_or_else(|| invalid(offset))? as usize + 1) * 4;
+    let header = data
+        .get(..header_size)
+        .ok_or_else(|| invalid(offset + header_size))?;
+    let (body, crc) = header.split_at(header_size - 4);
+    if crc32(body) != u32::from_le_bytes([crc[0], crc[1], crc[2], crc[3]]) {
+        return Err(invalid(offset + header_size - 4));
+    }
+    let flags = body[1];
+    if flags & 0x3c != 0 {
+        return Err(invalid(offset + 1));
+    }
+    let mut at = 2;
+    let compressed = match flags & 0x40 {
+        0 => None,
+        _ => Some(multibyte(body, &mut at).map_err(|_| invalid(offset + at))?),
+    };
+    let uncompressed = match flags & 0x80 {
+        0 => None,
+        _ => Some(multibyte(body, &mut at).map_err(|_| invalid(offset + at))?),
+    };
+    // the last filter compresses, the others would transform the data
+    let filters = (flags & 3) as usize + 1;
+    for index in 0..filters {
+        let id = multibyte(body, &mut at).map_err(|_| invalid(offset + at))?;
+        let size = multibyte(body, &mut at).map_err(|_| invalid(offset + at))? as usize;
+        if id != LZMA2 as u64 || index + 1 != filters {
+            return Err(Error::UnsupportedCompression(id as u32));
+        }
+        if size != 1 || body.get(at).is_none_or(|dictionary| *dictionary > 40) {
+            return Err(invalid(offset + at));
+        }
+        at += size;
+    }
+    if body[at..].iter().any(|byte| *byte != 0) {
+        return Err(invalid(offset + at));
+    }
+
+    let start = out.len();
+    let size = lzma2(&data[header_size..], out, limit, offset + header_size)?;
+    let decoded = (out.len() - start) as u64;
+    if compressed.is_some_and(|compressed| compressed != size as u64)
+        || uncompressed.is_some_and(|uncompressed| uncompressed != decoded)
+    {
+        return Err(invalid(offset + header_size + size));
+    }
+    let mut at = header_size + size;
+    let unpadded = at + check_size(check);
+    while !at.is_multiple_of(4) {
+        match data.get(at) {
+            Some(0) => at += 1,
+            _ => return Err(invalid(offset + at)),
+        }
+    }
+    let value = data
+        .get(at..at + check_size(check))
+        .ok_or_else(|| invalid(offset + at))?;
+    let valid = match check {
+        CHECK_CRC32 => crc32(&out[start..]).to_le_bytes() == value,
+        CHECK_CRC64 => crc64(&out[start..]).to_le_bytes() == value,
+        _ => true,
+    };
+    if !valid {
+        return Err(invalid(offset + at));
+    }
+    Ok((unpadded as u64, decoded))
+}
+
+/// Decode a stream, returns its size
+fn stream(data: &[u8], out: &mut Vec<u8>, limit: usize, offset: Offset) -> Result<usize> {
+    let header = data.get(..12).ok_or_else(|| invalid(offset))?;
+    if header[..6] != HEADER_MAGIC {
+        return Err(invalid(offset));
+    }
+    let flags = &header[6..8];
+    if crc32(flags).to_le_bytes() != header[8..12] || flags[0] != 0 || flags[1] > 0x0f {
+        return Err(invalid(offset + 6));
+    }
+    let check = flags[1];
+
+    let mut at = 12;
+    let mut blocks = Vec::new();
+    while data.get(at).is_some_and(|size| *size != 0) {
+        let rest = &data[at..];
+        let (unpadded, uncompressed) = block(rest, out, limit, check, offset + at)?;
+        blocks.push((unpadded, uncompressed));
+        at += (unpadded as usize).next_multiple_of(4);
+    }
+
+    // the index repeats the sizes of the blocks
+    let index = at;
+    at += 1;
+    let count = multibyte(data, &mut at).map_err(|_| invalid(offset + at))?;
+    if count != blocks.len() as u64 {
+        return Err(invalid(offset + at));
+    }
+    for expected in &blocks {
+        let unpadded = multibyte(data, &mut at).map_err(|_| invalid(offset + at))?;
+        let uncompressed = multibyte(data, &mut at).map_err(|_| invalid(offset + at))?;
+        if (unpadded, uncompressed) != *expected {
+            return Err(invalid(offset + at));
+        }
+    }
+    while !at.is_multiple_of(4) {
+        match data.get(at) {
+            Some(0) => at += 1,
+            _ => return Err(invalid(offset + at)),
+        }
+    }
+    let crc = data.get(at..at + 4).ok_or_else(|| invalid(offset + at))?;
+    if crc32(&data[index..at]).to_le_bytes() != crc {
+        return Err(invalid(offset + at));
+    }
+    at += 4;
+
+    let footer = data.get(at..at + 12).ok_or_else(|| invalid(offset + at))?;
+    let backward = u32::from_le_bytes([footer[4], footer[5], footer[6], footer[7]]) as usize;
+    let valid = crc32(&footer[4..10]).to_le_bytes() == footer[..4]
+        && (backward + 1) * 4 == at - index
+        && footer[8..10] == *flags
+        && footer[10..] == FOOTER_MAGIC;
+    if !valid {
+        return Err(invalid(offset + at));
+    }
+    Ok(at + 12)
+}
+
+/// Decode an xz file of at most `limit` bytes
+///
+/// The streams can be concatenated and separated by padding. Fails if a
+/// stream is invalid or truncated, if a check doesn't match, if a block
+/// uses another filter than LZMA2 or if it decodes to more than `limit`
+/// bytes.
+///
+/// ```
+/// use readbin::utils::xz;
+///
+/// // `printf hello | xz -C crc32`
+/// let stream = [
+///     0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00, 0x00, 0x01, 0x69, 0x22, 0xde, 0x36, 0x02, 0x00,
+///     0x21, 0x01, 0x16, 0x00, 0x00, 0x00, 0x74, 0x2f, 0xe5, 0xa3, 0x01, 0x00, 0x04, 0x68,
+///     0x65, 0x6c, 0x6c, 0x6f, 0x00, 0x00, 0x00, 0x00, 0x86, 0xa6, 0x10, 0x36, 0x00, 0x01,
+///     0x19, 0x05, 0xbc, 0xe8, 0xec, 0xcb, 0x90, 0x42, 0x99, 0x0d, 0x01, 0x00, 0x00, 0x00,
+///     0x00, 0x01, 0x59, 0x5a,
+/// ];
+/// assert_eq!(xz::decompress(&stream, 1024).unwrap(), b"hello");
+/// ```
+pub fn decompress(data: &[u8], limit: usize) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut at = 0;
+    loop {
+        at += stream(&data[at..], &mut out, limit, at)?;
+        // a stream padding is a multiple of 4 null bytes
+        let padding = data[at..].iter().take_while(|byte| **byte == 0).count();
+        if !padding.is_multiple_of(4) {
+            return Err(invalid(at + padding));
+        }
+        at += padding;
+        if at == data.len() {
+            return Ok(out);
+        }
+    }
+}
@@ -0,0 +1,425 @@
+use crate::alloc_prelude::*;
+use crate::{Error, Result};
+use core::fmt;
+
+/// Minimal YAML document
+///
+/// Covers the block mappings and sequences of the descriptions written by
+/// `readbin export`, with plain or quoted scalars and flow sequences of
+/// scalars. Anchors, tags, multi line scalars and flow mappings other
+/// than `{}` aren't supported. Scalars are kept as text, the reader
+/// decides how to interpret them.
+/// Sources:
+/// * <https://yaml.org/spec/1.2.2/>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Yaml {
+    Scalar(String),
+    List(Vec<Yaml>),
+    /// entries are kept in insertion order
+    Map(Vec<(String, Yaml)>),
+}
+
+impl Yaml {
+    /// Build a mapping from its entries
+    pub fn map<I, K>(entries: I) -> Yaml
+    where
+        I: IntoIterator<Item = (K, Yaml)>,
+        K: Into<String>,
+    {
+        Yaml::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.into(), value))
+                .collect(),
+        )
+    }
+
+    /// Build a sequence from its values
+    pub fn list<I: IntoIterator<Item = Yaml>>(values: I) -> Yaml {
+        Yaml::List(values.into_iter().collect())
+    }
+
+    /// The value of `key` if this is a mapping holding it
+    pub fn get(&self, key: &str) -> Option<&Yaml> {
+        match self {
+            Yaml::Map(entries) => entries
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// The text of a scalar
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Yaml::Scalar(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The values of a sequence
+    pub fn as_list(&self) -> Option<&[Yaml]> {
+        match self {
+            Yaml::List(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Parse a document
+    ///
+    /// An empty document is an empty mapping. Fails with the number of
+    /// the first line that can't be parsed.
+    pub fn parse(text: &str) -> Result<Yaml> {
+        let mut lines = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let number = index + 1;
+            let line = strip_comment(line).trim_end();
+            let trimmed = line.trim_start_matches(' ');
+            if trimmed.is_empty() || trimmed == "---" || trimmed.starts_with("--- ") {
+                continue;
+            }
+            if trimmed.starts_with('\t') {
+                return Err(Error::InvalidYaml(number));
+            }
+            lines.push(Line {
+                number,
+                indent: line.len() - trimmed.len(),
+                text: trimmed,
+            });
+        }
+        let indent = match lines.first() {
+            Some(line) => line.indent,
+            None => return Ok(Yaml::Map(Vec::new())),
+        };
+        let mut parser = Parser { lines, at: 0 };
+        let document = parser.node(indent)?;
+        match parser.lines.get(parser.at) {
+            Some(line) => Err(Error::InvalidYaml(line.number)),
+            None => Ok(document),
+        }
+    }
+}
+
+impl From<&str> for Yaml {
+    fn from(value: &str) -> Self {
+        Yaml::Scalar(value.to_string())
+    }
+}
+
+impl From<String> for Yaml {
+    fn from(value: String) -> Self {
+        Yaml::Scalar(value)
+    }
+}
+
+/// A line of the document without its indentation and comment
+#[derive(Debug, Clone, Copy)]
+struct Line<'a> {
+    number: usize,
+    indent: usize,
+    text: &'a str,
+}
+
+/// Remove the comment ending a line, `#` after a space outside of quotes
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (index, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '#') if previous == ' ' || previous == '\t' => return &line[..index],
+            (None, '\'' | '"') if previous == ' ' || index == 0 || "[,:-".contains(previous) => {
+                quote = Some(c)
+            }
+            (Some(open), c) if c == open => quote = None,
+            _ => {}
+        }
+        previous = c;
+    }
+    line
+}
+
+/// Offset of the `:` ending the key of a mapping entry, outside of quotes
+fn key_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    let bytes = text.as_bytes();
+    for (index, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '\'' | '"') if index == 0 => quote = Some(c),
+            (None, '[' | '{') if index == 0 => return None,
+            (Some(open), c) if c == open => quote = None,
+            (None, ':') if matches!(bytes.get(index + 1), None | Some(b' ')) => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Whether a line starts an item of a sequence
+fn is_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// Decode a scalar or a flow sequence of scalars
+fn scalar(text: &str, number: usize) -> Result<Yaml> {
+    let text = text.trim();
+    if let Some(inner) = text.strip_prefix('[') {
+        let inner = inner.strip_suffix(']').ok_or(Error::InvalidYaml(number))?;
+        if inner.trim().is_empty() {
+            return Ok(Yaml::List(Vec::new()));
+        }
+        return inner
+            .split(',')
+            .map(|item| scalar(item, number))
+            .collect::<Result<Vec<_>>>()
+            .map(Yaml::List);
+    }
+    if text == "{}" {
+        return Ok(Yaml::Map(Vec::new()));
+    }
+    let unquoted = match text.as_bytes().first() {
+        Some(b'\'') => text
+            .strip_prefix('\'')
+            .and_then(|text| text.strip_suffix('\''))
+            .filter(|inner| !inner.replace("''", "").contains('\''))
+            .map(|inner| inner.replace("''", "'")),
+        Some(b'"') => text
+            .strip_prefix('"')
+            .and_then(|text| text.strip_suffix('"'))
+            .and_then(unescape),
+        _ => Some(text.to_string()),
+    };
+    unquoted.map(Yaml::Scalar).ok_or(Error::InvalidYaml(number))
+}
+
+/// Decode the escapes of a double quoted scalar
+fn unescape(text: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return None,
+            '\\' => {
+                let escaped = match chars.next()? {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    '0' => '\0',
+                    '"' => '"',
+                    '\\' => '\\',
+                    '/' => '/',
+                    kind @ ('x' | 'u') => {
+                        let digits = if kind == 'x' { 2 } else { 4 };
+                        let hex: String = chars.by_ref().take(digits).collect();
+                        if hex.len() != digits {
+                            return None;
+                        }
+                        char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+                    }
+                    _ => return None,
+                };
+                out.push(escaped);
+            }
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+/// Recursive descent over the lines of a document
+struct Parser<'a> {
+    lines: Vec<Line<'a>>,
+    at: usize,
+}
+
+impl Parser<'_> {
+    /// The block starting at the current line, indented by `indent`
+    fn node(&mut self, indent: usize) -> Result<Yaml> {
+        match self.lines.get(self.at) {
+            Some(line) if is_item(line.text) => self.list(indent),
+            Some(_) => self.map(indent),
+            None => Ok(Yaml::Scalar(String::new())),
+        }
+    }
+
+    /// The value of an entry or an item whose line ends with the key or
+    /// the dash: a nested block or an empty scalar
+    fn nested(&mut self, indent: usize, in_map: bool) -> Result<Yaml> {
+        match self.lines.get(self.at) {
+            Some(line) if line.indent > indent => self.node(line.indent),
+            // the items of a sequence can be at the indentation of its key
+            Some(line) if in_map && line.indent == indent && is_item(line.text) => {
+                self.list(indent)
+            }
+            _ => Ok(Yaml::Scalar(String::new())),
+        }
+    }
+
+    fn map(&mut self, indent: usize) -> Result<Yaml> {
+        let mut entries = Vec::new();
+        while let Some(line) = self.lines.get(self.at).copied() {
+            if line.indent < indent {
+                break;
+            }
+            if line.indent > indent || is_item(line.text) {
+                return Err(Error::InvalidYaml(line.number));
+            }
+            let end = key_end(line.text).ok_or(Error::InvalidYaml(line.number))?;
+            let key = match scalar(&line.text[..end], line.number)? {
+                Yaml::Scalar(key) => key,
+                _ => return Err(Error::InvalidYaml(line.number)),
+            };
+            let rest = line.text[end + 1..].trim();
+            self.at += 1;
+            let value = match rest {
+                "" => self.nested(indent, true)?,
+                rest => scalar(rest, line.number)?,
+            };
+            entries.push((key, value));
+        }
+        Ok(Yaml::Map(entries))
+    }
+
+    fn list(&mut self, indent: usize) -> Result<Yaml> {
+        let mut values = Vec::new();
+        while let Some(line) = self.lines.get(self.at).copied() {
+            if line.indent < indent || (line.indent == indent && !is_item(line.text)) {
+                break;
+            }
+            if line.indent > indent {
+                return Err(Error::InvalidYaml(line.number));
+            }
+            let rest = line.text[1..].trim_start_matches(' ');
+            if rest.is_empty() {
+                self.at += 1;
+                values.push(self.nested(indent, false)?);
+                continue;
+            }
+            if is_item(rest) || key_end(rest).is_some() {
+                // the item is a block starting on the line of its dash
+                let inner = indent + line.text.len() - rest.len();
+                self.lines[self.at] = Line {
+                    number: line.number,
+                    indent: inner,
+                    text: rest,
+                };
+                values.push(self.node(inner)?);
+                continue;
+            }
+            self.at += 1;
+            values.push(scalar(rest, line.number)?);
+        }
+        Ok(Yaml::List(values))
+    }
+}
+
+/// Write a scalar, quoted if it could be read as something else
+fn write_scalar(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    let plain = !value.is_empty()
+        && value.trim() == value
+        && !value.starts_with(|c| "-?:,[]{}#&*!|>'\"%@`".contains(c))
+        && !value.contains(": ")
+        && !value.contains(" #")
+        && !value.ends_with(':')
+        && !value.contains(|c: char| c.is_control() || c == ',');
+    if plain {
+        return f.write_str(value);
+    }
+    if value.contains(|c: char| c.is_control()) {
+        f.write_str("\"")?;
+        for c in value.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\t' => f.write_str("\\t")?,
+                c if c.is_control() && (c as u32) < 0x100 => write!(f, "\\x{:02x}", c as u32)?,
+                c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                c => write!(f, "{}", c)?,
+            }
+        }
+        return f.write_str("\"");
+    }
+    write!(f, "'{}'", value.replace('\'', "''"))
+}
+
+/// Write the value following a key or a dash, `inline` if it fits on the line
+fn write_value(f: &mut fmt::Formatter<'_>, value: &Yaml, indent: usize) -> fmt::Result {
+    match value {
+        Yaml::Scalar(value) => {
+            f.write_str(" ")?;
+            write_scalar(f, value)?;
+            writeln!(f)
+        }
+        Yaml::List(values) if values.iter().all(|value| value.as_str().is_some()) => {
+            f.write_str(" [")?;
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    f.write_str(", ")?;
+                }
+                write_scalar(f, value.as_str().unwrap_or_default())?;
+            }
+            writeln!(f, "]")
+        }
+        Yaml::Map(entries) if entries.is_empty() => writeln!(f, " {{}}"),
+        value => {
+            writeln!(f)?;
+            write_block(f, value, indent + 2, false)
+        }
+    }
+}
+
+/// Write a mapping or a sequence, the first line without its indentation
+/// when it follows a dash
+fn write_block(
+    f: &mut fmt::Formatter<'_>,
+    value: &Yaml,
+    indent: usize,
+    after_dash: bool,
+) -> fmt::Result {
+    let mut pad = !after_dash;
+    match value {
+        Yaml::Map(entries) => {
+            for (key, value) in entries {
+                if pad {
+                    write!(f, "{:indent$}", "")?;
+                }
+                pad = true;
+                write_scalar(f, key)?;
+                f.write_str(":")?;
+                write_value(f, value, indent)?;
+            }
+        }
+        Yaml::List(values) => {
+            for value in values {
+                if pad {
+                    write!(f, "{:indent$}", "")?;
+                }
+                pad = true;
+                f.write_str("-")?;
+                match value {
+                    Yaml::Map(entries) if !entries.is_empty() => {
+                        f.write_str(" ")?;
+                        write_block(f, value, indent + 2, true)?;
+                    }
+                    value => write_value(f, value, indent)?,
+                }
+            }
+        }
+        Yaml::Scalar(value) => {
+            write_scalar(f, value)?;
+            writeln!(f)?;
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for Yaml {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Yaml::Map(entries) if entries.is_empty() => writeln!(f, "{{}}"),
+            Yaml::List(values) if values.is_empty() => writeln!(f, "[]"),
+            value => write_block(f, value, 0, false),
+        }
+    }
+}
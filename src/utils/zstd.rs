@@ -0,0 +1,1012 @@
+//! A Zstandard decoder for the zstd streams of compressed sections
+//!
+//! A stream is a series of frames, each holding blocks stored as is,
+//! repeating a byte, or compressed: literals coded with a Huffman code and
+//! sequences of `(literals length, offset, match length)` coded with FSE,
+//! a variant of arithmetic coding. The compressed data is read backward
+//! from the last byte of its bit stream. Dictionaries aren't supported,
+//! the content checksum is verified when present.
+//! Sources:
+//! * <https://www.rfc-editor.org/rfc/rfc8878>
+use crate::alloc_prelude::*;
+use crate::{Error, Result};
+
+/// Magic number of a frame
+const MAGIC: u32 = 0xfd2f_b528;
+
+/// Magic numbers of the skippable frames, the low 4 bits are free
+const SKIPPABLE_MAGIC: u32 = 0x184d_2a50;
+
+/// Default repeated offsets of a frame
+const REPEATED_OFFSETS: [usize; 3] = [1, 4, 8];
+
+/// Maximum accuracy log of the literal length, match length and offset
+/// tables
+const LL_MAX_LOG: u8 = 9;
+const ML_MAX_LOG: u8 = 9;
+const OF_MAX_LOG: u8 = 8;
+
+/// Maximum number of bits of a Huffman code
+const HUFFMAN_MAX_LOG: u8 = 11;
+
+/// Default distribution of the literal length codes
+const LL_DEFAULT: [i16; 36] = [
+    4, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 2, 1, 1, 1, 1, 1,
+    -1, -1, -1, -1,
+];
+
+/// Default distribution of the match length codes
+const ML_DEFAULT: [i16; 53] = [
+    1, 4, 3, 2, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1, -1, -1,
+];
+
+/// Default distribution of the offset codes
+const OF_DEFAULT: [i16; 29] = [
+    1, 1, 1, 1, 1, 1, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1,
+];
+
+/// Baseline and extra bits of the literal length codes 16 to 35, the
+/// codes below 16 are the length itself
+const LL_CODES: [(u32, u8); 20] = [
+    (16, 1),
+    (18, 1),
+    (20, 1),
+    (22, 1),
+    (24, 2),
+    (28, 2),
+    (32, 3),
+    (40, 3),
+    (48, 4),
+    (64, 6),
+    (128, 7),
+    (256, 8),
+    (512, 9),
+    (1024, 10),
+    (2048, 11),
+    (4096, 12),
+    (8192, 13),
+    (16384, 14),
+    (32768, 15),
+    (65536, 16),
+];
+
+/// Baseline and extra bits of the match length codes 32 to 52, the codes
+/// below 32 are the length minus 3
+const ML_CODES: [(u32, u8); 21] = [
+    (35, 1),
+    (37, 1),
+    (39, 1),
+    (41, 1),
+    (43, 2),
+    (47, 2),
+    (51, 3),
+    (59, 3),
+    (67, 4),
+    (83, 4),
+    (99, 5),
+    (131, 7),
+    (259, 8),
+    (515, 9),
+    (1027, 10),
+    (2051, 11),
+    (4099, 12),
+    (8195, 13),
+    (16387, 14),
+    (32771, 15),
+    (65539, 16),
+];
+
+/// A position in the input, where a decoding error is reported
+type Offset = usize;
+
+fn invalid(offset: Offset) -> Error {
+    Error::InvalidCompressedData(offset as u64)
+}
+
+/// Reads the bits of a stream forward, least significant first, for the
+/// FSE table descriptions
+struct ForwardBits<'a> {
+    data: &'a [u8],
+    /// position in bits
+    position: usize,
+}
+
+impl ForwardBits<'_> {
+    fn bits(&mut self, count: u8) -> Option<u32> {
+        let mut value = 0u32;
+        for bit in 0..count as usize {
+            let at = self.position + bit;
+            let byte = *self.data.get(at / 8)?;
+            value |= (((byte >> (at % 8)) & 1) as u32) << bit;
+        }
+        self.position += count as usize;
+        Some(value)
+    }
+
+    fn peek(&self, count: u8) -> Option<u32> {
+        ForwardBits {
+            data: self.data,
+            position: self.position,
+        }
+        .bits(count)
+    }
+
+    /// Number of bytes read, the last one possibly partly
+    fn bytes(&self) -> usize {
+        self.position.div_ceil(8)
+    }
+}
+
+/// Reads the bits of a stream backward, from the highest bit below the
+/// padding bit of its last byte
+///
+/// Reading past the start gives zeros, [`BackwardBits::overflowed`]
+/// tells when it happened.
+struct BackwardBits<'a> {
+    data: &'a [u8],
+    /// number of bits left
+    position: isize,
+}
+
+impl<'a> BackwardBits<'a> {
+    fn new(data: &'a [u8], offset: Offset) -> Result<Self> {
+        let last = *data.last().ok_or_else(|| invalid(offset))?;
+        if last == 0 {
+            return Err(invalid(offset + data.len() - 1));
+        }
+        let padding = last.leading_zeros() as isize + 1;
+        Ok(BackwardBits {
+            data,
+            position: data.len() as isize * 8 - padding,
+        })
+    }
+
+    /// The `count` bits starting at bit `start`, zeros below bit 0
+    fn get(&self, start: isize, count: u8) -> u64 {
+        let count = count as isize;
+        if count == 0 || start + count <= 0 {
+            return 0;
+        }
+        if start < 0 {
+            return self.get(0, (count + start) as u8) << -start;
+        }
+        let start = start as usize;
+        let mut value = 0u64;
+        let first = start / 8;
+        let last = (start + count as usize - 1) / 8;
+        for (index, byte) in self.data[first..=last].iter().enumerate() {
+            value |= (*byte as u64) << (8 * index);
+        }
+        (value >> (start % 8)) & ((1u64 << count) - 1)
+    }
+
+    fn peek(&self, count: u8) -> u64 {
+        self.get(self.position - count as isize, count)
+    }
+
+    fn bits(&mut self, count: u8) -> u64 {
+        let value = self.peek(count);
+        self.position -= count as isize;
+        value
+    }
+
+    fn overflowed(&self) -> bool {
+        self.position < 0
+    }
+
+    fn finished(&self) -> bool {
+        self.position == 0
+    }
+}
+
+/// An FSE decoding table, the symbol, number of bits and baseline of the
+/// next state of each state
+#[derive(Debug, Default, Clone)]
+struct Fse {
+    accuracy_log: u8,
+    states: Vec<(u8, u8, u16)>,
+}
+
+impl Fse {
+    /// Build the table of a distribution, -1 for the symbols of
+    /// probability lower than 1
+    fn new(distribution: &[i16], accuracy_log: u8) -> Option<Self> {
+        let size = 1usize << accuracy_log;
+        let total: usize = distribution
+            .iter()
+            .map(|probability| probability.unsigned_abs() as usize)
+            .sum();
+        if total != size {
+            return None;
+        }
+        let mut symbols = vec![0u8; size];
+        let mut high = size;
+        for (symbol, probability) in distribution.iter().enumerate() {
+            if *probability == -1 {
+                high = high.checked_sub(1)?;
+                symbols[high] = symbol as u8;
+            }
+        }
+        let step = (size >> 1) + (size >> 3) + 3;
+        let mut position = 0;
+        for (symbol, probability) in distribution.iter().enumerate() {
+            for _ in 0..(*probability).max(0) {
+                *symbols.get_mut(position)? = symbol as u8;
+                position = (position + step) & (size - 1);
+                while position >= high {
+                    position = (position + step) & (size - 1);
+                }
+            }
+        }
+        let mut next: Vec<u32> = distribution
+            .iter()
+            .map(|probability| match probability {
+                -1 => 1,
+                probability => *probability as u32,
+            })
+            .collect();
+        let states = symbols
+            .iter()
+            .map(|symbol| {
+                let state = &mut next[*symbol as usize];
+                let bits = accuracy_log - (31 - state.leading_zeros()) as u8;
+                let baseline = ((*state << bits) as usize - size) as u16;
+                *state += 1;
+                (*symbol, bits, baseline)
+            })
+            .collect();
+        Some(Fse {
+            accuracy_log,
+            states,
+        })
+    }
+
+    /// The table of a single symbol, the RLE mode of the sequences
+    fn rle(symbol: u8) -> Self {
+        Fse {
+            accuracy_log: 0,
+            states: vec![(symbol, 0, 0)],
+        }
+    }
+
+    /// Read a table description, returns the table and the size of the
+    /// description
+    fn read(data: &[u8], max_log: u8, max_symbol: usize, offset: Offset) -> Result<(Self, usize)> {
+        let mut bits = ForwardBits { data, position: 0 };
+        let error = |bits: &ForwardBits| invalid(offset + bits.position / 8);
+        let accuracy_log = bits.bits(4).ok_or_else(|| error(&bits))? as u8 + 5;
+        if accuracy_log > max_log {
+            return Err(invalid(offset));
+        }
+        let mut remaining = (1i32 << accuracy_log) + 1;
+        let mut threshold = 1i32 << accuracy_log;
+        let mut count = accuracy_log + 1;
+        let mut distribution = Vec::new();
+        while remaining > 1 {
+            if distribution.len() > max_symbol {
+                return Err(error(&bits));
+            }
+            let max = (2 * threshold - 1) - remaining;
+            let value = bits.peek(count).ok_or_else(|| error(&bits))? as i32;
+            let value = if (value & (threshold - 1)) < max {
+                bits.position += count as usize - 1;
+                value & (threshold - 1)
+            } else {
+                bits.position += count as usize;
+                let value = value & (2 * threshold - 1);
+                match value >= threshold {
+                    true => value - max,
+                    false => value,
+                }
+            };
+            let probability = value - 1;
+            remaining -= probability.abs();
+            if remaining < 1 {
+                return Err(error(&bits));
+            }
+            distribution.push(probability as i16);
+            if probability == 0 {
+                loop {
+                    let repeat = bits.bits(2).ok_or_else(|| error(&bits))?;
+                    distribution.extend(core::iter::repeat_n(0, repeat as usize));
+                    if repeat != 3 {
+                        break;
+                    }
+                }
+            }
+            while remaining < threshold && count > 1 {
+                count -= 1;
+                threshold >>= 1;
+            }
+        }
+        if remaining != 1 || distribution.len() > max_symbol + 1 {
+            return Err(error(&bits));
+        }
+        let fse = Fse::new(&distribution, accuracy_log).ok_or_else(|| error(&bits))?;
+        Ok((fse, bits.bytes()))
+    }
+
+    fn init(&self, bits: &mut BackwardBits) -> usize {
+        bits.bits(self.accuracy_log) as usize
+    }
+
+    fn symbol(&self, state: usize) -> u8 {
+        self.states[state].0
+    }
+
+    fn update(&self, state: usize, bits: &mut BackwardBits) -> usize {
+        let (_, count, baseline) = self.states[state];
+        baseline as usize + bits.bits(count) as usize
+    }
+}
+
+/// A Huffman decoding table indexed by the next `max_bits` bits of the
+/// stream, the symbol and the length of its code
+#[derive(Debug, Default, Clone)]
+struct Huffman {
+    max_bits: u8,
+    entries: Vec<(u8, u8)>,
+}
+
+impl Huffman {
+    /// Read a tree description, returns the table and the size of the
+    /// description
+    fn read(data: &[u8], offset: Offset) -> Result<(Self, usize)> {
+        let header = *data.first().ok_or_else(|| invalid(offset))? as usize;
+        let (mut weights, size) = match header {
+            0..=127 => {
+                let compressed = data.get(1..1 + header).ok_or_else(|| invalid(offset))?;
+                (Self::fse_weights(compressed, offset + 1)?, 1 + header)
+            }
+            _ => {
+                let count = header - 127;
+                let bytes = data
+                    .get(1..1 + count.div_ceil(2))
+                    .ok_or_else(|| invalid(offset))?;
+                let weights = (0..count)
+                    .map(|index| match index % 2 {
+                        0 => bytes[index / 2] >> 4,
+                        _ => bytes[index / 2] & 0xf,
+                    })
+                    .collect();
+                (weights, 1 + bytes.len())
+            }
+        };
+        // the weight of the last symbol completes the sum to a power of 2
+        let mut sum = 0u32;
+        for weight in &weights {
+            if *weight > HUFFMAN_MAX_LOG {
+                return Err(invalid(offset));
+            }
+            if *weight > 0 {
+                sum += 1 << (weight - 1);
+            }
+        }
+        if sum == 0 || weights.len() > 255 {
+            return Err(invalid(offset));
+        }
+        let max_bits = (32 - sum.leading_zeros()) as u8;
+        let left = (1u32 << max_bits) - sum;
+        if !left.is_power_of_two() || max_bits > HUFFMAN_MAX_LOG {
+            return Err(invalid(offset));
+        }
+        weights.push(left.trailing_zeros() as u8 + 1);
+
+        let mut starts = [0usize; HUFFMAN_MAX_LOG as usize + 2];
+        let mut next = 0;
+        for (weight, start) in starts
+            .iter_mut()
+            .enumerate()
+            .take(max_bits as usize + 1)
+            .skip(1)
+        {
+            *start = next;
+            let count = weights.iter().filter(|w| **w as usize == weight).count();
+            next += count << (weight - 1);
+        }
+        let mut entries = vec![(0, 0); 1 << max_bits];
+        for (symbol, weight) in weights.iter().enumerate() {
+            if *weight == 0 {
+                continue;
+            }
+            let length = 1 << (weight - 1);
+            let start = starts[*weight as usize];
+            entries[start..start + length].fill((symbol as u8, max_bits + 1 - weight));
+            starts[*weight as usize] += length;
+        }
+        Ok((Huffman { max_bits, entries }, size))
+    }
+
+    /// Decode the weights compressed with FSE, two interleaved states
+    fn fse_weights(data: &[u8], offset: Offset) -> Result<Vec<u8>> {
+        let (fse, size) = Fse::read(data, 6, 255, offset)?;
+        let mut bits = BackwardBits::new(&data[size..], offset + size)?;
+        let mut states = [fse.init(&mut bits), fse.init(&mut bits)];
+        let mut weights = Vec::new();
+        for index in (0..2).cycle() {
+            if weights.len() > 255 {
+                return Err(invalid(offset));
+            }
+            weights.push(fse.symbol(states[index]));
+            states[index] = fse.update(states[index], &mut bits);
+            if bits.overflowed() {
+                weights.push(fse.symbol(states[1 - index]));
+                break;
+            }
+        }
+        Ok(weights)
+    }
+
+    /// Decode `size` literals of a stream
+    fn decode(&self, data: &[u8], size: usize, out: &mut Vec<u8>, offset: Offset) -> Result<()> {
+        let mut bits = BackwardBits::new(data, offset)?;
+        for _ in 0..size {
+            let (symbol, length) = self.entries[bits.peek(self.max_bits) as usize];
+            out.push(symbol);
+            bits.position -= length as isize;
+        }
+        match bits.finished() {
+            true => Ok(()),
+            false => Err(invalid(offset)),
+        }
+    }
+}
+
+/// What the blocks of a frame inherit from the previous ones
+struct State {
+    huffman: Option<Huffman>,
+    ll: Option<Fse>,
+    of: Option<Fse>,
+    ml: Option<Fse>,
+    offsets: [usize; 3],
+}
+
+/// Read the little endian number of `size` bytes
+fn little_endian(data: &[u8], at: usize, size: usize, offset: Offset) -> Result<u64> {
+    let bytes = data
+        .get(at..at + size)
+        .ok_or_else(|| invalid(offset + at))?;
+    Ok(bytes
+        .iter()
+        .rev()
+        .fold(0u64, |value, byte| (value << 8) | *byte as u64))
+}
+
+/// Decode the literals section of a compressed block, returns its size
+fn literals(
+    data: &[u8],
+    state: &mut State,
+    literals: &mut Vec<u8>,
+    offset: Offset,
+) -> Result<usize> {
+    let header = *data.first().ok_or_else(|| invalid(offset))?;
+    let (kind, format) = (header & 3, (header >> 2) & 3);
+    if kind < 2 {
+        let (size, header_size) = match format {
+            0 | 2 => ((header >> 3) as usize, 1),
+            1 => (little_endian(data, 0, 2, offset)? as usize >> 4, 2),
+            _ => (little_endian(data, 0, 3, offset)? as usize >> 4, 3),
+        };
+        let bytes = match kind {
+            0 => data.get(header_size..header_size + size),
+            _ => data.get(header_size..header_size + 1),
+        }
+        .ok_or_else(|| invalid(offset))?;
+        return match kind {
+            0 => {
+                literals.extend_from_slice(bytes);
+                Ok(header_size + size)
+            }
+            _ => {
+                literals.extend(core::iter::repeat_n(bytes[0], size));
+                Ok(header_size + 1)
+            }
+        };
+    }
+    let (header_size, bits, streams) = match format {
+        0 => (3, 10, 1),
+        1 => (3, 10, 4),
+        2 => (4, 14, 4),
+        _ => (5, 18, 4),
+    };
+    let value = little_endian(data, 0, header_size, offset)? >> 4;
+    let mask = (1u64 << bits) - 1;
+    let regenerated = (value & mask) as usize;
+    let compressed = ((value >> bits) & mask) as usize;
+    let mut data = data
+        .get(header_size..header_size + compressed)
+        .ok_or_else(|| invalid(offset))?;
+    let mut at = offset + header_size;
+    if kind == 2 {
+        let (huffman, size) = Huffman::read(data, at)?;
+        state.huffman = Some(huffman);
+        data = &data[size..];
+        at += size;
+    }
+    let huffman = state.huffman.as_ref().ok_or_else(|| invalid(offset))?;
+    match streams {
+        1 => huffman.decode(data, regenerated, literals, at)?,
+        _ => {
+            let sizes = [
+                little_endian(data, 0, 2, at)? as usize,
+                little_endian(data, 2, 2, at)? as usize,
+                little_endian(data, 4, 2, at)? as usize,
+            ];
+            let each = regenerated.div_ceil(4);
+            let last = regenerated
+                .checked_sub(3 * each)
+                .ok_or_else(|| invalid(offset))?;
+            let mut start = 6;
+            for size in sizes {
+                let stream = data.get(start..start + size).ok_or_else(|| invalid(at))?;
+                huffman.decode(stream, each, literals, at + start)?;
+                start += size;
+            }
+            let stream = data.get(start..).ok_or_else(|| invalid(at))?;
+            huffman.decode(stream, last, literals, at + start)?;
+        }
+    }
+    Ok(header_size + compressed)
+}
+
+/// Read the table of a sequence code in the mode of the block header
+fn table(
+    mode: u8,
+    data: &[u8],
+    previous: &mut Option<Fse>,
+    (default, default_log, max_log, max_symbol): (&[i16], u8, u8, usize),
+    offset: Offset,
+) -> Result<usize> {
+    let size = match mode {
+        0 => {
+            *previous = Some(Fse::new(default, default_log).ok_or_else(|| invalid(offset))?);
+            0
+        }
+        1 => {
+            let symbol = *data.first().ok_or_else(|| invalid(offset))?;
+            if symbol as usize > max_symbol {
+                return Err(invalid(offset));
+            }
+            *previous = Some(Fse::rle(symbol));
+            1
+        }
+        2 => {
+            let (fse, size) = Fse::read(data, max_log, max_symbol, offset)?;
+            *previous = Some(fse);
+            size
+        }
+        _ if previous.is_some() => 0,
+        _ => return Err(invalid(offset)),
+    };
+    Ok(size)
+}
+
+/// Decode the sequences section of a compressed block and execute it
+fn sequences(
+    data: &[u8],
+    state: &mut State,
+    literals: &[u8],
+    out: &mut Vec<u8>,
+    limit: usize,
+    frame_start: usize,
+    offset: Offset,
+) -> Result<()> {
+    let byte = |at: usize| data.get(at).copied().ok_or_else(|| invalid(offset + at));
+    let (count, mut at) = match byte(0)? {
+        0 => (0, 1),
+        first @ 1..=127 => (first as usize, 1),
+        first @ 128..=254 => ((((first - 128) as usize) << 8) + byte(1)? as usize, 2),
+        _ => (byte(1)? as usize + ((byte(2)? as usize) << 8) + 0x7f00, 3),
+    };
+    let mut literals = literals;
+    if count > 0 {
+        let modes = byte(at)?;
+        at += 1;
+        if modes & 3 != 0 {
+            return Err(invalid(offset + at - 1));
+        }
+        at += table(
+            modes >> 6,
+            &data[at..],
+            &mut state.ll,
+            (&LL_DEFAULT, 6, LL_MAX_LOG, 35),
+            offset + at,
+        )?;
+        at += table(
+            (modes >> 4) & 3,
+            &data[at..],
+            &mut state.of,
+            (&OF_DEFAULT, 5, OF_MAX_LOG, 31),
+            offset + at,
+        )?;
+        at += table(
+            (modes >> 2) & 3,
+            &data[at..],
+            &mut state.ml,
+            (&ML_DEFAULT, 6, ML_MAX_LOG, 52),
+            offset + at,
+        )?;
+        let (ll, of, ml) = match (&state.ll, &state.of, &state.ml) {
+            (Some(ll), Some(of), Some(ml)) => (ll, of, ml),
+            _ => return Err(invalid(offset)),
+        };
+        let mut bits = BackwardBits::new(&data[at..], offset + at)?;
+        let (mut ll_state, mut of_state, mut ml_state) =
+            (ll.init(&mut bits), of.init(&mut bits), ml.init(&mut bits));
+        for index in 0..count {
+            let of_code = of.symbol(of_state);
+            let ml_code = ml.symbol(ml_state) as usize;
+            let ll_code = ll.symbol(ll_state) as usize;
+            if of_code > 31 {
+                return Err(invalid(offset + at));
+            }
+            let value = (1u64 << of_code) as usize + bits.bits(of_code) as usize;
+            let match_length = match ml_code {
+                0..=31 => ml_code + 3,
+                _ => {
+                    let (baseline, extra) = ML_CODES[ml_code - 32];
+                    baseline as usize + bits.bits(extra) as usize
+                }
+            };
+            let literal_length = match ll_code {
+                0..=15 => ll_code,
+                _ => {
+                    let (baseline, extra) = LL_CODES[ll_code - 16];
+                    baseline as usize + bits.bits(extra) as usize
+                }
+            };
+            if index + 1 < count {
+                ll_state = ll.update(ll_state, &mut bits);
+                ml_state = ml.update(ml_state, &mut bits);
+                of_state = of.update(of_state, &mut bits);
+            }
+            if bits.overflowed() {
+                return Err(invalid(offset + at));
+            }
+
+            let offsets = &mut state.offsets;
+            let distance = match value {
+                4.. => {
+                    let distance = value - 3;
+                    *offsets = [distance, offsets[0], offsets[1]];
+                    distance
+                }
+                _ => match value + (literal_length == 0) as usize {
+                    1 => offsets[0],
+                    2 => {
+                        offsets.swap(0, 1);
+                        offsets[0]
+                    }
+                    repeat => {
+                        let distance = match repeat {
+                            3 => offsets[2],
+                            _ => offsets[0].wrapping_sub(1),
+                        };
+                        if distance == 0 {
+                            return Err(invalid(offset + at));
+                        }
+                        *offsets = [distance, offsets[0], offsets[1]];
+                        distance
+                    }
+                },
+            };
+
+            let copied = literals
+                .get(..literal_length)
+                .ok_or_else(|| invalid(offset + at))?;
+            if out.len() + literal_length + match_length > limit {
+                return Err(invalid(offset + at));
+            }
+            out.extend_from_slice(copied);
+            literals = &literals[literal_length..];
+            if distance > out.len() - frame_start {
+                return Err(invalid(offset + at));
+            }
+            let start = out.len() - distance;
+            for from in start..start + match_length {
+                out.push(out[from]);
+            }
+        }
+        if !bits.finished() {
+            return Err(invalid(offset + at));
+        }
+    } else if at != data.len() {
+        return Err(invalid(offset + at));
+    }
+    if out.len() + literals.len() > limit {
+        return Err(invalid(offset));
+    }
+    out.extend_from_slice(literals);
+    Ok(())
+}
+
+/// Decode one frame, returns its size
+fn frame(data: &[u8], out: &mut Vec<u8>, limit: usize, offset: Offset) -> Result<usize> {
+    let descriptor = *data.get(4).ok_or_else(|| invalid(offset + 4))?;
+    let content_size_flag = descriptor >> 6;
+    let single_segment = descriptor & 0x20 != 0;
+    let checksum = descriptor & 0x04 != 0;
+    let dictionary_size = [0, 1, 2, 4][(descriptor & 3) as usize];
+    if descriptor & 0x08 != 0 {
+        return Err(invalid(offset + 4));
+    }
+    let mut at = 5 + (!single_segment) as usize;
+    let dictionary = little_endian(data, at, dictionary_size, offset)?;
+    if dictionary != 0 {
+        return Err(invalid(offset + 5));
+    }
+    at += dictionary_size;
+    let content_size = match (content_size_flag, single_segment) {
+        (0, false) => None,
+        (0, true) => Some(little_endian(data, at, 1, offset)?),
+        (1, _) => Some(little_endian(data, at, 2, offset)? + 256),
+        (2, _) => Some(little_endian(data, at, 4, offset)?),
+        _ => Some(little_endian(data, at, 8, offset)?),
+    };
+    at += match (content_size_flag, single_segment) {
+        (0, false) => 0,
+        (0, true) => 1,
+        (flag, _) => 1 << flag,
+    };
+
+    let start = out.len();
+    let mut state = State {
+        huffman: None,
+        ll: None,
+        of: None,
+        ml: None,
+        offsets: REPEATED_OFFSETS,
+    };
+    let mut block_literals = Vec::new();
+    loop {
+        let header = little_endian(data, at, 3, offset)? as usize;
+        at += 3;
+        let (last, kind, size) = (header & 1 != 0, (header >> 1) & 3, header >> 3);
+        match kind {
+            0 => {
+                let bytes = data
+                    .get(at..at + size)
+                    .ok_or_else(|| invalid(offset + at))?;
+                if out.len() + size > limit {
+                    return Err(invalid(offset + at));
+                }
+                out.extend_from_slice(bytes);
+                at += size;
+            }
+            1 => {
+                let byte = *data.get(at).ok_or_else(|| invalid(offset + at))?;
+                if out.len() + size > limit {
+                    return Err(invalid(offset + at));
+                }
+                out.extend(core::iter::repeat_n(byte, size));
+                at += 1;
+            }
+            2 => {
+                let block = data
+                    .get(at..at + size)
+                    .ok_or_else(|| invalid(offset + at))?;
+                block_literals.clear();
+                let used = literals(block, &mut state, &mut block_literals, offset + at)?;
+                sequences(
+                    &block[used..],
+                    &mut state,
+                    &block_literals,
+                    out,
+                    limit,
+                    start,
+                    offset + at + used,
+                )?;
+                at += size;
+            }
+            _ => return Err(invalid(offset + at - 3)),
+        }
+        if last {
+            break;
+        }
+    }
+    if content_size.is_some_and(|size| size != (out.len() - start) as u64) {
+        return Err(invalid(offset + at));
+    }
+    if checksum {
+        let expected = little_endian(data, at, 4, offset)? as u32;
+        if xxh64(&out[start..]) as u32 != expected {
+            return Err(invalid(offset + at));
+        }
+        at += 4;
+    }
+    Ok(at)
+}
+
+/// Decode a zstd stream of `size` bytes
+///
+/// Fails if a frame is invalid or truncated, needs a dictionary, or if
+/// the stream doesn't decode to `size` bytes.
+pub fn decompress(data: &[u8], size: usize) -> Result<Vec<u8>> {
+    // the size comes from the file, only trust it up to a ratio
+    let mut out = Vec::with_capacity(size.min(data.len().saturating_mul(64)));
+    let mut at = 0;
+    while at < data.len() {
+        let magic = little_endian(data, at, 4, 0)? as u32;
+        if magic & 0xffff_fff0 == SKIPPABLE_MAGIC {
+            let skipped = little_endian(data, at + 4, 4, 0)? as usize;
+            at = at
+                .checked_add(8 + skipped)
+                .filter(|end| *end <= data.len())
+                .ok_or_else(|| invalid(at))?;
+            continue;
+        }
+        if magic != MAGIC {
+            return Err(invalid(at));
+        }
+        at += frame(&data[at..], &mut out, size, at)?;
+    }
+    match out.len() == size {
+        true => Ok(out),
+        false => Err(invalid(data.len())),
+    }
+}
+
+/// The XXH64 hash with a seed of 0, whose low 32 bits are the content
+/// checksum
+fn xxh64(data: &[u8]) -> u64 {
+    const PRIME1: u64 = 0x9e37_79b1_85eb_ca87;
+    const PRIME2: u64 = 0xc2b2_ae3d_27d4_eb4f;
+    const PRIME3: u64 = 0x1656_67b1_9e37_79f9;
+    const PRIME4: u64 = 0x85eb_ca77_c2b2_ae63;
+    const PRIME5: u64 = 0x27d4_eb2f_1656_67c5;
+    let word = |bytes: &[u8]| u64::from_le_bytes(bytes[..8].try_into().expect("8 bytes"));
+    let round = |acc: u64, input: u64| {
+        acc.wrapping_add(input.wrapping_mul(PRIME2))
+            .rotate_left(31)
+            .wrapping_mul(PRIME1)
+    };
+    let merge = |acc: u64, value: u64| {
+        (acc ^ round(0, value))
+            .wrapping_mul(PRIME1)
+            .wrapping_add(PRIME4)
+    };
+
+    let mut chunks = data.chunks_exact(32);
+    let mut hash = if data.len() >= 32 {
+        let mut acc = [
+            PRIME1.wrapping_add(PRIME2),
+            PRIME2,
+            0,
+            0u64.wrapping_sub(PRIME1),
+        ];
+        for chunk in &mut chunks {
+            for (lane, acc) in acc.iter_mut().enumerate() {
+                *acc = round(*acc, word(&chunk[lane * 8..]));
+            }
+        }
+        let hash = acc[0]
+            .rotate_left(1)
+            .wrapping_add(acc[1].rotate_left(7))
+            .wrapping_add(acc[2].rotate_left(12))
+            .wrapping_add(acc[3].rotate_left(18));
+        acc.iter().fold(hash, |hash, acc| merge(hash, *acc))
+    } else {
+        PRIME5
+    };
+    hash = hash.wrapping_add(data.len() as u64);
+
+    let mut rest = chunks.remainder();
+    while rest.len() >= 8 {
+        hash = (hash ^ round(0, word(rest)))
+            .rotate_left(27)
+            .wrapping_mul(PRIME1)
+            .wrapping_add(PRIME4);
+        rest = &rest[8..];
+    }
+    if rest.len() >= 4 {
+        let value = u32::from_le_bytes(rest[..4].try_into().expect("4 bytes")) as u64;
+        hash = (hash ^ value.wrapping_mul(PRIME1))
+            .rotate_left(23)
+            .wrapping_mul(PRIME2)
+            .wrapping_add(PRIME3);
+        rest = &rest[4..];
+    }
+    for byte in rest {
+        hash = (hash ^ (*byte as u64).wrapping_mul(PRIME5))
+            .rotate_left(11)
+            .wrapping_mul(PRIME1);
+    }
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(PRIME2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(PRIME3);
+    hash ^ (hash >> 32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `readbin\n` in a raw block, with a checksum
+    const RAW: &[u8] = &[
+        0x28, 0xb5, 0x2f, 0xfd, 0x24, 0x08, 0x41, 0x00, 0x00, 0x72, 0x65, 0x61, 0x64, 0x62, 0x69,
+        0x6e, 0x0a, 0x9f, 0x16, 0x77, 0xf4,
+    ];
+
+    /// 300000 zeros, a compressed block then two RLE blocks
+    const ZEROS: &[u8] = &[
+        0x28, 0xb5, 0x2f, 0xfd, 0xa4, 0xe0, 0x93, 0x04, 0x00, 0x4c, 0x00, 0x00, 0x08, 0x00, 0x01,
+        0x00, 0xfc, 0xff, 0x39, 0x10, 0x02, 0x02, 0x00, 0x10, 0x00, 0x03, 0x9f, 0x04, 0x00, 0x2d,
+        0x28, 0xde, 0x26,
+    ];
+
+    /// The 48 lines of [`records`] in a compressed block, with Huffman
+    /// coded literals, FSE tables for the three sequence codes and repeated
+    /// offsets
+    const RECORDS: &[u8] = &[
+        0x28, 0xb5, 0x2f, 0xfd, 0x64, 0xa6, 0x03, 0x65, 0x06, 0x00, 0x72, 0xcb, 0x1c, 0x17, 0x80,
+        0x29, 0x69, 0x03, 0x04, 0x6d, 0x71, 0xbc, 0x48, 0x11, 0x22, 0x52, 0x87, 0xd0, 0xca, 0x9d,
+        0x52, 0xba, 0xb4, 0x18, 0x03, 0xa9, 0x10, 0x05, 0x3b, 0xb9, 0xfa, 0x8f, 0x5e, 0xc6, 0x7f,
+        0xc3, 0xf7, 0xe8, 0x8b, 0xf1, 0x67, 0x73, 0x3b, 0x1f, 0x6a, 0x98, 0x94, 0x99, 0xaa, 0xcc,
+        0x22, 0x17, 0x74, 0x0f, 0xb2, 0x4f, 0x35, 0x99, 0xc5, 0xfd, 0xf1, 0xfe, 0xbc, 0x35, 0xc1,
+        0x55, 0x62, 0xaa, 0xf9, 0xbd, 0x5d, 0xba, 0x2d, 0x65, 0x11, 0x3a, 0x2d, 0xaf, 0xca, 0xbf,
+        0x6c, 0x59, 0xb2, 0xf0, 0x05, 0x09, 0xe7, 0xce, 0xf8, 0x3a, 0xd2, 0x98, 0x1b, 0x5d, 0x2f,
+        0xdd, 0x87, 0xbe, 0x9e, 0x2c, 0xcc, 0x81, 0x30, 0x00, 0x04, 0x18, 0x18, 0x08, 0x08, 0x81,
+        0x82, 0x83, 0x01, 0x62, 0x66, 0x38, 0x9f, 0x09, 0x5e, 0xa8, 0x21, 0x54, 0xbb, 0xfb, 0x9f,
+        0x01, 0xb0, 0xa3, 0x92, 0x07, 0x10, 0x4a, 0x86, 0xe2, 0xb9, 0x1e, 0x84, 0x42, 0xa2, 0x08,
+        0x8a, 0x30, 0x45, 0x28, 0x84, 0x42, 0xa2, 0xd0, 0x72, 0x32, 0x3a, 0xe1, 0xe6, 0x51, 0x0d,
+        0xb5, 0xa3, 0x38, 0x8d, 0xd4, 0x48, 0xeb, 0x97, 0x8c, 0x7d, 0x8f, 0x98, 0xff, 0xc2, 0xef,
+        0x73, 0xaa, 0x27, 0x44, 0x62, 0x44, 0x7e, 0x38, 0xf8, 0x1e, 0xdd, 0x83, 0x8b, 0x5d, 0x90,
+        0xae, 0x6f, 0x1b, 0x5d, 0xdc, 0x60, 0x9b, 0x07, 0xaf, 0x8f, 0xf7, 0xfb, 0xc4, 0x63, 0x5e,
+        0x60, 0xbb, 0xad, 0x02, 0x61, 0xa2, 0xc1, 0x17,
+    ];
+
+    /// Lines of the same width whose fields change, so that the matches
+    /// between them repeat the offsets of the previous ones
+    fn records(count: u32) -> Vec<u8> {
+        (0..count)
+            .flat_map(|index| {
+                let address = 0x40_1000 + index * index * 16;
+                format!("{:08x} FUNC GLOBAL f{}\n", address, index).into_bytes()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn raw_block() {
+        assert_eq!(decompress(RAW, 8), Ok(b"readbin\n".to_vec()));
+    }
+
+    #[test]
+    fn rle_blocks() {
+        assert_eq!(decompress(ZEROS, 300_000), Ok(vec![0; 300_000]));
+    }
+
+    #[test]
+    fn compressed_block() {
+        let expected = records(48);
+        assert_eq!(expected.len(), 1190);
+        assert_eq!(decompress(RECORDS, expected.len()), Ok(expected));
+    }
+
+    #[test]
+    fn frames_and_skippable_frames() {
+        let mut data = RAW.to_vec();
+        data.extend(SKIPPABLE_MAGIC.to_le_bytes());
+        data.extend(3u32.to_le_bytes());
+        data.extend(b"pad");
+        data.extend(RECORDS);
+        data.extend(RAW);
+        let mut expected = b"readbin\n".to_vec();
+        expected.extend(records(48));
+        expected.extend(b"readbin\n");
+        assert_eq!(decompress(&data, expected.len()), Ok(expected));
+    }
+
+    #[test]
+    fn invalid_streams() {
+        let mut data = RAW.to_vec();
+        data[RAW.len() - 1] ^= 1;
+        assert_eq!(decompress(&data, 8), Err(invalid(17)));
+        // the size of the section must be the decoded one
+        assert_eq!(decompress(RAW, 9), Err(invalid(21)));
+        assert!(decompress(&RECORDS[..RECORDS.len() - 10], 1190).is_err());
+        assert_eq!(decompress(&RAW[1..], 8), Err(invalid(0)));
+    }
+}
@@ -0,0 +1,1138 @@
+//! The public API of the crate, used like a program depending on it would
+use readbin::headers::elf::builder::{ElfBuilder, Section, Segment, Symbol};
+use readbin::headers::elf::description;
+use readbin::headers::elf::find::Pattern;
+use readbin::headers::elf::machine::Machine;
+use readbin::headers::elf::process;
+use readbin::headers::elf::program::{PF, PT};
+use readbin::headers::elf::section::{SHF, SHT};
+use readbin::headers::elf::strings::{self, Encoding};
+use readbin::headers::elf::symbols::{STB, STT};
+use readbin::headers::elf::types::Type;
+use readbin::headers::ihex;
+use readbin::prelude::*;
+use readbin::utils::yaml::Yaml;
+
+/// A small executable with a code section and a string, built from YAML
+const DESCRIPTION: &str = "FileHeader:
+  Class: ELFCLASS64
+  Data: ELFDATA2LSB
+  Type: ET_EXEC
+  Machine: EM_X86_64
+  Entry: 0x401000
+ProgramHeaders:
+  - Type: PT_LOAD
+    Flags: [PF_X, PF_R]
+    FirstSection: .text
+    LastSection: .rodata
+    Align: 0x1000
+Sections:
+  - Name: .text
+    Type: SHT_PROGBITS
+    Flags: [SHF_ALLOC, SHF_EXECINSTR]
+    Address: 0x401000
+    AddressAlign: 0x10
+    Content: 31c0c3
+  - Name: .rodata
+    Type: SHT_PROGBITS
+    Flags: [SHF_ALLOC]
+    Address: 0x401003
+    Content: 68656c6c6f20776f726c6400
+";
+
+fn build() -> Vec<u8> {
+    let yaml = Yaml::parse(DESCRIPTION).expect("valid YAML");
+    description::build(&yaml).expect("valid description")
+}
+
+#[test]
+fn identify_by_magic() {
+    assert_eq!(identify(b"\x7fELF\x02\x01\x01"), Format::Elf);
+    assert_eq!(identify(b"\0asm\x01\0\0\0"), Format::Wasm);
+    assert_eq!(identify(b"!<arch>\n"), Format::Archive);
+    assert_eq!(identify(b"#!/bin/sh\n"), Format::Unknown);
+}
+
+#[test]
+fn parse_rejects_unknown_and_truncated_files() {
+    assert_eq!(
+        parse(b"#!/bin/sh\n").err(),
+        Some(Error::UnsupportedFormat(Format::Unknown))
+    );
+    let data = build();
+    assert!(parse(&data[..32]).is_err());
+    assert!(Elf::parse(&data[..32]).is_err());
+}
+
+#[test]
+fn parse_built_executable() {
+    let data = build();
+    let header = match parse(&data) {
+        Ok(Binary::Elf(ElfHeader::Elf64(header))) => header,
+        other => panic!(
+            "expected an ELF64 file, got {:?}",
+            other.map(|b| b.format())
+        ),
+    };
+    assert_eq!(header.e_entry, 0x401000);
+
+    let elf = Elf::parse(&data).expect("valid ELF64 file");
+    let text = elf.section_by_name(".text").expect(".text section");
+    assert_eq!(text.sh_type, SHT::PROGBITS);
+    assert_eq!(text.sh_addr, 0x401000);
+    assert_eq!(elf.section_bytes(".text"), Some(&[0x31, 0xc0, 0xc3][..]));
+    let loads: Vec<_> = elf
+        .program_headers()
+        .expect("program headers")
+        .filter(|phdr| phdr.p_type == PT::LOAD)
+        .collect();
+    assert_eq!(loads.len(), 1);
+    assert_eq!(
+        elf.file_offset_for_vaddr(0x401003),
+        Some(text.sh_offset + 3)
+    );
+
+    // an offset past 64 bits isn't in the file
+    let mut broken = data.clone();
+    let at = header.e_phoff as usize + 8;
+    broken[at..at + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+    let elf = Elf::parse(&broken).expect("valid file header");
+    assert_eq!(elf.file_offset_for_vaddr(0x401003), None);
+}
+
+#[test]
+fn cached_tables() {
+    let data = build();
+    let elf = Elf::parse(&data).expect("valid ELF64 file");
+    let offsets: Vec<_> = elf
+        .sections()
+        .expect("section headers")
+        .map(|shdr| shdr.sh_offset)
+        .collect();
+    let cached = elf.section_headers().expect("section headers");
+    assert!(cached.iter().map(|shdr| shdr.sh_offset).eq(offsets));
+    assert!(core::ptr::eq(
+        cached,
+        elf.section_headers().expect("cached")
+    ));
+    assert_eq!(
+        elf.section_containing(0x401004)
+            .expect("sections")
+            .map(|(index, _)| index),
+        Some(2)
+    );
+
+    // the error of a table is kept too
+    let mut broken = data.clone();
+    broken[0x28..0x30].copy_from_slice(&u64::MAX.to_le_bytes());
+    let elf = Elf::parse(&broken).expect("valid file header");
+    let error = elf.section_headers().expect_err("no section headers");
+    assert_eq!(elf.section_headers().err(), Some(error.clone()));
+    assert_eq!(elf.symbols().err(), Some(error));
+}
+
+#[test]
+fn strings_and_load_image() {
+    let data = build();
+    let elf = Elf::parse(&data).expect("valid ELF64 file");
+    let found = elf.strings(4, Encoding::Ascii).expect("strings");
+    let hit = found
+        .hits
+        .iter()
+        .find(|hit| hit.text == "hello world")
+        .expect("the .rodata string");
+    assert_eq!(hit.section, Some(".rodata".into()));
+    assert_eq!(hit.address, Some(0x401003));
+
+    let image: LoadImage = elf.load_image().expect("memory image");
+    assert_eq!(image.base, 0x401000);
+    assert_eq!(image.get(0x401003, 5), Some(&b"hello"[..]));
+}
+
+#[test]
+fn find_patterns() {
+    let data = build();
+    let elf = Elf::parse(&data).expect("valid ELF64 file");
+    let pattern = Pattern::hex("31 c? c3").expect("valid pattern");
+    let found = elf.find(&pattern).expect("matches");
+    assert_eq!(found.matches.len(), 1);
+    assert_eq!(found.matches[0].section, Some(".text".into()));
+    assert_eq!(found.matches[0].address, Some(0x401000));
+    let found = elf.find(&Pattern::text("world")).expect("matches");
+    assert_eq!(found.matches[0].address, Some(0x401009));
+    assert_eq!(Pattern::hex("31c0 ?? c3"), Pattern::hex("31 c0 ? c3"));
+    assert_eq!(Pattern::hex("31 c"), Err(Error::InvalidPattern(4)));
+    assert_eq!(Pattern::hex("31 cg"), Err(Error::InvalidPattern(4)));
+}
+
+#[test]
+fn build_from_scratch() {
+    let mut builder = ElfBuilder::new(Type::Exec, Machine::X86_64);
+    // mov edi, [rip + status]; mov eax, 60; syscall
+    let code = [
+        0x8b, 0x3d, 0x0a, 0x10, 0x00, 0x00, 0xb8, 0x3c, 0, 0, 0, 0x0f, 0x05,
+    ];
+    let text = builder.add_section(
+        Section::new(".text", SHT::PROGBITS)
+            .flags(SHF::ALLOC | SHF::EXECINSTR)
+            .align(16)
+            .content(code),
+    );
+    let data = builder.add_section(
+        Section::new(".data", SHT::PROGBITS)
+            .flags(SHF::ALLOC | SHF::WRITE)
+            .align(4)
+            .content(7u32.to_le_bytes()),
+    );
+    let bss = builder.add_section(
+        Section::new(".bss", SHT::NOBITS)
+            .flags(SHF::ALLOC | SHF::WRITE)
+            .align(16)
+            .size(0x20),
+    );
+    builder.add_segment(Segment::new(PT::LOAD, PF::R | PF::X).sections(text, text));
+    builder.add_segment(Segment::new(PT::LOAD, PF::R | PF::W).sections(data, bss));
+    builder.add_segment(Segment::new(PT::GNU_STACK, PF::R | PF::W));
+    builder.add_symbol(Symbol::new("_start").kind(STT::FUNC).section(text).size(13));
+    builder.add_symbol(
+        Symbol::new("status")
+            .bind(STB::LOCAL)
+            .kind(STT::OBJECT)
+            .section(data)
+            .size(4),
+    );
+    let builder = builder.entry_symbol("_start");
+    let bytes = builder.build().expect("valid file");
+
+    let elf = Elf::parse(&bytes).expect("valid ELF64 file");
+    let text = elf.section_by_name(".text").expect(".text section");
+    let data = elf.section_by_name(".data").expect(".data section");
+    let bss = elf.section_by_name(".bss").expect(".bss section");
+    assert_eq!(elf.header().e_entry, text.sh_addr);
+    assert_eq!(text.sh_addr % 0x1000, text.sh_offset % 0x1000);
+    assert_eq!(data.sh_addr % 0x1000, data.sh_offset % 0x1000);
+    assert!(data.sh_addr >= text.sh_addr + 0x1000);
+    assert_eq!(bss.sh_addr, data.sh_addr + 0x10);
+    // the rip relative load of the code reads .data
+    assert_eq!(text.sh_addr + 6 + 0x100a, data.sh_addr);
+    assert_eq!(elf.section_bytes(".data"), Some(&7u32.to_le_bytes()[..]));
+    assert!(elf.lint().expect("lint").max_severity().is_none());
+
+    let symtab = elf.symbols().expect("symbols").expect(".symtab");
+    let names: Vec<_> = symtab
+        .symbols
+        .clone()
+        .map(|symbol| symtab.name(&symbol).expect("name"))
+        .collect();
+    assert_eq!(names, ["", "status", "_start"]);
+    assert_eq!(symtab.section.sh_info, 2);
+    let relocatable = ElfBuilder::new(Type::Rel, Machine::X86_64)
+        .build()
+        .expect("empty file");
+    assert!(Elf::parse(&relocatable).is_ok());
+}
+
+/// An xz stream storing `data` in uncompressed LZMA2 chunks, with a CRC32
+#[cfg(feature = "xz")]
+fn stored_xz(data: &[u8]) -> Vec<u8> {
+    use readbin::headers::elf::debuglink::crc32;
+    let mut out = vec![0xfd, b'7', b'z', b'X', b'Z', 0, 0, 1];
+    out.extend(crc32(&[0, 1]).to_le_bytes());
+    // one LZMA2 filter with a 8 MiB dictionary
+    let header = [2, 0, 0x21, 1, 0x16, 0, 0, 0];
+    out.extend(header);
+    out.extend(crc32(&header).to_le_bytes());
+    let start = out.len();
+    for (index, chunk) in data.chunks(0x10000).enumerate() {
+        out.push(if index == 0 { 1 } else { 2 });
+        out.extend(((chunk.len() - 1) as u16).to_be_bytes());
+        out.extend(chunk);
+    }
+    out.push(0);
+    let unpadded = (12 + out.len() - start + 4) as u32;
+    out.resize(out.len().next_multiple_of(4), 0);
+    out.extend(crc32(data).to_le_bytes());
+    let mut index = vec![0, 1];
+    for mut value in [unpadded, data.len() as u32] {
+        while value >= 0x80 {
+            index.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        index.push(value as u8);
+    }
+    index.resize(index.len().next_multiple_of(4), 0);
+    index.extend(crc32(&index).to_le_bytes());
+    let footer = [
+        ((index.len() / 4 - 1) as u32).to_le_bytes(),
+        [0, 1, b'Y', b'Z'],
+    ]
+    .concat();
+    out.extend(&index);
+    out.extend(crc32(&footer[..6]).to_le_bytes());
+    out.extend(footer);
+    out
+}
+
+#[test]
+#[cfg(feature = "xz")]
+fn mini_debug_info() {
+    let mut mini = ElfBuilder::new(Type::Dyn, Machine::X86_64);
+    let text = mini.add_section(
+        Section::new(".text", SHT::NOBITS)
+            .flags(SHF::ALLOC | SHF::EXECINSTR)
+            .size(0x100),
+    );
+    mini.add_symbol(
+        Symbol::new("helper")
+            .bind(STB::LOCAL)
+            .kind(STT::FUNC)
+            .section(text)
+            .size(4),
+    );
+    let mini = mini.build().expect("valid file");
+
+    let mut builder = ElfBuilder::new(Type::Dyn, Machine::X86_64);
+    builder.add_section(Section::new(".gnu_debugdata", SHT::PROGBITS).content(stored_xz(&mini)));
+    let bytes = builder.build().expect("valid file");
+    let elf = Elf::parse(&bytes).expect("valid ELF64 file");
+    assert!(elf.symbols().expect("symbols").is_none());
+    let decoded = elf.mini_debug_info().expect("valid xz stream");
+    assert_eq!(decoded.as_deref(), Some(&mini[..]));
+    let decoded = decoded.expect("MiniDebugInfo");
+    let mini = Elf::parse(&decoded).expect("valid embedded file");
+    let names: Vec<_> = mini
+        .all_symbols()
+        .expect("symbols")
+        .map(|symbol| symbol.name)
+        .collect();
+    assert_eq!(names, ["", "helper"]);
+}
+
+#[test]
+fn process_maps() {
+    let data = build();
+    let maps = "\
+00401000-00402000 r-xp 00000000 fd:01 1234                       /opt/hello world
+00402000-00403000 rw-p 00000000 00:00 0 \n
+7ffd1000-7ffd2000 r--p 00000000 00:00 0                          [vvar]
+";
+    let regions = process::regions(maps).expect("valid maps");
+    assert_eq!(regions.len(), 3);
+    assert_eq!(regions[0].path, "/opt/hello world");
+    assert_eq!((regions[1].inode, regions[1].path), (0, ""));
+    assert_eq!(
+        process::regions("00400000 r-xp\n").err(),
+        Some(Error::InvalidMaps(1))
+    );
+
+    let found = process::from_maps(42, maps, "/opt/hello world", |address, len| {
+        let start = usize::try_from(address.checked_sub(0x401000)?).ok()?;
+        data.get(start..start + len).map(<[u8]>::to_vec)
+    })
+    .expect("valid maps");
+    let module = match &found.modules[..] {
+        [module] => module,
+        modules => panic!("expected one file, found {}", modules.len()),
+    };
+    assert!(module.main);
+    assert_eq!((module.start, module.end), (0x401000, 0x403000));
+    assert_eq!(module.bias, 0);
+    assert_eq!(module.entry(), Some(0x401000));
+    assert!(found.link_map.is_empty());
+}
+
+#[test]
+#[cfg(all(target_os = "linux", feature = "proc"))]
+fn this_process() {
+    let found = process::from_pid(std::process::id()).expect("readable process");
+    let exe = std::env::current_exe().expect("test binary");
+    let main = found
+        .modules
+        .iter()
+        .find(|module| module.main)
+        .expect("the test binary");
+    assert_eq!(main.path, exe.to_string_lossy());
+    let entry = main.entry().expect("entry point");
+    assert!((main.start..main.end).contains(&entry));
+}
+
+#[test]
+fn export_and_build_back() {
+    let data = build();
+    let elf = Elf::parse(&data).expect("valid ELF64 file");
+    let yaml = elf.describe().expect("description");
+    let text = yaml.to_string();
+    assert_eq!(Yaml::parse(&text), Ok(yaml.clone()));
+    assert_eq!(description::build(&yaml), Ok(data.clone()));
+
+    // a names table past the end of the file is described as it is
+    let mut moved = data;
+    let header = *Elf::parse(&moved).expect("valid ELF64 file").header();
+    let at = header.e_shoff as usize + header.e_shstrndx as usize * 64 + 24;
+    let past = moved.len() as u64 + 0x100;
+    moved[at..at + 8].copy_from_slice(&past.to_le_bytes());
+    let yaml = Elf::parse(&moved)
+        .and_then(|elf| elf.describe())
+        .expect("description");
+    assert_eq!(description::build(&yaml), Ok(moved));
+
+    // the index of the names table escaped to the Link of the null section
+    let mut escaped = build();
+    let shoff = header.e_shoff as usize;
+    escaped[62..64].copy_from_slice(&0xffffu16.to_le_bytes());
+    escaped[shoff + 40..shoff + 44].copy_from_slice(&(header.e_shstrndx as u32).to_le_bytes());
+    let elf = Elf::parse(&escaped).expect("valid ELF64 file");
+    assert!(elf.section_by_name(".text").is_some());
+    let yaml = elf.describe().expect("description");
+    assert_eq!(description::build(&yaml), Ok(escaped));
+}
+
+#[test]
+fn json_of_the_header() {
+    let data = build();
+    let header = readbin::headers::elf::from_bytes(&data).expect("valid header");
+    let json = header.to_json().to_string();
+    assert!(json.starts_with('{'));
+    assert!(json.contains("\"e_entry\":4198400"));
+}
+
+#[test]
+fn intel_hex_image() {
+    let hex = b":0B00000068656C6C6F20776F726C6499\n:00000001FF\n";
+    assert!(ihex::is_ihex(hex));
+    let image = ihex::from_bytes(hex).expect("valid Intel HEX");
+    assert_eq!(image.mappings.len(), 1);
+    let found = strings::from_image(&image, 4, Encoding::Ascii);
+    assert_eq!(found.hits[0].text, "hello world");
+    assert_eq!(
+        ihex::from_bytes(b":0B00000068656C6C6F20776F726C6400\n"),
+        Err(Error::InvalidIntelHex(1))
+    );
+}
+
+#[test]
+fn type_registries() {
+    use readbin::headers::elf::osabit::OsAbi;
+    use readbin::headers::elf::registry::{self, Names, Registry, Scope};
+    use readbin::headers::elf::x64::x64;
+    let arm = x64 {
+        e_machine: 40,
+        ..Default::default()
+    };
+    let x86_64 = x64 {
+        e_machine: 62,
+        ..Default::default()
+    };
+    assert_eq!(registry::section_type_name(&arm, 0x7000_0001), "ARM_EXIDX");
+    assert_eq!(
+        registry::section_type_name(&x86_64, 0x7000_0001),
+        "X86_64_UNWIND"
+    );
+    assert_eq!(
+        registry::section_type_name(&x86_64, 0x7000_0002),
+        "LOPROC+2"
+    );
+    assert_eq!(registry::segment_type_name(&arm, 0x7000_0001), "EXIDX");
+    assert_eq!(registry::section_type_name(&arm, SHT::PROGBITS), "PROGBITS");
+
+    // Solaris reuses the values of GNU types
+    let mut solaris = x86_64;
+    solaris.e_ident[7] = OsAbi::Solaris.into();
+    assert_eq!(
+        registry::section_type_name(&x86_64, 0x6fff_fff6),
+        "GNU_HASH"
+    );
+    assert_eq!(
+        registry::section_type_name(&solaris, 0x6fff_fff6),
+        "SUNW_SIGNATURE"
+    );
+
+    let mut registry = Registry::new();
+    registry.register(Names {
+        scope: Scope::Machine(Machine::X86_64),
+        sections: &[(0x7000_0002, "X86_64_CUSTOM"), (0x7000_0001, "UNWIND")],
+        segments: &[(0x7000_0000, "CUSTOM")],
+    });
+    assert_eq!(registry.section_type(&x86_64, 0x7000_0002), "X86_64_CUSTOM");
+    assert_eq!(registry.section_type(&x86_64, 0x7000_0001), "UNWIND");
+    assert_eq!(registry.segment_type(&x86_64, 0x7000_0000), "CUSTOM");
+    assert_eq!(registry.segment_type(&arm, 0x7000_0000), "LOPROC+0x0");
+
+    let mut builder = ElfBuilder::new(Type::Rel, Machine::Arm);
+    builder.add_section(Section::new(".ARM.exidx", 0x7000_0001).flags(SHF::ALLOC));
+    let data = builder.build().expect("valid file");
+    let elf = Elf::parse(&data).expect("ELF64 file");
+    let table = readbin::headers::elf::section::SectionHeaderTable {
+        data: &data,
+        header: elf.header(),
+        color: false,
+        wide: false,
+    };
+    assert!(table.to_string().contains(".ARM.exidx ARM_EXIDX"));
+    assert!(table
+        .to_json()
+        .to_string()
+        .contains("\"type\":\"ARM_EXIDX\""));
+}
+
+/// `tests/fixtures/arm-exidx.o` is assembled by llvm-mc for ARMv7 from
+/// functions with `.save`, `.setfp`, `.vsave`, `.pad`, `.cantunwind` and
+/// `.personality` directives
+#[test]
+fn arm_exception_tables() {
+    use readbin::headers::elf::exidx::{self, Op, Personality, Unwind};
+    let data = std::fs::read(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/arm-exidx.o"
+    ))
+    .expect("fixture");
+    let header = match readbin::headers::elf::from_bytes(&data) {
+        Ok(readbin::headers::elf::ElfHeader::Elf32(header)) => header,
+        _ => panic!("ELF32 header"),
+    };
+    let sections = exidx::from_bytes(&data, &header).expect("unwind tables");
+    assert_eq!(sections.len(), 1);
+    assert_eq!(sections[0].name, ".ARM.exidx");
+    let entries = &sections[0].entries;
+    let functions: Vec<_> = entries
+        .iter()
+        .map(|entry| (entry.function, entry.name))
+        .collect();
+    assert_eq!(
+        functions,
+        [
+            (0, Some("_start")),
+            (0x14, Some("f")),
+            (0x18, Some("g")),
+            (0x18, Some("g"))
+        ]
+    );
+    assert_eq!(entries[1].unwind, Unwind::CantUnwind);
+    match &entries[0].unwind {
+        Unwind::Table {
+            address: 0,
+            personality: Personality::Compact(1),
+            instructions,
+        } => {
+            let ops: Vec<_> = instructions
+                .iter()
+                .map(|instruction| instruction.op)
+                .collect();
+            assert_eq!(
+                ops,
+                [
+                    Op::SetVsp(7),
+                    Op::SubVsp(4),
+                    Op::Pop(1 << 4 | 1 << 7 | 1 << 14),
+                    Op::Finish,
+                    Op::Finish
+                ]
+            );
+        }
+        unwind => panic!("{:?}", unwind),
+    }
+    match &entries[2].unwind {
+        Unwind::Table { personality, .. } => assert_eq!(
+            *personality,
+            Personality::Routine {
+                address: 0x28,
+                name: Some("__gxx_personality_v0")
+            }
+        ),
+        unwind => panic!("{:?}", unwind),
+    }
+    let text = exidx::ExceptionTables {
+        data: &data,
+        header: &header,
+    }
+    .to_string();
+    assert!(text.contains("  0xb2 0x7f vsp = vsp + 1024\n  0xc9 0x87 pop {D8-D15}\n"));
+
+    let ops: Vec<_> = exidx::decode(&[0x80, 0x00, 0xa3, 0xb1, 0x05, 0xd1, 0xb5])
+        .into_iter()
+        .map(|instruction| instruction.op.to_string())
+        .collect();
+    assert_eq!(
+        ops,
+        [
+            "refuse to unwind",
+            "pop {r4, r5, r6, r7}",
+            "pop {r0, r2}",
+            "pop {D8-D9}",
+            "[Spare]"
+        ]
+    );
+}
+
+#[test]
+fn packer_triage() {
+    use readbin::headers::elf::triage::{self, Finding};
+    assert_eq!(triage::entropy(&[]), 0.0);
+    assert_eq!(triage::entropy(&[0; 64]), 0.0);
+    assert_eq!(triage::entropy(b"abab"), 1.0);
+    let all: Vec<u8> = (0..=255).collect();
+    assert!((triage::entropy(&all) - 8.0).abs() < 1e-9);
+
+    // a stub with the UPX magic and random bytes unpacked into a large
+    // executable segment
+    let mut stub = b"\xebUPX!".to_vec();
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    stub.extend((0..4096).map(|_| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as u8
+    }));
+    let mut builder = ElfBuilder::new(Type::Exec, Machine::X86_64);
+    let code = builder.add_section(
+        Section::new(".stub", SHT::PROGBITS)
+            .flags(SHF::ALLOC | SHF::EXECINSTR)
+            .content(stub),
+    );
+    let unpacked = builder.add_section(
+        Section::new(".unpacked", SHT::NOBITS)
+            .flags(SHF::ALLOC | SHF::EXECINSTR | SHF::WRITE)
+            .size(0x10_0000),
+    );
+    builder.add_segment(Segment::new(PT::LOAD, PF::R | PF::W | PF::X).sections(code, unpacked));
+    let data = builder.build().expect("valid file");
+    let elf = Elf::parse(&data).expect("ELF64 file");
+    let entry = elf.section_by_name(".stub").expect(".stub").sh_addr;
+    let mut data = data;
+    data[24..32].copy_from_slice(&entry.to_le_bytes());
+    let elf = Elf::parse(&data).expect("ELF64 file");
+
+    let report = elf.triage().expect("triage");
+    let kinds: Vec<_> = report.findings.iter().map(Finding::kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            "upx-magic",
+            "unpacked-segment",
+            "entry-outside-text",
+            "high-entropy"
+        ]
+    );
+    assert!(report
+        .sections
+        .iter()
+        .any(|section| section.name == ".stub" && section.entropy > 7.9));
+    assert!(report.to_string().contains("entry point"));
+    assert!(report
+        .to_json()
+        .to_string()
+        .contains("\"kind\":\"upx-magic\""));
+
+    let clean = build();
+    let report = Elf::parse(&clean)
+        .expect("ELF64 file")
+        .triage()
+        .expect("triage");
+    assert!(report.findings.is_empty(), "{:?}", report.findings);
+}
+
+#[test]
+fn section_digests() {
+    use readbin::utils::digest::{hex, Algorithm};
+    let vectors: [(Algorithm, &[u8], &str); 7] = [
+        (Algorithm::Crc32, b"123456789", "cbf43926"),
+        (Algorithm::Md5, b"", "d41d8cd98f00b204e9800998ecf8427e"),
+        (Algorithm::Md5, b"abc", "900150983cd24fb0d6963f7d28e17f72"),
+        (
+            Algorithm::Sha1,
+            b"abc",
+            "a9993e364706816aba3e25717850c26c9cd0d89d",
+        ),
+        (
+            Algorithm::Sha256,
+            b"",
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+        ),
+        (
+            Algorithm::Sha256,
+            b"abc",
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        ),
+        (
+            Algorithm::Sha256,
+            b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+        ),
+    ];
+    for (algorithm, bytes, digest) in vectors {
+        assert_eq!(hex(&algorithm.digest(bytes)), digest, "{}", algorithm);
+    }
+    let bytes: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+    for algorithm in Algorithm::ALL {
+        let parts = [&bytes[..3], &bytes[3..64], &bytes[64..]];
+        assert_eq!(algorithm.digest_all(&parts), algorithm.digest(&bytes));
+    }
+    assert_eq!("SHA1".parse(), Ok(Algorithm::Sha1));
+    assert!("sha512".parse::<Algorithm>().is_err());
+
+    // only the code is in the text digest
+    let file = |data: &[u8]| {
+        let mut builder = ElfBuilder::new(Type::Exec, Machine::X86_64);
+        builder.add_section(
+            Section::new(".text", SHT::PROGBITS)
+                .flags(SHF::ALLOC | SHF::EXECINSTR)
+                .content([0x90, 0xc3]),
+        );
+        builder.add_section(
+            Section::new(".rodata", SHT::PROGBITS)
+                .flags(SHF::ALLOC)
+                .content(data.to_vec()),
+        );
+        builder.add_section(Section::new(".comment", SHT::PROGBITS).content(*b"gcc\0"));
+        builder.build().expect("valid file")
+    };
+    let (old, new) = (file(b"old"), file(b"new"));
+    let old = Elf::parse(&old).expect("ELF64 file");
+    let new = Elf::parse(&new).expect("ELF64 file");
+    let old = old.digests(Algorithm::Sha256).expect("digests");
+    let new = new.digests(Algorithm::Sha256).expect("digests");
+    assert_eq!(old.text, Algorithm::Sha256.digest(&[0x90, 0xc3]));
+    assert_eq!(old.text_sections, [".text"]);
+    assert_eq!(old.text, new.text);
+    assert_ne!(old.file, new.file);
+    let names: Vec<_> = old.sections.iter().map(|section| section.name).collect();
+    assert_eq!(names, [".text", ".rodata"]);
+    assert_ne!(old.sections[1].digest, new.sections[1].digest);
+    assert!(old
+        .to_json()
+        .to_string()
+        .contains("\"algorithm\":\"sha256\""));
+}
+
+#[test]
+fn edit_symbols_sections_and_dependencies() {
+    let exe = std::fs::read(std::env::current_exe().expect("test binary")).expect("readable");
+    let elf = Elf::parse(&exe).expect("ELF64 file");
+    let phnum = elf.program_headers().expect("program headers").count();
+    let exports = elf.exports().expect("exports");
+    let export = exports
+        .symbols
+        .first()
+        .and_then(|symbol| symbol.name.to_str());
+    let mut editor = elf.editor();
+    editor
+        .add_needed("libreadbin-test.so")
+        .set_soname("readbin-test")
+        .add_section(".readbin.note", *b"note", 0)
+        .add_section(".readbin.data", *b"loaded", SHF::ALLOC | SHF::WRITE)
+        .rename_symbol("main", "readbin_main");
+    if let Some(export) = export {
+        editor.rename_symbol(export, "readbin_export");
+    }
+    let edited = editor.write().expect("edited file");
+    let elf = Elf::parse(&edited).expect("ELF64 file");
+    elf.header().validate(&edited).expect("valid headers");
+
+    let dynamic = elf.dynamic().expect("dynamic section").expect("dynamic");
+    assert_eq!(dynamic.needed().last(), Some(&"libreadbin-test.so"));
+    assert_eq!(dynamic.soname(), Some("readbin-test"));
+    assert_eq!(elf.section_bytes(".readbin.note"), Some(&b"note"[..]));
+    assert_eq!(elf.section_bytes(".readbin.data"), Some(&b"loaded"[..]));
+    // the loaded section is mapped by the new segment
+    let loaded = elf.section_by_name(".readbin.data").expect("added section");
+    assert_eq!(
+        elf.file_offset_for_vaddr(loaded.sh_addr),
+        Some(loaded.sh_offset)
+    );
+    assert_eq!(
+        elf.program_headers().expect("program headers").count(),
+        phnum + 1
+    );
+    let found = elf.all_symbols().expect("symbols");
+    let names: Vec<_> = found.map(|symbol| symbol.name).collect();
+    assert!(names.contains(&"readbin_main".into()) && !names.contains(&"main".into()));
+    if export.is_some() {
+        assert!(elf.lookup("readbin_export").expect("hash table").is_some());
+    }
+
+    // editing the result again doesn't lose the first edits
+    let mut editor = elf.editor();
+    editor
+        .add_needed("libreadbin-test.so")
+        .rename_symbol("readbin_main", "main");
+    let again = editor.write().expect("edited file");
+    let elf = Elf::parse(&again).expect("ELF64 file");
+    let dynamic = elf.dynamic().expect("dynamic section").expect("dynamic");
+    let needed = dynamic.needed();
+    assert_eq!(
+        needed
+            .iter()
+            .filter(|name| **name == "libreadbin-test.so")
+            .count(),
+        1
+    );
+    assert_eq!(elf.section_bytes(".readbin.note"), Some(&b"note"[..]));
+
+    let mut editor = elf.editor();
+    editor.rename_symbol("readbin_does_not_exist", "other");
+    assert!(matches!(editor.write(), Err(Error::InvalidEdit(_))));
+    let data = build();
+    let elf = Elf::parse(&data).expect("ELF64 file");
+    let mut editor = elf.editor();
+    editor.add_needed("libc.so.6");
+    assert!(matches!(editor.write(), Err(Error::InvalidEdit(_))));
+
+    // a damaged alignment of the names table, which both copy elsewhere
+    let mut damaged = again.clone();
+    let header = *Elf::parse(&again).expect("ELF64 file").header();
+    let at = header.e_shoff as usize + header.e_shstrndx as usize * 64 + 48;
+    damaged[at..at + 8].copy_from_slice(&95657511616513u64.to_le_bytes());
+    let elf = Elf::parse(&damaged).expect("ELF64 file");
+    let mut editor = elf.editor();
+    editor.add_section(".readbin.other", *b"other", 0);
+    assert!(matches!(editor.write(), Err(Error::OutOfBounds { .. })));
+    assert!(matches!(elf.strip(), Err(Error::OutOfBounds { .. })));
+}
+
+#[test]
+fn truncated_names() {
+    use readbin::headers::elf::{section, symbols};
+    let mut builder = ElfBuilder::new(Type::Rel, Machine::X86_64);
+    let text = builder.add_section(
+        Section::new(".text.readbin_long_section_name", SHT::PROGBITS)
+            .flags(SHF::ALLOC | SHF::EXECINSTR)
+            .content([0xc3]),
+    );
+    builder.add_symbol(
+        Symbol::new("readbin_a_rather_long_symbol_name")
+            .kind(STT::FUNC)
+            .section(text)
+            .size(1),
+    );
+    let data = builder.build().expect("valid file");
+    let elf = Elf::parse(&data).expect("ELF64 file");
+    let header = elf.header();
+
+    let narrow = |wide| {
+        let sections = section::SectionHeaderTable {
+            data: &data,
+            header,
+            color: false,
+            wide,
+        };
+        let symbols = symbols::SymbolTable {
+            data: &data,
+            header,
+            demangle: false,
+            color: false,
+            wide,
+        };
+        (sections.to_string(), symbols.to_string())
+    };
+    let (sections, symbols) = narrow(false);
+    assert!(sections.contains("] .text.readbi[...] PROGBITS "));
+    assert!(symbols.contains(" readbin_a_rather[...]\n"));
+    // the types start under their title on every row
+    let mut rows = sections.lines().filter(|line| line.starts_with("  ["));
+    let column = rows
+        .next()
+        .and_then(|title| title.find("Type"))
+        .expect("titles");
+    assert!(rows.all(|row| row[column - 1..].starts_with(' ') && !row[column..].starts_with(' ')));
+
+    let (sections, symbols) = narrow(true);
+    assert!(sections.contains("] .text.readbin_long_section_name PROGBITS "));
+    assert!(symbols.contains(" readbin_a_rather_long_symbol_name\n"));
+}
+
+#[test]
+fn names_that_are_not_utf8() {
+    use readbin::headers::elf::{section, symbols};
+    use readbin::utils::bytestr::ByteStr;
+    let mut builder = ElfBuilder::new(Type::Rel, Machine::X86_64);
+    let text = builder.add_section(
+        Section::new(".text.caf_", SHT::PROGBITS)
+            .flags(SHF::ALLOC | SHF::EXECINSTR)
+            .content([0xc3]),
+    );
+    builder.add_symbol(Symbol::new("caf_").kind(STT::FUNC).section(text).size(1));
+    let mut data = builder.build().expect("valid file");
+    // a Latin-1 é in both names, the section one comes first
+    for _ in 0..2 {
+        let at = data
+            .windows(5)
+            .position(|bytes| bytes == b"caf_\0")
+            .expect("name");
+        data[at + 3] = 0xe9;
+    }
+    let elf = Elf::parse(&data).expect("ELF64 file");
+    let header = elf.header();
+
+    let symbol = elf
+        .all_symbols()
+        .expect("symbols")
+        .find(|symbol| symbol.index == 1)
+        .expect("the function");
+    assert_eq!(symbol.name, &b"caf\xe9"[..]);
+    assert_ne!(symbol.name, "caf\u{fffd}");
+    assert_eq!(symbol.name.to_str(), None);
+    assert_eq!(symbol.section, Some(ByteStr::new(b".text.caf\xe9")));
+    assert_eq!(symbol.demangled(), "caf\u{fffd}");
+    let shdr = elf.section_by_name(b".text.caf\xe9").expect("the section");
+    assert_eq!(elf.section_name(&shdr), Ok(ByteStr::new(b".text.caf\xe9")));
+    let sizes = elf.sizes().expect("sizes").to_string();
+    assert!(sizes.contains(" .text.caf\u{fffd} "));
+    assert!(sizes.contains(" caf\u{fffd}\n"));
+
+    let sections = section::SectionHeaderTable {
+        data: &data,
+        header,
+        color: false,
+        wide: true,
+    };
+    let symbols = symbols::SymbolTable {
+        data: &data,
+        header,
+        demangle: true,
+        color: false,
+        wide: true,
+    };
+    let text = format!("{}{}", sections, symbols);
+    assert!(text.contains("] .text.caf\u{fffd} PROGBITS"));
+    assert!(text.contains(" caf\u{fffd}\n"));
+    assert!(!text.contains("<corrupt>"));
+    let json = symbols.to_json().to_string();
+    assert!(json.contains("\"name\":\"caf\u{fffd}\""));
+}
+
+/// The interpreter and the needed libraries are kept as bytes too
+#[test]
+fn dependencies_that_are_not_utf8() {
+    use readbin::headers::elf::dynamic::DT;
+    use readbin::utils::bytestr::ByteStr;
+    let mut builder = ElfBuilder::new(Type::Dyn, Machine::X86_64);
+    let interp = builder.add_section(
+        Section::new(".interp", SHT::PROGBITS)
+            .flags(SHF::ALLOC)
+            .content(&b"/lib/ld-caf\xe9.so\0"[..]),
+    );
+    let dynstr = builder.add_section(
+        Section::new(".dynstr", SHT::STRTAB)
+            .flags(SHF::ALLOC)
+            .content(&b"\0libcaf\xe9.so\0"[..]),
+    );
+    let entries: Vec<u8> = [(DT::NEEDED, 1), (DT::NULL, 0)]
+        .iter()
+        .flat_map(|&(tag, value): &(i64, u64)| {
+            let mut entry = tag.to_le_bytes().to_vec();
+            entry.extend(value.to_le_bytes());
+            entry
+        })
+        .collect();
+    builder.add_section(
+        Section::new(".dynamic", SHT::DYNAMIC)
+            .flags(SHF::ALLOC | SHF::WRITE)
+            .link(dynstr as u32)
+            .align(8)
+            .entsize(16)
+            .content(entries),
+    );
+    builder.add_segment(Segment::new(PT::INTERP, PF::R).sections(interp, interp));
+    let data = builder.build().expect("valid file");
+    let elf = Elf::parse(&data).expect("ELF64 file");
+    assert_eq!(elf.interpreter(), Some(ByteStr::new(b"/lib/ld-caf\xe9.so")));
+    assert_eq!(
+        elf.needed_libraries(),
+        Ok(vec![ByteStr::new(b"libcaf\xe9.so")])
+    );
+}
+
+#[test]
+fn filter_expressions() {
+    use readbin::headers::elf::{program, section};
+    use readbin::utils::filter::Filter;
+    let mut builder = ElfBuilder::new(Type::Rel, Machine::X86_64);
+    let text = builder.add_section(
+        Section::new(".text", SHT::PROGBITS)
+            .flags(SHF::ALLOC | SHF::EXECINSTR)
+            .content([0xc3; 0x2000]),
+    );
+    let data = builder.add_section(
+        Section::new(".data", SHT::PROGBITS)
+            .flags(SHF::ALLOC | SHF::WRITE)
+            .content([0; 16]),
+    );
+    for (name, section, kind, size) in [
+        ("readbin_main", text, STT::FUNC, 0x1800),
+        ("readbin_init", text, STT::FUNC, 0x800),
+        ("readbin_state", data, STT::OBJECT, 16),
+    ] {
+        builder.add_symbol(
+            Symbol::new(name)
+                .bind(STB::GLOBAL)
+                .kind(kind)
+                .section(section)
+                .size(size),
+        );
+    }
+    let data = builder.build().expect("valid file");
+    let elf = Elf::parse(&data).expect("ELF64 file");
+
+    let symbols = |expression| {
+        let filter = Filter::parse(expression).expect("valid filter");
+        filter
+            .check::<readbin::headers::elf::query::Symbol>()
+            .expect("symbol fields");
+        let symbols = elf.all_symbols().expect("symbols");
+        let kept = symbols.filter(|symbol| symbol.index != 0 && filter.matches(symbol));
+        kept.map(|symbol| symbol.name.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        symbols("type==FUNC && size>4096 && section==\".text\""),
+        ["readbin_main"]
+    );
+    assert_eq!(
+        symbols("(bind==GLOBAL) && !(name ~ main || size < 0x10)"),
+        ["readbin_init", "readbin_state"]
+    );
+    assert_eq!(symbols("ndx == 0x2"), ["readbin_state"]);
+    assert_eq!(symbols("section!~text && name"), ["readbin_state"]);
+
+    let sections = section::entries(&data, elf.header()).expect("sections");
+    let filter = Filter::parse("flags ~ A && size >= 0x10").expect("valid filter");
+    let kept: Vec<_> = sections
+        .iter()
+        .filter(|entry| filter.matches(*entry))
+        .map(|entry| entry.name.to_string())
+        .collect();
+    assert_eq!(kept, [".text", ".data"]);
+    assert!(filter.check::<program::SegmentEntry>().is_ok());
+    assert_eq!(
+        Filter::parse("section==.text")
+            .expect("valid filter")
+            .check::<section::SectionEntry>(),
+        Err(Error::UnknownField("section".to_string()))
+    );
+    assert_eq!(Filter::parse("size >"), Err(Error::InvalidFilter(6)));
+    assert_eq!(Filter::parse("(size"), Err(Error::InvalidFilter(5)));
+    assert_eq!(
+        Filter::parse("size == 1 ||| a"),
+        Err(Error::InvalidFilter(12))
+    );
+    assert_eq!(Filter::parse("name == \"a"), Err(Error::InvalidFilter(8)));
+}
+
+#[cfg(feature = "disasm")]
+#[test]
+fn disassemble_at_the_end_of_the_address_space() {
+    // the last instruction wraps around to address 0
+    let text = DESCRIPTION
+        .replace("0x401000", "0xfffffffffffffffe")
+        .replace("0x401003", "0x1");
+    let data = description::build(&Yaml::parse(&text).expect("valid YAML")).expect("valid file");
+    let elf = Elf::parse(&data).expect("ELF64 file");
+    let disassembly = elf.disassemble(None).expect("code sections").to_string();
+    assert!(disassembly.contains("fffffffffffffffe:\t31 c0"));
+    assert!(disassembly.contains("       0:\tc3"));
+}
+
+#[test]
+fn demangle_standard_abbreviations() {
+    use readbin::utils::demangle::demangle;
+    const STRING: &str = "std::basic_string<char, std::char_traits<char>, std::allocator<char> >";
+    // like readelf -C, the short name is the scope of a nested name
+    assert_eq!(
+        demangle("_ZNSs4_Rep10_M_destroyERKSaIcE"),
+        "std::string::_Rep::_M_destroy(std::allocator<char> const&)"
+    );
+    assert_eq!(demangle("_ZNKSs4sizeEv"), "std::string::size() const");
+    assert_eq!(
+        demangle("_ZNSs6appendERKSs"),
+        "std::string::append(std::string const&)"
+    );
+    assert_eq!(demangle("_ZNSi3getEv"), "std::istream::get()");
+    assert_eq!(demangle("_ZNSo5flushEv"), "std::ostream::flush()");
+    // but for the constructors and destructors, named after the complete name
+    assert_eq!(demangle("_ZNSsC1Ev"), format!("{}::basic_string()", STRING));
+    assert_eq!(
+        demangle("_ZNSdD0Ev"),
+        "std::basic_iostream<char, std::char_traits<char> >::~basic_iostream()"
+    );
+    assert_eq!(
+        demangle("_ZNSaIcEC1Ev"),
+        "std::allocator<char>::allocator()"
+    );
+    assert_eq!(demangle("_Z1fSiSo"), "f(std::istream, std::ostream)");
+}
+
+#[test]
+fn summarize_files() {
+    use readbin::summary::{self, Summary};
+    let summary = Summary::from_bytes(&build()).expect("known format");
+    assert_eq!(summary.format, "ELF");
+    assert_eq!(summary.class, Some("ELF64"));
+    assert_eq!(summary.kind, Some("EXEC"));
+    assert_eq!(summary.pie, Some(false));
+
+    let fixtures = std::path::Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"));
+    let mut files = Vec::new();
+    summary::walk(fixtures, &mut files).expect("readable directory");
+    assert!(files.windows(2).all(|pair| pair[0] < pair[1]));
+    let arm = files
+        .iter()
+        .find(|file| file.ends_with("arm.o"))
+        .expect("ARM fixture");
+    let summary = Summary::from_bytes(&std::fs::read(arm).expect("fixture")).expect("ELF32");
+    assert_eq!(summary.class, Some("ELF32"));
+    assert_eq!(summary.machine, Some("ARM"));
+    assert_eq!((summary.pie, summary.build_id), (None, None));
+    assert!(matches!(
+        Summary::from_bytes(b"not a binary"),
+        Err(Error::UnsupportedFormat(Format::Unknown))
+    ));
+}
+
+/// `tests/fixtures/relr.so` is linked by GNU ld 2.40 with
+/// `-z pack-relative-relocs` from a table of 12 pointers, one of them null
+#[test]
+fn packed_relative_relocations() {
+    use readbin::headers::elf::relocations::{self, RelocationTable};
+    let data = std::fs::read(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/relr.so"
+    ))
+    .expect("fixture");
+    let elf = Elf::parse(&data).expect("valid ELF64 file");
+    let shdr = elf.section_by_name(".relr.dyn").expect("SHT_RELR section");
+    // an address, then a bitmap of the next words
+    let addresses = relocations::relr_from_bytes(&data, elf.header(), &shdr).expect("RELR");
+    let mut expected = vec![0x2000];
+    expected.extend(
+        (0x2020..0x2080)
+            .step_by(8)
+            .filter(|address| *address != 0x2038),
+    );
+    assert_eq!(addresses, expected);
+    let table = RelocationTable {
+        data: &data,
+        header: elf.header(),
+        demangle: false,
+        wide: false,
+    }
+    .to_string();
+    assert!(table.contains("'.relr.dyn' at offset 0x260 contains 2 entries:\n  12 offsets\n"));
+
+    let mut image = elf.load_image().expect("image");
+    let word = |image: &LoadImage, address: u64| {
+        let at = (address - image.base) as usize;
+        u64::from_le_bytes(image.bytes[at..at + 8].try_into().expect("8 bytes"))
+    };
+    let before: Vec<u64> = addresses
+        .iter()
+        .map(|address| word(&image, *address))
+        .collect();
+    let base = 0x7f00_0000_0000;
+    let skipped = elf
+        .apply_relocations(&mut image, base, |_| None)
+        .expect("relocations");
+    assert!(skipped.is_empty());
+    for (address, before) in addresses.iter().zip(before) {
+        assert_eq!(word(&image, address + base), before + base);
+    }
+}
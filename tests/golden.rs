@@ -0,0 +1,290 @@
+//! The output of `readbin -a` compared with golden files
+//!
+//! The fixtures are the small objects of `tests/fixtures`, compiled for
+//! machines, classes and byte orders the host can't build, and files
+//! generated from a YAML description for the ELF64 variants. Their output
+//! is compared with `tests/golden/<name>.txt`, which were checked against
+//! `readelf -a` when written. After a deliberate change of the output run
+//! `READBIN_BLESS=1 cargo test --test golden` to write them again and
+//! review the diff.
+//!
+//! The descriptions of random files then check that what the writer
+//! builds is parsed back with the same contents and described back to the
+//! same bytes.
+use readbin::headers::elf::description;
+use readbin::headers::elf::section::SHT;
+use readbin::prelude::*;
+use readbin::utils::yaml::Yaml;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Objects checked in `tests/fixtures`
+const FIXTURES: [&str; 7] = [
+    "arm-exidx.o",
+    "arm.o",
+    "mips.o",
+    "mips64.o",
+    "riscv32e.o",
+    "riscv64.o",
+    "x32.o",
+];
+
+/// Generated files: name, byte order, machine and type
+const GENERATED: [(&str, &str, &str, &str); 4] = [
+    ("x86_64-exec", "ELFDATA2LSB", "EM_X86_64", "ET_EXEC"),
+    ("aarch64-dyn", "ELFDATA2LSB", "EM_AARCH64", "ET_DYN"),
+    ("ppc64-exec", "ELFDATA2MSB", "EM_PPC64", "ET_EXEC"),
+    ("s390x-dyn", "ELFDATA2MSB", "EM_S390", "ET_DYN"),
+];
+
+/// The description of the generated files, a code, a read only and a
+/// zeroed data segment
+const TEMPLATE: &str = "FileHeader:
+  Class: ELFCLASS64
+  Data: {data}
+  Type: {type}
+  Machine: {machine}
+  Entry: 0x10000
+ProgramHeaders:
+  - Type: PT_LOAD
+    Flags: [PF_X, PF_R]
+    FirstSection: .text
+    LastSection: .rodata
+    Align: 0x1000
+  - Type: PT_LOAD
+    Flags: [PF_W, PF_R]
+    FirstSection: .data
+    LastSection: .bss
+    Align: 0x1000
+  - Type: PT_GNU_STACK
+    Flags: [PF_W, PF_R]
+Sections:
+  - Name: .text
+    Type: SHT_PROGBITS
+    Flags: [SHF_ALLOC, SHF_EXECINSTR]
+    Address: 0x10000
+    AddressAlign: 0x10
+    Content: 0001020304050607
+  - Name: .rodata
+    Type: SHT_PROGBITS
+    Flags: [SHF_ALLOC, SHF_MERGE, SHF_STRINGS]
+    Address: 0x10008
+    AddressAlign: 0x1
+    EntSize: 0x1
+    Content: 676f6c64656e00
+  - Name: .data
+    Type: SHT_PROGBITS
+    Flags: [SHF_WRITE, SHF_ALLOC]
+    Address: 0x11100
+    AddressAlign: 0x8
+    Content: 2a00000000000000
+  - Name: .bss
+    Type: SHT_NOBITS
+    Flags: [SHF_WRITE, SHF_ALLOC]
+    Address: 0x11108
+    AddressAlign: 0x8
+    Size: 0x20
+  - Name: .comment
+    Type: SHT_PROGBITS
+    Flags: [SHF_MERGE, SHF_STRINGS]
+    AddressAlign: 0x1
+    EntSize: 0x1
+    Content: 7265616462696e00
+";
+
+fn root() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// The output of `readbin -a` without colors, the ELF32 files end with a
+/// note on the tables that are skipped
+fn readbin(path: &Path) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_main"))
+        .args(["-a", "--color=never"])
+        .arg(path)
+        .output()
+        .expect("readbin runs");
+    assert!(output.status.success(), "readbin -a {}", path.display());
+    String::from_utf8(output.stdout).expect("UTF-8 output")
+}
+
+/// Compare the output for a file with its golden file, or write it when
+/// READBIN_BLESS is set
+fn check(name: &str, path: &Path) -> Option<String> {
+    let actual = readbin(path);
+    let golden = root().join("tests/golden").join(format!("{}.txt", name));
+    if std::env::var_os("READBIN_BLESS").is_some() {
+        std::fs::write(&golden, &actual).expect("golden file written");
+        return None;
+    }
+    match std::fs::read_to_string(&golden) {
+        // a checkout with core.autocrlf writes the golden files with \r\n
+        Ok(expected) if expected.replace("\r\n", "\n") == actual => None,
+        Ok(expected) => {
+            let line = expected
+                .lines()
+                .zip(actual.lines())
+                .position(|(expected, actual)| expected != actual)
+                .unwrap_or(expected.lines().count().min(actual.lines().count()));
+            Some(format!(
+                "{}: differs from {} at line {}:\n  expected: {:?}\n  actual:   {:?}",
+                name,
+                golden.display(),
+                line + 1,
+                expected.lines().nth(line).unwrap_or_default(),
+                actual.lines().nth(line).unwrap_or_default(),
+            ))
+        }
+        Err(err) => Some(format!("{}: {}: {}", name, golden.display(), err)),
+    }
+}
+
+fn generate(data: &str, machine: &str, e_type: &str) -> Vec<u8> {
+    let text = TEMPLATE
+        .replace("{data}", data)
+        .replace("{machine}", machine)
+        .replace("{type}", e_type);
+    let yaml = Yaml::parse(&text).expect("valid YAML");
+    description::build(&yaml).expect("valid description")
+}
+
+#[test]
+fn golden_files() {
+    let mut failures = Vec::new();
+    for name in FIXTURES {
+        let path = root().join("tests/fixtures").join(name);
+        failures.extend(check(name.trim_end_matches(".o"), &path));
+    }
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    for (name, data, machine, e_type) in GENERATED {
+        let path = dir.join(format!("{}.elf", name));
+        std::fs::write(&path, generate(data, machine, e_type)).expect("fixture written");
+        failures.extend(check(name, &path));
+    }
+    assert!(
+        failures.is_empty(),
+        "{}\nrun READBIN_BLESS=1 cargo test --test golden if the change is expected",
+        failures.join("\n")
+    );
+}
+
+/// A xorshift generator, the same files on every run
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+}
+
+/// A section of a random file: name, type, flags, alignment and content,
+/// the size of a SHT_NOBITS section
+struct Section {
+    name: String,
+    sh_type: &'static str,
+    flags: Vec<&'static str>,
+    align: u64,
+    content: Vec<u8>,
+    size: u64,
+}
+
+fn random_sections(random: &mut Random) -> Vec<Section> {
+    const FLAGS: [&str; 4] = ["SHF_WRITE", "SHF_ALLOC", "SHF_EXECINSTR", "SHF_MERGE"];
+    (0..1 + random.below(8))
+        .map(|index| {
+            let nobits = random.below(4) == 0;
+            let count = random.below(64);
+            Section {
+                name: format!(".s{}", index),
+                sh_type: if nobits { "SHT_NOBITS" } else { "SHT_PROGBITS" },
+                flags: FLAGS
+                    .iter()
+                    .filter(|_| random.below(2) == 0)
+                    .copied()
+                    .collect(),
+                align: 1 << random.below(5),
+                content: match nobits {
+                    true => Vec::new(),
+                    false => (0..count).map(|_| random.next() as u8).collect(),
+                },
+                size: count,
+            }
+        })
+        .collect()
+}
+
+fn random_description(random: &mut Random, sections: &[Section]) -> String {
+    let data = ["ELFDATA2LSB", "ELFDATA2MSB"][random.below(2) as usize];
+    let machine = ["EM_X86_64", "EM_AARCH64", "EM_RISCV", "EM_PPC64"][random.below(4) as usize];
+    let mut text = format!(
+        "FileHeader:\n  Class: ELFCLASS64\n  Data: {}\n  Type: ET_REL\n  Machine: {}\nSections:\n",
+        data, machine
+    );
+    for section in sections {
+        text += &format!(
+            "  - Name: {}\n    Type: {}\n    Flags: [{}]\n    Address: 0x{:x}\n    AddressAlign: 0x{:x}\n",
+            section.name,
+            section.sh_type,
+            section.flags.join(", "),
+            random.below(0x100000) * section.align,
+            section.align,
+        );
+        match section.sh_type {
+            "SHT_NOBITS" => text += &format!("    Size: 0x{:x}\n", section.size),
+            _ if section.content.is_empty() => {}
+            _ => {
+                let content: String = section
+                    .content
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect();
+                text += &format!("    Content: {}\n", content);
+            }
+        }
+    }
+    text
+}
+
+#[test]
+fn random_files_round_trip() {
+    let mut random = Random(0x5eed_0f5e_c710_e5e5);
+    for _ in 0..200 {
+        let sections = random_sections(&mut random);
+        let text = random_description(&mut random, &sections);
+        let yaml = Yaml::parse(&text).expect("valid YAML");
+        let data = description::build(&yaml).unwrap_or_else(|err| panic!("{}\n{}", err, text));
+        let elf = Elf::parse(&data).unwrap_or_else(|err| panic!("{}\n{}", err, text));
+        for section in &sections {
+            let shdr = elf.section_by_name(&section.name).expect("section");
+            match section.sh_type {
+                "SHT_NOBITS" => {
+                    assert_eq!(shdr.sh_type, SHT::NOBITS);
+                    assert_eq!(shdr.sh_size, section.size, "{}", text);
+                }
+                _ => assert_eq!(
+                    elf.section_bytes(&section.name),
+                    Some(&section.content[..]),
+                    "{}",
+                    text
+                ),
+            }
+            assert_eq!(shdr.sh_addralign, section.align);
+        }
+        let described = elf.describe().expect("description");
+        assert_eq!(
+            description::build(&described).as_ref(),
+            Ok(&data),
+            "{}",
+            text
+        );
+        let reparsed = Yaml::parse(&described.to_string()).expect("valid YAML");
+        assert_eq!(reparsed, described);
+    }
+}